@@ -0,0 +1,89 @@
+//! Hot-path game logic: firing, fleet placement and winner determination.
+//! Run with `cargo bench`.
+
+use backend::state::{Game, GameConfig, Player, Ship};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use uuid::Uuid;
+
+/// Standard fleet (5, 4, 3, 3, 2), one ship per row.
+fn fleet() -> Vec<Ship> {
+    [5u8, 4, 3, 3, 2]
+        .iter()
+        .enumerate()
+        .map(|(row, &size)| Ship {
+            size,
+            hits: 0,
+            sunk: false,
+            x: 0,
+            y: row * 2,
+            vertical: false,
+        })
+        .collect()
+}
+
+fn placed_player() -> Player {
+    let mut p = Player::new(Uuid::new_v4(), "bench".to_string());
+    for ship in fleet() {
+        let (x, y, v) = (ship.x, ship.y, ship.vertical);
+        p.place_ship(ship, x, y, v).unwrap();
+    }
+    p
+}
+
+fn bench_fire(c: &mut Criterion) {
+    // A full 100-shot sweep: every hit, miss, sink and the final victory check
+    c.bench_function("fire/sweep_board", |b| {
+        b.iter_batched(
+            || (Player::new(Uuid::new_v4(), "shooter".to_string()), placed_player()),
+            |(mut shooter, mut target)| {
+                for y in 0..10 {
+                    for x in 0..10 {
+                        let _ = black_box(shooter.fire(&mut target.home, x, y, u32::MAX));
+                    }
+                }
+                black_box(target.home.grid.ship_cells_remaining())
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn bench_placement(c: &mut Criterion) {
+    c.bench_function("placement/fleet", |b| {
+        b.iter_batched(
+            fleet,
+            |ships| {
+                let mut p = Player::new(Uuid::new_v4(), "bench".to_string());
+                for ship in ships {
+                    let (x, y, v) = (ship.x, ship.y, ship.vertical);
+                    black_box(p.place_ship(ship, x, y, v)).unwrap();
+                }
+                p
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("placement/overlap_rejected", |b| {
+        let p = placed_player();
+        b.iter_batched(
+            || (p.clone(), fleet().remove(0)),
+            |(mut p, ship)| black_box(p.place_ship(ship, 0, 0, true)).unwrap_err(),
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+fn bench_winner(c: &mut Criterion) {
+    let mut game = Game::new(Uuid::new_v4(), "p1".to_string(), GameConfig::default());
+    game.join(Uuid::new_v4(), "p2".to_string()).unwrap();
+    game.player1 = placed_player();
+    game.player2 = Some(placed_player());
+    game.player1.home.ships[0].sunk = true;
+    game.player2.as_mut().unwrap().home.ships[0].sunk = true;
+
+    c.bench_function("determine_winner", |b| b.iter(|| black_box(&game).determine_winner()));
+}
+
+criterion_group!(benches, bench_fire, bench_placement, bench_winner);
+criterion_main!(benches);
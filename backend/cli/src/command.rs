@@ -0,0 +1,142 @@
+use battle_cp_client::protocol::{Board, ClientMessage, Coord, DifficultyVote, ShipPlacement, SpecialWeapon};
+
+pub const HELP: &str = "\
+Commands:
+  place                      standard fleet on rows 1, 3, 5, 7, 9
+  place A1h B3v C5h D7h E9h  carrier, battleship, cruiser, submarine, destroyer
+                             (bow cell, then h = horizontal / v = vertical)
+  ready                      confirm placement
+  B7 | fire B7               fire at a cell
+  cross B7                   fire a cross shot (earned by fast solves) at B7 and its neighbours
+  solve                      submit the assigned (or bonus) problem for verification
+  veto                       skip the assigned problem (timed unlock)
+  hint                       show the assigned problem's rating and tags (if the game allows, at a cost)
+  bonus                      ask for a bonus problem; solving it earns a shield
+  shield B7                  put an earned shield on your ship at B7
+  harder | easier            vote to change the difficulty (both players must agree)
+  extend                     host: keep the lobby open longer while waiting for an opponent
+  board                      redraw both boards and timers
+  quit";
+
+/// Ship sizes in the order `place` takes them.
+const FLEET: [u8; 5] = [5, 4, 3, 3, 2];
+
+#[derive(Debug)]
+pub enum Command {
+    Help,
+    Board,
+    Quit,
+    /// Verify the currently assigned problem, or the bonus problem when unlocked
+    Solve,
+    Send(ClientMessage),
+}
+
+impl std::str::FromStr for Command {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut words = line.split_whitespace();
+        let Some(first) = words.next() else {
+            return Ok(Command::Board);
+        };
+        let rest: Vec<&str> = words.collect();
+        match (first.to_ascii_lowercase().as_str(), rest.as_slice()) {
+            ("help" | "?", []) => Ok(Command::Help),
+            ("board" | "b", []) => Ok(Command::Board),
+            ("quit" | "exit" | "q", []) => Ok(Command::Quit),
+            ("ready", []) => Ok(Command::Send(ClientMessage::Ready)),
+            ("veto", []) => Ok(Command::Send(ClientMessage::Veto)),
+            ("hint", []) => Ok(Command::Send(ClientMessage::RequestHint)),
+            ("extend", []) => Ok(Command::Send(ClientMessage::ExtendLobby)),
+            ("bonus", []) => Ok(Command::Send(ClientMessage::RequestBonusProblem)),
+            ("shield", [cell]) => {
+                let at: Coord = cell.parse()?;
+                Ok(Command::Send(ClientMessage::PlaceShield { at }))
+            }
+            ("harder", []) => Ok(Command::Send(ClientMessage::VoteDifficulty { direction: DifficultyVote::Up })),
+            ("easier", []) => Ok(Command::Send(ClientMessage::VoteDifficulty { direction: DifficultyVote::Down })),
+            ("solve", []) => Ok(Command::Solve),
+            ("fire" | "f", [cell]) => fire(cell),
+            ("cross", [cell]) => {
+                let at: Coord = cell.parse()?;
+                Ok(Command::Send(ClientMessage::FireSpecial { weapon: SpecialWeapon::Cross, at, board: Board::Home }))
+            }
+            ("place", []) => Ok(Command::Send(ClientMessage::PlaceShips {
+                ships: FLEET
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &size)| ShipPlacement { x: 0, y: i * 2, size, vertical: false })
+                    .collect(),
+                forward: None,
+            })),
+            ("place", ships) if ships.len() == FLEET.len() => {
+                let ships = ships
+                    .iter()
+                    .zip(FLEET)
+                    .map(|(spec, size)| ship(spec, size))
+                    .collect::<Result<_, _>>()?;
+                Ok(Command::Send(ClientMessage::PlaceShips { ships, forward: None }))
+            }
+            ("place", _) => Err(format!("place takes no ships or all {}", FLEET.len())),
+            (_, []) => fire(first),
+            _ => Err(format!("Unknown command {:?} (try `help`)", line.trim())),
+        }
+    }
+}
+
+fn fire(cell: &str) -> Result<Command, String> {
+    let at: Coord = cell.parse()?;
+    Ok(Command::Send(ClientMessage::Fire { at, board: Board::Home }))
+}
+
+/// `"B3v"` → a ship of `size` with its bow at B3, vertical.
+fn ship(spec: &str, size: u8) -> Result<ShipPlacement, String> {
+    let (cell, vertical) = match spec.char_indices().last() {
+        Some((i, 'h' | 'H')) => (&spec[..i], false),
+        Some((i, 'v' | 'V')) => (&spec[..i], true),
+        _ => return Err(format!("{:?}: end with h or v", spec)),
+    };
+    let Coord { x, y } = cell.parse()?;
+    Ok(ShipPlacement { x, y, size, vertical })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_commands() {
+        let fire_at = |line: &str| match line.parse::<Command>() {
+            Ok(Command::Send(ClientMessage::Fire { at, .. })) => Some((at.x, at.y)),
+            _ => None,
+        };
+        assert_eq!(fire_at("B7"), Some((1, 6)));
+        assert_eq!(fire_at("fire j10"), Some((9, 9)));
+        assert_eq!(fire_at("7B"), None);
+        assert!(matches!(
+            "cross C3".parse::<Command>(),
+            Ok(Command::Send(ClientMessage::FireSpecial { weapon: SpecialWeapon::Cross, at: Coord { x: 2, y: 2 }, .. }))
+        ));
+
+        match "place A1h B3v C5h D7h E9h".parse::<Command>() {
+            Ok(Command::Send(ClientMessage::PlaceShips { ships, .. })) => {
+                let sizes: Vec<u8> = ships.iter().map(|s| s.size).collect();
+                assert_eq!(sizes, FLEET);
+                assert_eq!((ships[1].x, ships[1].y, ships[1].vertical), (1, 2, true));
+            }
+            other => panic!("parsed as {:?}", other),
+        }
+        assert!("place A1h".parse::<Command>().is_err());
+        assert!("place A1 B3v C5h D7h E9h".parse::<Command>().is_err());
+        assert!(matches!("".parse::<Command>(), Ok(Command::Board)));
+        assert!(matches!("solve".parse::<Command>(), Ok(Command::Solve)));
+        assert!(matches!(
+            "easier".parse::<Command>(),
+            Ok(Command::Send(ClientMessage::VoteDifficulty { direction: DifficultyVote::Down }))
+        ));
+        assert!(matches!(
+            "shield a5".parse::<Command>(),
+            Ok(Command::Send(ClientMessage::PlaceShield { at: Coord { x: 0, y: 4 } }))
+        ));
+    }
+}
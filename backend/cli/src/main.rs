@@ -0,0 +1,166 @@
+//! Play Battle-CP from a terminal.
+//!
+//! ```text
+//! battle-cp-cli --url http://127.0.0.1:3000 --create tourist [--difficulty 1200]
+//! battle-cp-cli --url http://127.0.0.1:3000 --join <game_id> --handle petr
+//! battle-cp-cli --url ... --join <game_id> --handle petr --player-id <id> --token <token>   # rejoin
+//! ```
+//!
+//! Commands are read line by line from stdin; `help` lists them. Both boards
+//! are redrawn after every shot and on `board`.
+
+mod command;
+mod render;
+
+use battle_cp_client::protocol::{ClientMessage, ServerMessage};
+use battle_cp_client::{create_game, Client, ClientError, Seat};
+use command::Command;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use uuid::Uuid;
+
+struct Args {
+    url: String,
+    create: Option<String>,
+    join: Option<Uuid>,
+    handle: Option<String>,
+    player_id: Option<Uuid>,
+    token: Option<String>,
+    difficulty: Option<u32>,
+}
+
+impl Args {
+    fn parse() -> anyhow::Result<Self> {
+        let mut args = Self {
+            url: "http://127.0.0.1:3000".to_string(),
+            create: None,
+            join: None,
+            handle: None,
+            player_id: None,
+            token: None,
+            difficulty: None,
+        };
+        let mut it = std::env::args().skip(1);
+        while let Some(flag) = it.next() {
+            let value = it
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("missing value for {}", flag))?;
+            match flag.as_str() {
+                "--url" => args.url = value.trim_end_matches('/').to_string(),
+                "--create" => args.create = Some(value),
+                "--join" => args.join = Some(value.parse()?),
+                "--handle" => args.handle = Some(value),
+                "--player-id" => args.player_id = Some(value.parse()?),
+                "--token" => args.token = Some(value),
+                "--difficulty" => args.difficulty = Some(value.parse()?),
+                other => anyhow::bail!(
+                    "unknown flag {} (expected --url, --create, --join, --handle, --player-id, --token, --difficulty)",
+                    other
+                ),
+            }
+        }
+        Ok(args)
+    }
+
+    async fn seat(&self) -> anyhow::Result<Seat> {
+        match (&self.create, self.join, &self.handle) {
+            (Some(handle), None, _) => {
+                let mut settings = serde_json::json!({});
+                if let Some(difficulty) = self.difficulty {
+                    settings["difficulty"] = difficulty.into();
+                }
+                let seat = create_game(&self.url, handle, &settings).await?;
+                println!("Created game {}", seat.game_id);
+                println!("Opponent joins with: --join {}", seat.game_id);
+                Ok(seat)
+            }
+            (None, Some(game_id), Some(handle)) => Ok(Seat {
+                game_id,
+                player_id: self.player_id.unwrap_or_else(Uuid::new_v4),
+                cf_handle: handle.clone(),
+                token: self.token.clone(),
+            }),
+            _ => anyhow::bail!("pass either --create <handle>, or --join <game_id> --handle <handle>"),
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse()?;
+    let seat = args.seat().await?;
+    let mut client = Client::join(&args.url, seat).await?;
+    let seat = client.seat();
+    println!(
+        "Joined as {} ({}). Rejoin with --player-id {}{}",
+        seat.cf_handle,
+        seat.player_id,
+        seat.player_id,
+        seat.token.as_deref().map(|t| format!(" --token {}", t)).unwrap_or_default()
+    );
+    println!("Type `help` for commands.");
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut printed_token = client.seat().token.is_some();
+    loop {
+        tokio::select! {
+            line = lines.next_line() => {
+                let Some(line) = line? else { break };
+                let command = match line.parse::<Command>() {
+                    Ok(command) => command,
+                    Err(e) => {
+                        println!("{}", e);
+                        continue;
+                    }
+                };
+                match command {
+                    Command::Help => println!("{}", command::HELP),
+                    Command::Board => print!("{}", render::screen(client.view())),
+                    Command::Quit => break,
+                    Command::Solve => {
+                        let view = client.view();
+                        let problem = if view.is_locked { &view.active_problem } else { &view.bonus_problem };
+                        match problem.clone() {
+                            Some((contest_id, index, _)) => client.solve(contest_id, index).await?,
+                            None => println!("No problem assigned"),
+                        }
+                    }
+                    // Through the client so our board shows the fleet once confirmed
+                    Command::Send(ClientMessage::PlaceShips { ships, forward: None }) => client.place_ships(ships).await?,
+                    Command::Send(msg) => client.send(msg).await?,
+                }
+            }
+            msg = client.recv() => {
+                let msg = match msg {
+                    Ok(Some(msg)) => msg,
+                    Ok(None) => break,
+                    Err(ClientError::Json(e)) => {
+                        println!("(unreadable message: {})", e);
+                        continue;
+                    }
+                    Err(e) => return Err(e.into()),
+                };
+                if !printed_token {
+                    if let Some(token) = &client.seat().token {
+                        println!("Session token: {} (needed to rejoin with --token)", token);
+                        printed_token = true;
+                    }
+                }
+                if let Some(line) = render::event(client.view(), &msg) {
+                    println!("{}", line);
+                }
+                if matches!(
+                    msg,
+                    ServerMessage::ShotResult { .. }
+                        | ServerMessage::SpecialShotResult { .. }
+                        | ServerMessage::GameStart
+                        | ServerMessage::GridSync { .. }
+                        | ServerMessage::ShipsConfirmed { .. }
+                        | ServerMessage::ShieldPlaced { .. }
+                ) {
+                    print!("{}", render::screen(client.view()));
+                }
+            }
+        }
+    }
+    Ok(())
+}
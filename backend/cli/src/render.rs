@@ -0,0 +1,236 @@
+use battle_cp_client::protocol::{DifficultyVote, ServerMessage};
+use battle_cp_client::GameView;
+
+/// Both boards side by side, then a status line with heat and timers.
+pub fn screen(view: &GameView) -> String {
+    let mut out = String::from(if view.coop {
+        "\n     YOUR FLEET                  HIDDEN FLEET (co-op)\n"
+    } else {
+        "\n     YOUR FLEET                  ENEMY WATERS\n"
+    });
+    let header: String = (0..10).map(|x| format!(" {}", (b'A' + x) as char)).collect();
+    out.push_str(&format!("   {}      {}\n", header, header));
+    for y in 0..10 {
+        let row = |grid: &[Vec<String>]| -> String {
+            grid.get(y)
+                .map(|cells| cells.iter().map(|c| format!(" {}", glyph(c))).collect())
+                .unwrap_or_default()
+        };
+        out.push_str(&format!(
+            "{:>2} {}   {:>2} {}\n",
+            y + 1,
+            row(&view.my_grid),
+            y + 1,
+            row(&view.enemy_grid)
+        ));
+    }
+    out.push_str(&status(view));
+    out.push('\n');
+    out
+}
+
+fn glyph(cell: &str) -> char {
+    match cell {
+        "ship" => '#',
+        "shielded" => '@',
+        "hit" => 'X',
+        "miss" => 'o',
+        _ => '.',
+    }
+}
+
+fn status(view: &GameView) -> String {
+    let mut line = format!(
+        "{:?} | {} | vetoes {} | {}",
+        view.status,
+        match view.ammo {
+            Some(ammo) => format!("ammo {}", ammo),
+            None => format!("heat {}/{}", view.heat, view.max_heat),
+        },
+        view.vetoes_remaining,
+        clock(view.time_remaining_secs)
+    );
+    if view.clock_paused {
+        line.push_str(" | clock paused (judge unreachable)");
+    }
+    if let Some(secs) = view.phase_time_remaining_secs {
+        line.push_str(&format!(" | phase ends in {}", clock(secs)));
+    }
+    for weapon in &view.special_weapons {
+        line.push_str(&format!(" | {:?} shot ready", weapon));
+    }
+    if view.shields_available > 0 {
+        line.push_str(&format!(" | shields to place: {}", view.shields_available));
+    }
+    let other = if view.coop { "teammate" } else { "opponent" };
+    if view.opponent_connected == Some(false) {
+        line.push_str(&format!(" | {} offline", other));
+    } else if view.opponent_working {
+        line.push_str(&format!(" | {} is working on a problem", other));
+    }
+    if let Some(rtt) = view.opponent_rtt_ms {
+        line.push_str(&format!(" | {} ping {}ms", other, rtt));
+    }
+    if view.spectators > 0 {
+        line.push_str(&format!(" | {} watching", view.spectators));
+    }
+    if let (false, Some((contest_id, index, name))) = (view.is_locked, &view.bonus_problem) {
+        line.push_str(&format!(" | bonus: {}{} {:?}", contest_id, index, name));
+    }
+    if view.is_locked {
+        line.push_str(" | LOCKED");
+        if let Some((contest_id, index, name)) = &view.active_problem {
+            let url = match &view.active_problem_url {
+                Some(url) => url.clone(),
+                None => format!("https://codeforces.com/contest/{}/problem/{}", contest_id, index),
+            };
+            line.push_str(&format!(" — solve {}{} {:?} ({})", contest_id, index, name, url));
+        }
+        if let Some((tags, rating)) = &view.hint {
+            line.push_str(&format!(" | hint: rated {}, {}", rating, tags.join(", ")));
+        }
+        if let Some(secs) = view.veto_time_remaining_secs {
+            line.push_str(&format!(" | veto unlocks in {}", clock(secs)));
+        }
+    }
+    line
+}
+
+fn clock(secs: u64) -> String {
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+/// "on Codeforces", or the link a problem from another judge came with.
+fn where_to_solve(url: &Option<String>) -> String {
+    match url {
+        Some(url) => format!("at {}", url),
+        None => "on Codeforces".to_string(),
+    }
+}
+
+/// A one-line note for messages worth telling the player about.
+pub fn event(view: &GameView, msg: &ServerMessage) -> Option<String> {
+    let me = view.player_id;
+    let who = |id: uuid::Uuid| if id == me { "You" } else { "Opponent" };
+    Some(match msg {
+        ServerMessage::PlayerJoined { player_id } if *player_id != me => "Opponent joined".to_string(),
+        ServerMessage::Presence { player_id, connected } if *player_id != me => {
+            format!("Opponent {}", if *connected { "is back online" } else { "went offline" })
+        }
+        ServerMessage::ShipsConfirmed { player_id } => format!("{} placed ships", who(*player_id)),
+        ServerMessage::PlayerReady { player_id } => format!("{} ready", who(*player_id)),
+        ServerMessage::Countdown { seconds_remaining } => format!("Starting in {}…", seconds_remaining),
+        ServerMessage::GameStart => "Combat!".to_string(),
+        ServerMessage::ProblemSet { problems } => {
+            let list: Vec<String> = problems
+                .iter()
+                .map(|p| format!("  {}{} {:?} (rated {})", p.contest_id, p.index, p.name, p.rating))
+                .collect();
+            format!("This game's problems — read ahead:\n{}", list.join("\n"))
+        }
+        ServerMessage::ShotResult { coord, hit, sunk, blocked, shooter_id, .. } => format!(
+            "{} fired at {}: {}",
+            who(*shooter_id),
+            coord,
+            match (hit, sunk, blocked) {
+                (_, _, true) => "blocked by a shield",
+                (_, true, _) => "SUNK",
+                (true, _, _) => "hit",
+                _ => "miss",
+            }
+        ),
+        ServerMessage::SpecialShotResult { shooter_id, weapon, coord, shots, .. } => format!(
+            "{} fired a {:?} shot at {}: {} hit, {} sunk",
+            who(*shooter_id),
+            weapon,
+            coord,
+            shots.iter().filter(|s| s.hit).count(),
+            shots.iter().filter(|s| s.sunk).count()
+        ),
+        ServerMessage::WeaponGranted { player_id, weapon } => {
+            format!("{} earned a {:?} shot with a fast solve", who(*player_id), weapon)
+        }
+        ServerMessage::BonusProblemAssigned { player_id, contest_id, problem_index, problem_name, rating, url }
+            if *player_id == me =>
+        {
+            format!(
+                "Bonus: solve {}{} {:?} (rated {}) {}, then type `solve` to earn a shield",
+                contest_id, problem_index, problem_name, rating, where_to_solve(url)
+            )
+        }
+        ServerMessage::ShieldEarned { player_id } => format!("{} earned a shield", who(*player_id)),
+        ServerMessage::ShieldPlaced { coord, .. } => format!("Shield placed on {}", coord),
+        ServerMessage::DifficultyVoteCast { player_id, direction } => format!(
+            "{} voted to make problems {} ({})",
+            who(*player_id),
+            match direction {
+                DifficultyVote::Up => "harder",
+                DifficultyVote::Down => "easier",
+            },
+            if *player_id == me { "waiting for the opponent" } else { "type `harder`/`easier` to agree" }
+        ),
+        ServerMessage::DifficultyChanged { difficulty, difficulty_mode } => {
+            format!("Difficulty is now {} ({:?})", difficulty, difficulty_mode)
+        }
+        ServerMessage::WeaponsLocked { player_id } => format!("{} overheated — weapons locked", who(*player_id)),
+        ServerMessage::WeaponsUnlocked { player_id, reason } => {
+            format!("{} unlocked ({})", who(*player_id), reason)
+        }
+        ServerMessage::ProblemAssigned { player_id, contest_id, problem_index, problem_name, rating, url }
+            if *player_id == me =>
+        {
+            format!(
+                "Solve {}{} {:?} (rated {}) {}, then type `solve`",
+                contest_id, problem_index, problem_name, rating, where_to_solve(url)
+            )
+        }
+        ServerMessage::Hint { contest_id, problem_index, rating, tags, max_heat } => format!(
+            "Hint for {}{}: rated {}, tags: {} (your heat limit is now {})",
+            contest_id,
+            problem_index,
+            rating,
+            if tags.is_empty() { "unavailable".to_string() } else { tags.join(", ") },
+            max_heat
+        ),
+        ServerMessage::VerifyPending { player_id } if *player_id == me => "Checking Codeforces…".to_string(),
+        ServerMessage::VerifyResult { player_id, message, .. } if *player_id == me => message.clone(),
+        ServerMessage::GameOver {
+            winner_id,
+            reason,
+            p1_id,
+            p1_locks_solved,
+            p1_locks_assigned,
+            p2_locks_solved,
+            p2_locks_assigned,
+            ..
+        } => {
+            let (solved, assigned) = if *p1_id == me {
+                (p1_locks_solved, p1_locks_assigned)
+            } else {
+                (p2_locks_solved, p2_locks_assigned)
+            };
+            format!(
+                "Game over ({}): {} (solved {}/{} lock problems)",
+                reason,
+                match winner_id {
+                    Some(id) if *id == me => "you win!",
+                    Some(_) => "you lose",
+                    None if reason == "CoopVictory" => "your team sank the fleet!",
+                    None if reason == "CoopTimeUp" => "the fleet survived",
+                    None => "draw",
+                },
+                solved,
+                assigned
+            )
+        }
+        ServerMessage::LobbyExpiring { in_secs } => {
+            format!("Lobby closes in {} unless someone joins (`extend` to keep it open)", clock(*in_secs))
+        }
+        ServerMessage::LobbyExtended { in_secs, extensions_left } => {
+            format!("Lobby extended: closes in {} ({} extensions left)", clock(*in_secs), extensions_left)
+        }
+        ServerMessage::GameExpired { reason } => format!("Game closed by the server ({})", reason),
+        ServerMessage::Error { code, message, .. } => format!("Error [{}]: {}", code, message),
+        _ => return None,
+    })
+}
@@ -0,0 +1,425 @@
+use futures_util::{SinkExt, StreamExt};
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+use crate::error::ClientError;
+use crate::lobby::Seat;
+use crate::protocol::{
+    Board, ClientMessage, CloseCode, CloseReason, Coord, DifficultyVote, MarkerKind, ServerMessage, ShipPlacement,
+    SpecialWeapon,
+    StampedMessage,
+};
+use crate::view::GameView;
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// How long to wait for `GameJoined` after sending `JoinGame`.
+const JOIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The stamp on a broadcast (see `protocol::StampedMessage`); absent on direct replies.
+#[derive(serde::Deserialize)]
+struct EventId {
+    event_id: Option<u64>,
+}
+
+/// How a [`Client`] retries when its socket drops before the game is over.
+/// Each attempt opens a new socket with the seat's session token and sends
+/// `Resume` (replaying the events missed) while the server's resume window
+/// lasts, else `JoinGame` again for a full resync.
+#[derive(Debug, Clone)]
+pub struct ReconnectPolicy {
+    /// 0 disables reconnecting
+    pub max_attempts: u32,
+    /// Doubles after each failed attempt, up to `max_backoff`
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Try `Resume` before rejoining
+    pub resume: bool,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(5),
+            resume: true,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    pub fn never() -> Self {
+        Self {
+            max_attempts: 0,
+            ..Self::default()
+        }
+    }
+}
+
+/// A player's WebSocket connection to one game.
+///
+/// Commands (`place_ships`, `fire`, …) only send; results arrive as
+/// `ServerMessage`s from `recv` / `wait_for`, which also keep `view()` up to
+/// date. Server-side validation errors arrive as `ServerMessage::Error`.
+pub struct Client {
+    ws_base: String,
+    seat: Seat,
+    socket: Socket,
+    view: GameView,
+    policy: ReconnectPolicy,
+    reconnects: u32,
+    expired: bool,
+    /// From the server's `ResumeToken`, for `Resume` on reconnect
+    resume_token: Option<String>,
+    /// Why the server last closed the socket, if it said
+    close_reason: Option<CloseReason>,
+    /// Read while joining; handed out by `recv` before anything new
+    pending: VecDeque<ServerMessage>,
+    /// The rest of a `Batch`, read off before the socket
+    unbatched: VecDeque<StampedMessage>,
+}
+
+impl Client {
+    /// Connect to `server_url` (`http(s)://` or `ws(s)://`) and take `seat`.
+    /// Fails with `ClientError::Server` if the server refuses the seat (e.g.
+    /// "game_full", "seat_reserved").
+    pub async fn join(server_url: &str, seat: Seat) -> Result<Self, ClientError> {
+        let ws_base = server_url
+            .trim_end_matches('/')
+            .replacen("https://", "wss://", 1)
+            .replacen("http://", "ws://", 1);
+        let socket = connect(&ws_base, &seat).await?;
+        let mut client = Self {
+            ws_base,
+            view: GameView::new(seat.player_id),
+            seat,
+            socket,
+            policy: ReconnectPolicy::default(),
+            reconnects: 0,
+            expired: false,
+            resume_token: None,
+            close_reason: None,
+            pending: VecDeque::new(),
+            unbatched: VecDeque::new(),
+        };
+        client.send_join().await?;
+        Ok(client)
+    }
+
+    pub fn with_reconnect(mut self, policy: ReconnectPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// The seat, including the session token once the server has issued one.
+    pub fn seat(&self) -> &Seat {
+        &self.seat
+    }
+
+    pub fn view(&self) -> &GameView {
+        &self.view
+    }
+
+    /// How many times the socket has been re-established.
+    pub fn reconnects(&self) -> u32 {
+        self.reconnects
+    }
+
+    /// Why the server closed the socket most recently, from its Close frame.
+    pub fn close_reason(&self) -> Option<&CloseReason> {
+        self.close_reason.as_ref()
+    }
+
+    pub async fn place_ships(&mut self, ships: Vec<ShipPlacement>) -> Result<(), ClientError> {
+        self.view.proposed_ships = ships.clone();
+        self.send(ClientMessage::PlaceShips { ships, forward: None }).await
+    }
+
+    /// Armada games: place the home and forward fleets together.
+    pub async fn place_armada(&mut self, ships: Vec<ShipPlacement>, forward: Vec<ShipPlacement>) -> Result<(), ClientError> {
+        self.view.proposed_ships = ships.clone();
+        self.view.proposed_forward = forward.clone();
+        self.send(ClientMessage::PlaceShips { ships, forward: Some(forward) }).await
+    }
+
+    /// Stage one ship; the fleet is committed when the last one is in.
+    pub async fn place_ship(&mut self, ship: ShipPlacement) -> Result<(), ClientError> {
+        self.send(ClientMessage::PlaceShip { ship }).await
+    }
+
+    pub async fn unplace_ship(&mut self, index: usize) -> Result<(), ClientError> {
+        self.send(ClientMessage::UnplaceShip { index }).await
+    }
+
+    pub async fn ready(&mut self) -> Result<(), ClientError> {
+        self.send(ClientMessage::Ready).await
+    }
+
+    pub async fn fire(&mut self, at: Coord) -> Result<(), ClientError> {
+        self.fire_on(Board::Home, at).await
+    }
+
+    /// Fire at one of the opponent's boards (armada games have two).
+    pub async fn fire_on(&mut self, board: Board, at: Coord) -> Result<(), ClientError> {
+        self.send(ClientMessage::Fire { at, board }).await
+    }
+
+    pub async fn fire_special(&mut self, weapon: SpecialWeapon, at: Coord) -> Result<(), ClientError> {
+        self.send(ClientMessage::FireSpecial { weapon, at, board: Board::Home }).await
+    }
+
+    /// Claim the assigned problem is solved; the server checks Codeforces.
+    pub async fn solve(&mut self, contest_id: i32, problem_index: impl Into<String>) -> Result<(), ClientError> {
+        self.send(ClientMessage::SolveCP {
+            contest_id,
+            problem_index: problem_index.into(),
+        })
+        .await
+    }
+
+    pub async fn veto(&mut self) -> Result<(), ClientError> {
+        self.send(ClientMessage::Veto).await
+    }
+
+    pub async fn request_bonus_problem(&mut self) -> Result<(), ClientError> {
+        self.send(ClientMessage::RequestBonusProblem).await
+    }
+
+    pub async fn place_shield(&mut self, at: Coord) -> Result<(), ClientError> {
+        self.send(ClientMessage::PlaceShield { at }).await
+    }
+
+    /// Note (or with `None`, clear) a private marker on an enemy cell.
+    pub async fn set_marker(&mut self, at: Coord, marker: Option<MarkerKind>) -> Result<(), ClientError> {
+        self.send(ClientMessage::SetMarker { at, marker }).await
+    }
+
+    pub async fn request_hint(&mut self) -> Result<(), ClientError> {
+        self.send(ClientMessage::RequestHint).await
+    }
+
+    pub async fn extend_lobby(&mut self) -> Result<(), ClientError> {
+        self.send(ClientMessage::ExtendLobby).await
+    }
+
+    pub async fn vote_difficulty(&mut self, direction: DifficultyVote) -> Result<(), ClientError> {
+        self.send(ClientMessage::VoteDifficulty { direction }).await
+    }
+
+    /// Send any message, reconnecting first if the socket has dropped.
+    pub async fn send(&mut self, msg: ClientMessage) -> Result<(), ClientError> {
+        let text = serde_json::to_string(&msg)?;
+        if self.socket.send(Message::Text(text.clone())).await.is_ok() {
+            return Ok(());
+        }
+        self.reconnect().await?;
+        self.socket.send(Message::Text(text)).await?;
+        Ok(())
+    }
+
+    /// The next message from the server, reconnecting if the socket drops
+    /// mid-game. `None` once the game is over and the server has closed.
+    pub async fn recv(&mut self) -> Result<Option<ServerMessage>, ClientError> {
+        if let Some(msg) = self.pending.pop_front() {
+            return Ok(Some(msg));
+        }
+        loop {
+            match self.read().await? {
+                Some(msg) => return Ok(Some(msg)),
+                None if self.is_over() => return Ok(None),
+                None => {
+                    self.reconnect().await?;
+                    if let Some(msg) = self.pending.pop_front() {
+                        return Ok(Some(msg));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Receive until a message matches `pred` and return it. Skipped messages
+    /// are still applied to `view()`.
+    pub async fn wait_for(
+        &mut self,
+        mut pred: impl FnMut(&ServerMessage) -> bool,
+        timeout: Duration,
+    ) -> Result<ServerMessage, ClientError> {
+        tokio::time::timeout(timeout, async {
+            loop {
+                match self.recv().await? {
+                    Some(msg) if pred(&msg) => return Ok(msg),
+                    Some(_) => {}
+                    None => return Err(ClientError::Closed),
+                }
+            }
+        })
+        .await
+        .map_err(|_| ClientError::Timeout)?
+    }
+
+    pub async fn close(mut self) -> Result<(), ClientError> {
+        self.socket.close(None).await?;
+        Ok(())
+    }
+
+    /// The game has ended (or been dropped by the server); nothing to rejoin.
+    fn is_over(&self) -> bool {
+        self.expired || self.view.game_over.is_some()
+    }
+
+    /// Next message off the socket, applied to the view. `None` when the
+    /// socket is gone. A `Batch` is handed out one event at a time.
+    async fn read(&mut self) -> Result<Option<ServerMessage>, ClientError> {
+        loop {
+            if let Some(event) = self.unbatched.pop_front() {
+                self.view.last_event_id = event.event_id;
+                return Ok(Some(self.accept(event.message)));
+            }
+            match self.socket.next().await {
+                Some(Ok(Message::Text(text))) => {
+                    let msg: ServerMessage = serde_json::from_str(&text)?;
+                    if let ServerMessage::Batch { events } = msg {
+                        self.unbatched.extend(events);
+                        continue;
+                    }
+                    // Answered here; latency pings aren't game messages
+                    if let ServerMessage::Ping { sent_at_ms } = msg {
+                        let pong = serde_json::to_string(&ClientMessage::Pong { sent_at_ms })?;
+                        let _ = self.socket.send(Message::Text(pong)).await;
+                        continue;
+                    }
+                    // Broadcasts carry their place in the game's event order
+                    if let Ok(EventId { event_id: Some(id) }) = serde_json::from_str(&text) {
+                        self.view.last_event_id = id;
+                    }
+                    return Ok(Some(self.accept(msg)));
+                }
+                Some(Ok(Message::Close(frame))) => {
+                    self.close_reason = frame.and_then(|f| {
+                        serde_json::from_str(&f.reason)
+                            .ok()
+                            .or_else(|| CloseCode::from_status(f.code.into()).map(CloseReason::from))
+                    });
+                    return Ok(None);
+                }
+                Some(Err(_)) | None => {
+                    self.close_reason = None;
+                    return Ok(None);
+                }
+                Some(Ok(_)) => {}
+            }
+        }
+    }
+
+    /// Keep what `msg` hands us and apply it to the view.
+    fn accept(&mut self, msg: ServerMessage) -> ServerMessage {
+        match &msg {
+            ServerMessage::SessionToken { token } => self.seat.token = Some(token.clone()),
+            ServerMessage::ResumeToken { token, .. } => self.resume_token = Some(token.clone()),
+            ServerMessage::Resumed { events } => {
+                for event in events {
+                    self.view.last_event_id = event.event_id;
+                    self.view.apply(&event.message);
+                }
+            }
+            ServerMessage::GameExpired { .. } => self.expired = true,
+            _ => {}
+        }
+        self.view.apply(&msg);
+        msg
+    }
+
+    /// Send `JoinGame` and read up to `GameJoined`, queueing everything read
+    /// for `recv`. An error before the join is the server refusing the seat.
+    async fn send_join(&mut self) -> Result<(), ClientError> {
+        let join = ClientMessage::JoinGame {
+            player_id: self.seat.player_id,
+            cf_handle: self.seat.cf_handle.clone(),
+            locale: None,
+        };
+        self.join_with(join).await
+    }
+
+    /// `Resume` where we hold a resume token, falling back to `JoinGame` on
+    /// the same socket once the window has passed.
+    async fn send_resume(&mut self) -> Result<(), ClientError> {
+        let Some(token) = self.resume_token.clone().filter(|_| self.policy.resume) else {
+            return self.send_join().await;
+        };
+        let resume = ClientMessage::Resume { token, last_event_id: self.view.last_event_id };
+        match self.join_with(resume).await {
+            Err(ClientError::Server { code, .. }) if code == "resume_expired" => {
+                self.resume_token = None;
+                self.send_join().await
+            }
+            result => result,
+        }
+    }
+
+    async fn join_with(&mut self, join: ClientMessage) -> Result<(), ClientError> {
+        self.socket.send(Message::Text(serde_json::to_string(&join)?)).await?;
+        tokio::time::timeout(JOIN_TIMEOUT, async {
+            loop {
+                match self.read().await? {
+                    Some(ServerMessage::Error { code, message, .. }) => {
+                        return Err(ClientError::Server { code, message })
+                    }
+                    Some(msg) => {
+                        let joined = matches!(msg, ServerMessage::GameJoined { .. });
+                        self.pending.push_back(msg);
+                        if joined {
+                            return Ok(());
+                        }
+                    }
+                    None => return Err(ClientError::Closed),
+                }
+            }
+        })
+        .await
+        .map_err(|_| ClientError::Timeout)?
+    }
+
+    async fn reconnect(&mut self) -> Result<(), ClientError> {
+        if let Some(reason) = self.close_reason.clone().filter(|r| !r.code.can_reconnect() && !self.is_over()) {
+            return Err(ClientError::ClosedByServer(reason));
+        }
+        if self.is_over() || self.policy.max_attempts == 0 {
+            return Err(ClientError::Closed);
+        }
+        let mut backoff = self.policy.initial_backoff;
+        for _ in 0..self.policy.max_attempts {
+            tokio::time::sleep(backoff).await;
+            let attempt = match connect(&self.ws_base, &self.seat).await {
+                Ok(socket) => {
+                    self.socket = socket;
+                    self.send_resume().await
+                }
+                Err(e) => Err(e),
+            };
+            match attempt {
+                Ok(()) => {
+                    self.reconnects += 1;
+                    return Ok(());
+                }
+                // Refused outright (seat taken, banned, game gone): retrying won't help
+                Err(e @ ClientError::Server { .. }) => return Err(e),
+                Err(_) => backoff = (backoff * 2).min(self.policy.max_backoff),
+            }
+        }
+        Err(ClientError::ReconnectFailed(self.policy.max_attempts))
+    }
+}
+
+async fn connect(ws_base: &str, seat: &Seat) -> Result<Socket, ClientError> {
+    let mut url = format!("{}/ws/{}?player_id={}&batch=true", ws_base, seat.game_id, seat.player_id);
+    if let Some(token) = &seat.token {
+        url.push_str("&token=");
+        url.push_str(token);
+    }
+    let (socket, _) = tokio_tungstenite::connect_async(url).await?;
+    Ok(socket)
+}
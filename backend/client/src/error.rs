@@ -0,0 +1,31 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ClientError {
+    #[error("WebSocket error: {0}")]
+    WebSocket(Box<tokio_tungstenite::tungstenite::Error>),
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("Invalid message: {0}")]
+    Json(#[from] serde_json::Error),
+    /// The server refused a request. `code` is its error code (e.g.
+    /// "game_full"), or the HTTP status for REST calls.
+    #[error("Server error {code}: {message}")]
+    Server { code: String, message: String },
+    #[error("Connection closed")]
+    Closed,
+    /// The server closed the socket for a reason reconnecting won't fix
+    /// (kicked, game removed, ...).
+    #[error("Closed by the server: {}", .0.message)]
+    ClosedByServer(crate::protocol::CloseReason),
+    #[error("Gave up reconnecting after {0} attempts")]
+    ReconnectFailed(u32),
+    #[error("Timed out waiting for the server")]
+    Timeout,
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for ClientError {
+    fn from(e: tokio_tungstenite::tungstenite::Error) -> Self {
+        Self::WebSocket(Box::new(e))
+    }
+}
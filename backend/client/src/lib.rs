@@ -0,0 +1,61 @@
+//! Client SDK for Battle-CP servers.
+//!
+//! `protocol` holds the typed `ClientMessage` / `ServerMessage` frames (the
+//! server uses the same definitions). With the default `client` feature the
+//! crate also provides a tokio WebSocket [`Client`] that joins a seat, keeps a
+//! [`GameView`] of the board in sync, and reconnects with the seat's session
+//! token when the socket drops, plus [`create_game`] for opening lobbies.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), battle_cp_client::ClientError> {
+//! use battle_cp_client::protocol::{ServerMessage, ShipPlacement};
+//! use battle_cp_client::{create_game, Client};
+//! use std::time::Duration;
+//!
+//! let server = "http://127.0.0.1:3000";
+//! let seat = create_game(server, "tourist", &serde_json::json!({ "heat_threshold": 5 })).await?;
+//! println!("Opponent joins at /game/{}", seat.game_id);
+//! let mut bot = Client::join(server, seat).await?;
+//!
+//! let fleet = [(5, 0), (4, 2), (3, 4), (3, 6), (2, 8)]
+//!     .map(|(size, y)| ShipPlacement { x: 0, y, size, vertical: false });
+//! bot.place_ships(fleet.to_vec()).await?;
+//! bot.wait_for(|m| matches!(m, ServerMessage::PlayerJoined { .. }), Duration::from_secs(600)).await?;
+//! bot.ready().await?;
+//! bot.wait_for(|m| matches!(m, ServerMessage::GameStart), Duration::from_secs(600)).await?;
+//! while bot.view().game_over.is_none() {
+//!     let target = bot.view().untargeted().next().filter(|_| !bot.view().is_locked);
+//!     match target {
+//!         Some(target) => {
+//!             bot.fire(target).await?;
+//!             let shot = |m: &ServerMessage| matches!(m, ServerMessage::ShotResult { .. } | ServerMessage::Error { .. });
+//!             bot.wait_for(shot, Duration::from_secs(5)).await?;
+//!         }
+//!         None => {
+//!             bot.recv().await?;
+//!         }
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod protocol;
+
+#[cfg(feature = "client")]
+mod client;
+#[cfg(feature = "client")]
+mod error;
+#[cfg(feature = "client")]
+mod lobby;
+#[cfg(feature = "client")]
+mod view;
+
+#[cfg(feature = "client")]
+pub use client::{Client, ReconnectPolicy};
+#[cfg(feature = "client")]
+pub use error::ClientError;
+#[cfg(feature = "client")]
+pub use lobby::{create_game, Seat};
+#[cfg(feature = "client")]
+pub use view::GameView;
@@ -0,0 +1,80 @@
+use uuid::Uuid;
+
+use crate::error::ClientError;
+
+/// Credentials for one seat in a game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Seat {
+    pub game_id: Uuid,
+    pub player_id: Uuid,
+    pub cf_handle: String,
+    /// Session token for reclaiming the seat. Hosts get one from
+    /// `POST /api/game`; guests are sent theirs when they take the seat.
+    /// Before that a bot's seat holds its bot token.
+    pub token: Option<String>,
+}
+
+impl Seat {
+    /// A fresh guest seat for `game_id`.
+    pub fn guest(game_id: Uuid, cf_handle: impl Into<String>) -> Self {
+        Self {
+            game_id,
+            player_id: Uuid::new_v4(),
+            cf_handle: cf_handle.into(),
+            token: None,
+        }
+    }
+
+    /// A fresh guest seat for a bot, joining with its bot token (from
+    /// `POST /api/admin/bots`) until the server issues a session token.
+    pub fn bot(game_id: Uuid, cf_handle: impl Into<String>, bot_token: impl Into<String>) -> Self {
+        Self {
+            token: Some(bot_token.into()),
+            ..Self::guest(game_id, cf_handle)
+        }
+    }
+}
+
+/// Open a lobby with `POST /api/game` and return the host's seat. `settings`
+/// is a JSON object of optional lobby settings (`difficulty`,
+/// `heat_threshold`, `game_duration_mins`, `scheduled_start`, …).
+pub async fn create_game(server_url: &str, cf_handle: &str, settings: &serde_json::Value) -> Result<Seat, ClientError> {
+    let mut body = match settings {
+        serde_json::Value::Object(map) => map.clone(),
+        _ => serde_json::Map::new(),
+    };
+    body.insert("cf_handle".to_string(), cf_handle.into());
+
+    let res = reqwest::Client::new()
+        .post(format!("{}/api/game", server_url.trim_end_matches('/')))
+        .json(&body)
+        .send()
+        .await?;
+    let status = res.status();
+    let body: serde_json::Value = res.json().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(ClientError::Server {
+            code: body["code"].as_str().map(str::to_string).unwrap_or_else(|| status.as_u16().to_string()),
+            message: body["error"].as_str().unwrap_or_default().to_string(),
+        });
+    }
+
+    let field = |key: &str| {
+        body[key].as_str().map(str::to_string).ok_or_else(|| ClientError::Server {
+            code: "invalid_response".to_string(),
+            message: format!("missing {}", key),
+        })
+    };
+    let uuid = |key: &str| {
+        field(key)?.parse::<Uuid>().map_err(|e| ClientError::Server {
+            code: "invalid_response".to_string(),
+            message: format!("{}: {}", key, e),
+        })
+    };
+    Ok(Seat {
+        game_id: uuid("game_id")?,
+        player_id: uuid("player_id")?,
+        cf_handle: cf_handle.to_string(),
+        token: Some(field("token")?),
+    })
+}
@@ -0,0 +1,898 @@
+//! JSON messages between client and server (WebSocket frames are one message
+//! each, tagged by `type`), plus the public game views the REST API returns.
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Which difficulty system to use when picking problems for a locked player.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DifficultyMode {
+    /// Standard CF mode: `difficulty` is an exact CF rating (800, 900, … 3500).
+    #[default]
+    Cf,
+    /// Band mode: `difficulty` is a band id (0 = SuperEasy … 4 = VeryHard).
+    /// Bands map to clist.by rating ranges (0–300, 301–600, 601–1000, 1001–1500, 1501+).
+    Band,
+}
+
+/// What a `RequestHint` costs the player who asks. Hints are off by default.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HintCost {
+    #[default]
+    Off,
+    /// Your next veto lasts 2 minutes longer
+    LongerVeto,
+    /// You overheat one shot sooner for the rest of the game
+    LowerMaxHeat,
+}
+
+/// How sudden death (a tie when the clock runs out) is played.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SuddenDeathMode {
+    /// The first hit wins
+    #[default]
+    FirstHit,
+    /// Each player gets 10 shots; the better hit rate wins
+    Accuracy,
+}
+
+/// One step of a heat ramp: from `after_mins` into combat, weapons lock at
+/// `heat_threshold` (until the next step).
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HeatStep {
+    pub after_mins: u32,
+    pub heat_threshold: u32,
+}
+
+/// Ammo mode, instead of heat: each player starts with `starting` rounds,
+/// firing (a shot or a special volley) spends one, and the last one locks the
+/// weapons until a solve reloads `per_solve`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AmmoConfig {
+    pub starting: u32,
+    pub per_solve: u32,
+}
+
+/// Which way a `VoteDifficulty` asks to move the problem difficulty: one band,
+/// or 100 rating in CF mode.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DifficultyVote {
+    Up,
+    Down,
+}
+
+/// A player's own note on an enemy cell (`SetMarker`); nobody else sees it.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkerKind {
+    /// Probably part of a ship
+    Suspect,
+    /// Can't hold a ship
+    RuledOut,
+}
+
+/// Which of a player's boards a shot is aimed at. Only armada games have a
+/// `forward` fleet; everywhere else it's always `home`.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Board {
+    #[default]
+    Home,
+    Forward,
+}
+
+impl Board {
+    pub fn is_home(&self) -> bool {
+        *self == Self::Home
+    }
+}
+
+/// What an `ActivityEvent` in the in-game feed is about.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    /// The game's first hit
+    FirstBlood,
+    ShipSunk,
+    Locked,
+    VetoUsed,
+    SuddenDeath,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Marker {
+    pub x: usize,
+    pub y: usize,
+    pub kind: MarkerKind,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum GameStatus {
+    Waiting,       // Waiting for P2 to join
+    PlacingShips,  // Both players joined, placing ships
+    Initializing,  // Both placed ships, fetching CF data / waiting for both Ready
+    Countdown,     // Both ready and CF data loaded, counting down to combat
+    Playing,       // Both placed ships, combat phase
+    SuddenDeath,   // Tiebreaker: first hit wins
+    Finished,      // Game over
+    Abandoned,     // Both players disconnected mid-game; resumes if either reconnects
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ClientMessage {
+    JoinGame {
+        player_id: Uuid,
+        cf_handle: String,
+        /// BCP 47 tag (`es`, `es-MX`) for the server's human-readable text;
+        /// English if absent or unsupported
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        locale: Option<String>,
+    },
+    /// Instead of `JoinGame` on a reconnect: the `ResumeToken` and the last
+    /// broadcast received. Answered with `GameJoined` + `Resumed`, or a full
+    /// resync if too much was missed; `resume_expired` means rejoin.
+    Resume {
+        token: String,
+        last_event_id: u64,
+    },
+    PlaceShips {
+        ships: Vec<ShipPlacement>,
+        /// Armada games: the forward fleet, placed together with the home one
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        forward: Option<Vec<ShipPlacement>>,
+    },
+    /// Place one ship at a time instead of `PlaceShips`. The server keeps
+    /// the half-built fleet (resent as `StagedShips` on reconnect) and
+    /// commits it like `PlaceShips` once the last ship is in.
+    PlaceShip {
+        ship: ShipPlacement,
+    },
+    /// Take back a staged ship, by its position in `StagedShips`.
+    UnplaceShip {
+        index: usize,
+    },
+    /// Confirm readiness after placing ships. Combat begins once both are ready.
+    Ready,
+    /// `{"type": "Fire", "x": 1, "y": 6}` or `{"type": "Fire", "coord": "B7"}`;
+    /// in armada games add `"board": "forward"` to aim at the forward fleet.
+    Fire {
+        #[serde(flatten)]
+        at: Coord,
+        #[serde(default, skip_serializing_if = "Board::is_home")]
+        board: Board,
+    },
+    /// Fire a special weapon from the inventory, aimed like `Fire`:
+    /// `{"type": "FireSpecial", "weapon": "cross", "coord": "B7"}`.
+    FireSpecial {
+        weapon: SpecialWeapon,
+        #[serde(flatten)]
+        at: Coord,
+        #[serde(default, skip_serializing_if = "Board::is_home")]
+        board: Board,
+    },
+    SolveCP {
+        // Client sends this to verify their submission.
+        // contest_id and problem_index MUST match the server-assigned problem.
+        contest_id: i32,
+        problem_index: String,
+    },
+    Veto,
+    /// Ask for a bonus problem while unlocked; solving it (with `SolveCP`)
+    /// earns a shield.
+    RequestBonusProblem,
+    /// Spend an earned shield on one of your own unhit ship cells, aimed like `Fire`.
+    PlaceShield {
+        #[serde(flatten)]
+        at: Coord,
+    },
+    /// Ask to make future problems harder or easier. Takes effect once both
+    /// players have voted the same way; a new vote replaces your last one.
+    VoteDifficulty {
+        direction: DifficultyVote,
+    },
+    /// While locked: reveal the assigned problem's rating and tags, paying
+    /// the game's `HintCost`. Asking again for the same problem is free.
+    RequestHint,
+    /// Host only, while waiting for an opponent: push the lobby's expiry back
+    /// (a bounded number of times).
+    ExtendLobby,
+    /// Reply to `Ping`, echoing its `sent_at_ms`.
+    Pong {
+        sent_at_ms: u64,
+    },
+    /// Mark an enemy cell, aimed like `Fire`, for your own deduction; leave
+    /// out `marker` to clear it. Private, kept with the game and resent on
+    /// reconnect.
+    SetMarker {
+        #[serde(flatten)]
+        at: Coord,
+        #[serde(default)]
+        marker: Option<MarkerKind>,
+    },
+}
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type")]
+pub enum ServerMessage {
+    //handles server messages
+    GameJoined {
+        //player successfully joined the game + lobby settings
+        game_id: Uuid,
+        player_id: Uuid,
+        difficulty: u32,
+        difficulty_mode: DifficultyMode,
+        max_heat: u32,
+        max_vetoes: u32,
+        /// Seconds locked out per veto; vetoes past the end use the last entry
+        #[serde(default)]
+        veto_penalties: Vec<u64>,
+        /// Scheduled matches: combat starts at this Unix time (seconds)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        scheduled_start: Option<u64>,
+        /// What a hint costs; `off` when hints are disabled
+        #[serde(default)]
+        hint_cost: HintCost,
+        /// Heat ramp: `max_heat` shrinks at these points (see `GameUpdate::max_heat`)
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        heat_ramp: Vec<HeatStep>,
+        /// Ammo mode replaces heat (see `GameUpdate::ammo`)
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ammo: Option<AmmoConfig>,
+        #[serde(default)]
+        sudden_death: SuddenDeathMode,
+        /// Co-op game: both players fire at one hidden fleet with shared weapons
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        coop: bool,
+        /// Armada game: each player has a home and a forward fleet (see `Board`)
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        armada: bool,
+        /// Virtual-contest game: the problem set is revealed at `GameStart`
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        reveal_problems: bool,
+    },
+    PlayerJoined {
+        player_id: Uuid,
+    },
+    /// Sent to a guest right after they take the empty seat: the session token
+    /// they must present (`?token=`) to reconnect. Hosts get theirs from `POST /api/game`.
+    SessionToken {
+        token: String,
+    },
+    /// Sent to each player once they've joined: presented in `Resume` to pick
+    /// up where this socket left off, within `window_secs` of it dropping.
+    ResumeToken {
+        token: String,
+        window_secs: u64,
+    },
+    /// The broadcasts missed since `Resume::last_event_id`, in order; apply
+    /// them as if they had arrived live.
+    Resumed {
+        events: Vec<StampedMessage>,
+    },
+    /// Broadcasts caused by one action (a shot, its lock and the GameOver),
+    /// in order, to apply together. Only sent to sockets that asked for
+    /// batches (`?batch=true`); the rest get the events one by one.
+    Batch {
+        events: Vec<StampedMessage>,
+    },
+    //Placement Phase
+    ShipsConfirmed {
+        player_id: Uuid,
+    },
+    PlayerReady {
+        player_id: Uuid,
+    },
+    /// Pre-combat countdown (5, 4, 3, 2, 1) — GameStart follows immediately after 1.
+    Countdown {
+        seconds_remaining: u32,
+    },
+    GameStart,
+    /// Virtual-contest games (`reveal_problems`), right after `GameStart`: every
+    /// problem the game can assign, so players can read ahead
+    ProblemSet {
+        problems: Vec<RevealedProblem>,
+    },
+
+    //Combat Phase
+    GameUpdate {
+        status: String,
+        is_active: bool,
+        heat: u32,
+        is_locked: bool,
+        time_remaining_secs: u64,
+        vetoes_remaining: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        veto_time_remaining_secs: Option<u64>,
+        /// Seconds until the lobby expires (Waiting) or the placement deadline
+        /// (PlacingShips / Initializing). Absent once combat starts.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        phase_time_remaining_secs: Option<u64>,
+        // Server-assigned problem for the current lock session.
+        // NOTE: No skip_serializing_if — None serializes as `null` so the frontend
+        // can distinguish "no problem" (null) from "field not present" (undefined).
+        // This prevents stale problem data from persisting on the frontend.
+        active_problem_contest_id: Option<i32>,
+        active_problem_index: Option<String>,
+        active_problem_name: Option<String>,
+        /// Link to the problem on judges other than Codeforces
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        active_problem_url: Option<String>,
+        /// One-use weapons this player holds
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        special_weapons: Vec<SpecialWeapon>,
+        /// Shields earned from bonus problems and not yet placed
+        #[serde(default)]
+        shields_available: u32,
+        /// Heat at which weapons lock right now (heat ramp and hint penalties
+        /// included), in combat
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_heat: Option<u32>,
+        /// Rounds left, in ammo mode
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ammo: Option<u32>,
+    },
+    ShotResult {
+        x: usize,
+        y: usize,
+        hit: bool,
+        sunk: bool,
+        shooter_id: Uuid,
+        /// The same cell in grid notation, e.g. "B7".
+        coord: String,
+        /// When sunk=true, the (x,y) coordinates of every cell of the sunk ship.
+        /// Frontend uses this to color sunk-ship cells differently from normal hits.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        sunk_cells: Option<Vec<[usize; 2]>>,
+        /// A shield absorbed the shot (`hit` is false): the shield is gone
+        /// and the cell can be fired at again.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        blocked: bool,
+        /// The target's board the shot landed on (armada games)
+        #[serde(default, skip_serializing_if = "Board::is_home")]
+        board: Board,
+    },
+    /// A special weapon fired: every cell it struck, aimed cell first.
+    /// Cells already fired at are skipped, so `shots` may be shorter than the pattern.
+    SpecialShotResult {
+        shooter_id: Uuid,
+        weapon: SpecialWeapon,
+        x: usize,
+        y: usize,
+        coord: String,
+        shots: Vec<CellShot>,
+        #[serde(default, skip_serializing_if = "Board::is_home")]
+        board: Board,
+    },
+    WeaponsLocked {
+        player_id: Uuid, //whatevers players weapons get lcoked
+    },
+    WeaponsUnlocked {
+        player_id: Uuid,
+        reason: String,
+    },
+    /// A fast solve earned a one-use weapon; follows WeaponsUnlocked.
+    WeaponGranted {
+        player_id: Uuid,
+        weapon: SpecialWeapon,
+    },
+
+    /// Server-assigned problem when weapons overheat.
+    /// Sent once when the problem is picked; also included in every GameUpdate tick.
+    ProblemAssigned {
+        player_id: Uuid,
+        contest_id: i32,
+        problem_index: String,
+        problem_name: String,
+        rating: u32,
+        /// Link to the problem on judges other than Codeforces, whose links
+        /// clients build from `contest_id` and `problem_index`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+    },
+
+    /// The problem a `RequestBonusProblem` drew. Solving it earns a shield.
+    BonusProblemAssigned {
+        player_id: Uuid,
+        contest_id: i32,
+        problem_index: String,
+        problem_name: String,
+        rating: u32,
+        /// Link to the problem on judges other than Codeforces, whose links
+        /// clients build from `contest_id` and `problem_index`
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+    },
+    /// Codeforces accepted the bonus problem: one shield to place.
+    ShieldEarned {
+        player_id: Uuid,
+    },
+    /// Sent only to the player who placed it; the opponent finds out when a
+    /// shot is blocked.
+    ShieldPlaced {
+        x: usize,
+        y: usize,
+        coord: String,
+    },
+
+    /// Answer to `RequestHint`, sent only to the player who asked. `max_heat`
+    /// is their heat limit after paying (lower with `HintCost::LowerMaxHeat`).
+    Hint {
+        contest_id: i32,
+        problem_index: String,
+        rating: u32,
+        /// Codeforces tags; empty if Codeforces couldn't be reached
+        tags: Vec<String>,
+        max_heat: u32,
+    },
+
+    /// A player voted to move the difficulty; the opponent's matching vote
+    /// makes it happen.
+    DifficultyVoteCast {
+        player_id: Uuid,
+        direction: DifficultyVote,
+    },
+    /// Both players agreed: problems assigned from now on use this difficulty.
+    DifficultyChanged {
+        difficulty: u32,
+        difficulty_mode: DifficultyMode,
+    },
+
+    /// A player's first socket opened or their last one closed.
+    Presence {
+        player_id: Uuid,
+        connected: bool,
+    },
+    /// Sent to each joined socket every `game.ping_interval_ms`; answer with
+    /// `Pong` so the server can measure the round trip. Not an event.
+    Ping {
+        /// Server time, Unix milliseconds
+        sent_at_ms: u64,
+    },
+    /// A player's latest round-trip time. Only in lobbies with `show_latency`
+    /// on.
+    Latency {
+        player_id: Uuid,
+        rtt_ms: u32,
+    },
+    /// The judge is unreachable while a player is locked: the game clock and
+    /// veto timers stop counting down. Only in lobbies with `pause_on_outage`
+    /// on.
+    ClockPaused {
+        message: String,
+    },
+    /// The judge is back (or nobody is locked any more): the clocks run again,
+    /// every deadline `paused_secs` later than it was.
+    ClockResumed {
+        paused_secs: u64,
+    },
+    /// Anti-snipe: a shot landed in the last minute, so the running clock
+    /// (regulation, or sudden death's) got `added_secs` longer and now has
+    /// `time_remaining_secs` left.
+    ClockExtended {
+        added_secs: u64,
+        time_remaining_secs: u64,
+    },
+    /// Spectators currently on the game's referee feed.
+    SpectatorCount {
+        count: u32,
+    },
+    /// Whether a locked player looks busy with their problem: locked, with the
+    /// lock or a verification attempt in the last 5 minutes. Only in lobbies
+    /// with `activity_indicator` on.
+    Working {
+        player_id: Uuid,
+        working: bool,
+    },
+    /// An unlocked player has sent nothing for `idle_timeout_secs`: they
+    /// forfeit in `forfeit_in_secs` unless they do. Only in lobbies with the
+    /// AFK rule on.
+    IdleWarning {
+        player_id: Uuid,
+        forfeit_in_secs: u64,
+    },
+    /// The warned player is back; the `IdleWarning` is lifted.
+    IdleCleared {
+        player_id: Uuid,
+    },
+    /// A line for the in-game activity feed, written by the server so every
+    /// client and replay tells the game the same way. `player_id` is who it
+    /// happened to or who did it; None for the whole game.
+    ActivityEvent {
+        kind: ActivityKind,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        player_id: Option<Uuid>,
+        text: String,
+    },
+
+    /// Sent immediately when a SolveCP request enters the CF API queue.
+    /// Frontend shows a spinner until VerifyResult or WeaponsUnlocked arrives.
+    VerifyPending {
+        player_id: Uuid,
+    },
+
+    /// Result of a SolveCP verification attempt (sent via broadcast).
+    /// Only sent on failure — success is signalled by WeaponsUnlocked instead.
+    VerifyResult {
+        player_id: Uuid,
+        accepted: bool,
+        message: String,
+    },
+
+    GameOver {
+        winner_id: Option<Uuid>,
+        reason: String,
+        // Full stats for both players — each client reads their own by player_id
+        p1_id: Uuid,
+        p1_ships_sunk: u32,
+        p1_cells_hit: u32,
+        p1_problems_solved: u32,
+        p2_ships_sunk: u32,
+        p2_cells_hit: u32,
+        p2_problems_solved: u32,
+        // Lock problems (bonus problems aside) each player solved and was
+        // assigned, for "solved 2/3"
+        #[serde(default)]
+        p1_locks_solved: u32,
+        #[serde(default)]
+        p1_locks_assigned: u32,
+        #[serde(default)]
+        p2_locks_solved: u32,
+        #[serde(default)]
+        p2_locks_assigned: u32,
+        // Post-game board reveal: both players' full grids + ship placements.
+        // Each cell is "empty", "ship", "hit", or "miss".
+        // Ships are serialized as {x, y, size, vertical, sunk}.
+        p1_grid: Vec<Vec<String>>,
+        p1_ships: Vec<RevealedShip>,
+        p2_grid: Vec<Vec<String>>,
+        p2_ships: Vec<RevealedShip>,
+        // Co-op games: the hidden fleet both players fired at
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        coop_grid: Option<Vec<Vec<String>>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        coop_ships: Option<Vec<RevealedShip>>,
+        // Armada games: each player's forward fleet
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        p1_forward_grid: Option<Vec<Vec<String>>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        p1_forward_ships: Option<Vec<RevealedShip>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        p2_forward_grid: Option<Vec<Vec<String>>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        p2_forward_ships: Option<Vec<RevealedShip>>,
+        // Swiss tiebreaker scores (server-authoritative)
+        time_taken_secs: u64,
+        winner_score: f64,
+        loser_score: f64,
+    },
+
+    /// A Waiting lobby will close in `in_secs` unless an opponent joins or
+    /// the host sends `ExtendLobby`.
+    LobbyExpiring {
+        in_secs: u64,
+    },
+    /// The host extended the lobby: it now expires in `in_secs`.
+    LobbyExtended {
+        in_secs: u64,
+        extensions_left: u32,
+    },
+
+    /// The server is dropping this game (finished, idle or abandoned past its
+    /// retention window). Sent right before removal; the socket closes after.
+    GameExpired {
+        reason: String,
+    },
+
+    // Errors
+    /// `code` is a stable identifier (the server's `GameError::code`, e.g. "already_fired");
+    /// `message` is the human-readable text. An `invalid_placement` error
+    /// also names the misplaced ship in `placement`; throttled requests
+    /// (`rate_limited`, `verify_cooldown`, `verify_throttled`) say when to
+    /// try again in `retry_after_secs`.
+    Error {
+        code: String,
+        message: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        placement: Option<PlacementFault>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        retry_after_secs: Option<u64>,
+    },
+
+    // Reconnection State
+    YourShips {
+        ships: Vec<ShipPlacement>,
+    },
+    /// Ships placed so far with `PlaceShip`, in order: the answer to each
+    /// `PlaceShip`/`UnplaceShip`, and part of the sync on rejoining during
+    /// placement.
+    StagedShips {
+        ships: Vec<ShipPlacement>,
+    },
+    /// Your enemy-grid markers, in the order set: the answer to each
+    /// `SetMarker`, and part of the sync on rejoining.
+    Markers {
+        markers: Vec<Marker>,
+    },
+    GridSync {
+        my_grid: Vec<Vec<String>>,    // "empty", "ship", "shielded", "hit", "miss"
+        enemy_grid: Vec<Vec<String>>, // "empty", "hit", "miss" (ships hidden)
+        /// Armada games: the forward boards, the same way
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        my_forward_grid: Option<Vec<Vec<String>>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        enemy_forward_grid: Option<Vec<Vec<String>>>,
+    },
+}
+
+/// One cell struck by a special weapon, reported like a `ShotResult`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CellShot {
+    pub x: usize,
+    pub y: usize,
+    pub coord: String,
+    pub hit: bool,
+    pub sunk: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sunk_cells: Option<Vec<[usize; 2]>>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub blocked: bool,
+}
+
+/// A target cell. Serializes as `{x, y}`; also deserializes from
+/// `{"coord": "B7"}`: column letter A-J (x = 0-9) then row number 1-10 (y = 0-9),
+/// matching the board labels. Letters past J and rows past 10 parse and are
+/// then rejected as out of bounds by the game.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(try_from = "CoordRepr")]
+pub struct Coord {
+    pub x: usize,
+    pub y: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CoordRepr {
+    Xy { x: usize, y: usize },
+    Notation { coord: String },
+}
+
+impl TryFrom<CoordRepr> for Coord {
+    type Error = String;
+
+    fn try_from(repr: CoordRepr) -> Result<Self, Self::Error> {
+        match repr {
+            CoordRepr::Xy { x, y } => Ok(Coord { x, y }),
+            CoordRepr::Notation { coord } => coord.parse(),
+        }
+    }
+}
+
+impl std::str::FromStr for Coord {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let mut chars = s.chars();
+        let col = chars
+            .next()
+            .filter(char::is_ascii_alphabetic)
+            .ok_or_else(|| format!("invalid coordinate {:?}", s))?;
+        let row: usize = chars
+            .as_str()
+            .parse()
+            .ok()
+            .filter(|&r| r >= 1)
+            .ok_or_else(|| format!("invalid coordinate {:?}", s))?;
+        Ok(Coord {
+            x: (col.to_ascii_uppercase() as u8 - b'A') as usize,
+            y: row - 1,
+        })
+    }
+}
+
+impl std::fmt::Display for Coord {
+    /// Grid notation ("B7"); only meaningful for in-bounds cells.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", (b'A' + self.x as u8) as char, self.y + 1)
+    }
+}
+
+/// Defined with the board rules so the server, this crate and the WASM build
+/// all validate the same type.
+pub use battle_cp_core::{PlacementFault, PlacementProblem, ShipPlacement, SpecialWeapon};
+
+/// Messages streamed to admin observers on `/api/admin/ws`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type")]
+#[allow(clippy::large_enum_variant)] // Game events far outnumber snapshots
+pub enum AdminMessage {
+    /// Sent once on connect: every game currently in the registry.
+    Snapshot {
+        games: Vec<GameSnapshot>,
+    },
+    /// A broadcast from one game, tagged with its id. Ticks are not forwarded.
+    GameEvent {
+        game_id: Uuid,
+        event_id: u64,
+        at_ms: u64,
+        event: ServerMessage,
+    },
+    /// A player's round-trip time from their latest `Pong`, and the 95th
+    /// percentile over the recent ones.
+    Latency {
+        game_id: Uuid,
+        player_id: Uuid,
+        rtt_ms: u32,
+        p95_ms: u32,
+    },
+}
+
+/// A game broadcast as it goes out on the socket and into the game's replay
+/// log: the message's own fields plus its place in the game's event order.
+/// Direct replies (errors, the join sync) aren't events and carry no stamp.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StampedMessage {
+    /// Counts the game's broadcasts from 1; every socket sees the same IDs
+    pub event_id: u64,
+    /// Server time the event was sent, in Unix milliseconds
+    pub at_ms: u64,
+    #[serde(flatten)]
+    pub message: ServerMessage,
+}
+
+/// Why the server closed a socket. The Close frame carries `status()` as its
+/// code (4000–4999, the application range) and a JSON `CloseReason` as its
+/// reason text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseCode {
+    /// The game was removed: cleaned up after it ended, expired or cancelled.
+    /// Also ends a socket to an archived game, right after its `GameOver`.
+    GameClosed,
+    /// The server is restarting
+    ServerShutdown,
+    /// Banned while connected
+    Kicked,
+    GameNotFound,
+    /// Kept sending faster than the per-message rate limits
+    RateLimited,
+}
+
+impl CloseCode {
+    pub fn status(self) -> u16 {
+        match self {
+            Self::GameClosed => 4000,
+            Self::ServerShutdown => 4001,
+            Self::Kicked => 4003,
+            Self::GameNotFound => 4004,
+            Self::RateLimited => 4029,
+        }
+    }
+
+    pub fn from_status(status: u16) -> Option<Self> {
+        [Self::GameClosed, Self::ServerShutdown, Self::Kicked, Self::GameNotFound, Self::RateLimited]
+            .into_iter()
+            .find(|c| c.status() == status)
+    }
+
+    /// Whether reconnecting (after a backoff) can help.
+    pub fn can_reconnect(self) -> bool {
+        matches!(self, Self::ServerShutdown | Self::RateLimited)
+    }
+}
+
+/// A Close frame's reason text. Kept well under the 123 bytes a frame allows.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CloseReason {
+    pub code: CloseCode,
+    pub message: String,
+}
+
+impl From<CloseCode> for CloseReason {
+    fn from(code: CloseCode) -> Self {
+        let message = match code {
+            CloseCode::GameClosed => "This game has closed.",
+            CloseCode::ServerShutdown => "The server is restarting. Reconnect in a moment.",
+            CloseCode::Kicked => "You were removed from this game by a moderator.",
+            CloseCode::GameNotFound => "Game not found.",
+            CloseCode::RateLimited => "Too many messages. Slow down and reconnect.",
+        };
+        Self { code, message: message.to_string() }
+    }
+}
+
+/// A line of commentary on `/ws/{game_id}/referee`: derived facts a
+/// Kriegspiel referee could announce, never where ships are.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RefereeMessage {
+    /// Seconds since combat started
+    pub game_secs: u64,
+    #[serde(flatten)]
+    pub event: RefereeEvent,
+    /// The same, as a sentence for stream overlays
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "type")]
+pub enum RefereeEvent {
+    /// On connect, and whenever a ship sinks. Never sent in fog-of-war games.
+    ShipsLeft { player_id: Uuid, ships_left: u32 },
+    Locked { player_id: Uuid },
+    /// Once per whole minute a lock lasts
+    StillLocked { player_id: Uuid, minutes: u64 },
+    Unlocked { player_id: Uuid, reason: String, minutes_locked: u64 },
+    GameOver { winner_id: Option<Uuid>, reason: String },
+}
+
+/// Sanitized public view of a game for `GET /api/game/:id`.
+/// Never includes grids, ship positions, or assigned problems.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GameSnapshot {
+    pub game_id: Uuid,
+    pub status: GameStatus,
+    pub difficulty: u32,
+    pub difficulty_mode: DifficultyMode,
+    pub heat_threshold: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub heat_ramp: Vec<HeatStep>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ammo: Option<AmmoConfig>,
+    #[serde(default)]
+    pub sudden_death: SuddenDeathMode,
+    pub max_vetoes: u32,
+    #[serde(default)]
+    pub veto_penalties: Vec<u64>,
+    pub game_duration_secs: u64,
+    pub time_remaining_secs: u64,
+    pub public: bool,
+    #[serde(default)]
+    pub coop: bool,
+    #[serde(default)]
+    pub reveal_problems: bool,
+    pub scheduled_start: Option<u64>,
+    /// Rating gate on joining (see `GameConfig::min_rating`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_rating: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_rating: Option<u32>,
+    pub room: Option<String>,
+    pub host: PlayerSnapshot,
+    pub guest: Option<PlayerSnapshot>,
+}
+
+/// Public per-player info inside a `GameSnapshot`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlayerSnapshot {
+    pub cf_handle: String,
+    /// The bot playing this seat, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bot: Option<String>,
+    pub ships_placed: bool,
+}
+
+/// One problem of a virtual-contest game's set (`ProblemSet`).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct RevealedProblem {
+    pub contest_id: i32,
+    pub index: String,
+    pub name: String,
+    pub rating: u32,
+    /// Where to solve it, for judges other than Codeforces
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// Ship data sent in the post-game board reveal.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RevealedShip {
+    pub x: usize,
+    pub y: usize,
+    pub size: u8,
+    pub vertical: bool,
+    pub sunk: bool,
+}
@@ -0,0 +1,385 @@
+use uuid::Uuid;
+
+use crate::protocol::{
+    Board, DifficultyMode, DifficultyVote, GameStatus, Marker, RevealedProblem, ServerMessage, ShipPlacement, SpecialWeapon,
+};
+
+const GRID_SIZE: usize = 10;
+
+/// One player's picture of the game, built from the messages they receive.
+/// A resync (on reconnect, or when the server notices the connection fell
+/// behind) replaces the boards wholesale.
+#[derive(Debug, Clone)]
+pub struct GameView {
+    pub player_id: Uuid,
+    pub opponent_id: Option<Uuid>,
+    /// Best guess from the messages seen so far
+    pub status: GameStatus,
+    /// Own board: "empty", "ship", "shielded", "hit" or "miss"
+    pub my_grid: Vec<Vec<String>>,
+    /// Opponent's board (in co-op, the hidden fleet): "empty", "hit" or "miss"
+    pub enemy_grid: Vec<Vec<String>>,
+    /// Co-op game: our teammate's shots land on `enemy_grid` too
+    pub coop: bool,
+    /// Armada game: the forward boards, kept like `my_grid` / `enemy_grid`
+    pub armada: bool,
+    pub my_forward_grid: Vec<Vec<String>>,
+    pub enemy_forward_grid: Vec<Vec<String>>,
+    pub my_ships: Vec<ShipPlacement>,
+    pub my_forward_ships: Vec<ShipPlacement>,
+    pub ships_placed: bool,
+    /// Ships placed one at a time (`PlaceShip`) while the fleet is incomplete
+    pub staged_ships: Vec<ShipPlacement>,
+    /// Our private notes on the enemy grid (`SetMarker`)
+    pub markers: Vec<Marker>,
+    pub heat: u32,
+    pub max_heat: u32,
+    /// Rounds left, in ammo mode (instead of heat)
+    pub ammo: Option<u32>,
+    pub is_locked: bool,
+    pub vetoes_remaining: u32,
+    /// Until a veto-skipped problem unlocks our weapons
+    pub veto_time_remaining_secs: Option<u64>,
+    pub time_remaining_secs: u64,
+    /// Until the lobby / placement deadline, before combat
+    pub phase_time_remaining_secs: Option<u64>,
+    /// `(contest_id, index, name)` of the problem that unlocks our weapons
+    pub active_problem: Option<(i32, String, String)>,
+    /// Its link, when the game's judge isn't Codeforces
+    pub active_problem_url: Option<String>,
+    /// A virtual-contest game's whole problem set, once combat starts
+    pub problem_set: Vec<RevealedProblem>,
+    /// One-use weapons we hold
+    pub special_weapons: Vec<SpecialWeapon>,
+    /// `(contest_id, index, name)` of the bonus problem we asked for
+    pub bonus_problem: Option<(i32, String, String)>,
+    /// Shields earned and not yet placed
+    pub shields_available: u32,
+    /// Difficulty of problems assigned from now on (see `DifficultyMode`)
+    pub difficulty: u32,
+    pub difficulty_mode: DifficultyMode,
+    /// `(tags, rating)` bought with `RequestHint` for the active problem
+    pub hint: Option<(Vec<String>, u32)>,
+    /// Standing difficulty votes, ours and the opponent's
+    pub difficulty_vote: Option<DifficultyVote>,
+    pub opponent_difficulty_vote: Option<DifficultyVote>,
+    /// Whether the opponent has a socket open, once the server has said
+    pub opponent_connected: Option<bool>,
+    /// Spectators on the referee feed
+    pub spectators: u32,
+    /// The opponent looks busy with their problem (see `ServerMessage::Working`)
+    pub opponent_working: bool,
+    /// The opponent's latest round trip, in lobbies with `show_latency` on
+    pub opponent_rtt_ms: Option<u32>,
+    /// The game clock is stopped for a judge outage (`ClockPaused`)
+    pub clock_paused: bool,
+    /// ID of the last game broadcast received (0 before any)
+    pub last_event_id: u64,
+    /// The final `GameOver` message, once the game has ended
+    pub game_over: Option<ServerMessage>,
+    /// Last placement sent, adopted as `my_ships` once the server confirms it
+    pub(crate) proposed_ships: Vec<ShipPlacement>,
+    pub(crate) proposed_forward: Vec<ShipPlacement>,
+}
+
+impl GameView {
+    pub(crate) fn new(player_id: Uuid) -> Self {
+        Self {
+            player_id,
+            opponent_id: None,
+            status: GameStatus::Waiting,
+            my_grid: empty_grid(),
+            enemy_grid: empty_grid(),
+            coop: false,
+            armada: false,
+            my_forward_grid: empty_grid(),
+            enemy_forward_grid: empty_grid(),
+            my_ships: Vec::new(),
+            my_forward_ships: Vec::new(),
+            ships_placed: false,
+            staged_ships: Vec::new(),
+            markers: Vec::new(),
+            heat: 0,
+            max_heat: 0,
+            ammo: None,
+            is_locked: false,
+            vetoes_remaining: 0,
+            veto_time_remaining_secs: None,
+            time_remaining_secs: 0,
+            phase_time_remaining_secs: None,
+            active_problem: None,
+            active_problem_url: None,
+            problem_set: Vec::new(),
+            special_weapons: Vec::new(),
+            bonus_problem: None,
+            shields_available: 0,
+            difficulty: 0,
+            difficulty_mode: DifficultyMode::default(),
+            hint: None,
+            difficulty_vote: None,
+            opponent_difficulty_vote: None,
+            opponent_connected: None,
+            spectators: 0,
+            opponent_working: false,
+            opponent_rtt_ms: None,
+            clock_paused: false,
+            last_event_id: 0,
+            game_over: None,
+            proposed_ships: Vec::new(),
+            proposed_forward: Vec::new(),
+        }
+    }
+
+    pub(crate) fn apply(&mut self, msg: &ServerMessage) {
+        let me = self.player_id;
+        match msg {
+            ServerMessage::GameJoined { max_heat, max_vetoes, difficulty, difficulty_mode, coop, armada, .. } => {
+                self.coop = *coop;
+                self.armada = *armada;
+                self.max_heat = *max_heat;
+                self.vetoes_remaining = *max_vetoes;
+                self.difficulty = *difficulty;
+                self.difficulty_mode = difficulty_mode.clone();
+            }
+            ServerMessage::PlayerJoined { player_id } if *player_id != me => {
+                self.opponent_id = Some(*player_id);
+                if self.status == GameStatus::Waiting {
+                    self.status = GameStatus::PlacingShips;
+                }
+            }
+            ServerMessage::ShipsConfirmed { player_id } if *player_id == me => {
+                self.ships_placed = true;
+                if !self.proposed_ships.is_empty() {
+                    let ships = std::mem::take(&mut self.proposed_ships);
+                    self.set_ships(ships);
+                }
+                if !self.proposed_forward.is_empty() {
+                    let ships = std::mem::take(&mut self.proposed_forward);
+                    mark_ships(&mut self.my_forward_grid, &ships);
+                    self.my_forward_ships = ships;
+                }
+            }
+            ServerMessage::YourShips { ships } => {
+                self.ships_placed = true;
+                self.staged_ships.clear();
+                self.set_ships(ships.clone());
+            }
+            ServerMessage::StagedShips { ships } => self.staged_ships = ships.clone(),
+            ServerMessage::Markers { markers } => self.markers = markers.clone(),
+            ServerMessage::Countdown { .. } => self.status = GameStatus::Countdown,
+            ServerMessage::GameStart => self.status = GameStatus::Playing,
+            ServerMessage::ProblemSet { problems } => self.problem_set = problems.clone(),
+            ServerMessage::GameUpdate {
+                status,
+                heat,
+                is_locked,
+                time_remaining_secs,
+                vetoes_remaining,
+                veto_time_remaining_secs,
+                phase_time_remaining_secs,
+                active_problem_contest_id,
+                active_problem_index,
+                active_problem_name,
+                active_problem_url,
+                special_weapons,
+                shields_available,
+                max_heat,
+                ammo,
+                ..
+            } => {
+                if status.starts_with("SUDDEN DEATH") {
+                    self.status = GameStatus::SuddenDeath;
+                } else if let Ok(status) = serde_json::from_value(serde_json::Value::String(status.clone())) {
+                    self.status = status;
+                }
+                self.heat = *heat;
+                self.ammo = *ammo;
+                self.is_locked = *is_locked;
+                self.time_remaining_secs = *time_remaining_secs;
+                self.vetoes_remaining = *vetoes_remaining;
+                self.veto_time_remaining_secs = *veto_time_remaining_secs;
+                self.phase_time_remaining_secs = *phase_time_remaining_secs;
+                self.active_problem = match (active_problem_contest_id, active_problem_index) {
+                    (Some(contest_id), Some(index)) => {
+                        Some((*contest_id, index.clone(), active_problem_name.clone().unwrap_or_default()))
+                    }
+                    _ => None,
+                };
+                self.active_problem_url = active_problem_url.clone();
+                self.special_weapons = special_weapons.clone();
+                self.shields_available = *shields_available;
+                if let Some(max_heat) = max_heat {
+                    self.max_heat = *max_heat;
+                }
+            }
+            ServerMessage::ShotResult { x, y, hit, blocked, shooter_id, sunk_cells, board, .. } => {
+                self.mark_shot(*shooter_id, *board, *x, *y, (*hit, *blocked), sunk_cells.as_deref());
+            }
+            ServerMessage::SpecialShotResult { shooter_id, weapon, shots, board, .. } => {
+                for shot in shots {
+                    let hit = (shot.hit, shot.blocked);
+                    self.mark_shot(*shooter_id, *board, shot.x, shot.y, hit, shot.sunk_cells.as_deref());
+                }
+                if *shooter_id == me {
+                    if let Some(i) = self.special_weapons.iter().position(|w| w == weapon) {
+                        self.special_weapons.remove(i);
+                    }
+                }
+            }
+            ServerMessage::WeaponGranted { player_id, weapon } if *player_id == me => {
+                self.special_weapons.push(*weapon);
+            }
+            ServerMessage::BonusProblemAssigned {
+                player_id,
+                contest_id,
+                problem_index,
+                problem_name,
+                ..
+            } if *player_id == me => {
+                self.bonus_problem = Some((*contest_id, problem_index.clone(), problem_name.clone()));
+            }
+            ServerMessage::ShieldEarned { player_id } if *player_id == me => {
+                self.bonus_problem = None;
+                self.shields_available += 1;
+            }
+            ServerMessage::ShieldPlaced { x, y, .. } => {
+                self.shields_available = self.shields_available.saturating_sub(1);
+                if let Some(cell) = self.my_grid.get_mut(*y).and_then(|row| row.get_mut(*x)) {
+                    *cell = "shielded".to_string();
+                }
+            }
+            ServerMessage::DifficultyVoteCast { player_id, direction } => {
+                if *player_id == me {
+                    self.difficulty_vote = Some(*direction);
+                } else {
+                    self.opponent_difficulty_vote = Some(*direction);
+                }
+            }
+            ServerMessage::DifficultyChanged { difficulty, difficulty_mode } => {
+                self.difficulty = *difficulty;
+                self.difficulty_mode = difficulty_mode.clone();
+                self.difficulty_vote = None;
+                self.opponent_difficulty_vote = None;
+            }
+            ServerMessage::Presence { player_id, connected } if *player_id != me => {
+                self.opponent_connected = Some(*connected);
+            }
+            ServerMessage::SpectatorCount { count } => self.spectators = *count,
+            ServerMessage::Working { player_id, working } if *player_id != me => {
+                self.opponent_working = *working;
+            }
+            ServerMessage::Latency { player_id, rtt_ms } if *player_id != me => {
+                self.opponent_rtt_ms = Some(*rtt_ms);
+            }
+            ServerMessage::ClockPaused { .. } => self.clock_paused = true,
+            ServerMessage::ClockResumed { .. } => self.clock_paused = false,
+            // In sudden death the extended clock isn't the regulation one shown here
+            ServerMessage::ClockExtended { time_remaining_secs, .. } if self.status == GameStatus::Playing => {
+                self.time_remaining_secs = *time_remaining_secs;
+            }
+            ServerMessage::WeaponsLocked { player_id } if *player_id == me => self.is_locked = true,
+            ServerMessage::WeaponsUnlocked { player_id, .. } if *player_id == me => {
+                self.is_locked = false;
+                self.active_problem = None;
+                self.active_problem_url = None;
+                self.hint = None;
+            }
+            ServerMessage::Hint { rating, tags, max_heat, .. } => {
+                self.hint = Some((tags.clone(), *rating));
+                self.max_heat = *max_heat;
+            }
+            ServerMessage::ProblemAssigned {
+                player_id,
+                contest_id,
+                problem_index,
+                problem_name,
+                url,
+                ..
+            } if *player_id == me => {
+                self.active_problem = Some((*contest_id, problem_index.clone(), problem_name.clone()));
+                self.active_problem_url = url.clone();
+                self.hint = None;
+            }
+            ServerMessage::GridSync { my_grid, enemy_grid, my_forward_grid, enemy_forward_grid } => {
+                self.my_grid = my_grid.clone();
+                self.enemy_grid = enemy_grid.clone();
+                if let (Some(mine), Some(enemy)) = (my_forward_grid, enemy_forward_grid) {
+                    self.my_forward_grid = mine.clone();
+                    self.enemy_forward_grid = enemy.clone();
+                }
+            }
+            ServerMessage::GameOver { .. } => {
+                self.status = GameStatus::Finished;
+                self.game_over = Some(msg.clone());
+            }
+            _ => {}
+        }
+    }
+
+    /// Mark a resolved shot on the board it landed on. A blocked shot only
+    /// takes the shield off our own cell.
+    fn mark_shot(
+        &mut self,
+        shooter_id: Uuid,
+        board: Board,
+        x: usize,
+        y: usize,
+        (hit, blocked): (bool, bool),
+        sunk_cells: Option<&[[usize; 2]]>,
+    ) {
+        let grid = match (shooter_id == self.player_id || self.coop, board) {
+            (true, Board::Home) => &mut self.enemy_grid,
+            (true, Board::Forward) => &mut self.enemy_forward_grid,
+            (false, Board::Home) => &mut self.my_grid,
+            (false, Board::Forward) => &mut self.my_forward_grid,
+        };
+        let mark = match (hit, blocked) {
+            (_, true) if shooter_id == self.player_id || self.coop => return,
+            (_, true) => "ship",
+            (true, _) => "hit",
+            _ => "miss",
+        };
+        if let Some(cell) = grid.get_mut(y).and_then(|row| row.get_mut(x)) {
+            *cell = mark.to_string();
+        }
+        for &[sx, sy] in sunk_cells.into_iter().flatten() {
+            if let Some(cell) = grid.get_mut(sy).and_then(|row| row.get_mut(sx)) {
+                *cell = "hit".to_string();
+            }
+        }
+    }
+
+    /// Record our fleet and mark its cells on our board.
+    fn set_ships(&mut self, ships: Vec<ShipPlacement>) {
+        mark_ships(&mut self.my_grid, &ships);
+        self.my_ships = ships;
+    }
+
+    /// Opponent cells we haven't fired at yet.
+    pub fn untargeted(&self) -> impl Iterator<Item = crate::protocol::Coord> + '_ {
+        self.enemy_grid.iter().enumerate().flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, cell)| cell.as_str() == "empty")
+                .map(move |(x, _)| crate::protocol::Coord { x, y })
+        })
+    }
+}
+
+/// Mark `ships`' cells on `grid` where nothing has happened yet.
+fn mark_ships(grid: &mut [Vec<String>], ships: &[ShipPlacement]) {
+    for ship in ships {
+        for i in 0..ship.size as usize {
+            let (x, y) = if ship.vertical { (ship.x, ship.y + i) } else { (ship.x + i, ship.y) };
+            if let Some(cell) = grid.get_mut(y).and_then(|row| row.get_mut(x)) {
+                if cell == "empty" {
+                    *cell = "ship".to_string();
+                }
+            }
+        }
+    }
+}
+
+fn empty_grid() -> Vec<Vec<String>> {
+    vec![vec!["empty".to_string(); GRID_SIZE]; GRID_SIZE]
+}
@@ -0,0 +1,44 @@
+use thiserror::Error;
+
+use crate::fleet::PlacementFault;
+
+/// A move the board rules don't allow. The server wraps these in its own
+/// `GameError`; `code` matches the `ServerMessage::Error` code it sends.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RuleError {
+    #[error("Invalid fleet: expected 5 ships, got {0}")]
+    FleetSize(usize),
+    #[error("Invalid fleet composition. Ships must be sizes 5, 4, 3, 3, 2")]
+    FleetComposition,
+    #[error("Ship starting position out of bounds")]
+    ShipStartOutOfBounds,
+    #[error("Ship extends beyond grid boundary")]
+    ShipOutOfBounds,
+    #[error("Ship overlaps with another ship")]
+    ShipOverlap,
+    /// A fleet was placed (`place_fleet`, `stage_ship`): which ship broke which rule
+    #[error("{0}")]
+    Placement(PlacementFault),
+    #[error("Out of bounds")]
+    OutOfBounds,
+    #[error("Already fired here")]
+    AlreadyFired,
+    #[error("Shields go on one of your own ship cells that hasn't been hit or shielded")]
+    InvalidShield,
+}
+
+impl RuleError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::FleetSize(_) => "fleet_size",
+            Self::FleetComposition => "fleet_composition",
+            Self::ShipStartOutOfBounds => "ship_start_out_of_bounds",
+            Self::ShipOutOfBounds => "ship_out_of_bounds",
+            Self::ShipOverlap => "ship_overlap",
+            Self::Placement(_) => "invalid_placement",
+            Self::OutOfBounds => "out_of_bounds",
+            Self::AlreadyFired => "already_fired",
+            Self::InvalidShield => "invalid_shield",
+        }
+    }
+}
@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::RuleError;
+use crate::grid::{Grid, GRID_SIZE};
+
+/// Standard Battleship fleet, largest first: Carrier (5), Battleship (4),
+/// Cruiser (3), Submarine (3), Destroyer (2).
+pub const FLEET: [u8; 5] = [5, 4, 3, 3, 2];
+
+/// One ship as a client places it (the `PlaceShips` wire format).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ShipPlacement {
+    pub x: usize,
+    pub y: usize,
+    pub size: u8,
+    pub vertical: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Ship {
+    pub size: u8,
+    pub hits: u8,
+    pub sunk: bool,
+    pub x: usize,
+    pub y: usize,
+    pub vertical: bool,
+}
+
+impl Ship {
+    /// Cells covered by this ship, as [x, y].
+    pub fn cells(&self) -> impl Iterator<Item = [usize; 2]> + '_ {
+        (0..self.size as usize).map(move |i| {
+            if self.vertical {
+                [self.x, self.y + i]
+            } else {
+                [self.x + i, self.y]
+            }
+        })
+    }
+
+    /// Bitboard of the cells this ship covers (must be in bounds).
+    pub fn mask(&self) -> u128 {
+        self.cells().fold(0, |m, [x, y]| m | Grid::cell_bit(x, y))
+    }
+}
+
+impl From<&ShipPlacement> for Ship {
+    fn from(p: &ShipPlacement) -> Self {
+        Self {
+            size: p.size,
+            hits: 0,
+            sunk: false,
+            x: p.x,
+            y: p.y,
+            vertical: p.vertical,
+        }
+    }
+}
+
+/// The fleet must be exactly the sizes in [`FLEET`], in any order.
+pub fn check_fleet(placements: &[ShipPlacement]) -> Result<(), RuleError> {
+    if placements.len() != FLEET.len() {
+        return Err(RuleError::FleetSize(placements.len()));
+    }
+    let mut sizes: Vec<u8> = placements.iter().map(|s| s.size).collect();
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    if sizes != FLEET {
+        return Err(RuleError::FleetComposition);
+    }
+    Ok(())
+}
+
+/// Put `ship` on the board with its bow at (x, y). Ships may touch but not
+/// overlap, and must fit entirely on the grid.
+pub fn place_ship(
+    grid: &mut Grid,
+    ships: &mut Vec<Ship>,
+    mut ship: Ship,
+    x: usize,
+    y: usize,
+    vertical: bool,
+) -> Result<(), RuleError> {
+    if x >= GRID_SIZE || y >= GRID_SIZE {
+        return Err(RuleError::ShipStartOutOfBounds);
+    }
+
+    // Check ship doesn't extend beyond grid
+    let end_x = if vertical { x } else { x + ship.size as usize };
+    let end_y = if vertical { y + ship.size as usize } else { y };
+    if end_x > GRID_SIZE || end_y > GRID_SIZE {
+        return Err(RuleError::ShipOutOfBounds);
+    }
+
+    ship.x = x;
+    ship.y = y;
+    ship.vertical = vertical;
+
+    let mask = ship.mask();
+    if grid.ships & mask != 0 {
+        return Err(RuleError::ShipOverlap);
+    }
+    grid.ships |= mask;
+    ships.push(ship);
+    Ok(())
+}
+
+/// Why a ship can't go where it was put. Ships may touch, so there is no
+/// adjacency rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlacementProblem {
+    /// Starts off the grid, or runs past its edge
+    OutOfBounds,
+    /// Covers a cell an earlier ship already does
+    Overlap,
+}
+
+/// The first ship of a `PlaceShips` request (or a staged `PlaceShip`) that
+/// breaks a placement rule.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlacementFault {
+    /// Position of the ship in the fleet, from 0
+    pub index: usize,
+    /// The ship as it was sent
+    pub ship: ShipPlacement,
+    pub problem: PlacementProblem,
+    /// For an overlap: index of the earlier ship it runs into
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub overlaps: Option<usize>,
+}
+
+impl std::fmt::Display for PlacementFault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = &self.ship;
+        let direction = if s.vertical { "down" } else { "across" };
+        write!(f, "Ship {} (size {} at {},{} {}) ", self.index + 1, s.size, s.x, s.y, direction)?;
+        match (self.problem, self.overlaps) {
+            (PlacementProblem::OutOfBounds, _) => write!(f, "goes off the grid"),
+            (PlacementProblem::Overlap, Some(other)) => write!(f, "overlaps ship {}", other + 1),
+            (PlacementProblem::Overlap, None) => write!(f, "overlaps another ship"),
+        }
+    }
+}
+
+/// Validate a whole `PlaceShips` request and build the resulting board. A
+/// misplaced ship is reported as `RuleError::Placement`, naming it.
+pub fn place_fleet(placements: &[ShipPlacement]) -> Result<(Grid, Vec<Ship>), RuleError> {
+    check_fleet(placements)?;
+    lay_out(placements)
+}
+
+/// Check one more ship for a fleet placed a ship at a time (`PlaceShip`):
+/// its size must still be missing from [`FLEET`], and it must fit next to
+/// the ships already `staged`. A misplaced ship is reported like
+/// [`place_fleet`] does, as the next ship of the fleet.
+pub fn stage_ship(staged: &[ShipPlacement], ship: &ShipPlacement) -> Result<(), RuleError> {
+    let wanted = FLEET.iter().filter(|&&size| size == ship.size).count();
+    if staged.iter().filter(|s| s.size == ship.size).count() >= wanted {
+        return Err(RuleError::FleetComposition);
+    }
+    let mut fleet = staged.to_vec();
+    fleet.push(ship.clone());
+    lay_out(&fleet).map(|_| ())
+}
+
+fn lay_out(placements: &[ShipPlacement]) -> Result<(Grid, Vec<Ship>), RuleError> {
+    let mut grid = Grid::new();
+    let mut ships: Vec<Ship> = Vec::with_capacity(placements.len());
+    for (index, p) in placements.iter().enumerate() {
+        if let Err(e) = place_ship(&mut grid, &mut ships, p.into(), p.x, p.y, p.vertical) {
+            let (problem, overlaps) = match e {
+                RuleError::ShipOverlap => {
+                    let mask = Ship::from(p).mask();
+                    (PlacementProblem::Overlap, ships.iter().position(|s| s.mask() & mask != 0))
+                }
+                _ => (PlacementProblem::OutOfBounds, None),
+            };
+            return Err(RuleError::Placement(PlacementFault { index, ship: p.clone(), problem, overlaps }));
+        }
+    }
+    Ok((grid, ships))
+}
@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::RuleError;
+
+/// Cells per side.
+pub const GRID_SIZE: usize = 10;
+
+/// 10x10 board as bitboards: bit `y * 10 + x` of each mask is one cell.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Grid {
+    /// Cells occupied by a ship (hit or not)
+    pub ships: u128,
+    /// Shots that struck a ship
+    pub hits: u128,
+    /// Shots into open water
+    pub misses: u128,
+    /// Unhit ship cells guarded by a shield, which absorbs the next shot there
+    #[serde(default)]
+    pub shields: u128,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub enum CellState {
+    //stats a cell can have for front end to figure out
+    Empty,
+    Ship,
+    /// Ship cell with an intact shield
+    Shielded,
+    Hit,
+    Miss,
+}
+
+/// Result of a shot on a cell that hadn't been fired at yet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shot {
+    Hit,
+    Miss,
+    /// A shield absorbed it: the shield is gone, the cell can be fired at again
+    Blocked,
+}
+
+impl Grid {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Single-bit mask for cell (x, y). Callers bounds-check first.
+    pub fn cell_bit(x: usize, y: usize) -> u128 {
+        1u128 << (y * GRID_SIZE + x)
+    }
+
+    pub fn cell(&self, x: usize, y: usize) -> CellState {
+        let bit = Self::cell_bit(x, y);
+        if self.hits & bit != 0 {
+            CellState::Hit
+        } else if self.misses & bit != 0 {
+            CellState::Miss
+        } else if self.shields & bit != 0 {
+            CellState::Shielded
+        } else if self.ships & bit != 0 {
+            CellState::Ship
+        } else {
+            CellState::Empty
+        }
+    }
+
+    pub fn receive_shot(&mut self, x: usize, y: usize) -> Result<Shot, RuleError> {
+        if x >= GRID_SIZE || y >= GRID_SIZE {
+            return Err(RuleError::OutOfBounds);
+        }
+
+        let bit = Self::cell_bit(x, y);
+        if (self.hits | self.misses) & bit != 0 {
+            Err(RuleError::AlreadyFired)
+        } else if self.shields & bit != 0 {
+            self.shields &= !bit;
+            Ok(Shot::Blocked)
+        } else if self.ships & bit != 0 {
+            self.hits |= bit;
+            Ok(Shot::Hit)
+        } else {
+            self.misses |= bit;
+            Ok(Shot::Miss)
+        }
+    }
+
+    /// Shield an unhit, unshielded ship cell.
+    pub fn place_shield(&mut self, x: usize, y: usize) -> Result<(), RuleError> {
+        if x >= GRID_SIZE || y >= GRID_SIZE {
+            return Err(RuleError::OutOfBounds);
+        }
+        match self.cell(x, y) {
+            CellState::Ship => {
+                self.shields |= Self::cell_bit(x, y);
+                Ok(())
+            }
+            _ => Err(RuleError::InvalidShield),
+        }
+    }
+
+    /// Ship cells not yet hit.
+    pub fn ship_cells_remaining(&self) -> u32 {
+        (self.ships & !self.hits).count_ones()
+    }
+
+    /// Serialize to a 2D Vec of cell-state strings for the protocol.
+    /// With `reveal_ships = false`, unhit ships and their shields show as
+    /// "empty" (enemy view).
+    pub fn to_strings(&self, reveal_ships: bool) -> Vec<Vec<String>> {
+        (0..GRID_SIZE)
+            .map(|y| {
+                (0..GRID_SIZE)
+                    .map(|x| match self.cell(x, y) {
+                        CellState::Empty => "empty".to_string(),
+                        CellState::Ship if reveal_ships => "ship".to_string(),
+                        CellState::Shielded if reveal_ships => "shielded".to_string(),
+                        CellState::Ship | CellState::Shielded => "empty".to_string(), // Hide ships!
+                        CellState::Hit => "hit".to_string(),
+                        CellState::Miss => "miss".to_string(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,24 @@
+//! The rules every Battle-CP board follows, with no runtime attached: the
+//! 10x10 bitboard, fleet and placement validation, shot resolution (special
+//! weapons included), and the end-of-clock tie-break. The server applies them to live games; with the
+//! `wasm` feature the same code is built for the browser (see `wasm.rs`), so
+//! the frontend checks placements and predicts shots exactly as the server
+//! will instead of keeping a TypeScript copy in step.
+
+mod error;
+mod fleet;
+mod grid;
+mod rules;
+#[cfg(feature = "wasm")]
+mod wasm;
+
+pub use error::RuleError;
+pub use fleet::{
+    check_fleet, place_fleet, place_ship, stage_ship, PlacementFault, PlacementProblem, Ship, ShipPlacement,
+    FLEET,
+};
+pub use grid::{CellState, Grid, Shot, GRID_SIZE};
+pub use rules::{
+    compare_accuracy, determine_winner, resolve_shot, resolve_special, ShotResolution, ShotTally, SpecialWeapon,
+    Standing, TiebreakResult, SUDDEN_DEATH_SHOTS,
+};
@@ -0,0 +1,274 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::RuleError;
+use crate::fleet::Ship;
+use crate::grid::{CellState, Grid, Shot, GRID_SIZE};
+
+/// A valid shot: hit or miss, plus the cells of the ship it sank, if any.
+pub type ShotResolution = (Shot, Option<Vec<[usize; 2]>>);
+
+/// Fire at (x, y) on a board. Out of bounds and repeat shots are errors; a
+/// shielded cell blocks the shot and loses its shield.
+pub fn resolve_shot(
+    grid: &mut Grid,
+    ships: &mut [Ship],
+    x: usize,
+    y: usize,
+) -> Result<ShotResolution, RuleError> {
+    let result = grid.receive_shot(x, y)?;
+    if result != Shot::Hit {
+        return Ok((result, None));
+    }
+
+    let bit = Grid::cell_bit(x, y);
+    let mut sunk_cells = None;
+    if let Some(ship) = ships.iter_mut().find(|s| s.mask() & bit != 0) {
+        ship.hits += 1;
+        // Sunk once every one of its cells is in the hit mask
+        if ship.mask() & !grid.hits == 0 && !ship.sunk {
+            ship.sunk = true;
+            sunk_cells = Some(ship.cells().collect());
+        }
+    }
+    Ok((result, sunk_cells))
+}
+
+/// One-use weapons earned during combat.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpecialWeapon {
+    /// Plus-shaped: the aimed cell and its four neighbours
+    Cross,
+}
+
+impl SpecialWeapon {
+    /// The cells covered when aimed at (x, y), aimed cell first. Cells off
+    /// the board are dropped, so a cross on an edge covers four, in a corner three.
+    pub fn pattern(self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let offsets: &[(isize, isize)] = match self {
+            Self::Cross => &[(0, 0), (0, -1), (-1, 0), (1, 0), (0, 1)],
+        };
+        offsets
+            .iter()
+            .filter_map(|&(dx, dy)| Some((x.checked_add_signed(dx)?, y.checked_add_signed(dy)?)))
+            .filter(|&(cx, cy)| cx < GRID_SIZE && cy < GRID_SIZE)
+            .collect()
+    }
+}
+
+/// Fire `weapon` aimed at (x, y): each cell of its pattern not fired at yet
+/// resolves like a normal shot, in pattern order. The aimed cell must be on
+/// the board, and at least one cell must be new.
+pub fn resolve_special(
+    grid: &mut Grid,
+    ships: &mut [Ship],
+    weapon: SpecialWeapon,
+    x: usize,
+    y: usize,
+) -> Result<Vec<([usize; 2], ShotResolution)>, RuleError> {
+    if x >= GRID_SIZE || y >= GRID_SIZE {
+        return Err(RuleError::OutOfBounds);
+    }
+    let fresh: Vec<_> = weapon
+        .pattern(x, y)
+        .into_iter()
+        .filter(|&(cx, cy)| {
+            matches!(grid.cell(cx, cy), CellState::Empty | CellState::Ship | CellState::Shielded)
+        })
+        .collect();
+    if fresh.is_empty() {
+        return Err(RuleError::AlreadyFired);
+    }
+    fresh
+        .into_iter()
+        .map(|(cx, cy)| Ok(([cx, cy], resolve_shot(grid, ships, cx, cy)?)))
+        .collect()
+}
+
+/// What decides a game that runs out of time.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Standing {
+    /// Own ships not yet sunk
+    pub ships_remaining: usize,
+    /// Hits scored on the opponent
+    pub cells_hit: u32,
+}
+
+impl Standing {
+    pub fn new(ships: &[Ship], cells_hit: u32) -> Self {
+        Self {
+            ships_remaining: ships.iter().filter(|s| !s.sunk).count(),
+            cells_hit,
+        }
+    }
+}
+
+// Tie-break result
+#[derive(Clone, Debug, PartialEq)]
+pub enum TiebreakResult {
+    Player1Wins,
+    Player2Wins,
+    SuddenDeath,
+}
+
+/// Most surviving ships wins; then most cells hit; otherwise sudden death.
+pub fn determine_winner(p1: Standing, p2: Standing) -> TiebreakResult {
+    use std::cmp::Ordering;
+
+    match p1
+        .ships_remaining
+        .cmp(&p2.ships_remaining)
+        .then(p1.cells_hit.cmp(&p2.cells_hit))
+    {
+        Ordering::Greater => TiebreakResult::Player1Wins,
+        Ordering::Less => TiebreakResult::Player2Wins,
+        Ordering::Equal => TiebreakResult::SuddenDeath,
+    }
+}
+
+/// Shots each player gets in accuracy sudden death.
+pub const SUDDEN_DEATH_SHOTS: u32 = 10;
+
+/// Shots taken and landed, for accuracy sudden death. A special weapon's
+/// volley counts as one shot, a hit if any of its cells hit.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ShotTally {
+    pub shots: u32,
+    pub hits: u32,
+}
+
+/// Player 1's hit rate against player 2's (`Greater` means player 1's is
+/// better). No shots is a rate of zero.
+pub fn compare_accuracy(p1: ShotTally, p2: ShotTally) -> std::cmp::Ordering {
+    let p1_rate = p1.hits as u64 * p2.shots.max(1) as u64;
+    let p2_rate = p2.hits as u64 * p1.shots.max(1) as u64;
+    p1_rate.cmp(&p2_rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fleet::{place_fleet, stage_ship, PlacementProblem, ShipPlacement, FLEET};
+
+    fn fleet() -> Vec<ShipPlacement> {
+        FLEET
+            .iter()
+            .enumerate()
+            .map(|(i, &size)| ShipPlacement { x: 0, y: i * 2, size, vertical: false })
+            .collect()
+    }
+
+    #[test]
+    fn test_stage_ship() {
+        let fleet = fleet();
+        let staged = &fleet[..2];
+        assert_eq!(stage_ship(staged, &fleet[2]), Ok(()));
+        let dup = ShipPlacement { x: 5, y: 5, size: 5, vertical: false };
+        assert_eq!(stage_ship(staged, &dup), Err(RuleError::FleetComposition));
+        let on_top = ShipPlacement { x: 4, y: 0, size: 3, vertical: true };
+        match stage_ship(staged, &on_top).unwrap_err() {
+            RuleError::Placement(f) => {
+                assert_eq!((f.index, f.problem, f.overlaps), (2, PlacementProblem::Overlap, Some(0)))
+            }
+            e => panic!("expected a placement fault, got {e:?}"),
+        }
+    }
+
+    #[test]
+    fn test_place_fleet_and_sink() {
+        let (mut grid, mut ships) = place_fleet(&fleet()).unwrap();
+        assert_eq!(grid.ship_cells_remaining(), 17);
+
+        let mut bad = fleet();
+        bad.pop();
+        assert_eq!(place_fleet(&bad).unwrap_err(), RuleError::FleetSize(4));
+        bad.push(ShipPlacement { x: 0, y: 9, size: 5, vertical: false });
+        assert_eq!(place_fleet(&bad).unwrap_err(), RuleError::FleetComposition);
+        let mut bad = fleet();
+        bad[4] = ShipPlacement { x: 9, y: 8, size: 2, vertical: false };
+        match place_fleet(&bad).unwrap_err() {
+            RuleError::Placement(f) => {
+                assert_eq!((f.index, f.problem, f.overlaps), (4, PlacementProblem::OutOfBounds, None))
+            }
+            e => panic!("expected a placement fault, got {e:?}"),
+        }
+        bad[4] = ShipPlacement { x: 1, y: 0, size: 2, vertical: true };
+        match place_fleet(&bad).unwrap_err() {
+            RuleError::Placement(f) => {
+                assert_eq!((f.index, f.problem, f.overlaps), (4, PlacementProblem::Overlap, Some(0)))
+            }
+            e => panic!("expected a placement fault, got {e:?}"),
+        }
+
+        // Destroyer on row 8
+        assert_eq!(resolve_shot(&mut grid, &mut ships, 0, 8).unwrap(), (Shot::Hit, None));
+        let (shot, sunk) = resolve_shot(&mut grid, &mut ships, 1, 8).unwrap();
+        assert_eq!((shot, sunk), (Shot::Hit, Some(vec![[0, 8], [1, 8]])));
+        assert_eq!(resolve_shot(&mut grid, &mut ships, 1, 8), Err(RuleError::AlreadyFired));
+        assert_eq!(resolve_shot(&mut grid, &mut ships, 9, 9).unwrap(), (Shot::Miss, None));
+        assert_eq!(resolve_shot(&mut grid, &mut ships, 0, 10), Err(RuleError::OutOfBounds));
+        assert_eq!(Standing::new(&ships, 0).ships_remaining, 4);
+    }
+
+    #[test]
+    fn test_shield_absorbs_one_shot() {
+        let (mut grid, mut ships) = place_fleet(&fleet()).unwrap();
+        assert_eq!(grid.place_shield(9, 9), Err(RuleError::InvalidShield), "open water");
+        grid.place_shield(0, 8).unwrap();
+        assert_eq!(grid.place_shield(0, 8), Err(RuleError::InvalidShield), "already shielded");
+        assert_eq!(grid.cell(0, 8), CellState::Shielded);
+        assert_eq!(grid.to_strings(true)[8][0], "shielded");
+        assert_eq!(grid.to_strings(false)[8][0], "empty");
+
+        assert_eq!(resolve_shot(&mut grid, &mut ships, 0, 8).unwrap(), (Shot::Blocked, None));
+        assert_eq!(grid.cell(0, 8), CellState::Ship);
+        assert_eq!(grid.ship_cells_remaining(), 17);
+        resolve_shot(&mut grid, &mut ships, 1, 8).unwrap();
+        let (shot, sunk) = resolve_shot(&mut grid, &mut ships, 0, 8).unwrap();
+        assert_eq!((shot, sunk), (Shot::Hit, Some(vec![[0, 8], [1, 8]])));
+        assert_eq!(grid.place_shield(0, 8), Err(RuleError::InvalidShield), "already hit");
+    }
+
+    #[test]
+    fn test_compare_accuracy() {
+        use std::cmp::Ordering;
+        let tally = |hits, shots| ShotTally { shots, hits };
+        assert_eq!(compare_accuracy(tally(3, 10), tally(2, 10)), Ordering::Greater);
+        assert_eq!(compare_accuracy(tally(2, 4), tally(4, 8)), Ordering::Equal);
+        assert_eq!(compare_accuracy(tally(1, 10), tally(1, 2)), Ordering::Less);
+        assert_eq!(compare_accuracy(tally(0, 0), tally(0, 5)), Ordering::Equal);
+        assert_eq!(compare_accuracy(tally(0, 0), tally(1, 5)), Ordering::Less);
+    }
+
+    #[test]
+    fn test_cross_shot() {
+        assert_eq!(SpecialWeapon::Cross.pattern(4, 4), [(4, 4), (4, 3), (3, 4), (5, 4), (4, 5)]);
+        assert_eq!(SpecialWeapon::Cross.pattern(0, 0), [(0, 0), (1, 0), (0, 1)]);
+        assert_eq!(SpecialWeapon::Cross.pattern(9, 5).len(), 4);
+
+        // Centred on the destroyer's stern: sinks it, misses around it, skips (0, 8)
+        let (mut grid, mut ships) = place_fleet(&fleet()).unwrap();
+        resolve_shot(&mut grid, &mut ships, 0, 8).unwrap();
+        let shots = resolve_special(&mut grid, &mut ships, SpecialWeapon::Cross, 1, 8).unwrap();
+        let cells: Vec<[usize; 2]> = shots.iter().map(|(cell, _)| *cell).collect();
+        assert_eq!(cells, [[1, 8], [1, 7], [2, 8], [1, 9]]);
+        assert_eq!(shots[0].1, (Shot::Hit, Some(vec![[0, 8], [1, 8]])));
+        assert!(shots[1..].iter().all(|(_, (shot, _))| *shot == Shot::Miss));
+
+        // Nothing new left under it
+        resolve_shot(&mut grid, &mut ships, 0, 9).unwrap();
+        resolve_shot(&mut grid, &mut ships, 0, 7).unwrap();
+        let err = resolve_special(&mut grid, &mut ships, SpecialWeapon::Cross, 0, 8);
+        assert_eq!(err, Err(RuleError::AlreadyFired));
+        let err = resolve_special(&mut grid, &mut ships, SpecialWeapon::Cross, 10, 0);
+        assert_eq!(err, Err(RuleError::OutOfBounds));
+    }
+
+    #[test]
+    fn test_determine_winner() {
+        let standing = |ships_remaining, cells_hit| Standing { ships_remaining, cells_hit };
+        assert_eq!(determine_winner(standing(3, 0), standing(2, 9)), TiebreakResult::Player1Wins);
+        assert_eq!(determine_winner(standing(2, 5), standing(2, 10)), TiebreakResult::Player2Wins);
+        assert_eq!(determine_winner(standing(2, 10), standing(2, 10)), TiebreakResult::SuddenDeath);
+    }
+}
@@ -0,0 +1,111 @@
+//! Browser bindings. Built with
+//! `wasm-pack build backend/core --target web -- --features wasm`; the
+//! frontend loads the package through `frontend/lib/core.ts`. Ship lists
+//! cross the boundary as the same JSON the `PlaceShips` message carries.
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    determine_winner, place_fleet, place_ship, resolve_shot, Grid, RuleError, Ship,
+    ShipPlacement, Shot, Standing, TiebreakResult,
+};
+
+/// A rejected placement or shot, thrown to JS (or returned by the checks).
+#[wasm_bindgen(getter_with_clone)]
+pub struct Violation {
+    /// Same code the server sends in `ServerMessage::Error`
+    pub code: String,
+    pub message: String,
+}
+
+impl From<RuleError> for Violation {
+    fn from(e: RuleError) -> Self {
+        Self {
+            code: e.code().to_string(),
+            message: e.to_string(),
+        }
+    }
+}
+
+fn parse(ships_json: &str) -> Result<Vec<ShipPlacement>, JsError> {
+    serde_json::from_str(ships_json).map_err(|e| JsError::new(&e.to_string()))
+}
+
+/// Whether these ships fit on the board without overlapping; the fleet may
+/// be incomplete (for drag-and-drop while placing).
+#[wasm_bindgen(js_name = checkPlacement)]
+pub fn check_placement(ships_json: &str) -> Result<Option<Violation>, JsError> {
+    let (mut grid, mut ships) = (Grid::new(), Vec::new());
+    for p in parse(ships_json)? {
+        if let Err(e) = place_ship(&mut grid, &mut ships, (&p).into(), p.x, p.y, p.vertical) {
+            return Ok(Some(e.into()));
+        }
+    }
+    Ok(None)
+}
+
+/// Everything the server checks on `PlaceShips`: placement plus fleet composition.
+#[wasm_bindgen(js_name = checkFleet)]
+pub fn check_full_fleet(ships_json: &str) -> Result<Option<Violation>, JsError> {
+    Ok(place_fleet(&parse(ships_json)?).err().map(Into::into))
+}
+
+/// End-of-clock result: "player1", "player2" or "sudden_death".
+#[wasm_bindgen(js_name = determineWinner)]
+pub fn determine_winner_js(p1_ships: usize, p1_hits: u32, p2_ships: usize, p2_hits: u32) -> String {
+    let standing = |ships_remaining, cells_hit| Standing { ships_remaining, cells_hit };
+    match determine_winner(standing(p1_ships, p1_hits), standing(p2_ships, p2_hits)) {
+        TiebreakResult::Player1Wins => "player1",
+        TiebreakResult::Player2Wins => "player2",
+        TiebreakResult::SuddenDeath => "sudden_death",
+    }
+    .to_string()
+}
+
+#[wasm_bindgen(getter_with_clone)]
+pub struct ShotPrediction {
+    pub hit: bool,
+    /// Cells of the ship this shot sinks, flattened as `y * 10 + x`; empty if none
+    pub sunk_cells: Vec<u32>,
+}
+
+/// A fleet on its own board, for replaying or predicting shots locally.
+#[wasm_bindgen]
+pub struct Board {
+    grid: Grid,
+    ships: Vec<Ship>,
+}
+
+#[wasm_bindgen]
+impl Board {
+    /// Throws a `Violation` if the server would reject this fleet.
+    #[wasm_bindgen(constructor)]
+    pub fn new(ships_json: &str) -> Result<Board, JsValue> {
+        let placements = parse(ships_json)?;
+        let (grid, ships) = place_fleet(&placements).map_err(Violation::from)?;
+        Ok(Self { grid, ships })
+    }
+
+    /// Fire at (x, y). Throws a `Violation` for out-of-bounds or repeat shots.
+    pub fn fire(&mut self, x: usize, y: usize) -> Result<ShotPrediction, Violation> {
+        let (shot, sunk) = resolve_shot(&mut self.grid, &mut self.ships, x, y)?;
+        Ok(ShotPrediction {
+            hit: shot == Shot::Hit,
+            sunk_cells: sunk
+                .unwrap_or_default()
+                .into_iter()
+                .map(|[x, y]| (y * crate::GRID_SIZE + x) as u32)
+                .collect(),
+        })
+    }
+
+    #[wasm_bindgen(js_name = shipsRemaining)]
+    pub fn ships_remaining(&self) -> usize {
+        Standing::new(&self.ships, 0).ships_remaining
+    }
+
+    /// The board as the server serializes it: rows of "empty" / "ship" / "hit" / "miss".
+    pub fn cells(&self, reveal_ships: bool) -> String {
+        serde_json::to_string(&self.grid.to_strings(reveal_ships)).unwrap_or_default()
+    }
+}
@@ -0,0 +1,73 @@
+//! The in-game activity feed: `ActivityEvent`s for the moments worth a line
+//! in a scrolling feed (first blood, ships sunk, locks, vetoes, sudden death).
+//!
+//! Shots and locks already go out as broadcasts, so their feed lines follow
+//! from those as `GameTx::send` logs them (`Feed::follow`); they say no more
+//! than the broadcast did, fog of war included. Moments without a broadcast
+//! of their own send their line directly. Either way the line is in the
+//! replay log, so every client and every replay reads the same feed.
+
+use crate::protocol::{ActivityKind, ServerMessage, SuddenDeathMode};
+use uuid::Uuid;
+
+fn event(kind: ActivityKind, player_id: Option<Uuid>, text: String) -> ServerMessage {
+    ServerMessage::ActivityEvent { kind, player_id, text }
+}
+
+/// What the feed remembers of the broadcasts so far.
+#[derive(Debug, Default)]
+pub struct Feed {
+    hit_yet: bool,
+}
+
+impl Feed {
+    /// The feed lines `msg` calls for, in order.
+    pub fn follow(&mut self, msg: &ServerMessage) -> Vec<ServerMessage> {
+        // (shooter, coord, hit, sunk cells if revealed)
+        let shots: Vec<(Uuid, &str, bool, Option<usize>)> = match msg {
+            ServerMessage::ShotResult { shooter_id, coord, hit, sunk, sunk_cells, .. } => {
+                vec![(*shooter_id, coord, *hit, sunk.then(|| sunk_cells.as_ref().map_or(0, Vec::len)))]
+            }
+            ServerMessage::SpecialShotResult { shooter_id, shots, .. } => shots
+                .iter()
+                .map(|s| (*shooter_id, s.coord.as_str(), s.hit, s.sunk.then(|| s.sunk_cells.as_ref().map_or(0, Vec::len))))
+                .collect(),
+            ServerMessage::WeaponsLocked { player_id } => {
+                return vec![event(ActivityKind::Locked, Some(*player_id), "Weapons locked".to_string())];
+            }
+            _ => return vec![],
+        };
+        let mut lines = vec![];
+        for (shooter, coord, hit, sunk) in shots {
+            if hit && !std::mem::replace(&mut self.hit_yet, true) {
+                lines.push(event(ActivityKind::FirstBlood, Some(shooter), format!("First blood at {}", coord)));
+            }
+            match sunk {
+                Some(0) => lines.push(event(ActivityKind::ShipSunk, Some(shooter), format!("Sank a ship at {}", coord))),
+                Some(len) => lines.push(event(
+                    ActivityKind::ShipSunk,
+                    Some(shooter),
+                    format!("Sank a {}-cell ship at {}", len, coord),
+                )),
+                None => {}
+            }
+        }
+        lines
+    }
+}
+
+/// `player_id` vetoed their lock problem, sitting out `minutes`.
+pub fn veto_used(player_id: Uuid, minutes: u64) -> ServerMessage {
+    event(ActivityKind::VetoUsed, Some(player_id), format!("Vetoed the problem: {} minute wait", minutes))
+}
+
+/// The clock ran out on a tie.
+pub fn sudden_death(mode: SuddenDeathMode) -> ServerMessage {
+    let text = match mode {
+        SuddenDeathMode::FirstHit => "Sudden death: first hit wins".to_string(),
+        SuddenDeathMode::Accuracy => {
+            format!("Sudden death: best hit rate over {} shots wins", battle_cp_core::SUDDEN_DEATH_SHOTS)
+        }
+    };
+    event(ActivityKind::SuddenDeath, None, text)
+}
@@ -0,0 +1,239 @@
+//! Per-game actor tasks.
+//!
+//! Every `Game` is owned by its own tokio task. Everything else talks to it
+//! through a cloneable `GameHandle`, which queues closures over an mpsc
+//! channel; the actor runs them one at a time against `&mut Game`. A busy
+//! game therefore only serializes its own commands — the registry lock is
+//! held just long enough to clone a handle. Between commands the actor also
+//! sleeps until the game's next deadline (see `timers.rs`) and publishes each
+//! player's serialized `GameUpdate` to their `PlayerView` whenever it changed
+//! (checked once a second), plus a keepalive every few seconds.
+
+use crate::game::TickState;
+use crate::state::{Game, GameStatus};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot, watch};
+use tokio::time::MissedTickBehavior;
+use tracing::Instrument;
+use uuid::Uuid;
+
+/// Commands queued per game before senders start waiting.
+const COMMAND_BUFFER: usize = 256;
+
+/// Ticks are re-sent at least this often even when nothing changed, so clients
+/// resync their local countdowns and recover from a missed update.
+const TICK_KEEPALIVE: Duration = Duration::from_secs(5);
+
+/// A unit of work run by the actor with exclusive access to its game.
+/// Returns an optional reply, delivered only after the new status is published
+/// so callers never observe a stale `summary()` right after `call` returns.
+pub type GameCommand = Box<dyn FnOnce(&mut Game) -> Option<Reply> + Send>;
+pub type Reply = Box<dyn FnOnce() + Send>;
+
+/// Lifecycle fields the actor republishes after every command, so registry-wide
+/// sweeps (cleanup, health, capacity) can read them without queueing on the game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameSummary {
+    pub status: GameStatus,
+    pub created_at: Instant,
+    pub placement_started_at: Option<Instant>,
+    pub finished_at: Option<Instant>,
+    pub abandoned_at: Option<Instant>,
+    /// Scheduled matches: retention windows before combat count from here
+    pub scheduled_start_at: Option<Instant>,
+    /// Waiting lobbies: times the host extended the lobby
+    pub lobby_extensions: u32,
+    /// The clock is stopped for a judge outage (see `Game::sync_outage`)
+    pub clock_paused: bool,
+    pub host_id: Uuid,
+    /// Once someone has taken the guest seat
+    pub guest_id: Option<Uuid>,
+}
+
+impl GameSummary {
+    fn of(game: &Game) -> Self {
+        Self {
+            status: game.status.clone(),
+            created_at: game.created_at,
+            placement_started_at: game.placement_started_at,
+            finished_at: game.finished_at,
+            abandoned_at: game.abandoned_at,
+            scheduled_start_at: game.scheduled_start_at,
+            lobby_extensions: game.lobby_extensions,
+            clock_paused: game.clock_paused_at.is_some(),
+            host_id: game.player1.id,
+            guest_id: game.player2.as_ref().map(|p| p.id),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct GameHandle {
+    pub id: Uuid,
+    /// The game's broadcast channel (subscribe / send without a round trip).
+    pub tx: crate::events::GameTx,
+    /// IP that created the lobby (fixed at creation).
+    pub creator_ip: Option<std::net::IpAddr>,
+    /// Tournament tag (fixed at creation; see `metrics.rs`).
+    pub tournament: Option<String>,
+    /// Latest summary, published by the actor after every command.
+    summary: watch::Receiver<GameSummary>,
+    cmd_tx: mpsc::Sender<GameCommand>,
+}
+
+impl GameHandle {
+    /// Spawn the actor task for `game`. It exits once every handle is dropped
+    /// (i.e. the game was removed from the registry), dropping the game and
+    /// closing its broadcast channel.
+    pub fn spawn(game: Game) -> Self {
+        let (cmd_tx, mut cmd_rx) = mpsc::channel::<GameCommand>(COMMAND_BUFFER);
+        let (summary_tx, summary) = watch::channel(GameSummary::of(&game));
+        let handle = Self {
+            id: game.id,
+            tx: game.tx.clone(),
+            creator_ip: game.creator_ip,
+            tournament: game.tournament.clone(),
+            summary,
+            cmd_tx,
+        };
+
+        let span = tracing::info_span!("game", game_id = %game.id);
+        let game_id = game.id;
+        tokio::spawn(crate::reporting::in_task(
+            "game_actor",
+            Some(game_id),
+            async move {
+                let mut game = game;
+                // How often to check whether per-player `GameUpdate` frames are due
+                let mut ticks = tokio::time::interval(crate::config::global().game.tick_interval());
+                ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+                // What the last published views reflected, and when they went out
+                let mut last_tick: Option<(TickState, Instant)> = None;
+                loop {
+                    // Sleep until the game's next timeout/veto expiry, or the next command
+                    let deadline = crate::timers::next_deadline(&game);
+                    let timer = async {
+                        match deadline {
+                            Some(at) => tokio::time::sleep_until(at.into()).await,
+                            None => std::future::pending().await,
+                        }
+                    };
+                    let reply = tokio::select! {
+                        cmd = cmd_rx.recv() => match cmd {
+                            Some(cmd) => cmd(&mut game),
+                            None => break,
+                        },
+                        _ = timer => {
+                            crate::timers::fire_due(&mut game);
+                            None
+                        }
+                        _ = ticks.tick() => {
+                            let state = TickState::of(&game);
+                            let due = match &last_tick {
+                                Some((prev, at)) => *prev != state || at.elapsed() >= TICK_KEEPALIVE,
+                                None => true,
+                            };
+                            if due {
+                                crate::game::publish_views(&game);
+                                last_tick = Some((state, Instant::now()));
+                            }
+                            None
+                        }
+                    };
+                    game.sync_working();
+                    summary_tx.send_if_modified(|s| {
+                        let now = GameSummary::of(&game);
+                        if *s != now {
+                            *s = now;
+                            true
+                        } else {
+                            false
+                        }
+                    });
+                    if let Some(reply) = reply {
+                        reply();
+                    }
+                }
+                tracing::debug!(target: "game", "Game actor {:?} stopped", game.id);
+            }
+            .instrument(span),
+        ));
+
+        handle
+    }
+
+    /// Run `f` on the game and return its result.
+    /// `None` if the actor has stopped (game removed).
+    pub async fn call<R, F>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut Game) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        call(&self.cmd_tx, f).await
+    }
+
+    /// A handle that doesn't keep the actor running, for tasks that follow
+    /// the game until it is removed.
+    pub fn downgrade(&self) -> WeakGameHandle {
+        WeakGameHandle { id: self.id, cmd_tx: self.cmd_tx.downgrade() }
+    }
+
+    /// Queue `f` without waiting for it to run. Returns false if the actor has stopped.
+    pub async fn cast<F>(&self, f: F) -> bool
+    where
+        F: FnOnce(&mut Game) + Send + 'static,
+    {
+        let cmd: GameCommand = Box::new(move |game| {
+            f(game);
+            None
+        });
+        self.cmd_tx.send(cmd).await.is_ok()
+    }
+
+    /// Summary as of the last processed command.
+    pub fn summary(&self) -> GameSummary {
+        self.summary.borrow().clone()
+    }
+
+    /// Status as of the last processed command.
+    pub fn status(&self) -> GameStatus {
+        self.summary.borrow().status.clone()
+    }
+}
+
+/// A `GameHandle` that doesn't count towards keeping the game alive: once the
+/// game is removed from the registry its actor stops, and `call` returns `None`.
+/// Followers hold one of these next to their broadcast receiver, so the
+/// receiver sees `Closed` when the game goes.
+#[derive(Clone)]
+pub struct WeakGameHandle {
+    pub id: Uuid,
+    cmd_tx: mpsc::WeakSender<GameCommand>,
+}
+
+impl WeakGameHandle {
+    /// `GameHandle::call`, if the actor is still running.
+    pub async fn call<R, F>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut Game) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        call(&self.cmd_tx.upgrade()?, f).await
+    }
+}
+
+async fn call<R, F>(cmd_tx: &mpsc::Sender<GameCommand>, f: F) -> Option<R>
+where
+    F: FnOnce(&mut Game) -> R + Send + 'static,
+    R: Send + 'static,
+{
+    let (reply_tx, reply_rx) = oneshot::channel();
+    let cmd: GameCommand = Box::new(move |game| {
+        let result = f(game);
+        Some(Box::new(move || {
+            let _ = reply_tx.send(result);
+        }) as Reply)
+    });
+    cmd_tx.send(cmd).await.ok()?;
+    reply_rx.await.ok()
+}
@@ -0,0 +1,678 @@
+//! Admin-only endpoints for tournament organizers.
+//!
+//! Handles:
+//! - Live observation of every game over a single WebSocket (`read`)
+//! - Ending any game (`manage`)
+//! - A game's anti-cheat audit log (`read`)
+//! - Unlocking a player, setting their heat or cancelling their veto (`manage`)
+//! - A game's replay log of stamped broadcasts (`read`)
+//! - Creating a batch of games from handle pairings (`manage`)
+//! - Issuing bot tokens (`manage`)
+//! - Prometheus gauges for tournament games (`read`; see `metrics.rs`)
+//! - Viewing (`read`) and editing (`manage`) the ban list
+//! - Viewing (`read`) and changing (`manage`) the log filter
+//!
+//! Gated by API keys, each with a scope: `read` keys may observe, `manage`
+//! keys may also change things. Keys come from `ADMIN_API_KEYS`
+//! (`key:scope,key:scope`), from the file named by `ADMIN_KEYS_FILE` (one
+//! `key:scope` per line, `#` comments), and from the legacy `ADMIN_TOKEN`
+//! (a `manage` key), or the same settings under `[admin]` in the config file. With no keys configured, all admin endpoints are disabled.
+//! Keys are sent as `Authorization: Bearer <key>` or `?token=`.
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        Path, Query, State, WebSocketUpgrade,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use uuid::Uuid;
+
+use crate::moderation::Cidr;
+use crate::protocol::AdminMessage;
+use crate::state::AppState;
+
+#[derive(Deserialize)]
+pub struct AdminQuery {
+    pub token: Option<String>,
+}
+
+/// A ban to add (`POST` body) or lift (`DELETE` query): a CF handle, an IP
+/// range (`203.0.113.0/24`, or a single address), or both.
+#[derive(Deserialize)]
+pub struct BanRequest {
+    pub handle: Option<String>,
+    pub cidr: Option<String>,
+    /// Admin key, for `DELETE` without an `Authorization` header
+    pub token: Option<String>,
+}
+
+/// What an admin key may do. `Manage` implies `Read`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Scope {
+    Read,
+    Manage,
+}
+
+impl std::str::FromStr for Scope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "read" | "read-only" | "readonly" => Ok(Self::Read),
+            "manage" => Ok(Self::Manage),
+            other => Err(format!("unknown admin scope {:?}", other)),
+        }
+    }
+}
+
+/// Configured admin keys. Only SHA-256 digests are kept, and lookups compare
+/// digests in constant time against every key, so neither timing nor an early
+/// exit reveals how much of a guess was right.
+#[derive(Default)]
+pub struct AdminKeys {
+    keys: Vec<([u8; 32], Scope)>,
+}
+
+impl AdminKeys {
+    pub fn new<'a>(keys: impl IntoIterator<Item = (&'a str, Scope)>) -> Self {
+        Self {
+            keys: keys
+                .into_iter()
+                .filter(|(key, _)| !key.is_empty())
+                .map(|(key, scope)| (digest(key), scope))
+                .collect(),
+        }
+    }
+
+    /// Keys from the `[admin]` config.
+    pub fn from_config(config: &crate::config::AdminConfig) -> Self {
+        let mut entries: Vec<(String, Scope)> = Vec::new();
+        let mut add = |spec: &str, source: &str| {
+            let spec = spec.trim();
+            if spec.is_empty() || spec.starts_with('#') {
+                return;
+            }
+            match spec.rsplit_once(':') {
+                Some((key, scope)) => match scope.parse() {
+                    Ok(scope) => entries.push((key.trim().to_string(), scope)),
+                    Err(e) => tracing::warn!("Ignoring admin key from {}: {}", source, e),
+                },
+                None => tracing::warn!("Ignoring admin key from {}: expected key:scope", source),
+            }
+        };
+        config.api_keys.iter().for_each(|spec| add(spec, "ADMIN_API_KEYS"));
+        if let Some(path) = &config.keys_file {
+            let source = path.display().to_string();
+            match std::fs::read_to_string(path) {
+                Ok(contents) => contents.lines().for_each(|spec| add(spec, &source)),
+                Err(e) => tracing::error!("Cannot read ADMIN_KEYS_FILE {}: {}", source, e),
+            }
+        }
+        if let Some(token) = &config.token {
+            entries.push((token.clone(), Scope::Manage));
+        }
+        Self::new(entries.iter().map(|(key, scope)| (key.as_str(), *scope)))
+    }
+
+    /// Scope granted to `key`, if it is a configured key.
+    pub fn scope_of(&self, key: &str) -> Option<Scope> {
+        let presented = digest(key);
+        self.keys.iter().fold(None, |best, (stored, scope)| {
+            if bool::from(stored.ct_eq(&presented)) {
+                best.max(Some(*scope))
+            } else {
+                best
+            }
+        })
+    }
+
+    /// 401 for a missing or unknown key, 403 for a key without `needed`.
+    pub fn authorize(
+        &self,
+        headers: &HeaderMap,
+        query_token: Option<String>,
+        needed: Scope,
+    ) -> Result<(), (StatusCode, &'static str)> {
+        let scope = crate::auth::request_token(headers, query_token)
+            .and_then(|key| self.scope_of(&key));
+        match scope {
+            Some(scope) if scope >= needed => Ok(()),
+            Some(_) => Err((StatusCode::FORBIDDEN, "Admin key lacks the required scope")),
+            None => Err((StatusCode::UNAUTHORIZED, "Invalid admin token")),
+        }
+    }
+}
+
+fn digest(key: &str) -> [u8; 32] {
+    Sha256::digest(key.as_bytes()).into()
+}
+
+/// WebSocket upgrade for the admin observation feed.
+pub async fn admin_ws_handler(
+    ws: WebSocketUpgrade,
+    Query(query): Query<AdminQuery>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(denied) = state.admin_keys.authorize(&headers, query.token, Scope::Read) {
+        return denied.into_response();
+    }
+    ws.max_message_size(4096) // Observers never need to send anything large
+        .on_upgrade(move |socket| handle_admin_socket(socket, state))
+        .into_response()
+}
+
+/// Stream a snapshot of all games, then every game event as it happens.
+async fn handle_admin_socket(socket: WebSocket, state: AppState) {
+    let (mut sender, mut receiver) = socket.split();
+
+    // Subscribe BEFORE taking the snapshot so no event falls in the gap
+    let mut rx = state.admin_tx.subscribe();
+    let mut games = Vec::new();
+    for handle in state.all_games() {
+        if let Some(snapshot) = handle.call(|g| g.snapshot()).await {
+            games.push(snapshot);
+        }
+    }
+    let snapshot = AdminMessage::Snapshot { games };
+    if let Ok(text) = serde_json::to_string(&snapshot) {
+        if sender.send(Message::Text(text.into())).await.is_err() {
+            return;
+        }
+    }
+    tracing::info!(target: "admin", "Observer connected ({} total)", state.admin_tx.receiver_count());
+
+    loop {
+        tokio::select! {
+            msg_opt = receiver.next() => {
+                match msg_opt {
+                    // Observers are read-only; ignore anything they send
+                    Some(Ok(_)) => {}
+                    Some(Err(_)) | None => break,
+                }
+            }
+            event_res = rx.recv() => {
+                match event_res {
+                    Ok(event) => {
+                        if let Ok(text) = serde_json::to_string(&event) {
+                            if sender.send(Message::Text(text.into())).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!(target: "admin", "Observer lagged by {} events", n);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }
+    }
+    tracing::info!(target: "admin", "Observer disconnected");
+}
+
+/// End any unfinished game (no winner). Both players get a GameOver with
+/// reason `AdminTerminated`; the game is then cleaned up like any finished one.
+pub async fn terminate_game(
+    State(state): State<AppState>,
+    Path(game_id): Path<Uuid>,
+    Query(query): Query<AdminQuery>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(denied) = state.admin_keys.authorize(&headers, query.token, Scope::Manage) {
+        return denied.into_response();
+    }
+    let ended = state
+        .with_game(game_id, |game| {
+            if game.status == crate::state::GameStatus::Finished {
+                return false;
+            }
+            game.finish(None, "AdminTerminated");
+            true
+        })
+        .await;
+    match ended {
+        None => (StatusCode::NOT_FOUND, Json(json!({ "error": "Game not found" }))).into_response(),
+        Some(false) => (
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "Game has already ended" })),
+        )
+            .into_response(),
+        Some(true) => {
+            tracing::info!(target: "admin", "Game {:?} terminated", game_id);
+            (StatusCode::OK, Json(json!({ "terminated": game_id }))).into_response()
+        }
+    }
+}
+
+/// Anomalies flagged in one game (see `anticheat.rs`).
+pub async fn game_audit_log(
+    State(state): State<AppState>,
+    Path(game_id): Path<Uuid>,
+    Query(query): Query<AdminQuery>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(denied) = state.admin_keys.authorize(&headers, query.token, Scope::Read) {
+        return denied.into_response();
+    }
+    match state
+        .with_game(game_id, |game| {
+            let latency: Vec<serde_json::Value> = std::iter::once(&game.player1)
+                .chain(game.player2.as_ref())
+                .map(|p| json!({ "player_id": p.id, "rtt_ms": p.latency.last(), "p95_ms": p.latency.p95() }))
+                .collect();
+            (game.audit_log.entries().to_vec(), latency)
+        })
+        .await
+    {
+        Some((entries, latency)) => {
+            Json(json!({ "game_id": game_id, "entries": entries, "latency": latency })).into_response()
+        }
+        None => (StatusCode::NOT_FOUND, Json(json!({ "error": "Game not found" }))).into_response(),
+    }
+}
+
+/// A fix to one player's lock state, for when a Codeforces glitch left them
+/// wrongly locked. Body of `POST /api/admin/game/:id/player/:player_id`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PlayerAdjustment {
+    /// Unlock their weapons, closing the lock problem (and refunding a running veto)
+    Unlock,
+    /// Set their heat; it must stay under the current threshold
+    SetHeat { heat: u32 },
+    /// End their veto wait now, refund the veto and unlock their weapons
+    CancelVeto,
+}
+
+/// Apply `adjustment` to `player_id`, recording it in the audit log.
+/// Returns the audit entry's detail.
+fn apply_adjustment(
+    game: &mut crate::state::Game,
+    player_id: Uuid,
+    adjustment: PlayerAdjustment,
+) -> Result<String, (StatusCode, &'static str)> {
+    if game.status == crate::state::GameStatus::Finished {
+        return Err((StatusCode::CONFLICT, "Game has already ended"));
+    }
+    let game_id = game.id;
+    let threshold = game.heat_threshold();
+    let ammo = game.config.ammo;
+    let Some(player) = game.player_mut(player_id) else {
+        return Err((StatusCode::NOT_FOUND, "Player not in this game"));
+    };
+    let detail = match adjustment {
+        PlayerAdjustment::Unlock | PlayerAdjustment::CancelVeto if !player.is_locked => {
+            return Err((StatusCode::CONFLICT, "Player's weapons are not locked"));
+        }
+        PlayerAdjustment::CancelVeto if player.veto_started_at.is_none() => {
+            return Err((StatusCode::CONFLICT, "Player is not waiting out a veto"));
+        }
+        PlayerAdjustment::SetHeat { heat } if heat >= threshold => {
+            return Err((StatusCode::BAD_REQUEST, "Heat must stay under the threshold"));
+        }
+        PlayerAdjustment::SetHeat { heat } => {
+            let detail = format!("Heat set from {} to {}", player.heat, heat);
+            player.heat = heat;
+            player.heat_relief_at = None;
+            detail
+        }
+        PlayerAdjustment::Unlock | PlayerAdjustment::CancelVeto => {
+            let detail = if player.veto_started_at.is_some() {
+                player.vetoes_used = player.vetoes_used.saturating_sub(1);
+                "Veto cancelled and refunded; weapons unlocked".to_string()
+            } else {
+                "Weapons unlocked".to_string()
+            };
+            player.unlock_weapons();
+            player.veto_extension_secs = 0;
+            if let Some(ammo) = ammo {
+                player.reload(ammo.per_solve);
+            }
+            let _ = game.tx.send(crate::state::GameEvent::Message(
+                crate::protocol::ServerMessage::WeaponsUnlocked {
+                    player_id,
+                    reason: "admin".to_string(),
+                },
+            ));
+            game.lock_closed(player_id, crate::state::LockOutcome::Overridden);
+            detail
+        }
+    };
+    if let Some(player) = std::iter::once(&game.player1).chain(game.player2.as_ref()).find(|p| p.id == player_id) {
+        game.audit_log.record_admin(game_id, player, detail.clone());
+    }
+    // A co-op team shares one lock
+    game.share_weapons(player_id);
+    Ok(detail)
+}
+
+/// Unlock a player, set their heat or cancel their veto in a live game.
+pub async fn adjust_player(
+    State(state): State<AppState>,
+    Path((game_id, player_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<AdminQuery>,
+    headers: HeaderMap,
+    Json(adjustment): Json<PlayerAdjustment>,
+) -> Response {
+    if let Err(denied) = state.admin_keys.authorize(&headers, query.token, Scope::Manage) {
+        return denied.into_response();
+    }
+    match state
+        .with_game(game_id, move |game| apply_adjustment(game, player_id, adjustment))
+        .await
+    {
+        None => (StatusCode::NOT_FOUND, Json(json!({ "error": "Game not found" }))).into_response(),
+        Some(Err((status, error))) => (status, Json(json!({ "error": error }))).into_response(),
+        Some(Ok(detail)) => {
+            tracing::info!(target: "admin", "Game {:?}, player {:?}: {}", game_id, player_id, detail);
+            Json(json!({ "game_id": game_id, "player_id": player_id, "detail": detail })).into_response()
+        }
+    }
+}
+
+/// Every broadcast a game has sent so far, stamped (see `events.rs`), with its
+/// RNG seed and lock history. Paged and filtered like the public log.
+pub async fn game_event_log(
+    State(state): State<AppState>,
+    Path(game_id): Path<Uuid>,
+    Query(query): Query<AdminQuery>,
+    Query(page): Query<crate::pagination::PageQuery>,
+    Query(filter): Query<crate::events::EventFilter>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(denied) = state.admin_keys.authorize(&headers, query.token, Scope::Read) {
+        return denied.into_response();
+    }
+    match state.game(game_id) {
+        Some(handle) => {
+            let events = match crate::events::page(handle.tx.events(), &filter, &page) {
+                Ok(events) => events,
+                Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response(),
+            };
+            let (seed, lock_history) = handle.call(|g| (g.seed, g.lock_history.clone())).await.unzip();
+            Json(json!({
+                "game_id": game_id,
+                "seed": seed,
+                "lock_history": lock_history,
+                "events": events.items,
+                "next_cursor": events.next_cursor,
+            }))
+            .into_response()
+        }
+        None => (StatusCode::NOT_FOUND, Json(json!({ "error": "Game not found" }))).into_response(),
+    }
+}
+
+/// Most games one bulk request may create.
+const MAX_BULK_GAMES: usize = 64;
+
+/// `POST /api/admin/games` body: one game per pairing, all with `config`.
+#[derive(Deserialize)]
+pub struct BulkCreateRequest {
+    pub pairs: Vec<Pairing>,
+    #[serde(default)]
+    pub config: crate::handlers::GameSettings,
+    /// Tag the games with a tournament, exporting them on `/api/admin/metrics`
+    pub tournament: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct Pairing {
+    pub host: String,
+    pub guest: String,
+}
+
+/// Create a game per pairing, e.g. a bracket round. Both seats are assigned up
+/// front: each player gets a join link carrying their player ID (and the host
+/// their session token), and the guest seat only accepts the guest's ID
+/// joining under the listed handle (in any case).
+pub async fn bulk_create_games(
+    State(state): State<AppState>,
+    Query(query): Query<AdminQuery>,
+    headers: HeaderMap,
+    Json(request): Json<BulkCreateRequest>,
+) -> Response {
+    if let Err(denied) = state.admin_keys.authorize(&headers, query.token, Scope::Manage) {
+        return denied.into_response();
+    }
+    let bad_request = |error: String| (StatusCode::BAD_REQUEST, Json(json!({ "error": error }))).into_response();
+
+    if request.pairs.is_empty() || request.pairs.len() > MAX_BULK_GAMES {
+        return bad_request(format!("pairs must list 1 to {} pairings", MAX_BULK_GAMES));
+    }
+    let valid_handle = crate::handlers::is_valid_cf_handle;
+    for (i, pair) in request.pairs.iter().enumerate() {
+        let (host, guest) = (pair.host.trim(), pair.guest.trim());
+        if !valid_handle(host) || !valid_handle(guest) {
+            return bad_request(format!("pairs[{}]: invalid Codeforces handle", i));
+        }
+        if host.eq_ignore_ascii_case(guest) {
+            return bad_request(format!("pairs[{}]: a player cannot play themselves", i));
+        }
+    }
+    if let Err(error) = request.config.build(&state.judges) {
+        return bad_request(error.to_string());
+    }
+    if request.tournament.as_deref().is_some_and(|t| !crate::metrics::is_valid_tournament(t)) {
+        return bad_request(format!(
+            "tournament must be 1 to {} letters, digits, '-', '_' or '.'",
+            crate::metrics::MAX_TOURNAMENT_LEN
+        ));
+    }
+    if state.active_games() + request.pairs.len() > state.max_active_games {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({ "error": "Not enough capacity for this many games" })),
+        )
+            .into_response();
+    }
+
+    let mut games = Vec::with_capacity(request.pairs.len());
+    for pair in &request.pairs {
+        let (host, guest) = (pair.host.trim(), pair.guest.trim());
+        let Ok((config, scheduled_start_at)) = request.config.build(&state.judges) else {
+            continue; // validated above
+        };
+        let (host_id, guest_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut game = crate::state::Game::new(host_id, host.to_string(), config);
+        game.reserved_guest = Some((guest_id, guest.to_string()));
+        game.scheduled_start_at = scheduled_start_at;
+        game.tournament = request.tournament.clone();
+        let game_id = game.id;
+        state.insert_game(game);
+        let token = state.sessions.issue(host_id, game_id, crate::auth::Role::Host);
+        games.push(json!({
+            "game_id": game_id,
+            "host": {
+                "cf_handle": host,
+                "player_id": host_id,
+                "join_url": crate::handlers::seat_link(&state, game_id, host_id, host, Some(&token)),
+            },
+            "guest": {
+                "cf_handle": guest,
+                "player_id": guest_id,
+                "join_url": crate::handlers::seat_link(&state, game_id, guest_id, guest, None),
+            },
+        }));
+    }
+    tracing::info!(target: "admin", "Created {} games in bulk", games.len());
+    (StatusCode::CREATED, Json(json!({ "games": games }))).into_response()
+}
+
+/// List banned handles and IP ranges.
+pub async fn list_bans(
+    State(state): State<AppState>,
+    Query(query): Query<AdminQuery>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(denied) = state.admin_keys.authorize(&headers, query.token, Scope::Read) {
+        return denied.into_response();
+    }
+    Json(state.bans.snapshot()).into_response()
+}
+
+/// Ban a handle and/or IP range. Takes effect for new games and joins at once,
+/// and closes the sockets of players it covers (`kicked`).
+pub async fn add_ban(
+    State(state): State<AppState>,
+    Query(query): Query<AdminQuery>,
+    headers: HeaderMap,
+    Json(ban): Json<BanRequest>,
+) -> Response {
+    if let Err(denied) = state.admin_keys.authorize(&headers, query.token, Scope::Manage) {
+        return denied.into_response();
+    }
+    update_bans(&state, ban, true)
+}
+
+/// Lift a handle and/or IP range ban.
+pub async fn remove_ban(
+    State(state): State<AppState>,
+    Query(ban): Query<BanRequest>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(denied) = state.admin_keys.authorize(&headers, ban.token.clone(), Scope::Manage) {
+        return denied.into_response();
+    }
+    update_bans(&state, ban, false)
+}
+
+fn update_bans(state: &AppState, ban: BanRequest, add: bool) -> Response {
+    let handle = ban.handle.filter(|h| !h.trim().is_empty());
+    let cidr = match ban.cidr.as_deref().map(str::parse::<Cidr>).transpose() {
+        Ok(cidr) => cidr,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response(),
+    };
+    if handle.is_none() && cidr.is_none() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Provide a handle and/or a cidr" })),
+        )
+            .into_response();
+    }
+    let verb = if add { "banned" } else { "unbanned" };
+    if let Some(handle) = handle {
+        if add {
+            state.bans.ban_handle(&handle);
+        } else {
+            state.bans.unban_handle(&handle);
+        }
+        tracing::info!(target: "admin", "Handle {:?} {}", handle, verb);
+    }
+    if let Some(cidr) = cidr {
+        if add {
+            state.bans.ban_cidr(cidr);
+        } else {
+            state.bans.unban_cidr(cidr);
+        }
+        tracing::info!(target: "admin", "IP range {} {}", cidr, verb);
+    }
+    if add {
+        state.close_sockets(crate::ws::CloseTarget::Banned, crate::protocol::CloseCode::Kicked);
+    }
+    Json(state.bans.snapshot()).into_response()
+}
+
+/// New log filter for `PUT /api/admin/log`.
+#[derive(Deserialize)]
+pub struct LogFilterRequest {
+    /// `RUST_LOG`-style directives, e.g. `info,ws=debug`
+    pub filter: String,
+}
+
+/// `POST /api/admin/bots` body. Without `games` the token covers public
+/// lobbies hosted by a human.
+#[derive(Deserialize)]
+pub struct BotTokenRequest {
+    pub name: String,
+    #[serde(default)]
+    pub games: Vec<Uuid>,
+    /// Lifetime; `auth.bot_token_ttl_secs` if unset, at most
+    /// `auth.bot_token_max_ttl_secs`
+    pub ttl_secs: Option<u64>,
+}
+
+/// Issue a long-lived token for an automated client (see `auth.rs`).
+pub async fn issue_bot_token(
+    State(state): State<AppState>,
+    Query(query): Query<AdminQuery>,
+    headers: HeaderMap,
+    Json(req): Json<BotTokenRequest>,
+) -> Response {
+    if let Err(denied) = state.admin_keys.authorize(&headers, query.token, Scope::Manage) {
+        return denied.into_response();
+    }
+    let name = req.name.trim();
+    if name.is_empty() || name.len() > 32 {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Bot name must be 1-32 characters" }))).into_response();
+    }
+    let scope = if req.games.is_empty() {
+        crate::auth::BotScope::Public
+    } else {
+        crate::auth::BotScope::Games(req.games)
+    };
+    let auth = &crate::config::global().auth;
+    let ttl_secs = req.ttl_secs.unwrap_or(auth.bot_token_ttl_secs);
+    if !(1..=auth.bot_token_max_ttl_secs).contains(&ttl_secs) {
+        let error = format!("ttl_secs must be 1-{}", auth.bot_token_max_ttl_secs);
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": error }))).into_response();
+    }
+    let ttl = std::time::Duration::from_secs(ttl_secs);
+    let token = state.sessions.issue_bot(name, scope.clone(), ttl);
+    tracing::info!(target: "admin", "Issued bot token for {:?} ({:?})", name, scope);
+    Json(json!({ "bot": name, "scope": scope, "token": token, "expires_in_secs": ttl.as_secs() })).into_response()
+}
+
+/// The log filter in effect.
+pub async fn get_log_filter(
+    State(state): State<AppState>,
+    Query(query): Query<AdminQuery>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(denied) = state.admin_keys.authorize(&headers, query.token, Scope::Read) {
+        return denied.into_response();
+    }
+    match crate::logging::control() {
+        Some(log) => Json(json!({ "filter": log.current() })).into_response(),
+        None => log_unavailable(),
+    }
+}
+
+/// Swap the log filter without a restart (see `logging.rs`).
+pub async fn set_log_filter(
+    State(state): State<AppState>,
+    Query(query): Query<AdminQuery>,
+    headers: HeaderMap,
+    Json(req): Json<LogFilterRequest>,
+) -> Response {
+    if let Err(denied) = state.admin_keys.authorize(&headers, query.token, Scope::Manage) {
+        return denied.into_response();
+    }
+    let Some(log) = crate::logging::control() else {
+        return log_unavailable();
+    };
+    match log.set(&req.filter) {
+        Ok(()) => {
+            tracing::info!(target: "admin", "Log filter set to {:?}", req.filter);
+            Json(json!({ "filter": log.current() })).into_response()
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response(),
+    }
+}
+
+fn log_unavailable() -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({ "error": "Runtime log control is not enabled" })),
+    )
+        .into_response()
+}
@@ -0,0 +1,198 @@
+//! Anomaly detection for tournament review.
+//!
+//! Each game keeps an `AuditLog`. The game's actor feeds it every shot and
+//! every verified solve, and it flags patterns a fair player is very unlikely
+//! to produce:
+//! - `FastSolve` — a solve verified within `FAST_SOLVE_SECS` of locking
+//! - `PerfectAccuracy` — `ACCURACY_MIN_SHOTS` or more shots without a miss
+//! - `ShotCadence` — `CADENCE_SHOTS` shots inside `CADENCE_SPAN`, faster than
+//!   one socket is allowed to fire (so a bot, or several sockets)
+//!
+//! The log also notes `Reaped` — a player was absent when a stuck game was
+//! given up on (see `Game::reap`). That one is not an anomaly and is not sent
+//! to the webhook. Nor is `AdminAction` — an organizer unlocked a player,
+//! changed their heat or cancelled their veto mid-game.
+//!
+//! Flags never affect the game. Each kind is recorded at most once per player.
+//! Entries can be read through `GET /api/admin/game/:id/audit`, and are POSTed
+//! to `ANTICHEAT_WEBHOOK_URL` when it is set (Discord-compatible `content`
+//! plus structured fields).
+
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+use crate::state::Player;
+
+const FAST_SOLVE_SECS: u64 = 30;
+const ACCURACY_MIN_SHOTS: u32 = 12;
+const CADENCE_SHOTS: usize = 5;
+const CADENCE_SPAN: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum Anomaly {
+    FastSolve,
+    PerfectAccuracy,
+    ShotCadence,
+    Reaped,
+    AdminAction,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AuditEntry {
+    /// Seconds since the Unix epoch
+    pub at: u64,
+    pub player_id: Uuid,
+    pub cf_handle: String,
+    pub anomaly: Anomaly,
+    pub detail: String,
+}
+
+#[derive(Debug, Default)]
+pub struct AuditLog {
+    entries: Vec<AuditEntry>,
+    /// Recent shot times per player, for the cadence check
+    shots: HashMap<Uuid, VecDeque<Instant>>,
+}
+
+impl AuditLog {
+    pub fn entries(&self) -> &[AuditEntry] {
+        &self.entries
+    }
+
+    /// Check a shot `player` just fired (stats already updated).
+    pub fn record_shot(&mut self, game_id: Uuid, player: &Player) {
+        let now = Instant::now();
+        let times = self.shots.entry(player.id).or_default();
+        times.push_back(now);
+        if times.len() > CADENCE_SHOTS {
+            times.pop_front();
+        }
+        if times.len() == CADENCE_SHOTS {
+            let span = now.duration_since(times[0]);
+            if span < CADENCE_SPAN {
+                let detail = format!("{} shots in {}ms", CADENCE_SHOTS, span.as_millis());
+                self.flag(game_id, player, Anomaly::ShotCadence, detail);
+            }
+        }
+
+        let stats = &player.stats;
+        if stats.cells_missed == 0 && stats.cells_hit >= ACCURACY_MIN_SHOTS {
+            let detail = format!("{} hits, no misses", stats.cells_hit);
+            self.flag(game_id, player, Anomaly::PerfectAccuracy, detail);
+        }
+    }
+
+    /// Check a solve CF just verified for `player` (still locked).
+    pub fn record_solve(&mut self, game_id: Uuid, player: &Player) {
+        let Some(locked_at) = player.locked_at_unix else {
+            return;
+        };
+        let locked_for = unix_now().saturating_sub(locked_at);
+        if locked_for < FAST_SOLVE_SECS {
+            let problem = player
+                .active_problem
+                .as_ref()
+                .map(|p| format!("{}{}", p.contest_id, p.index))
+                .unwrap_or_default();
+            let detail = format!("{} verified {}s after locking", problem, locked_for);
+            self.flag(game_id, player, Anomaly::FastSolve, detail);
+        }
+    }
+
+    /// Note that `player` was absent when the game was reaped.
+    pub fn record_reaped(&mut self, game_id: Uuid, player: &Player, detail: String) {
+        tracing::info!(target: "anticheat", %game_id, player_id = %player.id, "Reaped: {}", detail);
+        self.entries.push(AuditEntry {
+            at: unix_now(),
+            player_id: player.id,
+            cf_handle: player.cf_handle.clone(),
+            anomaly: Anomaly::Reaped,
+            detail,
+        });
+    }
+
+    /// Note an admin's change to `player`'s lock state.
+    pub fn record_admin(&mut self, game_id: Uuid, player: &Player, detail: String) {
+        tracing::info!(target: "anticheat", %game_id, player_id = %player.id, "Admin action: {}", detail);
+        self.entries.push(AuditEntry {
+            at: unix_now(),
+            player_id: player.id,
+            cf_handle: player.cf_handle.clone(),
+            anomaly: Anomaly::AdminAction,
+            detail,
+        });
+    }
+
+    fn flag(&mut self, game_id: Uuid, player: &Player, anomaly: Anomaly, detail: String) {
+        if self
+            .entries
+            .iter()
+            .any(|e| e.player_id == player.id && e.anomaly == anomaly)
+        {
+            return;
+        }
+        tracing::warn!(
+            target: "anticheat",
+            %game_id,
+            player_id = %player.id,
+            "Flagged {:?} for {}: {}",
+            anomaly,
+            player.cf_handle,
+            detail
+        );
+        let entry = AuditEntry {
+            at: unix_now(),
+            player_id: player.id,
+            cf_handle: player.cf_handle.clone(),
+            anomaly,
+            detail,
+        };
+        notify(game_id, &entry);
+        self.entries.push(entry);
+    }
+}
+
+/// POST the flag to `ANTICHEAT_WEBHOOK_URL`, if set. Fire-and-forget.
+fn notify(game_id: Uuid, entry: &AuditEntry) {
+    let url = match &crate::config::global().webhooks.anticheat_url {
+        Some(u) if !u.is_empty() => u.clone(),
+        _ => return,
+    };
+    // Outside a runtime (e.g. sync tests) there is nothing to spawn on
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    let body = serde_json::json!({
+        "content": format!(
+            "⚠️ Anti-cheat: {:?} — {} in game {} ({})",
+            entry.anomaly, entry.cf_handle, game_id, entry.detail
+        ),
+        "game_id": game_id,
+        "entry": entry,
+    });
+    runtime.spawn(async move {
+        static CLIENT: std::sync::OnceLock<reqwest::Client> = std::sync::OnceLock::new();
+        let client = CLIENT.get_or_init(|| {
+            reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default()
+        });
+        match client.post(&url).json(&body).send().await {
+            Ok(resp) if !resp.status().is_success() => {
+                tracing::warn!("Anti-cheat webhook returned {}", resp.status())
+            }
+            Err(e) => tracing::warn!("Anti-cheat webhook POST failed: {}", e),
+            Ok(_) => {}
+        }
+    });
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
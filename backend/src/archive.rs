@@ -0,0 +1,121 @@
+//! Finished games past their cleanup window, frozen for sharing.
+//!
+//! When a finished game leaves the registry, everything the read-only
+//! endpoints serve is read off its actor once: the snapshot, the final
+//! `GameOver`, the replay log, the report, the analysis inputs, the boards
+//! and the referee feed's closing lines. The actor then stops with its
+//! channels. Records are kept for `cleanup.archived_secs`, and at most
+//! `cleanup.archived_max_games` of them; past that the oldest is dropped.
+
+use dashmap::DashMap;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::board_image::Boards;
+use crate::match_report::GameReport;
+use crate::protocol::{GameSnapshot, RefereeMessage, ServerMessage, StampedMessage};
+use crate::state::{Game, LockRecord};
+
+/// One player's side of `GET /api/game/:id/analysis`: ID, handle, solved
+/// problems and locks.
+pub type AnalysisPlayer = (Uuid, String, HashSet<String>, Vec<LockRecord>);
+
+/// The analysis inputs: whether the game was judged on Codeforces, and each
+/// player's side.
+pub fn analysis_inputs(game: &Game) -> (bool, Vec<AnalysisPlayer>) {
+    let players = std::iter::once(&game.player1)
+        .chain(game.player2.as_ref())
+        .map(|p| {
+            let locks: Vec<_> = game.lock_history.iter().filter(|r| r.player_id == p.id).cloned().collect();
+            (p.id, p.cf_handle.clone(), p.solved_set.clone(), locks)
+        })
+        .collect();
+    (game.config.judge == crate::judge::CODEFORCES, players)
+}
+
+/// What's left of a finished game once it's archived.
+#[derive(Debug, Clone)]
+pub struct ArchivedGame {
+    pub archived_at: Instant,
+    pub snapshot: GameSnapshot,
+    pub game_over: Option<ServerMessage>,
+    pub seed: u64,
+    pub lock_history: Vec<LockRecord>,
+    /// The replay log, oldest first
+    pub events: Vec<StampedMessage>,
+    /// `None` if the game never reached combat
+    pub report: Option<GameReport>,
+    pub analysis: (bool, Vec<AnalysisPlayer>),
+    /// `None` if there's no image to share (see `board_image::visible_boards`)
+    pub boards: Option<Boards>,
+    /// What the referee feed tells a spectator, if the game had one
+    pub referee: Option<Vec<RefereeMessage>>,
+}
+
+impl ArchivedGame {
+    /// Read everything off `game`, whose replay log is `events`.
+    pub fn freeze(game: &Game, events: Vec<StampedMessage>) -> Self {
+        Self {
+            archived_at: Instant::now(),
+            snapshot: game.snapshot(),
+            game_over: game.game_over_msg.clone(),
+            seed: game.seed,
+            lock_history: game.lock_history.clone(),
+            report: crate::match_report::build(game, &events),
+            events,
+            analysis: analysis_inputs(game),
+            boards: crate::board_image::visible_boards(game),
+            referee: game.config.referee_feed.then(|| crate::referee::opening_lines(game)),
+        }
+    }
+}
+
+/// Archived games by ID, at most `max` of them.
+pub struct Archive {
+    games: DashMap<Uuid, Arc<ArchivedGame>>,
+    max: usize,
+}
+
+impl Archive {
+    pub fn new(max: usize) -> Self {
+        Self { games: DashMap::new(), max }
+    }
+
+    /// Keep `game`, dropping the oldest record if the archive is full.
+    pub fn insert(&self, game_id: Uuid, game: ArchivedGame) {
+        if self.max == 0 {
+            return;
+        }
+        while self.games.len() >= self.max {
+            let oldest = self.games.iter().min_by_key(|e| e.archived_at).map(|e| *e.key());
+            let Some(oldest) = oldest else { break };
+            self.games.remove(&oldest);
+        }
+        self.games.insert(game_id, Arc::new(game));
+    }
+
+    pub fn get(&self, game_id: Uuid) -> Option<Arc<ArchivedGame>> {
+        self.games.get(&game_id).map(|e| e.clone())
+    }
+
+    pub fn contains(&self, game_id: Uuid) -> bool {
+        self.games.contains_key(&game_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    /// Drop records older than `ttl`. Returns how many were dropped.
+    pub fn purge_expired(&self, ttl: Duration) -> usize {
+        let before = self.games.len();
+        self.games.retain(|_, game| game.archived_at.elapsed() < ttl);
+        before - self.games.len()
+    }
+}
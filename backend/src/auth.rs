@@ -0,0 +1,193 @@
+//! Signed session tokens for players.
+//!
+//! A player's UUID used to be their only credential, so anyone who learned the
+//! opponent's ID could connect as them. Now the server hands out an HS256 JWT
+//! binding `player_id` + `game_id` + seat: the host gets one from
+//! `POST /api/game`, the guest in a `SessionToken` message once they take the
+//! empty seat. Reconnecting as an existing participant requires the token on
+//! the WebSocket upgrade (`?token=` or `Authorization: Bearer`), and every
+//! mutating message is checked against it.
+//!
+//! Alongside it each joined socket gets a resume token (`ResumeToken`): the
+//! same claims marked `resume`, good for `Resume` but not for the upgrade.
+//! Presented within `game.resume_window_secs` of the disconnect, it replays
+//! the events the player missed instead of a full resync (see `ws.rs`).
+//!
+//! Automated clients (practice bots, integration tests) instead get a
+//! long-lived bot token from `POST /api/admin/bots`, naming the bot and
+//! scoped to a list of games or to public lobbies hosted by a human. It is
+//! presented on the upgrade like a session token and lets the socket take
+//! the guest seat of a game in scope; the seat is marked as the bot's, so
+//! bots show up as such in snapshots and reports and draw on their own
+//! verification budget instead of their IP's.
+//!
+//! The signing key comes from `JWT_SECRET` (`auth.jwt_secret`); without it a random per-process key
+//! is used, which is fine for a single instance since games live in memory anyway.
+
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Host,
+    Guest,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claims {
+    /// Player ID
+    pub sub: Uuid,
+    /// Game ID
+    pub gid: Uuid,
+    pub role: Role,
+    /// Expiry, seconds since the Unix epoch
+    pub exp: u64,
+    /// A resume token (`SessionKeys::issue_resume`), only for `Resume`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub resume: bool,
+}
+
+impl Claims {
+    /// True if these claims authorize acting as `player_id` in `game_id` right now.
+    pub fn allows(&self, player_id: Uuid, game_id: Uuid) -> bool {
+        self.sub == player_id && self.gid == game_id && self.exp > unix_now()
+    }
+}
+
+/// Which games a bot token may join.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BotScope {
+    /// Only these games
+    Games(Vec<Uuid>),
+    /// Public lobbies (`GameConfig::public`) hosted by a human
+    Public,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BotClaims {
+    /// Bot name, shown in place of a human's in stats
+    pub bot: String,
+    pub scope: BotScope,
+    /// Expiry, seconds since the Unix epoch
+    pub exp: u64,
+}
+
+impl BotClaims {
+    /// True if this bot may take the guest seat of `game`.
+    pub fn allows(&self, game: &crate::state::Game) -> bool {
+        let in_scope = match &self.scope {
+            BotScope::Games(ids) => ids.contains(&game.id),
+            BotScope::Public => game.config.public && game.player1.bot.is_none(),
+        };
+        in_scope && self.exp > unix_now()
+    }
+}
+
+#[derive(Clone)]
+pub struct SessionKeys {
+    encoding: Arc<EncodingKey>,
+    decoding: Arc<DecodingKey>,
+    ttl: Duration,
+}
+
+impl SessionKeys {
+    pub fn new(secret: &[u8], ttl: Duration) -> Self {
+        Self {
+            encoding: Arc::new(EncodingKey::from_secret(secret)),
+            decoding: Arc::new(DecodingKey::from_secret(secret)),
+            ttl,
+        }
+    }
+
+    /// Keys from `auth.jwt_secret` / `auth.jwt_ttl_secs` (env `JWT_SECRET` /
+    /// `JWT_TTL_SECS`), or a random secret if unset.
+    pub fn from_config(config: &crate::config::AuthConfig) -> Self {
+        let ttl = Duration::from_secs(config.jwt_ttl_secs);
+        match &config.jwt_secret {
+            Some(secret) if !secret.is_empty() => Self::new(secret.as_bytes(), ttl),
+            _ => {
+                tracing::warn!("JWT_SECRET not set; session tokens won't survive a restart");
+                let secret: [u8; 32] = rand::random();
+                Self::new(&secret, ttl)
+            }
+        }
+    }
+
+    /// Sign a token for `player_id` in `game_id`.
+    pub fn issue(&self, player_id: Uuid, game_id: Uuid, role: Role) -> String {
+        self.sign(Claims {
+            sub: player_id,
+            gid: game_id,
+            role,
+            exp: unix_now() + self.ttl.as_secs(),
+            resume: false,
+        })
+    }
+
+    /// Sign a resume token for `player_id` in `game_id`. It lasts as long as
+    /// a session token; the resume window is checked against the disconnect.
+    pub fn issue_resume(&self, player_id: Uuid, game_id: Uuid, role: Role) -> String {
+        self.sign(Claims {
+            sub: player_id,
+            gid: game_id,
+            role,
+            exp: unix_now() + self.ttl.as_secs(),
+            resume: true,
+        })
+    }
+
+    /// Sign a bot token for `name`, good for `ttl`.
+    pub fn issue_bot(&self, name: &str, scope: BotScope, ttl: Duration) -> String {
+        self.sign(BotClaims {
+            bot: name.to_string(),
+            scope,
+            exp: unix_now().saturating_add(ttl.as_secs()),
+        })
+    }
+
+    fn sign(&self, claims: impl Serialize) -> String {
+        jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &self.encoding)
+            .expect("HS256 signing cannot fail")
+    }
+
+    /// Check the signature and expiry. `None` for anything invalid.
+    pub fn verify(&self, token: &str) -> Option<Claims> {
+        self.decode(token)
+    }
+
+    /// Check a bot token's signature and expiry. `None` for anything invalid,
+    /// including player session tokens.
+    pub fn verify_bot(&self, token: &str) -> Option<BotClaims> {
+        self.decode(token)
+    }
+
+    fn decode<T: serde::de::DeserializeOwned>(&self, token: &str) -> Option<T> {
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.leeway = 0;
+        jsonwebtoken::decode::<T>(token, &self.decoding, &validation)
+            .ok()
+            .map(|data| data.claims)
+    }
+}
+
+/// Token from an `Authorization: Bearer` header, else from the query string.
+pub fn request_token(headers: &axum::http::HeaderMap, query: Option<String>) -> Option<String> {
+    headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|t| t.trim().to_string())
+        .or(query)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -1,250 +1,78 @@
+use crate::actor::GameSummary;
 use crate::protocol::ServerMessage;
-use crate::state::{AppState, GameEvent, GameStatus, TiebreakResult}; //our app state 
+use crate::state::{AppState, GameEvent, GameStatus}; //our app state 
 use tokio::time::{sleep, Duration};
-use uuid::Uuid;
+
+/// Name of the ticker task in `/health` and in panic reports.
+pub const GLOBAL_TICKER: &str = "global_ticker";
+
+/// Run the global ticker under the state's supervisor, so a panic in one sweep
+/// doesn't stop cleanup for the rest of the server's life.
+pub fn spawn_global_ticker(state: &AppState) {
+    let ticker_state = state.clone();
+    state.supervisor.spawn(
+        GLOBAL_TICKER,
+        Some(state.config.game.sweep_interval()),
+        move || start_global_ticker(ticker_state.clone()),
+    );
+}
 
 //main game loop / server handling multiple game states at a timeR
 pub async fn start_global_ticker(state: AppState) {
     //our async global ticker, keep passing the app state
     loop {
-        sleep(Duration::from_secs(1)).await; //1 tick  is 1 second
-
-        // ── PHASE 1: Collect game IDs + broadcast Tick (brief read lock) ──────
-        // Each game has its own broadcast channel. Sending Tick only needs the
-        // Sender, which we can clone cheaply under a read lock.
-        let game_ids: Vec<(Uuid, tokio::sync::broadcast::Sender<GameEvent>)> = {
-            let games = state.games.read().await;
-            games.values().map(|g| (g.id, g.tx.clone())).collect()
-        };
-        // Broadcast Tick to all games without holding any write lock
-        for (_, tx) in &game_ids {
-            let _ = tx.send(GameEvent::Tick);
-        }
-
-        // ── PHASE 2: Per-game state mutations (one write lock per game) ───────
-        // Process each game individually so contention is 1/50th of the old approach.
-        // A WebSocket handler waiting for game X is only blocked while we process game X,
-        // not while we process all 50 other games.
-        for (id, _) in &game_ids {
-            let mut games = state.games.write().await;
-            let game = match games.get_mut(id) {
-                Some(g) => g,
-                None => continue, // Game was cleaned up, skip
-            };
-
-            if game.status == GameStatus::Waiting {
-                if game.created_at.elapsed() >= std::time::Duration::from_secs(300) { //if you waited for more than 5 minutes
-                    game.status = GameStatus::Finished;
-                    game.finished_at = Some(std::time::Instant::now());
-                    let go_msg = crate::game::build_game_over(game, None, "LobbyTimeout".to_string());
-                    game.game_over_msg = Some(go_msg.clone());
-                    let _ = game.tx.send(GameEvent::Message(go_msg));
-                    tracing::info!("Game {:?} lobby timed out (5 min)", game.id);
-                }
-            }
-
-            // Placement timeout: 10 minutes from when placement actually started (P2 joined).
-            // Using placement_started_at (not created_at) guarantees a full 10 minutes
-            // regardless of how long the lobby waited for P2.
-            // Also covers Initializing (both placed, CF fetch in progress) — same timeout.
-            if game.status == GameStatus::PlacingShips || game.status == GameStatus::Initializing {
-                if let Some(placement_start) = game.placement_started_at {
-                    if placement_start.elapsed() >= std::time::Duration::from_secs(600) {
-                        game.status = GameStatus::Finished;
-                        game.finished_at = Some(std::time::Instant::now());
-                        let go_msg = crate::game::build_game_over(game, None, "PlacementTimeout".to_string());
-                        game.game_over_msg = Some(go_msg.clone());
-                        let _ = game.tx.send(GameEvent::Message(go_msg));
-                        tracing::info!("Game {:?} placement timed out (10 min)", game.id);
-                    }
-                }
-            }
-            if game.status == GameStatus::Playing || game.status == GameStatus::SuddenDeath {
-                // Check veto timer expiry for both players
-                let veto_durations = game.config.veto_penalties;
-
-                //Check player 1 veto expiry
-                if game.player1.is_locked {
-                    if let Some(veto_start) = game.player1.veto_started_at {
-                        let duration = veto_durations
-                            .get(game.player1.vetoes_used.saturating_sub(1) as usize)
-                            .copied()
-                            .unwrap_or(900);
-                        if veto_start.elapsed().as_secs() >= duration {
-                            // Veto expired — assign next problem from queue (must solve to unlock)
-                            game.player1.veto_started_at = None;
-                            game.player1.last_verification_attempt = None;
-                            let idx = game.p1_queue_idx;
-                            if idx < game.problem_queue.len() {
-                                let ap = game.problem_queue[idx].clone();
-                                game.p1_queue_idx += 1;
-                                game.player1.active_problem = Some(ap.clone());
-                                let _ = game.tx.send(GameEvent::Message(
-                                    ServerMessage::ProblemAssigned {
-                                        player_id: game.player1.id,
-                                        contest_id: ap.contest_id,
-                                        problem_index: ap.index,
-                                        problem_name: ap.name,
-                                        rating: ap.rating,
-                                    },
-                                ));
-                            } else {
-                                // Queue exhausted — unlock as mercy rule
-                                game.player1.unlock_weapons();
-                                let _ =
-                                    game.tx
-                                        .send(GameEvent::Message(ServerMessage::WeaponsUnlocked {
-                                            player_id: game.player1.id,
-                                            reason: "veto_expired".to_string(),
-                                        }));
-                            }
-                        }
-                    }
-                }
-
-                //Check player 2 veto expiry
-                let p2_veto_expired = game.player2.as_ref().and_then(|p2| {
-                    if p2.is_locked {
-                        if let Some(veto_start) = p2.veto_started_at {
-                            let duration = veto_durations
-                                .get(p2.vetoes_used.saturating_sub(1) as usize)
-                                .copied()
-                                .unwrap_or(900);
-                            if veto_start.elapsed().as_secs() >= duration {
-                                return Some(p2.id);
-                            }
-                        }
-                    }
-                    None
-                });
-
-                if let Some(p2_id) = p2_veto_expired {
-                    let idx = game.p2_queue_idx;
-                    let next_problem = game.problem_queue.get(idx).cloned();
-
-                    if let Some(ap) = next_problem {
-                        game.p2_queue_idx += 1;
-                        let p2 = game.player2.as_mut().unwrap();
-                        p2.veto_started_at = None;
-                        p2.last_verification_attempt = None;
-                        p2.active_problem = Some(ap.clone());
-                        let _ = game.tx.send(GameEvent::Message(
-                            ServerMessage::ProblemAssigned {
-                                player_id: p2_id,
-                                contest_id: ap.contest_id,
-                                problem_index: ap.index,
-                                problem_name: ap.name,
-                                rating: ap.rating,
-                            },
-                        ));
-                    } else {
-                        // Queue exhausted — unlock as mercy rule
-                        let p2 = game.player2.as_mut().unwrap();
-                        p2.unlock_weapons();
-                        let _ = game.tx.send(GameEvent::Message(
-                            ServerMessage::WeaponsUnlocked {
-                                player_id: p2_id,
-                                reason: "veto_expired".to_string(),
-                            },
-                        ));
-                    }
-                }
-
-                //Check game timeout
-                if let Some(start) = game.game_started_at {
-                    // Only check for initial timeout if we are clearly in Playing state
-                    // If we are already in SuddenDeath, we ignore the standard game duration
-                    if game.status == GameStatus::Playing
-                        && start.elapsed().as_secs() >= game.config.game_duration_secs
-                    {
-                        //Time Over! Determine winner or enter sudden death
-                        let winner_result = game.determine_winner();
+        sleep(state.config.game.sweep_interval()).await; //1 tick  is 1 second by default
+        state.supervisor.heartbeat(GLOBAL_TICKER);
 
-                        //Determine what happens based on TiebreakResult
-                        match winner_result {
-                            TiebreakResult::Player1Wins => {
-                                game.status = GameStatus::Finished;
-                                game.finished_at = Some(std::time::Instant::now());
-                                let winner = Some(game.player1.id);
-                                let go_msg = crate::game::build_game_over(game, winner, "Timeout - More ships remaining".to_string());
-                                game.game_over_msg = Some(go_msg.clone());
-                                let _ = game.tx.send(GameEvent::Message(go_msg));
-                                crate::discord::log_game(game, winner, "Timeout");
-                            }
-                            TiebreakResult::Player2Wins => {
-                                game.status = GameStatus::Finished;
-                                game.finished_at = Some(std::time::Instant::now());
-                                let winner = game.player2.as_ref().map(|p| p.id);
-                                let go_msg = crate::game::build_game_over(game, winner, "Timeout - More ships remaining".to_string());
-                                game.game_over_msg = Some(go_msg.clone());
-                                let _ = game.tx.send(GameEvent::Message(go_msg));
-                                crate::discord::log_game(game, winner, "Timeout");
-                            }
-                            TiebreakResult::SuddenDeath => {
-                                // Sudden Death: first player to land a HIT wins.
-                                // No player state changes on entry — heat locks, veto timers,
-                                // and unlock requirements ALL carry over unchanged.
-                                // The Tick handler propagates per-player state every second,
-                                // advertising "SuddenDeath" status to both clients.
-                                game.status = GameStatus::SuddenDeath;
-                            }
-                        }
-                    }
+        // Ticks, timeouts and veto expiry are NOT driven from here — each game
+        // actor publishes its players' views and sleeps until its next
+        // deadline (see `actor.rs` / `timers.rs`).
+        let handles = state.all_games();
 
-                    // SUDDEN DEATH TIMEOUT: 10 minutes max to prevent infinite games
-                    // (e.g., both players locked with no vetoes remaining)
-                    const SUDDEN_DEATH_TIMEOUT_SECS: u64 = 600; // 10 minutes
-                    if game.status == GameStatus::SuddenDeath
-                        && start.elapsed().as_secs()
-                            >= game.config.game_duration_secs + SUDDEN_DEATH_TIMEOUT_SECS
-                    {
-                        game.status = GameStatus::Finished;
-                        game.finished_at = Some(std::time::Instant::now());
-                        let go_msg = crate::game::build_game_over(game, None, "SuddenDeathTimeout".to_string());
-                        game.game_over_msg = Some(go_msg.clone());
-                        let _ = game.tx.send(GameEvent::Message(go_msg));
-                        crate::discord::log_game(game, None, "SuddenDeathTimeout");
-                        tracing::info!("Game {:?} sudden death timed out (10 min)", game.id);
-                    }
-                }
+        // ── Judge outages: pause (or resume) the clock of games on a judge
+        // that's down, while someone is locked (`pause_on_outage`) ──────────
+        let any_down = state.judges.any_down();
+        for handle in &handles {
+            let summary = handle.summary();
+            let in_combat = matches!(summary.status, GameStatus::Playing | GameStatus::SuddenDeath);
+            if summary.clock_paused || (any_down && in_combat) {
+                let judges = state.judges.clone();
+                handle
+                    .cast(move |game| {
+                        let down = judges.for_game(&game.config).is_down();
+                        game.sync_outage(down);
+                    })
+                    .await;
             }
-            // Write lock is dropped here at end of this iteration, freeing it for
-            // WebSocket handlers targeting this game before we move to the next one.
         }
 
-        // ── PHASE 3: Cleanup (brief write lock, retain is fast) ───────────────
-        {
-            let mut games = state.games.write().await;
-            // CLEANUP: Remove games that:
-            // - Finished more than 5 minutes ago
-            // - Are waiting > 30 mins
-            // - Are placing ships > 30 mins (player joined but never placed)
-            let finished_cleanup_threshold = std::time::Duration::from_secs(300); // 5 minutes after finish
-            let waiting_cleanup_threshold = std::time::Duration::from_secs(1800); // 30 minutes if waiting
-            let placing_cleanup_threshold = std::time::Duration::from_secs(1800); // 30 minutes if placing ships
-
-            let before = games.len();
-            games.retain(|_id, game| {
-                if let Some(finished) = game.finished_at {
-                    // If finished, keep only if within threshold
-                    finished.elapsed() < finished_cleanup_threshold
-                } else if game.status == GameStatus::Waiting {
-                    // If waiting for P2, keep only if within threshold
-                    game.created_at.elapsed() < waiting_cleanup_threshold
-                } else if game.status == GameStatus::PlacingShips || game.status == GameStatus::Initializing {
-                    // If stuck in placement/init phase, clean up after threshold from when placement started
-                    game.placement_started_at
-                        .map(|ps| ps.elapsed() < placing_cleanup_threshold)
-                        .unwrap_or_else(|| game.created_at.elapsed() < placing_cleanup_threshold)
-                } else {
-                    // Keep active/playing games (Playing, SuddenDeath)
-                    true
+        // ── Cleanup (read-only, from each actor's published summary) ──────────
+        // CLEANUP: Remove games past their retention window (`CleanupPolicy`):
+        // finished, waiting for P2, stuck in placement, or abandoned by both players.
+        // Lingering sockets get a GameExpired first, then see the channel close.
+        // Finished games are frozen into the archive instead, where they stay
+        // readable until `archived` has passed too.
+        let policy = &state.cleanup_policy;
+        let expired: Vec<_> = handles
+            .iter()
+            .filter_map(|h| policy.expiry_reason(&h.summary()).map(|reason| (h, reason)))
+            .collect();
+        if !expired.is_empty() {
+            for (handle, reason) in &expired {
+                if handle.summary().finished_at.is_some() && !policy.archived.is_zero() {
+                    state.archive_game(handle.id).await;
+                    continue;
                 }
-            });
-            let removed = before - games.len();
-            if removed > 0 {
-                tracing::info!("Cleaned up {} finished/abandoned games ({} remaining)", removed, games.len());
+                let _ = handle.tx.send(GameEvent::Message(ServerMessage::GameExpired {
+                    reason: reason.to_string(),
+                }));
+                state.remove_game(handle.id);
             }
+            tracing::info!("Cleaned up {} finished/abandoned games ({} remaining)", expired.len(), state.games.len());
+        }
+        let dropped = state.archive.purge_expired(policy.archived);
+        if dropped > 0 {
+            tracing::info!("Dropped {} archived games ({} remaining)", dropped, state.archive.len());
         }
 
         // RATE LIMITER CLEANUP: Purge expired entries every 60 seconds
@@ -261,10 +89,78 @@ pub async fn start_global_ticker(state: AppState) {
             let before = limiter.len();
             let window = std::time::Duration::from_secs(300);
             limiter.retain(|_, (created, _)| created.elapsed() < window);
-            let purged = before - limiter.len();
+            let mut purged = before - limiter.len();
+            drop(limiter);
+            purged += state.ip_limiters.create_game.purge_expired().await;
+            purged += state.ip_limiters.contest.purge_expired().await;
+            purged += state.verify_limiters.per_game.purge_expired();
+            purged += state.verify_limiters.per_ip.purge_expired();
+            purged += state.verify_limiters.per_bot.purge_expired();
+            purged += state.idempotency_keys.purge_expired();
             if purged > 0 {
                 tracing::debug!("Purged {} expired rate limiter entries", purged);
             }
         }
     }
 }
+
+/// How long games are kept in memory, by state. Configured under `[cleanup]`
+/// or from env (seconds): FINISHED_GAME_TTL_SECS (300), WAITING_GAME_TTL_SECS
+/// (1800), PLACING_GAME_TTL_SECS (1800), ABANDONED_GAME_TTL_SECS (300),
+/// ARCHIVED_GAME_TTL_SECS (86400).
+#[derive(Clone, Debug)]
+pub struct CleanupPolicy {
+    /// After the game ends (results stay replayable on reconnect until then)
+    pub finished: Duration,
+    /// Lobby never got a second player
+    pub waiting: Duration,
+    /// Both joined but never reached combat (measured from placement start)
+    pub placing: Duration,
+    /// Both players disconnected mid-game
+    pub abandoned: Duration,
+    /// After `finished`, in the archive: boards, report and replay stay
+    /// readable so shared result links keep working
+    pub archived: Duration,
+}
+
+impl Default for CleanupPolicy {
+    fn default() -> Self {
+        Self::from_config(&crate::config::CleanupConfig::default())
+    }
+}
+
+impl CleanupPolicy {
+    pub fn from_config(config: &crate::config::CleanupConfig) -> Self {
+        Self {
+            finished: Duration::from_secs(config.finished_secs),
+            waiting: Duration::from_secs(config.waiting_secs),
+            placing: Duration::from_secs(config.placing_secs),
+            abandoned: Duration::from_secs(config.abandoned_secs),
+            archived: Duration::from_secs(config.archived_secs),
+        }
+    }
+
+    /// Why a game has outlived its retention window, or None to keep it.
+    /// Scheduled matches are kept at least until their scheduled start.
+    pub fn expiry_reason(&self, game: &GameSummary) -> Option<&'static str> {
+        let since = |at: std::time::Instant| game.scheduled_start_at.map_or(at, |s| at.max(s));
+        if let Some(finished) = game.finished_at {
+            // If finished, drop once past threshold
+            (finished.elapsed() >= self.finished).then_some("Game finished")
+        } else if game.status == GameStatus::Waiting {
+            // If waiting for P2, drop once past threshold (plus any host extensions)
+            let waiting = self.waiting + crate::timers::LOBBY_EXTENSION * game.lobby_extensions;
+            (since(game.created_at).elapsed() >= waiting).then_some("Lobby expired")
+        } else if game.status == GameStatus::PlacingShips || game.status == GameStatus::Initializing {
+            // If stuck in placement/init phase, clean up after threshold from when placement started
+            let started = game.placement_started_at.unwrap_or(game.created_at);
+            (since(started).elapsed() >= self.placing).then_some("Placement never finished")
+        } else if game.status == GameStatus::Abandoned {
+            let left = game.abandoned_at.unwrap_or(game.created_at);
+            (since(left).elapsed() >= self.abandoned).then_some("Both players left")
+        } else {
+            // Keep active/playing games (Countdown, Playing, SuddenDeath)
+            None
+        }
+    }
+}
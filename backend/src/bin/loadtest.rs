@@ -0,0 +1,258 @@
+//! Load test: runs N concurrent games against a live server, each driven by two
+//! scripted WebSocket clients (join → place → ready → fire until locked or the
+//! game ends), and reports shot round-trip latency.
+//!
+//! ```text
+//! cargo run --release --features loadtest --bin loadtest -- \
+//!     --url http://127.0.0.1:3000 --games 200 --duration 60 --fire-ms 250
+//! ```
+//!
+//! Every game is created from this machine, so raise the server's creation limits
+//! for large runs (MAX_ACTIVE_GAMES, MAX_LOBBIES_PER_IP, RATE_LIMIT_CREATE_PER_MIN).
+//! Combat starts only once the server has fetched its problem pool from CF.
+
+use backend::protocol::{ClientMessage, Coord, ServerMessage, ShipPlacement};
+use battle_cp_client::{create_game, Client, ClientError, ReconnectPolicy, Seat};
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Placement and Ready are rate-limited to one per 2s per connection.
+const RESEND_EVERY: Duration = Duration::from_millis(2500);
+
+struct Args {
+    url: String,
+    games: usize,
+    duration: Duration,
+    fire_every: Duration,
+}
+
+impl Args {
+    fn parse() -> anyhow::Result<Self> {
+        let mut args = Self {
+            url: "http://127.0.0.1:3000".to_string(),
+            games: 50,
+            duration: Duration::from_secs(60),
+            fire_every: Duration::from_millis(250),
+        };
+        let mut it = std::env::args().skip(1);
+        while let Some(flag) = it.next() {
+            let value = it
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("missing value for {}", flag))?;
+            match flag.as_str() {
+                "--url" => args.url = value.trim_end_matches('/').to_string(),
+                "--games" => args.games = value.parse()?,
+                "--duration" => args.duration = Duration::from_secs(value.parse()?),
+                "--fire-ms" => args.fire_every = Duration::from_millis(value.parse()?),
+                other => anyhow::bail!(
+                    "unknown flag {} (expected --url, --games, --duration, --fire-ms)",
+                    other
+                ),
+            }
+        }
+        Ok(args)
+    }
+}
+
+#[derive(Default)]
+struct Stats {
+    games_created: AtomicU64,
+    create_failed: AtomicU64,
+    clients_failed: AtomicU64,
+    games_started: AtomicU64,
+    games_finished: AtomicU64,
+    shots_fired: AtomicU64,
+    messages_received: AtomicU64,
+    server_errors: AtomicU64,
+    shot_latencies: Mutex<Vec<Duration>>,
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Arc::new(Args::parse()?);
+    let stats = Arc::new(Stats::default());
+    let deadline = Instant::now() + args.duration;
+
+    println!(
+        "Starting {} games against {} for {:?}",
+        args.games, args.url, args.duration
+    );
+
+    let mut clients = Vec::with_capacity(args.games * 2);
+    for i in 0..args.games {
+        let settings = serde_json::json!({ "heat_threshold": 20, "game_duration_mins": 10 });
+        let host = match create_game(&args.url, &format!("lt{}h", i), &settings).await {
+            Ok(seat) => seat,
+            Err(e) => {
+                eprintln!("game {}: create failed: {}", i, e);
+                stats.create_failed.fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+        };
+        stats.games_created.fetch_add(1, Ordering::Relaxed);
+
+        let game_id = host.game_id;
+        // The guest takes the empty seat tokenless; the server issues theirs on join
+        let guest = Seat::guest(game_id, format!("lt{}g", i));
+        for (seat, is_host) in [(host, true), (guest, false)] {
+            let (args, stats) = (args.clone(), stats.clone());
+            clients.push(tokio::spawn(async move {
+                let pid = seat.player_id;
+                if let Err(e) = run_client(&args, &stats, seat, is_host, deadline).await {
+                    eprintln!("game {} client {}: {}", game_id, pid, e);
+                    stats.clients_failed.fetch_add(1, Ordering::Relaxed);
+                }
+            }));
+        }
+    }
+
+    for client in clients {
+        let _ = client.await;
+    }
+    report(&stats, args.duration);
+    Ok(())
+}
+
+/// Standard fleet (5, 4, 3, 3, 2) on every other row, so any layout is valid.
+fn fleet() -> Vec<ShipPlacement> {
+    let mut rng = rand::thread_rng();
+    let mut rows = [0usize, 2, 4, 6, 8];
+    rows.shuffle(&mut rng);
+    [5u8, 4, 3, 3, 2]
+        .iter()
+        .zip(rows)
+        .map(|(&size, y)| ShipPlacement {
+            x: 0,
+            y,
+            size,
+            vertical: false,
+        })
+        .collect()
+}
+
+async fn run_client(
+    args: &Args,
+    stats: &Stats,
+    seat: Seat,
+    is_host: bool,
+    deadline: Instant,
+) -> anyhow::Result<()> {
+    let pid = seat.player_id;
+    let mut client = Client::join(&args.url, seat)
+        .await?
+        .with_reconnect(ReconnectPolicy::never());
+
+    let mut shots: Vec<(usize, usize)> = (0..10).flat_map(|y| (0..10).map(move |x| (x, y))).collect();
+    shots.shuffle(&mut rand::thread_rng());
+    let mut shots = shots.into_iter();
+    let mut in_flight: HashMap<(usize, usize), Instant> = HashMap::new();
+
+    let (mut opponent, mut placed, mut started, mut locked) = (!is_host, false, false, false);
+    let mut last_setup_send: Option<Instant> = None;
+    let mut step = tokio::time::interval(args.fire_every);
+
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep_until(deadline.into()) => break,
+            _ = step.tick() => {
+                let resend_due = last_setup_send.is_none_or(|t| t.elapsed() >= RESEND_EVERY);
+                let next = if !placed {
+                    resend_due.then(|| ClientMessage::PlaceShips { ships: fleet() })
+                } else if !started {
+                    (opponent && resend_due).then_some(ClientMessage::Ready)
+                } else if !locked {
+                    shots.next().map(|(x, y)| {
+                        in_flight.insert((x, y), Instant::now());
+                        ClientMessage::Fire { at: Coord { x, y } }
+                    })
+                } else {
+                    None
+                };
+                if let Some(msg) = next {
+                    if matches!(msg, ClientMessage::Fire { .. }) {
+                        stats.shots_fired.fetch_add(1, Ordering::Relaxed);
+                    } else {
+                        last_setup_send = Some(Instant::now());
+                    }
+                    client.send(msg).await?;
+                }
+            }
+            msg = client.recv() => {
+                let msg = match msg {
+                    Ok(Some(msg)) => msg,
+                    Ok(None) | Err(ClientError::Closed) => break,
+                    Err(ClientError::Json(_)) => continue,
+                    Err(e) => return Err(e.into()),
+                };
+                stats.messages_received.fetch_add(1, Ordering::Relaxed);
+                match msg {
+                    ServerMessage::PlayerJoined { .. } => opponent = true,
+                    ServerMessage::ShipsConfirmed { player_id } if player_id == pid => {
+                        placed = true;
+                        last_setup_send = None;
+                    }
+                    ServerMessage::GameStart if !started => {
+                        started = true;
+                        if is_host {
+                            stats.games_started.fetch_add(1, Ordering::Relaxed);
+                        }
+                    }
+                    ServerMessage::ShotResult { x, y, shooter_id, .. } if shooter_id == pid => {
+                        if let Some(sent) = in_flight.remove(&(x, y)) {
+                            stats.shot_latencies.lock().unwrap().push(sent.elapsed());
+                        }
+                    }
+                    ServerMessage::WeaponsLocked { player_id } if player_id == pid => locked = true,
+                    ServerMessage::WeaponsUnlocked { player_id, .. } if player_id == pid => locked = false,
+                    ServerMessage::GameOver { .. } => {
+                        if is_host {
+                            stats.games_finished.fetch_add(1, Ordering::Relaxed);
+                        }
+                        break;
+                    }
+                    ServerMessage::Error { .. } => {
+                        stats.server_errors.fetch_add(1, Ordering::Relaxed);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    let _ = client.close().await;
+    Ok(())
+}
+
+fn report(stats: &Stats, duration: Duration) {
+    let n = |c: &AtomicU64| c.load(Ordering::Relaxed);
+    let mut lat = stats.shot_latencies.lock().unwrap().clone();
+    lat.sort_unstable();
+    let pct = |p: f64| -> Duration {
+        if lat.is_empty() {
+            Duration::ZERO
+        } else {
+            lat[((lat.len() - 1) as f64 * p).round() as usize]
+        }
+    };
+
+    println!();
+    println!("games      created {:>6}  failed {:>6}", n(&stats.games_created), n(&stats.create_failed));
+    println!("           started {:>6}  finished {:>4}", n(&stats.games_started), n(&stats.games_finished));
+    println!("clients    failed  {:>6}", n(&stats.clients_failed));
+    println!(
+        "messages   received {:>5}  ({:.0}/s)  server errors {}",
+        n(&stats.messages_received),
+        n(&stats.messages_received) as f64 / duration.as_secs_f64(),
+        n(&stats.server_errors)
+    );
+    println!("shots      fired {:>8}  answered {}", n(&stats.shots_fired), lat.len());
+    println!(
+        "latency    p50 {:?}  p90 {:?}  p99 {:?}  max {:?}",
+        pct(0.5),
+        pct(0.9),
+        pct(0.99),
+        lat.last().copied().unwrap_or_default()
+    );
+}
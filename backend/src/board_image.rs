@@ -0,0 +1,171 @@
+//! Server-rendered boards for sharing results (`GET /api/game/:id/board.png`
+//! and `board.svg`).
+//!
+//! Once a game has been played out, both fleets are drawn in full. While a
+//! game with the referee feed on is in combat, the boards are drawn the way a
+//! spectator would see them: shots only, no unhit ships. Anything else (lobbies,
+//! placement, private games in progress) has no image. Co-op games have one
+//! board, the hidden fleet; armada games have each player's home and forward
+//! boards.
+//!
+//! The PNG has no text; the SVG labels each board with whose fleet it is.
+
+use crate::protocol::GameStatus;
+use crate::state::Game;
+use battle_cp_core::{CellState, Grid, GRID_SIZE};
+
+/// Pixels per cell side, grid line included.
+const CELL: u32 = 32;
+/// Border around the whole image and gap between boards.
+const PAD: u32 = 16;
+/// Room above each board for its label (SVG only).
+const LABEL: u32 = 24;
+
+const BACKGROUND: [u8; 3] = [0x0b, 0x12, 0x20];
+const GRID_LINE: [u8; 3] = [0x1e, 0x3a, 0x5f];
+
+/// One board to draw.
+#[derive(Debug, Clone)]
+pub struct Board {
+    pub label: String,
+    pub grid: Grid,
+    /// Draw unhit ships and shields (otherwise they look like open water)
+    pub reveal_ships: bool,
+}
+
+/// What `visible_boards` allows to be drawn.
+#[derive(Debug, Clone)]
+pub struct Boards {
+    pub boards: Vec<Board>,
+    /// The game is over, so the image will never change
+    pub is_final: bool,
+}
+
+/// The boards anyone may see right now, or `None` when the game doesn't
+/// have a shareable image (yet).
+pub fn visible_boards(game: &Game) -> Option<Boards> {
+    let is_final = match game.status {
+        // Cancelled lobbies never got to play
+        GameStatus::Finished if game.game_started_at.is_some() => true,
+        GameStatus::Playing | GameStatus::SuddenDeath if game.config.referee_feed => false,
+        _ => return None,
+    };
+    let board = |label: String, grid: &Grid| Board { label, grid: grid.clone(), reveal_ships: is_final };
+    let boards = match &game.coop_board {
+        Some(fleet) => vec![board("Hidden fleet".to_string(), &fleet.grid)],
+        None => std::iter::once(&game.player1)
+            .chain(game.player2.as_ref())
+            .flat_map(|p| {
+                std::iter::once(board(format!("{}'s fleet", p.cf_handle), &p.home.grid))
+                    .chain(p.forward.as_ref().map(|f| board(format!("{}'s forward fleet", p.cf_handle), &f.grid)))
+            })
+            .collect(),
+    };
+    Some(Boards { boards, is_final })
+}
+
+fn cell_color(board: &Board, x: usize, y: usize) -> [u8; 3] {
+    match board.grid.cell(x, y) {
+        CellState::Hit => [0xef, 0x44, 0x44],
+        CellState::Miss => [0xcb, 0xd5, 0xe1],
+        CellState::Ship if board.reveal_ships => [0x64, 0x74, 0x8b],
+        CellState::Shielded if board.reveal_ships => [0x22, 0xd3, 0xee],
+        CellState::Empty | CellState::Ship | CellState::Shielded => [0x0f, 0x27, 0x42],
+    }
+}
+
+/// Width and height of an image holding `n` boards side by side, plus the
+/// offset of each board's top edge.
+fn layout(n: usize, label: u32) -> (u32, u32, u32) {
+    let side = CELL * GRID_SIZE as u32 + 1;
+    let n = n.max(1) as u32;
+    (PAD + n * (side + PAD), label + side + 2 * PAD, PAD + label)
+}
+
+/// Left edge of board `i`.
+fn board_left(i: usize) -> u32 {
+    PAD + i as u32 * (CELL * GRID_SIZE as u32 + 1 + PAD)
+}
+
+/// 8-bit RGB PNG of the boards side by side.
+pub fn render_png(boards: &[Board]) -> Vec<u8> {
+    let (width, height, top) = layout(boards.len(), 0);
+    let mut pixels: Vec<u8> = BACKGROUND.repeat((width * height) as usize);
+    let mut fill = |x0: u32, y0: u32, w: u32, h: u32, rgb: [u8; 3]| {
+        for y in y0..y0 + h {
+            let row = (y * width) as usize * 3;
+            for x in x0..x0 + w {
+                pixels[row + x as usize * 3..][..3].copy_from_slice(&rgb);
+            }
+        }
+    };
+
+    for (i, board) in boards.iter().enumerate() {
+        let left = board_left(i);
+        let side = CELL * GRID_SIZE as u32 + 1;
+        fill(left, top, side, side, GRID_LINE);
+        for y in 0..GRID_SIZE {
+            for x in 0..GRID_SIZE {
+                let (px, py) = (left + 1 + x as u32 * CELL, top + 1 + y as u32 * CELL);
+                fill(px, py, CELL - 1, CELL - 1, cell_color(board, x, y));
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder
+        .write_header()
+        .and_then(|mut w| w.write_image_data(&pixels))
+        .expect("in-memory PNG encoding cannot fail for a well-sized buffer");
+    out
+}
+
+fn hex(rgb: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", rgb[0], rgb[1], rgb[2])
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&apos;")
+}
+
+/// SVG of the boards side by side, each labelled.
+pub fn render_svg(boards: &[Board]) -> String {
+    let (width, height, top) = layout(boards.len(), LABEL);
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\
+         <rect width=\"{w}\" height=\"{h}\" fill=\"{bg}\"/>",
+        w = width,
+        h = height,
+        bg = hex(BACKGROUND),
+    );
+    let side = CELL * GRID_SIZE as u32 + 1;
+    for (i, board) in boards.iter().enumerate() {
+        let left = board_left(i);
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" fill=\"#e2e8f0\" font-family=\"sans-serif\" font-size=\"16\">{}</text>\
+             <rect x=\"{}\" y=\"{}\" width=\"{side}\" height=\"{side}\" fill=\"{}\"/>",
+            left,
+            PAD + LABEL - 8,
+            escape_xml(&board.label),
+            left,
+            top,
+            hex(GRID_LINE),
+        ));
+        for y in 0..GRID_SIZE {
+            for x in 0..GRID_SIZE {
+                svg.push_str(&format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{c}\" height=\"{c}\" fill=\"{}\"/>",
+                    left + 1 + x as u32 * CELL,
+                    top + 1 + y as u32 * CELL,
+                    hex(cell_color(board, x, y)),
+                    c = CELL - 1,
+                ));
+            }
+        }
+    }
+    svg.push_str("</svg>");
+    svg
+}
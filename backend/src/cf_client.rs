@@ -1,18 +1,28 @@
 use rand::seq::SliceRandom;
+use rand::Rng;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::error::Error;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::sync::{mpsc, oneshot, watch, Mutex};
 
+use crate::config::CodeforcesConfig;
+use crate::error::JudgeError;
 use crate::state::DifficultyMode;
 
-/// Minimum interval between any two Codeforces API requests.
-/// CF's public limit is ~1 request per 2 seconds; violating it causes 429 / temporary bans.
-/// 2.1s adds a 100ms buffer against clock-drift edge cases.
-const CF_RATE_LIMIT_INTERVAL: Duration = Duration::from_millis(2100);
+/// Whether `contest_id` / `index` look like a real Codeforces problem
+/// (e.g. 1950 / "C", 1872 / "F2"). Checked before anything client-supplied
+/// reaches the CF queue or a URL.
+pub fn is_valid_problem_ref(contest_id: i32, index: &str) -> bool {
+    let mut chars = index.chars();
+    let letter = chars.next().is_some_and(|c| c.is_ascii_uppercase());
+    let digit = match chars.next() {
+        None => true,
+        Some(c) => c.is_ascii_digit() && chars.next().is_none(),
+    };
+    (1..=999_999).contains(&contest_id) && letter && digit
+}
 
 // ---------------------------------------------------------------------------
 // Cache type aliases
@@ -21,6 +31,9 @@ const CF_RATE_LIMIT_INTERVAL: Duration = Duration::from_millis(2100);
 /// Type alias for the contest-problem cache (keyed by contest_id)
 type ProblemCache = Arc<Mutex<HashMap<i32, (std::time::Instant, Vec<ContestProblem>)>>>;
 
+/// Type alias for the whole-problemset cache (one entry)
+type ProblemsetCache = Arc<Mutex<Option<(Instant, Arc<Vec<ContestProblem>>)>>>;
+
 // ---------------------------------------------------------------------------
 // Static problem database – loaded once at startup from embedded JSON
 // ---------------------------------------------------------------------------
@@ -92,6 +105,7 @@ impl ProblemDb {
         }
 
         tracing::info!(
+            target: "cf",
             "ProblemDb loaded: {} problems, {} rating levels, {} bands",
             problems.len(),
             by_rating.len(),
@@ -130,6 +144,11 @@ pub struct CFClient {
     client: Client,
     /// Cache for contest.standings results (used only by /api/contest/:id endpoint)
     contest_cache: ProblemCache,
+    /// Cache for the whole problemset.problems list (post-game training plans)
+    problemset_cache: ProblemsetCache,
+    contest_cache_ttl: Duration,
+    /// Minimum gap between CF API requests (see `CfApiQueue::spawn_with`)
+    request_interval: Duration,
     /// Static problem database (shared across all clones via Arc)
     problem_db: Arc<ProblemDb>,
 }
@@ -162,6 +181,19 @@ struct UserStatusResponse {
     result: Vec<Submission>,
 }
 
+#[derive(Debug, Deserialize)]
+struct UserInfoResponse {
+    status: String,
+    #[serde(default)]
+    result: Vec<UserInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UserInfo {
+    /// Absent for unrated users
+    rating: Option<i32>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Submission {
     pub verdict: Option<String>,
@@ -171,6 +203,17 @@ pub struct Submission {
     pub creation_time_seconds: Option<i64>,
 }
 
+/// What a handle's recent submissions say about an assigned problem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Accepted after the lock
+    Accepted,
+    /// Wrong answer or time limit after the lock: a genuine attempt
+    Attempted,
+    /// Nothing on this problem since the lock
+    Nothing,
+}
+
 /// Minimal problem shape used only within user.status responses.
 #[derive(Debug, Deserialize)]
 pub struct SubmissionProblem {
@@ -187,12 +230,19 @@ impl Default for CFClient {
 
 impl CFClient {
     pub fn new() -> Self {
+        Self::from_config(&CodeforcesConfig::default())
+    }
+
+    pub fn from_config(config: &CodeforcesConfig) -> Self {
         Self {
             client: Client::builder()
-                .timeout(Duration::from_secs(15))
+                .timeout(config.request_timeout())
                 .build()
                 .unwrap_or_else(|_| Client::new()),
             contest_cache: Arc::new(Mutex::new(HashMap::new())),
+            problemset_cache: Arc::new(Mutex::new(None)),
+            contest_cache_ttl: config.contest_cache_ttl(),
+            request_interval: config.request_interval(),
             problem_db: Arc::new(ProblemDb::new()),
         }
     }
@@ -204,12 +254,12 @@ impl CFClient {
     pub async fn fetch_contest_problems(
         &self,
         contest_id: i32,
-    ) -> Result<Vec<ContestProblem>, Box<dyn Error + Send + Sync>> {
-        // 1. Check Cache (5 min TTL)
+    ) -> Result<Vec<ContestProblem>, JudgeError> {
+        // 1. Check Cache (5 min TTL by default)
         {
             let cache = self.contest_cache.lock().await;
             if let Some((timestamp, problems)) = cache.get(&contest_id) {
-                if timestamp.elapsed() < Duration::from_secs(300) {
+                if timestamp.elapsed() < self.contest_cache_ttl {
                     return Ok(problems.clone());
                 }
             }
@@ -222,17 +272,19 @@ impl CFClient {
         );
         // Simple sleep for this rarely-hit endpoint (5-min cache covers normal use).
         // Not routed through the queue because it returns a different type.
-        tokio::time::sleep(CF_RATE_LIMIT_INTERVAL).await;
+        tokio::time::sleep(self.request_interval).await;
         let resp = self
             .client
             .get(&url)
             .send()
-            .await?
+            .await
+            .map_err(JudgeError::Http)?
             .json::<ContestStandingsResponse>()
-            .await?;
+            .await
+            .map_err(JudgeError::Parse)?;
 
         if resp.status != "OK" {
-            return Err("Failed to fetch contest problems".into());
+            return Err(JudgeError::ApiStatus(resp.status));
         }
 
         let problems = resp.result.problems;
@@ -246,6 +298,35 @@ impl CFClient {
         Ok(problems)
     }
 
+    /// Every problem on Codeforces, with tags and ratings (used only by
+    /// `GET /api/game/:id/analysis`). Cached like contest problems.
+    pub async fn fetch_problemset(&self) -> Result<Arc<Vec<ContestProblem>>, JudgeError> {
+        if let Some((timestamp, problems)) = self.problemset_cache.lock().await.as_ref() {
+            if timestamp.elapsed() < self.contest_cache_ttl {
+                return Ok(problems.clone());
+            }
+        }
+
+        // Same envelope as contest.standings: result.problems (problemStatistics ignored)
+        tokio::time::sleep(self.request_interval).await;
+        let resp = self
+            .client
+            .get("https://codeforces.com/api/problemset.problems")
+            .send()
+            .await
+            .map_err(JudgeError::Http)?
+            .json::<ContestStandingsResponse>()
+            .await
+            .map_err(JudgeError::Parse)?;
+        if resp.status != "OK" {
+            return Err(JudgeError::ApiStatus(resp.status));
+        }
+
+        let problems = Arc::new(resp.result.problems);
+        *self.problemset_cache.lock().await = Some((Instant::now(), problems.clone()));
+        Ok(problems)
+    }
+
     // -----------------------------------------------------------------------
     // Problem selection  (in-memory – zero CF API calls for the pool lookup)
     // -----------------------------------------------------------------------
@@ -262,14 +343,16 @@ impl CFClient {
         difficulty: u32,
         mode: DifficultyMode,
         solved_set: &HashSet<String>,
-    ) -> Result<StaticProblem, Box<dyn Error + Send + Sync>> {
+        rng: &mut impl Rng,
+    ) -> Result<StaticProblem, JudgeError> {
         // 1. Try the exact target difficulty
-        if let Some(p) = self.try_pick_unsolved(difficulty, &mode, solved_set) {
+        if let Some(p) = self.try_pick_unsolved(difficulty, &mode, solved_set, rng) {
             return Ok(p);
         }
 
         // 2. All problems at this level are solved — try adjacent levels
         tracing::info!(
+            target: "cf",
             "All unsolved problems exhausted at difficulty={} mode={:?} — trying adjacent levels",
             difficulty, mode
         );
@@ -282,8 +365,8 @@ impl CFClient {
                     for &dir in &[1, -1] {
                         let cand = difficulty as i32 + offset * dir;
                         if (0..=4).contains(&cand) {
-                            if let Some(p) = self.try_pick_unsolved(cand as u32, &mode, solved_set) {
-                                tracing::info!("Fallback: serving band {} instead of {}", cand, difficulty);
+                            if let Some(p) = self.try_pick_unsolved(cand as u32, &mode, solved_set, rng) {
+                                tracing::info!(target: "cf", "Fallback: serving band {} instead of {}", cand, difficulty);
                                 found = Some(p);
                                 break;
                             }
@@ -300,8 +383,8 @@ impl CFClient {
                     for &dir in &[1, -1] {
                         let cand = difficulty as i32 + offset * 100 * dir;
                         if (800..=3500).contains(&cand) {
-                            if let Some(p) = self.try_pick_unsolved(cand as u32, &mode, solved_set) {
-                                tracing::info!("Fallback: serving rating {} instead of {}", cand, difficulty);
+                            if let Some(p) = self.try_pick_unsolved(cand as u32, &mode, solved_set, rng) {
+                                tracing::info!(target: "cf", "Fallback: serving rating {} instead of {}", cand, difficulty);
                                 found = Some(p);
                                 break;
                             }
@@ -319,21 +402,14 @@ impl CFClient {
 
         // 3. Every single problem in the DB is solved — reuse target pool
         tracing::warn!(
+            target: "cf",
             "Player has solved ALL problems in the database — reusing target pool at difficulty={}",
             difficulty
         );
         let pool = self.problem_db.pool(difficulty, &mode);
-        if pool.is_empty() {
-            return Err(format!(
-                "No problems in database for difficulty={} mode={:?}",
-                difficulty, mode
-            )
-            .into());
-        }
-        let mut rng = rand::thread_rng();
-        pool.choose(&mut rng)
+        pool.choose(rng)
             .map(|p| (*p).clone())
-            .ok_or_else(|| "No problems available".into())
+            .ok_or(JudgeError::NoProblems { difficulty, mode })
     }
 
     /// Build a shared problem queue for both players.
@@ -350,10 +426,12 @@ impl CFClient {
         p1_solved: &HashSet<String>,
         p2_solved: &HashSet<String>,
         count: usize,
+        rng: &mut impl Rng,
     ) -> Vec<StaticProblem> {
         let pool = self.problem_db.pool(difficulty, mode);
         if pool.is_empty() {
             tracing::warn!(
+                target: "cf",
                 "build_shared_queue: empty pool for difficulty={} mode={:?}",
                 difficulty, mode
             );
@@ -372,9 +450,8 @@ impl CFClient {
             }
         }
 
-        let mut rng = rand::thread_rng();
-        unsolved.shuffle(&mut rng);
-        solved.shuffle(&mut rng);
+        unsolved.shuffle(rng);
+        solved.shuffle(rng);
 
         let mut queue: Vec<StaticProblem> = Vec::with_capacity(count);
 
@@ -389,6 +466,7 @@ impl CFClient {
         }
 
         tracing::info!(
+            target: "cf",
             "Built shared queue: {} problems ({} unsolved + {} filler) for difficulty={} mode={:?}",
             queue.len(),
             take_unsolved,
@@ -407,6 +485,7 @@ impl CFClient {
         difficulty: u32,
         mode: &DifficultyMode,
         solved_set: &HashSet<String>,
+        rng: &mut impl Rng,
     ) -> Option<StaticProblem> {
         let pool = self.problem_db.pool(difficulty, mode);
         if pool.is_empty() {
@@ -420,8 +499,7 @@ impl CFClient {
         if unsolved.is_empty() {
             return None;
         }
-        let mut rng = rand::thread_rng();
-        unsolved.choose(&mut rng).map(|p| (*p).clone())
+        unsolved.choose(rng).map(|p| (*p).clone())
     }
 }
 
@@ -433,14 +511,18 @@ impl CFClient {
 enum CfApiRequest {
     FetchSolvedSet {
         handle: String,
-        resp: oneshot::Sender<Result<HashSet<String>, String>>,
+        resp: oneshot::Sender<Result<HashSet<String>, JudgeError>>,
     },
     VerifySubmission {
         handle: String,
         contest_id: i32,
         index: String,
         locked_since_unix: Option<u64>,
-        resp: oneshot::Sender<Result<bool, String>>,
+        resp: oneshot::Sender<Result<Verdict, JudgeError>>,
+    },
+    FetchRating {
+        handle: String,
+        resp: oneshot::Sender<Result<Option<u32>, JudgeError>>,
     },
 }
 
@@ -448,34 +530,55 @@ enum CfApiRequest {
 /// shares the same underlying worker via the mpsc channels.
 #[derive(Clone)]
 pub struct CfApiQueue {
-    /// High-priority channel (verify_submission — player is actively waiting)
+    /// High-priority channel (verify_submission, fetch_rating — player is actively waiting)
     high_tx: mpsc::Sender<CfApiRequest>,
     /// Low-priority channel (fetch_solved_set — pre-fetch / game-start)
     low_tx: mpsc::Sender<CfApiRequest>,
+    /// The worker's circuit breaker: true while CF looks down (see `Breaker`)
+    outage: watch::Receiver<bool>,
 }
 
 impl CfApiQueue {
-    /// Spawn the queue worker and return a handle.
+    /// Spawn the queue worker with the default CF settings and return a handle.
     /// Must be called inside a Tokio runtime.
     pub fn spawn() -> Self {
+        Self::spawn_with(&CodeforcesConfig::default())
+    }
+
+    /// Spawn the queue worker. `config.request_interval_ms` is the minimum gap
+    /// between any two CF API requests: CF's public limit is ~1 request per 2
+    /// seconds and violating it causes 429 / temporary bans, so the default
+    /// 2.1s adds a 100ms buffer against clock-drift edge cases.
+    pub fn spawn_with(config: &CodeforcesConfig) -> Self {
         let (high_tx, high_rx) = mpsc::channel::<CfApiRequest>(256);
         let (low_tx, low_rx) = mpsc::channel::<CfApiRequest>(256);
+        let (outage_tx, outage) = watch::channel(false);
         let client = Client::builder()
-            .timeout(Duration::from_secs(15))
+            .timeout(config.request_timeout())
             .build()
             .unwrap_or_else(|_| Client::new());
 
-        tokio::spawn(cf_queue_worker(high_rx, low_rx, client));
-        tracing::info!("CF API queue worker spawned (high + low priority channels)");
+        tokio::spawn(crate::reporting::in_task(
+            "cf_queue",
+            None,
+            cf_queue_worker(high_rx, low_rx, client, config.request_interval(), Breaker::new(outage_tx)),
+        ));
+        tracing::info!(target: "cf", "CF API queue worker spawned (high + low priority channels)");
+
+        Self { high_tx, low_tx, outage }
+    }
 
-        Self { high_tx, low_tx }
+    /// Whether the circuit breaker is open: the last `OUTAGE_AFTER_FAILURES`
+    /// requests got no usable answer from CF.
+    pub fn is_down(&self) -> bool {
+        *self.outage.borrow()
     }
 
     /// Queue a solved-set fetch (low priority).
     /// The caller should retry on `Err` — the queue worker does NOT retry
     /// internally, so a transient CF failure returns Err immediately and the
     /// request slot is freed for the next caller.
-    pub async fn fetch_solved_set(&self, handle: &str) -> Result<HashSet<String>, String> {
+    pub async fn fetch_solved_set(&self, handle: &str) -> Result<HashSet<String>, JudgeError> {
         let (tx, rx) = oneshot::channel();
         self.low_tx
             .send(CfApiRequest::FetchSolvedSet {
@@ -483,8 +586,8 @@ impl CfApiQueue {
                 resp: tx,
             })
             .await
-            .map_err(|_| "CF queue closed".to_string())?;
-        rx.await.map_err(|_| "CF worker dropped request".to_string())?
+            .map_err(|_| JudgeError::QueueClosed)?;
+        rx.await.map_err(|_| JudgeError::WorkerDropped)?
     }
 
     /// Queue a submission verification (high priority — jumps ahead of prefetches).
@@ -494,7 +597,7 @@ impl CfApiQueue {
         contest_id: i32,
         index: &str,
         locked_since_unix: Option<u64>,
-    ) -> Result<bool, String> {
+    ) -> Result<Verdict, JudgeError> {
         let (tx, rx) = oneshot::channel();
         self.high_tx
             .send(CfApiRequest::VerifySubmission {
@@ -505,8 +608,22 @@ impl CfApiQueue {
                 resp: tx,
             })
             .await
-            .map_err(|_| "CF queue closed".to_string())?;
-        rx.await.map_err(|_| "CF worker dropped request".to_string())?
+            .map_err(|_| JudgeError::QueueClosed)?;
+        rx.await.map_err(|_| JudgeError::WorkerDropped)?
+    }
+
+    /// Queue a `user.info` lookup (high priority — a guest is waiting to join a
+    /// rating-gated lobby). `None` for an unrated handle.
+    pub async fn fetch_rating(&self, handle: &str) -> Result<Option<u32>, JudgeError> {
+        let (tx, rx) = oneshot::channel();
+        self.high_tx
+            .send(CfApiRequest::FetchRating {
+                handle: handle.to_string(),
+                resp: tx,
+            })
+            .await
+            .map_err(|_| JudgeError::QueueClosed)?;
+        rx.await.map_err(|_| JudgeError::WorkerDropped)?
     }
 }
 
@@ -518,12 +635,15 @@ impl CfApiQueue {
 ///   won't be stuck behind prefetch requests from a tournament start.
 /// - No caching — each game needs a fresh solved set because the player
 ///   may have solved new problems since the last fetch.
+/// - Every result feeds `breaker`, so games can tell when CF is down.
 async fn cf_queue_worker(
     mut high_rx: mpsc::Receiver<CfApiRequest>,
     mut low_rx: mpsc::Receiver<CfApiRequest>,
     client: Client,
+    interval: Duration,
+    mut breaker: Breaker,
 ) {
-    let mut last_request_at = Instant::now() - CF_RATE_LIMIT_INTERVAL;
+    let mut last_request_at = Instant::now() - interval;
 
     loop {
         // Wait for next request — high priority (verify) always served first.
@@ -535,35 +655,83 @@ async fn cf_queue_worker(
 
         match req {
             CfApiRequest::FetchSolvedSet { handle, resp } => {
-                enforce_rate_limit(&mut last_request_at).await;
-                match do_fetch_solved(&client, &handle).await {
+                enforce_rate_limit(&mut last_request_at, interval).await;
+                let result = do_fetch_solved(&client, &handle).await;
+                breaker.record(&result);
+                match result {
                     Ok(set) => { let _ = resp.send(Ok(set)); }
                     Err(e) => {
-                        tracing::warn!("CF queue: fetch_solved('{}') failed: {}", handle, e);
+                        tracing::warn!(target: "cf", "CF queue: fetch_solved('{}') failed: {}", handle, e);
                         let _ = resp.send(Err(e));
                     }
                 }
             }
             CfApiRequest::VerifySubmission { handle, contest_id, index, locked_since_unix, resp } => {
-                enforce_rate_limit(&mut last_request_at).await;
-                match do_verify_submission(&client, &handle, contest_id, &index, locked_since_unix).await {
-                    Ok(accepted) => { let _ = resp.send(Ok(accepted)); }
+                enforce_rate_limit(&mut last_request_at, interval).await;
+                let result = do_verify_submission(&client, &handle, contest_id, &index, locked_since_unix).await;
+                breaker.record(&result);
+                match result {
+                    Ok(verdict) => { let _ = resp.send(Ok(verdict)); }
                     Err(e) => {
-                        tracing::warn!("CF queue: verify('{}', {}-{}) failed: {}", handle, contest_id, index, e);
+                        tracing::warn!(target: "cf", "CF queue: verify('{}', {}-{}) failed: {}", handle, contest_id, index, e);
                         let _ = resp.send(Err(e));
                     }
                 }
             }
+            CfApiRequest::FetchRating { handle, resp } => {
+                enforce_rate_limit(&mut last_request_at, interval).await;
+                let result = do_fetch_rating(&client, &handle).await;
+                breaker.record(&result);
+                if let Err(e) = &result {
+                    tracing::warn!(target: "cf", "CF queue: fetch_rating('{}') failed: {}", handle, e);
+                }
+                let _ = resp.send(result);
+            }
+        }
+    }
+    tracing::warn!(target: "cf", "CF API queue worker exiting — all senders dropped");
+}
+
+/// Requests in a row that got no answer from CF (a network error, or a body
+/// that isn't the API's JSON, e.g. a 502 page) before it counts as down.
+const OUTAGE_AFTER_FAILURES: u32 = 3;
+
+/// Circuit breaker over the worker's requests: opens after
+/// `OUTAGE_AFTER_FAILURES` failures in a row and closes on the next request CF
+/// answers, even with an error status (e.g. an unknown handle). Requests keep
+/// going through while it's open; it only reports the outage.
+struct Breaker {
+    failures: u32,
+    open: watch::Sender<bool>,
+}
+
+impl Breaker {
+    fn new(open: watch::Sender<bool>) -> Self {
+        Self { failures: 0, open }
+    }
+
+    fn record<T>(&mut self, result: &Result<T, JudgeError>) {
+        match result {
+            Err(JudgeError::Http(_) | JudgeError::Parse(_)) => self.failures += 1,
+            _ => self.failures = 0,
+        }
+        let open = self.failures >= OUTAGE_AFTER_FAILURES;
+        if *self.open.borrow() != open {
+            if open {
+                tracing::error!(target: "cf", "CF unreachable for {} requests in a row, circuit breaker open", self.failures);
+            } else {
+                tracing::info!(target: "cf", "CF answering again, circuit breaker closed");
+            }
+            self.open.send_replace(open);
         }
     }
-    tracing::warn!("CF API queue worker exiting — all senders dropped");
 }
 
 /// Sleep until at least `CF_RATE_LIMIT_INTERVAL` has passed since `last`.
-async fn enforce_rate_limit(last: &mut Instant) {
+async fn enforce_rate_limit(last: &mut Instant, interval: Duration) {
     let elapsed = last.elapsed();
-    if elapsed < CF_RATE_LIMIT_INTERVAL {
-        tokio::time::sleep(CF_RATE_LIMIT_INTERVAL - elapsed).await;
+    if elapsed < interval {
+        tokio::time::sleep(interval - elapsed).await;
     }
     *last = Instant::now();
 }
@@ -573,18 +741,16 @@ async fn enforce_rate_limit(last: &mut Instant) {
 async fn do_fetch_solved(
     client: &Client,
     handle: &str,
-) -> Result<HashSet<String>, String> {
+) -> Result<HashSet<String>, JudgeError> {
     let encoded = urlencoding::encode(handle);
     let url = format!(
         "https://codeforces.com/api/user.status?handle={}&from=1&count=5000",
         encoded
     );
-    let http_resp = client.get(&url).send().await
-        .map_err(|e| format!("HTTP error: {e}"))?;
-    let resp: UserStatusResponse = http_resp.json().await
-        .map_err(|e| format!("JSON parse error: {e}"))?;
+    let http_resp = client.get(&url).send().await.map_err(JudgeError::Http)?;
+    let resp: UserStatusResponse = http_resp.json().await.map_err(JudgeError::Parse)?;
     if resp.status != "OK" {
-        return Err(format!("CF API returned status={}", resp.status));
+        return Err(JudgeError::ApiStatus(resp.status));
     }
     let mut solved = HashSet::new();
     for sub in resp.result {
@@ -597,44 +763,61 @@ async fn do_fetch_solved(
     Ok(solved)
 }
 
+/// `handle`'s current rating from `user.info`; `None` if they're unrated.
+/// An unknown handle is an `ApiStatus` error.
+async fn do_fetch_rating(client: &Client, handle: &str) -> Result<Option<u32>, JudgeError> {
+    let url = format!(
+        "https://codeforces.com/api/user.info?handles={}",
+        urlencoding::encode(handle)
+    );
+    let http_resp = client.get(&url).send().await.map_err(JudgeError::Http)?;
+    let resp: UserInfoResponse = http_resp.json().await.map_err(JudgeError::Parse)?;
+    if resp.status != "OK" {
+        return Err(JudgeError::ApiStatus(resp.status));
+    }
+    Ok(resp
+        .result
+        .first()
+        .and_then(|u| u.rating)
+        .map(|r| r.max(0) as u32))
+}
+
 /// Verify whether `handle` has an AC for `contest_id`/`index`
-/// created AFTER `locked_since_unix` (with 30 s tolerance).
+/// created AFTER `locked_since_unix` (with 30 s tolerance), or failing that
+/// a wrong answer / time limit there (a genuine attempt).
 async fn do_verify_submission(
     client: &Client,
     handle: &str,
     contest_id: i32,
     index: &str,
     locked_since_unix: Option<u64>,
-) -> Result<bool, String> {
+) -> Result<Verdict, JudgeError> {
     let encoded = urlencoding::encode(handle);
     let url = format!(
         "https://codeforces.com/api/user.status?handle={}&from=1&count=5",
         encoded
     );
-    let http_resp = client.get(&url).send().await
-        .map_err(|e| format!("HTTP error: {e}"))?;
-    let resp: UserStatusResponse = http_resp.json().await
-        .map_err(|e| format!("JSON parse error: {e}"))?;
+    let http_resp = client.get(&url).send().await.map_err(JudgeError::Http)?;
+    let resp: UserStatusResponse = http_resp.json().await.map_err(JudgeError::Parse)?;
     if resp.status != "OK" {
-        return Err(format!("CF API returned status={}", resp.status));
+        return Err(JudgeError::ApiStatus(resp.status));
     }
+    let mut attempted = false;
     for submission in resp.result {
-        if let Some(verdict) = submission.verdict {
-            if verdict == "OK"
-                && submission.problem.contest_id == Some(contest_id)
-                && submission.problem.index == index
-            {
-                // SECURITY: reject pre-solved submissions (30 s clock-skew tolerance)
-                if let Some(lock_time) = locked_since_unix {
-                    if let Some(creation_time) = submission.creation_time_seconds {
-                        if (creation_time as u64) + 30 < lock_time {
-                            continue;
-                        }
-                    }
-                }
-                return Ok(true);
+        if submission.problem.contest_id != Some(contest_id) || submission.problem.index != index {
+            continue;
+        }
+        // SECURITY: reject pre-solved submissions (30 s clock-skew tolerance)
+        if let (Some(lock_time), Some(creation_time)) = (locked_since_unix, submission.creation_time_seconds) {
+            if (creation_time as u64) + 30 < lock_time {
+                continue;
             }
         }
+        match submission.verdict.as_deref() {
+            Some("OK") => return Ok(Verdict::Accepted),
+            Some("WRONG_ANSWER" | "TIME_LIMIT_EXCEEDED") => attempted = true,
+            _ => {}
+        }
     }
-    Ok(false)
+    Ok(if attempted { Verdict::Attempted } else { Verdict::Nothing })
 }
@@ -0,0 +1,583 @@
+//! Server configuration.
+//!
+//! Settings are layered: built-in defaults, then a TOML file, then environment
+//! variables. The file is `CONFIG_FILE` if set, else `battlecp.toml` in the
+//! working directory when it exists (see `battlecp.example.toml`). Every env
+//! var the server has always read still works and wins over the file, so
+//! existing deployments keep their `.env`.
+//!
+//! `Config::load` validates the result, and `main` logs the effective config
+//! (secrets redacted) at startup. Code without an `AppState` at hand (the
+//! broadcast buffer, webhook senders, proxy-header trust) reads the installed
+//! config through `config::global()`.
+
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+pub use crate::error::ConfigError;
+
+/// Used when `CONFIG_FILE` is unset, if present.
+const DEFAULT_CONFIG_FILE: &str = "battlecp.toml";
+
+/// CF's public limit is ~1 request per 2 seconds; going faster gets the server banned.
+const MIN_CF_REQUEST_INTERVAL_MS: u64 = 2000;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub log: LogConfig,
+    pub server: ServerConfig,
+    pub cors: CorsConfig,
+    pub game: GameSettings,
+    pub cleanup: CleanupConfig,
+    pub codeforces: CodeforcesConfig,
+    pub judges: JudgesConfig,
+    pub limits: LimitsConfig,
+    pub auth: AuthConfig,
+    pub admin: AdminConfig,
+    pub moderation: ModerationConfig,
+    pub webhooks: WebhookConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// `RUST_LOG`-style directives; adjustable at runtime (see `logging.rs`)
+    pub filter: String,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            filter: crate::logging::DEFAULT_FILTER.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub bind_addr: IpAddr,
+    pub port: u16,
+    /// PEM certificate chain; with `tls_key_path`, the server terminates TLS itself
+    pub tls_cert_path: Option<PathBuf>,
+    pub tls_key_path: Option<PathBuf>,
+    /// Trust `X-Real-IP` / `X-Forwarded-For` (only behind nginx)
+    pub trust_proxy_headers: bool,
+    /// Public frontend origin for join links; relative paths if unset
+    pub frontend_url: Option<String>,
+    /// Public origin of this API, for links Discord fetches itself (board images)
+    pub public_url: Option<String>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            bind_addr: Ipv4Addr::UNSPECIFIED.into(),
+            port: 3000,
+            tls_cert_path: None,
+            tls_key_path: None,
+            trust_proxy_headers: false,
+            frontend_url: None,
+            public_url: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// Exact origins, `https://*.domain` wildcards or `regex:` patterns
+    pub allowed_origins: Vec<String>,
+    /// Accept every origin. Local development only.
+    pub allow_any: bool,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: [
+                "http://localhost:3000",
+                "http://localhost:3001",
+                "http://127.0.0.1:3000",
+                "http://127.0.0.1:3001",
+            ]
+            .map(String::from)
+            .to_vec(),
+            allow_any: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameSettings {
+    /// How often each game actor checks whether `GameUpdate` frames are due
+    pub tick_interval_ms: u64,
+    /// How often the global ticker sweeps expired games and limiter entries
+    pub sweep_interval_ms: u64,
+    /// Per-game broadcast buffer (events); lagging connections get a full resync
+    pub broadcast_capacity: usize,
+    /// How long after a disconnect a resume token still replays missed events
+    pub resume_window_secs: u64,
+    /// How often joined sockets are sent a `Ping` to measure latency; 0 disables
+    pub ping_interval_ms: u64,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        Self {
+            tick_interval_ms: 1000,
+            sweep_interval_ms: 1000,
+            broadcast_capacity: 2000,
+            resume_window_secs: 120,
+            ping_interval_ms: 5000,
+        }
+    }
+}
+
+impl GameSettings {
+    pub fn tick_interval(&self) -> Duration {
+        Duration::from_millis(self.tick_interval_ms)
+    }
+
+    pub fn sweep_interval(&self) -> Duration {
+        Duration::from_millis(self.sweep_interval_ms)
+    }
+
+    pub fn resume_window(&self) -> Duration {
+        Duration::from_secs(self.resume_window_secs)
+    }
+
+    pub fn ping_interval(&self) -> Option<Duration> {
+        (self.ping_interval_ms > 0).then(|| Duration::from_millis(self.ping_interval_ms))
+    }
+}
+
+/// Retention windows, in seconds (see `background::CleanupPolicy`), and the
+/// archive's size.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CleanupConfig {
+    pub finished_secs: u64,
+    pub waiting_secs: u64,
+    pub placing_secs: u64,
+    pub abandoned_secs: u64,
+    /// Read-only retention after `finished_secs` (0 drops finished games then)
+    pub archived_secs: u64,
+    /// Most games kept in the archive; the oldest goes first
+    pub archived_max_games: usize,
+}
+
+impl Default for CleanupConfig {
+    fn default() -> Self {
+        Self {
+            finished_secs: 300,
+            waiting_secs: 1800,
+            placing_secs: 1800,
+            abandoned_secs: 300,
+            archived_secs: 86400,
+            archived_max_games: 1000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CodeforcesConfig {
+    /// Minimum gap between two CF API requests (2100 = CF's limit plus a buffer)
+    pub request_interval_ms: u64,
+    pub request_timeout_secs: u64,
+    /// How long `/api/contest/:id` problem lists are cached
+    pub contest_cache_secs: u64,
+}
+
+impl Default for CodeforcesConfig {
+    fn default() -> Self {
+        Self {
+            request_interval_ms: 2100,
+            request_timeout_secs: 15,
+            contest_cache_secs: 300,
+        }
+    }
+}
+
+impl CodeforcesConfig {
+    pub fn request_interval(&self) -> Duration {
+        Duration::from_millis(self.request_interval_ms)
+    }
+
+    pub fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.request_timeout_secs)
+    }
+
+    pub fn contest_cache_ttl(&self) -> Duration {
+        Duration::from_secs(self.contest_cache_secs)
+    }
+}
+
+/// Built-in plugin judges to register (see `judge.rs`). Codeforces is always on.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JudgesConfig {
+    /// CSES problemset tasks, verified by scraping cses.fi (see `cses.rs`)
+    pub cses: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    pub max_active_games: usize,
+    pub max_lobbies_per_ip: usize,
+    /// Rooms held in memory (see `rooms.rs`)
+    pub max_rooms: usize,
+    pub create_game_per_min: u32,
+    pub contest_per_min: u32,
+    pub verify_per_game_per_min: u32,
+    pub verify_per_ip_per_min: u32,
+    /// Bots spend from their own budget instead of their IP's
+    pub verify_per_bot_per_min: u32,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_active_games: 1000,
+            max_lobbies_per_ip: 5,
+            max_rooms: 500,
+            create_game_per_min: 10,
+            contest_per_min: 30,
+            verify_per_game_per_min: 8,
+            verify_per_ip_per_min: 12,
+            verify_per_bot_per_min: 12,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+    /// Signs session tokens; a random per-process key if unset
+    pub jwt_secret: Option<String>,
+    pub jwt_ttl_secs: u64,
+    /// Default lifetime of bot tokens (`POST /api/admin/bots`)
+    pub bot_token_ttl_secs: u64,
+    /// Longest lifetime a bot token may be issued with
+    pub bot_token_max_ttl_secs: u64,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            jwt_secret: None,
+            jwt_ttl_secs: 6 * 60 * 60,
+            bot_token_ttl_secs: 90 * 24 * 60 * 60,
+            bot_token_max_ttl_secs: 365 * 24 * 60 * 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminConfig {
+    /// `key:scope` entries (scope `read` or `manage`)
+    pub api_keys: Vec<String>,
+    /// File with one `key:scope` per line
+    pub keys_file: Option<PathBuf>,
+    /// Legacy single key, granted the manage scope
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModerationConfig {
+    pub banned_handles: Vec<String>,
+    pub banned_cidrs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhookConfig {
+    /// Match reports
+    pub discord_url: Option<String>,
+    /// Anti-cheat flags
+    pub anticheat_url: Option<String>,
+    /// Panics and significant errors (see `reporting.rs`)
+    pub error_url: Option<String>,
+    /// Let per-game webhooks (`webhooks.rs`) call plain `http://` URLs
+    pub game_callbacks_allow_http: bool,
+    /// Let per-game webhooks call loopback, private and link-local addresses
+    pub game_callbacks_allow_private: bool,
+}
+
+impl Config {
+    /// Defaults, then the config file, then env overrides; validated.
+    pub fn load() -> Result<Self, ConfigError> {
+        let (path, file) = read_config_file()?;
+        let config = Self::from_sources(file.as_deref(), |var| std::env::var(var).ok())?;
+        if let Some(path) = path {
+            tracing::info!("Loaded config file {}", path.display());
+        }
+        Ok(config)
+    }
+
+    /// Defaults and the config file only, ignoring env overrides (for SIGHUP
+    /// reloads, where the file is what the operator just edited).
+    pub fn load_file() -> Result<Self, ConfigError> {
+        let (_, file) = read_config_file()?;
+        Self::from_sources(file.as_deref(), |_| None)
+    }
+
+    /// Layer `toml` (if any) and then `env` over the defaults, and validate.
+    pub fn from_sources(
+        toml: Option<&str>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigError> {
+        let mut config: Config = match toml {
+            Some(text) => toml::from_str(text).map_err(|e| ConfigError::Parse(e.to_string()))?,
+            None => Self::default(),
+        };
+        config.apply_env(env)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn apply_env(&mut self, env: impl Fn(&str) -> Option<String>) -> Result<(), ConfigError> {
+        let env = |var: &str| env(var).filter(|v| !v.trim().is_empty());
+        let mut e = EnvOverrides { env: &env };
+
+        e.parse("RUST_LOG", &mut self.log.filter)?;
+
+        let s = &mut self.server;
+        e.parse("BIND_ADDR", &mut s.bind_addr)?;
+        e.parse("PORT", &mut s.port)?;
+        e.some("TLS_CERT_PATH", &mut s.tls_cert_path)?;
+        e.some("TLS_KEY_PATH", &mut s.tls_key_path)?;
+        e.flag("TRUST_PROXY_HEADERS", &mut s.trust_proxy_headers)?;
+        e.some("FRONTEND_URL", &mut s.frontend_url)?;
+        e.some("PUBLIC_URL", &mut s.public_url)?;
+
+        e.list("ALLOWED_ORIGINS", &mut self.cors.allowed_origins);
+        e.flag("CORS_ALLOW_ANY", &mut self.cors.allow_any)?;
+
+        let g = &mut self.game;
+        e.parse("TICK_INTERVAL_MS", &mut g.tick_interval_ms)?;
+        e.parse("SWEEP_INTERVAL_MS", &mut g.sweep_interval_ms)?;
+        e.parse("BROADCAST_CAPACITY", &mut g.broadcast_capacity)?;
+        e.parse("RESUME_WINDOW_SECS", &mut g.resume_window_secs)?;
+        e.parse("PING_INTERVAL_MS", &mut g.ping_interval_ms)?;
+
+        let c = &mut self.cleanup;
+        e.parse("FINISHED_GAME_TTL_SECS", &mut c.finished_secs)?;
+        e.parse("WAITING_GAME_TTL_SECS", &mut c.waiting_secs)?;
+        e.parse("PLACING_GAME_TTL_SECS", &mut c.placing_secs)?;
+        e.parse("ABANDONED_GAME_TTL_SECS", &mut c.abandoned_secs)?;
+        e.parse("ARCHIVED_GAME_TTL_SECS", &mut c.archived_secs)?;
+        e.parse("ARCHIVED_GAME_MAX", &mut c.archived_max_games)?;
+
+        let cf = &mut self.codeforces;
+        e.parse("CF_REQUEST_INTERVAL_MS", &mut cf.request_interval_ms)?;
+        e.parse("CF_REQUEST_TIMEOUT_SECS", &mut cf.request_timeout_secs)?;
+        e.parse("CF_CONTEST_CACHE_SECS", &mut cf.contest_cache_secs)?;
+
+        e.flag("JUDGE_CSES", &mut self.judges.cses)?;
+
+        let l = &mut self.limits;
+        e.parse("MAX_ACTIVE_GAMES", &mut l.max_active_games)?;
+        e.parse("MAX_LOBBIES_PER_IP", &mut l.max_lobbies_per_ip)?;
+        e.parse("MAX_ROOMS", &mut l.max_rooms)?;
+        e.parse("RATE_LIMIT_CREATE_PER_MIN", &mut l.create_game_per_min)?;
+        e.parse("RATE_LIMIT_CONTEST_PER_MIN", &mut l.contest_per_min)?;
+        e.parse("VERIFY_PER_GAME_PER_MIN", &mut l.verify_per_game_per_min)?;
+        e.parse("VERIFY_PER_IP_PER_MIN", &mut l.verify_per_ip_per_min)?;
+        e.parse("VERIFY_PER_BOT_PER_MIN", &mut l.verify_per_bot_per_min)?;
+
+        e.some("JWT_SECRET", &mut self.auth.jwt_secret)?;
+        e.parse("JWT_TTL_SECS", &mut self.auth.jwt_ttl_secs)?;
+        e.parse("BOT_TOKEN_TTL_SECS", &mut self.auth.bot_token_ttl_secs)?;
+        e.parse("BOT_TOKEN_MAX_TTL_SECS", &mut self.auth.bot_token_max_ttl_secs)?;
+
+        e.list("ADMIN_API_KEYS", &mut self.admin.api_keys);
+        e.some("ADMIN_KEYS_FILE", &mut self.admin.keys_file)?;
+        e.some("ADMIN_TOKEN", &mut self.admin.token)?;
+
+        e.list("BANNED_HANDLES", &mut self.moderation.banned_handles);
+        e.list("BANNED_CIDRS", &mut self.moderation.banned_cidrs);
+
+        e.some("DISCORD_WEBHOOK_URL", &mut self.webhooks.discord_url)?;
+        e.some("ANTICHEAT_WEBHOOK_URL", &mut self.webhooks.anticheat_url)?;
+        e.some("ERROR_REPORT_URL", &mut self.webhooks.error_url)?;
+        e.parse("GAME_WEBHOOKS_ALLOW_HTTP", &mut self.webhooks.game_callbacks_allow_http)?;
+        e.parse("GAME_WEBHOOKS_ALLOW_PRIVATE", &mut self.webhooks.game_callbacks_allow_private)?;
+        Ok(())
+    }
+
+    /// Reject settings the server can't run with.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |msg: String| Err(ConfigError::Invalid(msg));
+        if let Err(e) = crate::logging::parse(&self.log.filter) {
+            return invalid(format!("log.filter: {}", e));
+        }
+        let s = &self.server;
+        if s.tls_cert_path.is_some() != s.tls_key_path.is_some() {
+            return invalid("server.tls_cert_path and server.tls_key_path must be set together".into());
+        }
+        if let Err(e) = crate::cors::AllowedOrigins::parse(&self.cors.allowed_origins.join(",")) {
+            return invalid(format!("cors.allowed_origins: {}", e));
+        }
+        for cidr in &self.moderation.banned_cidrs {
+            if let Err(e) = cidr.parse::<crate::moderation::Cidr>() {
+                return invalid(format!("moderation.banned_cidrs: {}", e));
+            }
+        }
+
+        let positive = [
+            ("game.tick_interval_ms", self.game.tick_interval_ms),
+            ("game.sweep_interval_ms", self.game.sweep_interval_ms),
+            ("game.broadcast_capacity", self.game.broadcast_capacity as u64),
+            ("cleanup.finished_secs", self.cleanup.finished_secs),
+            ("cleanup.waiting_secs", self.cleanup.waiting_secs),
+            ("cleanup.placing_secs", self.cleanup.placing_secs),
+            ("cleanup.abandoned_secs", self.cleanup.abandoned_secs),
+            ("codeforces.request_timeout_secs", self.codeforces.request_timeout_secs),
+            ("limits.max_active_games", self.limits.max_active_games as u64),
+            ("limits.max_lobbies_per_ip", self.limits.max_lobbies_per_ip as u64),
+            ("limits.max_rooms", self.limits.max_rooms as u64),
+            ("limits.create_game_per_min", self.limits.create_game_per_min.into()),
+            ("limits.contest_per_min", self.limits.contest_per_min.into()),
+            ("limits.verify_per_game_per_min", self.limits.verify_per_game_per_min.into()),
+            ("limits.verify_per_ip_per_min", self.limits.verify_per_ip_per_min.into()),
+            ("limits.verify_per_bot_per_min", self.limits.verify_per_bot_per_min.into()),
+            ("auth.jwt_ttl_secs", self.auth.jwt_ttl_secs),
+            ("auth.bot_token_ttl_secs", self.auth.bot_token_ttl_secs),
+            ("auth.bot_token_max_ttl_secs", self.auth.bot_token_max_ttl_secs),
+        ];
+        if let Some((name, _)) = positive.iter().find(|(_, v)| *v == 0) {
+            return invalid(format!("{} must be greater than 0", name));
+        }
+        if self.auth.bot_token_ttl_secs > self.auth.bot_token_max_ttl_secs {
+            return invalid("auth.bot_token_ttl_secs must not exceed auth.bot_token_max_ttl_secs".into());
+        }
+        if self.codeforces.request_interval_ms < MIN_CF_REQUEST_INTERVAL_MS {
+            return invalid(format!(
+                "codeforces.request_interval_ms must be at least {} (CF rate limit)",
+                MIN_CF_REQUEST_INTERVAL_MS
+            ));
+        }
+        Ok(())
+    }
+
+    /// The effective config as TOML, with secrets replaced by `"<redacted>"`.
+    pub fn summary(&self) -> String {
+        let mut shown = self.clone();
+        let redact = |s: &mut Option<String>| {
+            if s.is_some() {
+                *s = Some("<redacted>".into());
+            }
+        };
+        redact(&mut shown.auth.jwt_secret);
+        redact(&mut shown.admin.token);
+        redact(&mut shown.webhooks.discord_url);
+        redact(&mut shown.webhooks.anticheat_url);
+        redact(&mut shown.webhooks.error_url);
+        for key in &mut shown.admin.api_keys {
+            let scope = key.rsplit_once(':').map(|(_, s)| s.to_string()).unwrap_or_default();
+            *key = format!("<redacted>:{}", scope);
+        }
+        toml::to_string_pretty(&shown).unwrap_or_else(|e| format!("<unprintable config: {}>", e))
+    }
+}
+
+/// The config file's path and contents, if there is one.
+fn read_config_file() -> Result<(Option<PathBuf>, Option<String>), ConfigError> {
+    let path = std::env::var("CONFIG_FILE")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .map(PathBuf::from)
+        .or_else(|| Some(PathBuf::from(DEFAULT_CONFIG_FILE)).filter(|p| p.exists()));
+    let file = match &path {
+        Some(path) => Some(std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.clone(),
+            source,
+        })?),
+        None => None,
+    };
+    Ok((path, file))
+}
+
+/// Applies `VAR` overrides onto config fields.
+struct EnvOverrides<'a> {
+    env: &'a dyn Fn(&str) -> Option<String>,
+}
+
+impl EnvOverrides<'_> {
+    fn parse<T: std::str::FromStr>(&mut self, var: &'static str, slot: &mut T) -> Result<(), ConfigError> {
+        if let Some(value) = (self.env)(var) {
+            *slot = value
+                .trim()
+                .parse()
+                .map_err(|_| ConfigError::Env { var, value })?;
+        }
+        Ok(())
+    }
+
+    fn some<T: std::str::FromStr>(&mut self, var: &'static str, slot: &mut Option<T>) -> Result<(), ConfigError> {
+        if let Some(value) = (self.env)(var) {
+            *slot = Some(
+                value
+                    .trim()
+                    .parse()
+                    .map_err(|_| ConfigError::Env { var, value })?,
+            );
+        }
+        Ok(())
+    }
+
+    /// `true`/`1` or `false`/`0`
+    fn flag(&mut self, var: &'static str, slot: &mut bool) -> Result<(), ConfigError> {
+        if let Some(value) = (self.env)(var) {
+            *slot = match value.trim() {
+                "true" | "1" => true,
+                "false" | "0" => false,
+                _ => return Err(ConfigError::Env { var, value }),
+            };
+        }
+        Ok(())
+    }
+
+    /// Comma-separated
+    fn list(&mut self, var: &'static str, slot: &mut Vec<String>) {
+        if let Some(value) = (self.env)(var) {
+            *slot = value
+                .split(',')
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(String::from)
+                .collect();
+        }
+    }
+}
+
+static GLOBAL: OnceLock<Config> = OnceLock::new();
+
+/// Make `config` the process-wide config. Call once at startup, before
+/// building the `AppState`; later calls are ignored.
+pub fn install(config: Config) {
+    if GLOBAL.set(config).is_err() {
+        tracing::warn!("Config already installed; keeping the first one");
+    }
+}
+
+/// The installed config. Without `install` (tests, benches) it is loaded on
+/// first use, falling back to the defaults if that fails.
+pub fn global() -> &'static Config {
+    GLOBAL.get_or_init(|| {
+        Config::load().unwrap_or_else(|e| {
+            tracing::error!("Invalid configuration, using defaults: {}", e);
+            Config::default()
+        })
+    })
+}
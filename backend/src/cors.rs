@@ -0,0 +1,142 @@
+//! CORS origin policy.
+//!
+//! `cors.allowed_origins` (env `ALLOWED_ORIGINS`, comma-separated) lists the
+//! origins browsers may call from. Each entry is one of:
+//! - an exact origin: `https://battle-cp.vercel.app`
+//! - a wildcard subdomain: `https://*.vercel.app` (any one or more labels,
+//!   same scheme, so preview deployments work without editing the list)
+//! - a regex, prefixed `regex:` and matched against the whole origin:
+//!   `regex:^https://battle-cp-[a-z0-9-]+\.vercel\.app$`
+//!
+//! `cors.allow_any` (`CORS_ALLOW_ANY=true`) allows every origin. It is meant for local
+//! development only and logs a warning at startup.
+//!
+//! Browsers only let scripts read the response headers listed in
+//! `Access-Control-Expose-Headers`: the request ID, the rate-limit quota,
+//! `Retry-After` and `Idempotent-Replayed` are exposed.
+
+use axum::http::{header, HeaderValue};
+use regex::Regex;
+use tower_http::cors::{AllowOrigin, Any, CorsLayer};
+
+#[derive(Debug, Clone)]
+enum Rule {
+    Exact(String),
+    /// `scheme://*.suffix` — `prefix` is `scheme://`, `suffix` starts with `.`
+    Wildcard { prefix: String, suffix: String },
+    Regex(Regex),
+}
+
+impl Rule {
+    fn parse(entry: &str) -> Result<Self, String> {
+        if let Some(pattern) = entry.strip_prefix("regex:") {
+            let pattern = format!("^(?:{})$", pattern.trim_start_matches('^').trim_end_matches('$'));
+            return Regex::new(&pattern)
+                .map(Rule::Regex)
+                .map_err(|e| format!("invalid origin regex {:?}: {}", entry, e));
+        }
+        if let Some((prefix, suffix)) = entry.split_once("://*.") {
+            if suffix.is_empty() || suffix.contains('*') || prefix.contains('*') {
+                return Err(format!("invalid wildcard origin {:?}", entry));
+            }
+            return Ok(Rule::Wildcard {
+                prefix: format!("{}://", prefix.to_ascii_lowercase()),
+                suffix: format!(".{}", suffix.to_ascii_lowercase()),
+            });
+        }
+        if entry.contains('*') {
+            return Err(format!(
+                "wildcards are only supported as the first label, e.g. https://*.example.com (got {:?})",
+                entry
+            ));
+        }
+        Ok(Rule::Exact(entry.trim_end_matches('/').to_ascii_lowercase()))
+    }
+
+    fn matches(&self, origin: &str) -> bool {
+        match self {
+            Rule::Exact(o) => origin.eq_ignore_ascii_case(o),
+            Rule::Wildcard { prefix, suffix } => {
+                let origin = origin.to_ascii_lowercase();
+                origin
+                    .strip_prefix(prefix.as_str())
+                    .and_then(|rest| rest.strip_suffix(suffix.as_str()))
+                    .is_some_and(|sub| {
+                        !sub.is_empty()
+                            && !sub.starts_with('.')
+                            && !sub.ends_with('.')
+                            && sub.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.')
+                    })
+            }
+            Rule::Regex(re) => re.is_match(origin),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AllowedOrigins {
+    any: bool,
+    rules: Vec<Rule>,
+}
+
+impl AllowedOrigins {
+    /// Parse a comma-separated `ALLOWED_ORIGINS` value.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let rules = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|e| !e.is_empty())
+            .map(Rule::parse)
+            .collect::<Result<_, _>>()?;
+        Ok(Self { any: false, rules })
+    }
+
+    /// Every origin allowed (`CORS_ALLOW_ANY`).
+    pub fn any() -> Self {
+        Self { any: true, rules: vec![] }
+    }
+
+    /// From the `[cors]` config. Entries were checked by `Config::validate`;
+    /// any that still fail to parse are skipped with an error log.
+    pub fn from_config(config: &crate::config::CorsConfig) -> Self {
+        if config.allow_any {
+            tracing::warn!("CORS_ALLOW_ANY is set: accepting requests from ANY origin. Do not use in production.");
+            return Self::any();
+        }
+        let mut rules = Vec::new();
+        for entry in config.allowed_origins.iter().map(|e| e.trim()).filter(|e| !e.is_empty()) {
+            match Rule::parse(entry) {
+                Ok(rule) => rules.push(rule),
+                Err(e) => tracing::error!("Ignoring ALLOWED_ORIGINS entry: {}", e),
+            }
+        }
+        Self { any: false, rules }
+    }
+
+    pub fn allows(&self, origin: &str) -> bool {
+        self.any || self.rules.iter().any(|r| r.matches(origin))
+    }
+
+    /// The CORS layer for the router, exposing `expose` along with the
+    /// rate-limit and idempotency headers.
+    pub fn layer(self, expose: axum::http::HeaderName) -> CorsLayer {
+        let allow_origin = if self.any {
+            AllowOrigin::any()
+        } else {
+            AllowOrigin::predicate(move |origin: &HeaderValue, _| {
+                origin.to_str().is_ok_and(|o| self.allows(o))
+            })
+        };
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods(Any)
+            .allow_headers(Any)
+            .expose_headers(
+                [expose]
+                    .into_iter()
+                    .chain(crate::rate_limit::QUOTA_HEADERS)
+                    .chain([header::RETRY_AFTER, crate::idempotency::REPLAYED_HEADER])
+                    .collect::<Vec<_>>(),
+            )
+    }
+}
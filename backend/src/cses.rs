@@ -0,0 +1,242 @@
+//! CSES (<https://cses.fi/problemset/>) as a plugin judge: the example for
+//! `judge::JudgeClient`, registered when `judges.cses` is on.
+//!
+//! CSES has no public API, so a player's results are scraped from their
+//! user page (`/problemset/user/{id}/`), where each task is marked solved,
+//! attempted or untouched. Players enter their numeric CSES user id as their
+//! handle. Tasks are identified by their task id with an empty index.
+//!
+//! The page has no submission times, so a solve can't be checked against the
+//! lock time. The queue puts tasks neither player has solved first, so this
+//! only matters once a game has used them all up: a lock on a task the player
+//! already solved verifies at once.
+
+use crate::cf_client::Verdict;
+use crate::config::CodeforcesConfig;
+use crate::error::JudgeError;
+use crate::judge::JudgeClient;
+use crate::state::AssignedProblem;
+use futures::future::BoxFuture;
+use regex::Regex;
+use reqwest::Client;
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Minimum gap between two requests to cses.fi.
+const REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Problemset tasks with rough Codeforces-equivalent ratings: (task id, name, rating).
+const TASKS: &[(i32, &str, u32)] = &[
+    // Introductory Problems
+    (1068, "Weird Algorithm", 800),
+    (1083, "Missing Number", 800),
+    (1069, "Repetitions", 800),
+    (1094, "Increasing Array", 800),
+    (1070, "Permutations", 900),
+    (1071, "Number Spiral", 1200),
+    (1072, "Two Knights", 1400),
+    (1092, "Two Sets", 1200),
+    (1617, "Bit Strings", 900),
+    (1618, "Trailing Zeros", 1000),
+    (1754, "Coin Piles", 1200),
+    (1755, "Palindrome Reorder", 1100),
+    (2205, "Gray Code", 1300),
+    (2165, "Tower of Hanoi", 1300),
+    (1622, "Creating Strings", 1200),
+    (1623, "Apple Division", 1200),
+    (1624, "Chessboard and Queens", 1500),
+    (2431, "Digit Queries", 1700),
+    // Sorting and Searching
+    (1621, "Distinct Numbers", 800),
+    (1084, "Apartments", 1100),
+    (1090, "Ferris Wheel", 1200),
+    (1091, "Concert Tickets", 1400),
+    (1619, "Restaurant Customers", 1300),
+    (1629, "Movie Festival", 1300),
+    (1640, "Sum of Two Values", 1200),
+    (1643, "Maximum Subarray Sum", 1200),
+    (1074, "Stick Lengths", 1200),
+    (2183, "Missing Coin Sum", 1500),
+    (2216, "Collecting Numbers", 1300),
+    (1141, "Playlist", 1500),
+    (1073, "Towers", 1500),
+    (1163, "Traffic Lights", 1700),
+    (1164, "Room Allocation", 1600),
+    (1620, "Factory Machines", 1500),
+    (1630, "Tasks and Deadlines", 1300),
+    (1641, "Sum of Three Values", 1500),
+    (1642, "Sum of Four Values", 1800),
+    (1645, "Nearest Smaller Values", 1600),
+    (1660, "Subarray Sums I", 1400),
+    (1661, "Subarray Sums II", 1600),
+    (1662, "Subarray Divisibility", 1700),
+    (1085, "Array Division", 1800),
+    (1076, "Sliding Window Median", 2000),
+    (1077, "Sliding Window Cost", 2100),
+    // Dynamic Programming
+    (1633, "Dice Combinations", 1300),
+    (1634, "Minimizing Coins", 1400),
+    (1635, "Coin Combinations I", 1500),
+    (1636, "Coin Combinations II", 1600),
+    (1637, "Removing Digits", 1300),
+    (1638, "Grid Paths", 1400),
+    (1158, "Book Shop", 1600),
+    (1746, "Array Description", 1700),
+    (2413, "Counting Towers", 2000),
+    (1639, "Edit Distance", 1700),
+    (1744, "Rectangle Cutting", 1800),
+    (1745, "Money Sums", 1600),
+    (1097, "Removal Game", 1900),
+    (1093, "Two Sets II", 1800),
+    (1145, "Increasing Subsequence", 1800),
+    (1140, "Projects", 2000),
+    // Graph Algorithms
+    (1192, "Counting Rooms", 1300),
+    (1193, "Labyrinth", 1500),
+    (1666, "Building Roads", 1400),
+    (1667, "Message Route", 1500),
+    (1668, "Building Teams", 1500),
+    (1669, "Round Trip", 1700),
+    (1194, "Monsters", 1900),
+    (1671, "Shortest Routes I", 1700),
+    (1672, "Shortest Routes II", 1700),
+    (1673, "High Score", 2100),
+    (1195, "Flight Discount", 2000),
+    (1197, "Cycle Finding", 2100),
+    (1675, "Road Reparation", 1800),
+    (1676, "Road Construction", 1800),
+    (1679, "Course Schedule", 1700),
+    (1680, "Longest Flight Route", 1900),
+    (1681, "Game Routes", 1900),
+];
+
+/// How a user's results page marks a task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskScore {
+    Solved,
+    Attempted,
+}
+
+/// Every task link on a results page with a score: `<a href="/problemset/task/1068/"
+/// class="task-score icon full">` is solved, `... icon zero">` attempted.
+pub fn parse_results(html: &str) -> HashMap<i32, TaskScore> {
+    static LINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<a\s[^>]*>").unwrap());
+    static HREF: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"href="/problemset/task/(\d+)/?""#).unwrap());
+    static CLASS: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#"class="([^"]*)""#).unwrap());
+
+    let mut scores = HashMap::new();
+    for link in LINK.find_iter(html).map(|m| m.as_str()) {
+        let Some(task) = HREF.captures(link).and_then(|c| c[1].parse().ok()) else {
+            continue;
+        };
+        let classes: Vec<&str> = match CLASS.captures(link) {
+            Some(c) => c.get(1).map_or("", |m| m.as_str()).split_whitespace().collect(),
+            None => continue,
+        };
+        let score = if !classes.contains(&"task-score") {
+            continue;
+        } else if classes.contains(&"full") {
+            TaskScore::Solved
+        } else if classes.contains(&"zero") {
+            TaskScore::Attempted
+        } else {
+            continue;
+        };
+        scores.insert(task, score);
+    }
+    scores
+}
+
+pub struct Cses {
+    client: Client,
+    problems: Vec<AssignedProblem>,
+    /// When the last request went out (see `REQUEST_INTERVAL`)
+    last_request_at: Mutex<Instant>,
+}
+
+impl Cses {
+    /// Uses the Codeforces request timeout for cses.fi too.
+    pub fn from_config(config: &CodeforcesConfig) -> Self {
+        let client = Client::builder()
+            .timeout(config.request_timeout())
+            .build()
+            .unwrap_or_else(|_| Client::new());
+        let problems = TASKS
+            .iter()
+            .map(|&(task, name, rating)| AssignedProblem {
+                contest_id: task,
+                index: String::new(),
+                name: name.to_string(),
+                rating,
+                url: Some(format!("https://cses.fi/problemset/task/{}", task)),
+            })
+            .collect();
+        Self { client, problems, last_request_at: Mutex::new(Instant::now() - REQUEST_INTERVAL) }
+    }
+
+    /// Scores from `user_id`'s results page.
+    async fn fetch_results(&self, user_id: &str) -> Result<HashMap<i32, TaskScore>, JudgeError> {
+        {
+            let mut last = self.last_request_at.lock().await;
+            let elapsed = last.elapsed();
+            if elapsed < REQUEST_INTERVAL {
+                tokio::time::sleep(REQUEST_INTERVAL - elapsed).await;
+            }
+            *last = Instant::now();
+        }
+        let url = format!("https://cses.fi/problemset/user/{}/", urlencoding::encode(user_id));
+        let resp = self.client.get(&url).send().await.map_err(JudgeError::Http)?;
+        if !resp.status().is_success() {
+            return Err(JudgeError::ApiStatus(format!("CSES HTTP {}", resp.status().as_u16())));
+        }
+        let html = resp.text().await.map_err(JudgeError::Http)?;
+        Ok(parse_results(&html))
+    }
+}
+
+impl JudgeClient for Cses {
+    fn id(&self) -> &str {
+        "cses"
+    }
+
+    fn name(&self) -> &str {
+        "CSES"
+    }
+
+    fn problems(&self) -> Option<&[AssignedProblem]> {
+        Some(&self.problems)
+    }
+
+    fn is_valid_problem_ref(&self, contest_id: i32, index: &str) -> bool {
+        (1000..=9999).contains(&contest_id) && index.is_empty()
+    }
+
+    fn solved_set<'a>(&'a self, handle: &'a str) -> BoxFuture<'a, Result<HashSet<String>, JudgeError>> {
+        Box::pin(async move {
+            let results = self.fetch_results(handle).await?;
+            Ok(results
+                .into_iter()
+                .filter(|&(_, score)| score == TaskScore::Solved)
+                .map(|(task, _)| format!("{}-", task))
+                .collect())
+        })
+    }
+
+    fn verify<'a>(
+        &'a self,
+        handle: &'a str,
+        problem: &'a AssignedProblem,
+        _since_unix: Option<u64>,
+    ) -> BoxFuture<'a, Result<Verdict, JudgeError>> {
+        Box::pin(async move {
+            let results = self.fetch_results(handle).await?;
+            Ok(match results.get(&problem.contest_id) {
+                Some(TaskScore::Solved) => Verdict::Accepted,
+                Some(TaskScore::Attempted) => Verdict::Attempted,
+                None => Verdict::Nothing,
+            })
+        })
+    }
+}
@@ -1,7 +1,19 @@
-//! Discord webhook integration for post-game match logging.
+//! Discord webhook integration for the match lifecycle.
 //! Set the `DISCORD_WEBHOOK_URL` environment variable to enable.
-
-use crate::state::{DifficultyMode, Game};
+//!
+//! Each game gets a `Lifecycle` follower subscribed to the same broadcast
+//! channel as its sockets. It posts:
+//! - the lobby link when a public game is created (`public: true`)
+//! - a ping to the host (`discord_id` at creation) when an opponent joins —
+//!   for public lobbies the post goes out even without an ID
+//! - the match report with both players' stats on `GameOver`, for every game
+//!   that reached combat
+
+use crate::state::{DifficultyMode, Game, GameConfig, HintCost, SuddenDeathMode};
+use crate::config::ServerConfig;
+use crate::actor::WeakGameHandle;
+use crate::protocol::{ServerMessage, StampedMessage};
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
 /// Snapshot of all data needed for the Discord match report.
@@ -32,7 +44,7 @@ pub struct MatchReport {
     pub difficulty: u32,
     pub difficulty_mode: DifficultyMode,
     pub heat_threshold: u32,
-    pub veto_penalties: [u64; 3],
+    pub veto_penalties: Vec<u64>,
     pub max_vetoes: u32,
     pub game_duration_secs: u64,
 }
@@ -70,7 +82,7 @@ impl MatchReport {
             p1_handle: game.player1.cf_handle.clone(),
             p1_score,
             p1_ships_sunk: game.player1.stats.ships_sunk,
-            p1_ships_lost: game.player1.ships.iter().filter(|s| s.sunk).count() as u32,
+            p1_ships_lost: game.player1.fleet().iter().filter(|s| s.sunk).count() as u32,
             p1_problems_solved: game.player1.stats.problems_solved,
             p1_cells_hit: game.player1.stats.cells_hit,
             p1_cells_missed: game.player1.stats.cells_missed,
@@ -81,7 +93,7 @@ impl MatchReport {
             p2_score,
             p2_ships_sunk: p2.map(|p| p.stats.ships_sunk).unwrap_or(0),
             p2_ships_lost: p2
-                .map(|p| p.ships.iter().filter(|s| s.sunk).count() as u32)
+                .map(|p| p.fleet().iter().filter(|s| s.sunk).count() as u32)
                 .unwrap_or(0),
             p2_problems_solved: p2.map(|p| p.stats.problems_solved).unwrap_or(0),
             p2_cells_hit: p2.map(|p| p.stats.cells_hit).unwrap_or(0),
@@ -90,7 +102,7 @@ impl MatchReport {
             difficulty: game.config.difficulty,
             difficulty_mode: game.config.difficulty_mode.clone(),
             heat_threshold: game.config.heat_threshold,
-            veto_penalties: game.config.veto_penalties,
+            veto_penalties: game.config.veto_penalties.clone(),
             max_vetoes: game.config.max_vetoes,
             game_duration_secs: game.config.game_duration_secs,
         }
@@ -99,7 +111,7 @@ impl MatchReport {
 
 // ── Helper formatters ──────────────────────────────────────────────────────────
 
-fn fmt_duration(secs: u64) -> String {
+pub(crate) fn fmt_duration(secs: u64) -> String {
     if secs == 0 {
         return "0s".to_string();
     }
@@ -115,7 +127,7 @@ fn fmt_duration(secs: u64) -> String {
     }
 }
 
-fn accuracy(hit: u32, miss: u32) -> String {
+pub(crate) fn accuracy(hit: u32, miss: u32) -> String {
     let total = hit + miss;
     if total == 0 {
         return "—".to_string();
@@ -123,8 +135,8 @@ fn accuracy(hit: u32, miss: u32) -> String {
     format!("{:.1}%", hit as f64 / total as f64 * 100.0)
 }
 
-fn penalty_time(vetoes_used: u32, penalties: &[u64; 3]) -> u64 {
-    (0..vetoes_used.min(3) as usize).map(|i| penalties[i]).sum()
+fn penalty_time(vetoes_used: u32, config: &GameConfig) -> u64 {
+    (0..vetoes_used).filter_map(|i| config.veto_penalty(i)).sum()
 }
 
 fn band_name(band: u32) -> &'static str {
@@ -138,6 +150,32 @@ fn band_name(band: u32) -> &'static str {
     }
 }
 
+/// One-line summary of a game's settings, for lobby and match embeds (and
+/// Markdown match reports).
+pub(crate) fn settings_line(config: &GameConfig) -> String {
+    let difficulty_str = match config.difficulty_mode {
+        DifficultyMode::Band => format!(
+            "Band — {} (tier {})",
+            band_name(config.difficulty),
+            config.difficulty
+        ),
+        DifficultyMode::Cf => format!("CF Rating — {}", config.difficulty),
+    };
+    let weapons_str = match config.ammo {
+        Some(ammo) => format!("**Ammo:** {} (+{} per solve)", ammo.starting, ammo.per_solve),
+        None => format!("**Overheat:** {} shots", config.heat_threshold),
+    };
+    format!(
+        "**Mode:** {}   **|**   **Time Limit:** {}   **|**   {}   **|**   **Max Vetoes:** {}   **|**   **Penalties:** {}",
+        difficulty_str,
+        fmt_duration(config.game_duration_secs),
+        weapons_str,
+        config.max_vetoes,
+        config.veto_penalties.iter().map(|&p| fmt_duration(p)).collect::<Vec<_>>().join(" / "),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
 fn stats_block(
     ships_sunk: u32,
     ships_lost: u32,
@@ -217,7 +255,12 @@ fn build_embed(rep: &MatchReport) -> serde_json::Value {
         ("🏆  WINNER", "💀  DEFEATED")
     };
 
-    let outcome_desc = if is_draw {
+    let outcome_desc = if rep.reason == "CoopVictory" {
+        format!(
+            "🤝  **{}** and **{}** sank the hidden fleet",
+            rep.p1_handle, rep.p2_handle
+        )
+    } else if is_draw {
         format!("🤝  **DRAW** · `{}`", rep.reason)
     } else if p1_wins {
         format!(
@@ -231,31 +274,44 @@ fn build_embed(rep: &MatchReport) -> serde_json::Value {
         )
     };
 
-    let difficulty_str = match rep.difficulty_mode {
-        DifficultyMode::Band => format!(
-            "Band — {} (tier {})",
-            band_name(rep.difficulty),
-            rep.difficulty
-        ),
-        DifficultyMode::Cf => format!("CF Rating — {}", rep.difficulty),
+    let config = GameConfig {
+        difficulty: rep.difficulty,
+        difficulty_mode: rep.difficulty_mode.clone(),
+        heat_threshold: rep.heat_threshold,
+        heat_ramp: vec![],
+        ammo: None,
+        sudden_death: SuddenDeathMode::FirstHit,
+        veto_penalties: rep.veto_penalties.clone(),
+        max_vetoes: rep.max_vetoes,
+        game_duration_secs: rep.game_duration_secs,
+        public: false,
+        scheduled_start: None,
+        fast_solve_secs: None,
+        hide_sunk_info: false,
+        hint_cost: HintCost::Off,
+        referee_feed: false,
+        activity_indicator: false,
+        show_latency: false,
+        pause_on_outage: false,
+        heat_relief_secs: None,
+        idle_timeout_secs: None,
+        anti_snipe_secs: None,
+        anti_snipe_regulation: false,
+        armada: false,
+        coop: false,
+        seed: None,
+        judge: crate::judge::CODEFORCES.to_string(),
+        reveal_problems: false,
+        min_rating: None,
+        max_rating: None,
     };
-
-    let config_str = format!(
-        "**Mode:** {}   **|**   **Time Limit:** {}   **|**   **Overheat:** {} shots   **|**   **Max Vetoes:** {}   **|**   **Penalties:** {}/{}/{} min",
-        difficulty_str,
-        fmt_duration(rep.game_duration_secs),
-        rep.heat_threshold,
-        rep.max_vetoes,
-        rep.veto_penalties[0] / 60,
-        rep.veto_penalties[1] / 60,
-        rep.veto_penalties[2] / 60,
-    );
+    let config_str = settings_line(&config);
 
     // Green if decisive winner, gray if draw/timeout-no-winner
     let color: u32 = if rep.winner_id.is_some() { 5_763_719 } else { 10_066_613 };
 
-    let lft_pen = penalty_time(lft_vet, &rep.veto_penalties);
-    let rgt_pen = penalty_time(rgt_vet, &rep.veto_penalties);
+    let lft_pen = penalty_time(lft_vet, &config);
+    let rgt_pen = penalty_time(rgt_vet, &config);
 
     serde_json::json!({
         "title": "⚔️  BATTLE CP — MATCH REPORT",
@@ -310,7 +366,148 @@ fn build_embed(rep: &MatchReport) -> serde_json::Value {
     })
 }
 
-// ── Public API ─────────────────────────────────────────────────────────────────
+
+fn lobby_embed(host: &str, config: &GameConfig, join: &str) -> serde_json::Value {
+    let mut fields = vec![
+        serde_json::json!({ "name": "⚙️  Game Configuration", "value": settings_line(config), "inline": false }),
+    ];
+    if let Some(range) = config.rating_range() {
+        fields.push(serde_json::json!({ "name": "🎯  Rating", "value": range, "inline": true }));
+    }
+    serde_json::json!({
+        "title": "⚓  BATTLE CP — OPEN LOBBY",
+        "description": format!("> **{}** is looking for an opponent\n> {}", host, join),
+        "color": 3_447_003,
+        "fields": fields,
+        "footer": { "text": "Battle CP  ·  Lobbies  ·  oGhostyyy" }
+    })
+}
+
+// ── Lifecycle follower ─────────────────────────────────────────────────────────
+
+/// Follows one game's broadcasts and queues its Discord posts.
+pub struct Lifecycle {
+    rx: broadcast::Receiver<StampedMessage>,
+    game_id: Uuid,
+    host_id: Uuid,
+    host_handle: String,
+    host_discord_id: Option<String>,
+    config: GameConfig,
+    /// Join link (absolute if `server.frontend_url` is set, else the game code)
+    join: String,
+    /// Final-board image for the match report (needs `server.public_url`)
+    board_url: Option<String>,
+}
+
+impl Lifecycle {
+    /// Subscribe to a game that is about to start. `None` when Discord
+    /// posting is off, so no follower task runs.
+    pub fn watch(game: &Game, server: &ServerConfig) -> Option<Self> {
+        QUEUE_TX.get()?;
+        webhook_url()?;
+        let join = match server.frontend_url.as_deref() {
+            Some(base) => format!("{}/game/{}", base.trim_end_matches('/'), game.id),
+            None => format!("Game code: `{}`", game.id),
+        };
+        Some(Self {
+            rx: game.tx.subscribe(),
+            game_id: game.id,
+            host_id: game.player1.id,
+            host_handle: game.player1.cf_handle.clone(),
+            host_discord_id: game.host_discord_id.clone(),
+            config: game.config.clone(),
+            join,
+            board_url: server
+                .public_url
+                .as_deref()
+                .map(|base| format!("{}/api/game/{}/board.png", base.trim_end_matches('/'), game.id)),
+        })
+    }
+
+    /// Announce the lobby (if public) and follow the game until it ends or
+    /// is removed.
+    pub fn spawn(self, handle: WeakGameHandle) {
+        let game_id = self.game_id;
+        tokio::spawn(crate::reporting::in_task("discord_lifecycle", Some(game_id), self.run(handle)));
+    }
+
+    async fn run(mut self, handle: WeakGameHandle) {
+        if self.config.public {
+            enqueue(Post {
+                body: serde_json::json!({
+                    "username": "Battle CP",
+                    "embeds": [lobby_embed(&self.host_handle, &self.config, &self.join)]
+                }),
+                what: format!("lobby announcement for game {}", self.game_id),
+            });
+        }
+        loop {
+            match self.rx.recv().await {
+                Ok(StampedMessage { message: ServerMessage::PlayerJoined { player_id }, .. }) if player_id != self.host_id => {
+                    self.opponent_joined(&handle, player_id).await;
+                }
+                Ok(StampedMessage { message: ServerMessage::GameOver { winner_id, reason, .. }, .. }) => {
+                    let report = handle
+                        .call(move |game| {
+                            // Cancelled lobbies never got to play
+                            game.game_started_at?;
+                            Some(MatchReport::from_game(game, winner_id, reason))
+                        })
+                        .await
+                        .flatten();
+                    if let Some(report) = report {
+                        let mut embed = build_embed(&report);
+                        if let Some(url) = &self.board_url {
+                            embed["image"] = serde_json::json!({ "url": url });
+                        }
+                        enqueue(Post {
+                            body: serde_json::json!({
+                                "username": "Battle CP",
+                                "embeds": [embed]
+                            }),
+                            what: format!("match report for game {}", report.game_id),
+                        });
+                    }
+                    return;
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!(target: "game", "Discord follower for game {:?} lagged by {} messages", self.game_id, n);
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            }
+        }
+    }
+
+    async fn opponent_joined(&self, handle: &WeakGameHandle, player_id: Uuid) {
+        let Some(Some(guest)) = handle
+            .call(move |game| game.player(player_id).map(|p| p.cf_handle.clone()))
+            .await
+        else {
+            return;
+        };
+        let (mention, allowed) = match &self.host_discord_id {
+            Some(id) => (format!("<@{}> ", id), vec![id.clone()]),
+            // Private lobby with nobody to ping: nothing worth posting
+            None if !self.config.public => return,
+            None => (String::new(), vec![]),
+        };
+        enqueue(Post {
+            body: serde_json::json!({
+                "username": "Battle CP",
+                "content": format!(
+                    "{}⚔️ **{}** joined **{}**'s lobby — place your ships! {}",
+                    mention, guest, self.host_handle, self.join
+                ),
+                // Only ever ping the host, whatever the handles contain
+                "allowed_mentions": { "parse": [], "users": allowed },
+            }),
+            what: format!("join ping for game {}", self.game_id),
+        });
+    }
+}
+
+// ── Delivery ───────────────────────────────────────────────────────────────────
 
 /// Shared HTTP client — avoids creating a new client per request.
 static CLIENT: std::sync::OnceLock<reqwest::Client> = std::sync::OnceLock::new();
@@ -324,56 +521,59 @@ fn get_client() -> &'static reqwest::Client {
     })
 }
 
+fn webhook_url() -> Option<&'static str> {
+    crate::config::global()
+        .webhooks
+        .discord_url
+        .as_deref()
+        .filter(|u| !u.is_empty())
+}
+
+/// A webhook body plus what it is, for logs.
+struct Post {
+    body: serde_json::Value,
+    what: String,
+}
+
 /// Bounded channel for serializing webhook POSTs (prevents burst flooding Discord).
-static QUEUE_TX: std::sync::OnceLock<tokio::sync::mpsc::Sender<MatchReport>> =
-    std::sync::OnceLock::new();
+static QUEUE_TX: std::sync::OnceLock<tokio::sync::mpsc::Sender<Post>> = std::sync::OnceLock::new();
 
 /// Initialize the webhook worker. Call once at startup.
 pub fn init_worker() {
-    let (tx, rx) = tokio::sync::mpsc::channel::<MatchReport>(64);
+    let (tx, rx) = tokio::sync::mpsc::channel::<Post>(64);
     QUEUE_TX.set(tx).ok();
+    if webhook_url().is_none() {
+        tracing::info!("DISCORD_WEBHOOK_URL not set or empty — Discord announcements disabled");
+    }
     tracing::info!("Discord webhook worker initialized");
-    tokio::spawn(webhook_worker(rx));
+    tokio::spawn(crate::reporting::in_task("discord_worker", None, webhook_worker(rx)));
 }
 
-/// Background worker: drains the queue and POSTs one report at a time.
-async fn webhook_worker(mut rx: tokio::sync::mpsc::Receiver<MatchReport>) {
-    while let Some(report) = rx.recv().await {
-        post_report_with_retry(&report).await;
+/// Background worker: drains the queue and POSTs one message at a time.
+async fn webhook_worker(mut rx: tokio::sync::mpsc::Receiver<Post>) {
+    while let Some(post) = rx.recv().await {
+        post_with_retry(&post).await;
     }
 }
 
-/// Fire-and-forget: extract match data and enqueue for the webhook worker.
-/// Safe to call while holding the games write-lock — enqueue is non-blocking.
-pub fn log_game(game: &Game, winner_id: Option<Uuid>, reason: &str) {
-    let report = MatchReport::from_game(game, winner_id, reason.to_string());
+/// Non-blocking: drop the post if the queue is full (unlikely at 64).
+fn enqueue(post: Post) {
     if let Some(tx) = QUEUE_TX.get() {
-        // try_send: don't block the game loop; drop report if queue full (unlikely at 64)
-        if tx.try_send(report).is_err() {
-            tracing::warn!("Discord webhook queue full — dropping match report");
+        if tx.try_send(post).is_err() {
+            tracing::warn!("Discord webhook queue full — dropping a post");
         }
     }
 }
 
 /// POST with up to 3 retries, respecting Discord 429 Retry-After header.
-async fn post_report_with_retry(report: &MatchReport) {
-    let url = match std::env::var("DISCORD_WEBHOOK_URL") {
-        Ok(u) if !u.is_empty() => u,
-        _ => {
-            tracing::info!("DISCORD_WEBHOOK_URL not set or empty — Discord match reporting disabled");
-            return;
-        }
+async fn post_with_retry(post: &Post) {
+    let Some(url) = webhook_url() else {
+        return;
     };
 
-    let embed = build_embed(report);
-    let body = serde_json::json!({
-        "username": "Battle CP",
-        "embeds": [embed]
-    });
-
     let client = get_client();
     for attempt in 0..3u32 {
-        match client.post(&url).json(&body).send().await {
+        match client.post(url).json(&post.body).send().await {
             Ok(resp) => {
                 if resp.status().is_success() {
                     return; // Done
@@ -410,5 +610,8 @@ async fn post_report_with_retry(report: &MatchReport) {
         let backoff = std::time::Duration::from_millis(500 * 2u64.pow(attempt));
         tokio::time::sleep(backoff).await;
     }
-    tracing::error!("Discord webhook failed after 3 retries — match report lost");
+    crate::reporting::report_error(
+        None,
+        format!("Discord webhook failed after 3 retries — {} lost", post.what),
+    );
 }
@@ -0,0 +1,336 @@
+//! Typed errors for game rules, the Codeforces judge and startup config.
+//!
+//! `GameError` covers every reason the server rejects a client action; it
+//! converts straight into `ServerMessage::Error`, whose `code` is the stable
+//! machine-readable identifier and whose `message` is the human text shown in
+//! the UI. `JudgeError` covers Codeforces API and queue failures, `RoomError`
+//! room and challenge requests, and `ConfigError` a config file or env var the
+//! server can't start with.
+
+use crate::protocol::ServerMessage;
+use battle_cp_core::{PlacementFault, RuleError};
+use crate::state::DifficultyMode;
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum GameError {
+    #[error("Game not found")]
+    GameNotFound,
+    #[error("Game has already ended")]
+    GameEnded,
+    #[error("Player identity already established")]
+    IdentityLocked,
+    #[error("Missing or invalid session token")]
+    Unauthorized,
+    #[error("Too long since you disconnected to resume. Rejoining instead.")]
+    ResumeExpired,
+    #[error("No player ID")]
+    NoPlayerId,
+    #[error("Not in game")]
+    NotInGame,
+    #[error("You cannot play against yourself!")]
+    SelfPlay,
+    #[error("You are banned from this server")]
+    Banned,
+    #[error("This bot token doesn't cover this game")]
+    BotNotAllowed,
+    #[error("Game already has 2 players.")]
+    GameFull,
+    #[error("This seat is reserved for another player")]
+    SeatReserved,
+    #[error("Waiting for opponent")]
+    WaitingForOpponent,
+    #[error("Opponent left")]
+    OpponentLeft,
+
+    // Placement
+    #[error("Cannot place ships after game has started")]
+    PlacementClosed,
+    #[error("Invalid fleet: expected 5 ships, got {0}")]
+    FleetSize(usize),
+    #[error("Invalid fleet composition. Ships must be sizes 5, 4, 3, 3, 2")]
+    FleetComposition,
+    #[error("{0}")]
+    InvalidPlacement(PlacementFault),
+    #[error("Ship starting position out of bounds")]
+    ShipStartOutOfBounds,
+    #[error("Ship extends beyond grid boundary")]
+    ShipOutOfBounds,
+    #[error("Ship overlaps with another ship")]
+    ShipOverlap,
+    #[error("Ready check is only available before combat")]
+    ReadyClosed,
+    #[error("Place your ships before readying up")]
+    ShipsNotPlaced,
+    #[error("Your fleet is already placed")]
+    FleetPlaced,
+    #[error("No staged ship at that position")]
+    NoStagedShip,
+
+    // Combat
+    #[error("Game has not started yet. Wait for both players to place ships.")]
+    NotStarted,
+    #[error("Weapons Locked! Wait for veto timer.")]
+    LockedVeto,
+    #[error("Weapons Locked! Solve CP problem or use Veto.")]
+    Locked,
+    #[error("Out of bounds")]
+    OutOfBounds,
+    #[error("Already fired here")]
+    AlreadyFired,
+    #[error("You don't have that special weapon")]
+    NoSpecialWeapon,
+    #[error("Out of ammo! Solve your problem to reload.")]
+    OutOfAmmo,
+    #[error("You've taken all your sudden death shots. Wait for your opponent's.")]
+    SuddenDeathShotsUsed,
+    #[error("Shields go on one of your own ship cells that hasn't been hit or shielded")]
+    InvalidShield,
+    #[error("No shield to place. Solve a bonus problem to earn one.")]
+    NoShieldAvailable,
+
+    // Problems and vetoes
+    #[error("Cannot solve during veto penalty. You must wait for the timer.")]
+    SolveDuringVeto,
+    #[error("Cannot verify - weapons are not locked")]
+    NothingToVerify,
+    #[error("No problem assigned yet. Wait for the server to assign one.")]
+    NoProblemAssigned,
+    #[error("Invalid problem reference")]
+    InvalidProblem,
+    #[error("You must solve the assigned problem. Use veto to get a new one.")]
+    WrongProblem,
+    #[error("Please wait {0} seconds before verifying again.")]
+    VerifyCooldown(u64),
+    #[error("Too many verification attempts. Try again in {0} seconds.")]
+    VerifyThrottled(u64),
+    #[error("Slow down: try that again in {0} seconds.")]
+    RateLimited(u64),
+    #[error("Cannot use veto - weapons are not locked")]
+    NothingToVeto,
+    #[error("Already on veto timer. Wait for it to expire.")]
+    VetoRunning,
+    #[error("No vetoes remaining")]
+    NoVetoesLeft,
+    #[error("Invalid veto configuration")]
+    InvalidVetoConfig,
+    #[error("Bonus problems are only available while your weapons are unlocked")]
+    BonusWhileLocked,
+    #[error("Solve your current bonus problem first")]
+    BonusPending,
+    #[error("Difficulty is already at its limit")]
+    DifficultyAtLimit,
+    #[error("Hints are turned off for this game")]
+    HintsDisabled,
+    #[error("Only the host can do that")]
+    NotHost,
+    #[error("Only a lobby waiting for an opponent can be extended")]
+    LobbyNotWaiting,
+    #[error("This lobby can't be extended any further")]
+    NoExtensionsLeft,
+    #[error("Not available in a co-op game")]
+    CoopUnavailable,
+    #[error("This game has no forward fleet")]
+    NoForwardFleet,
+    #[error("Armada game: place both fleets at once with PlaceShips (ships and forward)")]
+    ArmadaPlacement,
+    #[error("This game's problem set is fixed")]
+    ProblemSetFixed,
+    #[error("This lobby is for players rated {range}; you're rated {rating}")]
+    RatingOutOfRange { range: String, rating: u32 },
+    #[error("This lobby is for players rated {0}; unrated players can't join")]
+    Unrated(String),
+    #[error("Couldn't look up your rating. Check your handle and try again.")]
+    RatingUnavailable,
+}
+
+impl GameError {
+    /// Stable snake_case identifier sent to clients as `Error.code`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::GameNotFound => "game_not_found",
+            Self::GameEnded => "game_ended",
+            Self::IdentityLocked => "identity_locked",
+            Self::Unauthorized => "unauthorized",
+            Self::ResumeExpired => "resume_expired",
+            Self::NoPlayerId => "no_player_id",
+            Self::NotInGame => "not_in_game",
+            Self::SelfPlay => "self_play",
+            Self::Banned => "banned",
+            Self::BotNotAllowed => "bot_not_allowed",
+            Self::GameFull => "game_full",
+            Self::SeatReserved => "seat_reserved",
+            Self::WaitingForOpponent => "waiting_for_opponent",
+            Self::OpponentLeft => "opponent_left",
+            Self::PlacementClosed => "placement_closed",
+            Self::FleetSize(_) => "fleet_size",
+            Self::FleetComposition => "fleet_composition",
+            Self::InvalidPlacement(_) => "invalid_placement",
+            Self::ShipStartOutOfBounds => "ship_start_out_of_bounds",
+            Self::ShipOutOfBounds => "ship_out_of_bounds",
+            Self::ShipOverlap => "ship_overlap",
+            Self::ReadyClosed => "ready_closed",
+            Self::ShipsNotPlaced => "ships_not_placed",
+            Self::FleetPlaced => "fleet_placed",
+            Self::NoStagedShip => "no_staged_ship",
+            Self::NotStarted => "not_started",
+            Self::LockedVeto => "weapons_locked_veto",
+            Self::Locked => "weapons_locked",
+            Self::OutOfBounds => "out_of_bounds",
+            Self::AlreadyFired => "already_fired",
+            Self::NoSpecialWeapon => "no_special_weapon",
+            Self::OutOfAmmo => "out_of_ammo",
+            Self::SuddenDeathShotsUsed => "sudden_death_shots_used",
+            Self::InvalidShield => "invalid_shield",
+            Self::NoShieldAvailable => "no_shield_available",
+            Self::SolveDuringVeto => "solve_during_veto",
+            Self::NothingToVerify => "not_locked",
+            Self::NoProblemAssigned => "no_problem_assigned",
+            Self::InvalidProblem => "invalid_problem",
+            Self::WrongProblem => "wrong_problem",
+            Self::VerifyCooldown(_) => "verify_cooldown",
+            Self::VerifyThrottled(_) => "verify_throttled",
+            Self::RateLimited(_) => "rate_limited",
+            Self::NothingToVeto => "not_locked",
+            Self::VetoRunning => "veto_running",
+            Self::NoVetoesLeft => "no_vetoes_left",
+            Self::InvalidVetoConfig => "invalid_veto_config",
+            Self::BonusWhileLocked => "bonus_while_locked",
+            Self::BonusPending => "bonus_pending",
+            Self::DifficultyAtLimit => "difficulty_at_limit",
+            Self::HintsDisabled => "hints_disabled",
+            Self::NotHost => "not_host",
+            Self::LobbyNotWaiting => "lobby_not_waiting",
+            Self::NoExtensionsLeft => "no_extensions_left",
+            Self::CoopUnavailable => "coop_unavailable",
+            Self::NoForwardFleet => "no_forward_fleet",
+            Self::ArmadaPlacement => "armada_placement",
+            Self::ProblemSetFixed => "problem_set_fixed",
+            Self::RatingOutOfRange { .. } => "rating_out_of_range",
+            Self::Unrated(_) => "unrated",
+            Self::RatingUnavailable => "rating_unavailable",
+        }
+    }
+}
+
+/// Board-rule violations from `battle_cp_core` keep their codes and messages.
+impl From<RuleError> for GameError {
+    fn from(e: RuleError) -> Self {
+        match e {
+            RuleError::FleetSize(n) => Self::FleetSize(n),
+            RuleError::FleetComposition => Self::FleetComposition,
+            RuleError::ShipStartOutOfBounds => Self::ShipStartOutOfBounds,
+            RuleError::ShipOutOfBounds => Self::ShipOutOfBounds,
+            RuleError::ShipOverlap => Self::ShipOverlap,
+            RuleError::OutOfBounds => Self::OutOfBounds,
+            RuleError::AlreadyFired => Self::AlreadyFired,
+            RuleError::InvalidShield => Self::InvalidShield,
+            RuleError::Placement(fault) => Self::InvalidPlacement(fault),
+        }
+    }
+}
+
+impl From<GameError> for ServerMessage {
+    fn from(e: GameError) -> Self {
+        let placement = match &e {
+            GameError::InvalidPlacement(fault) => Some(fault.clone()),
+            _ => None,
+        };
+        let retry_after_secs = match e {
+            GameError::VerifyCooldown(secs) | GameError::VerifyThrottled(secs) | GameError::RateLimited(secs) => {
+                Some(secs)
+            }
+            _ => None,
+        };
+        ServerMessage::Error {
+            code: e.code().to_string(),
+            message: e.to_string(),
+            placement,
+            retry_after_secs,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum JudgeError {
+    #[error("HTTP error: {0}")]
+    Http(reqwest::Error),
+    #[error("JSON parse error: {0}")]
+    Parse(reqwest::Error),
+    #[error("CF API returned status={0}")]
+    ApiStatus(String),
+    #[error("CF queue closed")]
+    QueueClosed,
+    #[error("CF worker dropped request")]
+    WorkerDropped,
+    #[error("No problems in database for difficulty={difficulty} mode={mode:?}")]
+    NoProblems { difficulty: u32, mode: DifficultyMode },
+}
+
+impl JudgeError {
+    /// Stable snake_case identifier, for logs and REST error bodies.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Http(_) | Self::QueueClosed | Self::WorkerDropped => "judge_unavailable",
+            Self::Parse(_) | Self::ApiStatus(_) => "judge_bad_response",
+            Self::NoProblems { .. } => "no_problems",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RoomError {
+    #[error("Room not found")]
+    NotFound,
+    #[error("Room names must be 3-40 characters with at least 3 letters or digits")]
+    InvalidName,
+    #[error("A room with that name already exists")]
+    NameTaken,
+    #[error("Too many rooms on this server")]
+    TooManyRooms,
+    #[error("Room is full")]
+    RoomFull,
+    #[error("That handle is already in this room")]
+    HandleTaken,
+    #[error("Missing or invalid room token")]
+    Unauthorized,
+    #[error("Both players must be members of the room")]
+    NotMember,
+    #[error("Challenge not found or no longer open")]
+    ChallengeNotFound,
+    #[error("This challenge is not for you")]
+    NotChallenged,
+}
+
+impl RoomError {
+    /// Stable snake_case identifier, for REST error bodies.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound => "room_not_found",
+            Self::InvalidName => "invalid_room_name",
+            Self::NameTaken => "room_name_taken",
+            Self::TooManyRooms => "too_many_rooms",
+            Self::RoomFull => "room_full",
+            Self::HandleTaken => "handle_taken",
+            Self::Unauthorized => "invalid_room_token",
+            Self::NotMember => "not_member",
+            Self::ChallengeNotFound => "challenge_not_found",
+            Self::NotChallenged => "not_challenged",
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("cannot read config file {path}: {source}")]
+    Read {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("invalid config file: {0}")]
+    Parse(String),
+    #[error("invalid value for {var}: {value:?}")]
+    Env { var: &'static str, value: String },
+    #[error("invalid config: {0}")]
+    Invalid(String),
+}
@@ -0,0 +1,177 @@
+//! Each game's broadcast channel and replay log.
+//!
+//! `GameTx::send` numbers every broadcast from 1 in the order the game sent
+//! it and stamps it with the server time, keeps it in the replay log, then
+//! broadcasts it. Numbering and sending happen under one lock, so every
+//! socket receives events in ID order and both players' logs line up with the
+//! server's. The log can be read through `GET /api/admin/game/:id/events`,
+//! and by anyone once the game is over (`GET /api/game/:id/events`).
+//!
+//! Each broadcast is followed by the activity feed lines it calls for (see
+//! `activity.rs`), logged and sent the same way.
+//!
+//! Sockets read `frames()` instead: the same events, except that everything
+//! sent while a `batch()` guard is held arrives as one frame, so a client
+//! never sees a shot without the lock or GameOver it caused.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+
+use crate::pagination::{Page, PageQuery};
+use crate::protocol::StampedMessage;
+use crate::state::GameEvent;
+
+/// Events kept per game; the oldest are dropped past this (a full game sends
+/// a few hundred).
+const MAX_LOGGED_EVENTS: usize = 10_000;
+
+/// Events that go out on a socket together, oldest first.
+pub type Frame = Arc<[StampedMessage]>;
+
+#[derive(Debug, Default)]
+struct EventLog {
+    next_id: u64,
+    events: VecDeque<StampedMessage>,
+    /// Open `batch()` guards, and the events they are holding back from `frames`
+    batch_depth: u32,
+    batched: Vec<StampedMessage>,
+    feed: crate::activity::Feed,
+}
+
+/// Filter for the event log endpoints: `?type=ShotResult`.
+#[derive(Debug, Default, serde::Deserialize)]
+pub struct EventFilter {
+    #[serde(rename = "type")]
+    pub kind: Option<String>,
+}
+
+/// A page of `events`, by event ID, keeping those `filter` matches.
+pub fn page(events: Vec<StampedMessage>, filter: &EventFilter, page: &PageQuery) -> Result<Page<StampedMessage>, &'static str> {
+    let events = match filter.kind.as_deref() {
+        Some(kind) => events
+            .into_iter()
+            .filter(|e| serde_json::to_value(&e.message).is_ok_and(|m| m["type"] == kind))
+            .collect(),
+        None => events,
+    };
+    page.paginate(events, |e| e.event_id)
+}
+
+/// A game's broadcast sender. Cloning shares the channels and the log.
+#[derive(Clone, Debug)]
+pub struct GameTx {
+    tx: broadcast::Sender<StampedMessage>,
+    frames: broadcast::Sender<Frame>,
+    log: Arc<Mutex<EventLog>>,
+}
+
+/// Holds back socket frames until dropped; see `GameTx::batch`.
+#[must_use = "the batch is sent when this guard is dropped"]
+pub struct Batch {
+    frames: broadcast::Sender<Frame>,
+    log: Arc<Mutex<EventLog>>,
+}
+
+impl Drop for Batch {
+    fn drop(&mut self) {
+        let mut log = self.log.lock().unwrap_or_else(|e| e.into_inner());
+        log.batch_depth -= 1;
+        if log.batch_depth == 0 && !log.batched.is_empty() {
+            let frame: Frame = std::mem::take(&mut log.batched).into();
+            let _ = self.frames.send(frame);
+        }
+    }
+}
+
+impl GameTx {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            tx: broadcast::channel(capacity).0,
+            frames: broadcast::channel(capacity).0,
+            log: Arc::default(),
+        }
+    }
+
+    /// Until the guard is dropped, everything sent goes to sockets as one
+    /// frame. Guards nest; the outermost one sends. Other subscribers still
+    /// get each event as it's sent.
+    pub fn batch(&self) -> Batch {
+        self.log.lock().unwrap_or_else(|e| e.into_inner()).batch_depth += 1;
+        Batch {
+            frames: self.frames.clone(),
+            log: self.log.clone(),
+        }
+    }
+
+    /// Stamp, log and broadcast `event`, then its activity feed lines.
+    /// Returns how many receivers `event` reached; it is logged even when
+    /// that is none.
+    pub fn send(&self, event: GameEvent) -> usize {
+        let GameEvent::Message(message) = event;
+        let mut log = self.log.lock().unwrap_or_else(|e| e.into_inner());
+        let lines = log.feed.follow(&message);
+        // One frame for the event and its feed lines
+        let _batch = (!lines.is_empty()).then(|| {
+            log.batch_depth += 1;
+            Batch { frames: self.frames.clone(), log: self.log.clone() }
+        });
+        log.next_id += 1;
+        let stamped = StampedMessage {
+            event_id: log.next_id,
+            at_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            message,
+        };
+        if log.events.len() == MAX_LOGGED_EVENTS {
+            log.events.pop_front();
+        }
+        log.events.push_back(stamped.clone());
+        if log.batch_depth > 0 {
+            log.batched.push(stamped.clone());
+        } else {
+            let _ = self.frames.send(Arc::from([stamped.clone()]));
+        }
+        drop(log);
+        let reached = self.tx.send(stamped).unwrap_or(0);
+        for line in lines {
+            self.send(GameEvent::Message(line));
+        }
+        reached
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<StampedMessage> {
+        self.tx.subscribe()
+    }
+
+    /// Events grouped as sockets send them (see `batch`).
+    pub fn frames(&self) -> broadcast::Receiver<Frame> {
+        self.frames.subscribe()
+    }
+
+    /// Event and frame subscribers together.
+    pub fn receiver_count(&self) -> usize {
+        self.tx.receiver_count() + self.frames.receiver_count()
+    }
+
+    /// The replay log, oldest first.
+    pub fn events(&self) -> Vec<StampedMessage> {
+        let log = self.log.lock().unwrap_or_else(|e| e.into_inner());
+        log.events.iter().cloned().collect()
+    }
+
+    /// Everything after `last_id`, oldest first, for a resuming socket.
+    /// `None` if that's more than `max` events or the log no longer reaches
+    /// back that far.
+    pub fn events_since(&self, last_id: u64, max: usize) -> Option<Vec<StampedMessage>> {
+        let log = self.log.lock().unwrap_or_else(|e| e.into_inner());
+        let missed = log.next_id.checked_sub(last_id)? as usize;
+        if missed > max || missed > log.events.len() {
+            return None;
+        }
+        Some(log.events.iter().skip(log.events.len() - missed).cloned().collect())
+    }
+}
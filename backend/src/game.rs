@@ -1,41 +1,601 @@
+use crate::error::GameError;
 use crate::state::*;
 use uuid::Uuid; //a custom type for unique ids
                 //read
                 //all the game logic
+
+pub use battle_cp_core::Shot;
+
+/// Outcome of a single valid shot: (hit or miss, sunk_this_shot, sunk ship cells).
+pub type ShotOutcome = (Shot, bool, Option<Vec<[usize; 2]>>);
+
+/// Minimum wait between a player's CF verifications.
+const VERIFY_COOLDOWN: std::time::Duration = std::time::Duration::from_secs(10);
+/// What `HintCost::LongerVeto` adds to the next veto.
+const HINT_VETO_EXTENSION_SECS: u64 = 120;
+/// Failed verifications stop doubling the cooldown after this many (10s → 320s).
+const VERIFY_MAX_DOUBLINGS: u32 = 5;
+/// A locked player counts as working this long after the lock or their latest
+/// verification attempt.
+const WORKING_WINDOW: std::time::Duration = std::time::Duration::from_secs(300);
+/// How many times a host may push back their Waiting lobby's expiry.
+pub const MAX_LOBBY_EXTENSIONS: u32 = 3;
 #[allow(unused)]
 impl Game {
     pub fn new(player1_id: Uuid, player1_handle: String, config: GameConfig) -> Self {
-        let (tx, _) = tokio::sync::broadcast::channel(2000);
+        let tx = crate::events::GameTx::new(broadcast_capacity());
+        let seed = config.seed.unwrap_or_else(rand::random);
+        let ammo = config.ammo.map(|a| a.starting);
         Self {
             id: Uuid::new_v4(),
-            player1: Player::new(player1_id, player1_handle),
+            player1: Player { ammo, ..Player::new(player1_id, player1_handle) },
             player2: None,
             status: GameStatus::Waiting,
             config,
             created_at: std::time::Instant::now(),
             placement_started_at: None,
             game_started_at: None,
+            clock_paused_at: None,
+            clock_extended_secs: 0,
             finished_at: None,
+            abandoned_at: None,
+            abandoned_from: None,
             game_over_msg: None,
             problem_queue: vec![],
             p1_queue_idx: 0,
             p2_queue_idx: 0,
+            problem_queue_ready: false,
+            problem_set: vec![],
+            creator_ip: None,
+            host_discord_id: None,
+            webhooks: None,
+            scheduled_start_at: None,
+            room: None,
+            tournament: None,
+            reserved_guest: None,
+            schedule_reached: false,
+            audit_log: Default::default(),
+            spectators: 0,
+            lobby_extensions: 0,
+            lobby_expiry_warned: false,
+            coop_board: None,
+            lock_history: vec![],
+            seed,
+            rng: rand::SeedableRng::seed_from_u64(seed),
             tx,
         }
     }
 
-    pub fn join(&mut self, player2_id: Uuid, player2_handle: String) -> Result<(), &'static str> {
+    pub fn join(&mut self, player2_id: Uuid, player2_handle: String) -> Result<(), GameError> {
         if player2_id == self.player1.id {
-            return Err("Cannot play against yourself, silly");
+            return Err(GameError::SelfPlay);
         }
         if self.player2.is_some() {
-            return Err("Game already has 2 players.");
+            return Err(GameError::GameFull);
         }
-        self.player2 = Some(Player::new(player2_id, player2_handle));
+        let ammo = self.config.ammo.map(|a| a.starting);
+        self.player2 = Some(Player { ammo, ..Player::new(player2_id, player2_handle) });
         // Record when placement phase actually started (P2 just joined)
         self.placement_started_at = Some(std::time::Instant::now());
         Ok(())
     }
+
+    /// `at`, or the scheduled start if that is later: for scheduled matches the
+    /// pre-game timeouts only count from the scheduled time.
+    pub fn not_before_schedule(&self, at: std::time::Instant) -> std::time::Instant {
+        self.scheduled_start_at.map_or(at, |start| at.max(start))
+    }
+
+    /// Seat a guest as player 2 and open ship placement. The slot check and the
+    /// seating happen in one actor command, so of two guests racing for the
+    /// slot exactly one gets it and the other sees `GameFull`.
+    pub fn seat_guest(&mut self, pid: Uuid, cf_handle: String) -> Result<(), GameError> {
+        if self.player2.is_some() {
+            return Err(GameError::GameFull);
+        }
+        if let Some((id, handle)) = &self.reserved_guest {
+            if *id != pid || !handle.eq_ignore_ascii_case(cf_handle.trim()) {
+                return Err(GameError::SeatReserved);
+            }
+        }
+        if self.player1.cf_handle.eq_ignore_ascii_case(&cf_handle) {
+            return Err(GameError::SelfPlay);
+        }
+        self.join(pid, cf_handle)?;
+        self.status = GameStatus::PlacingShips;
+        if self.config.coop {
+            self.place_coop_fleet();
+        }
+        Ok(())
+    }
+
+    /// Co-op: hide a random fleet for both players to hunt and skip their own
+    /// placement, so the game goes straight to `Initializing`.
+    pub fn place_coop_fleet(&mut self) {
+        let (grid, ships) =
+            battle_cp_core::place_fleet(&random_fleet(&mut self.rng)).expect("random_fleet places a legal fleet");
+        self.coop_board = Some(Board { grid, ships });
+        for p in std::iter::once(&mut self.player1).chain(self.player2.as_mut()) {
+            p.ships_placed = true;
+        }
+        self.status = GameStatus::Initializing;
+    }
+
+    /// Co-op: give `from`'s partner the same weapons — the shared heat pool and
+    /// its lock, veto and problem — and tell them what changed. Called after
+    /// anything that changes one player's weapons; does nothing in a duel.
+    pub fn share_weapons(&mut self, from: Uuid) {
+        if !self.config.coop {
+            return;
+        }
+        let Some(p2) = self.player2.as_mut() else {
+            return;
+        };
+        let (src, dst) = if self.player1.id == from {
+            (&self.player1, p2)
+        } else if p2.id == from {
+            (&*p2, &mut self.player1)
+        } else {
+            return;
+        };
+        let (was_locked, had_problem) = (dst.is_locked, dst.active_problem.clone());
+        dst.heat = src.heat;
+        dst.ammo = src.ammo;
+        dst.is_locked = src.is_locked;
+        dst.vetoes_used = src.vetoes_used;
+        dst.veto_started_at = src.veto_started_at;
+        dst.veto_extension_secs = src.veto_extension_secs;
+        dst.heat_penalty = src.heat_penalty;
+        dst.active_problem = src.active_problem.clone();
+        dst.locked_at_unix = src.locked_at_unix;
+        dst.heat_relief_at = src.heat_relief_at;
+
+        let mut msgs = vec![];
+        match (was_locked, dst.is_locked) {
+            (false, true) => msgs.push(crate::protocol::ServerMessage::WeaponsLocked { player_id: dst.id }),
+            (true, false) => {
+                dst.last_verification_attempt = None;
+                dst.last_active_at = None;
+                msgs.push(crate::protocol::ServerMessage::WeaponsUnlocked {
+                    player_id: dst.id,
+                    reason: "teammate".to_string(),
+                });
+            }
+            _ => {}
+        }
+        if let Some(ap) = dst.active_problem.as_ref().filter(|&ap| had_problem.as_ref() != Some(ap)) {
+            msgs.push(crate::protocol::ServerMessage::ProblemAssigned {
+                player_id: dst.id,
+                contest_id: ap.contest_id,
+                problem_index: ap.index.clone(),
+                problem_name: ap.name.clone(),
+                rating: ap.rating,
+                url: ap.url.clone(),
+            });
+        }
+        for msg in msgs {
+            let _ = self.tx.send(GameEvent::Message(msg));
+        }
+    }
+
+    /// The participant with this id, if any.
+    pub fn player(&self, pid: Uuid) -> Option<&Player> {
+        std::iter::once(&self.player1)
+            .chain(self.player2.as_ref())
+            .find(|p| p.id == pid)
+    }
+
+    /// Mutable access to the participant with this id, if any.
+    pub fn player_mut(&mut self, pid: Uuid) -> Option<&mut Player> {
+        std::iter::once(&mut self.player1)
+            .chain(self.player2.as_mut())
+            .find(|p| p.id == pid)
+    }
+
+    /// A participant's socket joined. Revives the game if it was abandoned.
+    pub fn connect(&mut self, pid: Uuid) {
+        let player = if self.player1.id == pid {
+            &mut self.player1
+        } else if let Some(p2) = self.player2.as_mut().filter(|p| p.id == pid) {
+            p2
+        } else {
+            return;
+        };
+        player.connections += 1;
+        player.disconnected_at = None;
+        if player.connections == 1 {
+            let _ = self.tx.send(GameEvent::Message(crate::protocol::ServerMessage::Presence {
+                player_id: pid,
+                connected: true,
+            }));
+        }
+        self.revive();
+    }
+
+    /// Resume an abandoned game where it left off. Overdue deadlines fire on
+    /// the actor's next loop.
+    pub fn revive(&mut self) {
+        if self.status == GameStatus::Abandoned {
+            self.status = self.abandoned_from.take().unwrap_or(GameStatus::PlacingShips);
+            self.abandoned_at = None;
+        }
+    }
+
+    /// A participant's socket closed. Once both players of a started lobby are
+    /// gone the game becomes `Abandoned`; returns true if this call did that.
+    pub fn disconnect(&mut self, pid: Uuid) -> bool {
+        let player = if self.player1.id == pid {
+            &mut self.player1
+        } else if let Some(p2) = self.player2.as_mut().filter(|p| p.id == pid) {
+            p2
+        } else {
+            return false;
+        };
+        player.connections = player.connections.saturating_sub(1);
+        if player.connections == 0 {
+            player.disconnected_at = Some(std::time::Instant::now());
+            let _ = self.tx.send(GameEvent::Message(crate::protocol::ServerMessage::Presence {
+                player_id: pid,
+                connected: false,
+            }));
+        }
+
+        let everyone_gone = self.player1.connections == 0
+            && self.player2.as_ref().is_some_and(|p| p.connections == 0);
+        // Waiting lobbies have their own TTL; a countdown is only seconds long
+        let abandonable = matches!(
+            self.status,
+            GameStatus::PlacingShips | GameStatus::Initializing | GameStatus::Playing | GameStatus::SuddenDeath
+        );
+        if everyone_gone && abandonable {
+            self.abandoned_from = Some(std::mem::replace(&mut self.status, GameStatus::Abandoned));
+            self.abandoned_at = Some(std::time::Instant::now());
+            return true;
+        }
+        false
+    }
+
+    /// Since when `p` has had no socket open, counting from placement start
+    /// (or the scheduled start) if they never connected; None while connected.
+    pub fn absent_since(&self, p: &Player) -> Option<std::time::Instant> {
+        if p.connections > 0 {
+            return None;
+        }
+        let since = p.disconnected_at.or(self.placement_started_at).unwrap_or(self.created_at);
+        Some(self.not_before_schedule(since))
+    }
+
+    /// `pid` sent something: restart their AFK clock, and tell both players
+    /// if that lifts a warning.
+    pub fn note_input(&mut self, pid: Uuid) {
+        let Some(player) = self.player_mut(pid) else { return };
+        player.idle_from = Some(std::time::Instant::now());
+        if std::mem::take(&mut player.idle_warned) {
+            let _ = self.tx.send(GameEvent::Message(crate::protocol::ServerMessage::IdleCleared { player_id: pid }));
+        }
+    }
+
+    /// Since when `p` has been idle under the AFK rule (`idle_timeout_secs`):
+    /// unlocked in a running versus combat without sending anything, counting
+    /// from the start of combat at the earliest. None when the rule doesn't apply.
+    pub fn idle_since(&self, p: &Player) -> Option<std::time::Instant> {
+        self.config.idle_timeout_secs?;
+        let running = matches!(self.status, GameStatus::Playing | GameStatus::SuddenDeath)
+            && self.clock_paused_at.is_none();
+        if !running || self.config.coop || p.is_locked {
+            return None;
+        }
+        let started = self.game_started_at?;
+        Some(p.idle_from.map_or(started, |at| at.max(started)))
+    }
+
+    /// Give up on a game stuck with absent players (see `timers::stuck_deadline`):
+    /// mark it `Abandoned`, so cleanup drops it unless someone comes back, and
+    /// note each absent player in the audit log.
+    pub fn reap(&mut self) {
+        let game_id = self.id;
+        let status = self.status.clone();
+        let mut audit_log = std::mem::take(&mut self.audit_log);
+        for player in std::iter::once(&self.player1).chain(self.player2.as_ref()) {
+            if let Some(since) = self.absent_since(player) {
+                let detail = format!("{:?} with no socket for {}s", status, since.elapsed().as_secs());
+                audit_log.record_reaped(game_id, player, detail);
+            }
+        }
+        self.audit_log = audit_log;
+        tracing::warn!(target: "game", "Game {:?} stuck in {:?} with absent players; abandoning it", game_id, status);
+        self.abandoned_from = Some(std::mem::replace(&mut self.status, GameStatus::Abandoned));
+        self.abandoned_at = Some(std::time::Instant::now());
+    }
+
+    /// End the game: mark it Finished, broadcast `GameOver`, and keep that
+    /// message so participants who reconnect before cleanup still get the result.
+    /// A game that has already finished keeps its first result.
+    pub fn finish(&mut self, winner_id: Option<Uuid>, reason: &str) {
+        if self.status == GameStatus::Finished {
+            return;
+        }
+        self.status = GameStatus::Finished;
+        self.finished_at = Some(std::time::Instant::now());
+        let ids: Vec<Uuid> = self.lock_history.iter().filter(|r| r.outcome.is_none()).map(|r| r.player_id).collect();
+        for id in ids {
+            self.lock_closed(id, LockOutcome::Unsolved);
+        }
+        for player in std::iter::once(&self.player1).chain(self.player2.as_ref()) {
+            if let Some(p95) = player.latency.p95() {
+                tracing::info!(
+                    target: "game",
+                    "Game {:?}: {} had a p95 round trip of {}ms over {} pings",
+                    self.id,
+                    player.cf_handle,
+                    p95,
+                    player.latency.len()
+                );
+            }
+        }
+        let go_msg = build_game_over(self, winner_id, reason.to_string());
+        self.game_over_msg = Some(go_msg.clone());
+        let _ = self.tx.send(GameEvent::Message(go_msg));
+    }
+
+    /// `player_id` was assigned `problem` to unlock their weapons.
+    pub fn lock_opened(&mut self, player_id: Uuid, problem: &AssignedProblem) {
+        self.lock_history.push(LockRecord {
+            player_id,
+            problem: problem.clone(),
+            assigned_at_unix: unix_now(),
+            outcome: None,
+            secs_taken: None,
+        });
+    }
+
+    /// `player_id`'s open lock problem is done with. In co-op games either
+    /// player closes the team's. No-op without an open one.
+    pub fn lock_closed(&mut self, player_id: Uuid, outcome: LockOutcome) {
+        let coop = self.config.coop;
+        if let Some(record) = self
+            .lock_history
+            .iter_mut()
+            .rev()
+            .find(|r| r.outcome.is_none() && (coop || r.player_id == player_id))
+        {
+            record.outcome = Some(outcome);
+            record.secs_taken = Some(unix_now().saturating_sub(record.assigned_at_unix));
+        }
+    }
+
+    /// (assigned, solved) lock problems for `player_id`.
+    pub fn lock_counts(&self, player_id: Uuid) -> (u32, u32) {
+        self.lock_history.iter().filter(|r| r.player_id == player_id).fold((0, 0), |(assigned, solved), r| {
+            (assigned + 1, solved + u32::from(r.outcome == Some(LockOutcome::Solved)))
+        })
+    }
+
+    /// Warn the lobby that it will close in `in_secs` unless an opponent
+    /// joins or the host extends it. Once per deadline.
+    pub fn warn_lobby_expiring(&mut self, in_secs: u64) {
+        if self.status != GameStatus::Waiting || self.lobby_expiry_warned {
+            return;
+        }
+        self.lobby_expiry_warned = true;
+        let _ = self.tx.send(GameEvent::Message(crate::protocol::ServerMessage::LobbyExpiring { in_secs }));
+    }
+
+    /// Host asks to keep the Waiting lobby open another `timers::LOBBY_EXTENSION`;
+    /// broadcasts the new deadline and returns the extensions left.
+    pub fn extend_lobby(&mut self, pid: Uuid) -> Result<u32, GameError> {
+        if self.status != GameStatus::Waiting {
+            return Err(GameError::LobbyNotWaiting);
+        }
+        if pid != self.player1.id {
+            return Err(GameError::NotHost);
+        }
+        if self.lobby_extensions >= MAX_LOBBY_EXTENSIONS {
+            return Err(GameError::NoExtensionsLeft);
+        }
+        self.lobby_extensions += 1;
+        self.lobby_expiry_warned = false;
+        let extensions_left = MAX_LOBBY_EXTENSIONS - self.lobby_extensions;
+        let _ = self.tx.send(GameEvent::Message(crate::protocol::ServerMessage::LobbyExtended {
+            in_secs: crate::timers::phase_time_remaining_secs(self).unwrap_or(0),
+            extensions_left,
+        }));
+        Ok(extensions_left)
+    }
+
+    /// A referee feed socket opened (`joined`) or closed; tells the players.
+    pub fn spectator_moved(&mut self, joined: bool) {
+        self.spectators = if joined { self.spectators + 1 } else { self.spectators.saturating_sub(1) };
+        let _ = self.tx.send(GameEvent::Message(crate::protocol::ServerMessage::SpectatorCount {
+            count: self.spectators,
+        }));
+    }
+
+    /// Record a round trip `player_id`'s socket measured, broadcasting it as
+    /// `Latency` when `show_latency` is on. Returns the player's p95 so far.
+    pub fn record_rtt(&mut self, player_id: Uuid, rtt_ms: u32) -> Option<u32> {
+        let player = self.player_mut(player_id)?;
+        player.latency.record(rtt_ms);
+        let p95 = player.latency.p95();
+        if self.config.show_latency {
+            let _ = self.tx.send(GameEvent::Message(crate::protocol::ServerMessage::Latency { player_id, rtt_ms }));
+        }
+        p95
+    }
+
+    /// Broadcast `Working` for each player whose `working()` changed since the
+    /// last one. The actor runs this after every command and deadline.
+    pub fn sync_working(&mut self) {
+        if !self.config.activity_indicator {
+            return;
+        }
+        for player in std::iter::once(&mut self.player1).chain(self.player2.as_mut()) {
+            let working = player.working();
+            if working != player.shown_working {
+                player.shown_working = working;
+                let _ = self.tx.send(GameEvent::Message(crate::protocol::ServerMessage::Working {
+                    player_id: player.id,
+                    working,
+                }));
+            }
+        }
+    }
+
+    /// Pause or resume the clock for a judge outage (`down`), under
+    /// `pause_on_outage`: it stands still while the judge is down during
+    /// combat and someone is locked, since they can't verify a solve. On
+    /// resume the start of the game and every running veto, heat relief and
+    /// AFK clock move forward by the pause. The global ticker calls this every sweep while a judge is
+    /// down or the clock is paused.
+    pub fn sync_outage(&mut self, down: bool) {
+        let combat = matches!(self.status, GameStatus::Playing | GameStatus::SuddenDeath);
+        let locked = std::iter::once(&self.player1).chain(self.player2.as_ref()).any(|p| p.is_locked);
+        let pause = self.config.pause_on_outage && down && combat && locked;
+        match (pause, self.clock_paused_at) {
+            (true, None) => {
+                self.clock_paused_at = Some(std::time::Instant::now());
+                tracing::warn!(target: "game", "Game {:?}: judge unreachable, clock paused", self.id);
+                let _ = self.tx.send(GameEvent::Message(crate::protocol::ServerMessage::ClockPaused {
+                    message: "The judge can't be reached, so solves can't be verified: the game clock and veto timers are paused until it's back.".to_string(),
+                }));
+            }
+            // Over during the pause: nothing left to resume
+            (false, Some(_)) if !combat => self.clock_paused_at = None,
+            (false, Some(paused_at)) => {
+                self.clock_paused_at = None;
+                let paused = paused_at.elapsed();
+                self.game_started_at = self.game_started_at.map(|at| at + paused);
+                for p in std::iter::once(&mut self.player1).chain(self.player2.as_mut()) {
+                    p.veto_started_at = p.veto_started_at.map(|at| at + paused);
+                    p.heat_relief_at = p.heat_relief_at.map(|at| at + paused);
+                    p.idle_from = p.idle_from.map(|at| at + paused);
+                }
+                tracing::info!(target: "game", "Game {:?}: clock resumed after {:?}", self.id, paused);
+                let _ = self.tx.send(GameEvent::Message(crate::protocol::ServerMessage::ClockResumed {
+                    paused_secs: paused.as_secs(),
+                }));
+            }
+            _ => {}
+        }
+    }
+
+    /// Now, on the game clock: the moment it stopped while paused.
+    pub fn clock_now(&self) -> std::time::Instant {
+        self.clock_paused_at.unwrap_or_else(std::time::Instant::now)
+    }
+
+    /// Seconds of combat so far on the game clock (0 before the game starts).
+    pub fn combat_secs(&self) -> u64 {
+        self.game_started_at
+            .map_or(0, |s| self.clock_now().saturating_duration_since(s).as_secs())
+    }
+
+    /// Combat seconds until regulation time ends: the configured duration plus
+    /// any anti-snipe extensions.
+    pub fn regulation_secs(&self) -> u64 {
+        self.config.game_duration_secs + self.clock_extended_secs
+    }
+
+    /// Seconds of combat time left (full duration until the game starts).
+    pub fn time_remaining_secs(&self) -> u64 {
+        self.regulation_secs().saturating_sub(self.combat_secs())
+    }
+
+    /// The heat threshold in force now (see `GameConfig::heat_ramp`); the
+    /// base threshold before combat.
+    pub fn heat_threshold(&self) -> u32 {
+        self.config.heat_threshold_at(self.combat_secs())
+    }
+
+    /// Public snapshot for the REST API — handles and readiness only, no board data.
+    pub fn snapshot(&self) -> crate::protocol::GameSnapshot {
+        let view = |p: &Player| crate::protocol::PlayerSnapshot {
+            cf_handle: p.cf_handle.clone(),
+            bot: p.bot.clone(),
+            ships_placed: p.ships_placed,
+        };
+        crate::protocol::GameSnapshot {
+            game_id: self.id,
+            status: self.status.clone(),
+            difficulty: self.config.difficulty,
+            difficulty_mode: self.config.difficulty_mode.clone(),
+            heat_threshold: self.config.heat_threshold,
+            heat_ramp: self.config.heat_ramp.clone(),
+            ammo: self.config.ammo,
+            sudden_death: self.config.sudden_death,
+            max_vetoes: self.config.max_vetoes,
+            veto_penalties: self.config.veto_penalties.clone(),
+            game_duration_secs: self.config.game_duration_secs,
+            time_remaining_secs: self.time_remaining_secs(),
+            public: self.config.public,
+            coop: self.config.coop,
+            reveal_problems: self.config.reveal_problems,
+            scheduled_start: self.config.scheduled_start,
+            min_rating: self.config.min_rating,
+            max_rating: self.config.max_rating,
+            room: self.room.clone(),
+            host: view(&self.player1),
+            guest: self.player2.as_ref().map(view),
+        }
+    }
+
+    /// Record `pid`'s difficulty vote. Once both players have voted the same
+    /// way the difficulty moves one step (a band, or 100 rating) and the votes
+    /// clear; returns the new difficulty then.
+    pub fn vote_difficulty(
+        &mut self,
+        pid: Uuid,
+        direction: crate::protocol::DifficultyVote,
+    ) -> Result<Option<u32>, GameError> {
+        use crate::protocol::DifficultyVote;
+        if self.config.reveal_problems {
+            return Err(GameError::ProblemSetFixed);
+        }
+        let (step, range) = match self.config.difficulty_mode {
+            DifficultyMode::Band => (1, 0..=4),
+            DifficultyMode::Cf => (100, 800..=3500),
+        };
+        let target = match direction {
+            DifficultyVote::Up => self.config.difficulty.checked_add(step),
+            DifficultyVote::Down => self.config.difficulty.checked_sub(step),
+        }
+        .filter(|d| range.contains(d))
+        .ok_or(GameError::DifficultyAtLimit)?;
+
+        self.player_mut(pid).ok_or(GameError::NotInGame)?.difficulty_vote = Some(direction);
+        let unanimous = std::iter::once(&self.player1)
+            .chain(self.player2.as_ref())
+            .all(|p| p.difficulty_vote == Some(direction));
+        if !unanimous || self.player2.is_none() {
+            return Ok(None);
+        }
+        self.config.difficulty = target;
+        self.player1.difficulty_vote = None;
+        if let Some(p2) = self.player2.as_mut() {
+            p2.difficulty_vote = None;
+        }
+        Ok(Some(target))
+    }
+
+    /// End-of-clock result by the shared tie-break rules (see `battle_cp_core::determine_winner`).
+    /// Ends accuracy sudden death: the better hit rate wins, equal rates draw.
+    pub fn finish_on_accuracy(&mut self) {
+        let p2 = self.player2.as_ref().map_or(Default::default(), |p| p.sudden_death);
+        match battle_cp_core::compare_accuracy(self.player1.sudden_death, p2) {
+            std::cmp::Ordering::Greater => self.finish(Some(self.player1.id), "SuddenDeath - Better accuracy"),
+            std::cmp::Ordering::Less => {
+                let winner = self.player2.as_ref().map(|p| p.id);
+                self.finish(winner, "SuddenDeath - Better accuracy");
+            }
+            std::cmp::Ordering::Equal => self.finish(None, "SuddenDeath - Equal accuracy"),
+        }
+    }
+
+    /// The status line a `GameUpdate` carries.
+    pub fn status_line(&self, locale: crate::locale::Locale) -> String {
+        locale.game_status(&self.status, self.config.sudden_death)
+    }
+
     pub fn determine_winner(&self) -> crate::state::TiebreakResult {
         let p1 = &self.player1;
 
@@ -45,26 +605,10 @@ impl Game {
             None => return crate::state::TiebreakResult::Player1Wins, // P2 forfeit/missing
         };
 
-        // Count surviving ships for each player (ships that are NOT sunk)
-        let p1_ships_remaining = p1.ships.iter().filter(|s| !s.sunk).count();
-        let p2_ships_remaining = p2.ships.iter().filter(|s| !s.sunk).count();
-
-        // 1. Ships Remaining (most important - your surviving fleet)
-        if p1_ships_remaining > p2_ships_remaining {
-            return crate::state::TiebreakResult::Player1Wins;
-        } else if p2_ships_remaining > p1_ships_remaining {
-            return crate::state::TiebreakResult::Player2Wins;
-        }
-
-        // 2. Cells Hit (total hits you scored on enemy)
-        if p1.stats.cells_hit > p2.stats.cells_hit {
-            return crate::state::TiebreakResult::Player1Wins;
-        } else if p2.stats.cells_hit > p1.stats.cells_hit {
-            return crate::state::TiebreakResult::Player2Wins;
-        }
-
-        // 3. Tie -> Sudden Death mode
-        crate::state::TiebreakResult::SuddenDeath
+        battle_cp_core::determine_winner(
+            battle_cp_core::Standing::new(&p1.fleet(), p1.stats.cells_hit),
+            battle_cp_core::Standing::new(&p2.fleet(), p2.stats.cells_hit),
+        )
     }
 }
 
@@ -73,176 +617,330 @@ impl Player {
         Self {
             id,
             cf_handle,
-            grid: Grid::new(),
-            ships: vec![],
+            bot: None,
+            locale: Default::default(),
+            home: Board::default(),
+            forward: None,
             heat: 0,
+            ammo: None,
             is_locked: false,
             vetoes_used: 0,
             stats: PlayerStats::default(),
+            sudden_death: Default::default(),
             ships_placed: false,
+            staged_ships: Vec::new(),
+            ready: false,
+            inventory: Vec::new(),
+            shields_available: 0,
+            markers: Vec::new(),
             veto_started_at: None,
             last_verification_attempt: None,
+            failed_verifications: 0,
             active_problem: None,
             locked_at_unix: None,
+            bonus_problem: None,
+            bonus_assigned_at_unix: None,
+            difficulty_vote: None,
+            hinted_problem: None,
+            veto_extension_secs: 0,
+            heat_penalty: 0,
             solved_set: std::collections::HashSet::new(),
             solved_set_ready: false,
+            connections: 0,
+            disconnected_at: None,
+            last_active_at: None,
+            shown_working: false,
+            latency: Default::default(),
+            heat_relief_at: None,
+            idle_from: None,
+            idle_warned: false,
+            view: PlayerView::default(),
+        }
+    }
+
+    /// The player's home board, or in armada games the forward one. None if
+    /// there's no such board.
+    pub fn board_mut(&mut self, board: crate::protocol::Board) -> Option<&mut Board> {
+        match board {
+            crate::protocol::Board::Home => Some(&mut self.home),
+            crate::protocol::Board::Forward => self.forward.as_mut(),
         }
     }
 
+    /// This player's boards: home, then forward in armada games.
+    pub fn boards(&self) -> impl Iterator<Item = &Board> {
+        std::iter::once(&self.home).chain(self.forward.as_ref())
+    }
+
+    /// Ships on all of this player's boards.
+    pub fn fleet(&self) -> Vec<Ship> {
+        self.boards().flat_map(|b| &b.ships).cloned().collect()
+    }
+
+    /// Unhit ship cells on all of this player's boards; the game is lost at 0.
+    pub fn ship_cells_remaining(&self) -> u32 {
+        self.boards().map(|b| b.grid.ship_cells_remaining()).sum()
+    }
+
     pub fn fire(
         &mut self,
-        opponent: &mut Player,
+        target: &mut Board,
+        x: usize,
+        y: usize,
+        heat_threshold: u32,
+    ) -> Result<ShotOutcome, GameError> {
+        self.check_armed()?;
+
+        // Process shot on grid (out of bounds / repeat shots are errors, not turns)
+        let (result, sunk_cells) =
+            battle_cp_core::resolve_shot(&mut target.grid, &mut target.ships, x, y)?;
+        let outcome = self.record_shot(result, sunk_cells);
+        self.spend_shot(heat_threshold);
+        Ok(outcome)
+    }
+
+    /// Fire a special weapon from the inventory, aimed at (x, y). Each new cell
+    /// it covers counts as a shot in the stats, but the volley adds heat like
+    /// a single shot. The weapon is only used up if the volley is valid.
+    pub fn fire_special(
+        &mut self,
+        target: &mut Board,
+        weapon: SpecialWeapon,
         x: usize,
         y: usize,
         heat_threshold: u32,
-    ) -> Result<(String, bool, Option<Vec<[usize; 2]>>), &'static str> {
+    ) -> Result<Vec<([usize; 2], ShotOutcome)>, GameError> {
+        self.check_armed()?;
+        let slot = self
+            .inventory
+            .iter()
+            .position(|&w| w == weapon)
+            .ok_or(GameError::NoSpecialWeapon)?;
+
+        let shots =
+            battle_cp_core::resolve_special(&mut target.grid, &mut target.ships, weapon, x, y)?;
+        self.inventory.remove(slot);
+        let outcomes = shots
+            .into_iter()
+            .map(|(cell, (result, sunk_cells))| (cell, self.record_shot(result, sunk_cells)))
+            .collect();
+        self.spend_shot(heat_threshold);
+        Ok(outcomes)
+    }
+
+    fn check_armed(&self) -> Result<(), GameError> {
+        if self.ammo == Some(0) {
+            return Err(GameError::OutOfAmmo);
+        }
         if self.is_locked {
             if self.veto_started_at.is_some() {
-                return Err("Weapons Locked! Wait for veto timer.");
+                return Err(GameError::LockedVeto);
             } else {
-                return Err("Weapons Locked! Solve CP problem or use Veto.");
+                return Err(GameError::Locked);
             }
         }
+        Ok(())
+    }
 
-        // Process shot on grid
-        let mut sunk_this_shot = false;
-        let mut sunk_cells: Option<Vec<[usize; 2]>> = None;
-        let result = opponent.grid.receive_shot(x, y);
+    fn record_shot(&mut self, result: Shot, sunk_cells: Option<Vec<[usize; 2]>>) -> ShotOutcome {
+        let sunk_this_shot = sunk_cells.is_some();
 
-        // Handle invalid shots - return error instead of treating as valid
-        if result == "Out of bounds" || result == "Already fired here" {
-            return Err(if result == "Out of bounds" {
-                "Out of bounds"
-            } else {
-                "Already fired here"
-            });
+        // Update stats (a shot a shield blocked counts as neither)
+        match result {
+            Shot::Hit => {
+                self.stats.cells_hit += 1;
+                if sunk_this_shot {
+                    self.stats.ships_sunk += 1; // Shooter gets credit
+                }
+            }
+            Shot::Miss => self.stats.cells_missed += 1,
+            Shot::Blocked => {}
         }
+        (result, sunk_this_shot, sunk_cells)
+    }
 
-        // Update stats
-        if result == "Hit" {
-            self.stats.cells_hit += 1;
-
-            // Find which ship was hit and update it
-            for ship in &mut opponent.ships {
-                // Check if (x,y) is part of this ship
-                let is_hit = if ship.vertical {
-                    x == ship.x && y >= ship.y && y < ship.y + ship.size as usize
-                } else {
-                    y == ship.y && x >= ship.x && x < ship.x + ship.size as usize
-                };
-
-                if is_hit {
-                    ship.hits += 1;
-                    if ship.hits >= ship.size && !ship.sunk {
-                        ship.sunk = true;
-                        self.stats.ships_sunk += 1; // Shooter gets credit
-                        sunk_this_shot = true;
-                        // Collect the coordinates of every cell in the sunk ship
-                        let cells: Vec<[usize; 2]> = (0..ship.size as usize)
-                            .map(|i| {
-                                if ship.vertical {
-                                    [ship.x, ship.y + i]
-                                } else {
-                                    [ship.x + i, ship.y]
-                                }
-                            })
-                            .collect();
-                        sunk_cells = Some(cells);
-                    }
-                    break;
+    /// Spend an earned shield on one of our own unhit ship cells.
+    pub fn place_shield(&mut self, x: usize, y: usize) -> Result<(), GameError> {
+        if self.shields_available == 0 {
+            return Err(GameError::NoShieldAvailable);
+        }
+        self.home.grid.place_shield(x, y)?;
+        self.shields_available -= 1;
+        Ok(())
+    }
+
+    /// Note (or, with `None`, clear) what we make of an enemy cell. One
+    /// marker per cell; a new one replaces it.
+    pub fn set_marker(&mut self, x: usize, y: usize, kind: Option<crate::protocol::MarkerKind>) -> Result<(), GameError> {
+        if x >= battle_cp_core::GRID_SIZE || y >= battle_cp_core::GRID_SIZE {
+            return Err(GameError::OutOfBounds);
+        }
+        self.markers.retain(|m| (m.x, m.y) != (x, y));
+        if let Some(kind) = kind {
+            self.markers.push(crate::protocol::Marker { x, y, kind });
+        }
+        Ok(())
+    }
+
+    /// Pay for a valid shot or volley: a round in ammo mode (the last one
+    /// locks), otherwise heat.
+    fn spend_shot(&mut self, heat_threshold: u32) {
+        match self.ammo {
+            Some(ammo) => {
+                self.ammo = Some(ammo.saturating_sub(1));
+                if ammo <= 1 {
+                    self.lock();
                 }
             }
-        } else if result == "Miss" {
-            self.stats.cells_missed += 1;
+            None => self.add_heat(heat_threshold),
         }
+    }
 
+    fn add_heat(&mut self, heat_threshold: u32) {
         // Heat Logic: Every valid shot adds +1 heat
-        if result == "Hit" || result == "Miss" {
-            self.heat += 1;
-        }
+        self.heat += 1;
 
         // Lock at heat >= threshold
         if self.heat >= heat_threshold {
-            self.is_locked = true;
-            // Record wall-clock time of lock for submission timing validation
-            self.locked_at_unix = Some(
-                std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs()
-            );
+            self.lock();
         }
+    }
 
-        Ok((result, sunk_this_shot, sunk_cells))
+    fn lock(&mut self) {
+        self.is_locked = true;
+        self.last_active_at = Some(std::time::Instant::now());
+        // Record wall-clock time of lock for submission timing validation
+        self.locked_at_unix = Some(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        );
+    }
+
+    /// Ammo mode: add `rounds` after a solve. No-op under heat.
+    pub fn reload(&mut self, rounds: u32) {
+        if let Some(ammo) = self.ammo.as_mut() {
+            *ammo += rounds;
+        }
     }
 
     pub fn place_ship(
         &mut self,
-        mut ship: Ship,
+        ship: Ship,
         x: usize,
         y: usize,
         vertical: bool,
-    ) -> Result<(), &'static str> {
-        // Validation logic - check BOTH start position and ship end position
-        // Grid is 10x10, valid indices are 0-9
-        if x >= 10 || y >= 10 {
-            return Err("Ship starting position out of bounds");
-        }
-
-        // Check ship doesn't extend beyond grid
-        let end_x = if vertical { x } else { x + ship.size as usize };
-        let end_y = if vertical { y + ship.size as usize } else { y };
+    ) -> Result<(), GameError> {
+        // Ships may be adjacent — only overlap and leaving the grid are forbidden
+        battle_cp_core::place_ship(&mut self.home.grid, &mut self.home.ships, ship, x, y, vertical)?;
+        Ok(())
+    }
 
-        if end_x > 10 || end_y > 10 {
-            return Err("Ship extends beyond grid boundary");
-        }
+    /// Wait required between verifications: 10s, doubled for each failed
+    /// verification since the last success, capped at 320s.
+    pub fn verify_cooldown(&self) -> std::time::Duration {
+        VERIFY_COOLDOWN * 2u32.pow(self.failed_verifications.min(VERIFY_MAX_DOUBLINGS))
+    }
 
-        // Validate no overlap. Ships may be adjacent — only overlap is forbidden.
-        for i in 0..ship.size as usize {
-            let (cx, cy) = if vertical { (x, y + i) } else { (x + i, y) };
-            if self.grid.cells[cy][cx] != CellState::Empty {
-                return Err("Ship overlaps with another ship");
-            }
-        }
+    /// Heat at which this player's weapons lock: the game's threshold less
+    /// any hint penalty, never below 1.
+    pub fn heat_limit(&self, heat_threshold: u32) -> u32 {
+        heat_threshold.saturating_sub(self.heat_penalty).max(1)
+    }
 
-        // Update ship coords
-        ship.x = x;
-        ship.y = y;
-        ship.vertical = vertical;
+    /// Length of the veto `vetoes_used` counts last, with any hint extension.
+    pub fn veto_secs(&self, config: &GameConfig) -> u64 {
+        config.veto_penalty(self.vetoes_used.saturating_sub(1)).unwrap_or(900) + self.veto_extension_secs
+    }
 
-        // Place ship on grid
-        for i in 0..ship.size as usize {
-            let (cx, cy) = if vertical { (x, y + i) } else { (x + i, y) };
-            self.grid.cells[cy][cx] = CellState::Ship;
+    /// Pay for a hint on the assigned problem (free if this problem was
+    /// already hinted). Only while locked and not waiting out a veto.
+    pub fn buy_hint(&mut self, cost: HintCost) -> Result<AssignedProblem, GameError> {
+        if cost == HintCost::Off {
+            return Err(GameError::HintsDisabled);
         }
-
-        self.ships.push(ship);
-        Ok(())
+        if !self.is_locked {
+            return Err(GameError::NothingToVerify);
+        }
+        if self.veto_started_at.is_some() {
+            return Err(GameError::LockedVeto);
+        }
+        let problem = self.active_problem.clone().ok_or(GameError::NoProblemAssigned)?;
+        let key = (problem.contest_id, problem.index.clone());
+        if self.hinted_problem.as_ref() != Some(&key) {
+            match cost {
+                HintCost::LongerVeto => self.veto_extension_secs += HINT_VETO_EXTENSION_SECS,
+                HintCost::LowerMaxHeat => self.heat_penalty += 1,
+                HintCost::Off => {}
+            }
+            self.hinted_problem = Some(key);
+        }
+        Ok(problem)
     }
 
     pub fn unlock_weapons(&mut self) {
         self.is_locked = false;
         self.heat = 0;
         self.active_problem = None; // Clear problem commitment for next session
-        self.veto_started_at = None; // Clear veto timer — prevents spurious WeaponsUnlocked from ticker
+        self.veto_started_at = None; // Clear veto timer — prevents spurious WeaponsUnlocked from the veto deadline
         self.last_verification_attempt = None; // Allow immediate verify in next lock session
         self.locked_at_unix = None; // Clear lock timestamp
+        self.last_active_at = None;
+        self.heat_relief_at = None;
+        self.idle_from = Some(std::time::Instant::now()); // The AFK clock starts over
+    }
+
+    /// A verification found a genuine (wrong or too slow) attempt on the lock
+    /// problem: 1 heat comes off `interval` from now, unless relief is already
+    /// pending. Returns whether relief is pending.
+    pub fn note_attempt(&mut self, interval: std::time::Duration) -> bool {
+        if !self.is_locked || self.veto_started_at.is_some() {
+            return false;
+        }
+        self.heat_relief_at.get_or_insert(std::time::Instant::now() + interval);
+        true
+    }
+
+    /// Take off the relief that came due; unlocks once heat is under the
+    /// limit (keeping the remaining heat). Returns whether it unlocked.
+    pub fn relieve_heat(&mut self, heat_threshold: u32) -> bool {
+        self.heat_relief_at = None;
+        if !self.is_locked || self.veto_started_at.is_some() {
+            return false;
+        }
+        let heat = self.heat.saturating_sub(1);
+        if heat >= self.heat_limit(heat_threshold) {
+            self.heat = heat;
+            return false;
+        }
+        self.unlock_weapons();
+        self.heat = heat;
+        true
+    }
+
+    /// Locked, and locked or tried a verification within `WORKING_WINDOW`.
+    pub fn working(&self) -> bool {
+        self.working_until().is_some_and(|until| std::time::Instant::now() < until)
+    }
+
+    /// When `working` lapses without another verification attempt.
+    pub fn working_until(&self) -> Option<std::time::Instant> {
+        self.last_active_at.filter(|_| self.is_locked).map(|at| at + WORKING_WINDOW)
     }
 }
 
-/// Serialize a grid to a 2D Vec of cell-state strings for the protocol.
-fn grid_to_strings(grid: &Grid) -> Vec<Vec<String>> {
-    grid.cells
+/// A legal fleet in random positions.
+pub fn random_fleet(rng: &mut impl rand::Rng) -> Vec<battle_cp_core::ShipPlacement> {
+    let (mut grid, mut ships) = (Grid::new(), Vec::new());
+    battle_cp_core::FLEET
         .iter()
-        .map(|row| {
-            row.iter()
-                .map(|cell| match cell {
-                    CellState::Empty => "empty".to_string(),
-                    CellState::Ship  => "ship".to_string(),
-                    CellState::Hit   => "hit".to_string(),
-                    CellState::Miss  => "miss".to_string(),
-                })
-                .collect()
+        .map(|&size| loop {
+            let (vertical, x, y) = (rng.gen_bool(0.5), rng.gen_range(0..10), rng.gen_range(0..10));
+            let ship = battle_cp_core::ShipPlacement { x, y, size, vertical };
+            if battle_cp_core::place_ship(&mut grid, &mut ships, (&ship).into(), x, y, vertical).is_ok() {
+                break ship;
+            }
         })
         .collect()
 }
@@ -261,6 +959,135 @@ fn ships_to_revealed(ships: &[Ship]) -> Vec<crate::protocol::RevealedShip> {
         .collect()
 }
 
+/// Per-tick state for one player: a `GameUpdate`, preceded by a
+/// `PlayerJoined` fallback if the host somehow missed the guest's arrival.
+fn tick_messages(game: &Game, pid: Uuid) -> Vec<crate::protocol::ServerMessage> {
+    let is_p1 = game.player1.id == pid;
+    let p = if is_p1 {
+        &game.player1
+    } else if let Some(p2) = game.player2.as_ref().filter(|p| p.id == pid) {
+        p2
+    } else {
+        return vec![];
+    };
+
+    let mut msgs = Vec::with_capacity(2);
+
+    // FALLBACK: If this is P1 (Host) and P2 exists but game is still Waiting,
+    // send PlayerJoined to ensure Host knows about Guest
+    if let (true, Some(p2), GameStatus::Waiting) = (is_p1, game.player2.as_ref(), &game.status) {
+        msgs.push(crate::protocol::ServerMessage::PlayerJoined { player_id: p2.id });
+    }
+
+    let remaining = game.time_remaining_secs();
+
+    // Calculate veto time remaining if player is on veto timer
+    let veto_time_remaining = if let Some(veto_start) = p.veto_started_at {
+        let duration = p.veto_secs(&game.config);
+        let elapsed_veto = game.clock_now().saturating_duration_since(veto_start).as_secs();
+        if elapsed_veto < duration {
+            Some(duration - elapsed_veto)
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    msgs.push(crate::protocol::ServerMessage::GameUpdate {
+        status: game.status_line(p.locale),
+        is_active: true,
+        heat: p.heat,
+        is_locked: p.is_locked,
+        time_remaining_secs: remaining,
+        vetoes_remaining: game.config.max_vetoes.saturating_sub(p.vetoes_used),
+        veto_time_remaining_secs: veto_time_remaining,
+        phase_time_remaining_secs: crate::timers::phase_time_remaining_secs(game),
+        active_problem_contest_id: p.active_problem.as_ref().map(|ap| ap.contest_id),
+        active_problem_index: p.active_problem.as_ref().map(|ap| ap.index.clone()),
+        active_problem_name: p.active_problem.as_ref().map(|ap| ap.name.clone()),
+        active_problem_url: p.active_problem.as_ref().and_then(|ap| ap.url.clone()),
+        special_weapons: p.inventory.clone(),
+        shields_available: p.shields_available,
+        max_heat: game.game_started_at.map(|_| p.heat_limit(game.heat_threshold())),
+        ammo: p.ammo,
+    });
+    msgs
+}
+
+/// Everything a tick's `GameUpdate` is derived from, minus the clock. The actor
+/// only re-publishes player views when this changes (or as a keepalive); clients count the
+/// game and veto timers down locally in between.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TickState {
+    status: GameStatus,
+    game_started_at: Option<std::time::Instant>,
+    clock_paused: bool,
+    /// Moves with the heat ramp
+    heat_threshold: u32,
+    players: Vec<PlayerTickState>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct PlayerTickState {
+    id: Uuid,
+    heat: u32,
+    ammo: Option<u32>,
+    is_locked: bool,
+    vetoes_used: u32,
+    veto_started_at: Option<std::time::Instant>,
+    active_problem: Option<AssignedProblem>,
+    inventory: Vec<SpecialWeapon>,
+    shields_available: u32,
+    locale: crate::locale::Locale,
+}
+
+impl TickState {
+    pub fn of(game: &Game) -> Self {
+        let players = std::iter::once(&game.player1)
+            .chain(game.player2.as_ref())
+            .map(|p| PlayerTickState {
+                id: p.id,
+                heat: p.heat,
+                ammo: p.ammo,
+                is_locked: p.is_locked,
+                vetoes_used: p.vetoes_used,
+                veto_started_at: p.veto_started_at,
+                active_problem: p.active_problem.clone(),
+                inventory: p.inventory.clone(),
+                shields_available: p.shields_available,
+                locale: p.locale,
+            })
+            .collect();
+        Self {
+            status: game.status.clone(),
+            game_started_at: game.game_started_at,
+            clock_paused: game.clock_paused_at.is_some(),
+            heat_threshold: game.heat_threshold(),
+            players,
+        }
+    }
+}
+
+/// Serialize each player's tick messages once and publish them to that
+/// player's view, watched by all of their sockets.
+pub fn publish_views(game: &Game) {
+    for p in std::iter::once(&game.player1).chain(game.player2.as_ref()) {
+        let frames = tick_messages(game, p.id)
+            .iter()
+            .filter_map(|msg| serde_json::to_string(msg).ok())
+            .collect();
+        p.view.publish(frames);
+    }
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
 /// Build a GameOver message with full board-reveal data.
 /// Used from ws.rs and background.rs to avoid duplication.
 pub fn build_game_over(
@@ -276,16 +1103,23 @@ pub fn build_game_over(
                 p.stats.ships_sunk,
                 p.stats.cells_hit,
                 p.stats.problems_solved,
-                grid_to_strings(&p.grid),
-                ships_to_revealed(&p.ships),
+                p.home.grid.to_strings(true),
+                ships_to_revealed(&p.home.ships),
             )
         })
         .unwrap_or_else(|| (0, 0, 0, vec![vec!["empty".to_string(); 10]; 10], vec![]));
 
+    let (p1_forward, p2_forward) = (
+        game.player1.forward.as_ref(),
+        game.player2.as_ref().and_then(|p| p.forward.as_ref()),
+    );
+    let p1_locks = game.lock_counts(game.player1.id);
+    let p2_locks = game.player2.as_ref().map_or((0, 0), |p| game.lock_counts(p.id));
+
     let game_duration = game.config.game_duration_secs;
     let time_taken_secs = game
         .game_started_at
-        .map(|s| s.elapsed().as_secs().min(game_duration))
+        .map(|_| game.combat_secs().min(game_duration))
         .unwrap_or(game_duration);
     let (winner_score, loser_score) = if winner_id.is_some() {
         let w = (game_duration - time_taken_secs) as f64 + 1.0;
@@ -304,48 +1138,26 @@ pub fn build_game_over(
         p2_ships_sunk: p2_sunk,
         p2_cells_hit: p2_hit,
         p2_problems_solved: p2_solved,
-        p1_grid: grid_to_strings(&game.player1.grid),
-        p1_ships: ships_to_revealed(&game.player1.ships),
+        p1_locks_solved: p1_locks.1,
+        p1_locks_assigned: p1_locks.0,
+        p2_locks_solved: p2_locks.1,
+        p2_locks_assigned: p2_locks.0,
+        p1_grid: game.player1.home.grid.to_strings(true),
+        p1_ships: ships_to_revealed(&game.player1.home.ships),
         p2_grid,
         p2_ships,
+        coop_grid: game.coop_board.as_ref().map(|b| b.grid.to_strings(true)),
+        coop_ships: game.coop_board.as_ref().map(|b| ships_to_revealed(&b.ships)),
+        p1_forward_grid: p1_forward.map(|f| f.grid.to_strings(true)),
+        p1_forward_ships: p1_forward.map(|f| ships_to_revealed(&f.ships)),
+        p2_forward_grid: p2_forward.map(|f| f.grid.to_strings(true)),
+        p2_forward_ships: p2_forward.map(|f| ships_to_revealed(&f.ships)),
         time_taken_secs,
         winner_score,
         loser_score,
     }
 }
 
-impl Default for Grid {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl Grid {
-    pub fn new() -> Self {
-        Self {
-            cells: [[CellState::Empty; 10]; 10],
-        }
-    }
-
-    pub fn receive_shot(&mut self, x: usize, y: usize) -> String {
-        if x >= 10 || y >= 10 {
-            return "Out of bounds".to_string();
-        }
-
-        match self.cells[y][x] {
-            CellState::Empty => {
-                self.cells[y][x] = CellState::Miss;
-                "Miss".to_string()
-            }
-            CellState::Ship => {
-                self.cells[y][x] = CellState::Hit;
-                "Hit".to_string()
-            }
-            _ => "Already fired here".to_string(),
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -390,17 +1202,17 @@ mod tests {
         game.join(p2_id, "p2".to_string()).unwrap();
 
         // Add ships to both players
-        game.player1.ships = create_ships();
-        game.player2.as_mut().unwrap().ships = create_ships();
+        game.player1.home.ships = create_ships();
+        game.player2.as_mut().unwrap().home.ships = create_ships();
 
         // Case 1: P1 has more ships remaining (fewer sunk)
-        game.player1.ships[0].sunk = false; // 3 remaining
-        game.player2.as_mut().unwrap().ships[0].sunk = true; // 2 remaining
+        game.player1.home.ships[0].sunk = false; // 3 remaining
+        game.player2.as_mut().unwrap().home.ships[0].sunk = true; // 2 remaining
         assert_eq!(game.determine_winner(), TiebreakResult::Player1Wins);
 
         // Case 2: Ships equal, P2 has more hits
-        game.player1.ships[0].sunk = true; // Both have 2 remaining
-        game.player2.as_mut().unwrap().ships[0].sunk = true;
+        game.player1.home.ships[0].sunk = true; // Both have 2 remaining
+        game.player2.as_mut().unwrap().home.ships[0].sunk = true;
         game.player1.stats.cells_hit = 5;
         game.player2.as_mut().unwrap().stats.cells_hit = 10;
         assert_eq!(game.determine_winner(), TiebreakResult::Player2Wins);
@@ -410,4 +1222,481 @@ mod tests {
         game.player2.as_mut().unwrap().stats.cells_hit = 10;
         assert_eq!(game.determine_winner(), TiebreakResult::SuddenDeath);
     }
+
+    #[test]
+    fn test_abandon_and_revive() {
+        let (p1, p2) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut game = Game::new(p1, "p1".to_string(), GameConfig::default());
+        game.join(p2, "p2".to_string()).unwrap();
+        game.status = GameStatus::Playing;
+
+        game.connect(p1);
+        game.connect(p2);
+        game.connect(p2); // second tab
+        assert!(!game.disconnect(p1));
+        assert!(!game.disconnect(p2));
+        assert!(game.disconnect(p2));
+        assert_eq!(game.status, GameStatus::Abandoned);
+        assert!(game.abandoned_at.is_some());
+
+        game.connect(p1);
+        assert_eq!(game.status, GameStatus::Playing);
+        assert!(game.abandoned_at.is_none());
+
+        // Finished games never become abandoned
+        game.status = GameStatus::Finished;
+        assert!(!game.disconnect(p1));
+        assert_eq!(game.status, GameStatus::Finished);
+    }
+
+    #[test]
+    fn test_stuck_games_are_reaped() {
+        use crate::timers::{fire_due, stuck_deadline};
+        let ago = |secs| std::time::Instant::now().checked_sub(std::time::Duration::from_secs(secs));
+        let (p1, p2) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut game = Game::new(p1, "p1".to_string(), GameConfig::default());
+        game.join(p2, "p2".to_string()).unwrap();
+        game.status = GameStatus::PlacingShips;
+        game.connect(p1);
+        game.connect(p2);
+        assert_eq!(stuck_deadline(&game), None);
+
+        // One player gone during placement: reaped after a few minutes
+        game.disconnect(p2);
+        fire_due(&mut game);
+        assert_eq!(game.status, GameStatus::PlacingShips);
+        game.player2.as_mut().unwrap().disconnected_at = ago(181);
+        fire_due(&mut game);
+        assert_eq!(game.status, GameStatus::Abandoned);
+        let entries = game.audit_log.entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!((entries[0].player_id, entries[0].anomaly), (p2, crate::anticheat::Anomaly::Reaped));
+        game.connect(p2);
+        assert_eq!(game.status, GameStatus::PlacingShips);
+
+        // Both left during the countdown, so the last disconnect didn't abandon it
+        game.status = GameStatus::Countdown;
+        assert!(!game.disconnect(p1));
+        assert!(!game.disconnect(p2));
+        game.status = GameStatus::Playing;
+        game.player1.disconnected_at = ago(121);
+        fire_due(&mut game);
+        assert_eq!(game.status, GameStatus::Playing, "only reaped once both have been gone long enough");
+        game.player2.as_mut().unwrap().disconnected_at = ago(121);
+        fire_due(&mut game);
+        assert_eq!(game.status, GameStatus::Abandoned);
+        assert_eq!(game.abandoned_from, Some(GameStatus::Playing));
+    }
+
+    #[test]
+    fn test_heat_relief_after_genuine_attempt() {
+        use std::time::Duration;
+        let (p1, p2) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut game = Game::new(p1, "p1".to_string(), GameConfig::default());
+        game.join(p2, "p2".to_string()).unwrap();
+        game.status = GameStatus::Playing;
+        let mut rx = game.tx.subscribe();
+        for _ in 0..7 {
+            game.player1.add_heat(7);
+        }
+        assert!(game.player1.is_locked);
+
+        // Relief is due an interval after the first attempt; later ones don't push it back
+        assert!(game.player1.note_attempt(Duration::ZERO));
+        let due = game.player1.heat_relief_at;
+        assert!(game.player1.note_attempt(Duration::from_secs(60)));
+        assert_eq!(game.player1.heat_relief_at, due);
+        assert_eq!(crate::timers::next_deadline(&game), due);
+
+        // 1 heat off puts the player under the limit: unlocked, keeping the rest
+        crate::timers::fire_due(&mut game);
+        assert!(!game.player1.is_locked);
+        assert_eq!((game.player1.heat, game.player1.heat_relief_at), (6, None));
+        match rx.try_recv().map(|e| e.message) {
+            Ok(crate::protocol::ServerMessage::WeaponsUnlocked { player_id, reason }) => {
+                assert_eq!((player_id, reason.as_str()), (p1, "heat_relief"))
+            }
+            other => panic!("expected WeaponsUnlocked, got {:?}", other),
+        }
+
+        // Over a lowered limit it takes more than one; none while unlocked or on a veto
+        assert!(!game.player1.note_attempt(Duration::ZERO));
+        game.player1.add_heat(7);
+        game.player1.heat_penalty = 2;
+        assert!(game.player1.note_attempt(Duration::ZERO));
+        assert!(!game.player1.relieve_heat(7));
+        assert_eq!((game.player1.heat, game.player1.is_locked), (6, true));
+        game.player1.veto_started_at = Some(std::time::Instant::now());
+        assert!(!game.player1.note_attempt(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_coop_team_shares_weapons() {
+        use crate::protocol::ServerMessage;
+        let (p1, p2) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut game = Game::new(p1, "p1".to_string(), GameConfig { coop: true, ..GameConfig::default() });
+        game.join(p2, "p2".to_string()).unwrap();
+        game.place_coop_fleet();
+        assert_eq!(game.status, GameStatus::Initializing);
+        assert!(game.player1.ships_placed && game.player2.as_ref().unwrap().ships_placed);
+        assert_eq!(game.coop_board.as_ref().unwrap().grid.ship_cells_remaining(), 17);
+
+        // One player overheating locks the team on the same problem
+        let mut rx = game.tx.subscribe();
+        game.status = GameStatus::Playing;
+        for _ in 0..7 {
+            game.player1.add_heat(7);
+        }
+        let problem = AssignedProblem { contest_id: 1, index: "A".to_string(), name: "A".to_string(), rating: 800, url: None };
+        game.player1.active_problem = Some(problem.clone());
+        game.share_weapons(p1);
+        let p2_state = game.player2.as_ref().unwrap();
+        assert_eq!((p2_state.heat, p2_state.is_locked), (7, true));
+        assert_eq!(p2_state.active_problem, Some(problem));
+        let msgs: Vec<_> = std::iter::from_fn(|| rx.try_recv().ok().map(|e| e.message)).collect();
+        assert!(matches!(msgs[..], [
+            ServerMessage::WeaponsLocked { player_id: a },
+            ServerMessage::ActivityEvent { kind: crate::protocol::ActivityKind::Locked, .. },
+            ServerMessage::ProblemAssigned { player_id: b, contest_id: 1, .. },
+        ] if a == p2 && b == p2));
+
+        // Either one's solve unlocks both
+        game.player_mut(p2).unwrap().unlock_weapons();
+        game.share_weapons(p2);
+        assert!(!game.player1.is_locked && game.player1.active_problem.is_none());
+        match rx.try_recv().map(|e| e.message) {
+            Ok(ServerMessage::WeaponsUnlocked { player_id, reason }) => {
+                assert_eq!((player_id, reason.as_str()), (p1, "teammate"))
+            }
+            other => panic!("expected WeaponsUnlocked, got {:?}", other),
+        }
+
+        // Duel players keep their own weapons
+        game.config.coop = false;
+        game.player1.add_heat(7);
+        game.share_weapons(p1);
+        assert_eq!(game.player2.as_ref().unwrap().heat, 0);
+    }
+
+    #[test]
+    fn test_lock_history_counts_in_game_over() {
+        use crate::protocol::ServerMessage;
+        let (p1, p2) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut game = Game::new(p1, "p1".to_string(), GameConfig::default());
+        game.join(p2, "p2".to_string()).unwrap();
+        let problem = |contest_id| AssignedProblem { contest_id, index: "A".to_string(), name: "A".to_string(), rating: 800, url: None };
+
+        game.lock_opened(p1, &problem(1));
+        game.lock_closed(p1, LockOutcome::Solved);
+        game.lock_opened(p1, &problem(2));
+        game.lock_closed(p1, LockOutcome::Vetoed);
+        game.lock_closed(p1, LockOutcome::Solved); // nothing open: no-op
+        game.lock_opened(p1, &problem(3));
+        game.lock_opened(p2, &problem(4));
+        game.lock_closed(p2, LockOutcome::Solved);
+        assert_eq!((game.lock_counts(p1), game.lock_counts(p2)), ((3, 1), (1, 1)));
+
+        game.finish(None, "TimeUp");
+        let outcomes: Vec<_> = game.lock_history.iter().map(|r| r.outcome).collect();
+        assert_eq!(outcomes, [
+            Some(LockOutcome::Solved),
+            Some(LockOutcome::Vetoed),
+            Some(LockOutcome::Unsolved),
+            Some(LockOutcome::Solved),
+        ]);
+        assert!(game.lock_history.iter().all(|r| r.secs_taken.is_some()));
+        match game.game_over_msg {
+            Some(ServerMessage::GameOver { p1_locks_solved, p1_locks_assigned, p2_locks_assigned, .. }) => {
+                assert_eq!((p1_locks_solved, p1_locks_assigned, p2_locks_assigned), (1, 3, 1))
+            }
+            other => panic!("expected GameOver, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_seed_reproduces_hidden_fleet() {
+        let fleet = |seed| {
+            let config = GameConfig { coop: true, seed: Some(seed), ..GameConfig::default() };
+            let mut game = Game::new(Uuid::new_v4(), "p1".to_string(), config);
+            game.join(Uuid::new_v4(), "p2".to_string()).unwrap();
+            game.place_coop_fleet();
+            game.coop_board.unwrap().ships.iter().flat_map(|s| s.cells()).collect::<Vec<_>>()
+        };
+        assert_eq!(fleet(42), fleet(42));
+        assert_ne!(fleet(42), fleet(43));
+    }
+
+    #[test]
+    fn test_difficulty_vote_needs_both_players() {
+        use crate::protocol::DifficultyVote::{Down, Up};
+        let (p1, p2) = (Uuid::new_v4(), Uuid::new_v4());
+        let config = GameConfig { difficulty: 3, ..GameConfig::default() };
+        let mut game = Game::new(p1, "p1".to_string(), config);
+        game.join(p2, "p2".to_string()).unwrap();
+
+        assert_eq!(game.vote_difficulty(p1, Up), Ok(None));
+        assert_eq!(game.vote_difficulty(p2, Down), Ok(None), "split vote");
+        assert_eq!(game.vote_difficulty(p1, Down), Ok(Some(2)));
+        assert_eq!(game.config.difficulty, 2);
+        assert_eq!(game.vote_difficulty(p2, Down), Ok(None), "votes clear after a change");
+
+        game.config.difficulty = 4;
+        assert_eq!(game.vote_difficulty(p1, Up), Err(GameError::DifficultyAtLimit));
+        game.config = GameConfig { difficulty_mode: DifficultyMode::Cf, difficulty: 800, ..GameConfig::default() };
+        assert_eq!(game.vote_difficulty(p1, Down), Err(GameError::DifficultyAtLimit));
+        game.vote_difficulty(p1, Up).unwrap();
+        assert_eq!(game.vote_difficulty(p2, Up), Ok(Some(900)));
+    }
+
+    #[test]
+    fn test_working_lapses_without_verification() {
+        let config = GameConfig { activity_indicator: true, ..GameConfig::default() };
+        let mut game = Game::new(Uuid::new_v4(), "p1".to_string(), config);
+        let mut rx = game.tx.subscribe();
+        game.player1.add_heat(1);
+        game.sync_working();
+        assert!(game.player1.shown_working);
+        game.sync_working();
+
+        game.player1.last_active_at = std::time::Instant::now().checked_sub(WORKING_WINDOW);
+        game.sync_working();
+        let working: Vec<bool> = std::iter::from_fn(|| rx.try_recv().ok())
+            .filter_map(|event| match event.message {
+                crate::protocol::ServerMessage::Working { working, .. } => Some(working),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(working, vec![true, false], "one broadcast per change");
+    }
+
+    #[test]
+    fn test_outage_pauses_clock_while_locked() {
+        use std::time::{Duration, Instant};
+        let config = GameConfig { pause_on_outage: true, ..GameConfig::default() };
+        let mut game = Game::new(Uuid::new_v4(), "p1".to_string(), config);
+        game.join(Uuid::new_v4(), "p2".to_string()).unwrap();
+        game.status = GameStatus::Playing;
+        // Connected, so the stuck-game deadline stays out of the way
+        game.player1.connections = 1;
+        let started = Instant::now() - Duration::from_secs(60);
+        let veto = Instant::now() - Duration::from_secs(30);
+        game.game_started_at = Some(started);
+        let mut rx = game.tx.subscribe();
+
+        // Nobody locked: an outage doesn't matter yet
+        game.sync_outage(true);
+        assert!(game.clock_paused_at.is_none());
+
+        game.player1.is_locked = true;
+        game.player1.veto_started_at = Some(veto);
+        let relief = Instant::now() + Duration::from_secs(40);
+        game.player1.heat_relief_at = Some(relief);
+        let idle = Instant::now() - Duration::from_secs(10);
+        game.player2.as_mut().unwrap().idle_from = Some(idle);
+        game.sync_outage(true);
+        game.sync_outage(true);
+        let paused_at = game.clock_paused_at.expect("paused");
+        let remaining = game.time_remaining_secs();
+        assert_eq!(crate::timers::next_deadline(&game), None, "vetoes, relief, AFK and the end of the game wait");
+
+        game.clock_paused_at = Some(paused_at - Duration::from_secs(20));
+        game.sync_outage(false);
+        assert!(game.clock_paused_at.is_none());
+        assert!(game.game_started_at.unwrap() >= started + Duration::from_secs(20));
+        assert!(game.player1.veto_started_at.unwrap() >= veto + Duration::from_secs(20));
+        assert!(game.player1.heat_relief_at.unwrap() >= relief + Duration::from_secs(20));
+        assert!(game.player2.as_ref().unwrap().idle_from.unwrap() >= idle + Duration::from_secs(20));
+        assert!(game.time_remaining_secs() >= remaining);
+
+        let kinds: Vec<&str> = std::iter::from_fn(|| rx.try_recv().ok())
+            .filter_map(|event| match event.message {
+                crate::protocol::ServerMessage::ClockPaused { .. } => Some("paused"),
+                crate::protocol::ServerMessage::ClockResumed { paused_secs } => {
+                    assert!(paused_secs >= 20);
+                    Some("resumed")
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(kinds, vec!["paused", "resumed"]);
+    }
+
+    #[test]
+    fn test_bitboard_fire_and_sink() {
+        let mut shooter = Player::new(Uuid::new_v4(), "a".to_string());
+        let mut target = Player::new(Uuid::new_v4(), "b".to_string());
+        for ship in create_ships() {
+            let (x, y, v) = (ship.x, ship.y, ship.vertical);
+            target.place_ship(ship, x, y, v).unwrap();
+        }
+        assert_eq!(target.home.grid.ship_cells_remaining(), 12);
+        let overlapping = Ship { size: 2, hits: 0, sunk: false, x: 2, y: 0, vertical: true };
+        assert_eq!(target.place_ship(overlapping, 2, 0, true), Err(GameError::ShipOverlap));
+
+        // Sink the 3-cell ship on row 2
+        for x in 0..2 {
+            let (res, sunk, _) = shooter.fire(&mut target.home, x, 2, 100).unwrap();
+            assert_eq!((res, sunk), (Shot::Hit, false));
+        }
+        let (_, sunk, cells) = shooter.fire(&mut target.home, 2, 2, 100).unwrap();
+        assert!(sunk);
+        assert_eq!(cells.unwrap(), vec![[0, 2], [1, 2], [2, 2]]);
+        assert!(target.home.ships[2].sunk);
+        assert_eq!(target.home.grid.ship_cells_remaining(), 9);
+
+        assert_eq!(shooter.fire(&mut target.home, 9, 9, 100).unwrap().0, Shot::Miss);
+        assert_eq!(shooter.fire(&mut target.home, 9, 9, 100), Err(GameError::AlreadyFired));
+        assert_eq!(shooter.fire(&mut target.home, 10, 0, 100), Err(GameError::OutOfBounds));
+        assert_eq!(target.home.grid.cell(9, 9), CellState::Miss);
+        assert_eq!(target.home.grid.to_strings(false)[0][0], "empty");
+        assert_eq!(target.home.grid.to_strings(true)[0][0], "ship");
+    }
+
+    #[test]
+    fn test_fire_special_uses_up_weapon() {
+        let mut shooter = Player::new(Uuid::new_v4(), "a".to_string());
+        let mut target = Player::new(Uuid::new_v4(), "b".to_string());
+        for ship in create_ships() {
+            let (x, y, v) = (ship.x, ship.y, ship.vertical);
+            target.place_ship(ship, x, y, v).unwrap();
+        }
+        let cross = SpecialWeapon::Cross;
+        assert_eq!(shooter.fire_special(&mut target.home, cross, 4, 1, 100), Err(GameError::NoSpecialWeapon));
+
+        shooter.inventory.push(cross);
+        assert_eq!(shooter.fire_special(&mut target.home, cross, 4, 4, 100).unwrap().len(), 5);
+        assert!(shooter.inventory.is_empty());
+        assert_eq!(shooter.heat, 1, "a volley heats like one shot");
+        assert_eq!(shooter.stats.cells_missed, 5);
+
+        // Rows 0-2 hold ships from column 0: every cell of a cross at (1, 1) hits
+        shooter.inventory.push(cross);
+        let shots = shooter.fire_special(&mut target.home, cross, 1, 1, 1).unwrap();
+        let hits = shots.iter().filter(|(_, (res, _, _))| *res == Shot::Hit).count();
+        assert_eq!(hits, 5);
+        assert_eq!(shooter.stats.cells_hit, 5);
+        assert!(shooter.is_locked, "heat threshold still applies");
+
+        shooter.inventory.push(cross);
+        assert_eq!(shooter.fire_special(&mut target.home, cross, 8, 8, 100), Err(GameError::Locked));
+        assert_eq!(shooter.inventory, vec![cross], "a refused volley keeps the weapon");
+    }
+
+    #[test]
+    fn test_shield_blocks_one_shot() {
+        let mut shooter = Player::new(Uuid::new_v4(), "a".to_string());
+        let mut target = Player::new(Uuid::new_v4(), "b".to_string());
+        for ship in create_ships() {
+            let (x, y, v) = (ship.x, ship.y, ship.vertical);
+            target.place_ship(ship, x, y, v).unwrap();
+        }
+        assert_eq!(target.place_shield(0, 0), Err(GameError::NoShieldAvailable));
+        target.shields_available = 1;
+        assert_eq!(target.place_shield(9, 9), Err(GameError::InvalidShield));
+        target.place_shield(0, 0).unwrap();
+        assert_eq!(target.shields_available, 0);
+
+        let (res, sunk, _) = shooter.fire(&mut target.home, 0, 0, 100).unwrap();
+        assert_eq!((res, sunk), (Shot::Blocked, false));
+        assert_eq!((shooter.stats.cells_hit, shooter.stats.cells_missed), (0, 0));
+        assert_eq!(shooter.heat, 1, "a blocked shot still heats");
+        assert_eq!(shooter.fire(&mut target.home, 0, 0, 100).unwrap().0, Shot::Hit);
+    }
+
+    #[test]
+    fn test_hint_costs_once_per_problem() {
+        let config = GameConfig::default();
+        let mut p = Player::new(Uuid::new_v4(), "p".to_string());
+        assert_eq!(p.buy_hint(HintCost::LongerVeto), Err(GameError::NothingToVerify));
+        p.is_locked = true;
+        assert_eq!(p.buy_hint(HintCost::Off), Err(GameError::HintsDisabled));
+        assert_eq!(p.buy_hint(HintCost::LongerVeto), Err(GameError::NoProblemAssigned));
+
+        p.active_problem = Some(AssignedProblem {
+            contest_id: 1,
+            index: "A".to_string(),
+            name: "Theatre Square".to_string(),
+            rating: 1000,
+            url: None,
+        });
+        assert_eq!(p.buy_hint(HintCost::LongerVeto).unwrap().rating, 1000);
+        p.buy_hint(HintCost::LongerVeto).unwrap();
+        assert_eq!(p.veto_extension_secs, 120, "asking again is free");
+        p.vetoes_used = 1;
+        assert_eq!(p.veto_secs(&config), 180 + 120);
+
+        p.buy_hint(HintCost::LowerMaxHeat).unwrap();
+        assert_eq!(p.heat_penalty, 0, "already hinted");
+        p.active_problem.as_mut().unwrap().contest_id = 2;
+        p.buy_hint(HintCost::LowerMaxHeat).unwrap();
+        assert_eq!(p.heat_limit(config.heat_threshold), 6);
+        p.heat_penalty = 50;
+        assert_eq!(p.heat_limit(config.heat_threshold), 1);
+    }
+
+    /// The ramp's threshold takes over at each step's minute, and a shot past
+    /// the lowered limit locks.
+    #[test]
+    fn test_heat_ramp_lowers_threshold() {
+        use crate::state::HeatStep;
+        let config = GameConfig {
+            heat_threshold: 10,
+            heat_ramp: vec![
+                HeatStep { after_mins: 15, heat_threshold: 7 },
+                HeatStep { after_mins: 30, heat_threshold: 5 },
+            ],
+            ..GameConfig::default()
+        };
+        let at = |mins: u64| config.heat_threshold_at(mins * 60);
+        assert_eq!([at(0), at(14), at(15), at(29), at(30), at(45)], [10, 10, 7, 7, 5, 5]);
+
+        let mut game = Game::new(Uuid::new_v4(), "p1".to_string(), config);
+        game.join(Uuid::new_v4(), "p2".to_string()).unwrap();
+        assert_eq!(game.heat_threshold(), 10, "the base threshold before combat");
+        game.game_started_at = Some(std::time::Instant::now() - std::time::Duration::from_secs(20 * 60));
+        assert_eq!(game.heat_threshold(), 7);
+
+        let threshold = game.heat_threshold();
+        let p2 = game.player2.as_mut().unwrap();
+        game.player1.heat = 6;
+        game.player1.fire(&mut p2.home, 9, 9, threshold).unwrap();
+        assert!(game.player1.is_locked, "7 heat locks 20 minutes in");
+    }
+
+    /// In ammo mode shots spend rounds instead of heat, the last one locks,
+    /// and a reload after the solve re-arms.
+    #[test]
+    fn test_ammo_mode_spends_and_reloads() {
+        use crate::state::AmmoConfig;
+        let config = GameConfig { ammo: Some(AmmoConfig { starting: 2, per_solve: 3 }), ..GameConfig::default() };
+        let mut game = Game::new(Uuid::new_v4(), "p1".to_string(), config);
+        game.join(Uuid::new_v4(), "p2".to_string()).unwrap();
+        assert_eq!(game.player2.as_ref().unwrap().ammo, Some(2));
+
+        let p2 = game.player2.as_mut().unwrap();
+        game.player1.fire(&mut p2.home, 9, 9, 7).unwrap();
+        assert_eq!((game.player1.ammo, game.player1.heat, game.player1.is_locked), (Some(1), 0, false));
+        game.player1.fire(&mut p2.home, 8, 9, 7).unwrap();
+        assert_eq!(game.player1.ammo, Some(0));
+        assert!(game.player1.is_locked, "the last round locks");
+        assert_eq!(game.player1.fire(&mut p2.home, 7, 9, 7).unwrap_err(), GameError::OutOfAmmo);
+
+        game.player1.unlock_weapons();
+        game.player1.reload(3);
+        game.player1.fire(&mut p2.home, 7, 9, 7).unwrap();
+        assert_eq!(game.player1.ammo, Some(2));
+    }
+
+    #[test]
+    fn test_verify_cooldown_backs_off() {
+        let mut p = Player::new(Uuid::new_v4(), "p".to_string());
+        let secs = |p: &Player| p.verify_cooldown().as_secs();
+        assert_eq!(secs(&p), 10);
+        p.failed_verifications = 1;
+        assert_eq!(secs(&p), 20);
+        p.failed_verifications = 3;
+        assert_eq!(secs(&p), 80);
+        p.failed_verifications = 50;
+        assert_eq!(secs(&p), 320, "backoff is capped");
+    }
 }
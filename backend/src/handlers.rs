@@ -1,26 +1,317 @@
-use crate::state::{AppState, DifficultyMode, Game, GameConfig};
-use axum::{extract::State, http::StatusCode, response::Json};
-use serde::Deserialize;
+use crate::state::{
+    AmmoConfig, AppState, DifficultyMode, Game, GameConfig, GameStatus, HeatStep, HintCost, SuddenDeathMode,
+};
+use crate::pagination::PageQuery;
+use crate::rate_limit::ClientIp;
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use uuid::Uuid;
 
-#[derive(Deserialize)]
-pub struct CreateGameRequest {
-    pub cf_handle: String,
+/// Game settings shared by `POST /api/game` and organizer bulk creation
+/// (see `admin.rs`). Out-of-range numbers are clamped; a bad schedule is refused.
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct GameSettings {
     pub difficulty: Option<u32>,
     pub difficulty_mode: Option<DifficultyMode>,
     pub heat_threshold: Option<u32>,
+    /// Heat ramp: the threshold drops to each step's from its minute on
+    pub heat_ramp: Option<Vec<HeatStep>>,
+    /// Ammo mode: limited rounds, reloaded by solves, instead of heat
+    pub ammo: Option<AmmoConfig>,
+    /// Tie-break: `first_hit` (default) or `accuracy` over 10 shots each
+    pub sudden_death: Option<SuddenDeathMode>,
     pub game_duration_mins: Option<u32>,
     pub veto_strictness: Option<String>, // "low", "medium", "high"
+    /// Seconds locked out per veto, overriding `veto_strictness`
+    pub veto_penalties: Option<Vec<u64>>,
     pub max_vetoes: Option<u32>,
+    /// Announce the lobby link on Discord
+    pub public: Option<bool>,
+    /// Unix time (seconds) to start combat at, for a scheduled match
+    pub scheduled_start: Option<u64>,
+    /// Solves within this many minutes of the lock earn a cross shot; 0 disables
+    pub fast_solve_mins: Option<u32>,
+    /// Fog of war: shot results don't report sunk ships until the game ends
+    pub hide_sunk_info: Option<bool>,
+    /// What a hint costs (`longer_veto` / `lower_max_heat`); hints are off by default
+    pub hint_cost: Option<HintCost>,
+    /// Open the spectator referee feed (`/ws/{game_id}/referee`)
+    pub referee_feed: Option<bool>,
+    /// Show each player when the opponent is busy with a problem
+    pub activity_indicator: Option<bool>,
+    /// Broadcast both players' round-trip times
+    pub show_latency: Option<bool>,
+    /// Pause the clock while the judge is down and a player is locked
+    pub pause_on_outage: Option<bool>,
+    /// Mercy rule: minutes after a wrong answer / time limit on the lock
+    /// problem until 1 heat comes off; 0 or absent disables it
+    pub heat_relief_mins: Option<u32>,
+    /// AFK rule: minutes an unlocked player may send nothing before an
+    /// `IdleWarning` (and as long again before they forfeit); 0 or absent disables it
+    pub idle_timeout_mins: Option<u32>,
+    /// Anti-snipe: seconds a shot in the last minute of sudden death adds to
+    /// its clock; 0 or absent disables it
+    pub anti_snipe_secs: Option<u32>,
+    /// Anti-snipe also extends the last minute of regulation time
+    pub anti_snipe_regulation: Option<bool>,
+    /// Co-op: both players hunt one server-placed fleet against the clock
+    pub coop: Option<bool>,
+    /// Armada: two fleets each (home and forward); games default to 90 minutes
+    pub armada: Option<bool>,
+    /// Judge id (`codeforces` by default; see `judge.rs`)
+    pub judge: Option<String>,
+    /// Virtual contest: reveal the game's whole problem set at the start
+    pub reveal_problems: Option<bool>,
+    /// Rating gate: only guests rated at least this may join
+    pub min_rating: Option<u32>,
+    /// Rating gate: only guests rated at most this may join
+    pub max_rating: Option<u32>,
+}
+
+/// How far ahead a match can be scheduled (7 days).
+const MAX_SCHEDULE_AHEAD_SECS: u64 = 7 * 24 * 3600;
+/// Upper bound on `max_vetoes`, and on the length of `veto_penalties`.
+pub const MAX_VETOES: u32 = 20;
+/// Most steps a heat ramp may have.
+const MAX_HEAT_STEPS: usize = 5;
+/// Cap on both ammo settings.
+const MAX_AMMO: u32 = 50;
+/// Cap on the anti-snipe extension per shot.
+const MAX_ANTI_SNIPE_SECS: u32 = 30;
+/// Rating gates are capped here (above every Codeforces rating).
+const MAX_RATING: u32 = 4000;
+/// Allowed range for each custom veto penalty (30 seconds to 30 minutes).
+const VETO_PENALTY_SECS: std::ops::RangeInclusive<u64> = 30..=1800;
+
+impl GameSettings {
+    /// The game config, plus the scheduled start as an `Instant`. The judge
+    /// must be one of `judges`.
+    pub fn build(
+        &self,
+        judges: &crate::judge::JudgeRegistry,
+    ) -> Result<(GameConfig, Option<std::time::Instant>), &'static str> {
+        // Explicit penalties, or the strictness preset
+        let veto_penalties = match &self.veto_penalties {
+            Some(penalties) => {
+                if penalties.is_empty() || penalties.len() > MAX_VETOES as usize {
+                    return Err("veto_penalties must list 1 to 20 durations");
+                }
+                if !penalties.iter().all(|p| VETO_PENALTY_SECS.contains(p)) {
+                    return Err("veto_penalties must each be 30 to 1800 seconds");
+                }
+                penalties.clone()
+            }
+            None => match self.veto_strictness.as_deref() {
+                Some("low") => vec![60, 120, 180],   // 1, 2, 3 min
+                Some("high") => vec![300, 420, 600], // 5, 7, 10 min
+                _ => vec![180, 300, 420],            // 3, 5, 7 min (default/medium)
+            },
+        };
+
+        let judge = self.judge.as_deref().unwrap_or(crate::judge::CODEFORCES);
+        if judges.get(judge).is_none() {
+            return Err("judge is not available on this server");
+        }
+        // Rating gate: only on judges that rate their players
+        let min_rating = self.min_rating.map(|r| r.min(MAX_RATING));
+        let max_rating = self.max_rating.map(|r| r.min(MAX_RATING));
+        if min_rating.is_some() || max_rating.is_some() {
+            if min_rating.zip(max_rating).is_some_and(|(min, max)| min > max) {
+                return Err("min_rating must not be above max_rating");
+            }
+            if !judges.get(judge).is_some_and(|j| j.has_ratings()) {
+                return Err("this judge has no ratings to gate on");
+            }
+        }
+
+        // Heat ramp: steps in order, within the longest game
+        let heat_ramp = self.heat_ramp.clone().unwrap_or_default();
+        if heat_ramp.len() > MAX_HEAT_STEPS
+            || heat_ramp.windows(2).any(|w| w[0].after_mins >= w[1].after_mins)
+            || !heat_ramp.iter().all(|s| (1..=120).contains(&s.after_mins))
+        {
+            return Err("heat_ramp must list up to 5 steps, 1 to 120 minutes in, in increasing order");
+        }
+        let heat_ramp: Vec<HeatStep> = heat_ramp
+            .into_iter()
+            .map(|s| HeatStep { heat_threshold: s.heat_threshold.clamp(3, 20), ..s })
+            .collect();
+
+        // Ammo mode replaces heat, so nothing else may tune heat
+        let ammo = self.ammo.map(|a| AmmoConfig {
+            starting: a.starting.clamp(1, MAX_AMMO),
+            per_solve: a.per_solve.clamp(1, MAX_AMMO),
+        });
+        if ammo.is_some()
+            && (!heat_ramp.is_empty()
+                || self.heat_relief_mins.unwrap_or(0) > 0
+                || self.hint_cost == Some(HintCost::LowerMaxHeat))
+        {
+            return Err("ammo mode replaces heat: heat_ramp, heat_relief_mins and the lower_max_heat hint cost don't apply");
+        }
+
+        let anti_snipe_secs = match self.anti_snipe_secs.unwrap_or(0) {
+            0 => None,
+            s => Some(s.min(MAX_ANTI_SNIPE_SECS) as u64),
+        };
+        if self.anti_snipe_regulation.unwrap_or(false) && anti_snipe_secs.is_none() {
+            return Err("anti_snipe_regulation needs anti_snipe_secs");
+        }
+
+        let armada = self.armada.unwrap_or(false);
+        if armada && self.coop.unwrap_or(false) {
+            return Err("armada and coop can't be combined");
+        }
+
+        let mode = self.difficulty_mode.clone().unwrap_or(DifficultyMode::Band);
+
+        // Validate difficulty range depends on the mode
+        let difficulty = match mode {
+            DifficultyMode::Cf => self.difficulty.unwrap_or(800).clamp(800, 3500),
+            DifficultyMode::Band => self.difficulty.unwrap_or(0).clamp(0, 4),
+        };
+
+        let config = GameConfig {
+            difficulty,
+            difficulty_mode: mode,
+            heat_threshold: self.heat_threshold.unwrap_or(7).clamp(3, 20),
+            heat_ramp,
+            ammo,
+            sudden_death: self.sudden_death.unwrap_or_default(),
+            // Prevent overflow: clamp minutes first, then convert
+            game_duration_secs: self
+                .game_duration_mins
+                .map(|m| m.clamp(1, 120)) // Clamp to 1-120 minutes first
+                .map(|m| (m as u64).saturating_mul(60)) // Safe conversion to u64
+                .unwrap_or(if armada { 90 * 60 } else { 45 * 60 })
+                .clamp(60, 7200), // Final clamp to 1-120 minutes in seconds
+            veto_penalties,
+            // 0 = no vetoes: a locked player has to solve
+            max_vetoes: self.max_vetoes.unwrap_or(3).min(MAX_VETOES),
+            public: self.public.unwrap_or(false),
+            scheduled_start: self.scheduled_start,
+            fast_solve_secs: match self.fast_solve_mins.unwrap_or(5) {
+                0 => None,
+                m => Some(m.min(30) as u64 * 60),
+            },
+            hide_sunk_info: self.hide_sunk_info.unwrap_or(false),
+            hint_cost: self.hint_cost.unwrap_or_default(),
+            referee_feed: self.referee_feed.unwrap_or(false),
+            activity_indicator: self.activity_indicator.unwrap_or(false),
+            show_latency: self.show_latency.unwrap_or(false),
+            pause_on_outage: self.pause_on_outage.unwrap_or(false),
+            heat_relief_secs: match self.heat_relief_mins.unwrap_or(0) {
+                0 => None,
+                m => Some(m.min(30) as u64 * 60),
+            },
+            idle_timeout_secs: match self.idle_timeout_mins.unwrap_or(0) {
+                0 => None,
+                m => Some(m.min(30) as u64 * 60),
+            },
+            anti_snipe_secs,
+            anti_snipe_regulation: self.anti_snipe_regulation.unwrap_or(false),
+            coop: self.coop.unwrap_or(false),
+            armada,
+            // Never from the request (see `GameConfig::seed`)
+            seed: None,
+            judge: judge.to_string(),
+            reveal_problems: self.reveal_problems.unwrap_or(false),
+            min_rating,
+            max_rating,
+        };
+
+        // Scheduled start must be in the future, within a week
+        let scheduled_start_at = match self.scheduled_start {
+            None => None,
+            Some(at) => {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                if at <= now || at - now > MAX_SCHEDULE_AHEAD_SECS {
+                    return Err("scheduled_start must be a Unix time within the next 7 days");
+                }
+                Some(std::time::Instant::now() + std::time::Duration::from_secs(at - now))
+            }
+        };
+        Ok((config, scheduled_start_at))
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CreateGameRequest {
+    pub cf_handle: String,
+    /// Discord user ID (snowflake) to ping when an opponent joins
+    pub discord_id: Option<String>,
+    /// Callback URLs for lifecycle events, signed with `webhook_secret` (see `webhooks.rs`)
+    #[serde(default)]
+    pub webhooks: Vec<crate::webhooks::Subscription>,
+    pub webhook_secret: Option<String>,
+    #[serde(flatten)]
+    pub settings: GameSettings,
 }
 
 pub async fn create_game(
     State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    headers: axum::http::HeaderMap,
     Json(payload): Json<CreateGameRequest>,
-) -> (StatusCode, Json<Value>) {
+) -> Response {
     let handle = payload.cf_handle.trim();
 
+    let idempotency_key = match headers.get("idempotency-key") {
+        None => None,
+        Some(value) => match value.to_str().ok().filter(|k| crate::idempotency::valid_key(k)) {
+            Some(key) => Some(key.to_string()),
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({ "error": "Idempotency-Key must be 1-255 visible ASCII characters" })),
+                )
+                    .into_response();
+            }
+        },
+    };
+
+    if state.bans.is_banned(handle, ip) {
+        tracing::info!("Refused game creation for banned handle {:?} / ip {:?}", handle, ip);
+        let e = crate::error::GameError::Banned;
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": e.to_string(), "code": e.code() })),
+        )
+            .into_response();
+    }
+
+    // IDEMPOTENCY: a retried request gets the lobby it already created
+    let live = |game_id: Uuid| state.games.contains_key(&game_id);
+    let fingerprint = crate::idempotency::Fingerprint::new(
+        ip,
+        &(&payload.discord_id, &payload.webhooks, &payload.webhook_secret, &payload.settings),
+    );
+    if let Some(key) = idempotency_key.as_deref() {
+        match state.idempotency_keys.lookup(handle, key, &fingerprint, live) {
+            Ok(Some(created)) => return created_response(&state, created, true),
+            Ok(None) => {}
+            Err(crate::idempotency::KeyReused) => return idempotency_key_reused(),
+        }
+    }
+
+    // BACKPRESSURE: global active-game cap and per-IP open lobby cap
+    if let Err((error, retry_after)) = state.check_capacity(ip) {
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_after.to_string())],
+            Json(json!({ "error": error, "retry_after_secs": retry_after })),
+        )
+            .into_response();
+    }
+
     // Trust the user's CF handle verification removed for performance.
     // Entering a wrong handle is self-punishing already 
 
@@ -40,70 +331,859 @@ pub async fn create_game(
         } else {
             entry.1 += 1;
             if entry.1 > 3 {
-                return (
+                let quota = crate::rate_limit::Quota {
+                    limit: 3,
+                    remaining: 0,
+                    reset: window.saturating_sub(now.duration_since(entry.0)),
+                };
+                let mut resp = (
                     StatusCode::TOO_MANY_REQUESTS,
-                    Json(json!({ "error": "Too many games created. Please wait a few minutes." })),
-                );
+                    [(header::RETRY_AFTER, quota.reset_secs().to_string())],
+                    Json(json!({
+                        "error": "Too many games created. Please wait a few minutes.",
+                        "retry_after_secs": quota.reset_secs(),
+                    })),
+                )
+                    .into_response();
+                quota.write_headers(resp.headers_mut());
+                return resp;
             }
         }
     }
 
     let player_id = Uuid::new_v4();
 
-    // Parse veto strictness to penalties
-    let veto_penalties = match payload.veto_strictness.as_deref() {
-        Some("low") => [60, 120, 180],    // 1, 2, 3 min
-        Some("high") => [300, 420, 600],  // 5, 7, 10 min
-        _ => [180, 300, 420],             // 3, 5, 7 min (default/medium)
+    let (config, scheduled_start_at) = match payload.settings.build(&state.judges) {
+        Ok(built) => built,
+        Err(error) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({ "error": error }))).into_response();
+        }
     };
 
-    let mode = payload.difficulty_mode.unwrap_or(DifficultyMode::Band);
+    // Discord user IDs are 17–20 digit snowflakes; anything else can't be pinged
+    let discord_id = payload.discord_id.as_deref().map(str::trim).filter(|id| !id.is_empty());
+    if let Some(id) = discord_id {
+        if !(17..=20).contains(&id.len()) || !id.bytes().all(|b| b.is_ascii_digit()) {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": "discord_id must be a numeric Discord user ID" })),
+            )
+                .into_response();
+        }
+    }
 
-    // Validate difficulty range depends on the mode
-    let difficulty = match mode {
-        DifficultyMode::Cf => payload.difficulty.unwrap_or(800).clamp(800, 3500),
-        DifficultyMode::Band => payload.difficulty.unwrap_or(0).clamp(0, 4),
+    let webhooks = match crate::webhooks::Webhooks::new(payload.webhooks, payload.webhook_secret, &state.config.webhooks) {
+        Ok(webhooks) => webhooks,
+        Err(error) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({ "error": error }))).into_response();
+        }
     };
+    if let Some(webhooks) = &webhooks {
+        if let Err(error) = webhooks.check_resolved().await {
+            return (StatusCode::BAD_REQUEST, Json(json!({ "error": error }))).into_response();
+        }
+    }
+
+    let mut new_game = Game::new(player_id, handle.to_string(), config);
+    new_game.creator_ip = ip;
+    new_game.host_discord_id = discord_id.map(str::to_string);
+    new_game.webhooks = webhooks;
+    new_game.scheduled_start_at = scheduled_start_at;
+    let created = crate::idempotency::Created { game_id: new_game.id, player_id };
 
-    let config = GameConfig {
-        difficulty,
-        difficulty_mode: mode,
-        heat_threshold: payload.heat_threshold.unwrap_or(7).clamp(3, 20),
-        // Prevent overflow: clamp minutes first, then convert
-        game_duration_secs: payload
-            .game_duration_mins
-            .map(|m| m.clamp(1, 120)) // Clamp to 1-120 minutes first
-            .map(|m| (m as u64).saturating_mul(60)) // Safe conversion to u64
-            .unwrap_or(45 * 60)
-            .clamp(60, 7200), // Final clamp to 1-120 minutes in seconds
-        veto_penalties,
-        max_vetoes: payload.max_vetoes.unwrap_or(3).clamp(1, 20),
+    // A concurrent retry may have claimed the key while this one was building
+    if let Some(key) = idempotency_key.as_deref() {
+        match state.idempotency_keys.claim(handle, key, fingerprint, created, live) {
+            Ok(Some(existing)) => return created_response(&state, existing, true),
+            Ok(None) => {}
+            Err(crate::idempotency::KeyReused) => return idempotency_key_reused(),
+        }
+    }
+
+    state.insert_game(new_game);
+    created_response(&state, created, false)
+}
+
+/// 422 for an `Idempotency-Key` already used by a different request.
+fn idempotency_key_reused() -> Response {
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(json!({ "error": "This Idempotency-Key was already used for a different request" })),
+    )
+        .into_response()
+}
+
+/// 201 body for a new lobby, with a fresh host token. `replayed` marks a
+/// response served from an earlier request's `Idempotency-Key`.
+fn created_response(state: &AppState, created: crate::idempotency::Created, replayed: bool) -> Response {
+    let token = state
+        .sessions
+        .issue(created.player_id, created.game_id, crate::auth::Role::Host);
+
+    let body = Json(json!({
+        "game_id": created.game_id,
+        "player_id": created.player_id,
+        "token": token
+    }));
+    if replayed {
+        (StatusCode::CREATED, [(crate::idempotency::REPLAYED_HEADER, "true")], body).into_response()
+    } else {
+        (StatusCode::CREATED, body).into_response()
+    }
+}
+
+#[derive(Deserialize)]
+pub struct SuggestConfigQuery {
+    pub handle: String,
+    /// Judge whose rating to go by (`codeforces` by default)
+    pub judge: Option<String>,
+}
+
+/// Settings for a host rated `rating` (`None` if unrated), as
+/// (difficulty in `cf` mode, heat threshold): lock problems a little below
+/// their rating, so a lock costs minutes rather than the game, and more
+/// shots between locks for newer players, who solve slower.
+pub fn suggested_settings(rating: Option<u32>) -> (u32, u32) {
+    let Some(rating) = rating else {
+        return (800, 9);
+    };
+    let difficulty = (rating.saturating_sub(200) / 100 * 100).clamp(800, 3500);
+    let heat_threshold = match rating {
+        0..=1199 => 9,
+        1200..=1599 => 8,
+        1600..=1999 => 7,
+        _ => 6,
     };
+    (difficulty, heat_threshold)
+}
 
-    let new_game = Game::new(player_id, handle.to_string(), config);
-    let game_id = new_game.id;
+/// Suggested lobby settings for a host who doesn't know what to pick: their
+/// rating on the judge and `suggested_settings` for it, ready to pass to
+/// `POST /api/game`.
+pub async fn suggest_config(
+    State(state): State<AppState>,
+    axum::extract::Query(query): axum::extract::Query<SuggestConfigQuery>,
+) -> Response {
+    let handle = query.handle.trim();
+    if !is_valid_cf_handle(handle) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Invalid handle" }))).into_response();
+    }
+    let judge_id = query.judge.as_deref().unwrap_or(crate::judge::CODEFORCES);
+    let judge = match state.judges.get(judge_id) {
+        Some(judge) if judge.has_ratings() => judge,
+        Some(_) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({ "error": "this judge has no ratings to go by" }))).into_response();
+        }
+        None => {
+            return (StatusCode::BAD_REQUEST, Json(json!({ "error": "judge is not available on this server" }))).into_response();
+        }
+    };
+    let rating = match judge.rating(handle).await {
+        Ok(rating) => rating,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({ "error": e.to_string(), "code": e.code() })),
+            )
+                .into_response()
+        }
+    };
+    let (difficulty, heat_threshold) = suggested_settings(rating);
+    Json(json!({
+        "handle": handle,
+        "judge": judge_id,
+        "rating": rating,
+        "difficulty_mode": DifficultyMode::Cf,
+        "difficulty": difficulty,
+        "heat_threshold": heat_threshold,
+    }))
+    .into_response()
+}
 
-    state.games.write().await.insert(game_id, new_game);
+/// Fetch problems for a specific Codeforces contest. Responses carry an ETag
+/// and `Cache-Control`, and a matching `If-None-Match` gets a 304 (see
+/// `response_cache.rs`).
+pub async fn get_contest_problems(
+    State(state): State<AppState>,
+    axum::extract::Path(contest_id): axum::extract::Path<i32>,
+    headers: axum::http::HeaderMap,
+) -> Response {
+    if !(1..=999_999).contains(&contest_id) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Invalid contest id" })),
+        )
+            .into_response();
+    }
+    let cached = match state.contest_responses.get(contest_id) {
+        Some(cached) => cached,
+        None => match state.cf_client.fetch_contest_problems(contest_id).await {
+            Ok(problems) => {
+                let body = serde_json::to_vec(&json!({ "problems": problems })).unwrap_or_default();
+                state.contest_responses.insert(contest_id, body.into())
+            }
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(json!({ "error": e.to_string(), "code": e.code() })),
+                )
+                    .into_response()
+            }
+        },
+    };
+    let cache_headers = [
+        (header::ETAG, cached.etag.clone()),
+        (header::CACHE_CONTROL, state.contest_responses.cache_control()),
+    ];
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| cached.matches(v));
+    if not_modified {
+        return (StatusCode::NOT_MODIFIED, cache_headers).into_response();
+    }
+    (
+        StatusCode::OK,
+        cache_headers,
+        [(header::CONTENT_TYPE, "application/json")],
+        cached.body,
+    )
+        .into_response()
+}
 
+/// Public game snapshot so the frontend can render the lobby/join page
+/// before opening a WebSocket.
+pub async fn get_game(
+    State(state): State<AppState>,
+    axum::extract::Path(game_id): axum::extract::Path<Uuid>,
+) -> (StatusCode, Json<Value>) {
+    let snapshot = match state.game(game_id) {
+        Some(handle) => handle.call(|game| game.snapshot()).await,
+        None => state.archive.get(game_id).map(|archived| archived.snapshot.clone()),
+    };
+    match snapshot {
+        Some(snapshot) => (StatusCode::OK, Json(json!(snapshot))),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Game not found" })),
+        ),
+    }
+}
+
+/// A finished game's replay log: every broadcast with its event ID and server
+/// time (see `events.rs`), plus the game's RNG seed and lock history. 409 while
+/// the game is still running.
+pub async fn get_game_events(
+    State(state): State<AppState>,
+    axum::extract::Path(game_id): axum::extract::Path<Uuid>,
+    axum::extract::Query(page): axum::extract::Query<PageQuery>,
+    axum::extract::Query(filter): axum::extract::Query<crate::events::EventFilter>,
+) -> (StatusCode, Json<Value>) {
+    let not_found = || (StatusCode::NOT_FOUND, Json(json!({ "error": "Game not found" })));
+    let (events, seed, lock_history) = if let Some(handle) = state.game(game_id) {
+        if handle.status() != GameStatus::Finished {
+            return (
+                StatusCode::CONFLICT,
+                Json(json!({ "error": "The event log opens when the game ends" })),
+            );
+        }
+        let Some((seed, lock_history)) = handle.call(|g| (g.seed, g.lock_history.clone())).await else {
+            return not_found();
+        };
+        (handle.tx.events(), seed, lock_history)
+    } else if let Some(archived) = state.archive.get(game_id) {
+        (archived.events.clone(), archived.seed, archived.lock_history.clone())
+    } else {
+        return not_found();
+    };
+    let events = match crate::events::page(events, &filter, &page) {
+        Ok(events) => events,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))),
+    };
     (
-        StatusCode::CREATED,
+        StatusCode::OK,
         Json(json!({
             "game_id": game_id,
-            "player_id": player_id
+            "seed": seed,
+            "lock_history": lock_history,
+            "events": events.items,
+            "next_cursor": events.next_cursor,
         })),
     )
 }
 
-/// Fetch problems for a specific Codeforces contest
-pub async fn get_contest_problems(
+#[derive(Deserialize, Default)]
+pub struct ReportQuery {
+    /// "json" (default) or "markdown"
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+/// A finished game's match report (see `match_report.rs`), as JSON or, with
+/// `?format=markdown`, rendered Markdown. 409 while the game is still running
+/// or if it never reached combat.
+pub async fn get_game_report(
     State(state): State<AppState>,
-    axum::extract::Path(contest_id): axum::extract::Path<i32>,
+    axum::extract::Path(game_id): axum::extract::Path<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<ReportQuery>,
+) -> Response {
+    let markdown = match query.format.as_deref() {
+        None | Some("json") => false,
+        Some("markdown" | "md") => true,
+        Some(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": "format must be json or markdown" })),
+            )
+                .into_response();
+        }
+    };
+    let report = if let Some(handle) = state.game(game_id) {
+        if handle.status() != GameStatus::Finished {
+            return (
+                StatusCode::CONFLICT,
+                Json(json!({ "error": "The report is ready when the game ends" })),
+            )
+                .into_response();
+        }
+        let events = handle.tx.events();
+        handle.call(move |g| crate::match_report::build(g, &events)).await.flatten()
+    } else if let Some(archived) = state.archive.get(game_id) {
+        archived.report.clone()
+    } else {
+        return (StatusCode::NOT_FOUND, Json(json!({ "error": "Game not found" }))).into_response();
+    };
+    let Some(report) = report else {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "This game never reached combat" })),
+        )
+            .into_response();
+    };
+    if markdown {
+        (
+            StatusCode::OK,
+            [(header::CONTENT_TYPE, "text/markdown; charset=utf-8")],
+            crate::match_report::render_markdown(&report),
+        )
+            .into_response()
+    } else {
+        (StatusCode::OK, Json(json!(report))).into_response()
+    }
+}
+
+/// A finished game's training plan (see `training.rs`): each player's lock
+/// problems, the ones they missed, and similar ones to practise. 409 while the game is running.
+/// If Codeforces can't be reached, the misses come without recommendations.
+pub async fn get_game_analysis(
+    State(state): State<AppState>,
+    axum::extract::Path(game_id): axum::extract::Path<Uuid>,
 ) -> (StatusCode, Json<Value>) {
-    match state.cf_client.fetch_contest_problems(contest_id).await {
-        Ok(problems) => (StatusCode::OK, Json(json!({ "problems": problems }))),
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({ "error": e.to_string() })),
-        ),
+    use crate::training::{missed_problems, recommend, MAX_RECOMMENDATIONS};
+
+    let (on_codeforces, players) = if let Some(handle) = state.game(game_id) {
+        if handle.status() != GameStatus::Finished {
+            return (
+                StatusCode::CONFLICT,
+                Json(json!({ "error": "The analysis is ready when the game ends" })),
+            );
+        }
+        handle.call(|g| crate::archive::analysis_inputs(g)).await.unwrap_or_default()
+    } else if let Some(archived) = state.archive.get(game_id) {
+        archived.analysis.clone()
+    } else {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "Game not found" })),
+        );
+    };
+
+    let players: Vec<_> = players
+        .into_iter()
+        .map(|(id, cf_handle, solved, locks)| {
+            let missed = missed_problems(id, &locks);
+            (id, cf_handle, solved, locks, missed)
+        })
+        .collect();
+    // Recommendations come from the Codeforces problemset, so other judges' games get none
+    let problemset = if on_codeforces && players.iter().any(|(.., missed)| !missed.is_empty()) {
+        state
+            .cf_client
+            .fetch_problemset()
+            .await
+            .inspect_err(|e| tracing::warn!(target: "cf", "Analysis: no problemset for game {}: {}", game_id, e))
+            .ok()
+    } else {
+        None
+    };
+    let players: Vec<Value> = players
+        .into_iter()
+        .map(|(player_id, cf_handle, solved, locks, missed)| {
+            let recommendations = problemset
+                .as_deref()
+                .map(|set| recommend(&missed, set, &solved, MAX_RECOMMENDATIONS))
+                .unwrap_or_default();
+            json!({
+                "player_id": player_id,
+                "cf_handle": cf_handle,
+                "locks": locks,
+                "missed": missed,
+                "recommendations": recommendations,
+            })
+        })
+        .collect();
+    (
+        StatusCode::OK,
+        Json(json!({ "game_id": game_id, "players": players })),
+    )
+}
+
+/// The boards as a PNG, for sharing results (see `board_image.rs`).
+pub async fn get_board_png(
+    State(state): State<AppState>,
+    axum::extract::Path(game_id): axum::extract::Path<Uuid>,
+) -> Response {
+    board_image(state, game_id, "image/png", crate::board_image::render_png).await
+}
+
+/// The boards as a labelled SVG (see `board_image.rs`).
+pub async fn get_board_svg(
+    State(state): State<AppState>,
+    axum::extract::Path(game_id): axum::extract::Path<Uuid>,
+) -> Response {
+    board_image(state, game_id, "image/svg+xml", |b| crate::board_image::render_svg(b).into_bytes()).await
+}
+
+/// 409 until the game ends, unless spectators may follow it live. Final
+/// boards never change, so they may be cached.
+async fn board_image(
+    state: AppState,
+    game_id: Uuid,
+    content_type: &'static str,
+    render: fn(&[crate::board_image::Board]) -> Vec<u8>,
+) -> Response {
+    let visible = match state.game(game_id) {
+        Some(handle) => handle.call(|g| crate::board_image::visible_boards(g)).await,
+        None => state.archive.get(game_id).map(|archived| archived.boards.clone()),
+    };
+    let Some(visible) = visible else {
+        return (StatusCode::NOT_FOUND, Json(json!({ "error": "Game not found" }))).into_response();
+    };
+    let Some(visible) = visible else {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "The boards are shown once the game ends" })),
+        )
+            .into_response();
+    };
+    let cache = if visible.is_final { "public, max-age=86400" } else { "no-store" };
+    (
+        StatusCode::OK,
+        [(header::CONTENT_TYPE, content_type), (header::CACHE_CONTROL, cache)],
+        render(&visible.boards),
+    )
+        .into_response()
+}
+
+/// Filter for `list_player_games`: `?status=Waiting`.
+#[derive(Deserialize, Default)]
+pub struct PlayerGamesFilter {
+    pub status: Option<GameStatus>,
+}
+
+/// List a player's games still in play (not finished or abandoned) with
+/// their seat's join link, so someone who closed their tab can find their way
+/// back. Games are picked by their summaries; only the player's own are
+/// asked for a snapshot. Paged by game ID.
+pub async fn list_player_games(
+    State(state): State<AppState>,
+    axum::extract::Path(player_id): axum::extract::Path<Uuid>,
+    axum::extract::Query(page): axum::extract::Query<PageQuery>,
+    axum::extract::Query(filter): axum::extract::Query<PlayerGamesFilter>,
+) -> (StatusCode, Json<Value>) {
+    let mut entries: Vec<(Uuid, Value)> = Vec::new();
+    for handle in state.all_games() {
+        let summary = handle.summary();
+        let role = if summary.host_id == player_id {
+            "host"
+        } else if summary.guest_id == Some(player_id) {
+            "guest"
+        } else {
+            continue;
+        };
+        let status = summary.status;
+        if matches!(status, GameStatus::Finished | GameStatus::Abandoned)
+            || filter.status.as_ref().is_some_and(|s| *s != status)
+        {
+            continue;
+        }
+        let entry = handle
+            .call(move |g| Some((g.player(player_id)?.cf_handle.clone(), g.snapshot())))
+            .await
+            .flatten();
+        if let Some((cf_handle, snapshot)) = entry {
+            entries.push((handle.id, json!({
+                "role": role,
+                "join_url": seat_link(&state, handle.id, player_id, &cf_handle, None),
+                "game": snapshot,
+            })));
+        }
+    }
+
+    match page.paginate(entries, |(id, _)| id.to_string()) {
+        Ok(page) => {
+            let games: Vec<Value> = page.items.into_iter().map(|(_, entry)| entry).collect();
+            (StatusCode::OK, Json(json!({ "games": games, "next_cursor": page.next_cursor })))
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))),
+    }
+}
+
+#[derive(Deserialize)]
+pub struct CancelGameQuery {
+    pub player_id: Uuid,
+    /// Session token, if not sent as `Authorization: Bearer`
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+/// Cancel a lobby that is still waiting for an opponent.
+/// Only the host (player 1) may cancel. Connected sockets receive a
+/// `GameOver { reason: "LobbyCancelled" }` before the game is removed.
+pub async fn cancel_game(
+    State(state): State<AppState>,
+    axum::extract::Path(game_id): axum::extract::Path<Uuid>,
+    axum::extract::Query(query): axum::extract::Query<CancelGameQuery>,
+    headers: axum::http::HeaderMap,
+) -> (StatusCode, Json<Value>) {
+    let authorized = crate::auth::request_token(&headers, query.token)
+        .and_then(|t| state.sessions.verify(&t))
+        .is_some_and(|c| c.allows(query.player_id, game_id));
+    if !authorized {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({ "error": "Missing or invalid session token" })),
+        );
+    }
+
+    let result = state
+        .with_game(game_id, move |game| {
+            // SECURITY: Only the host may cancel (the token proves who the caller is)
+            if game.player1.id != query.player_id {
+                return Err((StatusCode::FORBIDDEN, "Only the host can cancel this lobby"));
+            }
+
+            if game.status != GameStatus::Waiting {
+                return Err((
+                    StatusCode::CONFLICT,
+                    "Lobby can only be cancelled while waiting for an opponent",
+                ));
+            }
+
+            game.finish(None, "LobbyCancelled");
+            Ok(())
+        })
+        .await;
+
+    match result {
+        None => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({ "error": "Game not found" })),
+            )
+        }
+        Some(Err((status, error))) => return (status, Json(json!({ "error": error }))),
+        Some(Ok(())) => {}
     }
+
+    // Removing the game stops its actor, which drops the broadcast sender, so
+    // subscribers see the GameOver above followed by channel close.
+    state.remove_game(game_id);
+    tracing::info!("Game {:?} lobby cancelled by host", game_id);
+
+    (StatusCode::OK, Json(json!({ "cancelled": game_id })))
+}
+
+/// Codeforces handles are 3–24 letters, digits, `_`, `-` or `.` (which also
+/// keeps them safe to put in join links).
+pub fn is_valid_cf_handle(handle: &str) -> bool {
+    (3..=24).contains(&handle.len())
+        && handle
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'-' | b'.'))
+}
+
+/// A join link that signs the player straight into their seat (the frontend
+/// stores the credentials and drops them from the URL). Hosts also get their
+/// session token; guests receive theirs on joining.
+pub fn seat_link(state: &AppState, game_id: Uuid, player_id: Uuid, cf_handle: &str, token: Option<&str>) -> String {
+    let base = state.config.server.frontend_url.as_deref().unwrap_or_default();
+    let mut link = format!(
+        "{}/game/{}?player_id={}&cf_handle={}",
+        base.trim_end_matches('/'),
+        game_id,
+        player_id,
+        cf_handle
+    );
+    if let Some(token) = token {
+        link.push_str("&token=");
+        link.push_str(token);
+    }
+    link
+}
+
+// ── Rooms (see `rooms.rs`) ─────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+pub struct CreateRoomRequest {
+    pub name: String,
+}
+
+/// Body of join / accept / decline, and query of leave.
+#[derive(Deserialize)]
+pub struct RoomMemberRequest {
+    pub cf_handle: String,
+    /// Room token from joining, if not sent as `Authorization: Bearer`
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ChallengeRequest {
+    pub from: String,
+    pub to: String,
+    /// `from`'s room token, if not sent as `Authorization: Bearer`
+    #[serde(default)]
+    pub token: Option<String>,
+    #[serde(flatten)]
+    pub settings: GameSettings,
+}
+
+fn room_error(e: crate::error::RoomError) -> Response {
+    use crate::error::RoomError;
+    let status = match e {
+        RoomError::NotFound | RoomError::ChallengeNotFound => StatusCode::NOT_FOUND,
+        RoomError::InvalidName => StatusCode::BAD_REQUEST,
+        RoomError::NameTaken | RoomError::RoomFull | RoomError::HandleTaken => StatusCode::CONFLICT,
+        RoomError::Unauthorized => StatusCode::UNAUTHORIZED,
+        RoomError::TooManyRooms => StatusCode::SERVICE_UNAVAILABLE,
+        RoomError::NotMember | RoomError::NotChallenged => StatusCode::FORBIDDEN,
+    };
+    (status, Json(json!({ "error": e.to_string(), "code": e.code() }))).into_response()
+}
+
+fn banned() -> Response {
+    let e = crate::error::GameError::Banned;
+    (
+        StatusCode::FORBIDDEN,
+        Json(json!({ "error": e.to_string(), "code": e.code() })),
+    )
+        .into_response()
+}
+
+pub async fn create_room(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateRoomRequest>,
+) -> Response {
+    match state.rooms.create(&payload.name, state.config.limits.max_rooms) {
+        Ok(slug) => {
+            tracing::info!("Room {:?} created", slug);
+            (StatusCode::CREATED, Json(json!({ "slug": slug }))).into_response()
+        }
+        Err(e) => room_error(e),
+    }
+}
+
+/// Members, open challenges, the leaderboard, and snapshots of the room's
+/// games that are still on the server.
+pub async fn get_room(
+    State(state): State<AppState>,
+    axum::extract::Path(slug): axum::extract::Path<String>,
+) -> Response {
+    state.rooms.retain_games(&slug, |id| state.games.contains_key(id));
+    let Some(room) = state.rooms.snapshot(&slug) else {
+        return room_error(crate::error::RoomError::NotFound);
+    };
+    let mut games = Vec::with_capacity(room.games.len());
+    for game_id in &room.games {
+        if let Some(snapshot) = state.with_game(*game_id, |g| g.snapshot()).await {
+            games.push(snapshot);
+        }
+    }
+    Json(json!({
+        "slug": room.slug,
+        "name": room.name,
+        "created_at": room.created_at,
+        "members": room.members,
+        "challenges": room.challenges,
+        "leaderboard": room.leaderboard,
+        "games": games,
+    }))
+    .into_response()
+}
+
+/// A room's leaderboard on its own, paged by rank (wins, then fewest losses).
+pub async fn get_room_leaderboard(
+    State(state): State<AppState>,
+    axum::extract::Path(slug): axum::extract::Path<String>,
+    axum::extract::Query(page): axum::extract::Query<PageQuery>,
+) -> Response {
+    let Some(room) = state.rooms.snapshot(&slug) else {
+        return room_error(crate::error::RoomError::NotFound);
+    };
+    let ranked: Vec<(u64, crate::rooms::Standing)> = (1..).zip(room.leaderboard).collect();
+    match page.paginate(ranked, |(rank, _)| *rank) {
+        Ok(page) => {
+            let leaderboard: Vec<Value> = page
+                .items
+                .into_iter()
+                .map(|(rank, standing)| json!({ "rank": rank, "standing": standing }))
+                .collect();
+            Json(json!({ "slug": slug, "leaderboard": leaderboard, "next_cursor": page.next_cursor })).into_response()
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, Json(json!({ "error": e }))).into_response(),
+    }
+}
+
+/// Join a room. Returns the member's room token, needed for everything they
+/// do in the room; rejoining as an existing member requires it too.
+pub async fn join_room(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    axum::extract::Path(slug): axum::extract::Path<String>,
+    headers: axum::http::HeaderMap,
+    Json(payload): Json<RoomMemberRequest>,
+) -> Response {
+    let handle = payload.cf_handle.trim();
+    if !is_valid_cf_handle(handle) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Invalid Codeforces handle" }))).into_response();
+    }
+    if state.bans.is_banned(handle, ip) {
+        return banned();
+    }
+    let token = crate::auth::request_token(&headers, payload.token);
+    match state.rooms.join(&slug, handle, token.as_deref()) {
+        Ok(token) => (StatusCode::OK, Json(json!({ "joined": slug, "token": token }))).into_response(),
+        Err(e) => room_error(e),
+    }
+}
+
+pub async fn leave_room(
+    State(state): State<AppState>,
+    axum::extract::Path(slug): axum::extract::Path<String>,
+    axum::extract::Query(query): axum::extract::Query<RoomMemberRequest>,
+    headers: axum::http::HeaderMap,
+) -> Response {
+    let token = crate::auth::request_token(&headers, query.token);
+    match state.rooms.leave(&slug, query.cf_handle.trim(), token.as_deref()) {
+        Ok(()) => (StatusCode::OK, Json(json!({ "left": slug }))).into_response(),
+        Err(e) => room_error(e),
+    }
+}
+
+/// Challenge another member: opens a lobby hosted by the challenger with the
+/// guest seat held for the challenged member. Returns the challenger's seat.
+pub async fn create_challenge(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    axum::extract::Path(slug): axum::extract::Path<String>,
+    headers: axum::http::HeaderMap,
+    Json(payload): Json<ChallengeRequest>,
+) -> Response {
+    let token = crate::auth::request_token(&headers, payload.token);
+    let (from, to) = match state.rooms.members(&slug, payload.from.trim(), payload.to.trim(), token.as_deref()) {
+        Ok(pair) => pair,
+        Err(e) => return room_error(e),
+    };
+    if from.eq_ignore_ascii_case(&to) {
+        let e = crate::error::GameError::SelfPlay;
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": e.to_string(), "code": e.code() }))).into_response();
+    }
+    if state.bans.is_banned(&from, ip) {
+        return banned();
+    }
+    if let Err((error, retry_after)) = state.check_capacity(ip) {
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_after.to_string())],
+            Json(json!({ "error": error, "retry_after_secs": retry_after })),
+        )
+            .into_response();
+    }
+    let (config, scheduled_start_at) = match payload.settings.build(&state.judges) {
+        Ok(built) => built,
+        Err(error) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({ "error": error }))).into_response();
+        }
+    };
+
+    let (host_id, guest_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let mut game = Game::new(host_id, from.clone(), config);
+    game.creator_ip = ip;
+    game.room = Some(slug.clone());
+    game.reserved_guest = Some((guest_id, to.clone()));
+    game.scheduled_start_at = scheduled_start_at;
+    let game_id = game.id;
+    state.insert_game(game);
+    state.rooms.add_challenge(&slug, game_id, from.clone(), to.clone(), guest_id);
+    let token = state.sessions.issue(host_id, game_id, crate::auth::Role::Host);
+    tracing::info!("Room {:?}: {} challenged {} (game {:?})", slug, from, to, game_id);
+
+    (
+        StatusCode::CREATED,
+        Json(json!({
+            "game_id": game_id,
+            "player_id": host_id,
+            "token": token,
+            "join_url": seat_link(&state, game_id, host_id, &from, Some(&token)),
+        })),
+    )
+        .into_response()
+}
+
+/// The challenged member claims the reserved guest seat.
+pub async fn accept_challenge(
+    State(state): State<AppState>,
+    ClientIp(ip): ClientIp,
+    axum::extract::Path((slug, game_id)): axum::extract::Path<(String, Uuid)>,
+    headers: axum::http::HeaderMap,
+    Json(payload): Json<RoomMemberRequest>,
+) -> Response {
+    let handle = payload.cf_handle.trim();
+    if state.bans.is_banned(handle, ip) {
+        return banned();
+    }
+    let token = crate::auth::request_token(&headers, payload.token);
+    match state.rooms.accept(&slug, game_id, handle, token.as_deref()) {
+        Ok(guest_id) => Json(json!({
+            "game_id": game_id,
+            "player_id": guest_id,
+            "join_url": seat_link(&state, game_id, guest_id, handle, None),
+        }))
+        .into_response(),
+        Err(e) => room_error(e),
+    }
+}
+
+/// Decline (challenged member) or withdraw (challenger) an open challenge;
+/// its lobby is closed.
+pub async fn decline_challenge(
+    State(state): State<AppState>,
+    axum::extract::Path((slug, game_id)): axum::extract::Path<(String, Uuid)>,
+    headers: axum::http::HeaderMap,
+    Json(payload): Json<RoomMemberRequest>,
+) -> Response {
+    let token = crate::auth::request_token(&headers, payload.token);
+    if let Err(e) = state.rooms.decline(&slug, game_id, payload.cf_handle.trim(), token.as_deref()) {
+        return room_error(e);
+    }
+    state
+        .with_game(game_id, |game| {
+            if game.status == GameStatus::Waiting {
+                game.finish(None, "ChallengeDeclined");
+            }
+        })
+        .await;
+    state.remove_game(game_id);
+    (StatusCode::OK, Json(json!({ "declined": game_id }))).into_response()
 }
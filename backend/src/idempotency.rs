@@ -0,0 +1,126 @@
+//! Idempotency keys for game creation.
+//!
+//! Clients may send an `Idempotency-Key` header with `POST /api/game`. The
+//! first request with a key creates the lobby as usual; repeats from the same
+//! CF handle within `WINDOW` get the original lobby back (with a fresh host
+//! token) instead of a duplicate, so a retry after a dropped response is safe.
+//! Keys whose game has since been removed are treated as unused.
+//!
+//! Handles are unverified, so a key is also bound to the request's
+//! `Fingerprint`: the client IP and a hash of the settings. Only the same
+//! request is replayed; anything else reusing a live key is refused
+//! (`KeyReused`) rather than handed the lobby and a host token.
+
+use dashmap::DashMap;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// How long a key keeps pointing at the lobby it created.
+pub const WINDOW: Duration = Duration::from_secs(600);
+
+/// Longest accepted `Idempotency-Key` value.
+pub const MAX_KEY_LEN: usize = 255;
+
+/// Set (`true`) on a response handed back for a repeated key.
+pub const REPLAYED_HEADER: axum::http::HeaderName = axum::http::HeaderName::from_static("idempotent-replayed");
+
+/// The lobby a key created.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Created {
+    pub game_id: Uuid,
+    pub player_id: Uuid,
+}
+
+/// Who sent a request and what it asked for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fingerprint {
+    ip: Option<IpAddr>,
+    settings: [u8; 32],
+}
+
+impl Fingerprint {
+    /// `settings` is everything in the request besides the handle.
+    pub fn new(ip: Option<IpAddr>, settings: &impl Serialize) -> Self {
+        let json = serde_json::to_vec(settings).unwrap_or_default();
+        Self { ip, settings: Sha256::digest(&json).into() }
+    }
+}
+
+/// The key is held by a live lobby created by a different request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyReused;
+
+#[derive(Default)]
+pub struct IdempotencyKeys {
+    /// (lowercased CF handle, key) → when it was first used, by which request,
+    /// and what it created
+    keys: DashMap<(String, String), (Instant, Fingerprint, Created)>,
+}
+
+/// Whether `key` is usable: non-empty, bounded, visible ASCII only.
+pub fn valid_key(key: &str) -> bool {
+    !key.is_empty() && key.len() <= MAX_KEY_LEN && key.bytes().all(|b| b.is_ascii_graphic())
+}
+
+impl IdempotencyKeys {
+    /// The lobby an earlier request with this key created, if it is within
+    /// the window and `live` still reports its game as present. `KeyReused`
+    /// if that earlier request isn't this one.
+    pub fn lookup(
+        &self,
+        handle: &str,
+        key: &str,
+        fingerprint: &Fingerprint,
+        live: impl Fn(Uuid) -> bool,
+    ) -> Result<Option<Created>, KeyReused> {
+        let Some(entry) = self.keys.get(&(handle.to_lowercase(), key.to_string())) else {
+            return Ok(None);
+        };
+        let (used_at, first, created) = *entry;
+        if used_at.elapsed() >= WINDOW || !live(created.game_id) {
+            return Ok(None);
+        }
+        if first != *fingerprint {
+            return Err(KeyReused);
+        }
+        Ok(Some(created))
+    }
+
+    /// Record `created` under the key, unless a concurrent request already
+    /// claimed it for a live game — in which case that game is returned (or
+    /// `KeyReused`, if the other request differs) and the caller should hand
+    /// it back instead of inserting its own.
+    pub fn claim(
+        &self,
+        handle: &str,
+        key: &str,
+        fingerprint: Fingerprint,
+        created: Created,
+        live: impl Fn(Uuid) -> bool,
+    ) -> Result<Option<Created>, KeyReused> {
+        let mut entry = self
+            .keys
+            .entry((handle.to_lowercase(), key.to_string()))
+            .or_insert((Instant::now(), fingerprint, created));
+        let (used_at, first, existing) = *entry;
+        if existing != created && used_at.elapsed() < WINDOW && live(existing.game_id) {
+            if first != fingerprint {
+                return Err(KeyReused);
+            }
+            return Ok(Some(existing));
+        }
+        *entry = (Instant::now(), fingerprint, created);
+        Ok(None)
+    }
+
+    /// Drop keys past the window (called from the background ticker).
+    pub fn purge_expired(&self) -> usize {
+        let before = self.keys.len();
+        self.keys
+            .retain(|_, (used_at, _, _)| used_at.elapsed() < WINDOW);
+        before - self.keys.len()
+    }
+}
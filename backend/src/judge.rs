@@ -0,0 +1,190 @@
+//! Judges: where a game's problems come from and how solves are checked.
+//!
+//! Every game is verified by one judge, named by `GameConfig::judge`.
+//! Codeforces (`"codeforces"`, through the rate-limited `CfApiQueue`) is built
+//! in. Self-hosters add others (CSES, a university judge, ...) by implementing
+//! `JudgeClient` and registering it with `ServerBuilder::judge_plugin`, no fork
+//! needed. `cses.rs` is the example: a fixed task list, verified by scraping
+//! each user's results page; it is registered when `judges.cses` is on.
+//!
+//! A plugin judge brings its own problem list with nominal Codeforces-style
+//! ratings. Games on it draw the problems nearest their difficulty (see
+//! `pool_queue`); hints and post-game training plans stay Codeforces-only.
+
+use crate::cf_client::{CfApiQueue, Verdict};
+use crate::error::JudgeError;
+use crate::state::{AssignedProblem, DifficultyMode};
+use futures::future::BoxFuture;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// `GameConfig::judge` for Codeforces, the default.
+pub const CODEFORCES: &str = "codeforces";
+
+/// A judge that can tell what a player has solved.
+///
+/// Problems are identified by `contest_id` and `index`, as on Codeforces; a
+/// judge without contests can use its task id and an empty index. Solved sets
+/// use the same `"contest_id-index"` keys as `Player::solved_set`.
+pub trait JudgeClient: Send + Sync {
+    /// Stable id, as in `GameConfig::judge`
+    fn id(&self) -> &str;
+
+    /// For players, e.g. "Solve it on CSES first!"
+    fn name(&self) -> &str;
+
+    /// The problems this judge assigns, each with a nominal Codeforces-style
+    /// rating. `None` uses the built-in Codeforces problem DB.
+    fn problems(&self) -> Option<&[AssignedProblem]> {
+        None
+    }
+
+    /// Whether a client-supplied reference could be one of this judge's
+    /// problems. Checked before anything reaches the judge or a URL.
+    fn is_valid_problem_ref(&self, contest_id: i32, index: &str) -> bool {
+        crate::cf_client::is_valid_problem_ref(contest_id, index)
+    }
+
+    /// Whether `rating` means anything, so lobbies on this judge can be
+    /// rating-gated.
+    fn has_ratings(&self) -> bool {
+        false
+    }
+
+    /// `handle`'s rating, checked when a guest joins a rating-gated lobby;
+    /// `None` if they're unrated.
+    fn rating<'a>(&'a self, _handle: &'a str) -> BoxFuture<'a, Result<Option<u32>, JudgeError>> {
+        Box::pin(async { Ok(None) })
+    }
+
+    /// Whether the judge looks unreachable right now, so solves can't be
+    /// verified. Games with `pause_on_outage` stop their clock meanwhile.
+    fn is_down(&self) -> bool {
+        false
+    }
+
+    /// Everything `handle` has solved. Callers retry on `Err`.
+    fn solved_set<'a>(&'a self, handle: &'a str) -> BoxFuture<'a, Result<HashSet<String>, JudgeError>>;
+
+    /// Whether `handle` solved `problem` (at or after `since_unix`, when the
+    /// judge can tell), or at least made a genuine attempt at it.
+    fn verify<'a>(
+        &'a self,
+        handle: &'a str,
+        problem: &'a AssignedProblem,
+        since_unix: Option<u64>,
+    ) -> BoxFuture<'a, Result<Verdict, JudgeError>>;
+}
+
+impl JudgeClient for CfApiQueue {
+    fn id(&self) -> &str {
+        CODEFORCES
+    }
+
+    fn name(&self) -> &str {
+        "Codeforces"
+    }
+
+    fn has_ratings(&self) -> bool {
+        true
+    }
+
+    fn rating<'a>(&'a self, handle: &'a str) -> BoxFuture<'a, Result<Option<u32>, JudgeError>> {
+        Box::pin(self.fetch_rating(handle))
+    }
+
+    fn is_down(&self) -> bool {
+        CfApiQueue::is_down(self)
+    }
+
+    fn solved_set<'a>(&'a self, handle: &'a str) -> BoxFuture<'a, Result<HashSet<String>, JudgeError>> {
+        Box::pin(self.fetch_solved_set(handle))
+    }
+
+    fn verify<'a>(
+        &'a self,
+        handle: &'a str,
+        problem: &'a AssignedProblem,
+        since_unix: Option<u64>,
+    ) -> BoxFuture<'a, Result<Verdict, JudgeError>> {
+        Box::pin(self.verify_submission(handle, problem.contest_id, &problem.index, since_unix))
+    }
+}
+
+/// The judges games may be played on, by id.
+#[derive(Clone)]
+pub struct JudgeRegistry {
+    judges: HashMap<String, Arc<dyn JudgeClient>>,
+}
+
+impl JudgeRegistry {
+    /// Just Codeforces, through `cf_queue`.
+    pub fn new(cf_queue: CfApiQueue) -> Self {
+        let mut registry = Self { judges: HashMap::new() };
+        registry.register(Arc::new(cf_queue));
+        registry
+    }
+
+    /// The built-in judges `config` turns on.
+    pub fn from_config(cf_queue: CfApiQueue, config: &crate::config::Config) -> Self {
+        let mut registry = Self::new(cf_queue);
+        if config.judges.cses {
+            registry.register(Arc::new(crate::cses::Cses::from_config(&config.codeforces)));
+        }
+        registry
+    }
+
+    /// Add `judge`, replacing any registered under the same id.
+    pub fn register(&mut self, judge: Arc<dyn JudgeClient>) {
+        self.judges.insert(judge.id().to_string(), judge);
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<dyn JudgeClient>> {
+        self.judges.get(id).cloned()
+    }
+
+    /// Whether any registered judge is down (see `JudgeClient::is_down`).
+    pub fn any_down(&self) -> bool {
+        self.judges.values().any(|j| j.is_down())
+    }
+
+    /// The judge for a game. Game creation only accepts registered ids, so
+    /// the Codeforces fallback is for games injected by a host app.
+    pub fn for_game(&self, config: &crate::state::GameConfig) -> Arc<dyn JudgeClient> {
+        self.get(&config.judge)
+            .or_else(|| self.get(CODEFORCES))
+            .expect("the Codeforces judge is always registered")
+    }
+}
+
+/// The rating a plugin judge's problems are matched against: the difficulty
+/// itself in Cf mode, or a nominal rating per band (Super Easy 800 up to
+/// Very Hard 2400).
+pub fn target_rating(difficulty: u32, mode: &DifficultyMode) -> u32 {
+    match mode {
+        DifficultyMode::Cf => difficulty,
+        DifficultyMode::Band => 800 + difficulty.min(4) * 400,
+    }
+}
+
+/// A shared problem queue holding a plugin judge's whole `pool`: problems
+/// neither player has solved first, then the rest, each group nearest `target`
+/// first (in steps of 100 rating, shuffled within a step).
+pub fn pool_queue(
+    pool: &[AssignedProblem],
+    target: u32,
+    p1_solved: &HashSet<String>,
+    p2_solved: &HashSet<String>,
+    rng: &mut impl Rng,
+) -> Vec<AssignedProblem> {
+    let mut queue: Vec<AssignedProblem> = pool.to_vec();
+    queue.shuffle(rng);
+    queue.sort_by_key(|p| {
+        let key = format!("{}-{}", p.contest_id, p.index);
+        let solved = p1_solved.contains(&key) || p2_solved.contains(&key);
+        (solved, p.rating.abs_diff(target) / 100)
+    });
+    queue
+}
@@ -1,8 +1,41 @@
+pub mod activity;
+pub mod actor;
+pub mod admin;
+pub mod anticheat;
+pub mod archive;
+pub mod auth;
 pub mod background;
+pub mod board_image;
 pub mod cf_client;
+pub mod config;
+pub mod cors;
+pub mod cses;
 pub mod discord;
+pub mod error;
+pub mod events;
 pub mod game;
+pub mod moderation;
 pub mod handlers;
+pub mod idempotency;
+pub mod judge;
+pub mod locale;
+pub mod logging;
+pub mod match_report;
+pub mod metrics;
+pub mod pagination;
 pub mod protocol;
+pub mod rate_limit;
+pub mod referee;
+pub mod reporting;
+pub mod response_cache;
+pub mod rooms;
+pub mod server;
+pub mod sim;
 pub mod state;
+pub mod supervisor;
+pub mod timers;
+pub mod training;
+pub mod webhooks;
 pub mod ws;
+
+pub use server::{Server, ServerBuilder};
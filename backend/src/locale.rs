@@ -0,0 +1,150 @@
+//! Localized text in server messages.
+//!
+//! A client may name its locale (a BCP 47 tag such as `es` or `es-MX`) in
+//! `JoinGame` or `Resume`. The socket's `Error` messages are then written in
+//! that language, chosen by their `code`, and so are the `GameUpdate` status
+//! lines of the player it joined as (`Player::locale`). Machine-readable
+//! fields — error codes, `reason`s, statuses in snapshots — are never
+//! translated, and text without a template in the locale stays in English.
+
+use serde::{Deserialize, Serialize};
+
+use crate::protocol::ServerMessage;
+use crate::state::{GameStatus, SuddenDeathMode};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    #[default]
+    En,
+    Es,
+}
+
+/// Status lines sent outside the regular tick.
+#[derive(Debug, Clone, Copy)]
+pub enum Status {
+    PlacementComplete,
+    ShipsPlaced,
+    VetoActivated { minutes: u64 },
+}
+
+impl Locale {
+    /// The supported locale for a BCP 47 tag (`es-MX` is `Es`); English for
+    /// anything else.
+    pub fn from_tag(tag: &str) -> Self {
+        let language = tag.split(['-', '_']).next().unwrap_or_default();
+        match language.to_ascii_lowercase().as_str() {
+            "es" => Self::Es,
+            _ => Self::En,
+        }
+    }
+
+    /// Rewrite `msg`'s human text for this locale, where there's a template.
+    pub fn localize(self, msg: &mut ServerMessage) {
+        if let ServerMessage::Error { code, message, retry_after_secs, .. } = msg {
+            if let Some(text) = self.error(code, *retry_after_secs) {
+                *message = text;
+            }
+        }
+    }
+
+    /// The tick's status line for a game in `status`.
+    pub fn game_status(self, status: &GameStatus, sudden_death: SuddenDeathMode) -> String {
+        let shots = battle_cp_core::SUDDEN_DEATH_SHOTS;
+        match (self, status, sudden_death) {
+            (Self::En, GameStatus::SuddenDeath, SuddenDeathMode::FirstHit) => "SUDDEN DEATH! First hit wins!".to_string(),
+            (Self::En, GameStatus::SuddenDeath, SuddenDeathMode::Accuracy) => {
+                format!("SUDDEN DEATH! Best hit rate over {} shots wins!", shots)
+            }
+            (Self::En, status, _) => format!("{:?}", status),
+            (Self::Es, GameStatus::SuddenDeath, SuddenDeathMode::FirstHit) => {
+                "¡MUERTE SÚBITA! ¡Gana el primer impacto!".to_string()
+            }
+            (Self::Es, GameStatus::SuddenDeath, SuddenDeathMode::Accuracy) => {
+                format!("¡MUERTE SÚBITA! ¡Gana la mejor puntería en {} disparos!", shots)
+            }
+            (Self::Es, status, _) => match status {
+                GameStatus::Waiting => "Esperando rival",
+                GameStatus::PlacingShips => "Colocando barcos",
+                GameStatus::Initializing => "Preparando la partida",
+                GameStatus::Countdown => "Cuenta atrás",
+                GameStatus::Playing | GameStatus::SuddenDeath => "En combate",
+                GameStatus::Finished => "Partida terminada",
+                GameStatus::Abandoned => "Partida abandonada",
+            }
+            .to_string(),
+        }
+    }
+
+    pub fn status(self, status: Status) -> String {
+        match (self, status) {
+            (Self::En, Status::PlacementComplete) => "Placement Complete".to_string(),
+            (Self::En, Status::ShipsPlaced) => "Ships Placed".to_string(),
+            (Self::En, Status::VetoActivated { minutes }) => format!("Veto activated. Wait {} minutes.", minutes),
+            (Self::Es, Status::PlacementComplete) => "Colocación completa".to_string(),
+            (Self::Es, Status::ShipsPlaced) => "Barcos colocados".to_string(),
+            (Self::Es, Status::VetoActivated { minutes }) => format!("Veto activado. Espera {} minutos.", minutes),
+        }
+    }
+
+    /// The error message for `code`, if this locale has a template for it.
+    /// `{secs}` is filled from the error's `retry_after_secs`.
+    fn error(self, code: &str, retry_after_secs: Option<u64>) -> Option<String> {
+        let template = match self {
+            Self::En => return None,
+            Self::Es => ES_ERRORS.iter().find(|(c, _)| *c == code)?.1,
+        };
+        Some(match retry_after_secs {
+            Some(secs) => template.replace("{secs}", &secs.to_string()),
+            None if template.contains("{secs}") => return None,
+            None => template.to_string(),
+        })
+    }
+}
+
+const ES_ERRORS: &[(&str, &str)] = &[
+    ("game_not_found", "Partida no encontrada"),
+    ("game_ended", "La partida ya ha terminado"),
+    ("unauthorized", "Falta el token de sesión o no es válido"),
+    ("resume_expired", "Ha pasado demasiado tiempo desde la desconexión para reanudar. Volviendo a unirte."),
+    ("not_in_game", "No estás en la partida"),
+    ("self_play", "¡No puedes jugar contra ti mismo!"),
+    ("banned", "Tienes prohibida la entrada a este servidor"),
+    ("game_full", "La partida ya tiene 2 jugadores."),
+    ("seat_reserved", "Este puesto está reservado para otro jugador"),
+    ("waiting_for_opponent", "Esperando al rival"),
+    ("opponent_left", "Tu rival se ha ido"),
+    ("placement_closed", "No se pueden colocar barcos una vez empezada la partida"),
+    ("fleet_composition", "Flota no válida. Los barcos deben medir 5, 4, 3, 3 y 2"),
+    ("ship_start_out_of_bounds", "El barco empieza fuera del tablero"),
+    ("ship_out_of_bounds", "El barco se sale del tablero"),
+    ("ship_overlap", "El barco se solapa con otro"),
+    ("ready_closed", "Solo puedes declararte listo antes del combate"),
+    ("ships_not_placed", "Coloca tus barcos antes de declararte listo"),
+    ("fleet_placed", "Tu flota ya está colocada"),
+    ("no_forward_fleet", "Esta partida no tiene flota avanzada"),
+    ("armada_placement", "Partida armada: coloca ambas flotas a la vez con PlaceShips (ships y forward)"),
+    ("no_staged_ship", "No hay ningún barco en esa posición"),
+    ("not_started", "La partida aún no ha empezado. Espera a que ambos coloquen sus barcos."),
+    ("weapons_locked_veto", "¡Armas bloqueadas! Espera a que termine el veto."),
+    ("weapons_locked", "¡Armas bloqueadas! Resuelve el problema o usa un veto."),
+    ("out_of_bounds", "Fuera del tablero"),
+    ("already_fired", "Ya has disparado aquí"),
+    ("no_special_weapon", "No tienes esa arma especial"),
+    ("out_of_ammo", "¡Sin munición! Resuelve tu problema para recargar."),
+    ("sudden_death_shots_used", "Ya has hecho todos tus disparos de muerte súbita. Espera los de tu rival."),
+    ("no_shield_available", "No tienes escudos. Resuelve un problema extra para ganar uno."),
+    ("solve_during_veto", "No puedes resolver durante el veto. Espera a que termine."),
+    ("not_locked", "Tus armas no están bloqueadas"),
+    ("no_problem_assigned", "Aún no tienes problema asignado. Espera a que el servidor te asigne uno."),
+    ("wrong_problem", "Debes resolver el problema asignado. Usa un veto para cambiarlo."),
+    ("verify_cooldown", "Espera {secs} segundos antes de volver a verificar."),
+    ("verify_throttled", "Demasiados intentos de verificación. Vuelve a intentarlo en {secs} segundos."),
+    ("rate_limited", "Más despacio: vuelve a intentarlo en {secs} segundos."),
+    ("veto_running", "Ya estás en un veto. Espera a que termine."),
+    ("no_vetoes_left", "No te quedan vetos"),
+    ("bonus_while_locked", "Los problemas extra solo están disponibles con las armas desbloqueadas"),
+    ("bonus_pending", "Resuelve antes tu problema extra actual"),
+    ("hints_disabled", "Las pistas están desactivadas en esta partida"),
+    ("not_host", "Solo el anfitrión puede hacer eso"),
+];
@@ -0,0 +1,84 @@
+//! Log output and its runtime-adjustable filter.
+//!
+//! The filter uses `RUST_LOG` syntax (`log.filter` in the config file) and can
+//! be swapped without a restart, either through `PUT /api/admin/log` or by
+//! editing the config file and sending the process `SIGHUP`.
+//!
+//! Server logs use these targets:
+//! - `ws` — socket lifecycle (connect, send failures, lag/resync)
+//! - `game` — game flow (joins, problem assignment, timeouts, CF prefetch)
+//! - `cf` — Codeforces API queue and problem database
+//! - `admin` — organizer actions and observers
+//! - `anticheat` — anomaly flags
+//! - `supervisor` — background task restarts
+//!
+//! Each game's actor and sockets run inside spans carrying `game_id`, so one
+//! misbehaving game can be traced on its own with e.g.
+//! `info,[{game_id=5b0c…}]=trace`.
+
+use std::sync::{Mutex, OnceLock};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// Used when neither `RUST_LOG` nor `log.filter` says otherwise.
+pub const DEFAULT_FILTER: &str = "info";
+
+pub struct LogControl {
+    handle: reload::Handle<EnvFilter, Registry>,
+    /// The directives in effect (an `EnvFilter` can't be turned back into them)
+    current: Mutex<String>,
+}
+
+impl LogControl {
+    pub fn current(&self) -> String {
+        self.current.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Replace the filter. On a parse error nothing changes.
+    pub fn set(&self, directives: &str) -> Result<(), String> {
+        let filter = parse(directives)?;
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        self.handle.reload(filter).map_err(|e| e.to_string())?;
+        *current = directives.trim().to_string();
+        Ok(())
+    }
+}
+
+static CONTROL: OnceLock<LogControl> = OnceLock::new();
+
+/// Install the global subscriber with `directives` (falling back to
+/// `DEFAULT_FILTER` if they don't parse). Later calls keep the first subscriber.
+pub fn init(directives: &str) -> &'static LogControl {
+    CONTROL.get_or_init(|| {
+        let (directives, filter) = match parse(directives) {
+            Ok(filter) => (directives.trim().to_string(), filter),
+            Err(e) => {
+                eprintln!("Invalid log filter {:?} ({}), using {:?}", directives, e, DEFAULT_FILTER);
+                (DEFAULT_FILTER.to_string(), EnvFilter::new(DEFAULT_FILTER))
+            }
+        };
+        let (filter, handle) = reload::Layer::new(filter);
+        // Another subscriber may already be set (tests); the handle is still usable
+        let _ = tracing_subscriber::registry()
+            .with(filter)
+            .with(tracing_subscriber::fmt::layer())
+            .try_init();
+        LogControl {
+            handle,
+            current: Mutex::new(directives),
+        }
+    })
+}
+
+/// The installed control, if `init` has run.
+pub fn control() -> Option<&'static LogControl> {
+    CONTROL.get()
+}
+
+/// Check that `directives` form a valid filter.
+pub fn parse(directives: &str) -> Result<EnvFilter, String> {
+    EnvFilter::builder()
+        .parse(directives.trim())
+        .map_err(|e| e.to_string())
+}
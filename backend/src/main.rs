@@ -1,103 +1,58 @@
-use axum::http::HeaderValue;
-use axum::{routing::get, Router};
-use backend::{handlers, state::AppState, ws};
-use std::net::SocketAddr;
-use tower_http::cors::{Any, CorsLayer};
-use tower_http::trace::TraceLayer;
-
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Load local .env variables if present
     dotenvy::dotenv().ok();
 
-    tracing_subscriber::fmt::init();
-    let app_state = AppState::new();
-
-    // Start global ticker
-    tokio::spawn(backend::background::start_global_ticker(app_state.clone()));
+    // Log with RUST_LOG until the config is loaded, so config errors are visible
+    let log = backend::logging::init(
+        &std::env::var("RUST_LOG").unwrap_or_else(|_| backend::logging::DEFAULT_FILTER.to_string()),
+    );
+
+    // Defaults < config file < env vars; refuse to start on invalid settings
+    let config = backend::config::Config::load()?;
+    if config.log.filter != log.current() {
+        log.set(&config.log.filter).map_err(anyhow::Error::msg)?;
+    }
+    tracing::info!("Effective configuration:\n{}", config.summary());
+    backend::reporting::install_panic_hook();
+
+    // SIGHUP applies the config file's log filter
+    #[cfg(unix)]
+    tokio::spawn(reload_log_filter_on_sighup());
+    // State, routes, and the global ticker (restarted with backoff if it dies)
+    let server = backend::Server::builder().config(config.clone()).build();
 
     // Start Discord webhook worker (serializes POSTs, handles rate limits)
     backend::discord::init_worker();
 
-    // Parse CORS origins from env var or use localhost defaults
-    let allowed_origins: Vec<HeaderValue> = std::env::var("ALLOWED_ORIGINS")
-        .unwrap_or_else(|_| {
-            "http://localhost:3000,http://localhost:3001,http://127.0.0.1:3000,http://127.0.0.1:3001".to_string()
-        })
-        .split(',')
-        .filter_map(|s| s.trim().parse::<HeaderValue>().ok())
-        .collect();
-                            
-    let app = Router::new()
-        .route("/", get(root))
-        .route("/health", get(health))
-        .route("/api/game", axum::routing::post(handlers::create_game))
-        .route(
-            "/api/contest/{contest_id}",
-            get(handlers::get_contest_problems),
-        )
-        .route("/ws/{game_id}", get(ws::ws_handler))
-        .layer(TraceLayer::new_for_http())
-        .layer(
-            CorsLayer::new()
-                .allow_origin(allowed_origins)
-                .allow_methods(Any)
-                .allow_headers(Any),
-        )
-        // Security Headers
-        .layer(tower_http::set_header::SetResponseHeaderLayer::overriding(
-            axum::http::header::X_CONTENT_TYPE_OPTIONS,
-            axum::http::HeaderValue::from_static("nosniff"),
-        ))
-        .layer(tower_http::set_header::SetResponseHeaderLayer::overriding(
-            axum::http::header::X_FRAME_OPTIONS,
-            axum::http::HeaderValue::from_static("DENY"),
-        ))
-        .layer(tower_http::set_header::SetResponseHeaderLayer::overriding(
-            axum::http::header::X_XSS_PROTECTION,
-            axum::http::HeaderValue::from_static("1; mode=block"),
-        ))
-        .layer(tower_http::set_header::SetResponseHeaderLayer::overriding(
-            axum::http::header::STRICT_TRANSPORT_SECURITY,
-            axum::http::HeaderValue::from_static("max-age=31536000; includeSubDomains; preload"),
-        ))
-        .with_state(app_state);
-
-    let port = std::env::var("PORT")
-        .unwrap_or_else(|_| "3000".to_string())
-        .parse::<u16>()
-        .unwrap_or(3000);
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
-    tracing::info!("listening on {}", addr);
-
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
-
-    Ok(())
-}
-
-async fn root() -> &'static str {
-    "Battle CP Backend Online, made by oGhostyyy"
+    // Bind address and port, plus built-in TLS when a cert and key are configured
+    let listen = backend::server::ListenConfig::from_config(&config.server);
+    server.serve(listen).await
 }
 
-/// Health check for Azure Container Apps probes and uptime monitoring.
-/// Returns active game count for observability.
-async fn health(
-    axum::extract::State(state): axum::extract::State<AppState>,
-) -> axum::Json<serde_json::Value> {
-    let games = state.games.read().await;
-    let total = games.len();
-    let active = games
-        .values()
-        .filter(|g| {
-            g.status == backend::state::GameStatus::Playing
-                || g.status == backend::state::GameStatus::SuddenDeath
-        })
-        .count();
-    drop(games);
-    axum::Json(serde_json::json!({
-        "status": "ok",
-        "games_total": total,
-        "games_active": active,
-    }))
+/// On each SIGHUP, re-read the config file and apply its `log.filter` (env
+/// `RUST_LOG` is not re-read). Only the log filter is reloaded; other settings
+/// still need a restart.
+#[cfg(unix)]
+async fn reload_log_filter_on_sighup() {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut hangups = match signal(SignalKind::hangup()) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::warn!("Cannot listen for SIGHUP: {}", e);
+            return;
+        }
+    };
+    while hangups.recv().await.is_some() {
+        let Some(log) = backend::logging::control() else {
+            return;
+        };
+        match backend::config::Config::load_file() {
+            Ok(config) => match log.set(&config.log.filter) {
+                Ok(()) => tracing::info!("SIGHUP: log filter is now {:?}", config.log.filter),
+                Err(e) => tracing::error!("SIGHUP: invalid log filter: {}", e),
+            },
+            Err(e) => tracing::error!("SIGHUP: config not reloaded: {}", e),
+        }
+    }
 }
@@ -0,0 +1,279 @@
+//! Post-game reports (`GET /api/game/:id/report`) for organizers who archive
+//! results in wikis or Discord threads: players, settings, a timeline of shots
+//! and solves, final boards and stats, as JSON or rendered Markdown.
+//!
+//! Results and boards come from the `GameOver` in the game's replay log (see
+//! `events.rs`), so a report reads the same however long after the game it is
+//! fetched.
+
+use crate::discord::{accuracy, fmt_duration, settings_line};
+use crate::protocol::{ServerMessage, SpecialWeapon, StampedMessage};
+use crate::state::{Game, GameConfig};
+use serde::Serialize;
+use std::fmt::Write;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GameReport {
+    pub game_id: Uuid,
+    /// `None` for draws and co-op games
+    pub winner_id: Option<Uuid>,
+    /// The `GameOver` reason, e.g. "AllShipsSunk"
+    pub reason: String,
+    pub time_taken_secs: u64,
+    pub config: GameConfig,
+    /// Host first
+    pub players: Vec<PlayerReport>,
+    pub timeline: Vec<TimelineEntry>,
+    /// The hidden fleet, in co-op games
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coop_grid: Option<Vec<Vec<String>>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerReport {
+    pub player_id: Uuid,
+    pub cf_handle: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bot: Option<String>,
+    pub score: f64,
+    pub ships_sunk: u32,
+    pub cells_hit: u32,
+    pub cells_missed: u32,
+    pub problems_solved: u32,
+    pub vetoes_used: u32,
+    /// This player's own board at the end, ships revealed
+    pub grid: Vec<Vec<String>>,
+    /// Armada games: the forward fleet's board, likewise
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forward_grid: Option<Vec<Vec<String>>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TimelineEntry {
+    /// Seconds since combat started
+    pub game_secs: u64,
+    #[serde(flatten)]
+    pub event: TimelineEvent,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum TimelineEvent {
+    /// `result` is "hit", "miss", "sunk" or "blocked" (by a shield)
+    Shot { player_id: Uuid, coord: String, result: &'static str },
+    SpecialShot { player_id: Uuid, weapon: SpecialWeapon, coord: String, hits: u32 },
+    Locked { player_id: Uuid },
+    ProblemAssigned { player_id: Uuid, contest_id: i32, problem_index: String, rating: u32 },
+    /// `reason` as in `WeaponsUnlocked` ("solved", "veto_expired", ...)
+    Unlocked { player_id: Uuid, reason: String },
+}
+
+/// The report for a game that has been played out, from its replay log.
+/// `None` if the log has no `GameOver` or combat never started.
+pub fn build(game: &Game, events: &[StampedMessage]) -> Option<GameReport> {
+    game.game_started_at?;
+    let started_ms = events
+        .iter()
+        .find(|e| matches!(e.message, ServerMessage::GameStart))
+        .or(events.first())
+        .map_or(0, |e| e.at_ms);
+    let game_over = events
+        .iter()
+        .rev()
+        .map(|e| &e.message)
+        .find(|m| matches!(m, ServerMessage::GameOver { .. }))?;
+    let ServerMessage::GameOver {
+        winner_id,
+        reason,
+        p1_grid,
+        p2_grid,
+        coop_grid,
+        p1_forward_grid,
+        p2_forward_grid,
+        time_taken_secs,
+        winner_score,
+        loser_score,
+        ..
+    } = game_over
+    else {
+        return None;
+    };
+
+    let players = std::iter::once((&game.player1, p1_grid, p1_forward_grid))
+        .chain(game.player2.as_ref().map(|p| (p, p2_grid, p2_forward_grid)))
+        .map(|(p, grid, forward_grid)| PlayerReport {
+            player_id: p.id,
+            cf_handle: p.cf_handle.clone(),
+            bot: p.bot.clone(),
+            score: if winner_id.is_none() || *winner_id == Some(p.id) { *winner_score } else { *loser_score },
+            ships_sunk: p.stats.ships_sunk,
+            cells_hit: p.stats.cells_hit,
+            cells_missed: p.stats.cells_missed,
+            problems_solved: p.stats.problems_solved,
+            vetoes_used: p.vetoes_used,
+            grid: grid.clone(),
+            forward_grid: forward_grid.clone(),
+        })
+        .collect();
+
+    let timeline = events
+        .iter()
+        .filter(|e| e.at_ms >= started_ms)
+        .filter_map(|e| {
+            let event = match &e.message {
+                ServerMessage::ShotResult { shooter_id, coord, hit, sunk, blocked, .. } => TimelineEvent::Shot {
+                    player_id: *shooter_id,
+                    coord: coord.clone(),
+                    result: match (*blocked, *sunk, *hit) {
+                        (true, _, _) => "blocked",
+                        (_, true, _) => "sunk",
+                        (_, _, true) => "hit",
+                        _ => "miss",
+                    },
+                },
+                ServerMessage::SpecialShotResult { shooter_id, weapon, coord, shots, .. } => TimelineEvent::SpecialShot {
+                    player_id: *shooter_id,
+                    weapon: *weapon,
+                    coord: coord.clone(),
+                    hits: shots.iter().filter(|s| s.hit).count() as u32,
+                },
+                ServerMessage::WeaponsLocked { player_id } => TimelineEvent::Locked { player_id: *player_id },
+                ServerMessage::ProblemAssigned { player_id, contest_id, problem_index, rating, .. } => {
+                    TimelineEvent::ProblemAssigned {
+                        player_id: *player_id,
+                        contest_id: *contest_id,
+                        problem_index: problem_index.clone(),
+                        rating: *rating,
+                    }
+                }
+                // A co-op partner's mirrored unlock repeats the solve
+                ServerMessage::WeaponsUnlocked { player_id, reason } if reason != "teammate" => {
+                    TimelineEvent::Unlocked { player_id: *player_id, reason: reason.clone() }
+                }
+                _ => return None,
+            };
+            Some(TimelineEntry { game_secs: (e.at_ms - started_ms) / 1000, event })
+        })
+        .collect();
+
+    Some(GameReport {
+        game_id: game.id,
+        winner_id: *winner_id,
+        reason: reason.clone(),
+        time_taken_secs: *time_taken_secs,
+        config: game.config.clone(),
+        players,
+        timeline,
+        coop_grid: coop_grid.clone(),
+    })
+}
+
+/// One character per cell, for boards in a code block.
+fn board_text(grid: &[Vec<String>]) -> String {
+    let mut out = String::from("   A B C D E F G H I J\n");
+    for (y, row) in grid.iter().enumerate() {
+        let cells: Vec<&str> = row
+            .iter()
+            .map(|cell| match cell.as_str() {
+                "ship" => "#",
+                "shielded" => "S",
+                "hit" => "X",
+                "miss" => "o",
+                _ => ".",
+            })
+            .collect();
+        let _ = writeln!(out, "{:>2} {}", y + 1, cells.join(" "));
+    }
+    out
+}
+
+/// The report as Markdown: result, stats table, settings, timeline, boards.
+pub fn render_markdown(report: &GameReport) -> String {
+    let handle = |id: Uuid| {
+        report
+            .players
+            .iter()
+            .find(|p| p.player_id == id)
+            .map_or("?", |p| p.cf_handle.as_str())
+    };
+    let mut md = String::from("# Battle CP match report\n\n");
+
+    let names: Vec<&str> = report.players.iter().map(|p| p.cf_handle.as_str()).collect();
+    let result = match report.winner_id {
+        Some(id) => {
+            let loser = names.iter().find(|&&n| n != handle(id)).copied().unwrap_or("—");
+            format!("**{}** beat **{}**", handle(id), loser)
+        }
+        None if report.config.coop => format!("Co-op: **{}**", names.join("** and **")),
+        None => format!("Draw between **{}**", names.join("** and **")),
+    };
+    let _ = writeln!(
+        md,
+        "{} ({}) after {}.\n\nGame `{}`\n",
+        result,
+        report.reason,
+        fmt_duration(report.time_taken_secs),
+        report.game_id
+    );
+
+    md.push_str("| Player | Score | Ships sunk | Hits | Misses | Accuracy | Solved | Vetoes |\n");
+    md.push_str("|---|---|---|---|---|---|---|---|\n");
+    for p in &report.players {
+        let _ = writeln!(
+            md,
+            "| {}{} | {:.0} | {} | {} | {} | {} | {} | {} |",
+            p.cf_handle,
+            p.bot.as_ref().map(|b| format!(" (bot: {})", b)).unwrap_or_default(),
+            p.score,
+            p.ships_sunk,
+            p.cells_hit,
+            p.cells_missed,
+            accuracy(p.cells_hit, p.cells_missed),
+            p.problems_solved,
+            p.vetoes_used
+        );
+    }
+    let _ = writeln!(md, "\n{}\n", settings_line(&report.config));
+
+    md.push_str("## Timeline\n\n");
+    for entry in &report.timeline {
+        let line = match &entry.event {
+            TimelineEvent::Shot { player_id, coord, result } => {
+                format!("**{}** fired at {}: {}", handle(*player_id), coord, result)
+            }
+            TimelineEvent::SpecialShot { player_id, weapon, coord, hits } => {
+                format!("**{}** fired a {:?} shot at {}: {} hit", handle(*player_id), weapon, coord, hits)
+            }
+            TimelineEvent::Locked { player_id } => format!("**{}** overheated", handle(*player_id)),
+            TimelineEvent::ProblemAssigned { player_id, contest_id, problem_index, rating } => format!(
+                "**{}** got problem {}{} ({})",
+                handle(*player_id),
+                contest_id,
+                problem_index,
+                rating
+            ),
+            TimelineEvent::Unlocked { player_id, reason } => {
+                format!("**{}** unlocked ({})", handle(*player_id), reason)
+            }
+        };
+        let _ = writeln!(md, "- `{}` {}", fmt_duration(entry.game_secs), line);
+    }
+
+    md.push_str("\n## Final boards\n\n");
+    let boards: Vec<(String, &Vec<Vec<String>>)> = match &report.coop_grid {
+        Some(grid) => vec![("Hidden fleet".to_string(), grid)],
+        None => report
+            .players
+            .iter()
+            .flat_map(|p| {
+                std::iter::once((format!("{}'s fleet", p.cf_handle), &p.grid))
+                    .chain(p.forward_grid.as_ref().map(|grid| (format!("{}'s forward fleet", p.cf_handle), grid)))
+            })
+            .collect(),
+    };
+    for (label, grid) in boards {
+        let _ = writeln!(md, "{}\n\n```\n{}```\n", label, board_text(grid));
+    }
+    md
+}
@@ -0,0 +1,154 @@
+//! Prometheus gauges for tournament organizers.
+//!
+//! `GET /api/admin/metrics` (`read` scope) exports, in the Prometheus text
+//! format, the progress of every game an organizer tagged with a tournament
+//! (`POST /api/admin/games` with `tournament`): its status and clock, and each
+//! player's ships left, heat and lock. Every sample is labelled with the
+//! tournament and game, so one Grafana board can follow all of a round's
+//! matches at once. Untagged games aren't exported.
+
+use std::fmt::Write;
+
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap},
+    response::{IntoResponse, Response},
+};
+
+use crate::admin::{AdminQuery, Scope};
+use crate::state::{AppState, Game, GameStatus, Player};
+
+/// Longest tournament tag, in characters.
+pub const MAX_TOURNAMENT_LEN: usize = 64;
+
+/// Tags are label values on every sample: keep them short and plain.
+pub fn is_valid_tournament(tag: &str) -> bool {
+    (1..=MAX_TOURNAMENT_LEN).contains(&tag.len())
+        && tag.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// One game's gauges, read on its actor.
+struct GameSample {
+    tournament: String,
+    game_id: uuid::Uuid,
+    status: GameStatus,
+    time_remaining_secs: u64,
+    players: Vec<PlayerSample>,
+}
+
+struct PlayerSample {
+    seat: &'static str,
+    handle: String,
+    ships_remaining: usize,
+    heat: u32,
+    locked: bool,
+}
+
+impl PlayerSample {
+    fn of(seat: &'static str, p: &Player) -> Self {
+        Self {
+            seat,
+            handle: p.cf_handle.clone(),
+            ships_remaining: p.fleet().iter().filter(|s| !s.sunk).count(),
+            heat: p.heat,
+            locked: p.is_locked,
+        }
+    }
+}
+
+impl GameSample {
+    fn of(tournament: String, game: &Game) -> Self {
+        let players = std::iter::once(PlayerSample::of("host", &game.player1))
+            .chain(game.player2.as_ref().map(|p| PlayerSample::of("guest", p)))
+            .collect();
+        Self {
+            tournament,
+            game_id: game.id,
+            status: game.status.clone(),
+            time_remaining_secs: game.time_remaining_secs(),
+            players,
+        }
+    }
+
+    fn labels(&self) -> String {
+        format!("tournament=\"{}\",game_id=\"{}\"", escape(&self.tournament), self.game_id)
+    }
+}
+
+/// A label value with `\`, `"` and newlines escaped.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// `HELP` and `TYPE` lines, then one sample per labelled value.
+fn gauge(out: &mut String, name: &str, help: &str, samples: impl IntoIterator<Item = (String, u64)>) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} gauge", name);
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{}{{{}}} {}", name, labels, value);
+    }
+}
+
+/// The exposition text for `games`.
+fn render(games: &[GameSample]) -> String {
+    let player_labels = |g: &GameSample, p: &PlayerSample| {
+        format!("{},seat=\"{}\",handle=\"{}\"", g.labels(), p.seat, escape(&p.handle))
+    };
+    let players = || games.iter().flat_map(|g| g.players.iter().map(move |p| (g, p)));
+
+    let mut out = String::new();
+    gauge(
+        &mut out,
+        "battlecp_game_status",
+        "Current status of a tournament game (1 for the status it's in).",
+        games.iter().map(|g| (format!("{},status=\"{:?}\"", g.labels(), g.status), 1)),
+    );
+    gauge(
+        &mut out,
+        "battlecp_game_time_remaining_seconds",
+        "Regulation time left on the game clock.",
+        games.iter().map(|g| (g.labels(), g.time_remaining_secs)),
+    );
+    gauge(
+        &mut out,
+        "battlecp_player_ships_remaining",
+        "Ships the player has left afloat.",
+        players().map(|(g, p)| (player_labels(g, p), p.ships_remaining as u64)),
+    );
+    gauge(
+        &mut out,
+        "battlecp_player_heat",
+        "The player's weapon heat.",
+        players().map(|(g, p)| (player_labels(g, p), p.heat as u64)),
+    );
+    gauge(
+        &mut out,
+        "battlecp_player_locked",
+        "1 while the player's weapons are locked.",
+        players().map(|(g, p)| (player_labels(g, p), p.locked as u64)),
+    );
+    out
+}
+
+/// `GET /api/admin/metrics`: gauges for every tournament-tagged game.
+pub async fn tournament_metrics(
+    State(state): State<AppState>,
+    Query(query): Query<AdminQuery>,
+    headers: HeaderMap,
+) -> Response {
+    if let Err(denied) = state.admin_keys.authorize(&headers, query.token, Scope::Read) {
+        return denied.into_response();
+    }
+    let mut games = Vec::new();
+    for handle in state.all_games() {
+        let Some(tournament) = handle.tournament.clone() else {
+            continue;
+        };
+        // A game removed since the listing just drops out
+        if let Some(sample) = handle.call(move |g| GameSample::of(tournament, g)).await {
+            games.push(sample);
+        }
+    }
+    games.sort_by(|a, b| (&a.tournament, a.game_id).cmp(&(&b.tournament, b.game_id)));
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], render(&games)).into_response()
+}
@@ -0,0 +1,155 @@
+//! Ban list for public lobbies.
+//!
+//! Organizers can exclude Codeforces handles and IP ranges. Both are checked
+//! when a game is created (`create_game`) and whenever a socket joins a game
+//! (`JoinGame`, including reconnects). The list is seeded from `BANNED_HANDLES`
+//! and `BANNED_CIDRS` (comma-separated; `[moderation]` in the config file) and edited at runtime through the admin
+//! API (`/api/admin/bans`); runtime edits are not persisted.
+
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::net::IpAddr;
+use std::sync::{Arc, RwLock};
+
+/// An IP network such as `203.0.113.0/24` or `2001:db8::/32`. A bare address
+/// is a single-host network.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Cidr {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Cidr {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            // IPv4-mapped IPv6 peers (dual-stack listeners) match IPv4 ranges
+            (IpAddr::V4(_), IpAddr::V6(ip)) => ip
+                .to_ipv4_mapped()
+                .is_some_and(|v4| self.contains(IpAddr::V4(v4))),
+            (IpAddr::V6(_), IpAddr::V4(_)) => false,
+        }
+    }
+}
+
+impl std::str::FromStr for Cidr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr.parse().map_err(|_| format!("invalid address in {:?}", s))?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(p) => p
+                .parse::<u8>()
+                .ok()
+                .filter(|&p| p <= max)
+                .ok_or_else(|| format!("invalid prefix length in {:?}", s))?,
+            None => max,
+        };
+        // Store the network address, so `10.1.2.3/8` and `10.0.0.0/8` are the same ban
+        let addr = match addr {
+            IpAddr::V4(a) => {
+                let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+                IpAddr::V4((u32::from(a) & mask).into())
+            }
+            IpAddr::V6(a) => {
+                let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+                IpAddr::V6((u128::from(a) & mask).into())
+            }
+        };
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl std::fmt::Display for Cidr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+/// Current bans, as returned by `GET /api/admin/bans`.
+#[derive(Debug, Clone, Default, Serialize, PartialEq, Eq)]
+pub struct BanSnapshot {
+    pub handles: Vec<String>,
+    pub cidrs: Vec<String>,
+}
+
+#[derive(Default)]
+struct Bans {
+    /// Lowercased — CF handles are case-insensitive
+    handles: BTreeSet<String>,
+    cidrs: BTreeSet<Cidr>,
+}
+
+/// Shared, runtime-editable ban list.
+#[derive(Clone, Default)]
+pub struct BanList {
+    inner: Arc<RwLock<Bans>>,
+}
+
+impl BanList {
+    pub fn from_config(config: &crate::config::ModerationConfig) -> Self {
+        let list = Self::default();
+        for handle in config.banned_handles.iter().filter(|h| !h.trim().is_empty()) {
+            list.ban_handle(handle);
+        }
+        for cidr in &config.banned_cidrs {
+            match cidr.parse() {
+                Ok(cidr) => {
+                    list.ban_cidr(cidr);
+                }
+                Err(e) => tracing::warn!("Ignoring BANNED_CIDRS entry: {}", e),
+            }
+        }
+        list
+    }
+
+    /// True if the handle or the IP is banned.
+    pub fn is_banned(&self, cf_handle: &str, ip: Option<IpAddr>) -> bool {
+        let bans = self.inner.read().unwrap_or_else(|e| e.into_inner());
+        bans.handles.contains(&cf_handle.trim().to_lowercase())
+            || ip.is_some_and(|ip| bans.cidrs.iter().any(|c| c.contains(ip)))
+    }
+
+    /// Returns false if the handle was already banned.
+    pub fn ban_handle(&self, cf_handle: &str) -> bool {
+        self.write().handles.insert(cf_handle.trim().to_lowercase())
+    }
+
+    /// Returns false if the handle wasn't banned.
+    pub fn unban_handle(&self, cf_handle: &str) -> bool {
+        self.write().handles.remove(&cf_handle.trim().to_lowercase())
+    }
+
+    pub fn ban_cidr(&self, cidr: Cidr) -> bool {
+        self.write().cidrs.insert(cidr)
+    }
+
+    pub fn unban_cidr(&self, cidr: Cidr) -> bool {
+        self.write().cidrs.remove(&cidr)
+    }
+
+    pub fn snapshot(&self) -> BanSnapshot {
+        let bans = self.inner.read().unwrap_or_else(|e| e.into_inner());
+        BanSnapshot {
+            handles: bans.handles.iter().cloned().collect(),
+            cidrs: bans.cidrs.iter().map(Cidr::to_string).collect(),
+        }
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Bans> {
+        self.inner.write().unwrap_or_else(|e| e.into_inner())
+    }
+}
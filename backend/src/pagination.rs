@@ -0,0 +1,97 @@
+//! Cursor pagination shared by the list endpoints.
+//!
+//! Lists take `?limit=` (default 50, at most 200) and `?cursor=` (the
+//! `next_cursor` of the previous page), and answer with their items under
+//! their usual name plus `next_cursor`, `null` on the last page. A cursor is
+//! the sort key of the last item sent, so a page never repeats or skips items
+//! because others were added in between. `?order=desc` walks a list from the
+//! other end.
+//!
+//! Filters are plain query parameters named after the field they match
+//! (`?type=ShotResult`, `?status=Waiting`), in each endpoint's own query
+//! struct next to `PageQuery`; they apply before paging.
+
+use serde::{Deserialize, Serialize};
+
+pub const DEFAULT_LIMIT: usize = 50;
+pub const MAX_LIMIT: usize = 200;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// `?limit=&cursor=&order=`, extracted with `Query<PageQuery>` alongside the
+/// endpoint's filters.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PageQuery {
+    pub limit: Option<usize>,
+    pub cursor: Option<String>,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+/// A list's sort key, as written into cursors.
+pub trait CursorKey: Ord + Sized {
+    fn encode(&self) -> String;
+    fn decode(cursor: &str) -> Option<Self>;
+}
+
+impl CursorKey for u64 {
+    fn encode(&self) -> String {
+        self.to_string()
+    }
+
+    fn decode(cursor: &str) -> Option<Self> {
+        cursor.parse().ok()
+    }
+}
+
+impl CursorKey for String {
+    fn encode(&self) -> String {
+        self.clone()
+    }
+
+    fn decode(cursor: &str) -> Option<Self> {
+        Some(cursor.to_string())
+    }
+}
+
+/// One page of a list.
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+impl PageQuery {
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+    }
+
+    /// The page of `items` after the cursor, ordered by `key` (which must be
+    /// unique per item). `Err` if the cursor isn't one of this list's.
+    pub fn paginate<T, K: CursorKey>(&self, mut items: Vec<T>, key: impl Fn(&T) -> K) -> Result<Page<T>, &'static str> {
+        let after = match self.cursor.as_deref() {
+            Some(cursor) => Some(K::decode(cursor).ok_or("Invalid cursor")?),
+            None => None,
+        };
+        items.sort_by_key(|item| key(item));
+        if self.order == SortOrder::Desc {
+            items.reverse();
+        }
+        if let Some(after) = after {
+            items.retain(|item| match self.order {
+                SortOrder::Asc => key(item) > after,
+                SortOrder::Desc => key(item) < after,
+            });
+        }
+        let limit = self.limit();
+        let next_cursor = (items.len() > limit).then(|| key(&items[limit - 1]).encode());
+        items.truncate(limit);
+        Ok(Page { items, next_cursor })
+    }
+}
@@ -0,0 +1,298 @@
+//! Per-IP rate limiting for REST endpoints, and budgets on CF verifications.
+//!
+//! Fixed-window counters keyed by client IP, applied as an axum middleware
+//! on individual routes (game creation, contest-problem proxy). CF
+//! verifications are budgeted per game and per IP with the same kind of
+//! window, checked synchronously from inside the game's actor.
+
+use axum::{
+    extract::{ConnectInfo, FromRequestParts, Request, State},
+    http::{request::Parts, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset`, as `Quota`
+/// writes them.
+pub const QUOTA_HEADERS: [HeaderName; 3] = [
+    HeaderName::from_static("ratelimit-limit"),
+    HeaderName::from_static("ratelimit-remaining"),
+    HeaderName::from_static("ratelimit-reset"),
+];
+
+/// Where a client stands with a limiter after a request: sent back as
+/// `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset` (seconds
+/// until the window resets), so clients can count down instead of retrying
+/// blindly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quota {
+    pub limit: u32,
+    pub remaining: u32,
+    pub reset: Duration,
+}
+
+impl Quota {
+    /// Whole seconds until the window resets, at least 1 (for `Retry-After`).
+    pub fn reset_secs(&self) -> u64 {
+        (self.reset.as_millis().div_ceil(1000) as u64).max(1)
+    }
+
+    pub fn write_headers(&self, headers: &mut HeaderMap) {
+        let [limit, remaining, reset] = QUOTA_HEADERS;
+        headers.insert(limit, HeaderValue::from(self.limit));
+        headers.insert(remaining, HeaderValue::from(self.remaining));
+        headers.insert(reset, HeaderValue::from(self.reset_secs()));
+    }
+}
+
+/// Fixed-window limiter: at most `limit` requests per `window` per IP.
+#[derive(Clone)]
+pub struct IpRateLimiter {
+    limit: u32,
+    window: Duration,
+    hits: Arc<Mutex<HashMap<IpAddr, (Instant, u32)>>>,
+}
+
+impl IpRateLimiter {
+    pub fn new(limit: u32, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            hits: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn per_minute(limit: u32) -> Self {
+        Self::new(limit, Duration::from_secs(60))
+    }
+
+    /// Record a hit. Returns the IP's quota, as `Err` (nothing remaining,
+    /// `reset` is the retry-after) if it is over its limit.
+    pub async fn check(&self, ip: IpAddr) -> Result<Quota, Quota> {
+        let mut hits = self.hits.lock().await;
+        let now = Instant::now();
+        let entry = hits.entry(ip).or_insert((now, 0));
+        if now.duration_since(entry.0) > self.window {
+            // Window expired — reset
+            *entry = (now, 0);
+        }
+        let over = entry.1 >= self.limit;
+        if !over {
+            entry.1 += 1;
+        }
+        let quota = Quota {
+            limit: self.limit,
+            remaining: self.limit - entry.1,
+            reset: self.window.saturating_sub(now.duration_since(entry.0)),
+        };
+        if over {
+            Err(quota)
+        } else {
+            Ok(quota)
+        }
+    }
+
+    /// Drop entries whose window has expired (called from the background ticker).
+    pub async fn purge_expired(&self) -> usize {
+        let mut hits = self.hits.lock().await;
+        let before = hits.len();
+        hits.retain(|_, (started, _)| started.elapsed() < self.window);
+        before - hits.len()
+    }
+}
+
+/// Per-route limiters held in `AppState`.
+#[derive(Clone)]
+pub struct IpLimiters {
+    /// `POST /api/game` — default 10 per minute per IP
+    pub create_game: IpRateLimiter,
+    /// `GET /api/contest/:id` — default 30 per minute per IP
+    pub contest: IpRateLimiter,
+}
+
+impl IpLimiters {
+    pub fn from_config(limits: &crate::config::LimitsConfig) -> Self {
+        Self {
+            create_game: IpRateLimiter::per_minute(limits.create_game_per_min),
+            contest: IpRateLimiter::per_minute(limits.contest_per_min),
+        }
+    }
+}
+
+/// Synchronous fixed-window limiter keyed by anything hashable, for checks
+/// that run inside a game actor command (which can't await).
+#[derive(Clone)]
+pub struct KeyedLimiter<K> {
+    limit: u32,
+    window: Duration,
+    hits: Arc<std::sync::Mutex<HashMap<K, (Instant, u32)>>>,
+}
+
+impl<K: std::hash::Hash + Eq> KeyedLimiter<K> {
+    pub fn new(limit: u32, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            hits: Arc::new(std::sync::Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Record a hit. Returns `Err(retry_after)` if `key` is over its limit.
+    pub fn check(&self, key: K) -> Result<(), Duration> {
+        let mut hits = self.hits.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let entry = hits.entry(key).or_insert((now, 0));
+        if now.duration_since(entry.0) > self.window {
+            *entry = (now, 0);
+        }
+        if entry.1 >= self.limit {
+            return Err(self.window.saturating_sub(now.duration_since(entry.0)));
+        }
+        entry.1 += 1;
+        Ok(())
+    }
+
+    /// Give back a hit `check` recorded for `key` in the current window.
+    pub fn refund(&self, key: &K) {
+        let mut hits = self.hits.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = hits.get_mut(key) {
+            if entry.0.elapsed() <= self.window {
+                entry.1 = entry.1.saturating_sub(1);
+            }
+        }
+    }
+
+    /// Drop entries whose window has expired (called from the background ticker).
+    pub fn purge_expired(&self) -> usize {
+        let mut hits = self.hits.lock().unwrap_or_else(|e| e.into_inner());
+        let before = hits.len();
+        hits.retain(|_, (started, _)| started.elapsed() < self.window);
+        before - hits.len()
+    }
+}
+
+/// Server-wide budgets on CF verification calls, on top of each player's own
+/// cooldown, so no single game or client can burn the shared CF quota.
+#[derive(Clone)]
+pub struct VerifyLimiters {
+    /// Per game — default 8 per minute (`VERIFY_PER_GAME_PER_MIN`)
+    pub per_game: KeyedLimiter<uuid::Uuid>,
+    /// Per client IP, across all its games — default 12 per minute (`VERIFY_PER_IP_PER_MIN`)
+    pub per_ip: KeyedLimiter<IpAddr>,
+    /// Per bot, in place of its IP's — default 12 per minute (`VERIFY_PER_BOT_PER_MIN`)
+    pub per_bot: KeyedLimiter<String>,
+}
+
+impl VerifyLimiters {
+    pub fn from_config(limits: &crate::config::LimitsConfig) -> Self {
+        let minute = Duration::from_secs(60);
+        Self {
+            per_game: KeyedLimiter::new(limits.verify_per_game_per_min, minute),
+            per_ip: KeyedLimiter::new(limits.verify_per_ip_per_min, minute),
+            per_bot: KeyedLimiter::new(limits.verify_per_bot_per_min, minute),
+        }
+    }
+
+    /// Spend one verification from the game's budget, and the bot's or else
+    /// the IP's. `Err(retry_after)` if either is exhausted, in which case
+    /// neither is spent.
+    pub fn check(&self, game_id: uuid::Uuid, ip: Option<IpAddr>, bot: Option<&str>) -> Result<(), Duration> {
+        self.per_game.check(game_id)?;
+        let client = match (bot, ip) {
+            (Some(bot), _) => self.per_bot.check(bot.to_string()),
+            (None, Some(ip)) => self.per_ip.check(ip),
+            (None, None) => Ok(()),
+        };
+        if client.is_err() {
+            self.per_game.refund(&game_id);
+        }
+        client
+    }
+}
+
+/// Resolve the client IP. Proxy headers (`X-Real-IP`, then the first
+/// `X-Forwarded-For` hop) are only honoured when `TRUST_PROXY_HEADERS=true`
+/// (`server.trust_proxy_headers`),
+/// since anyone can forge them when the backend is exposed directly.
+pub fn client_ip(headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<IpAddr> {
+    if crate::config::global().server.trust_proxy_headers {
+        let forwarded = headers
+            .get("x-real-ip")
+            .and_then(|v| v.to_str().ok())
+            .or_else(|| {
+                headers
+                    .get("x-forwarded-for")
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.split(',').next())
+            })
+            .and_then(|v| v.trim().parse::<IpAddr>().ok());
+        if forwarded.is_some() {
+            return forwarded;
+        }
+    }
+    peer.map(|addr| addr.ip())
+}
+
+/// Extractor for the resolved client IP (`None` when neither a peer address
+/// nor trusted proxy headers are available, e.g. in-process tests).
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl<S: Send + Sync> FromRequestParts<S> for ClientIp {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let peer = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ci| ci.0);
+        Ok(ClientIp(client_ip(&parts.headers, peer)))
+    }
+}
+
+/// Middleware: reject with 429 + `Retry-After` once the IP exceeds the limiter.
+/// Every response it lets through or refuses carries the `RateLimit-*` headers.
+pub async fn limit_by_ip(
+    State(limiter): State<IpRateLimiter>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let peer = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ci| ci.0);
+    // No resolvable IP (e.g. in-process tests) — nothing to key on, let it through
+    let Some(ip) = client_ip(request.headers(), peer) else {
+        return next.run(request).await;
+    };
+
+    match limiter.check(ip).await {
+        Ok(quota) => {
+            let mut resp = next.run(request).await;
+            // A stricter limit inside the handler may have reported its own
+            if !resp.headers().contains_key("ratelimit-limit") {
+                quota.write_headers(resp.headers_mut());
+            }
+            resp
+        }
+        Err(quota) => {
+            let secs = quota.reset_secs();
+            tracing::warn!("Rate limited {} on {}", ip, request.uri().path());
+            let mut resp = (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(json!({ "error": "Too many requests. Please slow down.", "retry_after_secs": secs })),
+            )
+                .into_response();
+            resp.headers_mut()
+                .insert("retry-after", HeaderValue::from(secs));
+            quota.write_headers(resp.headers_mut());
+            resp
+        }
+    }
+}
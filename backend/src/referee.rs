@@ -0,0 +1,461 @@
+//! Referee feed for spectators and casters (`/ws/{game_id}/referee`).
+//!
+//! Like the referee in Kriegspiel, it announces what can be said without
+//! giving the boards away: ships left after each sink, who is locked and for
+//! how long, unlocks, and the result. Coordinates never appear. Games opt in
+//! with `GameConfig.referee_feed`; fog-of-war games keep their ship counts
+//! hidden here too.
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        Path, State, WebSocketUpgrade,
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use futures::{SinkExt, StreamExt};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::actor::GameHandle;
+use crate::protocol::{RefereeEvent, RefereeMessage, ServerMessage, StampedMessage};
+use crate::state::{AppState, Game};
+
+/// How often running locks are checked for another whole minute.
+const LOCK_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// What the feed knows about one player.
+#[derive(Clone, Debug)]
+struct Seat {
+    id: Uuid,
+    handle: String,
+    ships_left: u32,
+    /// When the current lock began
+    locked_since: Option<Instant>,
+    /// Whole minutes of the current lock already announced
+    announced_minutes: u64,
+}
+
+/// The game facts the feed works from, read on the game's actor.
+struct Facts {
+    started_at: Option<Instant>,
+    hide_ships: bool,
+    seats: Vec<Seat>,
+    game_over: Option<ServerMessage>,
+}
+
+fn facts(game: &Game) -> Facts {
+    let now_unix = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let seats = std::iter::once(&game.player1)
+        .chain(game.player2.as_ref())
+        .map(|p| {
+            let locked_since = p.is_locked.then(|| {
+                let secs = now_unix.saturating_sub(p.locked_at_unix.unwrap_or(now_unix));
+                Instant::now()
+                    .checked_sub(Duration::from_secs(secs))
+                    .unwrap_or_else(Instant::now)
+            });
+            Seat {
+                id: p.id,
+                handle: p.cf_handle.clone(),
+                ships_left: p.fleet().iter().filter(|s| !s.sunk).count() as u32,
+                locked_since,
+                announced_minutes: locked_since.map_or(0, |at| at.elapsed().as_secs() / 60),
+            }
+        })
+        .collect();
+    Facts {
+        started_at: game.game_started_at,
+        hide_ships: game.config.hide_sunk_info,
+        seats,
+        game_over: game.game_over_msg.clone(),
+    }
+}
+
+/// One spectator's running commentary.
+struct Feed {
+    started_at: Option<Instant>,
+    hide_ships: bool,
+    seats: Vec<Seat>,
+    finished: bool,
+}
+
+impl Feed {
+    /// The feed, and the lines a spectator joining now should see.
+    fn open(facts: Facts) -> (Self, Vec<RefereeEvent>) {
+        let mut feed = Self {
+            started_at: facts.started_at,
+            hide_ships: facts.hide_ships,
+            seats: facts.seats,
+            finished: false,
+        };
+        let mut lines = vec![];
+        for seat in &feed.seats {
+            // Fleets aren't final until the game starts
+            if !feed.hide_ships && feed.started_at.is_some() {
+                lines.push(RefereeEvent::ShipsLeft {
+                    player_id: seat.id,
+                    ships_left: seat.ships_left,
+                });
+            }
+            match seat.announced_minutes {
+                _ if seat.locked_since.is_none() => {}
+                0 => lines.push(RefereeEvent::Locked { player_id: seat.id }),
+                minutes => lines.push(RefereeEvent::StillLocked {
+                    player_id: seat.id,
+                    minutes,
+                }),
+            }
+        }
+        if let Some(game_over) = &facts.game_over {
+            lines.extend(feed.apply(game_over));
+        }
+        (feed, lines)
+    }
+
+    /// Ship counts that changed since the feed last looked.
+    fn ships(&mut self, facts: &Facts) -> Vec<RefereeEvent> {
+        let mut lines = vec![];
+        for seat in &mut self.seats {
+            let Some(now) = facts.seats.iter().find(|s| s.id == seat.id) else {
+                continue;
+            };
+            if now.ships_left != seat.ships_left {
+                seat.ships_left = now.ships_left;
+                if !self.hide_ships {
+                    lines.push(RefereeEvent::ShipsLeft {
+                        player_id: seat.id,
+                        ships_left: seat.ships_left,
+                    });
+                }
+            }
+        }
+        lines
+    }
+
+    /// Lines for a game broadcast. Shots are handled by `ships`.
+    fn apply(&mut self, msg: &ServerMessage) -> Vec<RefereeEvent> {
+        match msg {
+            ServerMessage::GameStart => {
+                self.started_at = Some(Instant::now());
+                vec![]
+            }
+            ServerMessage::WeaponsLocked { player_id } => match self.seat_mut(*player_id) {
+                Some(seat) => {
+                    seat.locked_since = Some(Instant::now());
+                    seat.announced_minutes = 0;
+                    vec![RefereeEvent::Locked {
+                        player_id: *player_id,
+                    }]
+                }
+                None => vec![],
+            },
+            ServerMessage::WeaponsUnlocked { player_id, reason } => match self.seat_mut(*player_id)
+            {
+                Some(seat) => {
+                    let since = seat.locked_since.take();
+                    vec![RefereeEvent::Unlocked {
+                        player_id: *player_id,
+                        reason: reason.clone(),
+                        minutes_locked: since.map_or(0, |at| at.elapsed().as_secs() / 60),
+                    }]
+                }
+                None => vec![],
+            },
+            ServerMessage::GameOver {
+                winner_id, reason, ..
+            } if !self.finished => {
+                self.finished = true;
+                vec![RefereeEvent::GameOver {
+                    winner_id: *winner_id,
+                    reason: reason.clone(),
+                }]
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Another whole minute for each running lock.
+    fn tick(&mut self) -> Vec<RefereeEvent> {
+        let mut lines = vec![];
+        for seat in &mut self.seats {
+            let Some(since) = seat.locked_since else {
+                continue;
+            };
+            let minutes = since.elapsed().as_secs() / 60;
+            if minutes > seat.announced_minutes {
+                seat.announced_minutes = minutes;
+                lines.push(RefereeEvent::StillLocked {
+                    player_id: seat.id,
+                    minutes,
+                });
+            }
+        }
+        lines
+    }
+
+    fn seat_mut(&mut self, id: Uuid) -> Option<&mut Seat> {
+        self.seats.iter_mut().find(|s| s.id == id)
+    }
+
+    fn handle(&self, id: Uuid) -> &str {
+        self.seats
+            .iter()
+            .find(|s| s.id == id)
+            .map_or("A player", |s| s.handle.as_str())
+    }
+
+    /// `event` with the game clock and a sentence for overlays.
+    fn message(&self, event: RefereeEvent) -> RefereeMessage {
+        let plural =
+            |n: u64, word: &str| format!("{} {}{}", n, word, if n == 1 { "" } else { "s" });
+        let text = match &event {
+            RefereeEvent::ShipsLeft {
+                player_id,
+                ships_left,
+            } => {
+                format!(
+                    "{} has {} left",
+                    self.handle(*player_id),
+                    plural(*ships_left as u64, "ship")
+                )
+            }
+            RefereeEvent::Locked { player_id } => {
+                format!("{} overheated and is locked out", self.handle(*player_id))
+            }
+            RefereeEvent::StillLocked { player_id, minutes } => {
+                format!(
+                    "{} has been locked for {}",
+                    self.handle(*player_id),
+                    plural(*minutes, "minute")
+                )
+            }
+            RefereeEvent::Unlocked {
+                player_id,
+                reason,
+                minutes_locked,
+            } => format!(
+                "{} is firing again after {} ({})",
+                self.handle(*player_id),
+                plural(*minutes_locked, "minute"),
+                if reason == "solved" {
+                    "solved their problem"
+                } else {
+                    "veto expired"
+                }
+            ),
+            RefereeEvent::GameOver {
+                winner_id: Some(winner),
+                reason,
+            } => {
+                format!("{} wins ({})", self.handle(*winner), reason)
+            }
+            RefereeEvent::GameOver {
+                winner_id: None,
+                reason,
+            } => format!("No winner ({})", reason),
+        };
+        RefereeMessage {
+            game_secs: self.started_at.map_or(0, |at| at.elapsed().as_secs()),
+            event,
+            text,
+        }
+    }
+}
+
+/// WebSocket upgrade for a game's referee feed. 404 for an unknown game,
+/// 403 if the game didn't open its feed.
+pub async fn referee_ws_handler(
+    ws: WebSocketUpgrade,
+    Path(game_id): Path<Uuid>,
+    State(state): State<AppState>,
+) -> Response {
+    let Some(handle) = state.game(game_id) else {
+        // Archived games replay the closing lines they were frozen with
+        return match state.archive.get(game_id) {
+            Some(archived) => match archived.referee.clone() {
+                Some(lines) => ws
+                    .max_message_size(4096)
+                    .on_upgrade(move |socket| send_closing_lines(socket, lines))
+                    .into_response(),
+                None => (StatusCode::FORBIDDEN, "This game has no referee feed").into_response(),
+            },
+            None => (StatusCode::NOT_FOUND, "Game not found").into_response(),
+        };
+    };
+    match handle.call(|g| g.config.referee_feed).await {
+        Some(true) => {}
+        Some(false) => {
+            return (StatusCode::FORBIDDEN, "This game has no referee feed").into_response()
+        }
+        None => return (StatusCode::NOT_FOUND, "Game not found").into_response(),
+    }
+    ws.max_message_size(4096) // Spectators never need to send anything
+        .on_upgrade(move |socket| handle_referee_socket(socket, handle))
+        .into_response()
+}
+
+/// What a spectator joining `game` now is told first.
+pub(crate) fn opening_lines(game: &Game) -> Vec<RefereeMessage> {
+    let (feed, lines) = Feed::open(facts(game));
+    lines.into_iter().map(|event| feed.message(event)).collect()
+}
+
+/// An archived game's feed: its last word, then the socket closes.
+async fn send_closing_lines(mut socket: WebSocket, lines: Vec<RefereeMessage>) {
+    for line in lines {
+        let Ok(text) = serde_json::to_string(&line) else {
+            continue;
+        };
+        if socket.send(Message::Text(text.into())).await.is_err() {
+            return;
+        }
+    }
+    let _ = socket.close().await;
+}
+
+/// Opening lines, then commentary until the game ends or the spectator leaves.
+async fn handle_referee_socket(socket: WebSocket, handle: GameHandle) {
+    let (mut sender, mut receiver) = socket.split();
+
+    // Subscribe BEFORE reading the facts so no event falls in the gap
+    let mut rx = handle.tx.subscribe();
+    let Some(opening) = handle
+        .call(|g| {
+            g.spectator_moved(true);
+            facts(g)
+        })
+        .await
+    else {
+        return;
+    };
+    let (mut feed, mut lines) = Feed::open(opening);
+    let mut ticker = tokio::time::interval(LOCK_CHECK_INTERVAL);
+
+    'feed: loop {
+        for event in lines.drain(..) {
+            let Ok(text) = serde_json::to_string(&feed.message(event)) else {
+                continue;
+            };
+            if sender.send(Message::Text(text.into())).await.is_err() {
+                break 'feed;
+            }
+        }
+        if feed.finished {
+            break;
+        }
+        tokio::select! {
+            msg_opt = receiver.next() => {
+                match msg_opt {
+                    // Spectators are read-only; ignore anything they send
+                    Some(Ok(_)) => {}
+                    Some(Err(_)) | None => break,
+                }
+            }
+            _ = ticker.tick() => lines = feed.tick(),
+            event_res = rx.recv() => {
+                match event_res {
+                    Ok(StampedMessage {
+                        message: ServerMessage::ShotResult { .. } | ServerMessage::SpecialShotResult { .. },
+                        ..
+                    }) => {
+                        if let Some(now) = handle.call(|g| facts(g)).await {
+                            lines = feed.ships(&now);
+                        }
+                    }
+                    Ok(event) => lines = feed.apply(&event.message),
+                    // Missed shots can only have changed ship counts; catch up on those
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!(target: "ws", "Referee feed lagged by {} events", n);
+                        if let Some(now) = handle.call(|g| facts(g)).await {
+                            lines = feed.ships(&now);
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }
+    }
+    let _ = sender.close().await;
+    handle.cast(|g| g.spectator_moved(false)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seat(handle: &str, ships_left: u32) -> Seat {
+        Seat {
+            id: Uuid::new_v4(),
+            handle: handle.to_string(),
+            ships_left,
+            locked_since: None,
+            announced_minutes: 0,
+        }
+    }
+
+    #[test]
+    fn test_lock_minutes_announced_once_each() {
+        let (mut feed, opening) = Feed::open(Facts {
+            started_at: None,
+            hide_ships: true,
+            seats: vec![seat("tourist", 5), seat("petr", 5)],
+            game_over: None,
+        });
+        assert!(
+            opening.is_empty(),
+            "fog of war keeps ship counts off the feed"
+        );
+        let id = feed.seats[0].id;
+
+        feed.apply(&ServerMessage::WeaponsLocked { player_id: id });
+        assert!(feed.tick().is_empty());
+        feed.seats[0].locked_since = Instant::now().checked_sub(Duration::from_secs(6 * 60 + 5));
+        assert_eq!(
+            feed.tick(),
+            vec![RefereeEvent::StillLocked {
+                player_id: id,
+                minutes: 6
+            }]
+        );
+        assert!(feed.tick().is_empty());
+        assert_eq!(
+            feed.message(RefereeEvent::StillLocked {
+                player_id: id,
+                minutes: 6
+            })
+            .text,
+            "tourist has been locked for 6 minutes"
+        );
+
+        let unlocked = feed.apply(&ServerMessage::WeaponsUnlocked {
+            player_id: id,
+            reason: "solved".to_string(),
+        });
+        assert_eq!(
+            unlocked,
+            vec![RefereeEvent::Unlocked {
+                player_id: id,
+                reason: "solved".to_string(),
+                minutes_locked: 6
+            }]
+        );
+        assert!(feed.tick().is_empty());
+    }
+
+    #[test]
+    fn test_armada_ships_left_counts_both_fleets() {
+        let config = crate::state::GameConfig { armada: true, ..Default::default() };
+        let mut game = Game::new(Uuid::new_v4(), "tourist".to_string(), config);
+        let mut rng = rand::thread_rng();
+        for board in [&mut game.player1.home, game.player1.forward.insert(Default::default())] {
+            let (grid, ships) = battle_cp_core::place_fleet(&crate::game::random_fleet(&mut rng)).unwrap();
+            *board = crate::state::Board { grid, ships };
+        }
+        assert_eq!(facts(&game).seats[0].ships_left, 10);
+    }
+}
@@ -0,0 +1,197 @@
+//! Panic and error reporting.
+//!
+//! `install_panic_hook` (called from `main`) logs every panic under the
+//! `panic` target and turns it into a report. Long-running tasks (the global
+//! ticker, game actors, sockets and their helpers) run inside `in_task`, so a
+//! report says which task died and, for game tasks, which game. Errors worth
+//! an operator's attention (CF verification failing after retries, a game that
+//! can't be initialized, lost match reports) go through `report_error`.
+//!
+//! Reports are POSTed as JSON to `webhooks.error_url` (env `ERROR_REPORT_URL`)
+//! when set — a Discord-compatible `content` line plus the structured fields —
+//! at most `MAX_REPORTS_PER_MINUTE` per minute so a panic loop can't flood it.
+
+use serde::Serialize;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+const MAX_REPORTS_PER_MINUTE: u32 = 20;
+/// Backtraces are cut to this many bytes in webhook payloads
+const MAX_BACKTRACE_BYTES: usize = 4000;
+
+/// Which long-running task code is executing in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TaskContext {
+    pub task: &'static str,
+    pub game_id: Option<Uuid>,
+}
+
+tokio::task_local! {
+    static CONTEXT: TaskContext;
+}
+
+/// Run `fut` with `task` / `game_id` attached to any report raised inside it.
+pub fn in_task<F: Future>(
+    task: &'static str,
+    game_id: Option<Uuid>,
+    fut: F,
+) -> impl Future<Output = F::Output> {
+    CONTEXT.scope(TaskContext { task, game_id }, fut)
+}
+
+/// The context of the task currently being polled, if any.
+pub fn current_task() -> Option<TaskContext> {
+    CONTEXT.try_with(|c| *c).ok()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportKind {
+    Panic,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub kind: ReportKind,
+    pub message: String,
+    /// `file:line` of a panic
+    pub location: Option<String>,
+    pub task: Option<&'static str>,
+    pub game_id: Option<Uuid>,
+    pub backtrace: Option<String>,
+    /// Seconds since the Unix epoch
+    pub at: u64,
+}
+
+/// Log panics with their task context and report them. The hook runs for
+/// panics in spawned tasks too, which tokio would otherwise only surface as a
+/// `JoinError` nobody awaits.
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "Box<dyn Any>".to_string());
+        let location = info.location().map(|l| format!("{}:{}", l.file(), l.line()));
+        let backtrace = std::backtrace::Backtrace::force_capture().to_string();
+        let context = current_task();
+        tracing::error!(
+            target: "panic",
+            task = context.map(|c| c.task),
+            game_id = ?context.and_then(|c| c.game_id),
+            "Panic at {}: {}\n{}",
+            location.as_deref().unwrap_or("<unknown>"),
+            message,
+            backtrace
+        );
+        send(Report {
+            kind: ReportKind::Panic,
+            message,
+            location,
+            task: context.map(|c| c.task),
+            game_id: context.and_then(|c| c.game_id),
+            backtrace: Some(truncate(backtrace, MAX_BACKTRACE_BYTES)),
+            at: unix_now(),
+        });
+    }));
+}
+
+/// Log and report an error. `game_id` defaults to the current task's game.
+pub fn report_error(game_id: Option<Uuid>, message: impl Into<String>) {
+    let message = message.into();
+    let context = current_task();
+    let game_id = game_id.or(context.and_then(|c| c.game_id));
+    tracing::error!(target: "report", task = context.map(|c| c.task), game_id = ?game_id, "{}", message);
+    send(Report {
+        kind: ReportKind::Error,
+        message,
+        location: None,
+        task: context.map(|c| c.task),
+        game_id,
+        backtrace: None,
+        at: unix_now(),
+    });
+}
+
+/// POST the report to the configured sink. Fire-and-forget.
+fn send(report: Report) {
+    let url = match &crate::config::global().webhooks.error_url {
+        Some(u) if !u.is_empty() => u.clone(),
+        _ => return,
+    };
+    if !take_budget() {
+        return;
+    }
+    // Outside a runtime (e.g. a panic on a plain thread) there is nothing to spawn on
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    let content = format!(
+        "🚨 {} in {}{}: {}",
+        match report.kind {
+            ReportKind::Panic => "Panic",
+            ReportKind::Error => "Error",
+        },
+        report.task.unwrap_or("server"),
+        report.game_id.map(|g| format!(" (game {})", g)).unwrap_or_default(),
+        report.message
+    );
+    let body = serde_json::json!({ "content": content, "report": report });
+    runtime.spawn(async move {
+        static CLIENT: std::sync::OnceLock<reqwest::Client> = std::sync::OnceLock::new();
+        let client = CLIENT.get_or_init(|| {
+            reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default()
+        });
+        match client.post(&url).json(&body).send().await {
+            Ok(resp) if !resp.status().is_success() => {
+                tracing::warn!("Error report webhook returned {}", resp.status())
+            }
+            Err(e) => tracing::warn!("Error report webhook POST failed: {}", e),
+            Ok(_) => {}
+        }
+    });
+}
+
+/// Fixed one-minute window shared by all reports.
+fn take_budget() -> bool {
+    static WINDOW: Mutex<Option<(Instant, u32)>> = Mutex::new(None);
+    let mut window = WINDOW.lock().unwrap_or_else(|e| e.into_inner());
+    let now = Instant::now();
+    let (start, count) = window.get_or_insert((now, 0));
+    if now.duration_since(*start) >= Duration::from_secs(60) {
+        *start = now;
+        *count = 0;
+    }
+    if *count >= MAX_REPORTS_PER_MINUTE {
+        return false;
+    }
+    *count += 1;
+    true
+}
+
+fn truncate(mut s: String, max: usize) -> String {
+    if s.len() > max {
+        let mut end = max;
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        s.truncate(end);
+        s.push_str("\n…");
+    }
+    s
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,102 @@
+//! HTTP caching for `GET /api/contest/:contest_id`.
+//!
+//! The serialized problem list is kept in a small LRU keyed by contest ID,
+//! together with an `ETag` (a hash of the body). Responses carry the ETag and
+//! `Cache-Control: public, max-age=<contest cache TTL>`, and a request whose
+//! `If-None-Match` matches gets an empty 304. The ETag depends only on the
+//! body, so a list refetched from Codeforces after the TTL still validates if
+//! nothing changed.
+
+use axum::body::Bytes;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Most contest responses kept; the least recently used goes first.
+pub const CAPACITY: usize = 64;
+
+/// A serialized response and its validator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cached {
+    pub body: Bytes,
+    pub etag: String,
+}
+
+impl Cached {
+    pub fn new(body: Bytes) -> Self {
+        let digest = Sha256::digest(&body);
+        let hex: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+        Self { etag: format!("\"{}\"", hex), body }
+    }
+
+    /// Whether an `If-None-Match` header value names this response.
+    pub fn matches(&self, if_none_match: &str) -> bool {
+        if_none_match
+            .split(',')
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag == "*" || tag == self.etag)
+    }
+}
+
+struct Entry {
+    cached: Cached,
+    stored_at: Instant,
+    last_used: u64,
+}
+
+pub struct ResponseCache {
+    ttl: Duration,
+    capacity: usize,
+    /// contest ID → entry, plus a use counter for picking the LRU victim
+    entries: Mutex<(HashMap<i32, Entry>, u64)>,
+}
+
+impl ResponseCache {
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self { ttl, capacity: capacity.max(1), entries: Mutex::new((HashMap::new(), 0)) }
+    }
+
+    /// The `Cache-Control` value to send with these responses.
+    pub fn cache_control(&self) -> String {
+        format!("public, max-age={}", self.ttl.as_secs())
+    }
+
+    /// The cached response for `contest_id`, if it is within the TTL.
+    pub fn get(&self, contest_id: i32) -> Option<Cached> {
+        let mut guard = self.entries.lock().unwrap();
+        let (entries, clock) = &mut *guard;
+        *clock += 1;
+        let entry = entries.get_mut(&contest_id)?;
+        if entry.stored_at.elapsed() >= self.ttl {
+            entries.remove(&contest_id);
+            return None;
+        }
+        entry.last_used = *clock;
+        Some(entry.cached.clone())
+    }
+
+    /// Store `body` for `contest_id`, evicting the least recently used entry
+    /// when full.
+    pub fn insert(&self, contest_id: i32, body: Bytes) -> Cached {
+        let cached = Cached::new(body);
+        let mut guard = self.entries.lock().unwrap();
+        let (entries, clock) = &mut *guard;
+        *clock += 1;
+        if !entries.contains_key(&contest_id) && entries.len() >= self.capacity {
+            if let Some(&oldest) = entries.iter().min_by_key(|(_, e)| e.last_used).map(|(id, _)| id) {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(contest_id, Entry { cached: cached.clone(), stored_at: Instant::now(), last_used: *clock });
+        cached
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
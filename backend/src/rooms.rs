@@ -0,0 +1,368 @@
+//! Rooms: named, long-lived spaces for a club or class.
+//!
+//! Members (CF handles) join a room, see its ongoing games, challenge each
+//! other and build up a room leaderboard. Joining hands the member a room
+//! token, and everything they do in the room afterwards (leaving, challenging,
+//! answering a challenge) has to present it; the handle alone proves nothing.
+//! A challenge is an ordinary lobby whose guest seat is reserved for the
+//! challenged member's ID and handle: the challenger gets host credentials
+//! straight away, the challenged member claims the seat by accepting, and an
+//! unanswered challenge expires with the lobby timeout.
+//!
+//! Results of games that reached combat are added to the room's standings by
+//! a follower on the game's broadcasts (see `follow_game`). Rooms are held in
+//! memory for the life of the process (`limits.max_rooms`) and are never swept.
+
+use dashmap::DashMap;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use subtle::ConstantTimeEq;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::actor::WeakGameHandle;
+use crate::error::RoomError;
+use crate::protocol::{ServerMessage, StampedMessage};
+
+const MAX_MEMBERS: usize = 500;
+/// Finished or removed games are dropped from a room's list past this many.
+const MAX_ROOM_GAMES: usize = 200;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Member {
+    pub cf_handle: String,
+    /// Seconds since the Unix epoch
+    pub joined_at: u64,
+    /// Handed out on join; proves the caller is this member
+    #[serde(skip)]
+    token: String,
+}
+
+impl Member {
+    fn holds(&self, token: Option<&str>) -> bool {
+        token.is_some_and(|t| bool::from(self.token.as_bytes().ct_eq(t.as_bytes())))
+    }
+}
+
+/// A member's record in the room leaderboard.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Standing {
+    pub cf_handle: String,
+    pub played: u32,
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+/// An open challenge, as listed in the room.
+#[derive(Debug, Clone, Serialize)]
+pub struct Challenge {
+    pub game_id: Uuid,
+    pub from: String,
+    pub to: String,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+    /// Seat reserved for `to`; handed out only on accept
+    #[serde(skip)]
+    guest_id: Uuid,
+}
+
+#[derive(Debug)]
+struct Room {
+    name: String,
+    created_at: u64,
+    /// Keyed by lowercase handle
+    members: BTreeMap<String, Member>,
+    /// Games created in the room, oldest first
+    games: Vec<Uuid>,
+    challenges: HashMap<Uuid, Challenge>,
+    /// Keyed by lowercase handle
+    standings: HashMap<String, Standing>,
+}
+
+/// Everything `GET /api/rooms/:slug` shows except the live game snapshots.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomSnapshot {
+    pub slug: String,
+    pub name: String,
+    pub created_at: u64,
+    pub members: Vec<Member>,
+    pub games: Vec<Uuid>,
+    pub challenges: Vec<Challenge>,
+    /// Most wins first, then fewest losses
+    pub leaderboard: Vec<Standing>,
+}
+
+#[derive(Debug, Default)]
+pub struct RoomRegistry {
+    rooms: DashMap<String, Room>,
+}
+
+impl RoomRegistry {
+    /// Create a room; its slug is derived from the name.
+    pub fn create(&self, name: &str, max_rooms: usize) -> Result<String, RoomError> {
+        let name = name.trim();
+        let slug = slugify(name);
+        if !(3..=40).contains(&name.chars().count()) || slug.len() < 3 {
+            return Err(RoomError::InvalidName);
+        }
+        if self.rooms.len() >= max_rooms {
+            return Err(RoomError::TooManyRooms);
+        }
+        match self.rooms.entry(slug.clone()) {
+            dashmap::Entry::Occupied(_) => Err(RoomError::NameTaken),
+            dashmap::Entry::Vacant(v) => {
+                v.insert(Room {
+                    name: name.to_string(),
+                    created_at: unix_now(),
+                    members: BTreeMap::new(),
+                    games: Vec::new(),
+                    challenges: HashMap::new(),
+                    standings: HashMap::new(),
+                });
+                Ok(slug)
+            }
+        }
+    }
+
+    /// Join as `cf_handle`; returns the member's room token. A handle already
+    /// in the room only gets its token back by presenting it.
+    pub fn join(&self, slug: &str, cf_handle: &str, token: Option<&str>) -> Result<String, RoomError> {
+        let mut room = self.rooms.get_mut(slug).ok_or(RoomError::NotFound)?;
+        let key = cf_handle.to_lowercase();
+        if let Some(member) = room.members.get(&key) {
+            if !member.holds(token) {
+                return Err(RoomError::HandleTaken);
+            }
+            return Ok(member.token.clone());
+        }
+        if room.members.len() >= MAX_MEMBERS {
+            return Err(RoomError::RoomFull);
+        }
+        let token = Uuid::new_v4().simple().to_string();
+        room.members.insert(
+            key,
+            Member {
+                cf_handle: cf_handle.to_string(),
+                joined_at: unix_now(),
+                token: token.clone(),
+            },
+        );
+        Ok(token)
+    }
+
+    /// Leave the room. Standings are kept in case they come back.
+    pub fn leave(&self, slug: &str, cf_handle: &str, token: Option<&str>) -> Result<(), RoomError> {
+        let mut room = self.rooms.get_mut(slug).ok_or(RoomError::NotFound)?;
+        authenticate(&room, cf_handle, token)?;
+        room.members.remove(&cf_handle.to_lowercase());
+        Ok(())
+    }
+
+    /// Both handles must be members and `token` must be `from`'s; returns
+    /// their stored spelling.
+    pub fn members(&self, slug: &str, from: &str, to: &str, token: Option<&str>) -> Result<(String, String), RoomError> {
+        let room = self.rooms.get(slug).ok_or(RoomError::NotFound)?;
+        let from = authenticate(&room, from, token)?;
+        let to = room
+            .members
+            .get(&to.to_lowercase())
+            .map(|m| m.cf_handle.clone())
+            .ok_or(RoomError::NotMember)?;
+        Ok((from, to))
+    }
+
+    /// Record a challenge whose lobby (`game_id`) has just been created.
+    pub fn add_challenge(
+        &self,
+        slug: &str,
+        game_id: Uuid,
+        from: String,
+        to: String,
+        guest_id: Uuid,
+    ) {
+        if let Some(mut room) = self.rooms.get_mut(slug) {
+            room.challenges.insert(
+                game_id,
+                Challenge {
+                    game_id,
+                    from,
+                    to,
+                    created_at: unix_now(),
+                    guest_id,
+                },
+            );
+            room.games.push(game_id);
+        }
+    }
+
+    /// Accept a challenge as `cf_handle`, whose room token is `token`: returns
+    /// the reserved guest ID.
+    pub fn accept(&self, slug: &str, game_id: Uuid, cf_handle: &str, token: Option<&str>) -> Result<Uuid, RoomError> {
+        let mut room = self.rooms.get_mut(slug).ok_or(RoomError::NotFound)?;
+        authenticate(&room, cf_handle, token)?;
+        let challenge = room
+            .challenges
+            .get(&game_id)
+            .ok_or(RoomError::ChallengeNotFound)?;
+        if !challenge.to.eq_ignore_ascii_case(cf_handle) {
+            return Err(RoomError::NotChallenged);
+        }
+        let guest_id = challenge.guest_id;
+        room.challenges.remove(&game_id);
+        Ok(guest_id)
+    }
+
+    /// Withdraw (challenger) or decline (challenged) an open challenge.
+    pub fn decline(&self, slug: &str, game_id: Uuid, cf_handle: &str, token: Option<&str>) -> Result<(), RoomError> {
+        let mut room = self.rooms.get_mut(slug).ok_or(RoomError::NotFound)?;
+        authenticate(&room, cf_handle, token)?;
+        let challenge = room
+            .challenges
+            .get(&game_id)
+            .ok_or(RoomError::ChallengeNotFound)?;
+        if !challenge.to.eq_ignore_ascii_case(cf_handle)
+            && !challenge.from.eq_ignore_ascii_case(cf_handle)
+        {
+            return Err(RoomError::NotChallenged);
+        }
+        room.challenges.remove(&game_id);
+        Ok(())
+    }
+
+    pub fn snapshot(&self, slug: &str) -> Option<RoomSnapshot> {
+        let room = self.rooms.get(slug)?;
+        let mut challenges: Vec<_> = room.challenges.values().cloned().collect();
+        challenges.sort_by_key(|c| c.created_at);
+        let mut leaderboard: Vec<_> = room.standings.values().cloned().collect();
+        leaderboard.sort_by(|a, b| {
+            b.wins
+                .cmp(&a.wins)
+                .then(a.losses.cmp(&b.losses))
+                .then_with(|| a.cf_handle.to_lowercase().cmp(&b.cf_handle.to_lowercase()))
+        });
+        Some(RoomSnapshot {
+            slug: slug.to_string(),
+            name: room.name.clone(),
+            created_at: room.created_at,
+            members: room.members.values().cloned().collect(),
+            games: room.games.clone(),
+            challenges,
+            leaderboard,
+        })
+    }
+
+    /// Drop games that no longer exist from the room's list.
+    pub fn retain_games(&self, slug: &str, exists: impl Fn(&Uuid) -> bool) {
+        if let Some(mut room) = self.rooms.get_mut(slug) {
+            room.games.retain(|id| exists(id));
+            let excess = room.games.len().saturating_sub(MAX_ROOM_GAMES);
+            room.games.drain(..excess);
+        }
+    }
+
+    /// Count a played game. `winner` is the winner's handle, `None` for a draw.
+    pub fn record_result(&self, slug: &str, players: [&str; 2], winner: Option<&str>) {
+        let Some(mut room) = self.rooms.get_mut(slug) else {
+            return;
+        };
+        for handle in players {
+            let standing = room.standings.entry(handle.to_lowercase()).or_default();
+            standing.cf_handle = handle.to_string();
+            standing.played += 1;
+            match winner {
+                None => standing.draws += 1,
+                Some(w) if w.eq_ignore_ascii_case(handle) => standing.wins += 1,
+                Some(_) => standing.losses += 1,
+            }
+        }
+    }
+
+    fn close_challenge(&self, slug: &str, game_id: Uuid) {
+        if let Some(mut room) = self.rooms.get_mut(slug) {
+            room.challenges.remove(&game_id);
+        }
+    }
+}
+
+/// `cf_handle`'s stored spelling, if they are a member and `token` is theirs.
+fn authenticate(room: &Room, cf_handle: &str, token: Option<&str>) -> Result<String, RoomError> {
+    let member = room.members.get(&cf_handle.to_lowercase()).ok_or(RoomError::NotMember)?;
+    if !member.holds(token) {
+        return Err(RoomError::Unauthorized);
+    }
+    Ok(member.cf_handle.clone())
+}
+
+/// Follow a room game until it ends or is removed: close its challenge and,
+/// if it reached combat, add the result to the room's standings.
+pub fn follow_game(
+    rooms: Arc<RoomRegistry>,
+    slug: String,
+    handle: WeakGameHandle,
+    mut rx: broadcast::Receiver<StampedMessage>,
+) {
+    let game_id = handle.id;
+    tokio::spawn(crate::reporting::in_task(
+        "room_follower",
+        Some(game_id),
+        async move {
+            let winner_id = loop {
+                match rx.recv().await {
+                    Ok(StampedMessage { message: ServerMessage::GameOver { winner_id, .. }, .. }) => {
+                        break winner_id
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => {
+                        rooms.close_challenge(&slug, game_id);
+                        return;
+                    }
+                }
+            };
+            rooms.close_challenge(&slug, game_id);
+            let result = handle
+                .call(move |game| {
+                    game.game_started_at?;
+                    // Co-op games have no loser to rank
+                    if game.config.coop {
+                        return None;
+                    }
+                    let guest = game.player2.as_ref()?;
+                    let winner = winner_id
+                        .and_then(|id| game.player(id))
+                        .map(|p| p.cf_handle.clone());
+                    Some((
+                        game.player1.cf_handle.clone(),
+                        guest.cf_handle.clone(),
+                        winner,
+                    ))
+                })
+                .await
+                .flatten();
+            if let Some((host, guest, winner)) = result {
+                rooms.record_result(&slug, [&host, &guest], winner.as_deref());
+            }
+        },
+    ));
+}
+
+/// `"CP Club @ IIT"` → `"cp-club-iit"`.
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
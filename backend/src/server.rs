@@ -0,0 +1,473 @@
+//! The router, the embeddable `Server`, and listener setup: bind address,
+//! port and optional built-in TLS.
+//!
+//! `Server::builder()` is the library entry point: it builds the state and
+//! router from a config (or an injected `AppState`, judge and game registry),
+//! and the result can be served standalone or mounted into a larger axum app
+//! with `Router::nest`.
+//!
+//! Behind nginx (the usual deployment) the server speaks plain HTTP. Small
+//! deployments without a reverse proxy can set `TLS_CERT_PATH` and
+//! `TLS_KEY_PATH` (PEM files, e.g. from certbot) and the server terminates TLS
+//! itself with rustls, so browsers can connect over `https://` / `wss://`.
+//! `BIND_ADDR` (default `0.0.0.0`) and `PORT` (default `3000`) pick the socket.
+//! All four can also be set under `[server]` in the config file.
+//!
+//! On SIGINT or SIGTERM a standalone server closes every game socket with
+//! `server_shutdown` (so clients know to reconnect) before it stops.
+
+use axum::{routing::get, Router};
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tower_http::trace::TraceLayer;
+use axum_server::tls_rustls::RustlsConfig;
+use dashmap::DashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::actor::GameHandle;
+use crate::cf_client::{CFClient, CfApiQueue};
+use crate::config::{Config, CorsConfig, ServerConfig};
+use crate::judge::JudgeClient;
+use crate::rooms::RoomRegistry;
+use crate::state::AppState;
+use crate::{handlers, ws};
+
+const REQUEST_ID_HEADER: axum::http::HeaderName =
+    axum::http::HeaderName::from_static("x-request-id");
+
+/// How long sockets get to send their Close frames on shutdown.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(1);
+
+/// Every route with its middleware: what the server binary serves, and what
+/// the integration tests spin up.
+pub fn router(app_state: AppState, cors: &CorsConfig) -> Router {
+    // CORS origins (exact, wildcard subdomain or regex), or localhost defaults
+    let allowed_origins = crate::cors::AllowedOrigins::from_config(cors);
+
+    Router::new()
+        .route("/", get(root))
+        .route("/health", get(health))
+        .route(
+            "/api/game",
+            axum::routing::post(handlers::create_game).layer(axum::middleware::from_fn_with_state(
+                app_state.ip_limiters.create_game.clone(),
+                crate::rate_limit::limit_by_ip,
+            )),
+        )
+        .route(
+            "/api/game/{game_id}",
+            get(handlers::get_game).delete(handlers::cancel_game),
+        )
+        .route("/api/game/{game_id}/events", get(handlers::get_game_events))
+        .route("/api/game/{game_id}/report", get(handlers::get_game_report))
+        .route("/api/game/{game_id}/analysis", get(handlers::get_game_analysis))
+        .route("/api/game/{game_id}/board.png", get(handlers::get_board_png))
+        .route("/api/game/{game_id}/board.svg", get(handlers::get_board_svg))
+        .route(
+            "/api/players/{player_id}/games",
+            get(handlers::list_player_games),
+        )
+        .route(
+            "/api/contest/{contest_id}",
+            get(handlers::get_contest_problems).layer(axum::middleware::from_fn_with_state(
+                app_state.ip_limiters.contest.clone(),
+                crate::rate_limit::limit_by_ip,
+            )),
+        )
+        .route(
+            "/api/suggest-config",
+            get(handlers::suggest_config).layer(axum::middleware::from_fn_with_state(
+                app_state.ip_limiters.contest.clone(),
+                crate::rate_limit::limit_by_ip,
+            )),
+        )
+        .route(
+            "/api/rooms",
+            axum::routing::post(handlers::create_room).layer(axum::middleware::from_fn_with_state(
+                app_state.ip_limiters.create_game.clone(),
+                crate::rate_limit::limit_by_ip,
+            )),
+        )
+        .route("/api/rooms/{slug}", get(handlers::get_room))
+        .route("/api/rooms/{slug}/leaderboard", get(handlers::get_room_leaderboard))
+        .route(
+            "/api/rooms/{slug}/members",
+            axum::routing::post(handlers::join_room).delete(handlers::leave_room),
+        )
+        .route(
+            "/api/rooms/{slug}/challenges",
+            axum::routing::post(handlers::create_challenge).layer(axum::middleware::from_fn_with_state(
+                app_state.ip_limiters.create_game.clone(),
+                crate::rate_limit::limit_by_ip,
+            )),
+        )
+        .route(
+            "/api/rooms/{slug}/challenges/{game_id}/accept",
+            axum::routing::post(handlers::accept_challenge),
+        )
+        .route(
+            "/api/rooms/{slug}/challenges/{game_id}/decline",
+            axum::routing::post(handlers::decline_challenge),
+        )
+        .route("/ws/{game_id}", get(ws::ws_handler))
+        .route("/ws/{game_id}/referee", get(crate::referee::referee_ws_handler))
+        .route("/api/admin/ws", get(crate::admin::admin_ws_handler))
+        .route(
+            "/api/admin/game/{game_id}",
+            axum::routing::delete(crate::admin::terminate_game),
+        )
+        .route(
+            "/api/admin/game/{game_id}/player/{player_id}",
+            axum::routing::post(crate::admin::adjust_player),
+        )
+        .route(
+            "/api/admin/game/{game_id}/audit",
+            get(crate::admin::game_audit_log),
+        )
+        .route(
+            "/api/admin/game/{game_id}/events",
+            get(crate::admin::game_event_log),
+        )
+        .route("/api/admin/games", axum::routing::post(crate::admin::bulk_create_games))
+        .route("/api/admin/bots", axum::routing::post(crate::admin::issue_bot_token))
+        .route("/api/admin/metrics", get(crate::metrics::tournament_metrics))
+        .route(
+            "/api/admin/log",
+            get(crate::admin::get_log_filter).put(crate::admin::set_log_filter),
+        )
+        .route(
+            "/api/admin/bans",
+            get(crate::admin::list_bans)
+                .post(crate::admin::add_ban)
+                .delete(crate::admin::remove_ban),
+        )
+        // Request IDs: assign (or keep the caller's) x-request-id, tag the
+        // request's span with it, and echo it back on the response.
+        .layer(
+            TraceLayer::new_for_http().make_span_with(|req: &axum::http::Request<_>| {
+                let request_id = req
+                    .headers()
+                    .get(REQUEST_ID_HEADER)
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or("-");
+                tracing::info_span!(
+                    "http",
+                    method = %req.method(),
+                    uri = %req.uri(),
+                    request_id = %request_id,
+                )
+            }),
+        )
+        .layer(PropagateRequestIdLayer::new(REQUEST_ID_HEADER))
+        .layer(SetRequestIdLayer::new(REQUEST_ID_HEADER, MakeRequestUuid))
+        .layer(allowed_origins.layer(REQUEST_ID_HEADER))
+        // Security Headers
+        .layer(tower_http::set_header::SetResponseHeaderLayer::overriding(
+            axum::http::header::X_CONTENT_TYPE_OPTIONS,
+            axum::http::HeaderValue::from_static("nosniff"),
+        ))
+        .layer(tower_http::set_header::SetResponseHeaderLayer::overriding(
+            axum::http::header::X_FRAME_OPTIONS,
+            axum::http::HeaderValue::from_static("DENY"),
+        ))
+        .layer(tower_http::set_header::SetResponseHeaderLayer::overriding(
+            axum::http::header::X_XSS_PROTECTION,
+            axum::http::HeaderValue::from_static("1; mode=block"),
+        ))
+        .layer(tower_http::set_header::SetResponseHeaderLayer::overriding(
+            axum::http::header::STRICT_TRANSPORT_SECURITY,
+            axum::http::HeaderValue::from_static("max-age=31536000; includeSubDomains; preload"),
+        ))
+        .with_state(app_state)
+}
+
+async fn root() -> &'static str {
+    "Battle CP Backend Online, made by oGhostyyy"
+}
+
+/// Health check for Azure Container Apps probes and uptime monitoring.
+/// Returns active game count and the liveness of supervised background tasks;
+/// `status` is "degraded" while one of them is down or stalled. Always 200, as
+/// restarting the container would drop every in-memory game.
+async fn health(
+    axum::extract::State(state): axum::extract::State<AppState>,
+) -> axum::Json<serde_json::Value> {
+    let games = state.all_games();
+    let total = games.len();
+    let active = games
+        .iter()
+        .filter(|g| {
+            matches!(
+                g.status(),
+                crate::state::GameStatus::Playing | crate::state::GameStatus::SuddenDeath
+            )
+        })
+        .count();
+    let tasks = state.supervisor.statuses();
+    let status = if tasks.iter().all(|t| t.healthy) { "ok" } else { "degraded" };
+    axum::Json(serde_json::json!({
+        "status": status,
+        "games_total": total,
+        "games_active": active,
+        "tasks": tasks,
+    }))
+}
+
+/// The game engine as a library: its state plus the router serving it.
+///
+/// ```ignore
+/// let battle = backend::Server::builder().config(config).build();
+/// let app = Router::new()
+///     .route("/", get(home))
+///     .nest("/battle", battle.router());
+/// ```
+///
+/// Clients then use `/battle/ws/{game_id}`, `/battle/api/game` and so on.
+pub struct Server {
+    state: AppState,
+    router: Router,
+}
+
+impl Server {
+    pub fn builder() -> ServerBuilder {
+        ServerBuilder::default()
+    }
+
+    /// The shared state, for creating or inspecting games from the host app.
+    pub fn state(&self) -> &AppState {
+        &self.state
+    }
+
+    /// Every route and layer, ready to `nest` or `merge` into another router.
+    pub fn router(&self) -> Router {
+        self.router.clone()
+    }
+
+    /// Run standalone on `listen` until SIGINT or SIGTERM, then close every
+    /// game socket and stop.
+    pub async fn serve(self, listen: ListenConfig) -> anyhow::Result<()> {
+        let state = self.state;
+        serve_until(self.router, listen, async move {
+            shutdown_signal().await;
+            tracing::info!("Shutting down: closing game sockets");
+            state.close_sockets(ws::CloseTarget::All, crate::protocol::CloseCode::ServerShutdown);
+            tokio::time::sleep(SHUTDOWN_GRACE).await;
+        })
+        .await
+    }
+}
+
+/// Builds a `Server`. Anything not injected comes from the config: the
+/// installed one, or `Config::load()` as in `AppState::new`.
+#[derive(Default)]
+pub struct ServerBuilder {
+    config: Option<Config>,
+    state: Option<AppState>,
+    judge: Option<(CFClient, CfApiQueue)>,
+    judge_plugins: Vec<Arc<dyn JudgeClient>>,
+    games: Option<Arc<DashMap<Uuid, GameHandle>>>,
+    rooms: Option<Arc<RoomRegistry>>,
+    cors: Option<CorsConfig>,
+    no_background: bool,
+}
+
+impl ServerBuilder {
+    /// Settings for the state, CORS and background sweeps. Also installed as
+    /// the process-wide config (see `config::install`) unless one already is.
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Start from an existing state instead of building one from the config.
+    /// Injected judge and registries still replace its own.
+    pub fn state(mut self, state: AppState) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// The Codeforces client and API queue. Sharing one queue between several
+    /// servers keeps them under CF's rate limit together.
+    pub fn judge(mut self, cf_client: CFClient, cf_queue: CfApiQueue) -> Self {
+        self.judge = Some((cf_client, cf_queue));
+        self
+    }
+
+    /// Another judge games can be played on (see `judge.rs`), e.g. a
+    /// university's. Registering one under a built-in id replaces it.
+    pub fn judge_plugin(mut self, judge: Arc<dyn JudgeClient>) -> Self {
+        self.judge_plugins.push(judge);
+        self
+    }
+
+    /// Where game actors are registered, so the host app can list and look
+    /// up games directly.
+    pub fn games(mut self, games: Arc<DashMap<Uuid, GameHandle>>) -> Self {
+        self.games = Some(games);
+        self
+    }
+
+    pub fn rooms(mut self, rooms: Arc<RoomRegistry>) -> Self {
+        self.rooms = Some(rooms);
+        self
+    }
+
+    /// CORS origins for the engine's routes; defaults to the config's `[cors]`.
+    pub fn cors(mut self, cors: CorsConfig) -> Self {
+        self.cors = Some(cors);
+        self
+    }
+
+    /// Don't spawn the global ticker (cleanup and timeout sweeps). For hosts
+    /// that run `background::spawn_global_ticker` themselves.
+    pub fn without_background_tasks(mut self) -> Self {
+        self.no_background = true;
+        self
+    }
+
+    /// Must be called inside a Tokio runtime: the CF queue and the global
+    /// ticker are spawned here.
+    pub fn build(self) -> Server {
+        let mut state = match (self.state, self.config) {
+            (Some(state), _) => state,
+            (None, Some(config)) => {
+                crate::config::install(config.clone());
+                AppState::from_config(config)
+            }
+            (None, None) => AppState::from_config(crate::config::global().clone()),
+        };
+        let mut judges = (*state.judges).clone();
+        if let Some((cf_client, cf_queue)) = self.judge {
+            judges.register(Arc::new(cf_queue.clone()));
+            state.cf_client = cf_client;
+            state.cf_queue = cf_queue;
+        }
+        for judge in self.judge_plugins {
+            judges.register(judge);
+        }
+        state.judges = Arc::new(judges);
+        if let Some(games) = self.games {
+            state.games = games;
+        }
+        if let Some(rooms) = self.rooms {
+            state.rooms = rooms;
+        }
+        if !self.no_background {
+            crate::background::spawn_global_ticker(&state);
+        }
+        let cors = self.cors.unwrap_or_else(|| state.config.cors.clone());
+        Server { router: router(state.clone(), &cors), state }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsPaths {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListenConfig {
+    pub addr: SocketAddr,
+    pub tls: Option<TlsPaths>,
+}
+
+impl ListenConfig {
+    /// From `[server]` (env `BIND_ADDR`, `PORT`, `TLS_CERT_PATH`, `TLS_KEY_PATH`).
+    pub fn from_config(config: &ServerConfig) -> Self {
+        let tls = match (&config.tls_cert_path, &config.tls_key_path) {
+            (Some(cert), Some(key)) => Some(TlsPaths { cert: cert.clone(), key: key.clone() }),
+            _ => None,
+        };
+        Self { addr: SocketAddr::new(config.bind_addr, config.port), tls }
+    }
+
+    pub fn scheme(&self) -> &'static str {
+        if self.tls.is_some() {
+            "https"
+        } else {
+            "http"
+        }
+    }
+}
+
+/// Load the PEM certificate chain and private key.
+pub async fn load_tls(paths: &TlsPaths) -> anyhow::Result<RustlsConfig> {
+    // axum-server builds its rustls config on the process-wide provider
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    RustlsConfig::from_pem_file(&paths.cert, &paths.key)
+        .await
+        .map_err(|e| {
+            anyhow::anyhow!(
+                "failed to load TLS cert {} / key {}: {}",
+                paths.cert.display(),
+                paths.key.display(),
+                e
+            )
+        })
+}
+
+/// Serve `app` until the process exits. ConnectInfo gives the rate limiter
+/// the peer address when no proxy headers are trusted.
+pub async fn serve(app: Router, config: ListenConfig) -> anyhow::Result<()> {
+    serve_until(app, config, std::future::pending()).await
+}
+
+/// Serve `app` until `shutdown` completes, then stop accepting connections.
+pub async fn serve_until(
+    app: Router,
+    config: ListenConfig,
+    shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+) -> anyhow::Result<()> {
+    let make_service = app.into_make_service_with_connect_info::<SocketAddr>();
+    match &config.tls {
+        Some(paths) => {
+            let tls = load_tls(paths).await?;
+            tracing::info!("listening on {}://{} (TLS)", config.scheme(), config.addr);
+            let handle = axum_server::Handle::new();
+            let stop = handle.clone();
+            tokio::spawn(async move {
+                shutdown.await;
+                stop.graceful_shutdown(Some(SHUTDOWN_GRACE));
+            });
+            axum_server::bind_rustls(config.addr, tls).handle(handle).serve(make_service).await?;
+        }
+        None => {
+            let listener = tokio::net::TcpListener::bind(config.addr).await?;
+            tracing::info!("listening on {}://{}", config.scheme(), config.addr);
+            axum::serve(listener, make_service).with_graceful_shutdown(shutdown).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Ctrl-C, or SIGTERM on Unix (what `docker stop` and systemd send).
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!("Cannot listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!("Cannot listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
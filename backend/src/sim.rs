@@ -0,0 +1,520 @@
+//! Deterministic game simulation for testing the rules engine.
+//!
+//! A `Simulation` plays one game through the same `handle_client_message`
+//! the WebSocket loop uses, without sockets or Codeforces: both seats hold
+//! real session tokens, solved sets count as already fetched, and a `Solve`
+//! action stands in for an accepted verification. Time is simulated —
+//! `Advance` moves every timestamp in the game back and runs the game's
+//! deadlines — so a 45-minute game plays out in milliseconds.
+//!
+//! After every action `check_invariants` must hold, and once the longest a
+//! game can last (clock plus sudden death) has passed it must be finished.
+//! `run_random` draws actions from a seeded RNG, which also seeds the game
+//! itself (`GameConfig::seed`), so a failing seed replays exactly.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::auth::{Claims, Role};
+use crate::protocol::{ClientMessage, Coord, ServerMessage, ShipPlacement, SpecialWeapon, StampedMessage};
+use crate::state::{
+    AppState, AssignedProblem, Game, GameConfig, GameStatus, Player,
+};
+
+/// Sudden death can run this long past the game clock (see `timers.rs`).
+const SUDDEN_DEATH_SECS: u64 = 600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Host,
+    Guest,
+}
+
+#[derive(Debug, Clone)]
+pub enum Action {
+    Place(Side, Vec<ShipPlacement>),
+    Ready(Side),
+    Fire(Side, Coord),
+    FireSpecial(Side, SpecialWeapon, Coord),
+    Veto(Side),
+    /// Codeforces accepts the side's assigned problem
+    Solve(Side),
+    RequestBonus(Side),
+    /// Codeforces accepts the side's bonus problem
+    SolveBonus(Side),
+    PlaceShield(Side, Coord),
+    /// Let simulated time pass and apply any deadlines that fall due
+    Advance(Duration),
+}
+
+/// An invariant broken by the action at `step` (0-based, over all actions applied).
+#[derive(Debug, thiserror::Error)]
+#[error("step {step} ({action:?}): {message}")]
+pub struct Violation {
+    pub step: usize,
+    pub action: Action,
+    pub message: String,
+}
+
+/// How a simulated game ended.
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub steps: usize,
+    pub elapsed: Duration,
+    pub game_over: Option<ServerMessage>,
+}
+
+/// One seat's connection state, as the WebSocket loop would hold it.
+struct Connection {
+    player_id: Option<Uuid>,
+    session: Option<Claims>,
+}
+
+pub struct Simulation {
+    state: AppState,
+    pub game_id: Uuid,
+    ids: [Uuid; 2],
+    conns: [Connection; 2],
+    rng: StdRng,
+    events: broadcast::Receiver<StampedMessage>,
+    elapsed: Duration,
+    steps: usize,
+    /// Every message produced so far, replies and broadcasts, in order
+    pub log: Vec<ServerMessage>,
+}
+
+impl Simulation {
+    /// A game with both seats taken and joined, waiting for ship placement.
+    /// `seed` drives the simulated players, and the game's own randomness
+    /// unless `config.seed` is set.
+    pub async fn new(mut config: GameConfig, seed: u64) -> Self {
+        config.seed.get_or_insert(seed);
+        let state = AppState::new();
+        let ids = [Uuid::new_v4(), Uuid::new_v4()];
+        let mut game = Game::new(ids[0], "sim_host".to_string(), config);
+        game.join(ids[1], "sim_guest".to_string())
+            .expect("distinct player IDs");
+        // Nothing to prefetch: the simulation never talks to Codeforces
+        game.player1.solved_set_ready = true;
+        if let Some(p2) = game.player2.as_mut() {
+            p2.solved_set_ready = true;
+        }
+        // Both sides hold a socket open for the whole run
+        game.connect(ids[0]);
+        game.connect(ids[1]);
+        let game_id = game.id;
+        let events = game.tx.subscribe();
+        state.insert_game(game);
+
+        let conns = [(ids[0], Role::Host), (ids[1], Role::Guest)].map(|(pid, role)| Connection {
+            player_id: None,
+            session: state
+                .sessions
+                .verify(&state.sessions.issue(pid, game_id, role)),
+        });
+        let mut sim = Self {
+            state,
+            game_id,
+            ids,
+            conns,
+            rng: StdRng::seed_from_u64(seed),
+            events,
+            elapsed: Duration::ZERO,
+            steps: 0,
+            log: Vec::new(),
+        };
+        for side in [Side::Host, Side::Guest] {
+            let join = ClientMessage::JoinGame {
+                player_id: sim.id(side),
+                cf_handle: String::new(),
+                locale: None,
+            };
+            sim.send(side, join).await;
+        }
+        sim
+    }
+
+    pub fn id(&self, side: Side) -> Uuid {
+        self.ids[side as usize]
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    /// Read the game as the actor currently holds it.
+    pub async fn inspect<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Game) -> R + Send + 'static,
+    ) -> R {
+        self.state
+            .with_game(self.game_id, move |game| f(game))
+            .await
+            .expect("simulated game is never removed")
+    }
+
+    /// Place both fleets, ready up and skip the countdown, leaving the game
+    /// `Playing`. An empty problem queue (no problem cache loaded) is filled
+    /// with placeholder problems so locked players always have one to solve.
+    pub async fn start(
+        &mut self,
+        host: Vec<ShipPlacement>,
+        guest: Vec<ShipPlacement>,
+    ) -> Result<(), Violation> {
+        self.apply(Action::Place(Side::Host, host)).await?;
+        self.apply(Action::Place(Side::Guest, guest)).await?;
+        self.apply(Action::Ready(Side::Host)).await?;
+        self.apply(Action::Ready(Side::Guest)).await?;
+
+        // The problem queue is built by a spawned task; wait for it to hand over
+        for _ in 0..200 {
+            if self.inspect(|g| g.status != GameStatus::Initializing).await {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let status = self.inspect(|g| g.status.clone()).await;
+        if status != GameStatus::Countdown {
+            return Err(self.violation(
+                Action::Ready(Side::Guest),
+                format!(
+                    "expected Countdown after both players readied, got {:?}",
+                    status
+                ),
+            ));
+        }
+        self.state
+            .with_game(self.game_id, |game| {
+                if game.problem_queue.is_empty() {
+                    game.problem_queue = (1..=50)
+                        .map(|contest_id| AssignedProblem {
+                            contest_id,
+                            index: "A".to_string(),
+                            name: format!("Simulated {}", contest_id),
+                            rating: 800,
+                            url: None,
+                        })
+                        .collect();
+                }
+                crate::ws::start_combat(game);
+            })
+            .await;
+        self.drain();
+        Ok(())
+    }
+
+    /// A legal fleet in random positions.
+    pub fn random_fleet(&mut self) -> Vec<ShipPlacement> {
+        crate::game::random_fleet(&mut self.rng)
+    }
+
+    /// Apply one action, then check every invariant.
+    pub async fn apply(&mut self, action: Action) -> Result<Vec<ServerMessage>, Violation> {
+        let replies = match action.clone() {
+            Action::Place(side, ships) => {
+                self.send(side, ClientMessage::PlaceShips { ships, forward: None }).await
+            }
+            Action::Ready(side) => self.send(side, ClientMessage::Ready).await,
+            Action::Fire(side, at) => self.send(side, ClientMessage::Fire { at, board: Default::default() }).await,
+            Action::FireSpecial(side, weapon, at) => {
+                self.send(side, ClientMessage::FireSpecial { weapon, at, board: Default::default() }).await
+            }
+            Action::Veto(side) => self.send(side, ClientMessage::Veto).await,
+            Action::Solve(side) => {
+                let pid = self.id(side);
+                self.state
+                    .with_game(self.game_id, move |game| {
+                        crate::ws::apply_verified_solve(game, pid)
+                    })
+                    .await;
+                vec![]
+            }
+            Action::RequestBonus(side) => self.send(side, ClientMessage::RequestBonusProblem).await,
+            Action::SolveBonus(side) => {
+                let pid = self.id(side);
+                self.state
+                    .with_game(self.game_id, move |game| crate::ws::apply_bonus_solve(game, pid))
+                    .await;
+                vec![]
+            }
+            Action::PlaceShield(side, at) => self.send(side, ClientMessage::PlaceShield { at }).await,
+            Action::Advance(by) => {
+                self.elapsed += by;
+                self.state
+                    .with_game(self.game_id, move |game| {
+                        rewind(game, by);
+                        crate::timers::fire_due(game);
+                    })
+                    .await;
+                vec![]
+            }
+        };
+        self.drain();
+
+        let (checked, status, limit) = self
+            .inspect(|g| {
+                let limit = Duration::from_secs(g.regulation_secs() + SUDDEN_DEATH_SECS);
+                (check_invariants(g), g.status.clone(), limit)
+            })
+            .await;
+        let checked = checked.and_then(|()| match status {
+            GameStatus::Finished => Ok(()),
+            _ if self.elapsed > limit => Err(format!(
+                "still {:?} after {:?} of play (limit {:?})",
+                status, self.elapsed, limit
+            )),
+            _ => Ok(()),
+        });
+        self.steps += 1;
+        match checked {
+            Ok(()) => Ok(replies),
+            Err(message) => Err(self.violation(action, message)),
+        }
+    }
+
+    /// Random fleets, then random actions until the game ends. After
+    /// `max_steps` actions the clock is run out, which must end it.
+    pub async fn run_random(&mut self, max_steps: usize) -> Result<Outcome, Violation> {
+        let (host, guest) = (self.random_fleet(), self.random_fleet());
+        self.start(host, guest).await?;
+        for _ in 0..max_steps {
+            if self.inspect(|g| g.status == GameStatus::Finished).await {
+                break;
+            }
+            let action = self.random_action().await;
+            self.apply(action).await?;
+        }
+        // Run out the clock (in steps, so deadlines fire in order)
+        while self.inspect(|g| g.status != GameStatus::Finished).await {
+            self.apply(Action::Advance(Duration::from_secs(120)))
+                .await?;
+        }
+        Ok(Outcome {
+            steps: self.steps,
+            elapsed: self.elapsed,
+            game_over: self.inspect(|g| g.game_over_msg.clone()).await,
+        })
+    }
+
+    /// Mostly shots at untargeted cells, and any special weapon held;
+    /// locked players solve, veto or wait. Now and then a side takes a
+    /// bonus problem, and shields one of its own cells once it's solved.
+    /// A few actions are deliberately illegal (repeat shots, firing while
+    /// locked) to check they're rejected without changing the game.
+    async fn random_action(&mut self) -> Action {
+        let side = if self.rng.gen_bool(0.5) {
+            Side::Host
+        } else {
+            Side::Guest
+        };
+        let pid = self.id(side);
+        let (locked, fired, special, bonus, shields, own_ships) = self
+            .inspect(move |g| {
+                let (me, them) = if g.player1.id == pid {
+                    (Some(&g.player1), g.player2.as_ref())
+                } else {
+                    (g.player2.as_ref(), Some(&g.player1))
+                };
+                (
+                    me.is_some_and(|p| p.is_locked),
+                    them.map_or(0, |p| p.home.grid.hits | p.home.grid.misses),
+                    me.and_then(|p| p.inventory.first().copied()),
+                    me.is_some_and(|p| p.bonus_problem.is_some()),
+                    me.map_or(0, |p| p.shields_available),
+                    me.map_or(0, |p| p.home.grid.ships),
+                )
+            })
+            .await;
+
+        let roll: f64 = self.rng.gen();
+        if roll < 0.03 {
+            return Action::Advance(Duration::from_secs(self.rng.gen_range(1..=600)));
+        }
+        if locked && roll < 0.95 {
+            return match self.rng.gen_range(0..3) {
+                0 => Action::Solve(side),
+                1 => Action::Veto(side),
+                _ => Action::Advance(Duration::from_secs(self.rng.gen_range(1..=300))),
+            };
+        }
+        if roll < 0.06 {
+            return if shields > 0 {
+                // Any own ship cell; hit or shielded ones are refused
+                let cells: Vec<usize> = (0..100).filter(|i| own_ships & (1u128 << i) != 0).collect();
+                let i = cells[self.rng.gen_range(0..cells.len())];
+                Action::PlaceShield(side, Coord { x: i % 10, y: i / 10 })
+            } else if bonus {
+                Action::SolveBonus(side)
+            } else {
+                Action::RequestBonus(side)
+            };
+        }
+        let open: Vec<Coord> = (0..100)
+            .filter(|i| fired & (1u128 << i) == 0 || roll > 0.98)
+            .map(|i| Coord {
+                x: i % 10,
+                y: i / 10,
+            })
+            .collect();
+        match (open.len(), special) {
+            (0, _) => Action::Advance(Duration::from_secs(60)),
+            (n, Some(weapon)) if roll < 0.5 => {
+                Action::FireSpecial(side, weapon, open[self.rng.gen_range(0..n)])
+            }
+            (n, _) => Action::Fire(side, open[self.rng.gen_range(0..n)]),
+        }
+    }
+
+    async fn send(&mut self, side: Side, msg: ClientMessage) -> Vec<ServerMessage> {
+        let conn = &mut self.conns[side as usize];
+        let replies = crate::ws::handle_client_message(
+            msg,
+            &mut conn.player_id,
+            &mut conn.session,
+            None,
+            self.game_id,
+            None,
+            &self.state,
+        )
+        .await;
+        self.log.extend(replies.iter().cloned());
+        replies
+    }
+
+    /// Move broadcasts into the log.
+    fn drain(&mut self) {
+        loop {
+            match self.events.try_recv() {
+                Ok(event) => self.log.push(event.message),
+                Err(broadcast::error::TryRecvError::Lagged(_)) => {}
+                Err(_) => break,
+            }
+        }
+    }
+
+    fn violation(&self, action: Action, message: String) -> Violation {
+        Violation {
+            step: self.steps,
+            action,
+            message,
+        }
+    }
+}
+
+/// Move every timestamp in the game `by` into the past, as if that much
+/// time had gone by.
+fn rewind(game: &mut Game, by: Duration) {
+    let back = |at| earlier(at, by);
+    game.created_at = earlier(game.created_at, by);
+    game.placement_started_at = game.placement_started_at.map(back);
+    game.game_started_at = game.game_started_at.map(back);
+    game.finished_at = game.finished_at.map(back);
+    game.abandoned_at = game.abandoned_at.map(back);
+    game.scheduled_start_at = game.scheduled_start_at.map(back);
+    for p in std::iter::once(&mut game.player1).chain(game.player2.as_mut()) {
+        p.veto_started_at = p.veto_started_at.map(back);
+        p.last_verification_attempt = p.last_verification_attempt.map(back);
+        p.locked_at_unix = p.locked_at_unix.map(|t| t.saturating_sub(by.as_secs()));
+    }
+}
+
+fn earlier(at: std::time::Instant, by: Duration) -> std::time::Instant {
+    at.checked_sub(by)
+        .expect("simulated clock ran past the start of the monotonic clock")
+}
+
+/// Rules that hold after every action, whatever the players do:
+/// - heat never passes the threshold, and weapons are locked exactly at the
+///   player's own limit (lower after hints bought with `HintCost::LowerMaxHeat`)
+/// - each player's hit, miss and sink counts match the opponent's board
+/// - a ship is sunk exactly when all its cells are hit; shots land only on
+///   the grid's ship cells as hits and elsewhere as misses; shields sit only
+///   on unhit ship cells
+/// - vetoes used never exceed the limit
+/// - a fleet with no cells left means the game is over
+pub fn check_invariants(game: &Game) -> Result<(), String> {
+    let Some(p2) = game.player2.as_ref() else {
+        return Ok(());
+    };
+    let threshold = game.heat_threshold();
+    for (me, them) in [(&game.player1, p2), (p2, &game.player1)] {
+        let who = &me.cf_handle;
+        if me.heat > threshold {
+            return Err(format!(
+                "{}: heat {} over threshold {}",
+                who, me.heat, threshold
+            ));
+        }
+        let limit = me.heat_limit(threshold);
+        if me.is_locked != (me.heat >= limit) {
+            return Err(format!(
+                "{}: locked={} at heat {}/{}",
+                who, me.is_locked, me.heat, limit
+            ));
+        }
+        if me.vetoes_used > game.config.max_vetoes {
+            return Err(format!(
+                "{}: {} vetoes used of {}",
+                who, me.vetoes_used, game.config.max_vetoes
+            ));
+        }
+        check_board(me, them)
+            .map_err(|e| format!("{} firing at {}: {}", who, them.cf_handle, e))?;
+        if them.ships_placed
+            && them.home.grid.ship_cells_remaining() == 0
+            && game.status != GameStatus::Finished
+        {
+            return Err(format!(
+                "{}'s fleet is sunk but the game is {:?}",
+                them.cf_handle, game.status
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// `shooter`'s stats against `target`'s board.
+fn check_board(shooter: &Player, target: &Player) -> Result<(), String> {
+    let grid = &target.home.grid;
+    let fleet = target.home.ships.iter().fold(0, |m, s| m | s.mask());
+    if fleet != grid.ships {
+        return Err("ship mask differs from the ships".to_string());
+    }
+    if grid.hits & !grid.ships != 0 || grid.misses & grid.ships != 0 || grid.hits & grid.misses != 0
+    {
+        return Err("hits and misses disagree with ship cells".to_string());
+    }
+    if grid.shields & !(grid.ships & !grid.hits) != 0 {
+        return Err("shield on a cell that isn't an unhit ship cell".to_string());
+    }
+    if shooter.stats.cells_hit != grid.hits.count_ones()
+        || shooter.stats.cells_missed != grid.misses.count_ones()
+    {
+        return Err(format!(
+            "stats {} hit / {} missed, board {} / {}",
+            shooter.stats.cells_hit,
+            shooter.stats.cells_missed,
+            grid.hits.count_ones(),
+            grid.misses.count_ones()
+        ));
+    }
+    for ship in &target.home.ships {
+        if ship.sunk != (ship.mask() & !grid.hits == 0) {
+            return Err(format!(
+                "ship at ({}, {}) sunk={} disagrees with hits",
+                ship.x, ship.y, ship.sunk
+            ));
+        }
+    }
+    let sunk = target.home.ships.iter().filter(|s| s.sunk).count() as u32;
+    if shooter.stats.ships_sunk != sunk {
+        return Err(format!(
+            "credited {} sinks, board has {}",
+            shooter.stats.ships_sunk, sunk
+        ));
+    }
+    Ok(())
+}
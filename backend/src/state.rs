@@ -1,16 +1,58 @@
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use tokio::sync::{broadcast, RwLock, Mutex};
+use dashmap::DashMap;
+use tokio::sync::{broadcast, watch, Mutex};
 use uuid::Uuid;
 
+use crate::actor::GameHandle;
+pub use crate::protocol::{AmmoConfig, DifficultyMode, GameStatus, HeatStep, HintCost, SuddenDeathMode};
+pub use battle_cp_core::{CellState, Grid, Ship, SpecialWeapon, TiebreakResult};
+
 #[derive(Clone)]
 pub struct AppState {
-    pub games: Arc<RwLock<HashMap<Uuid, Game>>>,
+    /// Registry of running game actors. Sharded, so lookups from different
+    /// games don't contend; game state itself lives in each actor task (see `actor.rs`).
+    pub games: Arc<DashMap<Uuid, GameHandle>>,
     pub cf_client: crate::cf_client::CFClient,
     /// Global CF API queue — routes all live API calls through a single rate-limited worker.
     pub cf_queue: crate::cf_client::CfApiQueue,
+    /// Serialized `/api/contest/:id` responses with their ETags
+    pub contest_responses: Arc<crate::response_cache::ResponseCache>,
+    /// Judges games can be verified on, Codeforces (`cf_queue`) included (see `judge.rs`).
+    pub judges: Arc<crate::judge::JudgeRegistry>,
     pub rate_limiter: Arc<Mutex<HashMap<String, (std::time::Instant, u32)>>>, //a rate limiter for game creation 
+    /// Per-IP limiters for REST endpoints (see `rate_limit.rs`).
+    pub ip_limiters: crate::rate_limit::IpLimiters,
+    /// Budgets on CF verifications per game and per IP (see `rate_limit.rs`).
+    pub verify_limiters: crate::rate_limit::VerifyLimiters,
+    /// `Idempotency-Key`s seen on game creation (see `idempotency.rs`).
+    pub idempotency_keys: Arc<crate::idempotency::IdempotencyKeys>,
+    /// Cap on unfinished games held in memory (`limits.max_active_games`).
+    pub max_active_games: usize,
+    /// Cap on Waiting lobbies created from one IP (`limits.max_lobbies_per_ip`).
+    pub max_lobbies_per_ip: usize,
+    /// Finished games past `cleanup_policy.finished`, frozen for the read-only
+    /// endpoints (see `archive.rs`).
+    pub archive: Arc<crate::archive::Archive>,
+    /// How long finished/idle/abandoned games are kept (see `background.rs`).
+    pub cleanup_policy: crate::background::CleanupPolicy,
+    /// Server-wide feed of every game's broadcast messages (game_id-tagged) for admin observers.
+    pub admin_tx: broadcast::Sender<crate::protocol::AdminMessage>,
+    /// Signs and checks player session tokens (see `auth.rs`).
+    pub sessions: crate::auth::SessionKeys,
+    /// Scoped API keys for the admin endpoints (see `admin.rs`).
+    pub admin_keys: Arc<crate::admin::AdminKeys>,
+    /// Banned CF handles and IP ranges (see `moderation.rs`).
+    pub bans: crate::moderation::BanList,
+    /// The settings this state was built from (see `config.rs`).
+    pub config: Arc<crate::config::Config>,
+    /// Restarts and liveness of server-wide background tasks (see `supervisor.rs`).
+    pub supervisor: Arc<crate::supervisor::Supervisor>,
+    /// Club rooms: members, challenges and leaderboards (see `rooms.rs`).
+    pub rooms: Arc<crate::rooms::RoomRegistry>,
+    /// Server-initiated socket closes, watched by every connection (see `ws.rs`).
+    pub closes: broadcast::Sender<crate::ws::CloseNotice>,
 }
 
 impl Default for AppState {
@@ -20,32 +62,210 @@ impl Default for AppState {
 }
 
 impl AppState {
+    /// State from the current config file and environment (defaults if invalid).
     pub fn new() -> Self {
+        let config = crate::config::Config::load().unwrap_or_else(|e| {
+            tracing::error!("Invalid configuration, using defaults: {}", e);
+            crate::config::Config::default()
+        });
+        Self::from_config(config)
+    }
+
+    pub fn from_config(config: crate::config::Config) -> Self {
+        let cf_queue = crate::cf_client::CfApiQueue::spawn_with(&config.codeforces);
         Self {
-            games: Arc::new(RwLock::new(HashMap::new())),
-            cf_client: crate::cf_client::CFClient::new(),
-            cf_queue: crate::cf_client::CfApiQueue::spawn(),
+            games: Arc::new(DashMap::new()),
+            cf_client: crate::cf_client::CFClient::from_config(&config.codeforces),
+            cf_queue: cf_queue.clone(),
+            contest_responses: Arc::new(crate::response_cache::ResponseCache::new(
+                config.codeforces.contest_cache_ttl(),
+                crate::response_cache::CAPACITY,
+            )),
+            judges: Arc::new(crate::judge::JudgeRegistry::from_config(cf_queue, &config)),
             rate_limiter: Arc::new(Mutex::new(HashMap::new())),
+            ip_limiters: crate::rate_limit::IpLimiters::from_config(&config.limits),
+            verify_limiters: crate::rate_limit::VerifyLimiters::from_config(&config.limits),
+            idempotency_keys: Arc::new(crate::idempotency::IdempotencyKeys::default()),
+            max_active_games: config.limits.max_active_games,
+            max_lobbies_per_ip: config.limits.max_lobbies_per_ip,
+            archive: Arc::new(crate::archive::Archive::new(config.cleanup.archived_max_games)),
+            cleanup_policy: crate::background::CleanupPolicy::from_config(&config.cleanup),
+            admin_tx: broadcast::channel(1024).0,
+            sessions: crate::auth::SessionKeys::from_config(&config.auth),
+            admin_keys: Arc::new(crate::admin::AdminKeys::from_config(&config.admin)),
+            bans: crate::moderation::BanList::from_config(&config.moderation),
+            config: Arc::new(config),
+            supervisor: Arc::new(crate::supervisor::Supervisor::default()),
+            rooms: Arc::new(crate::rooms::RoomRegistry::default()),
+            closes: broadcast::channel(16).0,
+        }
+    }
+
+    /// Unfinished games, as counted against `max_active_games`.
+    pub fn active_games(&self) -> usize {
+        self.games
+            .iter()
+            .filter(|g| g.status() != GameStatus::Finished)
+            .count()
+    }
+
+    /// Backpressure check before creating a game. On refusal returns the
+    /// error message and a suggested Retry-After in seconds.
+    pub fn check_capacity(&self, ip: Option<std::net::IpAddr>) -> Result<(), (&'static str, u64)> {
+        if self.active_games() >= self.max_active_games {
+            return Err(("Server is at capacity. Please try again shortly.", 30));
         }
+        if let Some(ip) = ip {
+            let open_lobbies = self
+                .games
+                .iter()
+                .filter(|g| g.creator_ip == Some(ip) && g.status() == GameStatus::Waiting)
+                .count();
+            if open_lobbies >= self.max_lobbies_per_ip {
+                return Err(("Too many open lobbies. Start or cancel one first.", 60));
+            }
+        }
+        Ok(())
     }
+
+    /// Spawn the game's actor, register it, and mirror its broadcasts onto the
+    /// admin feed (and Discord, when configured). Scheduled matches also get
+    /// the task that starts them on time, and room games a follower that
+    /// records their result, and virtual-contest games their problem set. None
+    /// of these keep the actor alive: once the game is removed it stops, its
+    /// channel closes and they exit.
+    pub fn insert_game(&self, mut game: Game) -> GameHandle {
+        if game.config.reveal_problems && game.problem_set.is_empty() {
+            crate::ws::choose_problem_set(&mut game, self);
+        }
+        let game_id = game.id;
+        let mut rx = game.tx.subscribe();
+        let lifecycle = crate::discord::Lifecycle::watch(&game, &self.config.server);
+        let callbacks = crate::webhooks::Follower::watch(&game);
+        let scheduled_start_at = game.scheduled_start_at;
+        let room = game.room.clone().map(|slug| (slug, game.tx.subscribe()));
+        let handle = GameHandle::spawn(game);
+        self.games.insert(game_id, handle.clone());
+        if let Some(lifecycle) = lifecycle {
+            lifecycle.spawn(handle.downgrade());
+        }
+        if let Some(callbacks) = callbacks {
+            callbacks.spawn(handle.downgrade());
+        }
+        if let Some((slug, rx)) = room {
+            crate::rooms::follow_game(self.rooms.clone(), slug, handle.downgrade(), rx);
+        }
+        if let Some(start) = scheduled_start_at {
+            tokio::spawn(crate::reporting::in_task(
+                "scheduled_start",
+                Some(game_id),
+                crate::ws::start_when_scheduled(self.clone(), game_id, start),
+            ));
+        }
+
+        let admin_tx = self.admin_tx.clone();
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(event) => {
+                        // No observers connected — skip the clone
+                        if admin_tx.receiver_count() > 0 {
+                            let _ = admin_tx.send(crate::protocol::AdminMessage::GameEvent {
+                                game_id,
+                                event_id: event.event_id,
+                                at_ms: event.at_ms,
+                                event: event.message,
+                            });
+                        }
+                    }
+                    Err(broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("[Admin] Feed forwarder for game {:?} lagged by {} messages", game_id, n);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+        handle
+    }
+
+    /// Clone a game's handle (locks one registry shard only briefly).
+    pub fn game(&self, game_id: Uuid) -> Option<GameHandle> {
+        self.games.get(&game_id).map(|g| g.clone())
+    }
+
+    /// All registered handles, for registry-wide sweeps. Shards are locked one
+    /// at a time, so a sweep never blocks the whole registry.
+    pub fn all_games(&self) -> Vec<GameHandle> {
+        self.games.iter().map(|g| g.clone()).collect()
+    }
+
+    /// Run `f` on a game's actor. `None` if the game doesn't exist.
+    pub async fn with_game<R, F>(&self, game_id: Uuid, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut Game) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        self.game(game_id)?.call(f).await
+    }
+
+    /// Close the sockets `target` covers with a Close frame for `code`.
+    pub fn close_sockets(&self, target: crate::ws::CloseTarget, code: crate::protocol::CloseCode) {
+        let _ = self.closes.send(crate::ws::CloseNotice { target, code });
+    }
+
+    /// Unregister a game; its actor stops once in-flight handles are dropped.
+    pub fn remove_game(&self, game_id: Uuid) -> Option<GameHandle> {
+        self.games.remove(&game_id).map(|(_, handle)| handle)
+    }
+
+    /// Freeze a finished game into the archive (see `archive.rs`) and
+    /// unregister it, so its actor stops.
+    pub async fn archive_game(&self, game_id: Uuid) {
+        let Some(handle) = self.game(game_id) else {
+            return;
+        };
+        let events = handle.tx.events();
+        if let Some(archived) = handle.call(move |g| crate::archive::ArchivedGame::freeze(g, events)).await {
+            self.archive.insert(game_id, archived);
+        }
+        self.remove_game(game_id);
+    }
+}
+
+/// Per-game broadcast buffer (`game.broadcast_capacity`, default 2000 events).
+/// A connection that falls further behind than this gets a full resync.
+pub fn broadcast_capacity() -> usize {
+    crate::config::global().game.broadcast_capacity
 }
 
+/// Public game events, broadcast to every socket of the game (stamped and
+/// logged by `GameTx::send`, see `events.rs`). Per-player state goes through
+/// each player's `PlayerView` instead.
 #[derive(Clone, Debug)]
 pub enum GameEvent {
-    Tick,
     Message(crate::protocol::ServerMessage),
 }
 
-/// Which difficulty system to use when picking problems for a locked player.
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
-#[serde(rename_all = "snake_case")]
-pub enum DifficultyMode {
-    /// Standard CF mode: `difficulty` is an exact CF rating (800, 900, … 3500).
-    #[default]
-    Cf,
-    /// Band mode: `difficulty` is a band id (0 = SuperEasy … 4 = VeryHard).
-    /// Bands map to clist.by rating ranges (0–300, 301–600, 601–1000, 1001–1500, 1501+).
-    Band,
+/// A player's private view-model — their `GameUpdate` (heat, lock, timers),
+/// serialized once by the game's actor. Every socket of that player watches it,
+/// so only the latest view is ever sent and no lookup is needed to read it.
+#[derive(Clone, Debug)]
+pub struct PlayerView(watch::Sender<Arc<Vec<String>>>);
+
+impl Default for PlayerView {
+    fn default() -> Self {
+        Self(watch::channel(Arc::default()).0)
+    }
+}
+
+impl PlayerView {
+    pub fn publish(&self, frames: Vec<String>) {
+        self.0.send_replace(Arc::new(frames));
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<Arc<Vec<String>>> {
+        self.0.subscribe()
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -55,9 +275,99 @@ pub struct GameConfig {
     pub difficulty: u32,
     pub difficulty_mode: DifficultyMode,
     pub heat_threshold: u32,      // 5, 7, 10, 15
-    pub veto_penalties: [u64; 3], // seconds per veto (escalating)
+    /// Heat ramp: `heat_threshold` applies from the start of combat, then
+    /// each step's from its minute on (steps in order of `after_mins`)
+    #[serde(default)]
+    pub heat_ramp: Vec<HeatStep>,
+    /// Ammo mode: players fire from a limited supply instead of building heat
+    #[serde(default)]
+    pub ammo: Option<AmmoConfig>,
+    /// How a tie at the end of the clock is broken
+    #[serde(default)]
+    pub sudden_death: SuddenDeathMode,
+    /// Seconds locked out per veto, escalating; vetoes past the end of the
+    /// list use its last entry
+    pub veto_penalties: Vec<u64>,
     pub max_vetoes: u32,
     pub game_duration_secs: u64, // default: 45 * 60 = 2700
+    /// Listed publicly: the lobby link is announced on Discord (see `discord.rs`)
+    #[serde(default)]
+    pub public: bool,
+    /// Scheduled match: combat starts at this Unix time (seconds) instead of
+    /// when both players ready up
+    #[serde(default)]
+    pub scheduled_start: Option<u64>,
+    /// Solving within this many seconds of the lock earns a cross shot;
+    /// `None` disables special weapons
+    #[serde(default)]
+    pub fast_solve_secs: Option<u64>,
+    /// Fog of war: shot results never say a ship was sunk; the fleets are
+    /// only revealed at `GameOver`
+    #[serde(default)]
+    pub hide_sunk_info: bool,
+    /// What a `RequestHint` costs; `Off` disables hints
+    #[serde(default)]
+    pub hint_cost: HintCost,
+    /// Spectators may follow the game on the referee feed (see `referee.rs`)
+    #[serde(default)]
+    pub referee_feed: bool,
+    /// Tell each player when the opponent is busy with a problem (`Working`)
+    #[serde(default)]
+    pub activity_indicator: bool,
+    /// Broadcast each player's round-trip time (`Latency`)
+    #[serde(default)]
+    pub show_latency: bool,
+    /// While the judge is unreachable and someone is locked, stop the game
+    /// clock and veto timers (`ClockPaused` / `ClockResumed`)
+    #[serde(default)]
+    pub pause_on_outage: bool,
+    /// Mercy rule: a wrong answer or time limit on the lock problem takes 1
+    /// heat off this many seconds later; `None` disables it
+    #[serde(default)]
+    pub heat_relief_secs: Option<u64>,
+    /// AFK rule: an unlocked player silent this long gets an `IdleWarning`,
+    /// and forfeits after as long again; `None` disables it
+    #[serde(default)]
+    pub idle_timeout_secs: Option<u64>,
+    /// Anti-snipe: a shot landing in the last minute of sudden death adds
+    /// this many seconds to its clock, so the result isn't decided by who
+    /// clicked last before the timeout; `None` disables it
+    #[serde(default)]
+    pub anti_snipe_secs: Option<u64>,
+    /// Anti-snipe also extends the last minute of regulation time
+    #[serde(default)]
+    pub anti_snipe_regulation: bool,
+    /// Co-op: both players fire at a server-placed fleet with shared weapons,
+    /// and win together by sinking it before the clock runs out
+    #[serde(default)]
+    pub coop: bool,
+    /// Armada: each player places a home and a forward fleet, picks which of
+    /// the opponent's boards each shot goes at, and wins by sinking both
+    #[serde(default)]
+    pub armada: bool,
+    /// Seeds every server-side random choice in the game (the hidden co-op
+    /// fleet, problem order, simulated players), so a run replays exactly.
+    /// `None` draws a fresh seed. For tests and replay verification only:
+    /// `POST /api/game` doesn't take it, since a known seed reveals the problems
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Which registered judge assigns and verifies problems (see `judge.rs`)
+    #[serde(default = "default_judge")]
+    pub judge: String,
+    /// Virtual contest: the game's problem set is chosen at creation and
+    /// shown to both players at `GameStart`; every lock draws from it
+    #[serde(default)]
+    pub reveal_problems: bool,
+    /// Rating gate: guests rated below this (or unrated) can't join
+    #[serde(default)]
+    pub min_rating: Option<u32>,
+    /// Rating gate: guests rated above this can't join
+    #[serde(default)]
+    pub max_rating: Option<u32>,
+}
+
+fn default_judge() -> String {
+    crate::judge::CODEFORCES.to_string()
 }
 
 impl Default for GameConfig {
@@ -66,13 +376,67 @@ impl Default for GameConfig {
             difficulty: 0,
             difficulty_mode: DifficultyMode::Band,
             heat_threshold: 7,
-            veto_penalties: [180, 300, 420], // 3, 5, 7 minutes (medium default)
+            heat_ramp: vec![],
+            ammo: None,
+            sudden_death: SuddenDeathMode::FirstHit,
+            veto_penalties: vec![180, 300, 420], // 3, 5, 7 minutes (medium default)
             max_vetoes: 3,
             game_duration_secs: 2700, // 45 minutes (written in seconds)
+            public: false,
+            scheduled_start: None,
+            fast_solve_secs: Some(300), // 5 minutes
+            hide_sunk_info: false,
+            hint_cost: HintCost::Off,
+            referee_feed: false,
+            activity_indicator: false,
+            show_latency: false,
+            pause_on_outage: false,
+            heat_relief_secs: None,
+            idle_timeout_secs: None,
+            anti_snipe_secs: None,
+            anti_snipe_regulation: false,
+            coop: false,
+            armada: false,
+            seed: None,
+            judge: default_judge(),
+            reveal_problems: false,
+            min_rating: None,
+            max_rating: None,
         }
     }
 }
 
+impl GameConfig {
+    /// The heat threshold `elapsed_secs` into combat, following `heat_ramp`.
+    pub fn heat_threshold_at(&self, elapsed_secs: u64) -> u32 {
+        self.heat_ramp
+            .iter()
+            .take_while(|step| step.after_mins as u64 * 60 <= elapsed_secs)
+            .last()
+            .map_or(self.heat_threshold, |step| step.heat_threshold)
+    }
+
+    /// The rating gate for display, e.g. "1200–1600", "1900+" or "up to 1400";
+    /// `None` when anyone may join.
+    pub fn rating_range(&self) -> Option<String> {
+        match (self.min_rating, self.max_rating) {
+            (None, None) => None,
+            (Some(min), None) => Some(format!("{}+", min)),
+            (None, Some(max)) => Some(format!("up to {}", max)),
+            (Some(min), Some(max)) => Some(format!("{}–{}", min, max)),
+        }
+    }
+
+    /// Lockout in seconds for a veto taken after `vetoes_used` earlier ones.
+    /// `None` only if `veto_penalties` is empty.
+    pub fn veto_penalty(&self, vetoes_used: u32) -> Option<u64> {
+        self.veto_penalties
+            .get(vetoes_used as usize)
+            .or(self.veto_penalties.last())
+            .copied()
+    }
+}
+
 // Player statistics for tie-breaking
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct PlayerStats {
@@ -83,22 +447,83 @@ pub struct PlayerStats {
     pub problems_solved: u32,
 }
 
-// Tie-break result
-#[derive(Clone, Debug, PartialEq)]
-pub enum TiebreakResult {
-    Player1Wins,
-    Player2Wins,
-    SuddenDeath,
+/// A player's recent round-trip times, oldest first.
+#[derive(Clone, Debug, Default)]
+pub struct Latency {
+    samples: std::collections::VecDeque<u32>,
+}
+
+impl Latency {
+    /// Samples kept; at the default ping interval, the last 10 minutes.
+    pub const MAX_SAMPLES: usize = 120;
+
+    pub fn record(&mut self, rtt_ms: u32) {
+        if self.samples.len() == Self::MAX_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(rtt_ms);
+    }
+
+    pub fn last(&self) -> Option<u32> {
+        self.samples.back().copied()
+    }
+
+    /// 95th percentile (nearest rank) of the kept samples.
+    pub fn p95(&self) -> Option<u32> {
+        let mut sorted: Vec<u32> = self.samples.iter().copied().collect();
+        sorted.sort_unstable();
+        let rank = (sorted.len() * 95).div_ceil(100);
+        sorted.get(rank.checked_sub(1)?).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
 }
 
 /// A problem assigned by the server when weapons overheat.
 /// The server is the single source of truth for problem selection.
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct AssignedProblem {
     pub contest_id: i32,
     pub index: String,
     pub name: String,
     pub rating: u32,
+    /// Where to read the problem, for judges other than Codeforces (whose
+    /// links clients build from the ids)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+/// How a lock problem was put to rest.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LockOutcome {
+    Solved,
+    Vetoed,
+    /// Heat relief (`heat_relief_secs`) unlocked the player first
+    Relieved,
+    /// The game ended first
+    Unsolved,
+    /// An admin unlocked the player (see `admin.rs`)
+    Overridden,
+}
+
+/// One problem assigned to a locked player, and what came of it.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct LockRecord {
+    pub player_id: Uuid,
+    pub problem: AssignedProblem,
+    /// Unix seconds
+    pub assigned_at_unix: u64,
+    /// `None` while the player is still on it
+    pub outcome: Option<LockOutcome>,
+    /// Seconds from assignment to the outcome
+    pub secs_taken: Option<u64>,
 }
 
 #[derive(Debug, Serialize)]
@@ -114,8 +539,20 @@ pub struct Game {
     pub placement_started_at: Option<std::time::Instant>, // When both players joined and placement started
     #[serde(skip)]
     pub game_started_at: Option<std::time::Instant>,
+    /// The judge went down with a player locked (`pause_on_outage`); the game
+    /// clock and veto timers stand still from here until it's back.
+    #[serde(skip)]
+    pub clock_paused_at: Option<std::time::Instant>,
+    /// Seconds anti-snipe (`GameConfig::anti_snipe_secs`) has added to the clock.
+    #[serde(skip)]
+    pub clock_extended_secs: u64,
     #[serde(skip)]
     pub finished_at: Option<std::time::Instant>, // For auto-cleanup
+    /// When the last connected player left (status `Abandoned`), and the status to resume.
+    #[serde(skip)]
+    pub abandoned_at: Option<std::time::Instant>,
+    #[serde(skip)]
+    pub abandoned_from: Option<GameStatus>,
     #[serde(skip)]
     pub game_over_msg: Option<crate::protocol::ServerMessage>, // Cached for reconnect replay
     /// Shared problem queue — both players draw from this in order.
@@ -125,35 +562,127 @@ pub struct Game {
     pub p1_queue_idx: usize,
     #[serde(skip)]
     pub p2_queue_idx: usize,
+    /// A virtual-contest game's problems (`GameConfig::reveal_problems`),
+    /// chosen when it's registered; the queue is built from these alone.
+    #[serde(skip)]
+    pub problem_set: Vec<AssignedProblem>,
+    /// Set once `init_game_from_cf` has built the problem queue.
+    /// Combat can't begin (even if both are ready) until this is true.
+    #[serde(skip)]
+    pub problem_queue_ready: bool,
+    /// IP that created the lobby, for the per-IP open lobby cap.
+    #[serde(skip)]
+    pub creator_ip: Option<std::net::IpAddr>,
+    /// Host's Discord user ID, pinged when an opponent joins (see `discord.rs`).
+    #[serde(skip)]
+    pub host_discord_id: Option<String>,
+    /// Callback URLs for this game's lifecycle events (see `webhooks.rs`).
+    #[serde(skip)]
+    pub webhooks: Option<crate::webhooks::Webhooks>,
+    /// `config.scheduled_start` as an `Instant`; lobby, placement and cleanup
+    /// deadlines don't run before it.
+    #[serde(skip)]
+    pub scheduled_start_at: Option<std::time::Instant>,
+    /// Room the game was created in (see `rooms.rs`).
+    #[serde(skip)]
+    pub room: Option<String>,
+    /// Tournament an organizer tagged the game with (`POST /api/admin/games`);
+    /// its progress is exported by `metrics.rs`.
+    #[serde(skip)]
+    pub tournament: Option<String>,
+    /// Organizer-created games and challenges: only this player ID, joining
+    /// under this handle, may take the guest seat.
+    #[serde(skip)]
+    pub reserved_guest: Option<(Uuid, String)>,
+    /// The scheduled countdown has begun (or was due while the game wasn't
+    /// ready); from then on it starts as soon as it can.
+    #[serde(skip)]
+    pub schedule_reached: bool,
+    /// Anomalies flagged for tournament review (see `anticheat.rs`).
+    #[serde(skip)]
+    pub audit_log: crate::anticheat::AuditLog,
+    /// Open referee feed sockets
+    #[serde(skip)]
+    pub spectators: u32,
+    /// Times the host pushed back the Waiting lobby's expiry (`ExtendLobby`).
+    #[serde(skip)]
+    pub lobby_extensions: u32,
+    /// `LobbyExpiring` went out for the current expiry; cleared by an extension.
+    #[serde(skip)]
+    pub lobby_expiry_warned: bool,
+    /// Co-op games: the hidden fleet both players fire at, placed by the server
+    /// when the guest joins.
+    #[serde(skip)]
+    pub coop_board: Option<Board>,
+    /// Every lock problem assigned this game, oldest first (see `Game::lock_opened`).
+    /// In co-op games only the player who overheated is recorded.
+    #[serde(default)]
+    pub lock_history: Vec<LockRecord>,
+    /// `config.seed`, or the one drawn for this game; published with the replay log.
+    #[serde(skip)]
+    pub seed: u64,
+    /// Source of all the game's randomness, seeded from `seed`.
     #[serde(skip)]
-    pub tx: broadcast::Sender<GameEvent>,
+    pub rng: rand::rngs::StdRng,
+    #[serde(skip)]
+    pub tx: crate::events::GameTx,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
-pub enum GameStatus {
-    Waiting,       // Waiting for P2 to join
-    PlacingShips,  // Both players joined, placing ships
-    Initializing,  // Both placed ships, fetching CF data before combat starts
-    Playing,       // Both placed ships, combat phase
-    SuddenDeath,   // Tiebreaker: first hit wins
-    Finished,      // Game over
+/// One fleet and the grid it sits on: what a shot lands on.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Board {
+    pub grid: Grid,
+    pub ships: Vec<Ship>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Player {
     pub id: Uuid,
     pub cf_handle: String,
-    pub grid: Grid,
-    pub ships: Vec<Ship>,
+    /// Name of the bot playing this seat, if it joined with a bot token (see `auth.rs`)
+    #[serde(default)]
+    pub bot: Option<String>,
+    /// Language of this player's status lines (see `locale.rs`)
+    #[serde(default)]
+    pub locale: crate::locale::Locale,
+    /// The player's own fleet
+    #[serde(flatten)]
+    pub home: Board,
+    /// Armada games: the second, forward fleet
+    #[serde(default)]
+    pub forward: Option<Board>,
     pub heat: u32,
+    /// Rounds left in ammo mode (`GameConfig::ammo`); `None` under heat
+    pub ammo: Option<u32>,
     pub is_locked: bool,
     pub vetoes_used: u32,
     pub stats: PlayerStats,
+    /// Shots in accuracy sudden death (`SuddenDeathMode::Accuracy`)
+    #[serde(default)]
+    pub sudden_death: battle_cp_core::ShotTally,
     pub ships_placed: bool,
+    /// Ships sent one at a time with `PlaceShip`, until the fleet is complete
+    #[serde(default)]
+    pub staged_ships: Vec<battle_cp_core::ShipPlacement>,
+    /// Explicit ready-up after placement. Combat starts only when both are ready.
+    pub ready: bool,
+    /// One-use special weapons earned by fast solves, oldest first.
+    #[serde(default)]
+    pub inventory: Vec<SpecialWeapon>,
+    /// Shields earned by bonus solves and not yet placed.
+    #[serde(default)]
+    pub shields_available: u32,
+    /// Private notes on the enemy grid (`SetMarker`)
+    #[serde(default)]
+    pub markers: Vec<crate::protocol::Marker>,
     #[serde(skip)]
     pub veto_started_at: Option<std::time::Instant>,
     #[serde(skip)]
     pub last_verification_attempt: Option<std::time::Instant>,
+    /// Verifications rejected by CF since the last successful one; each doubles
+    /// the cooldown before the next attempt (see `Player::verify_cooldown`).
+    #[serde(skip)]
+    pub failed_verifications: u32,
     /// Server-assigned problem for the current lock session.
     /// Set by the backend when weapons overheat; cleared on unlock.
     /// The client can only solve THIS problem — no switching.
@@ -162,6 +691,26 @@ pub struct Player {
     /// Wall-clock Unix timestamp (seconds) when weapons were locked.
     #[serde(skip)]
     pub locked_at_unix: Option<u64>,
+    /// Optional extra problem asked for while unlocked; solving it earns a
+    /// shield. Survives locks, but can only be verified while unlocked.
+    #[serde(skip)]
+    pub bonus_problem: Option<AssignedProblem>,
+    /// Unix time (seconds) the bonus problem was assigned; only submissions
+    /// after it count.
+    #[serde(skip)]
+    pub bonus_assigned_at_unix: Option<u64>,
+    /// Standing vote to move the difficulty, until the opponent matches it
+    #[serde(skip)]
+    pub difficulty_vote: Option<crate::protocol::DifficultyVote>,
+    /// `(contest_id, index)` of the last problem a hint was bought for
+    #[serde(skip)]
+    pub hinted_problem: Option<(i32, String)>,
+    /// Seconds added to the next veto, paid for hints
+    #[serde(skip)]
+    pub veto_extension_secs: u64,
+    /// Shots taken off the heat threshold for the rest of the game, paid for hints
+    #[serde(default)]
+    pub heat_penalty: u32,
     /// Pre-fetched set of problem keys the player has already solved on CF.
     /// Populated once when the player joins, cleared when the game ends.
     /// Format: "contestId-index" (e.g., "1234-A").
@@ -171,28 +720,32 @@ pub struct Player {
     /// Distinguishes "not yet fetched" (false) from "fetched but empty" (true).
     #[serde(skip)]
     pub solved_set_ready: bool,
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Grid {
-    pub cells: [[CellState; 10]; 10], //10x10 grid
-}
-
-#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
-pub enum CellState {
-    //stats a cell can have for front end to figure out
-    Empty,
-    Ship,
-    Hit,
-    Miss,
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Ship {
-    pub size: u8,
-    pub hits: u8,
-    pub sunk: bool,
-    pub x: usize,
-    pub y: usize,
-    pub vertical: bool,
+    /// Open WebSocket connections that joined as this player.
+    #[serde(skip)]
+    pub connections: u32,
+    /// When `connections` last dropped to zero (None while connected, or if
+    /// they never connected)
+    #[serde(skip)]
+    pub disconnected_at: Option<std::time::Instant>,
+    /// The lock or the latest verification attempt during it, for `working`
+    #[serde(skip)]
+    pub last_active_at: Option<std::time::Instant>,
+    /// What the last `Working` broadcast said about this player
+    #[serde(skip)]
+    pub shown_working: bool,
+    /// Round-trip times from this player's `Pong`s
+    #[serde(skip)]
+    pub latency: Latency,
+    /// Heat relief earned by a genuine attempt comes due (`heat_relief_secs`)
+    #[serde(skip)]
+    pub heat_relief_at: Option<std::time::Instant>,
+    /// The player's last message, or their weapons unlocking, whichever is
+    /// later: where their AFK clock starts (see `Game::idle_since`)
+    #[serde(skip)]
+    pub idle_from: Option<std::time::Instant>,
+    /// An `IdleWarning` went out since `idle_from`
+    #[serde(skip)]
+    pub idle_warned: bool,
+    #[serde(skip)]
+    pub view: PlayerView,
 }
@@ -0,0 +1,161 @@
+//! Supervision for server-wide background tasks.
+//!
+//! A task spawned through `Supervisor::spawn` is restarted whenever it panics
+//! or returns, after a backoff that doubles from `initial_backoff` up to
+//! `max_backoff` (and resets once the task has stayed up for `max_backoff`).
+//! The panic itself is reported by the panic hook (see `reporting.rs`); the
+//! supervisor logs the restart under the `supervisor` target.
+//!
+//! Tasks that call `heartbeat` regularly also get a liveness check: `/health`
+//! lists every supervised task with its restart count and the age of its last
+//! heartbeat, and reports `degraded` when one is down or has gone quiet (e.g.
+//! the global ticker stuck on a lock), since a silent ticker means no game
+//! cleanup server-wide.
+
+use dashmap::DashMap;
+use serde::Serialize;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A task is stale once its heartbeat is this many intervals old.
+const STALE_AFTER_INTERVALS: u32 = 5;
+
+#[derive(Debug)]
+struct TaskHealth {
+    /// Expected time between heartbeats, if the task sends them
+    heartbeat_every: Option<Duration>,
+    alive: AtomicBool,
+    restarts: AtomicU64,
+    /// Millis since `Supervisor::epoch` of the last heartbeat (or start)
+    last_beat_ms: AtomicU64,
+}
+
+/// Liveness of one supervised task, as shown on `/health`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TaskStatus {
+    pub name: &'static str,
+    /// Running right now (false while waiting out a restart backoff)
+    pub alive: bool,
+    pub restarts: u64,
+    /// Seconds since the last heartbeat, or since the last (re)start
+    pub last_heartbeat_secs: f64,
+    /// Alive, and heartbeating on schedule if it is expected to
+    pub healthy: bool,
+}
+
+#[derive(Debug)]
+pub struct Supervisor {
+    tasks: DashMap<&'static str, Arc<TaskHealth>>,
+    epoch: Instant,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl Default for Supervisor {
+    fn default() -> Self {
+        Self::with_backoff(Duration::from_secs(1), Duration::from_secs(60))
+    }
+}
+
+impl Supervisor {
+    pub fn with_backoff(initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            tasks: DashMap::new(),
+            epoch: Instant::now(),
+            initial_backoff,
+            max_backoff,
+        }
+    }
+
+    /// Run `make()` as task `name` under `reporting::in_task`, restarting it
+    /// whenever it panics or returns. Tasks with a `heartbeat_every` should call
+    /// `heartbeat(name)` at least that often.
+    pub fn spawn<F, Fut>(self: &Arc<Self>, name: &'static str, heartbeat_every: Option<Duration>, make: F)
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let health = Arc::new(TaskHealth {
+            heartbeat_every,
+            alive: AtomicBool::new(false),
+            restarts: AtomicU64::new(0),
+            last_beat_ms: AtomicU64::new(self.now_ms()),
+        });
+        self.tasks.insert(name, health.clone());
+        let supervisor = self.clone();
+        tokio::spawn(async move {
+            let mut backoff = supervisor.initial_backoff;
+            loop {
+                health.last_beat_ms.store(supervisor.now_ms(), Ordering::Relaxed);
+                health.alive.store(true, Ordering::Relaxed);
+                let started = Instant::now();
+                let outcome = tokio::spawn(crate::reporting::in_task(name, None, make())).await;
+                health.alive.store(false, Ordering::Relaxed);
+
+                match outcome {
+                    Ok(()) => crate::reporting::report_error(
+                        None,
+                        format!("Supervised task {} exited unexpectedly", name),
+                    ),
+                    Err(e) if e.is_panic() => {} // already reported by the panic hook
+                    Err(_) => return, // runtime shutting down
+                }
+                if started.elapsed() >= supervisor.max_backoff {
+                    backoff = supervisor.initial_backoff;
+                }
+                let restarts = health.restarts.fetch_add(1, Ordering::Relaxed) + 1;
+                tracing::error!(
+                    target: "supervisor",
+                    task = name,
+                    restarts,
+                    "Task {} died after {:?}, restarting in {:?}",
+                    name,
+                    started.elapsed(),
+                    backoff
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(supervisor.max_backoff);
+            }
+        });
+    }
+
+    /// Record that `name` is still making progress.
+    pub fn heartbeat(&self, name: &'static str) {
+        if let Some(health) = self.tasks.get(name) {
+            health.last_beat_ms.store(self.now_ms(), Ordering::Relaxed);
+        }
+    }
+
+    /// Every supervised task, by name.
+    pub fn statuses(&self) -> Vec<TaskStatus> {
+        let now = self.now_ms();
+        let mut statuses: Vec<_> = self
+            .tasks
+            .iter()
+            .map(|entry| {
+                let health = entry.value();
+                let since_beat =
+                    Duration::from_millis(now.saturating_sub(health.last_beat_ms.load(Ordering::Relaxed)));
+                let alive = health.alive.load(Ordering::Relaxed);
+                let on_schedule = health
+                    .heartbeat_every
+                    .is_none_or(|every| since_beat <= every * STALE_AFTER_INTERVALS);
+                TaskStatus {
+                    name: entry.key(),
+                    alive,
+                    restarts: health.restarts.load(Ordering::Relaxed),
+                    last_heartbeat_secs: since_beat.as_secs_f64(),
+                    healthy: alive && on_schedule,
+                }
+            })
+            .collect();
+        statuses.sort_by_key(|s| s.name);
+        statuses
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.epoch.elapsed().as_millis() as u64
+    }
+}
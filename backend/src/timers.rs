@@ -0,0 +1,389 @@
+//! Per-game deadlines: lobby/placement timeouts, veto expiry, and the
+//! end-of-game tiebreak / sudden death timeout (or, in co-op, the loss), and
+//! the AFK rule's warning and forfeit. Anti-snipe pushes the end of the game
+//! back when shots land just before it.
+//! While the clock is paused for a judge outage (`Game::sync_outage`) veto
+//! expiry and the end of the game wait.
+//!
+//! Instead of polling every game once a second, each game actor asks
+//! `next_deadline` for the earliest pending deadline, sleeps until exactly
+//! then, and calls `fire_due`. Any command that changes the game (a veto, the
+//! game starting) makes the actor recompute its deadline.
+
+use crate::protocol::ServerMessage;
+use crate::state::{Game, GameEvent, GameStatus, Player, SuddenDeathMode, TiebreakResult};
+use std::time::{Duration, Instant};
+
+/// Lobby waiting for P2 closes after 5 minutes.
+const LOBBY_TIMEOUT: Duration = Duration::from_secs(300);
+/// Each host `ExtendLobby` keeps the lobby open this much longer.
+pub const LOBBY_EXTENSION: Duration = Duration::from_secs(300);
+/// The lobby is warned (`LobbyExpiring`) this long before it closes.
+const LOBBY_WARNING: Duration = Duration::from_secs(60);
+/// Placement (and CF initialization) must finish within 10 minutes of P2 joining.
+const PLACEMENT_TIMEOUT: Duration = Duration::from_secs(600);
+/// A pre-combat game is abandoned once a player has been gone this long.
+const STUCK_PLACEMENT: Duration = Duration::from_secs(180);
+/// A game past placement is abandoned once both players have been gone this
+/// long (normally the last disconnect abandons it at once; this catches games
+/// both left during the countdown).
+const STUCK_COMBAT: Duration = Duration::from_secs(120);
+/// Sudden death ends in a draw after 10 more minutes.
+const SUDDEN_DEATH_TIMEOUT_SECS: u64 = 600;
+/// Anti-snipe extends the clock for shots landing this close to its end.
+const ANTI_SNIPE_WINDOW_SECS: u64 = 60;
+/// Anti-snipe adds at most this much to a game, so it can't run forever.
+const ANTI_SNIPE_MAX_SECS: u64 = 300;
+
+/// When `p` is due an `IdleWarning`, or to forfeit once warned (see
+/// `Game::idle_since`). None while the AFK rule doesn't apply to them.
+fn idle_deadline(game: &Game, p: &Player) -> Option<Instant> {
+    let timeout = Duration::from_secs(game.config.idle_timeout_secs?);
+    let since = game.idle_since(p)?;
+    Some(since + if p.idle_warned { timeout * 2 } else { timeout })
+}
+
+/// How long `p`'s running veto lasts.
+fn veto_duration(game: &Game, p: &Player) -> Duration {
+    Duration::from_secs(p.veto_secs(&game.config))
+}
+
+/// When the current pre-combat phase times out: the lobby closing if no
+/// opponent joins (later for each host extension), or the placement deadline
+/// once one has. Scheduled matches get both windows counted from the scheduled start.
+pub fn phase_deadline(game: &Game) -> Option<Instant> {
+    match game.status {
+        GameStatus::Waiting => Some(
+            game.not_before_schedule(game.created_at) + LOBBY_TIMEOUT + LOBBY_EXTENSION * game.lobby_extensions,
+        ),
+        GameStatus::PlacingShips | GameStatus::Initializing => game
+            .placement_started_at
+            .map(|start| game.not_before_schedule(start) + PLACEMENT_TIMEOUT),
+        _ => None,
+    }
+}
+
+/// When the game counts as stuck with nobody playing it, and `Game::reap`
+/// abandons it: a player gone `STUCK_PLACEMENT` during placement, or both gone
+/// `STUCK_COMBAT` from the countdown on. None while that isn't the case.
+pub fn stuck_deadline(game: &Game) -> Option<Instant> {
+    let players = || std::iter::once(&game.player1).chain(game.player2.as_ref());
+    match game.status {
+        GameStatus::PlacingShips | GameStatus::Initializing => players()
+            .filter_map(|p| game.absent_since(p))
+            .min()
+            .map(|since| since + STUCK_PLACEMENT),
+        GameStatus::Countdown | GameStatus::Playing | GameStatus::SuddenDeath => {
+            let absent: Option<Vec<Instant>> = players().map(|p| game.absent_since(p)).collect();
+            absent?.into_iter().max().map(|since| since + STUCK_COMBAT)
+        }
+        _ => None,
+    }
+}
+
+/// Whole seconds left before `phase_deadline`, as reported in `GameUpdate`.
+pub fn phase_time_remaining_secs(game: &Game) -> Option<u64> {
+    phase_deadline(game).map(|at| at.saturating_duration_since(Instant::now()).as_secs())
+}
+
+/// Anti-snipe (`GameConfig::anti_snipe_secs`): a shot just landed, so if the
+/// running clock — sudden death's, or regulation's with
+/// `anti_snipe_regulation` — is in its last minute, push it back and tell
+/// both players (`ClockExtended`).
+pub fn extend_for_shot(game: &mut Game) {
+    let Some(secs) = game.config.anti_snipe_secs else {
+        return;
+    };
+    if game.game_started_at.is_none() || game.clock_paused_at.is_some() {
+        return;
+    }
+    let end = match game.status {
+        GameStatus::Playing if game.config.anti_snipe_regulation => game.regulation_secs(),
+        GameStatus::SuddenDeath => game.regulation_secs() + SUDDEN_DEATH_TIMEOUT_SECS,
+        _ => return,
+    };
+    let remaining = end.saturating_sub(game.combat_secs());
+    let added = secs.min(ANTI_SNIPE_MAX_SECS.saturating_sub(game.clock_extended_secs));
+    if remaining >= ANTI_SNIPE_WINDOW_SECS || added == 0 {
+        return;
+    }
+    game.clock_extended_secs += added;
+    let _ = game.tx.send(GameEvent::Message(ServerMessage::ClockExtended {
+        added_secs: added,
+        time_remaining_secs: remaining + added,
+    }));
+}
+
+/// The earliest moment `fire_due` has something to do, if any.
+pub fn next_deadline(game: &Game) -> Option<Instant> {
+    let mut deadlines: Vec<Instant> = Vec::with_capacity(4);
+    deadlines.extend(stuck_deadline(game));
+    match game.status {
+        GameStatus::Waiting | GameStatus::PlacingShips | GameStatus::Initializing => {
+            deadlines.extend(phase_deadline(game));
+            if game.status == GameStatus::Waiting && !game.lobby_expiry_warned {
+                deadlines.extend(phase_deadline(game).map(|at| at - LOBBY_WARNING));
+            }
+        }
+        GameStatus::Playing | GameStatus::SuddenDeath => {
+            let paused = game.clock_paused_at.is_some();
+            for p in std::iter::once(&game.player1).chain(game.player2.as_ref()) {
+                if let (true, Some(veto_start), false) = (p.is_locked, p.veto_started_at, paused) {
+                    deadlines.push(veto_start + veto_duration(game, p));
+                }
+                // The `Working` indicator lapsing (see `Game::sync_working`)
+                if p.shown_working {
+                    deadlines.extend(p.working_until());
+                }
+                // Like vetoes, heat relief and the AFK clock wait out a pause
+                if !paused {
+                    deadlines.extend(p.heat_relief_at);
+                    deadlines.extend(idle_deadline(game, p));
+                }
+            }
+            if let (Some(start), false) = (game.game_started_at, paused) {
+                let mut secs = game.regulation_secs();
+                if game.status == GameStatus::SuddenDeath {
+                    secs += SUDDEN_DEATH_TIMEOUT_SECS;
+                }
+                deadlines.push(start + Duration::from_secs(secs));
+            }
+        }
+        GameStatus::Countdown | GameStatus::Finished | GameStatus::Abandoned => {}
+    }
+    deadlines.into_iter().min()
+}
+
+/// Apply every deadline that has passed. Each branch changes the state it
+/// checked, so a fired deadline never comes back from `next_deadline`.
+pub fn fire_due(game: &mut Game) {
+    if stuck_deadline(game).is_some_and(|at| at <= Instant::now()) {
+        game.reap();
+    }
+
+    if game.status == GameStatus::Waiting {
+        let left = phase_deadline(game).map_or(Duration::ZERO, |at| at.saturating_duration_since(Instant::now()));
+        if left.is_zero() { //if you waited for more than 5 minutes (plus extensions)
+            game.finish(None, "LobbyTimeout");
+            tracing::info!(target: "game", "Game {:?} lobby timed out", game.id);
+        } else if left <= LOBBY_WARNING {
+            game.warn_lobby_expiring(left.as_secs().max(1));
+        }
+    }
+
+    // Placement timeout: 10 minutes from when placement actually started (P2 joined).
+    // Using placement_started_at (not created_at) guarantees a full 10 minutes
+    // regardless of how long the lobby waited for P2.
+    // Also covers Initializing (both placed, CF fetch in progress) — same timeout.
+    if game.status == GameStatus::PlacingShips || game.status == GameStatus::Initializing {
+        if let Some(placement_start) = game.placement_started_at {
+            if game.not_before_schedule(placement_start).elapsed() >= PLACEMENT_TIMEOUT {
+                game.finish(None, "PlacementTimeout");
+                tracing::info!(target: "game", "Game {:?} placement timed out (10 min)", game.id);
+            }
+        }
+    }
+    let paused = game.clock_paused_at.is_some();
+    if game.status == GameStatus::Playing || game.status == GameStatus::SuddenDeath {
+        // Check veto timer expiry for both players
+
+        //Check player 1 veto expiry
+        if game.player1.is_locked && !paused {
+            if let Some(veto_start) = game.player1.veto_started_at {
+                if veto_start.elapsed() >= veto_duration(game, &game.player1) {
+                    // Veto expired — assign next problem from queue (must solve to unlock)
+                    game.player1.veto_started_at = None;
+                    game.player1.veto_extension_secs = 0;
+                    game.player1.last_verification_attempt = None;
+                    let idx = game.p1_queue_idx;
+                    if idx < game.problem_queue.len() {
+                        let ap = game.problem_queue[idx].clone();
+                        game.p1_queue_idx += 1;
+                        game.player1.active_problem = Some(ap.clone());
+                        let id = game.player1.id;
+                        game.lock_opened(id, &ap);
+                        let _ = game.tx.send(GameEvent::Message(
+                            ServerMessage::ProblemAssigned {
+                                player_id: game.player1.id,
+                                contest_id: ap.contest_id,
+                                problem_index: ap.index,
+                                problem_name: ap.name,
+                                rating: ap.rating,
+                                url: ap.url,
+                            },
+                        ));
+                    } else {
+                        // Queue exhausted — unlock as mercy rule
+                        game.player1.unlock_weapons();
+                        if let Some(ammo) = game.config.ammo {
+                            game.player1.reload(ammo.per_solve);
+                        }
+                        let _ =
+                            game.tx
+                                .send(GameEvent::Message(ServerMessage::WeaponsUnlocked {
+                                    player_id: game.player1.id,
+                                    reason: "veto_expired".to_string(),
+                                }));
+                    }
+                    // A co-op team's veto runs out together
+                    let id = game.player1.id;
+                    game.share_weapons(id);
+                }
+            }
+        }
+
+        //Check player 2 veto expiry (a co-op partner's is player 1's, handled above)
+        let p2_veto_expired = game.player2.as_ref().filter(|_| !game.config.coop && !paused).and_then(|p2| {
+            if p2.is_locked {
+                if let Some(veto_start) = p2.veto_started_at {
+                    if veto_start.elapsed() >= veto_duration(game, p2) {
+                        return Some(p2.id);
+                    }
+                }
+            }
+            None
+        });
+
+        if let Some(p2_id) = p2_veto_expired {
+            if let Some(p2) = game.player2.as_mut() {
+                p2.veto_extension_secs = 0;
+            }
+            let idx = game.p2_queue_idx;
+            let next_problem = game.problem_queue.get(idx).cloned();
+
+            if let Some(ap) = next_problem {
+                game.p2_queue_idx += 1;
+                let p2 = game.player2.as_mut().unwrap();
+                p2.veto_started_at = None;
+                p2.last_verification_attempt = None;
+                p2.active_problem = Some(ap.clone());
+                game.lock_opened(p2_id, &ap);
+                let _ = game.tx.send(GameEvent::Message(
+                    ServerMessage::ProblemAssigned {
+                        player_id: p2_id,
+                        contest_id: ap.contest_id,
+                        problem_index: ap.index,
+                        problem_name: ap.name,
+                        rating: ap.rating,
+                        url: ap.url,
+                    },
+                ));
+            } else {
+                // Queue exhausted — unlock as mercy rule
+                let p2 = game.player2.as_mut().unwrap();
+                p2.unlock_weapons();
+                if let Some(ammo) = game.config.ammo {
+                    p2.reload(ammo.per_solve);
+                }
+                let _ = game.tx.send(GameEvent::Message(
+                    ServerMessage::WeaponsUnlocked {
+                        player_id: p2_id,
+                        reason: "veto_expired".to_string(),
+                    },
+                ));
+            }
+        }
+
+        // Heat relief earned by genuine attempts (`heat_relief_secs`)
+        let threshold = game.heat_threshold();
+        let mut relieved = vec![];
+        for p in std::iter::once(&mut game.player1).chain(game.player2.as_mut()) {
+            if !paused && p.heat_relief_at.is_some_and(|at| at <= Instant::now()) && p.relieve_heat(threshold) {
+                let _ = game.tx.send(GameEvent::Message(ServerMessage::WeaponsUnlocked {
+                    player_id: p.id,
+                    reason: "heat_relief".to_string(),
+                }));
+                relieved.push(p.id);
+            }
+        }
+        for id in relieved {
+            game.lock_closed(id, crate::state::LockOutcome::Relieved);
+        }
+
+        // AFK rule (`idle_timeout_secs`): warn, then forfeit to the opponent,
+        // or end in a draw if neither is playing
+        let due: Vec<(uuid::Uuid, bool)> = std::iter::once(&game.player1)
+            .chain(game.player2.as_ref())
+            .filter(|p| !paused && idle_deadline(game, p).is_some_and(|at| at <= Instant::now()))
+            .map(|p| (p.id, p.idle_warned))
+            .collect();
+        let forfeits: Vec<uuid::Uuid> = due.iter().filter(|(_, warned)| *warned).map(|(id, _)| *id).collect();
+        match forfeits.as_slice() {
+            [] => {
+                let forfeit_in_secs = game.config.idle_timeout_secs.unwrap_or_default();
+                for (id, _) in due {
+                    if let Some(p) = game.player_mut(id) {
+                        p.idle_warned = true;
+                    }
+                    let _ = game.tx.send(GameEvent::Message(ServerMessage::IdleWarning { player_id: id, forfeit_in_secs }));
+                }
+            }
+            [idle] => {
+                let winner = std::iter::once(&game.player1).chain(game.player2.as_ref()).find(|p| p.id != *idle).map(|p| p.id);
+                tracing::info!(target: "game", "Game {:?}: {} forfeits for being idle", game.id, idle);
+                game.finish(winner, "IdleForfeit");
+                return;
+            }
+            _ => {
+                tracing::info!(target: "game", "Game {:?}: both players idle", game.id);
+                game.finish(None, "IdleForfeit");
+                return;
+            }
+        }
+
+        //Check game timeout
+        if let (Some(start), false) = (game.game_started_at, paused) {
+            // Only check for initial timeout if we are clearly in Playing state
+            // If we are already in SuddenDeath, we ignore the standard game duration
+            if game.status == GameStatus::Playing
+                && game.config.coop
+                && start.elapsed() >= Duration::from_secs(game.regulation_secs())
+            {
+                // Co-op: the hidden fleet outlasted the clock
+                game.finish(None, "CoopTimeUp");
+            }
+            if game.status == GameStatus::Playing
+                && start.elapsed() >= Duration::from_secs(game.regulation_secs())
+            {
+                //Time Over! Determine winner or enter sudden death
+                let winner_result = game.determine_winner();
+
+                //Determine what happens based on TiebreakResult
+                match winner_result {
+                    TiebreakResult::Player1Wins => {
+                        let winner = Some(game.player1.id);
+                        game.finish(winner, "Timeout - More ships remaining");
+                    }
+                    TiebreakResult::Player2Wins => {
+                        let winner = game.player2.as_ref().map(|p| p.id);
+                        game.finish(winner, "Timeout - More ships remaining");
+                    }
+                    TiebreakResult::SuddenDeath => {
+                        // Sudden Death: first player to land a HIT wins.
+                        // No player state changes on entry — heat locks, veto timers,
+                        // and unlock requirements ALL carry over unchanged.
+                        // Player views propagate per-player state within a second,
+                        // advertising "SuddenDeath" status to both clients.
+                        game.status = GameStatus::SuddenDeath;
+                        let _ = game.tx.send(GameEvent::Message(crate::activity::sudden_death(game.config.sudden_death)));
+                    }
+                }
+            }
+
+            // SUDDEN DEATH TIMEOUT: 10 minutes max to prevent infinite games
+            // (e.g., both players locked with no vetoes remaining)
+            if game.status == GameStatus::SuddenDeath
+                && start.elapsed()
+                    >= Duration::from_secs(game.regulation_secs() + SUDDEN_DEATH_TIMEOUT_SECS)
+            {
+                if game.config.sudden_death == SuddenDeathMode::Accuracy {
+                    // Whatever shots were taken decide it
+                    game.finish_on_accuracy();
+                } else {
+                    game.finish(None, "SuddenDeathTimeout");
+                }
+                tracing::info!(target: "game", "Game {:?} sudden death timed out (10 min)", game.id);
+            }
+        }
+    }
+}
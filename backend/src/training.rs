@@ -0,0 +1,125 @@
+//! Post-game training plans (`GET /api/game/:id/analysis`).
+//!
+//! Once a game is over, each player's lock problems are read back from
+//! `Game::lock_history`: any they vetoed or never solved is a miss.
+//! For the misses, a short list of practice problems is drawn from the
+//! Codeforces problemset: within `RATING_WINDOW` of the missed problem's
+//! rating, sharing as many of its tags as possible, and not already solved.
+
+use crate::cf_client::ContestProblem;
+use crate::state::{LockOutcome, LockRecord};
+use serde::Serialize;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Practice problems per player.
+pub const MAX_RECOMMENDATIONS: usize = 5;
+
+/// How far (CF rating) a recommendation may be from the problem it follows up.
+pub const RATING_WINDOW: u32 = 100;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Recommendation {
+    pub contest_id: i32,
+    pub index: String,
+    pub name: String,
+    pub rating: u32,
+    pub tags: Vec<String>,
+    pub url: String,
+    /// The missed problem this follows up, e.g. "1950-C"
+    pub similar_to: String,
+}
+
+fn key(contest_id: i32, index: &str) -> String {
+    format!("{}-{}", contest_id, index)
+}
+
+/// `player_id`'s lock problems that ended without a solve, in the order they
+/// were assigned (see `Game::lock_history`).
+pub fn missed_problems(player_id: Uuid, lock_history: &[LockRecord]) -> Vec<LockRecord> {
+    lock_history
+        .iter()
+        .filter(|r| r.player_id == player_id && r.outcome != Some(LockOutcome::Solved))
+        .cloned()
+        .collect()
+}
+
+/// Up to `limit` practice problems for `missed`, taking turns between the
+/// misses (most recent first) so each gets followed up. Candidates are rated
+/// within `RATING_WINDOW`, share a tag with the miss (unless it has none),
+/// and aren't in `solved_set` (keys as in `Player::solved_set`). Best first:
+/// most shared tags, closest rating, newest contest.
+pub fn recommend(
+    missed: &[LockRecord],
+    problemset: &[ContestProblem],
+    solved_set: &HashSet<String>,
+    limit: usize,
+) -> Vec<Recommendation> {
+    let mut taken: HashSet<String> = solved_set.iter().cloned().collect();
+    taken.extend(missed.iter().map(|m| key(m.problem.contest_id, &m.problem.index)));
+
+    let ranked: Vec<(String, Vec<&ContestProblem>)> = missed
+        .iter()
+        .rev()
+        .map(|LockRecord { problem: m, .. }| {
+            let tags: &[String] = problemset
+                .iter()
+                .find(|p| p.contest_id == Some(m.contest_id) && p.index == m.index)
+                .map_or(&[], |p| &p.tags);
+            let shared = |p: &ContestProblem| p.tags.iter().filter(|t| tags.contains(t)).count();
+            let mut candidates: Vec<&ContestProblem> = problemset
+                .iter()
+                .filter(|p| p.contest_id.is_some())
+                .filter(|p| {
+                    p.rating.is_some_and(|r| {
+                        (r as i64 - m.rating as i64).unsigned_abs() <= RATING_WINDOW as u64
+                    })
+                })
+                .filter(|p| tags.is_empty() || shared(p) > 0)
+                .collect();
+            candidates.sort_by_key(|p| {
+                (
+                    std::cmp::Reverse(shared(p)),
+                    (p.rating.unwrap_or(0) as i64 - m.rating as i64).abs(),
+                    std::cmp::Reverse(p.contest_id),
+                )
+            });
+            (key(m.contest_id, &m.index), candidates)
+        })
+        .collect();
+
+    let mut picks = Vec::new();
+    let mut cursors = vec![0; ranked.len()];
+    while picks.len() < limit {
+        let mut progressed = false;
+        for (i, (similar_to, candidates)) in ranked.iter().enumerate() {
+            if picks.len() == limit {
+                break;
+            }
+            while let Some(p) = candidates.get(cursors[i]) {
+                cursors[i] += 1;
+                let contest_id = p.contest_id.unwrap_or_default();
+                if taken.insert(key(contest_id, &p.index)) {
+                    picks.push(Recommendation {
+                        contest_id,
+                        index: p.index.clone(),
+                        name: p.name.clone(),
+                        rating: p.rating.unwrap_or_default() as u32,
+                        tags: p.tags.clone(),
+                        url: format!(
+                            "https://codeforces.com/problemset/problem/{}/{}",
+                            contest_id, p.index
+                        ),
+                        similar_to: similar_to.clone(),
+                    });
+                    progressed = true;
+                    break;
+                }
+            }
+        }
+        if !progressed {
+            break;
+        }
+    }
+    picks
+}
@@ -0,0 +1,339 @@
+//! Per-game webhook subscriptions.
+//!
+//! `POST /api/game` may register up to `MAX_SUBSCRIPTIONS` callback URLs, each
+//! for some of `opponent_joined`, `game_started` and `game_over`, plus a
+//! `webhook_secret` the creator chooses. A `Follower` subscribed to the game's
+//! broadcasts POSTs a JSON body per event:
+//!
+//! ```json
+//! { "event": "opponent_joined", "game_id": "…", "event_id": 3, "at_ms": 1700000000000, "data": { … } }
+//! ```
+//!
+//! signed like `X-Battle-CP-Signature: sha256=<hex>`, the HMAC-SHA256 of
+//! `"<X-Battle-CP-Timestamp>.<body>"` under the secret (see `sign`). Failed
+//! deliveries (network errors, 408, 429 and 5xx) are retried with exponential
+//! backoff, up to `MAX_ATTEMPTS` in all; `event_id` lets receivers drop
+//! duplicates.
+//!
+//! Callbacks may not reach the server's own network: loopback, private,
+//! link-local and shared addresses are refused (unless
+//! `webhooks.game_callbacks_allow_private`), both as IP literals when the
+//! game is created and as what the host resolves to. Each delivery resolves
+//! the host again and connects only to the addresses it checked, so a name
+//! re-pointed after creation (DNS rebinding) is refused too.
+
+use crate::actor::WeakGameHandle;
+use crate::config::WebhookConfig;
+use crate::protocol::{ServerMessage, StampedMessage};
+use crate::state::Game;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+pub const MAX_SUBSCRIPTIONS: usize = 3;
+pub const MAX_URL_LEN: usize = 2048;
+pub const SECRET_LEN: std::ops::RangeInclusive<usize> = 16..=256;
+/// Deliveries per event, the first included.
+pub const MAX_ATTEMPTS: u32 = 5;
+/// Wait before the first retry; doubles each time.
+pub const FIRST_RETRY: Duration = Duration::from_secs(1);
+
+const INVALID_URL: &str = "Invalid webhook url";
+const URL_TOO_LONG: &str = "Webhook urls must be at most 2048 characters";
+const NOT_HTTPS: &str = "Webhook urls must be https";
+const NO_HOST: &str = "Webhook urls need a host";
+const UNRESOLVED: &str = "Webhook host doesn't resolve";
+const NOT_PUBLIC: &str = "Webhook urls can't point at loopback, private or link-local addresses";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    OpponentJoined,
+    GameStarted,
+    GameOver,
+}
+
+/// One callback URL and the events it wants.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Subscription {
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+}
+
+/// A game's validated subscriptions and signing secret.
+#[derive(Clone)]
+pub struct Webhooks {
+    subscriptions: Vec<Subscription>,
+    secret: String,
+    /// `webhooks.game_callbacks_allow_private` when the game was created
+    allow_private: bool,
+}
+
+impl std::fmt::Debug for Webhooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Webhooks").field("subscriptions", &self.subscriptions).finish_non_exhaustive()
+    }
+}
+
+impl Webhooks {
+    /// Check the creator's subscriptions. Callbacks must be `https://` unless
+    /// `webhooks.game_callbacks_allow_http` is set, and IP literals must be
+    /// public unless `webhooks.game_callbacks_allow_private` is. Hostnames are
+    /// checked by `check_resolved`.
+    pub fn new(subscriptions: Vec<Subscription>, secret: Option<String>, config: &WebhookConfig) -> Result<Option<Self>, &'static str> {
+        if subscriptions.is_empty() {
+            return Ok(None);
+        }
+        if subscriptions.len() > MAX_SUBSCRIPTIONS {
+            return Err("At most 3 webhooks per game");
+        }
+        let Some(secret) = secret.filter(|s| SECRET_LEN.contains(&s.len())) else {
+            return Err("webhook_secret must be 16-256 characters");
+        };
+        for sub in &subscriptions {
+            if sub.events.is_empty() {
+                return Err("Each webhook needs at least one event");
+            }
+            if sub.url.len() > MAX_URL_LEN {
+                return Err(URL_TOO_LONG);
+            }
+            let url = reqwest::Url::parse(&sub.url).map_err(|_| INVALID_URL)?;
+            let allow_http = config.game_callbacks_allow_http;
+            if url.scheme() != "https" && !(allow_http && url.scheme() == "http") {
+                return Err(NOT_HTTPS);
+            }
+            let host = url.host_str().ok_or(NO_HOST)?;
+            if !config.game_callbacks_allow_private && ip_literal(host).is_some_and(|ip| !is_public(ip)) {
+                return Err(NOT_PUBLIC);
+            }
+        }
+        let allow_private = config.game_callbacks_allow_private;
+        Ok(Some(Self { subscriptions, secret, allow_private }))
+    }
+
+    /// Resolve each callback's host and refuse any that points at a
+    /// non-public address (or nowhere).
+    pub async fn check_resolved(&self) -> Result<(), &'static str> {
+        if self.allow_private {
+            return Ok(());
+        }
+        for sub in &self.subscriptions {
+            let url = reqwest::Url::parse(&sub.url).map_err(|_| INVALID_URL)?;
+            public_addrs(&url).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether a callback may go to `ip`: not loopback, private (RFC 1918 or
+/// IPv6 unique local), link-local, shared (RFC 6598), broadcast or
+/// unspecified. IPv4-mapped IPv6 addresses are judged as IPv4.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            let shared = a == 100 && (64..128).contains(&b);
+            !(v4.is_loopback()
+                || v4.is_private()
+                || v4.is_link_local()
+                || v4.is_broadcast()
+                || v4.is_unspecified()
+                || a == 0
+                || shared)
+        }
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => is_public(IpAddr::V4(v4)),
+            None => !(v6.is_loopback() || v6.is_unspecified() || v6.is_unique_local() || v6.is_unicast_link_local()),
+        },
+    }
+}
+
+/// The address a URL host names directly, if it is an IP literal.
+fn ip_literal(host: &str) -> Option<IpAddr> {
+    host.trim_start_matches('[').trim_end_matches(']').parse().ok()
+}
+
+/// Where `url` can be delivered: every address its host resolves to, all of
+/// them public.
+async fn public_addrs(url: &reqwest::Url) -> Result<Vec<SocketAddr>, &'static str> {
+    let host = url.host_str().ok_or(NO_HOST)?;
+    let port = url.port_or_known_default().unwrap_or(443);
+    let addrs: Vec<SocketAddr> = match ip_literal(host) {
+        Some(ip) => vec![SocketAddr::new(ip, port)],
+        None => tokio::net::lookup_host((host, port)).await.map_err(|_| UNRESOLVED)?.collect(),
+    };
+    if addrs.is_empty() {
+        return Err(UNRESOLVED);
+    }
+    if !addrs.iter().all(|addr| is_public(addr.ip())) {
+        return Err(NOT_PUBLIC);
+    }
+    Ok(addrs)
+}
+
+/// `sha256=<hex>`: the HMAC-SHA256 of `"<timestamp>.<body>"` under `secret`.
+pub fn sign(secret: &str, timestamp: u64, body: &[u8]) -> String {
+    let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, secret.as_bytes());
+    let mut ctx = ring::hmac::Context::with_key(&key);
+    ctx.update(timestamp.to_string().as_bytes());
+    ctx.update(b".");
+    ctx.update(body);
+    let tag = ctx.sign();
+    let hex: String = tag.as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", hex)
+}
+
+/// Follows one game's broadcasts and delivers its subscribed events.
+pub struct Follower {
+    rx: broadcast::Receiver<StampedMessage>,
+    game_id: Uuid,
+    host_id: Uuid,
+    webhooks: Webhooks,
+}
+
+impl Follower {
+    /// `None` when the game has no subscriptions.
+    pub fn watch(game: &Game) -> Option<Self> {
+        Some(Self {
+            rx: game.tx.subscribe(),
+            game_id: game.id,
+            host_id: game.player1.id,
+            webhooks: game.webhooks.clone()?,
+        })
+    }
+
+    /// Follow the game until it ends or is removed.
+    pub fn spawn(self, handle: WeakGameHandle) {
+        let game_id = self.game_id;
+        tokio::spawn(crate::reporting::in_task("game_webhooks", Some(game_id), self.run(handle)));
+    }
+
+    async fn run(mut self, handle: WeakGameHandle) {
+        loop {
+            let stamped = match self.rx.recv().await {
+                Ok(stamped) => stamped,
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    tracing::warn!(target: "game", "Webhook follower for game {:?} lagged by {} messages", self.game_id, n);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            };
+            let (event, data) = match &stamped.message {
+                ServerMessage::PlayerJoined { player_id } if *player_id != self.host_id => {
+                    let player_id = *player_id;
+                    let handle_name = handle
+                        .call(move |game| game.player(player_id).map(|p| p.cf_handle.clone()))
+                        .await
+                        .flatten();
+                    (WebhookEvent::OpponentJoined, serde_json::json!({ "player_id": player_id, "cf_handle": handle_name }))
+                }
+                ServerMessage::GameStart => (WebhookEvent::GameStarted, serde_json::json!({})),
+                ServerMessage::GameOver { winner_id, reason, .. } => (
+                    WebhookEvent::GameOver,
+                    serde_json::json!({ "winner_id": winner_id, "reason": reason }),
+                ),
+                _ => continue,
+            };
+            let body = serde_json::json!({
+                "event": event,
+                "game_id": self.game_id,
+                "event_id": stamped.event_id,
+                "at_ms": stamped.at_ms,
+                "data": data,
+            });
+            let body = serde_json::to_vec(&body).unwrap_or_default();
+            for sub in self.webhooks.subscriptions.iter().filter(|s| s.events.contains(&event)) {
+                let delivery = deliver(
+                    sub.url.clone(),
+                    self.webhooks.secret.clone(),
+                    self.webhooks.allow_private,
+                    event,
+                    body.clone(),
+                );
+                tokio::spawn(crate::reporting::in_task("game_webhook_delivery", Some(self.game_id), delivery));
+            }
+            if event == WebhookEvent::GameOver {
+                return;
+            }
+        }
+    }
+}
+
+static CLIENT: std::sync::OnceLock<reqwest::Client> = std::sync::OnceLock::new();
+
+fn client_builder() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .redirect(reqwest::redirect::Policy::none())
+}
+
+fn get_client() -> &'static reqwest::Client {
+    CLIENT.get_or_init(|| client_builder().build().unwrap_or_default())
+}
+
+/// A client for one attempt at `url`. Unless private callbacks are allowed,
+/// the host is resolved and checked here and the client pinned to those
+/// addresses, so the connection goes where the check looked.
+async fn client_for(url: &str, allow_private: bool) -> Result<reqwest::Client, &'static str> {
+    if allow_private {
+        return Ok(get_client().clone());
+    }
+    let url = reqwest::Url::parse(url).map_err(|_| INVALID_URL)?;
+    let addrs = public_addrs(&url).await?;
+    let host = url.host_str().ok_or(NO_HOST)?;
+    client_builder().resolve_to_addrs(host, &addrs).build().map_err(|_| INVALID_URL)
+}
+
+/// POST one event, retrying transient failures with exponential backoff.
+async fn deliver(url: String, secret: String, allow_private: bool, event: WebhookEvent, body: Vec<u8>) {
+    let event_name = serde_json::to_value(event).ok().and_then(|v| v.as_str().map(str::to_string)).unwrap_or_default();
+    let mut backoff = FIRST_RETRY;
+    for attempt in 1..=MAX_ATTEMPTS {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let client = match client_for(&url, allow_private).await {
+            Ok(client) => client,
+            Err(e) if e == UNRESOLVED && attempt < MAX_ATTEMPTS => {
+                tracing::warn!("Game webhook {} failed (attempt {}): {}", event_name, attempt, e);
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!("Game webhook {} to {} refused: {}", event_name, url, e);
+                return;
+            }
+        };
+        let result = client
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("x-battle-cp-event", &event_name)
+            .header("x-battle-cp-timestamp", timestamp.to_string())
+            .header("x-battle-cp-signature", sign(&secret, timestamp, &body))
+            .body(body.clone())
+            .send()
+            .await;
+        let retry = match result {
+            Ok(resp) if resp.status().is_success() => return,
+            Ok(resp) => {
+                let status = resp.status();
+                tracing::warn!("Game webhook {} returned {} (attempt {})", event_name, status, attempt);
+                status.is_server_error() || status.as_u16() == 408 || status.as_u16() == 429
+            }
+            Err(e) => {
+                tracing::warn!("Game webhook {} failed (attempt {}): {}", event_name, attempt, e);
+                true
+            }
+        };
+        if !retry || attempt == MAX_ATTEMPTS {
+            break;
+        }
+        tokio::time::sleep(backoff).await;
+        backoff *= 2;
+    }
+    tracing::warn!("Game webhook {} to {} given up", event_name, url);
+}
@@ -6,24 +6,67 @@
 //! - Firing shots
 //! - CP problem solving verification
 //! - Veto timer mechanism
+//!
+//! When the server ends a connection itself (game removed, kicked, shutdown,
+//! spam) it sends a Close frame with a `CloseCode` and a JSON `CloseReason`,
+//! so clients can say why instead of "connection lost".
 
 use axum::{
     extract::{
-        ws::{Message, WebSocket},
+        ws::{CloseFrame, Message, WebSocket},
         Path, Query, State, WebSocketUpgrade,
     },
-    response::IntoResponse,
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
 };
 use futures::{SinkExt, StreamExt};
 use serde::Deserialize;
+use tracing::Instrument;
 use uuid::Uuid;
 
-use crate::protocol::{ClientMessage, ServerMessage};
-use crate::state::{AppState, CellState, GameStatus, Ship};
+use crate::auth::Claims;
+use crate::error::GameError;
+use crate::game::Shot;
+use crate::judge::JudgeClient;
+use std::sync::Arc;
+use crate::protocol::{CellShot, ClientMessage, CloseCode, CloseReason, ServerMessage, SpecialWeapon};
+use crate::rate_limit::ClientIp;
+use crate::state::{AppState, GameStatus, SuddenDeathMode};
+
+/// Consecutive rate-limited messages a socket may send before it's closed.
+const MAX_DROPPED_IN_A_ROW: u32 = 50;
+
+/// Which sockets a `CloseNotice` applies to.
+#[derive(Clone, Copy, Debug)]
+pub enum CloseTarget {
+    All,
+    Game(Uuid),
+    /// Sockets whose player handle or IP is now banned
+    Banned,
+}
+
+/// Sent through `AppState::close_sockets` to close sockets from outside
+/// their connection task.
+#[derive(Clone, Copy, Debug)]
+pub struct CloseNotice {
+    pub target: CloseTarget,
+    pub code: CloseCode,
+}
+
+/// A Close frame for `code`, with its JSON `CloseReason`.
+fn close_frame(code: CloseCode) -> Message {
+    let reason = serde_json::to_string(&CloseReason::from(code)).unwrap_or_default();
+    Message::Close(Some(CloseFrame { code: code.status(), reason: reason.into() }))
+}
 
 #[derive(Deserialize)]
 pub struct WsQuery {
     pub player_id: Option<Uuid>,
+    /// Session token, if not sent as `Authorization: Bearer`
+    pub token: Option<String>,
+    /// Receive broadcasts caused by one action as a single `Batch`
+    #[serde(default)]
+    pub batch: bool,
 }
 
 /// WebSocket upgrade handler
@@ -32,17 +75,65 @@ pub async fn ws_handler(
     Path(game_id): Path<Uuid>,
     Query(query): Query<WsQuery>,
     State(state): State<AppState>,
-) -> impl IntoResponse {
+    ClientIp(ip): ClientIp,
+    headers: HeaderMap,
+) -> Response {
+    // A token, if presented, must be valid for this game, or be a bot token.
+    // Without a session token the socket can only claim the empty guest seat.
+    let (session, bot) = match crate::auth::request_token(&headers, query.token.clone()) {
+        Some(token) => match (state.sessions.verify(&token), state.sessions.verify_bot(&token)) {
+            (Some(claims), _)
+                if claims.gid == game_id
+                    && !claims.resume
+                    && query.player_id.is_none_or(|pid| pid == claims.sub) =>
+            {
+                (Some(claims), None)
+            }
+            (None, Some(bot)) => (None, Some(bot)),
+            _ => return (StatusCode::UNAUTHORIZED, "Invalid session token").into_response(),
+        },
+        None => (None, None),
+    };
+    let initial_player_id = session.as_ref().map(|c| c.sub).or(query.player_id);
+
+    // The upgrade request's x-request-id doubles as the connection ID
+    let conn_id = headers
+        .get("x-request-id")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_owned)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    let span = tracing::info_span!(
+        "ws",
+        %game_id,
+        conn_id = %conn_id,
+        player_id = tracing::field::Empty,
+    );
+    if let Some(pid) = initial_player_id {
+        span.record("player_id", tracing::field::display(pid));
+    }
     ws.max_frame_size(8192) // 8 KB max frame — prevents memory bombs
         .max_message_size(16384) // 16 KB max message
-        .on_upgrade(move |socket| handle_socket(socket, game_id, query.player_id, state))
+        .on_upgrade(move |socket| {
+            crate::reporting::in_task(
+                "ws",
+                Some(game_id),
+                handle_socket(socket, game_id, initial_player_id, session, bot, query.batch, ip, state),
+            )
+            .instrument(span)
+        })
+        .into_response()
 }
 
 /// Main WebSocket connection handler
+#[allow(clippy::too_many_arguments)]
 async fn handle_socket(
     socket: WebSocket,
     game_id: Uuid,
     initial_player_id: Option<Uuid>,
+    mut session: Option<Claims>,
+    bot: Option<crate::auth::BotClaims>,
+    batch: bool,
+    ip: Option<std::net::IpAddr>,
     state: AppState,
 ) {
     let (mut sender, mut receiver) = socket.split();
@@ -52,36 +143,71 @@ async fn handle_socket(
     // Silently drops messages that arrive faster than the minimum interval.
     let mut last_fire_at:  Option<std::time::Instant> = None;
     let mut last_place_at: Option<std::time::Instant> = None;
+    let mut last_stage_at: Option<std::time::Instant> = None;
     let mut last_solve_at: Option<std::time::Instant> = None;
     let mut last_veto_at:  Option<std::time::Instant> = None;
     let mut last_join_at:  Option<std::time::Instant> = None;
+    let mut last_resume_at: Option<std::time::Instant> = None;
+    let mut last_ready_at: Option<std::time::Instant> = None;
+    let mut last_bonus_at: Option<std::time::Instant> = None;
+    let mut last_vote_at: Option<std::time::Instant> = None;
+    let mut last_hint_at: Option<std::time::Instant> = None;
+    let mut last_extend_at: Option<std::time::Instant> = None;
+    let mut last_pong_at: Option<std::time::Instant> = None;
+    let mut last_marker_at: Option<std::time::Instant> = None;
+    let mut dropped_in_a_row: u32 = 0;
+    // Set when the server is the one ending the connection
+    let mut close_code: Option<CloseCode> = None;
+    let mut closes = state.closes.subscribe();
+    // Player this socket joined as (for Abandoned tracking), and their view
+    let mut connected_as: Option<Uuid> = None;
+    let mut view_rx: Option<tokio::sync::watch::Receiver<std::sync::Arc<Vec<String>>>> = None;
+    // Last event a `Resume` replayed; the broadcast copies are skipped
+    let mut replayed_through: u64 = 0;
+    // Language of this socket's error messages: from `JoinGame`, then the player's
+    let mut locale = crate::locale::Locale::default();
+    // Latency pings, once the socket has joined as a player
+    let mut ping = state.config.game.ping_interval().map(|every| {
+        let mut ping = tokio::time::interval_at(tokio::time::Instant::now() + every, every);
+        ping.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        ping
+    });
+
+    // Archived games (finished, past cleanup) are read-only: the final
+    // result with both boards revealed, then the socket closes
+    if state.game(game_id).is_none() {
+        if let Some(game_over) = state.archive.get(game_id).and_then(|archived| archived.game_over.clone()) {
+            if let Ok(text) = serde_json::to_string(&game_over) {
+                let _ = sender.send(Message::Text(text.into())).await;
+            }
+            let _ = sender.send(close_frame(CloseCode::GameClosed)).await;
+            return;
+        }
+    }
 
     // Subscribe to game events
-    let rx = {
-        let games = state.games.read().await;
-        games.get(&game_id).map(|g| {
-            tracing::debug!(
-                "[WS] Player connecting to game {:?}, subscribing to broadcast (current subs: {})",
-                game_id,
-                g.tx.receiver_count()
-            );
-            g.tx.subscribe()
-        })
-    };
+    let rx = state.game(game_id).map(|g| {
+        tracing::debug!(
+            target: "ws",
+            "Player connecting to game {:?}, subscribing to broadcast (current subs: {})",
+            game_id,
+            g.tx.receiver_count()
+        );
+        g.tx.frames()
+    });
 
     let mut rx = match rx {
         Some(rx) => rx,
         None => {
-            tracing::warn!("[WS] Game {:?} not found!", game_id);
+            tracing::warn!(target: "ws", "Game {:?} not found!", game_id);
             let _ = sender
                 .send(Message::Text(
-                    serde_json::to_string(&ServerMessage::Error {
-                        message: "Game not found".to_string(),
-                    })
+                    serde_json::to_string(&ServerMessage::from(GameError::GameNotFound))
                     .unwrap()
                     .into(),
                 ))
                 .await;
+            let _ = sender.send(close_frame(CloseCode::GameNotFound)).await;
             return;
         }
     };
@@ -94,808 +220,1681 @@ async fn handle_socket(
                     Some(Ok(msg)) => {
                         if let Message::Text(text) = msg {
                             if let Ok(client_msg) = serde_json::from_str::<ClientMessage>(&text) {
+                                if let ClientMessage::JoinGame { locale: Some(tag), .. } = &client_msg {
+                                    locale = crate::locale::Locale::from_tag(tag);
+                                }
                                 // Per-message-type rate limiting.
                                 // Fire: 200ms (fast action), others: 2s (prevents lock contention spam).
+                                // The first message dropped after an accepted one gets a
+                                // `rate_limited` error saying when to retry; the rest are dropped silently.
                                 macro_rules! rate_check {
                                     ($tracker:expr, $min_ms:expr) => {
                                         if let Some(last) = $tracker {
-                                            if last.elapsed() < std::time::Duration::from_millis($min_ms) {
-                                                continue; // Silently drop rapid spam
+                                            let min = std::time::Duration::from_millis($min_ms);
+                                            if last.elapsed() < min {
+                                                dropped_in_a_row += 1;
+                                                if dropped_in_a_row >= MAX_DROPPED_IN_A_ROW {
+                                                    tracing::warn!(target: "ws", "Closing connection: kept exceeding the rate limits");
+                                                    close_code = Some(CloseCode::RateLimited);
+                                                    break 'main_loop;
+                                                }
+                                                if dropped_in_a_row == 1 {
+                                                    let wait = (min - last.elapsed()).as_millis().div_ceil(1000) as u64;
+                                                    let mut error = ServerMessage::from(GameError::RateLimited(wait.max(1)));
+                                                    locale.localize(&mut error);
+                                                    if let Ok(text) = serde_json::to_string(&error) {
+                                                        let _ = sender.send(Message::Text(text.into())).await;
+                                                    }
+                                                }
+                                                continue;
                                             }
                                         }
                                         $tracker = Some(std::time::Instant::now());
                                     };
                                 }
                                 match &client_msg {
-                                    ClientMessage::Fire { .. }       => { rate_check!(last_fire_at,  200);  }
+                                    ClientMessage::Fire { .. }
+                                    | ClientMessage::FireSpecial { .. } => { rate_check!(last_fire_at,  200);  }
                                     ClientMessage::PlaceShips { .. } => { rate_check!(last_place_at, 2000); }
+                                    ClientMessage::PlaceShip { .. }
+                                    | ClientMessage::UnplaceShip { .. } => { rate_check!(last_stage_at, 100); }
                                     ClientMessage::SolveCP { .. }    => { rate_check!(last_solve_at, 2000); }
                                     ClientMessage::Veto              => { rate_check!(last_veto_at,  2000); }
                                     ClientMessage::JoinGame { .. }   => { rate_check!(last_join_at,  2000); }
+                                    ClientMessage::Resume { .. }     => { rate_check!(last_resume_at, 2000); }
+                                    ClientMessage::Ready             => { rate_check!(last_ready_at, 2000); }
+                                    ClientMessage::RequestBonusProblem => { rate_check!(last_bonus_at, 2000); }
+                                    ClientMessage::PlaceShield { .. } => { rate_check!(last_place_at, 2000); }
+                                    ClientMessage::VoteDifficulty { .. } => { rate_check!(last_vote_at, 2000); }
+                                    ClientMessage::RequestHint       => { rate_check!(last_hint_at, 2000); }
+                                    ClientMessage::ExtendLobby       => { rate_check!(last_extend_at, 2000); }
+                                    ClientMessage::Pong { .. }       => { rate_check!(last_pong_at, 500); }
+                                    ClientMessage::SetMarker { .. }  => { rate_check!(last_marker_at, 100); }
                                 }
+                                dropped_in_a_row = 0;
 
-                                let responses = handle_client_message(
+                                // Anything but the automatic `Pong` shows the player is there (AFK rule)
+                                if let (Some(pid), false) = (connected_as, matches!(client_msg, ClientMessage::Pong { .. })) {
+                                    if let Some(handle) = state.game(game_id) {
+                                        handle.cast(move |game| game.note_input(pid)).await;
+                                    }
+                                }
+
+                                let mut responses = handle_client_message(
                                     client_msg,
                                     &mut player_id,
+                                    &mut session,
+                                    bot.as_ref(),
                                     game_id,
+                                    ip,
                                     &state,
                                 ).await;
 
-                                for resp in responses {
+                                // First successful join on this socket: count it as connected
+                                if connected_as.is_none() {
+                                    if let Some(pid) = responses.iter().find_map(|r| match r {
+                                        ServerMessage::GameJoined { player_id, .. } => Some(*player_id),
+                                        _ => None,
+                                    }) {
+                                        connected_as = Some(pid);
+                                        let joined = state
+                                            .with_game(game_id, move |game| {
+                                                game.connect(pid);
+                                                game.player(pid).map(|p| (p.view.subscribe(), p.locale))
+                                            })
+                                            .await
+                                            .flatten();
+                                        if let Some((_, player_locale)) = joined {
+                                            locale = player_locale;
+                                        }
+                                        view_rx = joined.map(|(rx, _)| rx);
+                                        // Deliver the current view right away
+                                        if let Some(rx) = view_rx.as_mut() {
+                                            rx.mark_changed();
+                                        }
+                                        if let Some(claims) = &session {
+                                            responses.push(ServerMessage::ResumeToken {
+                                                token: state.sessions.issue_resume(pid, game_id, claims.role),
+                                                window_secs: state.config.game.resume_window_secs,
+                                            });
+                                        }
+                                    }
+                                }
+                                // Replayed events also reached this socket's subscription
+                                if let Some(last) = responses.iter().find_map(|r| match r {
+                                    ServerMessage::Resumed { events } => events.last().map(|e| e.event_id),
+                                    _ => None,
+                                }) {
+                                    replayed_through = last;
+                                }
+
+                                for mut resp in responses {
+                                    locale.localize(&mut resp);
                                     let resp_text = match serde_json::to_string(&resp) {
                                         Ok(t) => t,
                                         Err(e) => {
-                                            tracing::error!("[WS] Failed to serialize response: {}", e);
+                                            tracing::error!(target: "ws", "Failed to serialize response: {}", e);
                                             continue;
                                         }
                                     };
                                     if sender.send(Message::Text(resp_text.into())).await.is_err() {
-                                        tracing::warn!("[WS] Failed to send response, closing connection");
+                                        tracing::warn!(target: "ws", "Failed to send response, closing connection");
                                         break 'main_loop;
                                     }
                                 }
                             } else {
-                                tracing::debug!("[WS] Failed to parse message: {}", text);
+                                tracing::debug!(target: "ws", "Failed to parse message: {}", text);
                             }
                         }
                     }
                     Some(Err(e)) => {
-                        tracing::warn!("[WS] Receive error: {:?}", e);
+                        tracing::warn!(target: "ws", "Receive error: {:?}", e);
                         break 'main_loop;
                     }
                     None => {
-                        tracing::debug!("[WS] Client disconnected");
+                        tracing::debug!(target: "ws", "Client disconnected");
+                        break 'main_loop;
+                    }
+                }
+            }
+
+            // Our player's view changed (heat, lock, timers) — already serialized by the actor
+            Some(frames) = async {
+                match view_rx.as_mut() {
+                    Some(v) => v.changed().await.ok().map(|_| v.borrow_and_update().clone()),
+                    None => std::future::pending().await,
+                }
+            } => {
+                for frame in frames.iter() {
+                    if sender.send(Message::Text(frame.as_str().into())).await.is_err() {
+                        tracing::warn!(target: "ws", "Failed to send view update, closing connection");
                         break 'main_loop;
                     }
                 }
             }
 
-            // Handle broadcast messages (game ticks, etc.)
+            // Time to measure the round trip again
+            _ = async {
+                match ping.as_mut() {
+                    Some(ping) if connected_as.is_some() => ping.tick().await,
+                    _ => std::future::pending().await,
+                }
+            } => {
+                let ping = ServerMessage::Ping { sent_at_ms: unix_ms() };
+                if let Ok(text) = serde_json::to_string(&ping) {
+                    if sender.send(Message::Text(text.into())).await.is_err() {
+                        tracing::warn!(target: "ws", "Failed to send ping, closing connection");
+                        break 'main_loop;
+                    }
+                }
+            }
+
+            // Handle broadcast messages (public game events)
             event_res = rx.recv() => {
                 match event_res {
-                    Ok(event) => {
-                        match event {
-                            crate::state::GameEvent::Tick => {
-                                // Send periodic game state update
-                                if let Some(pid) = player_id {
-                                    let games = state.games.read().await;
-                                    if let Some(game) = games.get(&game_id) {
-                                        let is_p1 = game.player1.id == pid;
-                                        let player = if is_p1 {
-                                            Some(&game.player1)
-                                        } else if game.player2.as_ref().map(|p| p.id) == Some(pid) {
-                                            game.player2.as_ref()
-                                        } else {
-                                            None
-                                        };
-
-                                        if let Some(p) = player {
-                                            // FALLBACK: If this is P1 (Host) and P2 exists but game is still Waiting,
-                                            // send PlayerJoined to ensure Host knows about Guest
-                                            if is_p1 && game.player2.is_some() && game.status == crate::state::GameStatus::Waiting {
-                                                let p2_id = game.player2.as_ref().unwrap().id;
-                                                let joined_msg = ServerMessage::PlayerJoined { player_id: p2_id };
-                                                if let Ok(msg_text) = serde_json::to_string(&joined_msg) {
-                                                    let _ = sender.send(Message::Text(msg_text.into())).await;
-                                                }
-                                            }
-
-                                            let elapsed = game.game_started_at.map(|s| s.elapsed().as_secs()).unwrap_or(0);
-                                            let remaining = game.config.game_duration_secs.saturating_sub(elapsed);
-
-                                            // Calculate veto time remaining if player is on veto timer
-                                            let veto_durations = game.config.veto_penalties;
-                                            let veto_time_remaining = if let Some(veto_start) = p.veto_started_at {
-                                                let duration = veto_durations
-                                                    .get(p.vetoes_used.saturating_sub(1) as usize)
-                                                    .copied()
-                                                    .unwrap_or(900);
-                                                let elapsed_veto = veto_start.elapsed().as_secs();
-                                                if elapsed_veto < duration {
-                                                    Some(duration - elapsed_veto)
-                                                } else {
-                                                    None
-                                                }
-                                            } else {
-                                                None
-                                            };
-
-                                            let update = ServerMessage::GameUpdate {
-                                                status: match game.status {
-                                                    crate::state::GameStatus::SuddenDeath =>
-                                                        "SUDDEN DEATH! First hit wins!".to_string(),
-                                                    _ => format!("{:?}", game.status),
-                                                },
-                                                is_active: true,
-                                                heat: p.heat,
-                                                is_locked: p.is_locked,
-                                                time_remaining_secs: remaining,
-                                                vetoes_remaining: game.config.max_vetoes.saturating_sub(p.vetoes_used),
-                                                veto_time_remaining_secs: veto_time_remaining,
-                                                active_problem_contest_id: p.active_problem.as_ref().map(|ap| ap.contest_id),
-                                                active_problem_index: p.active_problem.as_ref().map(|ap| ap.index.clone()),
-                                                active_problem_name: p.active_problem.as_ref().map(|ap| ap.name.clone()),
-                                            };
-                                            if let Ok(resp_text) = serde_json::to_string(&update) {
-                                                if sender.send(Message::Text(resp_text.into())).await.is_err() {
-                                                    tracing::warn!("[WS] Failed to send tick update, closing connection");
-                                                    break 'main_loop;
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            crate::state::GameEvent::Message(msg) => {
-                                 if let Ok(resp_text) = serde_json::to_string(&msg) {
-                                     if sender.send(Message::Text(resp_text.into())).await.is_err() {
-                                         tracing::warn!("[WS] Failed to send broadcast message, closing connection");
-                                         break 'main_loop;
-                                     }
-                                 }
+                    Ok(frame) => {
+                        let events: Vec<_> = frame.iter().filter(|e| e.event_id > replayed_through).cloned().collect();
+                        let texts: Vec<String> = if batch && events.len() > 1 {
+                            serde_json::to_string(&ServerMessage::Batch { events }).into_iter().collect()
+                        } else {
+                            events.iter().filter_map(|e| serde_json::to_string(e).ok()).collect()
+                        };
+                        for text in texts {
+                            if sender.send(Message::Text(text.into())).await.is_err() {
+                                tracing::warn!(target: "ws", "Failed to send broadcast message, closing connection");
+                                break 'main_loop;
                             }
                         }
                     }
                     Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
-                        tracing::warn!("[WS] Broadcast receiver lagged by {} messages", n);
-                        // Continue - we can recover from lag
+                        // Skipped events may include shots — push a full resync
+                        // so the client's boards and lock state are correct again.
+                        tracing::warn!(target: "ws", "Broadcast receiver lagged by {} messages, resyncing", n);
+                        if let Some(pid) = connected_as {
+                            let msgs = state
+                                .with_game(game_id, move |game| resync_messages(game, pid))
+                                .await
+                                .unwrap_or_default();
+                            for msg in msgs {
+                                if let Ok(resp_text) = serde_json::to_string(&msg) {
+                                    if sender.send(Message::Text(resp_text.into())).await.is_err() {
+                                        tracing::warn!(target: "ws", "Failed to send resync, closing connection");
+                                        break 'main_loop;
+                                    }
+                                }
+                            }
+                        }
                     }
                     Err(tokio::sync::broadcast::error::RecvError::Closed) => {
-                        tracing::debug!("[WS] Broadcast channel closed, ending connection");
+                        tracing::debug!(target: "ws", "Broadcast channel closed, ending connection");
+                        close_code = Some(CloseCode::GameClosed);
                         break 'main_loop;
                     }
                 }
             }
+
+            // Closed from outside: shutdown, or a ban that covers this socket
+            Ok(notice) = closes.recv() => {
+                let applies = match notice.target {
+                    CloseTarget::All => true,
+                    CloseTarget::Game(id) => id == game_id,
+                    CloseTarget::Banned => {
+                        let handle = match connected_as {
+                            Some(pid) => state
+                                .with_game(game_id, move |g| g.player(pid).map(|p| p.cf_handle.clone()))
+                                .await
+                                .flatten(),
+                            None => None,
+                        };
+                        state.bans.is_banned(handle.as_deref().unwrap_or(""), ip)
+                    }
+                };
+                if applies {
+                    tracing::info!(target: "ws", "Closing connection: {:?}", notice.code);
+                    close_code = Some(notice.code);
+                    break 'main_loop;
+                }
+            }
+        }
+    }
+    if let Some(code) = close_code {
+        let _ = sender.send(close_frame(code)).await;
+    }
+    if let Some(pid) = connected_as {
+        let abandoned = state
+            .with_game(game_id, move |game| game.disconnect(pid))
+            .await
+            .unwrap_or(false);
+        if abandoned {
+            tracing::info!(target: "ws", "Both players left game {:?}, marked Abandoned", game_id);
         }
     }
     tracing::debug!(
-        "[WS] Connection handler exiting for player {:?} in game {:?}",
+        target: "ws",
+        "Connection handler exiting for player {:?} in game {:?}",
         player_id,
         game_id
     );
 }
 
+/// Most missed events a `Resume` replays; past this it gets a full resync.
+const MAX_RESUME_EVENTS: usize = 200;
+
+/// Join confirmation with the lobby settings.
+fn game_joined(game: &crate::state::Game, pid: Uuid) -> ServerMessage {
+    ServerMessage::GameJoined {
+        game_id: game.id,
+        player_id: pid,
+        difficulty: game.config.difficulty,
+        difficulty_mode: game.config.difficulty_mode.clone(),
+        max_heat: game
+            .player(pid)
+            .map_or(game.heat_threshold(), |p| p.heat_limit(game.heat_threshold())),
+        max_vetoes: game.config.max_vetoes,
+        veto_penalties: game.config.veto_penalties.clone(),
+        scheduled_start: game.config.scheduled_start,
+        hint_cost: game.config.hint_cost,
+        heat_ramp: game.config.heat_ramp.clone(),
+        ammo: game.config.ammo,
+        sudden_death: game.config.sudden_death,
+        coop: game.config.coop,
+        armada: game.config.armada,
+        reveal_problems: game.config.reveal_problems,
+    }
+}
+
+/// A virtual-contest game's problem set, for `ProblemSet`.
+fn problem_set_message(game: &crate::state::Game) -> ServerMessage {
+    ServerMessage::ProblemSet {
+        problems: game
+            .problem_set
+            .iter()
+            .map(|p| crate::protocol::RevealedProblem {
+                contest_id: p.contest_id,
+                index: p.index.clone(),
+                name: p.name.clone(),
+                rating: p.rating,
+                url: p.url.clone(),
+            })
+            .collect(),
+    }
+}
+
+/// Everything a participant needs to rebuild their view of the game: sent on
+/// reconnect, and to a connection whose broadcast receiver lagged (missed events).
+fn resync_messages(game: &crate::state::Game, pid: Uuid) -> Vec<ServerMessage> {
+    let is_p1 = game.player1.id == pid;
+    let player = if is_p1 {
+        &game.player1
+    } else if let Some(p2) = game.player2.as_ref().filter(|p| p.id == pid) {
+        p2
+    } else {
+        return vec![];
+    };
+    let mut msgs = vec![];
+
+    // 1. Confirm Join
+    msgs.push(game_joined(game, pid));
+
+    // Already over — the result is all there is to sync
+    if let Some(go_msg) = &game.game_over_msg {
+        msgs.push(go_msg.clone());
+        return msgs;
+    }
+
+    // 2. Send Current State
+    let remaining = game.time_remaining_secs();
+    // Calculate remaining veto time so reconnected player sees the correct countdown
+    let veto_time_remaining = player.veto_started_at.and_then(|veto_start| {
+        let duration = player.veto_secs(&game.config);
+        let elapsed_veto = game.clock_now().saturating_duration_since(veto_start).as_secs();
+        if elapsed_veto < duration {
+            Some(duration - elapsed_veto)
+        } else {
+            None
+        }
+    });
+    msgs.push(ServerMessage::GameUpdate {
+        status: game.status_line(player.locale),
+        is_active: true,
+        heat: player.heat,
+        is_locked: player.is_locked,
+        time_remaining_secs: remaining,
+        vetoes_remaining: game.config.max_vetoes.saturating_sub(player.vetoes_used),
+        veto_time_remaining_secs: veto_time_remaining,
+        phase_time_remaining_secs: crate::timers::phase_time_remaining_secs(game),
+        active_problem_contest_id: player
+            .active_problem
+            .as_ref()
+            .map(|ap| ap.contest_id),
+        active_problem_index: player
+            .active_problem
+            .as_ref()
+            .map(|ap| ap.index.clone()),
+        active_problem_name: player
+            .active_problem
+            .as_ref()
+            .map(|ap| ap.name.clone()),
+        active_problem_url: player.active_problem.as_ref().and_then(|ap| ap.url.clone()),
+        special_weapons: player.inventory.clone(),
+        shields_available: player.shields_available,
+        max_heat: game.game_started_at.map(|_| player.heat_limit(game.heat_threshold())),
+        ammo: player.ammo,
+    });
+
+    // 3. If ships placed, confirm and RESEND ships
+    if player.ships_placed {
+        msgs.push(ServerMessage::ShipsConfirmed { player_id: pid });
+
+        if !player.home.ships.is_empty() {
+            msgs.push(ServerMessage::YourShips {
+                ships: player
+                    .home
+                    .ships
+                    .iter()
+                    .map(|s| crate::protocol::ShipPlacement {
+                        x: s.x,
+                        y: s.y,
+                        size: s.size,
+                        vertical: s.vertical,
+                    })
+                    .collect(),
+            });
+        }
+    } else if !player.staged_ships.is_empty() {
+        msgs.push(ServerMessage::StagedShips { ships: player.staged_ships.clone() });
+    }
+    if !player.markers.is_empty() {
+        msgs.push(ServerMessage::Markers { markers: player.markers.clone() });
+    }
+
+    // 4. Tell reconnecting player the opponent is here (Bug 2 fix)
+    if game.status == crate::state::GameStatus::PlacingShips
+        || game.status == crate::state::GameStatus::Initializing
+        || game.status == crate::state::GameStatus::Countdown
+        || game.status == crate::state::GameStatus::Playing
+        || game.status == crate::state::GameStatus::SuddenDeath
+    {
+        let opponent_id = if is_p1 {
+            game.player2.as_ref().map(|p| p.id)
+        } else {
+            Some(game.player1.id)
+        };
+        if let Some(oid) = opponent_id {
+            msgs.push(ServerMessage::PlayerJoined { player_id: oid });
+        }
+        let opponent = if is_p1 { game.player2.as_ref() } else { Some(&game.player1) };
+        if let Some(opponent) = opponent {
+            msgs.push(ServerMessage::Presence {
+                player_id: opponent.id,
+                connected: opponent.connections > 0,
+            });
+            if opponent.shown_working {
+                msgs.push(ServerMessage::Working { player_id: opponent.id, working: true });
+            }
+        }
+        if game.spectators > 0 {
+            msgs.push(ServerMessage::SpectatorCount { count: game.spectators });
+        }
+
+        // Re-send opponent's ShipsConfirmed if they already placed
+        let opponent_placed = if is_p1 {
+            game.player2
+                .as_ref()
+                .map(|p| p.ships_placed)
+                .unwrap_or(false)
+        } else {
+            game.player1.ships_placed
+        };
+        if opponent_placed {
+            let oid = if is_p1 {
+                game.player2.as_ref().unwrap().id
+            } else {
+                game.player1.id
+            };
+            msgs.push(ServerMessage::ShipsConfirmed { player_id: oid });
+        }
+
+        // Re-send ready state for both players while combat hasn't begun
+        if game.status == crate::state::GameStatus::PlacingShips
+            || game.status == crate::state::GameStatus::Initializing
+        {
+            let players = std::iter::once(&game.player1).chain(game.player2.as_ref());
+            for p in players.filter(|p| p.ready) {
+                msgs.push(ServerMessage::PlayerReady { player_id: p.id });
+            }
+        }
+    }
+
+    // 5. If game started (both placed), send GameStart and Grids
+    if game.status == crate::state::GameStatus::Playing
+        || game.status == crate::state::GameStatus::SuddenDeath
+    {
+        msgs.push(ServerMessage::GameStart);
+        if game.config.reveal_problems {
+            msgs.push(problem_set_message(game));
+        }
+
+        // My Grid
+        let my_grid = player.home.grid.to_strings(true);
+
+        // Enemy Grid
+        let enemy = if is_p1 {
+            game.player2.as_ref()
+        } else {
+            Some(&game.player1)
+        };
+
+        // Co-op players both fire at the hidden fleet
+        let enemy_grid = match game.coop_board.as_ref().or(enemy.map(|p| &p.home)) {
+            Some(enemy_board) => enemy_board.grid.to_strings(false),
+            // Should not happen if playing
+            None => vec![vec!["empty".to_string(); 10]; 10],
+        };
+
+        // Armada: the forward boards, seen the same way
+        let (my_forward_grid, enemy_forward_grid) = if game.config.armada {
+            (
+                player.forward.as_ref().map(|f| f.grid.to_strings(true)),
+                enemy.and_then(|e| e.forward.as_ref()).map(|f| f.grid.to_strings(false)),
+            )
+        } else {
+            (None, None)
+        };
+
+        msgs.push(ServerMessage::GridSync {
+            my_grid,
+            enemy_grid,
+            my_forward_grid,
+            enemy_forward_grid,
+        });
+
+        if let Some(ap) = &player.bonus_problem {
+            msgs.push(ServerMessage::BonusProblemAssigned {
+                player_id: pid,
+                contest_id: ap.contest_id,
+                problem_index: ap.index.clone(),
+                problem_name: ap.name.clone(),
+                rating: ap.rating,
+                url: ap.url.clone(),
+            });
+        }
+    }
+    msgs
+}
+
+fn game_not_found() -> Vec<ServerMessage> {
+    vec![GameError::GameNotFound.into()]
+}
+
+/// Put a validated fleet on `pid`'s board: broadcast `ShipsConfirmed`, and once
+/// both players have placed, start fetching CF data. Shared by `PlaceShips` and
+/// the `PlaceShip` that completes a staged fleet.
+fn commit_fleet(
+    game: &mut crate::state::Game,
+    pid: Uuid,
+    grid: battle_cp_core::Grid,
+    placed: Vec<battle_cp_core::Ship>,
+    forward: Option<(battle_cp_core::Grid, Vec<battle_cp_core::Ship>)>,
+    state: &AppState,
+) -> Vec<ServerMessage> {
+    let is_player1 = game.player1.id == pid;
+    {
+        let player = if is_player1 {
+            &mut game.player1
+        } else if let Some(ref mut p) = game.player2 {
+            p
+        } else {
+            return vec![GameError::OpponentLeft.into()];
+        };
+
+        // Replaces any earlier placement, so players can retry
+        player.home.grid = grid;
+        player.home.ships = placed;
+        player.forward = forward.map(|(grid, ships)| crate::state::Board { grid, ships });
+        player.ships_placed = true;
+        player.staged_ships.clear();
+    } // mutable borrow of player ends here
+
+    // Now we can safely check both players
+    let both_ready = game.player1.ships_placed
+        && game
+            .player2
+            .as_ref()
+            .map(|p| p.ships_placed)
+            .unwrap_or(false);
+
+    // IMPORTANT: Broadcast ShipsConfirmed FIRST so opponent knows we placed
+    let _ = game.tx.send(crate::state::GameEvent::Message(
+        ServerMessage::ShipsConfirmed { player_id: pid },
+    ));
+
+    // THEN check if both placed and start fetching CF data
+    if both_ready {
+        // Mark as Initializing while we fetch CF solved sets and wait for
+        // both players to ready up. NOT Playing — the game timer deadline
+        // only exists once combat starts, so the clock doesn't run yet.
+        game.status = crate::state::GameStatus::Initializing;
+        // DON'T set game_started_at yet — timer starts after the countdown.
+        spawn_init(game, state);
+
+        // Return immediately — the frontend shows "Initializing" / "Setting up battle..."
+        // until GameStart is broadcast by the spawned task.
+        return vec![];
+    }
+
+    // Only one player has placed — waiting for the other
+    let player = if is_player1 {
+        &game.player1
+    } else if let Some(ref p) = game.player2 {
+        p
+    } else {
+        return vec![GameError::OpponentLeft.into()];
+    };
+
+    vec![ServerMessage::GameUpdate {
+        status: player.locale.status(crate::locale::Status::PlacementComplete),
+        is_active: false,
+        heat: player.heat,
+        is_locked: player.is_locked,
+        time_remaining_secs: game.config.game_duration_secs,
+        vetoes_remaining: game.config.max_vetoes.saturating_sub(player.vetoes_used),
+        veto_time_remaining_secs: None,
+        phase_time_remaining_secs: crate::timers::phase_time_remaining_secs(game),
+        active_problem_contest_id: None,
+        active_problem_index: None,
+        active_problem_name: None,
+        active_problem_url: None,
+        special_weapons: player.inventory.clone(),
+        shields_available: player.shields_available,
+        max_heat: None,
+        ammo: player.ammo,
+    }]
+}
+
+/// `PlaceShip` / `UnplaceShip`: apply `change` to the player's staged ships
+/// and send back what is staged now. Staging survives reconnects (it is
+/// resent on join); the change that completes the fleet commits it like
+/// `PlaceShips`, after which the player readies up as usual.
+async fn handle_staging(
+    player_id: Option<Uuid>,
+    game_id: Uuid,
+    state: &AppState,
+    change: impl FnOnce(&mut Vec<crate::protocol::ShipPlacement>) -> Result<(), GameError> + Send + 'static,
+) -> Vec<ServerMessage> {
+    let Some(pid) = player_id else {
+        return vec![GameError::NoPlayerId.into()];
+    };
+    let st = state.clone();
+    state
+        .with_game(game_id, move |game| {
+            if game.config.coop {
+                return vec![GameError::CoopUnavailable.into()];
+            }
+            // Armada fleets arrive together in one PlaceShips
+            if game.config.armada {
+                return vec![GameError::ArmadaPlacement.into()];
+            }
+            if matches!(game.status, GameStatus::Playing | GameStatus::SuddenDeath | GameStatus::Finished) {
+                return vec![GameError::PlacementClosed.into()];
+            }
+            let player = if game.player1.id == pid {
+                &mut game.player1
+            } else if let Some(p) = game.player2.as_mut().filter(|p| p.id == pid) {
+                p
+            } else {
+                return vec![GameError::NotInGame.into()];
+            };
+            if player.ships_placed {
+                return vec![GameError::FleetPlaced.into()];
+            }
+            if let Err(e) = change(&mut player.staged_ships) {
+                return vec![e.into()];
+            }
+            if player.staged_ships.len() < battle_cp_core::FLEET.len() {
+                return vec![ServerMessage::StagedShips { ships: player.staged_ships.clone() }];
+            }
+
+            let ships = player.staged_ships.clone();
+            match battle_cp_core::place_fleet(&ships) {
+                Ok((grid, placed)) => {
+                    let mut msgs = vec![ServerMessage::YourShips { ships }];
+                    msgs.extend(commit_fleet(game, pid, grid, placed, None, &st));
+                    msgs
+                }
+                Err(e) => vec![GameError::from(e).into()],
+            }
+        })
+        .await
+        .unwrap_or_else(game_not_found)
+}
+
 /// Process individual client messages
-async fn handle_client_message(
+pub(crate) async fn handle_client_message(
     msg: ClientMessage,
     player_id: &mut Option<Uuid>,
+    session: &mut Option<Claims>,
+    bot: Option<&crate::auth::BotClaims>,
     game_id: Uuid,
+    ip: Option<std::net::IpAddr>,
     state: &AppState,
 ) -> Vec<ServerMessage> {
+    // SECURITY: Every action must be backed by a session token for this player
+    // and game. JoinGame checks its own (the empty guest seat needs none), and
+    // Resume carries its own token.
+    if let Some(pid) = *player_id {
+        let authorized = session.as_ref().is_some_and(|c| c.allows(pid, game_id));
+        if !authorized && !matches!(msg, ClientMessage::JoinGame { .. } | ClientMessage::Resume { .. }) {
+            return vec![GameError::Unauthorized.into()];
+        }
+    }
     match msg {
+        ClientMessage::Resume { token, last_event_id } => {
+            let claims = match state.sessions.verify(&token) {
+                Some(claims) if claims.resume && claims.gid == game_id => claims,
+                _ => return vec![GameError::Unauthorized.into()],
+            };
+            let pid = claims.sub;
+            if player_id.is_some_and(|existing| existing != pid) {
+                return vec![GameError::IdentityLocked.into()];
+            }
+            let window = state.config.game.resume_window();
+            let st = state.clone();
+            let responses = state
+                .with_game(game_id, move |game| {
+                    let Some(player) = game.player(pid) else {
+                        return vec![GameError::NotInGame.into()];
+                    };
+                    if st.bans.is_banned(&player.cf_handle, ip) {
+                        return vec![GameError::Banned.into()];
+                    }
+                    if player.disconnected_at.is_some_and(|at| at.elapsed() > window) {
+                        return vec![GameError::ResumeExpired.into()];
+                    }
+                    game.revive();
+                    // A short gap replays what was missed; a long one resyncs
+                    match game.tx.events_since(last_event_id, MAX_RESUME_EVENTS) {
+                        Some(events) => vec![game_joined(game, pid), ServerMessage::Resumed { events }],
+                        None => resync_messages(game, pid),
+                    }
+                })
+                .await
+                .unwrap_or_else(|| vec![GameError::GameNotFound.into()]);
+            if matches!(responses.first(), Some(ServerMessage::GameJoined { .. })) {
+                *player_id = Some(pid);
+                tracing::Span::current().record("player_id", tracing::field::display(pid));
+                *session = Some(claims);
+            }
+            responses
+        }
         ClientMessage::JoinGame {
             player_id: pid,
             cf_handle,
+            locale,
         } => {
-            // SECURITY: Lock player_id to the first JoinGame message.
+            let locale = locale.as_deref().map(crate::locale::Locale::from_tag);
+            // SECURITY: Lock player_id to the first successful JoinGame.
             // Subsequent JoinGame messages with a different player_id are rejected
             // to prevent impersonation attacks.
             if let Some(existing_pid) = *player_id {
                 if existing_pid != pid {
-                    return vec![ServerMessage::Error {
-                        message: "Player identity already established".to_string(),
-                    }];
+                    return vec![GameError::IdentityLocked.into()];
                 }
             }
-            *player_id = Some(pid);
-            let mut games = state.games.write().await;
-            if let Some(game) = games.get_mut(&game_id) {
-                // Check if game is finished - allow original participants to rejoin and see results
-                if game.status == crate::state::GameStatus::Finished {
+            if state.bans.is_banned(&cf_handle, ip) {
+                tracing::info!(target: "game", "Refused join for banned handle {:?} / ip {:?}", cf_handle, ip);
+                return vec![GameError::Banned.into()];
+            }
+            // Rating gate: a would-be guest's rating is looked up before the
+            // seating command below (a player already seated skips it)
+            let st = state.clone();
+            let gate = state
+                .with_game(game_id, move |game| {
+                    let seated = game.player(pid).is_some();
+                    let open = game.player2.is_none() && game.status != crate::state::GameStatus::Finished;
+                    let range = game.config.rating_range()?;
+                    (!seated && open).then(|| {
+                        let bounds = game.config.min_rating.unwrap_or(0)..=game.config.max_rating.unwrap_or(u32::MAX);
+                        (range, bounds, st.judges.for_game(&game.config))
+                    })
+                })
+                .await
+                .flatten();
+            if let Some((range, bounds, judge)) = gate {
+                match judge.rating(&cf_handle).await {
+                    Ok(Some(rating)) if bounds.contains(&rating) => {}
+                    Ok(Some(rating)) => return vec![GameError::RatingOutOfRange { range, rating }.into()],
+                    Ok(None) => return vec![GameError::Unrated(range).into()],
+                    Err(e) => {
+                        tracing::warn!(target: "game", "Rating lookup for {:?} failed: {}", cf_handle, e);
+                        return vec![GameError::RatingUnavailable.into()];
+                    }
+                }
+            }
+            let authorized = session.as_ref().is_some_and(|c| c.allows(pid, game_id));
+            let bot = bot.cloned();
+            let st = state.clone();
+            let responses = state
+                .with_game(game_id, move |game| {
+                    let state = &st;
                     let is_p1 = game.player1.id == pid;
                     let is_p2 = game.player2.as_ref().map(|p| p.id) == Some(pid);
-                    if is_p1 || is_p2 {
-                        if let Some(go_msg) = &game.game_over_msg {
-                            // Send GameJoined first so frontend sets playerId before processing GameOver
-                            return vec![
-                                ServerMessage::GameJoined {
-                                    game_id,
-                                    player_id: pid,
-                                    difficulty: game.config.difficulty,
-                                    difficulty_mode: game.config.difficulty_mode.clone(),
-                                    max_heat: game.config.heat_threshold,
-                                    max_vetoes: game.config.max_vetoes,
-                                },
-                                go_msg.clone(),
-                            ];
-                        }
+                    // SECURITY: Existing seats are only reclaimed with their token
+                    if (is_p1 || is_p2) && !authorized {
+                        return vec![GameError::Unauthorized.into()];
                     }
-                    return vec![ServerMessage::Error {
-                        message: "Game has already ended".to_string(),
-                    }];
-                }
-
-                // Check if player is already in the game (Reconnect)
-                // SECURITY: Only match by player_id — CF handles are public and not auth tokens
-                let is_p1 = game.player1.id == pid;
-                let is_p2 = game.player2.as_ref().map(|p| p.id) == Some(pid);
-
-                if is_p1 || is_p2 {
-                    // RECONNECTION LOGIC
-                    let mut msgs = vec![];
-
-                    // 1. Confirm Join
-                    msgs.push(ServerMessage::GameJoined {
-                        game_id,
-                        player_id: pid,
-                        difficulty: game.config.difficulty,
-                        difficulty_mode: game.config.difficulty_mode.clone(),
-                        max_heat: game.config.heat_threshold,
-                        max_vetoes: game.config.max_vetoes,
-                    });
 
-                    // 2. Send Current State
-                    let player = if is_p1 {
-                        &game.player1
-                    } else {
-                        game.player2.as_ref().unwrap()
-                    };
-                    let elapsed = game
-                        .game_started_at
-                        .map(|s| s.elapsed().as_secs())
-                        .unwrap_or(0);
-                    let remaining = game.config.game_duration_secs.saturating_sub(elapsed);
-                    // Calculate remaining veto time so reconnected player sees the correct countdown
-                    let veto_durations = game.config.veto_penalties;
-                    let veto_time_remaining = player.veto_started_at.and_then(|veto_start| {
-                        let duration = veto_durations
-                            .get(player.vetoes_used.saturating_sub(1) as usize)
-                            .copied()
-                            .unwrap_or(900);
-                        let elapsed_veto = veto_start.elapsed().as_secs();
-                        if elapsed_veto < duration {
-                            Some(duration - elapsed_veto)
-                        } else {
-                            None
-                        }
-                    });
-                    msgs.push(ServerMessage::GameUpdate {
-                        status: match game.status {
-                            crate::state::GameStatus::SuddenDeath => {
-                                "SUDDEN DEATH! First hit wins!".to_string()
+                    // Finished games are read-only: participants who missed the
+                    // end (e.g. dropped right before it) get GameJoined + the
+                    // cached GameOver with both boards revealed; nobody else gets in.
+                    if game.status == crate::state::GameStatus::Finished {
+                        if game.game_over_msg.is_some() {
+                            let msgs = resync_messages(game, pid);
+                            if !msgs.is_empty() {
+                                return msgs;
                             }
-                            _ => format!("{:?}", game.status),
-                        },
-                        is_active: true,
-                        heat: player.heat,
-                        is_locked: player.is_locked,
-                        time_remaining_secs: remaining,
-                        vetoes_remaining: game.config.max_vetoes.saturating_sub(player.vetoes_used),
-                        veto_time_remaining_secs: veto_time_remaining,
-                        active_problem_contest_id: player
-                            .active_problem
-                            .as_ref()
-                            .map(|ap| ap.contest_id),
-                        active_problem_index: player
-                            .active_problem
-                            .as_ref()
-                            .map(|ap| ap.index.clone()),
-                        active_problem_name: player
-                            .active_problem
-                            .as_ref()
-                            .map(|ap| ap.name.clone()),
-                    });
-
-                    // 3. If ships placed, confirm and RESEND ships
-                    if player.ships_placed {
-                        msgs.push(ServerMessage::ShipsConfirmed { player_id: pid });
-
-                        if !player.ships.is_empty() {
-                            msgs.push(ServerMessage::YourShips {
-                                ships: player
-                                    .ships
-                                    .iter()
-                                    .map(|s| crate::protocol::ShipPlacement {
-                                        x: s.x,
-                                        y: s.y,
-                                        size: s.size,
-                                        vertical: s.vertical,
-                                    })
-                                    .collect(),
-                            });
                         }
+                        return vec![GameError::GameEnded.into()];
                     }
 
-                    // 4. Tell reconnecting player the opponent is here (Bug 2 fix)
-                    if game.status == crate::state::GameStatus::PlacingShips
-                        || game.status == crate::state::GameStatus::Initializing
-                        || game.status == crate::state::GameStatus::Playing
-                        || game.status == crate::state::GameStatus::SuddenDeath
-                    {
-                        let opponent_id = if is_p1 {
-                            game.player2.as_ref().map(|p| p.id)
-                        } else {
-                            Some(game.player1.id)
-                        };
-                        if let Some(oid) = opponent_id {
-                            msgs.push(ServerMessage::PlayerJoined { player_id: oid });
+                    // Check if player is already in the game (Reconnect)
+                    // SECURITY: Only match by player_id — CF handles are public and not auth tokens
+                    if let (Some(locale), Some(player)) = (locale, game.player_mut(pid)) {
+                        if player.locale != locale {
+                            player.locale = locale;
+                            // The published view carries the old status line
+                            crate::game::publish_views(game);
                         }
-
-                        // Re-send opponent's ShipsConfirmed if they already placed
-                        let opponent_placed = if is_p1 {
-                            game.player2
-                                .as_ref()
-                                .map(|p| p.ships_placed)
-                                .unwrap_or(false)
+                    }
+                    if is_p1 || is_p2 {
+                        // RECONNECTION LOGIC
+                        game.revive();
+                        let msgs = resync_messages(game, pid);
+
+                        // Prefetch solved set on first connection if not already done.
+                        // Spreads CF API load: P1 prefetches while waiting for P2,
+                        // P2 prefetches during placement.
+                        let should_prefetch = if is_p1 {
+                            !game.player1.solved_set_ready
                         } else {
-                            game.player1.ships_placed
+                            game.player2.as_ref().map(|p| !p.solved_set_ready).unwrap_or(false)
                         };
-                        if opponent_placed {
-                            let oid = if is_p1 {
-                                game.player2.as_ref().unwrap().id
+                        if should_prefetch {
+                            let state2 = state.clone();
+                            let gid = game_id;
+                            let handle = if is_p1 {
+                                game.player1.cf_handle.clone()
                             } else {
-                                game.player1.id
+                                game.player2.as_ref().unwrap().cf_handle.clone()
                             };
-                            msgs.push(ServerMessage::ShipsConfirmed { player_id: oid });
+                            let p_id = pid;
+                            let judge = state.judges.for_game(&game.config);
+                            tokio::spawn(
+                                crate::reporting::in_task("prefetch", Some(gid), async move {
+                                    prefetch_solved_set(state2, judge, gid, p_id, handle).await;
+                                })
+                                .in_current_span(),
+                            );
                         }
+
+                        return msgs;
                     }
 
-                    // 5. If game started (both placed), send GameStart and Grids
-                    if game.status == crate::state::GameStatus::Playing
-                        || game.status == crate::state::GameStatus::SuddenDeath
-                    {
-                        msgs.push(ServerMessage::GameStart);
-
-                        // My Grid
-                        let my_grid: Vec<Vec<String>> = player
-                            .grid
-                            .cells
-                            .iter()
-                            .map(|row| {
-                                row.iter()
-                                    .map(|cell| match cell {
-                                        crate::state::CellState::Empty => "empty".to_string(),
-                                        crate::state::CellState::Ship => "ship".to_string(),
-                                        crate::state::CellState::Hit => "hit".to_string(),
-                                        crate::state::CellState::Miss => "miss".to_string(),
-                                    })
-                                    .collect()
-                            })
-                            .collect();
-
-                        // Enemy Grid
-                        let enemy = if is_p1 {
-                            game.player2.as_ref()
-                        } else {
-                            Some(&game.player1)
-                        };
+                    // Anyone else is trying to take the P2 seat. Checking and
+                    // seating happen in this one command, so concurrent guests
+                    // can't both be seated.
+                    if game.player1.id != pid {
+                        let p1_id = game.player1.id;
+                        if bot.as_ref().is_some_and(|b| !b.allows(game)) {
+                            return vec![GameError::BotNotAllowed.into()];
+                        }
 
-                        let enemy_grid: Vec<Vec<String>> = if let Some(enemy_p) = enemy {
-                            enemy_p
-                                .grid
-                                .cells
-                                .iter()
-                                .map(|row| {
-                                    row.iter()
-                                        .map(|cell| match cell {
-                                            crate::state::CellState::Empty
-                                            | crate::state::CellState::Ship => "empty".to_string(), // Hide ships!
-                                            crate::state::CellState::Hit => "hit".to_string(),
-                                            crate::state::CellState::Miss => "miss".to_string(),
-                                        })
-                                        .collect()
+                        // Trust the user's CF handle — verification removed for performance.
+                        // Entering a wrong handle is self-punishing: the player can't verify
+                        // CP solutions on someone else's account, so weapons stay locked forever.
+                        if let Err(e) = game.seat_guest(pid, cf_handle) {
+                            return vec![e.into()];
+                        }
+                        if let Some(guest) = game.player2.as_mut() {
+                            if let Some(bot) = bot {
+                                tracing::info!(target: "game", "Bot {:?} took the guest seat", bot.bot);
+                                guest.bot = Some(bot.bot);
+                            }
+                            if let Some(locale) = locale {
+                                guest.locale = locale;
+                            }
+                        }
+                        // Co-op skips placement: the server already hid the fleet
+                        if game.status == GameStatus::Initializing {
+                            spawn_init(game, state);
+                        }
+
+                        // Broadcast PlayerJoined to Host (P1) so they know P2 joined
+                        let reached = game.tx.send(crate::state::GameEvent::Message(
+                            ServerMessage::PlayerJoined { player_id: pid },
+                        ));
+                        tracing::debug!(
+                            target: "ws",
+                            "Broadcast PlayerJoined for {:?} - reached {} of {} subscribers",
+                            pid,
+                            reached,
+                            game.tx.receiver_count()
+                        );
+
+                        // Return GameJoined + PlayerJoined for P1 to the joining Guest
+                        // This tells Guest that the opponent (Host) already exists
+
+                        // Spawn P2 solved-set prefetch so it runs during the placement phase.
+                        // By the time both players finish placing ships, this is likely done.
+                        {
+                            let state2 = state.clone();
+                            let gid = game_id;
+                            let p2_handle = game.player2.as_ref().unwrap().cf_handle.clone();
+                            let judge = state.judges.for_game(&game.config);
+                            tokio::spawn(
+                                crate::reporting::in_task("prefetch", Some(gid), async move {
+                                    prefetch_solved_set(state2, judge, gid, pid, p2_handle).await;
                                 })
-                                .collect()
-                        } else {
-                            // Should not happen if playing
-                            vec![vec!["empty".to_string(); 10]; 10]
-                        };
+                                .in_current_span(),
+                            );
+                        }
+
+                        let token = state
+                            .sessions
+                            .issue(pid, game_id, crate::auth::Role::Guest);
+                        return vec![
+                            game_joined(game, pid),
+                            ServerMessage::SessionToken { token },
+                            ServerMessage::PlayerJoined { player_id: p1_id },
+                        ];
+                    }
+
+                    // If we reach here, player is P1 (host) connecting for first time
+                    // This should only happen if P1 connects before calling JoinGame
+                    vec![game_joined(game, pid)]
+                })
+                .await
+                .unwrap_or_else(game_not_found);
+
+            // Only a join that went through binds the socket to the player
+            if responses.iter().any(|r| matches!(r, ServerMessage::GameJoined { .. })) {
+                *player_id = Some(pid);
+                tracing::Span::current().record("player_id", tracing::field::display(pid));
+            }
+            // A freshly seated guest acts under the token they were just issued
+            if let Some(token) = responses.iter().find_map(|r| match r {
+                ServerMessage::SessionToken { token } => Some(token),
+                _ => None,
+            }) {
+                *session = state.sessions.verify(token);
+            }
+            responses
+        }
+
+        ClientMessage::PlaceShips { ships, forward } => {
+            let pid = (*player_id).unwrap_or_default(); // Should handle None better but simplified
+            if pid == Uuid::default() {
+                return vec![GameError::NoPlayerId.into()];
+            }
+
+            let st = state.clone();
+            state
+                .with_game(game_id, move |game| {
+                    let state = &st;
+
+                    // Determine if player is P1 or P2
+                    let is_player1 = game.player1.id == pid;
+                    let is_player2 = game.player2.as_ref().map(|p| p.id) == Some(pid);
 
-                        msgs.push(ServerMessage::GridSync {
-                            my_grid,
-                            enemy_grid,
-                        });
+                    if !is_player1 && !is_player2 {
+                        return vec![GameError::NotInGame.into()];
+                    }
+                    if game.config.coop {
+                        return vec![GameError::CoopUnavailable.into()];
                     }
 
-                    // Prefetch solved set on first connection if not already done.
-                    // Spreads CF API load: P1 prefetches while waiting for P2,
-                    // P2 prefetches during placement.
-                    let should_prefetch = if is_p1 {
-                        !game.player1.solved_set_ready
+                    // SECURITY: Prevent ship placement after game has started
+                    if game.status == GameStatus::Playing
+                        || game.status == GameStatus::SuddenDeath
+                        || game.status == GameStatus::Finished
+                    {
+                        return vec![GameError::PlacementClosed.into()];
+                    }
+
+                    // IDEMPOTENCE CHECK
+                    // Check if already placed WITHOUT borrowing mutable yet
+                    let already_placed = if is_player1 {
+                        game.player1.ships_placed
+                    } else if let Some(ref p) = game.player2 {
+                        p.ships_placed
                     } else {
-                        game.player2.as_ref().map(|p| !p.solved_set_ready).unwrap_or(false)
+                        false
                     };
-                    if should_prefetch {
-                        let state2 = state.clone();
-                        let gid = game_id;
-                        let handle = if is_p1 {
-                            game.player1.cf_handle.clone()
+
+                    if already_placed {
+                        let player = if is_player1 {
+                            &game.player1
+                        } else if let Some(ref p) = game.player2 {
+                            p
                         } else {
-                            game.player2.as_ref().unwrap().cf_handle.clone()
+                            return vec![GameError::OpponentLeft.into()];
                         };
-                        let p_id = pid;
-                        tokio::spawn(async move {
-                            prefetch_solved_set(state2, gid, p_id, handle).await;
-                        });
+                        return vec![
+                            ServerMessage::ShipsConfirmed { player_id: pid },
+                            ServerMessage::GameUpdate {
+                                status: player.locale.status(crate::locale::Status::ShipsPlaced),
+                                is_active: true,
+                                heat: player.heat,
+                                is_locked: player.is_locked,
+                                time_remaining_secs: game.config.game_duration_secs,
+                                vetoes_remaining: game.config.max_vetoes.saturating_sub(player.vetoes_used),
+                                veto_time_remaining_secs: None,
+                                phase_time_remaining_secs: crate::timers::phase_time_remaining_secs(game),
+                                active_problem_contest_id: None,
+                                active_problem_index: None,
+                                active_problem_name: None,
+                                active_problem_url: None,
+                                special_weapons: player.inventory.clone(),
+                                shields_available: player.shields_available,
+                                max_heat: None,
+                                ammo: player.ammo,
+                            },
+                        ];
                     }
 
-                    return msgs;
-                }
+                    match (game.config.armada, &forward) {
+                        (true, None) => return vec![GameError::ArmadaPlacement.into()],
+                        (false, Some(_)) => return vec![GameError::NoForwardFleet.into()],
+                        _ => {}
+                    }
 
-                // Check if player is trying to join as P2
-                if game.player1.id != pid && game.player2.is_none() {
-                    if game.player1.cf_handle.eq_ignore_ascii_case(&cf_handle) {
-                        return vec![ServerMessage::Error {
-                            message: "You cannot play against yourself!".to_string(),
-                        }];
+                    // ANTI-CHEAT: Validate fleet composition and placement with the shared rules
+                    let forward = match forward.as_deref().map(battle_cp_core::place_fleet).transpose() {
+                        Ok(forward) => forward,
+                        Err(e) => return vec![GameError::from(e).into()],
+                    };
+                    match battle_cp_core::place_fleet(&ships) {
+                        Ok((grid, placed)) => commit_fleet(game, pid, grid, placed, forward, state),
+                        Err(e) => vec![GameError::from(e).into()],
                     }
+                })
+                .await
+                .unwrap_or_else(game_not_found)
+        }
 
-                    // P2 is joining - get P1's ID before joining
-                    let p1_id = game.player1.id;
+        ClientMessage::PlaceShip { ship } => {
+            handle_staging(*player_id, game_id, state, move |staged| {
+                battle_cp_core::stage_ship(staged, &ship)?;
+                staged.push(ship);
+                Ok(())
+            })
+            .await
+        }
 
-                    // Trust the user's CF handle — verification removed for performance.
-                    // Entering a wrong handle is self-punishing: the player can't verify
-                    // CP solutions on someone else's account, so weapons stay locked forever.
+        ClientMessage::UnplaceShip { index } => {
+            handle_staging(*player_id, game_id, state, move |staged| {
+                if index >= staged.len() {
+                    return Err(GameError::NoStagedShip);
+                }
+                staged.remove(index);
+                Ok(())
+            })
+            .await
+        }
 
-                    if let Err(e) = game.join(pid, cf_handle) {
-                        return vec![ServerMessage::Error {
-                            message: e.to_string(),
-                        }];
+        ClientMessage::Ready => {
+            let pid = if let Some(p) = *player_id {
+                p
+            } else {
+                return vec![GameError::NoPlayerId.into()];
+            };
+            let st = state.clone();
+            state
+                .with_game(game_id, move |game| {
+                    let state = &st;
+
+                    if game.status != GameStatus::PlacingShips && game.status != GameStatus::Initializing {
+                        return vec![GameError::ReadyClosed.into()];
                     }
 
-                    // Set status to PlacingShips now that both players are in
-                    game.status = crate::state::GameStatus::PlacingShips;
+                    let player = if game.player1.id == pid {
+                        &mut game.player1
+                    } else if let Some(p) = game.player2.as_mut().filter(|p| p.id == pid) {
+                        p
+                    } else {
+                        return vec![GameError::NotInGame.into()];
+                    };
 
-                    // Broadcast PlayerJoined to Host (P1) so they know P2 joined
-                    let result = game.tx.send(crate::state::GameEvent::Message(
-                        ServerMessage::PlayerJoined { player_id: pid },
+                    if !player.ships_placed {
+                        return vec![GameError::ShipsNotPlaced.into()];
+                    }
+
+                    // Idempotent: re-sending Ready just re-broadcasts PlayerReady
+                    player.ready = true;
+                    let _ = game.tx.send(crate::state::GameEvent::Message(
+                        ServerMessage::PlayerReady { player_id: pid },
                     ));
-                    tracing::debug!(
-                        "[WS] Broadcast PlayerJoined for {:?} - result: {:?}, subscribers: {}",
-                        pid,
-                        result.is_ok(),
-                        game.tx.receiver_count()
-                    );
 
-                    // Return GameJoined + PlayerJoined for P1 to the joining Guest
-                    // This tells Guest that the opponent (Host) already exists
+                    begin_countdown_if_ready(game, state);
+                    vec![]
+                })
+                .await
+                .unwrap_or_else(game_not_found)
+        }
 
-                    // Spawn P2 solved-set prefetch so it runs during the placement phase.
-                    // By the time both players finish placing ships, this is likely done.
-                    {
-                        let state2 = state.clone();
-                        let gid = game_id;
-                        let p2_handle = game.player2.as_ref().unwrap().cf_handle.clone();
-                        tokio::spawn(async move {
-                            prefetch_solved_set(state2, gid, pid, p2_handle).await;
-                        });
+        ClientMessage::Fire { at, board } => handle_fire(at, board, None, *player_id, game_id, state).await,
+
+        ClientMessage::FireSpecial { weapon, at, board } => {
+            handle_fire(at, board, Some(weapon), *player_id, game_id, state).await
+        }
+
+        ClientMessage::SolveCP {
+            contest_id,
+            problem_index,
+        } => {
+            let pid = if let Some(p) = *player_id {
+                p
+            } else {
+                return vec![GameError::NoPlayerId.into()];
+            };
+            let st = state.clone();
+            state
+                .with_game(game_id, move |game| {
+                    let state = &st;
+
+                    if game.status == crate::state::GameStatus::Finished {
+                        return vec![GameError::GameEnded.into()];
+                    }
+                    // Reject garbage before it touches game state or the judge
+                    let judge = state.judges.for_game(&game.config);
+                    if !judge.is_valid_problem_ref(contest_id, &problem_index) {
+                        return vec![GameError::InvalidProblem.into()];
                     }
 
-                    return vec![
-                        ServerMessage::GameJoined {
-                            game_id,
-                            player_id: pid,
-                            difficulty: game.config.difficulty,
-                            difficulty_mode: game.config.difficulty_mode.clone(),
-                            max_heat: game.config.heat_threshold,
-                            max_vetoes: game.config.max_vetoes,
-                        },
-                        ServerMessage::PlayerJoined { player_id: p1_id },
-                    ];
-                }
+                    let player = if game.player1.id == pid {
+                        &mut game.player1
+                    } else if game.player2.as_ref().map(|p| p.id) == Some(pid) {
+                        if let Some(ref mut p) = game.player2 {
+                            p
+                        } else {
+                            return vec![GameError::WaitingForOpponent.into()];
+                        }
+                    } else {
+                        return vec![GameError::NotInGame.into()];
+                    };
 
-                // Third player trying to join a full game — explicitly reject
-                if game.player1.id != pid && game.player2.is_some() {
-                    return vec![ServerMessage::Error {
-                        message: "Game already has 2 players.".to_string(),
-                    }];
-                }
+                    // SECURITY: Block solving during active veto
+                    if player.veto_started_at.is_some() {
+                        return vec![GameError::SolveDuringVeto.into()];
+                    }
 
-                // If we reach here, player is P1 (host) connecting for first time
-                // This should only happen if P1 connects before calling JoinGame
-                vec![ServerMessage::GameJoined {
-                    game_id,
-                    player_id: pid,
-                    difficulty: game.config.difficulty,
-                    difficulty_mode: game.config.difficulty_mode.clone(),
-                    max_heat: game.config.heat_threshold,
-                    max_vetoes: game.config.max_vetoes,
-                }]
-            } else {
-                vec![ServerMessage::Error {
-                    message: "Game not found".to_string(),
-                }]
-            }
-        }
+                    // SECURITY: While unlocked the only thing to verify is a bonus problem.
+                    // Without this, a player could freely call SolveCP to inflate problems_solved
+                    // and get heat/lock reset for free at any time
+                    let solving = if player.is_locked {
+                        SolveFor::Unlock
+                    } else if player.bonus_problem.is_some() {
+                        SolveFor::Bonus
+                    } else {
+                        return vec![GameError::NothingToVerify.into()];
+                    };
+                    let (assigned, since) = match solving {
+                        SolveFor::Unlock => (&player.active_problem, player.locked_at_unix),
+                        SolveFor::Bonus => (&player.bonus_problem, player.bonus_assigned_at_unix),
+                    };
 
-        ClientMessage::PlaceShips { ships } => {
-            let pid = (*player_id).unwrap_or_default(); // Should handle None better but simplified
-            if pid == Uuid::default() {
-                return vec![ServerMessage::Error {
-                    message: "No player ID".to_string(),
-                }];
-            }
+                    // SECURITY: Server is the single source of truth for problem assignment.
+                    // The player MUST solve the problem the server assigned when weapons locked.
+                    // No client-side problem selection — prevents pre-solve exploits.
+                    let problem = match assigned {
+                        None => {
+                            return vec![GameError::NoProblemAssigned.into()];
+                        }
+                        Some(assigned) => {
+                            if assigned.contest_id != contest_id || assigned.index != problem_index {
+                                return vec![GameError::WrongProblem.into()];
+                            }
+                            assigned.clone()
+                        }
+                    };
 
-            let mut games = state.games.write().await;
-            let game = match games.get_mut(&game_id) {
-                Some(g) => g,
-                None => {
-                    return vec![ServerMessage::Error {
-                        message: "Game not found".to_string(),
-                    }]
-                }
-            };
+                    // RATE LIMIT CHECK: 10s cooldown, doubled per failed verification
+                    if let Some(last) = player.last_verification_attempt {
+                        let cooldown = player.verify_cooldown();
+                        if last.elapsed() < cooldown {
+                            let wait = (cooldown - last.elapsed()).as_millis().div_ceil(1000) as u64;
+                            return vec![GameError::VerifyCooldown(wait).into()];
+                        }
+                    }
+                    // Shared budgets, so one game or client can't drain the CF quota
+                    if let Err(retry_after) = state.verify_limiters.check(game_id, ip, player.bot.as_deref()) {
+                        tracing::warn!(target: "game", "Verification budget exhausted (game {:?}, ip {:?})", game_id, ip);
+                        return vec![GameError::VerifyThrottled(retry_after.as_secs().max(1)).into()];
+                    }
+                    // Update timestamp
+                    player.last_verification_attempt = Some(std::time::Instant::now());
+                    if matches!(solving, SolveFor::Unlock) {
+                        player.last_active_at = player.last_verification_attempt;
+                    }
 
-            // Determine if player is P1 or P2
-            let is_player1 = game.player1.id == pid;
-            let is_player2 = game.player2.as_ref().map(|p| p.id) == Some(pid);
+                    let handle = player.cf_handle.clone();
+                    let tx = game.tx.clone();
 
-            if !is_player1 && !is_player2 {
-                return vec![ServerMessage::Error {
-                    message: "Not in game".to_string(),
-                }];
-            }
+                    // Broadcast VerifyPending so the frontend shows a spinner
+                    let _ = tx.send(crate::state::GameEvent::Message(
+                        ServerMessage::VerifyPending { player_id: pid },
+                    ));
 
-            // SECURITY: Prevent ship placement after game has started
-            if game.status == GameStatus::Playing
-                || game.status == GameStatus::SuddenDeath
-                || game.status == GameStatus::Finished
-            {
-                return vec![ServerMessage::Error {
-                    message: "Cannot place ships after game has started".to_string(),
-                }];
-            }
+                    // Spawn a background task for the judge call so this WS
+                    // connection keeps processing ticks and broadcasts.
+                    let state2 = state.clone();
+                    tokio::spawn(
+                        crate::reporting::in_task("verify", Some(game_id), async move {
+                            verify_and_unlock(state2, judge, game_id, pid, handle, problem, since, solving).await;
+                        })
+                        .in_current_span(),
+                    );
+
+                    vec![]
+                })
+                .await
+                .unwrap_or_else(game_not_found)
+        }
 
-            // IDEMPOTENCE CHECK
-            // Check if already placed WITHOUT borrowing mutable yet
-            let already_placed = if is_player1 {
-                game.player1.ships_placed
-            } else if let Some(ref p) = game.player2 {
-                p.ships_placed
+        ClientMessage::Veto => {
+            let pid = if let Some(p) = *player_id {
+                p
             } else {
-                false
+                return vec![GameError::NoPlayerId.into()];
             };
+            state
+                .with_game(game_id, move |game| {
 
-            if already_placed {
-                let player = if is_player1 {
-                    &game.player1
-                } else if let Some(ref p) = game.player2 {
-                    p
-                } else {
-                    return vec![ServerMessage::Error {
-                        message: "Opponent left".to_string(),
-                    }];
-                };
-                return vec![
-                    ServerMessage::ShipsConfirmed { player_id: pid },
-                    ServerMessage::GameUpdate {
-                        status: "Ships Placed".to_string(),
-                        is_active: true,
+                    if game.status == crate::state::GameStatus::Finished {
+                        return vec![GameError::GameEnded.into()];
+                    }
+                    let game_remaining = game.time_remaining_secs();
+
+                    let player = if game.player1.id == pid {
+                        &mut game.player1
+                    } else if game.player2.as_ref().map(|p| p.id) == Some(pid) {
+                        if let Some(ref mut p) = game.player2 {
+                            p
+                        } else {
+                            return vec![GameError::WaitingForOpponent.into()];
+                        }
+                    } else {
+                        return vec![GameError::NotInGame.into()];
+                    };
+
+                    // Check if player is actually locked - can't use veto if not overheated
+                    if !player.is_locked {
+                        return vec![GameError::NothingToVeto.into()];
+                    }
+
+                    // Check if already on veto timer - can't double veto
+                    if player.veto_started_at.is_some() {
+                        return vec![GameError::VetoRunning.into()];
+                    }
+
+                    // Check if player has vetoes remaining (use config, not hardcoded 3)
+                    if player.vetoes_used >= game.config.max_vetoes {
+                        return vec![GameError::NoVetoesLeft.into()];
+                    }
+
+                    // Get veto duration based on current usage count (BEFORE incrementing)
+                    let duration_secs = match game.config.veto_penalty(player.vetoes_used) {
+                        Some(d) => d + player.veto_extension_secs,
+                        None => {
+                            return vec![GameError::InvalidVetoConfig.into()]
+                        }
+                    };
+
+                    // Start veto timer (on the game clock: a veto during an outage pause starts on resume)
+                    player.veto_started_at = Some(game.clock_paused_at.unwrap_or_else(std::time::Instant::now));
+
+                    // NOW increment vetoes_used
+                    player.vetoes_used += 1;
+
+                    // Clear the current problem — veto means SKIP solving entirely.
+                    // The player waits out the penalty, then the veto deadline fires in
+                    // the game's actor (timers.rs). No new problem is assigned during veto.
+                    // When they overheat again later, a new problem will be picked then.
+                    player.active_problem = None;
+
+                    // vetoes_remaining is now calculated AFTER incrementing
+                    let update = ServerMessage::GameUpdate {
+                        status: player.locale.status(crate::locale::Status::VetoActivated { minutes: duration_secs / 60 }),
+                        is_active: false,
                         heat: player.heat,
-                        is_locked: player.is_locked,
-                        time_remaining_secs: game.config.game_duration_secs,
+                        is_locked: true,
+                        time_remaining_secs: game_remaining,
                         vetoes_remaining: game.config.max_vetoes.saturating_sub(player.vetoes_used),
-                        veto_time_remaining_secs: None,
+                        veto_time_remaining_secs: Some(duration_secs),
+                        phase_time_remaining_secs: None,
+                        // Problem cleared — veto skips solving, no new problem assigned
                         active_problem_contest_id: None,
                         active_problem_index: None,
                         active_problem_name: None,
-                    },
-                ];
-            }
+                        active_problem_url: None,
+                        special_weapons: player.inventory.clone(),
+                        shields_available: player.shields_available,
+                        max_heat: None,
+                        ammo: player.ammo,
+                    };
+                    game.lock_closed(pid, crate::state::LockOutcome::Vetoed);
+                    let _ = game.tx.send(crate::state::GameEvent::Message(crate::activity::veto_used(pid, duration_secs / 60)));
+                    // Co-op: the veto covers the whole team's lock
+                    game.share_weapons(pid);
+                    vec![update]
+                })
+                .await
+                .unwrap_or_else(game_not_found)
+        }
 
-            // ANTI-CHEAT: Validate fleet composition
-            // Standard Battleship fleet: Carrier (5), Battleship (4), Cruiser (3), Submarine (3), Destroyer (2)
-            const VALID_FLEET: [u8; 5] = [5, 4, 3, 3, 2];
-            if ships.len() != 5 {
-                return vec![ServerMessage::Error {
-                    message: format!("Invalid fleet: expected 5 ships, got {}", ships.len()),
-                }];
-            }
-            let mut ship_sizes: Vec<u8> = ships.iter().map(|s| s.size).collect();
-            ship_sizes.sort_unstable();
-            ship_sizes.reverse(); // Sort descending to match VALID_FLEET
-            if ship_sizes != VALID_FLEET {
-                return vec![ServerMessage::Error {
-                    message: "Invalid fleet composition. Ships must be sizes 5, 4, 3, 3, 2"
-                        .to_string(),
-                }];
-            }
+        ClientMessage::RequestBonusProblem => {
+            let Some(pid) = *player_id else {
+                return vec![GameError::NoPlayerId.into()];
+            };
+            let st = state.clone();
+            state
+                .with_game(game_id, move |game| {
+                    let state = &st;
+                    if game.status == GameStatus::Finished {
+                        return vec![GameError::GameEnded.into()];
+                    }
+                    if !matches!(game.status, GameStatus::Playing | GameStatus::SuddenDeath) {
+                        return vec![GameError::NotStarted.into()];
+                    }
+                    let Some(player) = game.player(pid) else {
+                        return vec![GameError::NotInGame.into()];
+                    };
+                    if player.is_locked {
+                        return vec![GameError::BonusWhileLocked.into()];
+                    }
+                    if player.bonus_problem.is_some() {
+                        return vec![GameError::BonusPending.into()];
+                    }
+                    // Shields guard your own fleet, and co-op players have none
+                    if game.config.coop {
+                        return vec![GameError::CoopUnavailable.into()];
+                    }
 
-            // Place ships
-            let mut success = true;
-            {
-                let player = if is_player1 {
-                    &mut game.player1
-                } else if let Some(ref mut p) = game.player2 {
-                    p
-                } else {
-                    return vec![ServerMessage::Error {
-                        message: "Opponent left".to_string(),
-                    }];
-                };
+                    let Some(ap) = draw_problem(game, pid, state) else {
+                        return vec![GameError::NoProblemAssigned.into()];
+                    };
+                    if let Some(player) = game.player_mut(pid) {
+                        player.bonus_problem = Some(ap.clone());
+                        player.bonus_assigned_at_unix = Some(
+                            std::time::SystemTime::now()
+                                .duration_since(std::time::UNIX_EPOCH)
+                                .unwrap_or_default()
+                                .as_secs(),
+                        );
+                    }
+                    let _ = game.tx.send(crate::state::GameEvent::Message(
+                        ServerMessage::BonusProblemAssigned {
+                            player_id: pid,
+                            contest_id: ap.contest_id,
+                            problem_index: ap.index,
+                            problem_name: ap.name,
+                            rating: ap.rating,
+                            url: ap.url,
+                        },
+                    ));
+                    vec![]
+                })
+                .await
+                .unwrap_or_else(game_not_found)
+        }
 
-                // Clear existing state allow retries
-                player.ships.clear();
-                player.grid = crate::state::Grid::new();
-
-                for placement in ships {
-                    let ship = Ship {
-                        size: placement.size,
-                        hits: 0,
-                        sunk: false,
-                        x: placement.x,
-                        y: placement.y,
-                        vertical: placement.vertical,
+        ClientMessage::VoteDifficulty { direction } => {
+            let Some(pid) = *player_id else {
+                return vec![GameError::NoPlayerId.into()];
+            };
+            let st = state.clone();
+            state
+                .with_game(game_id, move |game| {
+                    let state = &st;
+                    if game.status == GameStatus::Finished {
+                        return vec![GameError::GameEnded.into()];
+                    }
+                    if !matches!(game.status, GameStatus::Playing | GameStatus::SuddenDeath) {
+                        return vec![GameError::NotStarted.into()];
+                    }
+                    let changed = match game.vote_difficulty(pid, direction) {
+                        Ok(changed) => changed,
+                        Err(e) => return vec![e.into()],
                     };
-                    if player
-                        .place_ship(ship, placement.x, placement.y, placement.vertical)
-                        .is_err()
-                    {
-                        success = false;
-                        break;
+                    let _ = game.tx.send(crate::state::GameEvent::Message(
+                        ServerMessage::DifficultyVoteCast { player_id: pid, direction },
+                    ));
+                    if let Some(difficulty) = changed {
+                        rebuild_problem_queue(game, state);
+                        tracing::info!(
+                            target: "game",
+                            "Game {:?}: difficulty voted to {} ({:?})",
+                            game_id, difficulty, game.config.difficulty_mode
+                        );
+                        let _ = game.tx.send(crate::state::GameEvent::Message(
+                            ServerMessage::DifficultyChanged {
+                                difficulty,
+                                difficulty_mode: game.config.difficulty_mode.clone(),
+                            },
+                        ));
                     }
-                }
+                    vec![]
+                })
+                .await
+                .unwrap_or_else(game_not_found)
+        }
+
+        ClientMessage::RequestHint => {
+            let Some(pid) = *player_id else {
+                return vec![GameError::NoPlayerId.into()];
+            };
+            let bought = state
+                .with_game(game_id, move |game| {
+                    if game.status == GameStatus::Finished {
+                        return Err(GameError::GameEnded);
+                    }
+                    if !matches!(game.status, GameStatus::Playing | GameStatus::SuddenDeath) {
+                        return Err(GameError::NotStarted);
+                    }
+                    let (cost, threshold) = (game.config.hint_cost, game.heat_threshold());
+                    let player = game.player_mut(pid).ok_or(GameError::NotInGame)?;
+                    let problem = player.buy_hint(cost)?;
+                    let max_heat = player.heat_limit(threshold);
+                    // A co-op team pays for hints together
+                    game.share_weapons(pid);
+                    Ok((problem, max_heat, game.config.judge == crate::judge::CODEFORCES))
+                })
+                .await;
+            let (problem, max_heat, on_codeforces) = match bought {
+                Some(Ok(bought)) => bought,
+                Some(Err(e)) => return vec![e.into()],
+                None => return game_not_found(),
+            };
 
-                if success {
-                    player.ships_placed = true;
+            // Tags aren't in the problem DB; a Codeforces outage just leaves them
+            // out, as do other judges
+            let tags = match on_codeforces {
+                false => Ok(vec![]),
+                true => state.cf_client.fetch_contest_problems(problem.contest_id).await,
+            };
+            let tags = match tags {
+                Ok(problems) => problems
+                    .into_iter()
+                    .find(|p| p.index == problem.index)
+                    .map(|p| p.tags)
+                    .unwrap_or_default(),
+                Err(e) => {
+                    tracing::warn!(target: "cf", "Hint: no tags for contest {}: {}", problem.contest_id, e);
+                    vec![]
                 }
-            } // mutable borrow of player ends here
+            };
+            vec![ServerMessage::Hint {
+                contest_id: problem.contest_id,
+                problem_index: problem.index,
+                rating: problem.rating,
+                tags,
+                max_heat,
+            }]
+        }
 
-            if !success {
-                return vec![ServerMessage::Error {
-                    message: "Invalid ship placement".to_string(),
-                }];
-            }
+        ClientMessage::PlaceShield { at } => {
+            let Some(pid) = *player_id else {
+                return vec![GameError::NoPlayerId.into()];
+            };
+            state
+                .with_game(game_id, move |game| {
+                    if game.status == GameStatus::Finished {
+                        return vec![GameError::GameEnded.into()];
+                    }
+                    if !matches!(game.status, GameStatus::Playing | GameStatus::SuddenDeath) {
+                        return vec![GameError::NotStarted.into()];
+                    }
+                    let Some(player) = game.player_mut(pid) else {
+                        return vec![GameError::NotInGame.into()];
+                    };
+                    // Only the placer learns where it went
+                    match player.place_shield(at.x, at.y) {
+                        Ok(()) => vec![ServerMessage::ShieldPlaced {
+                            x: at.x,
+                            y: at.y,
+                            coord: at.to_string(),
+                        }],
+                        Err(e) => vec![e.into()],
+                    }
+                })
+                .await
+                .unwrap_or_else(game_not_found)
+        }
 
-            // Now we can safely check both players
-            let both_ready = game.player1.ships_placed
-                && game
-                    .player2
-                    .as_ref()
-                    .map(|p| p.ships_placed)
-                    .unwrap_or(false);
-
-            // IMPORTANT: Broadcast ShipsConfirmed FIRST so opponent knows we placed
-            let _ = game.tx.send(crate::state::GameEvent::Message(
-                ServerMessage::ShipsConfirmed { player_id: pid },
-            ));
-
-            // THEN check if both ready and start game
-            if both_ready {
-                // Mark as Initializing while we fetch CF solved sets.
-                // NOT Playing — the background ticker ignores Initializing games,
-                // so the game timer doesn't start counting down during the CF fetch.
-                game.status = crate::state::GameStatus::Initializing;
-                // DON'T set game_started_at yet — timer starts after CF fetch completes.
-
-                let p1_handle = game.player1.cf_handle.clone();
-                let p2_handle = game.player2.as_ref().map(|p| p.cf_handle.clone()).unwrap_or_default();
-
-                // Spawn a background task for the CF fetch so we don't block
-                // this WS connection's message loop (ticks keep flowing).
-                let state2 = state.clone();
-                let gid = game_id;
-                tokio::spawn(async move {
-                    init_game_from_cf(state2, gid, p1_handle, p2_handle).await;
-                });
-
-                // Return immediately — the frontend shows "Initializing" / "Setting up battle..."
-                // until GameStart is broadcast by the spawned task.
+        ClientMessage::SetMarker { at, marker } => {
+            let Some(pid) = *player_id else {
+                return vec![GameError::NoPlayerId.into()];
+            };
+            state
+                .with_game(game_id, move |game| {
+                    let Some(player) = game.player_mut(pid) else {
+                        return vec![GameError::NotInGame.into()];
+                    };
+                    // Only ever sent back to the one who set it
+                    match player.set_marker(at.x, at.y, marker) {
+                        Ok(()) => vec![ServerMessage::Markers { markers: player.markers.clone() }],
+                        Err(e) => vec![e.into()],
+                    }
+                })
+                .await
+                .unwrap_or_else(game_not_found)
+        }
+
+        ClientMessage::ExtendLobby => {
+            let Some(pid) = *player_id else {
+                return vec![GameError::NoPlayerId.into()];
+            };
+            state
+                .with_game(game_id, move |game| match game.extend_lobby(pid) {
+                    Ok(left) => {
+                        tracing::info!(target: "game", "Game {:?}: lobby extended ({} left)", game_id, left);
+                        vec![]
+                    }
+                    Err(e) => vec![e.into()],
+                })
+                .await
+                .unwrap_or_else(game_not_found)
+        }
+        ClientMessage::Pong { sent_at_ms } => {
+            let Some(pid) = *player_id else {
+                return vec![];
+            };
+            // Echoes of pings we never sent (or from a skewed future) are ignored
+            let Some(rtt_ms) = unix_ms().checked_sub(sent_at_ms).filter(|&ms| ms <= MAX_RTT_MS) else {
                 return vec![];
+            };
+            let rtt_ms = rtt_ms as u32;
+            if let Some(Some(p95_ms)) = state.with_game(game_id, move |game| game.record_rtt(pid, rtt_ms)).await {
+                if state.admin_tx.receiver_count() > 0 {
+                    let _ = state.admin_tx.send(crate::protocol::AdminMessage::Latency {
+                        game_id,
+                        player_id: pid,
+                        rtt_ms,
+                        p95_ms,
+                    });
+                }
             }
+            vec![]
+        }
+    }
+}
 
-            // Only one player has placed — waiting for the other
-            let player = if is_player1 {
-                &game.player1
-            } else if let Some(ref p) = game.player2 {
-                p
-            } else {
-                return vec![ServerMessage::Error {
-                    message: "Opponent left".to_string(),
-                }];
-            };
+/// Longest round trip a `Pong` is believed for.
+const MAX_RTT_MS: u64 = 60_000;
 
-            vec![ServerMessage::GameUpdate {
-                status: "Placement Complete".to_string(),
-                is_active: false,
-                heat: player.heat,
-                is_locked: player.is_locked,
-                time_remaining_secs: game.config.game_duration_secs,
-                vetoes_remaining: game.config.max_vetoes.saturating_sub(player.vetoes_used),
-                veto_time_remaining_secs: None,
-                active_problem_contest_id: None,
-                active_problem_index: None,
-                active_problem_name: None,
-            }]
-        }
+fn unix_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
 
-        ClientMessage::Fire { x, y } => {
-            let pid = (*player_id).unwrap_or_default();
-            if pid == Uuid::default() {
-                return vec![ServerMessage::Error {
-                    message: "No player ID".to_string(),
-                }];
-            }
+/// A shot, or a special-weapon volley when `weapon` is set. Results, locks,
+/// game over and problem assignment are all broadcast; only errors are returned.
+async fn handle_fire(
+    at: crate::protocol::Coord,
+    board: crate::protocol::Board,
+    weapon: Option<SpecialWeapon>,
+    player_id: Option<Uuid>,
+    game_id: Uuid,
+    state: &AppState,
+) -> Vec<ServerMessage> {
+    let (x, y) = (at.x, at.y);
+    let pid = player_id.unwrap_or_default();
+    if pid == Uuid::default() {
+        return vec![GameError::NoPlayerId.into()];
+    }
 
-            let mut games = state.games.write().await;
-            let game = match games.get_mut(&game_id) {
-                Some(g) => g,
-                None => {
-                    return vec![ServerMessage::Error {
-                        message: "Game not found".to_string(),
-                    }]
-                }
-            };
+    let st = state.clone();
+    state
+        .with_game(game_id, move |game| {
+            let state = &st;
+
+            if game.status == GameStatus::Finished {
+                return vec![GameError::GameEnded.into()];
+            }
 
             // CRITICAL: Check game is in Playing or SuddenDeath status
             let is_sudden_death = game.status == GameStatus::SuddenDeath;
             if game.status != GameStatus::Playing && !is_sudden_death {
-                return vec![ServerMessage::Error {
-                    message: "Game has not started yet. Wait for both players to place ships."
-                        .to_string(),
-                }];
+                return vec![GameError::NotStarted.into()];
+            }
+            // Accuracy sudden death: a fixed number of shots each
+            let accuracy = is_sudden_death && game.config.sudden_death == SuddenDeathMode::Accuracy;
+            if accuracy && game.player(pid).is_some_and(|p| p.sudden_death.shots >= battle_cp_core::SUDDEN_DEATH_SHOTS) {
+                return vec![GameError::SuddenDeathShotsUsed.into()];
             }
 
-            let config = game.config.clone();
+            // The heat ramp's threshold at this moment
+            let threshold = game.heat_threshold();
+            let volley = |shooter: &mut crate::state::Player, target: Option<&mut crate::state::Board>| {
+                let target = target.ok_or(GameError::NoForwardFleet)?;
+                match weapon {
+                    None => {
+                        let limit = shooter.heat_limit(threshold);
+                        shooter.fire(target, x, y, limit).map(|outcome| vec![([x, y], outcome)])
+                    }
+                    Some(weapon) => {
+                        let limit = shooter.heat_limit(threshold);
+                        shooter.fire_special(target, weapon, x, y, limit)
+                    }
+                }
+            };
 
-            let res = if game.player1.id == pid {
+            let res = if let Some(hidden) = game.coop_board.as_mut() {
+                // Co-op: everyone fires at the hidden fleet
+                let shooter = if game.player1.id == pid {
+                    &mut game.player1
+                } else if let Some(p2) = game.player2.as_mut().filter(|p| p.id == pid) {
+                    p2
+                } else {
+                    return vec![GameError::NotInGame.into()];
+                };
+                // The hidden fleet has no forward board
+                volley(shooter, Some(hidden).filter(|_| board == crate::protocol::Board::Home))
+            } else if game.player1.id == pid {
                 if let Some(ref mut p2) = game.player2 {
-                    game.player1
-                        .fire(p2, x, y, config.heat_threshold)
+                    volley(&mut game.player1, p2.board_mut(board))
                 } else {
-                    return vec![ServerMessage::Error {
-                        message: "Waiting for opponent".to_string(),
-                    }];
+                    return vec![GameError::WaitingForOpponent.into()];
                 }
             } else if game.player2.as_ref().map(|p| p.id) == Some(pid) {
                 let p1 = &mut game.player1;
                 if let Some(ref mut p2) = game.player2 {
-                    p2.fire(p1, x, y, config.heat_threshold)
+                    volley(p2, p1.board_mut(board))
                 } else {
-                    return vec![ServerMessage::Error {
-                        message: "Opponent missing".to_string(),
-                    }];
+                    return vec![GameError::WaitingForOpponent.into()];
                 }
             } else {
-                return vec![ServerMessage::Error {
-                    message: "Not in game".to_string(),
-                }];
+                return vec![GameError::NotInGame.into()];
             };
 
-            match res {
-                Ok((result, sunk_this_shot, sunk_cells)) => {
+            match res {
+                Ok(shots) => {
+                    // The shot, any lock, GameOver and problem reach sockets as one frame
+                    let _batch = game.tx.batch();
+                    let shooter = if game.player1.id == pid {
+                        Some(&game.player1)
+                    } else {
+                        game.player2.as_ref()
+                    };
+                    if let Some(shooter) = shooter {
+                        game.audit_log.record_shot(game_id, shooter);
+                    }
+
                     // Check for victory logic
-                    let all_sunk = if game.player1.id == pid {
-                        game.player2.as_ref().is_some_and(|p2| {
-                            p2.grid
-                                .cells
-                                .iter()
-                                .flatten()
-                                .filter(|&&c| c == CellState::Ship)
-                                .count()
-                                == 0
-                        })
+                    let all_sunk = if let Some(board) = game.coop_board.as_ref() {
+                        board.grid.ship_cells_remaining() == 0
+                    } else if game.player1.id == pid {
+                        game.player2
+                            .as_ref()
+                            .is_some_and(|p2| p2.ship_cells_remaining() == 0)
                     } else {
-                        game.player1
-                            .grid
-                            .cells
-                            .iter()
-                            .flatten()
-                            .filter(|&&c| c == CellState::Ship)
-                            .count()
-                            == 0
+                        game.player1.ship_cells_remaining() == 0
                     };
 
-                    if all_sunk {
-                        game.status = GameStatus::Finished;
-                        game.finished_at = Some(std::time::Instant::now());
+                    // sunk_this_shot comes from fire() - true only if THAT shot sunk a ship.
+                    // Fog of war withholds it (and the sunk ship's cells) until GameOver.
+                    let reveal_sunk = !game.config.hide_sunk_info;
+                    let any_hit = shots.iter().any(|(_, (result, _, _))| *result == Shot::Hit);
+                    if accuracy {
+                        if let Some(shooter) = game.player_mut(pid) {
+                            shooter.sudden_death.shots += 1;
+                            shooter.sudden_death.hits += any_hit as u32;
+                        }
                     }
-
-                    // sunk_this_shot now comes from fire() - true only if THIS shot sunk a ship
-
-                    let shot_result = ServerMessage::ShotResult {
-                        x,
-                        y,
-                        hit: result == "Hit",
-                        sunk: sunk_this_shot,
-                        shooter_id: pid,
-                        sunk_cells,
+                    let shot_result = match weapon {
+                        None => {
+                            let (_, (result, sunk_this_shot, sunk_cells)) =
+                                shots.into_iter().next().expect("a shot resolves one cell");
+                            ServerMessage::ShotResult {
+                                x,
+                                y,
+                                hit: result == Shot::Hit,
+                                sunk: sunk_this_shot && reveal_sunk,
+                                shooter_id: pid,
+                                coord: at.to_string(),
+                                sunk_cells: sunk_cells.filter(|_| reveal_sunk),
+                                blocked: result == Shot::Blocked,
+                                board,
+                            }
+                        }
+                        Some(weapon) => ServerMessage::SpecialShotResult {
+                            shooter_id: pid,
+                            weapon,
+                            x,
+                            y,
+                            coord: at.to_string(),
+                            board,
+                            shots: shots
+                                .into_iter()
+                                .map(|([cx, cy], (result, sunk, sunk_cells))| CellShot {
+                                    x: cx,
+                                    y: cy,
+                                    coord: crate::protocol::Coord { x: cx, y: cy }.to_string(),
+                                    hit: result == Shot::Hit,
+                                    sunk: sunk && reveal_sunk,
+                                    sunk_cells: sunk_cells.filter(|_| reveal_sunk),
+                                    blocked: result == Shot::Blocked,
+                                })
+                                .collect(),
+                        },
                     };
 
                     // Broadcast to both players
                     let _ = game
                         .tx
-                        .send(crate::state::GameEvent::Message(shot_result.clone()));
+                        .send(crate::state::GameEvent::Message(shot_result));
 
                     // Check if shooter is now locked and broadcast
                     let shooter_locked = if game.player1.id == pid {
@@ -909,24 +1908,33 @@ async fn handle_client_message(
                         ));
                     }
 
-                    // If game over (all sunk), broadcast — but ONLY in standard mode.
-                    // In SuddenDeath, the SD path below always takes priority
-                    // to prevent sending two GameOver messages.
-                    if all_sunk && !is_sudden_death {
-                        let go_msg = crate::game::build_game_over(game, Some(pid), "AllShipsSunk".to_string());
-                        game.game_over_msg = Some(go_msg.clone());
-                        let _ = game.tx.send(crate::state::GameEvent::Message(go_msg));
-                        crate::discord::log_game(game, Some(pid), "AllShipsSunk");
+                    // If game over (all sunk), broadcast — but ONLY in standard mode
+                    // (or co-op, where sinking the fleet is the win in any phase).
+                    // In SuddenDeath, the SD path below otherwise takes priority;
+                    // it skips games that already ended so only one GameOver goes out.
+                    if all_sunk && game.config.coop {
+                        // Co-op: the team sank the hidden fleet in time — nobody lost
+                        game.finish(None, "CoopVictory");
+                    } else if all_sunk && (!is_sudden_death || accuracy) {
+                        game.finish(Some(pid), "AllShipsSunk");
                     }
 
                     // SUDDEN DEATH: First hit wins!
-                    if is_sudden_death && result == "Hit" {
-                        game.status = GameStatus::Finished;
-                        game.finished_at = Some(std::time::Instant::now());
-                        let go_msg = crate::game::build_game_over(game, Some(pid), "SuddenDeath - First hit wins!".to_string());
-                        game.game_over_msg = Some(go_msg.clone());
-                        let _ = game.tx.send(crate::state::GameEvent::Message(go_msg));
-                        crate::discord::log_game(game, Some(pid), "SuddenDeath");
+                    if is_sudden_death && !accuracy && any_hit && game.status != GameStatus::Finished {
+                        game.finish(Some(pid), "SuddenDeath - First hit wins!");
+                    }
+                    // ...or, on accuracy, once both players have taken their shots
+                    let shots_done = |p: &crate::state::Player| p.sudden_death.shots >= battle_cp_core::SUDDEN_DEATH_SHOTS;
+                    if accuracy
+                        && game.status != GameStatus::Finished
+                        && shots_done(&game.player1)
+                        && game.player2.as_ref().is_some_and(shots_done)
+                    {
+                        game.finish_on_accuracy();
+                    }
+                    // Anti-snipe: a shot that didn't end it buys the clock a few seconds
+                    if game.status != GameStatus::Finished {
+                        crate::timers::extend_for_shot(game);
                     }
 
                     // Bug 9 fix: Don't return ShotResult directly — broadcast handles it
@@ -945,38 +1953,7 @@ async fn handle_client_message(
 
                         if !already_has {
                             let is_p1 = game.player1.id == pid;
-
-                            // Draw from shared problem queue
-                            let queue_idx = if is_p1 { &mut game.p1_queue_idx } else { &mut game.p2_queue_idx };
-                            let assigned = if *queue_idx < game.problem_queue.len() {
-                                let ap = game.problem_queue[*queue_idx].clone();
-                                *queue_idx += 1;
-                                Some(ap)
-                            } else {
-                                // Queue exhausted — fallback to pick_problem()
-                                tracing::warn!("Problem queue exhausted for player {:?}, falling back to pick_problem", pid);
-                                let solved_set = if is_p1 {
-                                    &game.player1.solved_set
-                                } else {
-                                    &game.player2.as_ref().unwrap().solved_set
-                                };
-                                match state.cf_client.pick_problem(
-                                    game.config.difficulty,
-                                    game.config.difficulty_mode.clone(),
-                                    solved_set,
-                                ) {
-                                    Ok(p) => Some(crate::state::AssignedProblem {
-                                        contest_id: p.contest_id,
-                                        index: p.index,
-                                        name: p.name,
-                                        rating: p.rating,
-                                    }),
-                                    Err(e) => {
-                                        tracing::error!("Queue exhausted + pick_problem failed: {}", e);
-                                        None
-                                    }
-                                }
-                            };
+                            let assigned = draw_problem(game, pid, state);
 
                             if let Some(ap) = assigned {
                                 let tx = game.tx.clone();
@@ -985,6 +1962,7 @@ async fn handle_client_message(
                                 } else if let Some(ref mut p2) = game.player2 {
                                     p2.active_problem = Some(ap.clone());
                                 }
+                                game.lock_opened(pid, &ap);
                                 let _ = tx.send(crate::state::GameEvent::Message(
                                     ServerMessage::ProblemAssigned {
                                         player_id: pid,
@@ -992,235 +1970,155 @@ async fn handle_client_message(
                                         problem_index: ap.index,
                                         problem_name: ap.name,
                                         rating: ap.rating,
+                                        url: ap.url,
                                     },
                                 ));
                             }
                         }
                     }
+                    game.share_weapons(pid);
 
                     vec![]
                 }
-                Err(e) => vec![ServerMessage::Error {
-                    message: e.to_string(),
-                }],
-            }
-        }
-
-        ClientMessage::SolveCP {
-            contest_id,
-            problem_index,
-        } => {
-            let pid = if let Some(p) = *player_id {
-                p
-            } else {
-                return vec![ServerMessage::Error {
-                    message: "No player ID".to_string(),
-                }];
-            };
-            let mut games = state.games.write().await;
-            let game = if let Some(g) = games.get_mut(&game_id) {
-                g
-            } else {
-                return vec![ServerMessage::Error {
-                    message: "Game not found".to_string(),
-                }];
-            };
-
-            if game.status == crate::state::GameStatus::Finished {
-                return vec![ServerMessage::Error {
-                    message: "Game has already ended".to_string(),
-                }];
-            }
-
-            let player = if game.player1.id == pid {
-                &mut game.player1
-            } else if game.player2.as_ref().map(|p| p.id) == Some(pid) {
-                if let Some(ref mut p) = game.player2 {
-                    p
-                } else {
-                    return vec![ServerMessage::Error {
-                        message: "Waiting for opponent".to_string(),
-                    }];
-                }
-            } else {
-                return vec![ServerMessage::Error {
-                    message: "Not in game".to_string(),
-                }];
-            };
-
-            // SECURITY: Block solving during active veto
-            if player.veto_started_at.is_some() {
-                return vec![ServerMessage::Error {
-                    message: "Cannot solve during veto penalty. You must wait for the timer."
-                        .to_string(),
-                }];
-            }
-
-            // SECURITY: Block solving when weapons are not locked
-            // Without this, a player could freely call SolveCP to inflate problems_solved
-            // and get heat/lock reset for free at any time
-            if !player.is_locked {
-                return vec![ServerMessage::Error {
-                    message: "Cannot verify - weapons are not locked".to_string(),
-                }];
-            }
-
-            // SECURITY: Server is the single source of truth for problem assignment.
-            // The player MUST solve the problem the server assigned when weapons locked.
-            // No client-side problem selection — prevents pre-solve exploits.
-            match &player.active_problem {
-                None => {
-                    return vec![ServerMessage::Error {
-                        message: "No problem assigned yet. Wait for the server to assign one."
-                            .to_string(),
-                    }];
-                }
-                Some(assigned) => {
-                    if assigned.contest_id != contest_id || assigned.index != problem_index {
-                        return vec![ServerMessage::Error {
-                            message:
-                                "You must solve the assigned problem. Use veto to get a new one."
-                                    .to_string(),
-                        }];
-                    }
-                }
+                Err(e) => vec![e.into()],
             }
+        })
+        .await
+        .unwrap_or_else(game_not_found)
+}
 
-            // RATE LIMIT CHECK: 10 seconds cooldown
-            if let Some(last) = player.last_verification_attempt {
-                if last.elapsed() < std::time::Duration::from_secs(10) {
-                    return vec![ServerMessage::Error {
-                        message: "Please wait 10 seconds before verifying again.".to_string(),
-                    }];
-                }
-            }
-            // Update timestamp
-            player.last_verification_attempt = Some(std::time::Instant::now());
-
-            let handle = player.cf_handle.clone();
-            let locked_at = player.locked_at_unix;
-            let tx = game.tx.clone();
-            drop(games); // Drop lock strictly here
-
-            // Broadcast VerifyPending so the frontend shows a spinner
-            let _ = tx.send(crate::state::GameEvent::Message(
-                ServerMessage::VerifyPending { player_id: pid },
-            ));
-
-            // Spawn a background task for the CF API call so this WS
-            // connection keeps processing ticks and broadcasts.
-            let state2 = state.clone();
-            let pidx = problem_index.clone();
-            tokio::spawn(async move {
-                verify_and_unlock(state2, game_id, pid, handle, contest_id, pidx, locked_at).await;
-            });
+/// Build the shared problem queue at the current difficulty, skipping what
+/// either player has solved. Both players draw from its start again;
+/// problems already assigned are kept.
+fn rebuild_problem_queue(game: &mut crate::state::Game, state: &AppState) {
+    let empty_set = std::collections::HashSet::new();
+    let p2_solved = game.player2.as_ref().map(|p| &p.solved_set).unwrap_or(&empty_set);
+    // A virtual contest only ever assigns its revealed set
+    if !game.problem_set.is_empty() {
+        let target = crate::judge::target_rating(game.config.difficulty, &game.config.difficulty_mode);
+        game.problem_queue =
+            crate::judge::pool_queue(&game.problem_set, target, &game.player1.solved_set, p2_solved, &mut game.rng);
+        game.p1_queue_idx = 0;
+        game.p2_queue_idx = 0;
+        return;
+    }
+    // A plugin judge's own problems, nearest the difficulty
+    if let Some(pool) = state.judges.for_game(&game.config).problems() {
+        let target = crate::judge::target_rating(game.config.difficulty, &game.config.difficulty_mode);
+        game.problem_queue =
+            crate::judge::pool_queue(pool, target, &game.player1.solved_set, p2_solved, &mut game.rng);
+        game.p1_queue_idx = 0;
+        game.p2_queue_idx = 0;
+        return;
+    }
+    let queue = state.cf_client.build_shared_queue(
+        game.config.difficulty,
+        &game.config.difficulty_mode,
+        &game.player1.solved_set,
+        p2_solved,
+        50,
+        &mut game.rng,
+    );
+    game.problem_queue = queue
+        .into_iter()
+        .map(|p| crate::state::AssignedProblem {
+            contest_id: p.contest_id,
+            index: p.index,
+            name: p.name,
+            rating: p.rating,
+            url: None,
+        })
+        .collect();
+    game.p1_queue_idx = 0;
+    game.p2_queue_idx = 0;
+}
 
-            vec![]
+/// How many problems a virtual-contest game reveals.
+pub const PROBLEM_SET_SIZE: usize = 10;
+
+/// Pick a virtual-contest game's problem set when it's created: before
+/// either solved set is known, so the queue later puts the unsolved ones first.
+pub(crate) fn choose_problem_set(game: &mut crate::state::Game, state: &AppState) {
+    let none = std::collections::HashSet::new();
+    let set: Vec<crate::state::AssignedProblem> = match state.judges.for_game(&game.config).problems() {
+        Some(pool) => {
+            let target = crate::judge::target_rating(game.config.difficulty, &game.config.difficulty_mode);
+            crate::judge::pool_queue(pool, target, &none, &none, &mut game.rng)
+                .into_iter()
+                .take(PROBLEM_SET_SIZE)
+                .collect()
         }
+        None => state
+            .cf_client
+            .build_shared_queue(
+                game.config.difficulty,
+                &game.config.difficulty_mode,
+                &none,
+                &none,
+                PROBLEM_SET_SIZE,
+                &mut game.rng,
+            )
+            .into_iter()
+            .map(|p| crate::state::AssignedProblem {
+                contest_id: p.contest_id,
+                index: p.index,
+                name: p.name,
+                rating: p.rating,
+                url: None,
+            })
+            .collect(),
+    };
+    tracing::info!(target: "game", "Game {:?}: chose a problem set of {}", game.id, set.len());
+    game.problem_set = set;
+}
 
-        ClientMessage::Veto => {
-            let pid = if let Some(p) = *player_id {
-                p
-            } else {
-                return vec![ServerMessage::Error {
-                    message: "No player ID".to_string(),
-                }];
-            };
-            let mut games = state.games.write().await;
-            let game = if let Some(g) = games.get_mut(&game_id) {
-                g
-            } else {
-                return vec![ServerMessage::Error {
-                    message: "Game not found".to_string(),
-                }];
-            };
-
-            if game.status == crate::state::GameStatus::Finished {
-                return vec![ServerMessage::Error {
-                    message: "Game has already ended".to_string(),
-                }];
-            }
-
-            let player = if game.player1.id == pid {
-                &mut game.player1
-            } else if game.player2.as_ref().map(|p| p.id) == Some(pid) {
-                if let Some(ref mut p) = game.player2 {
-                    p
-                } else {
-                    return vec![ServerMessage::Error {
-                        message: "Waiting for opponent".to_string(),
-                    }];
-                }
-            } else {
-                return vec![ServerMessage::Error {
-                    message: "Not in game".to_string(),
-                }];
-            };
-
-            // Check if player is actually locked - can't use veto if not overheated
-            if !player.is_locked {
-                return vec![ServerMessage::Error {
-                    message: "Cannot use veto - weapons are not locked".to_string(),
-                }];
-            }
-
-            // Check if already on veto timer - can't double veto
-            if player.veto_started_at.is_some() {
-                return vec![ServerMessage::Error {
-                    message: "Already on veto timer. Wait for it to expire.".to_string(),
-                }];
-            }
-
-            // Check if player has vetoes remaining (use config, not hardcoded 3)
-            if player.vetoes_used >= game.config.max_vetoes {
-                return vec![ServerMessage::Error {
-                    message: "No vetoes remaining".to_string(),
-                }];
-            }
-
-            // Get veto duration based on current usage count (BEFORE incrementing)
-            let veto_durations = game.config.veto_penalties;
-            let duration_secs = match veto_durations.get(player.vetoes_used as usize).copied() {
-                Some(d) => d,
-                None => {
-                    return vec![ServerMessage::Error {
-                        message: "Invalid veto configuration".to_string(),
-                    }]
-                }
-            };
+/// The next problem for `pid` from the shared queue, falling back to
+/// `pick_problem` once their share of it is used up.
+fn draw_problem(
+    game: &mut crate::state::Game,
+    pid: Uuid,
+    state: &AppState,
+) -> Option<crate::state::AssignedProblem> {
+    let is_p1 = game.player1.id == pid;
+    // A co-op team shares the lock, so it shares one place in the queue too
+    let queue_idx = if is_p1 || game.config.coop { &mut game.p1_queue_idx } else { &mut game.p2_queue_idx };
+    if *queue_idx < game.problem_queue.len() {
+        let ap = game.problem_queue[*queue_idx].clone();
+        *queue_idx += 1;
+        return Some(ap);
+    }
 
-            // Start veto timer
-            player.veto_started_at = Some(std::time::Instant::now());
-
-            // NOW increment vetoes_used
-            player.vetoes_used += 1;
-
-            // Clear the current problem — veto means SKIP solving entirely.
-            // The player waits out the penalty, then unlock_weapons() is called
-            // by the background ticker. No new problem is assigned during veto.
-            // When they overheat again later, a new problem will be picked then.
-            player.active_problem = None;
-
-            let elapsed = game
-                .game_started_at
-                .map(|s| s.elapsed().as_secs())
-                .unwrap_or(0);
-            let game_remaining = game.config.game_duration_secs.saturating_sub(elapsed);
-
-            // vetoes_remaining is now calculated AFTER incrementing
-            vec![ServerMessage::GameUpdate {
-                status: format!("Veto activated. Wait {} minutes.", duration_secs / 60),
-                is_active: false,
-                heat: player.heat,
-                is_locked: true,
-                time_remaining_secs: game_remaining,
-                vetoes_remaining: game.config.max_vetoes.saturating_sub(player.vetoes_used),
-                veto_time_remaining_secs: Some(duration_secs),
-                // Problem cleared — veto skips solving, no new problem assigned
-                active_problem_contest_id: None,
-                active_problem_index: None,
-                active_problem_name: None,
-            }]
+    // Queue exhausted — fallback to pick_problem()
+    tracing::warn!(target: "game", "Problem queue exhausted for player {:?}, falling back to pick_problem", pid);
+    let player = if is_p1 { &game.player1 } else { game.player2.as_ref()? };
+    // A plugin judge's queue (or a virtual contest's) already held its whole
+    // list, so go round again
+    use rand::seq::SliceRandom;
+    if !game.problem_set.is_empty() {
+        return game.problem_set.choose(&mut game.rng).cloned();
+    }
+    if let Some(pool) = state.judges.for_game(&game.config).problems() {
+        return pool.choose(&mut game.rng).cloned();
+    }
+    match state.cf_client.pick_problem(
+        game.config.difficulty,
+        game.config.difficulty_mode.clone(),
+        &player.solved_set,
+        &mut game.rng,
+    ) {
+        Ok(p) => Some(crate::state::AssignedProblem {
+            contest_id: p.contest_id,
+            index: p.index,
+            name: p.name,
+            rating: p.rating,
+            url: None,
+        }),
+        Err(e) => {
+            crate::reporting::report_error(
+                Some(game.id),
+                format!("Queue exhausted + pick_problem failed: {}", e),
+            );
+            None
         }
     }
 }
@@ -1239,6 +2137,7 @@ async fn handle_client_message(
 /// game without properly checking both players' submission histories.
 async fn init_game_from_cf(
     state: AppState,
+    judge: Arc<dyn JudgeClient>,
     game_id: uuid::Uuid,
     p1_handle: String,
     p2_handle: String,
@@ -1249,43 +2148,39 @@ async fn init_game_from_cf(
         std::time::Duration::from_secs(300),
         async {
             // Check what the prefetch tasks have already fetched
-            let (p1_prefetched, p2_prefetched) = {
-                let games = state.games.read().await;
-                match games.get(&game_id) {
-                    Some(game) => {
-                        let p1 = if game.player1.solved_set_ready {
-                            Some(game.player1.solved_set.clone())
-                        } else {
-                            None
-                        };
-                        let p2 = game.player2.as_ref().and_then(|p| {
-                            if p.solved_set_ready { Some(p.solved_set.clone()) } else { None }
-                        });
-                        (p1, p2)
-                    }
-                    None => return None,
-                }
-            };
+            let (p1_prefetched, p2_prefetched) = state
+                .with_game(game_id, |game| {
+                    let p1 = if game.player1.solved_set_ready {
+                        Some(game.player1.solved_set.clone())
+                    } else {
+                        None
+                    };
+                    let p2 = game.player2.as_ref().and_then(|p| {
+                        if p.solved_set_ready { Some(p.solved_set.clone()) } else { None }
+                    });
+                    (p1, p2)
+                })
+                .await?;
 
             // Fetch only what's missing — prefetched sets save CF API calls
             let p1_set = match p1_prefetched {
                 Some(set) => {
-                    tracing::info!("init_game_from_cf {:?}: P1 solved set already prefetched ({} problems)", game_id, set.len());
+                    tracing::info!(target: "game", "init_game_from_cf {:?}: P1 solved set already prefetched ({} problems)", game_id, set.len());
                     set
                 }
                 None => {
-                    tracing::info!("init_game_from_cf {:?}: P1 solved set not ready, fetching now", game_id);
-                    fetch_solved_with_retry(&state, &p1_handle).await
+                    tracing::info!(target: "game", "init_game_from_cf {:?}: P1 solved set not ready, fetching now", game_id);
+                    fetch_solved_with_retry(judge.as_ref(), &p1_handle).await
                 }
             };
             let p2_set = match p2_prefetched {
                 Some(set) => {
-                    tracing::info!("init_game_from_cf {:?}: P2 solved set already prefetched ({} problems)", game_id, set.len());
+                    tracing::info!(target: "game", "init_game_from_cf {:?}: P2 solved set already prefetched ({} problems)", game_id, set.len());
                     set
                 }
                 None => {
-                    tracing::info!("init_game_from_cf {:?}: P2 solved set not ready, fetching now", game_id);
-                    fetch_solved_with_retry(&state, &p2_handle).await
+                    tracing::info!(target: "game", "init_game_from_cf {:?}: P2 solved set not ready, fetching now", game_id);
+                    fetch_solved_with_retry(judge.as_ref(), &p2_handle).await
                 }
             };
 
@@ -1301,84 +2196,161 @@ async fn init_game_from_cf(
         }
         Err(_) => {
             // 5-minute timeout — CF has been unreachable the entire time.
-            tracing::error!("init_game_from_cf: 5-min timeout fetching solved sets for game {:?}", game_id);
-            let mut games = state.games.write().await;
-            if let Some(game) = games.get_mut(&game_id) {
-                if game.status == crate::state::GameStatus::Initializing {
-                    game.status = crate::state::GameStatus::Finished;
-                    game.finished_at = Some(std::time::Instant::now());
-                    let go_msg = crate::game::build_game_over(game, None, "CFUnavailable".to_string());
-                    game.game_over_msg = Some(go_msg.clone());
-                    let _ = game.tx.send(crate::state::GameEvent::Message(go_msg));
-                }
-            }
+            crate::reporting::report_error(
+                Some(game_id),
+                format!("init_game_from_cf: 5-min timeout fetching solved sets for game {:?}", game_id),
+            );
+            state
+                .with_game(game_id, |game| {
+                    if game.status == crate::state::GameStatus::Initializing {
+                        game.finish(None, "CFUnavailable");
+                    }
+                })
+                .await;
             return;
         }
     };
 
-    // Re-acquire the lock and store results
-    let mut games = state.games.write().await;
-    let game = match games.get_mut(&game_id) {
-        Some(g) => g,
-        None => {
-            tracing::warn!("init_game_from_cf: game {:?} vanished", game_id);
-            return;
-        }
-    };
+    // Hand the results to the game's actor
+    let st = state.clone();
+    let stored = state
+        .with_game(game_id, move |game| {
+            let state = &st;
+            // Guard: game was cleaned up or finished while we were fetching
+            if game.status != crate::state::GameStatus::Initializing {
+                tracing::info!(
+                    target: "game",
+                    "init_game_from_cf: game {:?} no longer Initializing (status={:?}), aborting",
+                    game_id, game.status
+                );
+                return;
+            }
 
-    // Guard: game was cleaned up or finished while we were fetching
-    if game.status != crate::state::GameStatus::Initializing {
-        tracing::info!(
-            "init_game_from_cf: game {:?} no longer Initializing (status={:?}), aborting",
-            game_id, game.status
-        );
-        return;
+            game.player1.solved_set = p1_set;
+            game.player1.solved_set_ready = true;
+            if let Some(ref mut p2) = game.player2 {
+                p2.solved_set = p2_set;
+                p2.solved_set_ready = true;
+            }
+            tracing::info!(
+                target: "game",
+                "Game {:?}: fetched solved sets (P1: {}, P2: {})",
+                game_id,
+                game.player1.solved_set.len(),
+                game.player2.as_ref().map(|p| p.solved_set.len()).unwrap_or(0),
+            );
+
+            // Build shared problem queue from union of both solved sets
+            rebuild_problem_queue(game, state);
+            tracing::info!(
+                target: "game",
+                "Game {:?}: built shared queue with {} problems",
+                game_id, game.problem_queue.len(),
+            );
+
+            // CF data fetched and queue built — combat starts once both players are ready.
+            game.problem_queue_ready = true;
+            begin_countdown_if_ready(game, state);
+        })
+        .await;
+    if stored.is_none() {
+        tracing::warn!(target: "game", "init_game_from_cf: game {:?} vanished", game_id);
     }
+}
 
-    game.player1.solved_set = p1_set;
-    game.player1.solved_set_ready = true;
-    if let Some(ref mut p2) = game.player2 {
-        p2.solved_set = p2_set;
-        p2.solved_set_ready = true;
+/// Fetch the solved sets and build the problem queue for a game that just
+/// entered Initializing. Spawned so the CF fetch doesn't block this WS
+/// connection's message loop (ticks keep flowing).
+fn spawn_init(game: &crate::state::Game, state: &AppState) {
+    let p1_handle = game.player1.cf_handle.clone();
+    let p2_handle = game.player2.as_ref().map(|p| p.cf_handle.clone()).unwrap_or_default();
+    let state2 = state.clone();
+    let judge = state.judges.for_game(&game.config);
+    let gid = game.id;
+    tokio::spawn(
+        crate::reporting::in_task("init_game", Some(gid), async move {
+            init_game_from_cf(state2, judge, gid, p1_handle, p2_handle).await;
+        })
+        .in_current_span(),
+    );
+}
+
+/// Seconds of `Countdown` messages broadcast before combat begins.
+const COUNTDOWN_SECS: u32 = 5;
+
+/// Move an Initializing game into Countdown once the problem queue is built
+/// and both players have readied up. Safe to call from either trigger.
+/// Scheduled matches ignore Ready and wait for their countdown instead
+/// (both players have placed their ships by Initializing).
+fn begin_countdown_if_ready(game: &mut crate::state::Game, state: &AppState) {
+    let ready = if game.config.scheduled_start.is_some() {
+        game.schedule_reached
+    } else {
+        game.player1.ready && game.player2.as_ref().is_some_and(|p| p.ready)
+    };
+    if game.status != GameStatus::Initializing || !game.problem_queue_ready || !ready {
+        return;
     }
-    tracing::info!(
-        "Game {:?}: fetched solved sets (P1: {}, P2: {})",
-        game_id,
-        game.player1.solved_set.len(),
-        game.player2.as_ref().map(|p| p.solved_set.len()).unwrap_or(0),
+    game.status = GameStatus::Countdown;
+    let state2 = state.clone();
+    let gid = game.id;
+    let tx = game.tx.clone();
+    tokio::spawn(
+        crate::reporting::in_task("countdown", Some(gid), async move {
+            run_countdown(state2, gid, tx).await;
+        })
+        .in_current_span(),
     );
+}
 
-    // Build shared problem queue from union of both solved sets
-    {
-        let empty_set = std::collections::HashSet::new();
-        let p2_solved = game.player2.as_ref()
-            .map(|p| &p.solved_set)
-            .unwrap_or(&empty_set);
-        let queue = state.cf_client.build_shared_queue(
-            game.config.difficulty,
-            &game.config.difficulty_mode,
-            &game.player1.solved_set,
-            p2_solved,
-            50,
-        );
-        game.problem_queue = queue.into_iter().map(|p| crate::state::AssignedProblem {
-            contest_id: p.contest_id,
-            index: p.index,
-            name: p.name,
-            rating: p.rating,
-        }).collect();
-        tracing::info!(
-            "Game {:?}: built shared queue with {} problems",
-            game_id, game.problem_queue.len(),
-        );
+/// For a scheduled match: wake up `COUNTDOWN_SECS` before `start` so combat
+/// begins at the scheduled instant. A game that isn't ready by then (ships
+/// still being placed, CF data still loading) starts as soon as it is.
+pub async fn start_when_scheduled(state: AppState, game_id: uuid::Uuid, start: std::time::Instant) {
+    let countdown_at = start
+        .checked_sub(std::time::Duration::from_secs(COUNTDOWN_SECS as u64))
+        .unwrap_or(start);
+    tokio::time::sleep_until(countdown_at.into()).await;
+    let st = state.clone();
+    state
+        .with_game(game_id, move |game| {
+            game.schedule_reached = true;
+            tracing::info!(target: "game", "Scheduled start reached for game {:?} ({:?})", game_id, game.status);
+            begin_countdown_if_ready(game, &st);
+        })
+        .await;
+}
+
+/// Broadcast the pre-combat countdown, then switch the game to Playing.
+async fn run_countdown(
+    state: AppState,
+    game_id: uuid::Uuid,
+    tx: crate::events::GameTx,
+) {
+    for seconds_remaining in (1..=COUNTDOWN_SECS).rev() {
+        let _ = tx.send(crate::state::GameEvent::Message(ServerMessage::Countdown {
+            seconds_remaining,
+        }));
+        tokio::time::sleep(std::time::Duration::from_secs(1)).await;
     }
 
-    // CF data fetched and queue built — NOW start the game.
-    game.status = crate::state::GameStatus::Playing;
+    state.with_game(game_id, start_combat).await;
+}
+
+/// End of the countdown: start the clock and tell both players.
+pub(crate) fn start_combat(game: &mut crate::state::Game) {
+    // Guard: game may have been cancelled/finished during the countdown
+    if game.status != GameStatus::Countdown {
+        return;
+    }
+    game.status = GameStatus::Playing;
     game.game_started_at = Some(std::time::Instant::now());
 
     // Broadcast GameStart to both players
     let _ = game.tx.send(crate::state::GameEvent::Message(ServerMessage::GameStart));
+    if game.config.reveal_problems {
+        let _ = game.tx.send(crate::state::GameEvent::Message(problem_set_message(game)));
+    }
 }
 
 /// Pre-fetch a player's solved set in the background.
@@ -1387,19 +2359,21 @@ async fn init_game_from_cf(
 /// 4-minute timeout — leaves headroom for init_game_from_cf's 5-min timeout.
 async fn prefetch_solved_set(
     state: AppState,
+    judge: Arc<dyn JudgeClient>,
     game_id: uuid::Uuid,
     player_id: uuid::Uuid,
     handle: String,
 ) {
     let result = tokio::time::timeout(
         std::time::Duration::from_secs(240),
-        fetch_solved_with_retry(&state, &handle),
+        fetch_solved_with_retry(judge.as_ref(), &handle),
     ).await;
 
     let set = match result {
         Ok(set) => set,
         Err(_) => {
             tracing::warn!(
+                target: "game",
                 "prefetch_solved_set timed out for '{}' in game {:?}",
                 handle, game_id
             );
@@ -1407,40 +2381,44 @@ async fn prefetch_solved_set(
         }
     };
 
-    let mut games = state.games.write().await;
-    if let Some(game) = games.get_mut(&game_id) {
-        if game.player1.id == player_id {
-            game.player1.solved_set = set;
-            game.player1.solved_set_ready = true;
-            tracing::info!(
-                "Prefetched P1 ({}) solved set for game {:?}: {} problems",
-                handle, game_id, game.player1.solved_set.len()
-            );
-        } else if let Some(ref mut p2) = game.player2 {
-            if p2.id == player_id {
-                p2.solved_set = set;
-                p2.solved_set_ready = true;
+    state
+        .with_game(game_id, move |game| {
+            if game.player1.id == player_id {
+                game.player1.solved_set = set;
+                game.player1.solved_set_ready = true;
                 tracing::info!(
-                    "Prefetched P2 ({}) solved set for game {:?}: {} problems",
-                    handle, game_id, p2.solved_set.len()
+                    target: "game",
+                    "Prefetched P1 ({}) solved set for game {:?}: {} problems",
+                    handle, game_id, game.player1.solved_set.len()
                 );
+            } else if let Some(ref mut p2) = game.player2 {
+                if p2.id == player_id {
+                    p2.solved_set = set;
+                    p2.solved_set_ready = true;
+                    tracing::info!(
+                        target: "game",
+                        "Prefetched P2 ({}) solved set for game {:?}: {} problems",
+                        handle, game_id, p2.solved_set.len()
+                    );
+                }
             }
-        }
-    }
+        })
+        .await;
 }
 
-/// Fetch a player's solved set via the CF queue with infinite retry.
+/// Fetch a player's solved set from the game's judge with infinite retry.
 /// Retries with exponential backoff (capped at 8s) until success.
 /// The placement timeout (10 min) is the ultimate safety net.
-async fn fetch_solved_with_retry(state: &AppState, handle: &str) -> std::collections::HashSet<String> {
+async fn fetch_solved_with_retry(judge: &dyn JudgeClient, handle: &str) -> std::collections::HashSet<String> {
     let mut attempt: u32 = 0;
     loop {
-        match state.cf_queue.fetch_solved_set(handle).await {
+        match judge.solved_set(handle).await {
             Ok(set) => return set,
             Err(e) => {
                 attempt += 1;
                 let backoff = std::time::Duration::from_secs(2u64.saturating_pow(attempt).min(8));
                 tracing::warn!(
+                    target: "game",
                     "fetch_solved_with_retry('{}') attempt {} failed: {} — retrying in {:?}",
                     handle, attempt, e, backoff
                 );
@@ -1450,110 +2428,202 @@ async fn fetch_solved_with_retry(state: &AppState, handle: &str) -> std::collect
     }
 }
 
-/// Verify a submission via the CF queue and update game state.
+/// Which of a player's assigned problems a `SolveCP` is for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SolveFor {
+    /// The lock problem: unlocks weapons
+    Unlock,
+    /// The bonus problem: earns a shield
+    Bonus,
+}
+
+/// Verify a submission with the game's judge and update game state.
 ///
 /// Runs as a `tokio::spawn`-ed task so the WS loop stays responsive.
-/// Broadcasts VerifyResult on failure, WeaponsUnlocked on success.
+/// Broadcasts VerifyResult on failure; on success WeaponsUnlocked, or
+/// ShieldEarned for a bonus problem.
+#[allow(clippy::too_many_arguments)]
 async fn verify_and_unlock(
     state: AppState,
+    judge: Arc<dyn JudgeClient>,
     game_id: uuid::Uuid,
     pid: uuid::Uuid,
     handle: String,
-    contest_id: i32,
-    problem_index: String,
+    problem: crate::state::AssignedProblem,
     locked_at: Option<u64>,
+    solving: SolveFor,
 ) {
-    // Route through the global CF queue (high priority) with transparent retry.
-    // Up to 3 attempts with 3s backoff between retries — absorbs transient CF
-    // blips so the player just sees a spinner instead of an error + 10s wait.
-    let mut result = Err("no attempt made".to_string());
-    for attempt in 0..3u32 {
-        result = state.cf_queue.verify_submission(
-            &handle, contest_id, &problem_index, locked_at,
-        ).await;
+    // Codeforces goes through the global CF queue (high priority); retry
+    // transparently. Up to 3 attempts with 3s backoff between retries — absorbs
+    // transient blips so the player just sees a spinner instead of an error + 10s wait.
+    let mut attempt: u32 = 1;
+    let result = loop {
+        let result = judge.verify(&handle, &problem, locked_at).await;
         match &result {
-            Ok(_) => break,           // Got a definite answer (true or false)
-            Err(e) => {
-                if attempt < 2 {
-                    tracing::warn!(
-                        "verify_and_unlock: attempt {} failed for {:?}: {} — retrying",
-                        attempt + 1, pid, e
-                    );
-                    tokio::time::sleep(std::time::Duration::from_secs(3)).await;
-                }
+            Err(e) if attempt < 3 => {
+                tracing::warn!(
+                    target: "game",
+                    "verify_and_unlock: attempt {} failed for {:?}: {} — retrying",
+                    attempt, pid, e
+                );
+                attempt += 1;
+                tokio::time::sleep(std::time::Duration::from_secs(3)).await;
             }
+            // A definite answer (true or false), or out of retries
+            _ => break result,
         }
-    }
+    };
 
+    use crate::cf_client::Verdict;
     match result {
-        Ok(true) => {
-            // Re-acquire write lock to update state
-            let mut games = state.games.write().await;
-            let game = match games.get_mut(&game_id) {
-                Some(g) => g,
-                None => return,
-            };
-
-            // Guard: game may have ended while the CF API call was in-flight
-            if game.status == crate::state::GameStatus::Finished {
-                return;
-            }
-
-            let player = if game.player1.id == pid {
-                &mut game.player1
-            } else if let Some(ref mut p) = game.player2 {
-                p
-            } else {
-                return;
-            };
-
-            // Guard: player may have been unlocked by veto expiry racing with this
-            if !player.is_locked {
-                return;
-            }
-
-            // Add to solved_set so it's never re-assigned this game
-            if let Some(ref ap) = player.active_problem {
-                let key = format!("{}-{}", ap.contest_id, ap.index);
-                player.solved_set.insert(key);
-            }
-
-            player.unlock_weapons();
-            player.stats.problems_solved += 1;
-
-            // Broadcast WeaponsUnlocked
-            let _ = game.tx.send(crate::state::GameEvent::Message(
-                ServerMessage::WeaponsUnlocked {
-                    player_id: pid,
-                    reason: "solved".to_string(),
-                },
-            ));
+        Ok(Verdict::Accepted) => {
+            // Hand the unlock (or shield) to the game's actor
+            state
+                .with_game(game_id, move |game| match solving {
+                    SolveFor::Unlock => apply_verified_solve(game, pid),
+                    SolveFor::Bonus => apply_bonus_solve(game, pid),
+                })
+                .await;
         }
-        Ok(false) => {
-            // Not accepted — broadcast result so frontend shows feedback
-            let games = state.games.read().await;
-            if let Some(game) = games.get(&game_id) {
-                let _ = game.tx.send(crate::state::GameEvent::Message(
-                    ServerMessage::VerifyResult {
-                        player_id: pid,
-                        accepted: false,
-                        message: "Submission not accepted yet. Solve it on Codeforces first!".to_string(),
-                    },
-                ));
-            }
+        Ok(verdict) => {
+            // Not accepted — back off the next attempt and tell the player
+            let judge_name = judge.name().to_string();
+            state
+                .with_game(game_id, move |game| {
+                    let relief = game.config.heat_relief_secs.map(std::time::Duration::from_secs);
+                    let Some(player) = game.player_mut(pid) else {
+                        return;
+                    };
+                    player.failed_verifications += 1;
+                    // A genuine attempt on the lock problem earns heat relief, if the lobby allows it
+                    let relieving = match relief {
+                        Some(interval) if verdict == Verdict::Attempted && solving == SolveFor::Unlock => {
+                            player.note_attempt(interval)
+                        }
+                        _ => false,
+                    };
+                    game.share_weapons(pid);
+                    let message = if relieving {
+                        "Not accepted yet, but your attempt counts: heat relief is on its way.".to_string()
+                    } else {
+                        format!("Submission not accepted yet. Solve it on {} first!", judge_name)
+                    };
+                    let _ = game.tx.send(crate::state::GameEvent::Message(
+                        ServerMessage::VerifyResult {
+                            player_id: pid,
+                            accepted: false,
+                            message,
+                        },
+                    ));
+                })
+                .await;
         }
         Err(e) => {
-            // CF API error — broadcast so player knows to retry
-            let games = state.games.read().await;
-            if let Some(game) = games.get(&game_id) {
+            // Judge error — broadcast so player knows to retry
+            crate::reporting::report_error(
+                Some(game_id),
+                format!("{} verification failed after {} attempts for {:?}: {}", judge.id(), attempt, pid, e),
+            );
+            if let Some(game) = state.game(game_id) {
                 let _ = game.tx.send(crate::state::GameEvent::Message(
                     ServerMessage::VerifyResult {
                         player_id: pid,
                         accepted: false,
-                        message: format!("Codeforces API error: {}. Please retry.", e),
+                        message: format!("{} error: {}. Please retry.", judge.name(), e),
                     },
                 ));
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// Codeforces accepted `pid`'s bonus problem: they earn a shield to place.
+/// Also used by `sim.rs`.
+pub(crate) fn apply_bonus_solve(game: &mut crate::state::Game, pid: Uuid) {
+    if game.status == crate::state::GameStatus::Finished {
+        return;
+    }
+    let Some(player) = game.player_mut(pid) else {
+        return;
+    };
+    let Some(ap) = player.bonus_problem.take() else {
+        return;
+    };
+    player.solved_set.insert(format!("{}-{}", ap.contest_id, ap.index));
+    player.bonus_assigned_at_unix = None;
+    player.last_verification_attempt = None;
+    player.failed_verifications = 0;
+    player.shields_available += 1;
+    player.stats.problems_solved += 1;
+
+    let _ = game.tx.send(crate::state::GameEvent::Message(
+        ServerMessage::ShieldEarned { player_id: pid },
+    ));
+}
+
+/// Codeforces accepted `pid`'s assigned problem: unlock their weapons.
+/// Also used by `sim.rs`, which stands in for Codeforces.
+pub(crate) fn apply_verified_solve(game: &mut crate::state::Game, pid: Uuid) {
+    // Guard: game may have ended while the CF API call was in-flight
+    if game.status == crate::state::GameStatus::Finished {
+        return;
+    }
+    let game_id = game.id;
+
+    let player = if game.player1.id == pid {
+        &mut game.player1
+    } else if let Some(ref mut p) = game.player2 {
+        p
+    } else {
+        return;
+    };
+
+    // Guard: player may have been unlocked by veto expiry racing with this
+    if !player.is_locked {
+        return;
+    }
+
+    game.audit_log.record_solve(game_id, player);
+
+    // Add to solved_set so it's never re-assigned this game
+    if let Some(ref ap) = player.active_problem {
+        let key = format!("{}-{}", ap.contest_id, ap.index);
+        player.solved_set.insert(key);
+    }
+
+    // Fast solves earn a cross shot, timed from the lock
+    let now_unix = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let granted = match (game.config.fast_solve_secs, player.locked_at_unix) {
+        (Some(limit), Some(locked_at)) if now_unix.saturating_sub(locked_at) < limit => {
+            player.inventory.push(SpecialWeapon::Cross);
+            Some(SpecialWeapon::Cross)
+        }
+        _ => None,
+    };
+
+    player.unlock_weapons();
+    if let Some(ammo) = game.config.ammo {
+        player.reload(ammo.per_solve);
+    }
+    player.failed_verifications = 0;
+    player.stats.problems_solved += 1;
+    game.lock_closed(pid, crate::state::LockOutcome::Solved);
+
+    // Broadcast WeaponsUnlocked
+    let _ = game.tx.send(crate::state::GameEvent::Message(
+        ServerMessage::WeaponsUnlocked {
+            player_id: pid,
+            reason: "solved".to_string(),
+        },
+    ));
+    if let Some(weapon) = granted {
+        let _ = game.tx.send(crate::state::GameEvent::Message(
+            ServerMessage::WeaponGranted { player_id: pid, weapon },
+        ));
+    }
+    // Either co-op player's solve unlocks the team
+    game.share_weapons(pid);
+}
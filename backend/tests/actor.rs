@@ -0,0 +1,135 @@
+use backend::state::{AppState, Game, GameConfig, GameStatus};
+use tokio::time::{timeout, Duration};
+use uuid::Uuid;
+
+/// Commands run in order on the game's actor; removing the game stops the
+/// actor and closes its broadcast channel.
+#[tokio::test]
+async fn test_actor_lifecycle() {
+    let state = AppState::new();
+    let game = Game::new(Uuid::new_v4(), "host".to_string(), GameConfig::default());
+    let game_id = game.id;
+    let handle = state.insert_game(game);
+    let mut rx = handle.tx.subscribe();
+
+    // Status is published before call() returns
+    state
+        .with_game(game_id, |g| g.status = GameStatus::PlacingShips)
+        .await
+        .unwrap();
+    assert_eq!(handle.status(), GameStatus::PlacingShips);
+
+    // Lifecycle timestamps ride along in the summary (read by the ticker's cleanup)
+    state
+        .with_game(game_id, |g| g.finished_at = Some(std::time::Instant::now()))
+        .await
+        .unwrap();
+    assert!(handle.summary().finished_at.is_some());
+
+    // Casts are applied before later calls
+    for _ in 0..10 {
+        handle.cast(|g| g.player1.heat += 1).await;
+    }
+    assert_eq!(handle.call(|g| g.player1.heat).await, Some(10));
+
+    drop(handle);
+    state.remove_game(game_id).unwrap();
+    assert!(state.with_game(game_id, |_| ()).await.is_none());
+
+    // Actor exits → last Sender dropped → subscribers see Closed
+    loop {
+        match timeout(Duration::from_secs(2), rx.recv()).await.expect("channel never closed") {
+            Ok(_) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Veto expiry fires from the actor's own deadline, not a 1s poll.
+#[tokio::test]
+async fn test_veto_expiry_fires_on_deadline() {
+    let state = AppState::new();
+    let config = GameConfig {
+        veto_penalties: vec![1, 1, 1],
+        ..GameConfig::default()
+    };
+    let mut game = Game::new(Uuid::new_v4(), "host".to_string(), config);
+    game.join(Uuid::new_v4(), "guest".to_string()).unwrap();
+    game.status = GameStatus::Playing;
+    game.game_started_at = Some(std::time::Instant::now());
+    game.player1.is_locked = true;
+    game.player1.vetoes_used = 1;
+    let p1_id = game.player1.id;
+    let mut rx = game.tx.subscribe();
+
+    let started = std::time::Instant::now();
+    game.player1.veto_started_at = Some(started);
+    state.insert_game(game);
+
+    // Empty problem queue → mercy unlock once the 1s penalty is over
+    loop {
+        match timeout(Duration::from_secs(3), rx.recv()).await.expect("veto never expired").map(|e| e.message) {
+            Ok(backend::protocol::ServerMessage::WeaponsUnlocked {
+                player_id,
+                reason,
+            }) => {
+                assert_eq!(player_id, p1_id);
+                assert_eq!(reason, "veto_expired");
+                break;
+            }
+            Ok(_) => continue,
+            Err(e) => panic!("broadcast error: {:?}", e),
+        }
+    }
+    let waited = started.elapsed();
+    assert!(waited >= Duration::from_secs(1), "fired early: {:?}", waited);
+    assert!(waited < Duration::from_millis(1500), "fired late: {:?}", waited);
+}
+
+/// Each actor publishes every player's pre-serialized update to their own view.
+#[tokio::test]
+async fn test_player_views() {
+    let state = AppState::new();
+    let mut game = Game::new(Uuid::new_v4(), "host".to_string(), GameConfig::default());
+    game.seat_guest(Uuid::new_v4(), "guest".to_string()).unwrap();
+    let mut views = [
+        game.player1.view.subscribe(),
+        game.player2.as_ref().unwrap().view.subscribe(),
+    ];
+    state.insert_game(game);
+
+    for view in &mut views {
+        timeout(Duration::from_secs(2), view.changed()).await.expect("no view").unwrap();
+        let frames = view.borrow_and_update().clone();
+        let update: serde_json::Value = serde_json::from_str(frames.last().expect("no frame")).unwrap();
+        assert_eq!(update["type"], "GameUpdate");
+        // Placement deadline (10 min from the guest joining) rides along
+        let left = update["phase_time_remaining_secs"].as_u64().expect("no phase deadline");
+        assert!((595..=600).contains(&left), "placement deadline {}", left);
+    }
+}
+
+/// Unchanged views are only re-published as a keepalive; a change goes out on the next tick.
+#[tokio::test]
+async fn test_views_only_on_change() {
+    let state = AppState::new();
+    let game = Game::new(Uuid::new_v4(), "host".to_string(), GameConfig::default());
+    let game_id = game.id;
+    let mut view = game.player1.view.subscribe();
+    state.insert_game(game);
+
+    let mut next_view = async |within: Duration| {
+        timeout(within, view.changed()).await.ok()?.ok()?;
+        Some(view.borrow_and_update().clone())
+    };
+
+    // First view goes out immediately, then nothing while the game is idle
+    assert!(next_view(Duration::from_secs(1)).await.is_some());
+    assert!(next_view(Duration::from_millis(2500)).await.is_none());
+
+    // Heat change → fresh view on the next 1s tick
+    state.with_game(game_id, |g| g.player1.heat = 3).await.unwrap();
+    let frames = next_view(Duration::from_millis(1500)).await.expect("change not sent");
+    assert!(frames.last().unwrap().contains("\"heat\":3"));
+}
@@ -0,0 +1,179 @@
+use backend::protocol::{AdminMessage, ServerMessage};
+use backend::state::{AppState, Game, GameConfig, GameEvent};
+use futures::StreamExt;
+use tokio::time::{sleep, timeout, Duration};
+use tokio_tungstenite::connect_async;
+use uuid::Uuid;
+
+async fn next_admin_msg<S>(read: &mut S) -> AdminMessage
+where
+    S: futures::Stream<Item = Result<tokio_tungstenite::tungstenite::Message, tokio_tungstenite::tungstenite::Error>>
+        + Unpin,
+{
+    let msg = timeout(Duration::from_secs(2), read.next())
+        .await
+        .expect("timed out")
+        .expect("closed")
+        .unwrap();
+    serde_json::from_str(msg.to_text().unwrap()).unwrap()
+}
+
+/// Admin feed rejects bad tokens, then streams a snapshot and tagged game events.
+#[tokio::test]
+async fn test_admin_observation_feed() {
+    std::env::set_var("ADMIN_TOKEN", "secret");
+
+    let app_state = AppState::new();
+    let app = axum::Router::new()
+        .route("/api/admin/ws", axum::routing::get(backend::admin::admin_ws_handler))
+        .with_state(app_state.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    sleep(Duration::from_millis(100)).await;
+
+    // Wrong token is refused at upgrade time
+    let bad = connect_async(format!("ws://{}/api/admin/ws?token=nope", addr)).await;
+    assert!(bad.is_err(), "bad token should not upgrade");
+
+    let existing = Game::new(Uuid::new_v4(), "host".to_string(), GameConfig::default());
+    app_state.insert_game(existing);
+
+    let (ws, _) = connect_async(format!("ws://{}/api/admin/ws?token=secret", addr))
+        .await
+        .expect("Failed to connect");
+    let (_write, mut read) = ws.split();
+
+    match next_admin_msg(&mut read).await {
+        AdminMessage::Snapshot { games } => assert_eq!(games.len(), 1),
+        other => panic!("Expected Snapshot, got {:?}", other),
+    }
+
+    // A new game's broadcasts show up tagged with its id
+    let game = Game::new(Uuid::new_v4(), "second".to_string(), GameConfig::default());
+    let game_id = game.id;
+    let tx = game.tx.clone();
+    app_state.insert_game(game);
+    let _ = tx.send(GameEvent::Message(ServerMessage::GameStart));
+
+    match next_admin_msg(&mut read).await {
+        AdminMessage::GameEvent { game_id: gid, event: ServerMessage::GameStart, .. } => {
+            assert_eq!(gid, game_id)
+        }
+        other => panic!("Expected tagged GameStart, got {:?}", other),
+    }
+}
+
+/// Keys are scoped: read keys observe, only manage keys may end games.
+#[tokio::test]
+async fn test_admin_key_scopes() {
+    use axum::extract::{Path, Query, State};
+    use axum::http::{header, HeaderMap, StatusCode};
+    use backend::admin::{self, AdminKeys, AdminQuery, Scope};
+
+    let keys = AdminKeys::new([("observer-key", Scope::Read), ("organizer-key", Scope::Manage)]);
+    assert_eq!(keys.scope_of("observer-key"), Some(Scope::Read));
+    assert_eq!(keys.scope_of("organizer-key"), Some(Scope::Manage));
+    assert_eq!(keys.scope_of("organizer-ke"), None);
+    assert_eq!(keys.scope_of(""), None);
+    assert_eq!(AdminKeys::new([("", Scope::Manage)]).scope_of(""), None);
+
+    let mut app_state = AppState::new();
+    app_state.admin_keys = std::sync::Arc::new(keys);
+    let game = Game::new(Uuid::new_v4(), "host".to_string(), GameConfig::default());
+    let game_id = game.id;
+    let mut rx = game.tx.subscribe();
+    let handle = app_state.insert_game(game);
+
+    let terminate = |bearer: Option<&str>| {
+        let mut headers = HeaderMap::new();
+        if let Some(key) = bearer {
+            headers.insert(header::AUTHORIZATION, format!("Bearer {}", key).parse().unwrap());
+        }
+        admin::terminate_game(
+            State(app_state.clone()),
+            Path(game_id),
+            Query(AdminQuery { token: None }),
+            headers,
+        )
+    };
+    assert_eq!(terminate(None).await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(terminate(Some("guess")).await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(terminate(Some("observer-key")).await.status(), StatusCode::FORBIDDEN);
+    assert_eq!(handle.status(), backend::state::GameStatus::Waiting);
+
+    assert_eq!(terminate(Some("organizer-key")).await.status(), StatusCode::OK);
+    match rx.recv().await.map(|e| e.message) {
+        Ok(ServerMessage::GameOver { reason, winner_id, .. }) => {
+            assert_eq!(reason, "AdminTerminated");
+            assert_eq!(winner_id, None);
+        }
+        other => panic!("Expected GameOver, got {:?}", other),
+    }
+    assert_eq!(terminate(Some("organizer-key")).await.status(), StatusCode::CONFLICT);
+}
+
+/// Organizers can unlock a player a glitch left locked, fix their heat, or
+/// cancel their veto, and each change lands in the audit log.
+#[tokio::test]
+async fn test_admin_player_adjustments() {
+    use axum::extract::{Path, Query, State};
+    use axum::http::{header, HeaderMap, StatusCode};
+    use axum::Json;
+    use backend::admin::{self, AdminKeys, AdminQuery, PlayerAdjustment, Scope};
+    use backend::anticheat::Anomaly;
+
+    let mut app_state = AppState::new();
+    app_state.admin_keys = std::sync::Arc::new(AdminKeys::new([("organizer-key", Scope::Manage)]));
+    let mut game = Game::new(Uuid::new_v4(), "host".to_string(), GameConfig::default());
+    let (game_id, pid) = (game.id, game.player1.id);
+    game.player1.is_locked = true;
+    game.player1.heat = 7;
+    game.player1.vetoes_used = 1;
+    game.player1.veto_started_at = Some(std::time::Instant::now());
+    let mut rx = game.tx.subscribe();
+    let handle = app_state.insert_game(game);
+
+    let adjust = |player_id: Uuid, adjustment: PlayerAdjustment| {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, "Bearer organizer-key".parse().unwrap());
+        admin::adjust_player(
+            State(app_state.clone()),
+            Path((game_id, player_id)),
+            Query(AdminQuery { token: None }),
+            headers,
+            Json(adjustment),
+        )
+    };
+    assert_eq!(adjust(Uuid::new_v4(), PlayerAdjustment::Unlock).await.status(), StatusCode::NOT_FOUND);
+    assert_eq!(
+        adjust(pid, PlayerAdjustment::SetHeat { heat: 7 }).await.status(),
+        StatusCode::BAD_REQUEST
+    );
+
+    assert_eq!(adjust(pid, PlayerAdjustment::CancelVeto).await.status(), StatusCode::OK);
+    match rx.recv().await.map(|e| e.message) {
+        Ok(ServerMessage::WeaponsUnlocked { player_id, reason }) => {
+            assert_eq!((player_id, reason.as_str()), (pid, "admin"));
+        }
+        other => panic!("Expected WeaponsUnlocked, got {:?}", other),
+    }
+    let (locked, heat, vetoes_used) = handle
+        .call(|g| (g.player1.is_locked, g.player1.heat, g.player1.vetoes_used))
+        .await
+        .unwrap();
+    assert_eq!((locked, heat, vetoes_used), (false, 0, 0));
+    assert_eq!(adjust(pid, PlayerAdjustment::CancelVeto).await.status(), StatusCode::CONFLICT);
+    assert_eq!(adjust(pid, PlayerAdjustment::Unlock).await.status(), StatusCode::CONFLICT);
+
+    assert_eq!(adjust(pid, PlayerAdjustment::SetHeat { heat: 3 }).await.status(), StatusCode::OK);
+    let (heat, audit) = handle
+        .call(|g| (g.player1.heat, g.audit_log.entries().to_vec()))
+        .await
+        .unwrap();
+    assert_eq!(heat, 3);
+    assert_eq!(audit.len(), 2);
+    assert!(audit.iter().all(|e| e.player_id == pid && e.anomaly == Anomaly::AdminAction));
+}
@@ -0,0 +1,80 @@
+use backend::anticheat::{Anomaly, AuditLog};
+use backend::state::{AssignedProblem, Player};
+use uuid::Uuid;
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn anomalies(log: &AuditLog) -> Vec<Anomaly> {
+    log.entries().iter().map(|e| e.anomaly).collect()
+}
+
+/// Five shots inside a second is faster than one socket may fire.
+#[test]
+fn test_flags_impossible_cadence_once() {
+    let mut log = AuditLog::default();
+    let game_id = Uuid::new_v4();
+    let mut bot = Player::new(Uuid::new_v4(), "bot".to_string());
+    bot.stats.cells_missed = 1;
+
+    for _ in 0..4 {
+        log.record_shot(game_id, &bot);
+    }
+    assert!(log.entries().is_empty(), "four shots are not a pattern yet");
+    for _ in 0..10 {
+        log.record_shot(game_id, &bot);
+    }
+    assert_eq!(anomalies(&log), vec![Anomaly::ShotCadence], "flagged once per kind");
+    assert_eq!(log.entries()[0].player_id, bot.id);
+    assert_eq!(log.entries()[0].cf_handle, "bot");
+}
+
+#[test]
+fn test_flags_perfect_accuracy() {
+    let mut log = AuditLog::default();
+    let game_id = Uuid::new_v4();
+    let mut p = Player::new(Uuid::new_v4(), "sharpshooter".to_string());
+
+    p.stats.cells_hit = 11;
+    log.record_shot(game_id, &p);
+    assert!(log.entries().is_empty());
+
+    p.stats.cells_hit = 12;
+    log.record_shot(game_id, &p);
+    assert_eq!(anomalies(&log), vec![Anomaly::PerfectAccuracy]);
+
+    // One miss anywhere means no flag
+    let mut log = AuditLog::default();
+    p.stats.cells_hit = 16;
+    p.stats.cells_missed = 1;
+    log.record_shot(game_id, &p);
+    assert!(log.entries().is_empty());
+}
+
+#[test]
+fn test_flags_fast_solve() {
+    let mut log = AuditLog::default();
+    let game_id = Uuid::new_v4();
+    let mut p = Player::new(Uuid::new_v4(), "speedy".to_string());
+    p.is_locked = true;
+    p.active_problem = Some(AssignedProblem {
+        contest_id: 1950,
+        index: "C".to_string(),
+        name: "Clock Conversion".to_string(),
+        rating: 800,
+        url: None,
+    });
+
+    p.locked_at_unix = Some(unix_now() - 600);
+    log.record_solve(game_id, &p);
+    assert!(log.entries().is_empty(), "ten minutes is a normal solve");
+
+    p.locked_at_unix = Some(unix_now() - 5);
+    log.record_solve(game_id, &p);
+    assert_eq!(anomalies(&log), vec![Anomaly::FastSolve]);
+    assert!(log.entries()[0].detail.contains("1950C"));
+}
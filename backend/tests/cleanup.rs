@@ -0,0 +1,130 @@
+mod common;
+
+use backend::protocol::ServerMessage;
+use backend::state::{AppState, Game, GameConfig, GameStatus};
+use common::TestServer;
+use futures::StreamExt;
+use tokio::time::{sleep, timeout, Duration};
+use tokio_tungstenite::tungstenite::Message;
+use uuid::Uuid;
+
+/// Expired games get a GameExpired broadcast, then are dropped from the registry.
+#[tokio::test]
+async fn test_expired_game_notifies_then_removes() {
+    let mut state = AppState::new();
+    state.cleanup_policy.abandoned = Duration::from_millis(100);
+    tokio::spawn(backend::background::start_global_ticker(state.clone()));
+
+    let mut game = Game::new(Uuid::new_v4(), "host".to_string(), GameConfig::default());
+    game.join(Uuid::new_v4(), "guest".to_string()).unwrap();
+    game.status = GameStatus::Abandoned;
+    game.abandoned_at = Some(std::time::Instant::now());
+    let game_id = game.id;
+    let mut rx = game.tx.subscribe();
+    state.insert_game(game);
+
+    let reason = loop {
+        match timeout(Duration::from_secs(3), rx.recv()).await.expect("never expired").map(|e| e.message) {
+            Ok(ServerMessage::GameExpired { reason }) => break reason,
+            Ok(_) => continue,
+            Err(e) => panic!("broadcast error: {:?}", e),
+        }
+    };
+    assert_eq!(reason, "Both players left");
+    assert!(!state.games.contains_key(&game_id));
+}
+
+/// Active games are kept no matter how old; idle ones follow the policy.
+#[tokio::test]
+async fn test_cleanup_policy_by_status() {
+    let state = AppState::new();
+    let policy = backend::background::CleanupPolicy {
+        waiting: Duration::ZERO,
+        ..Default::default()
+    };
+
+    let lobby = state.insert_game(Game::new(Uuid::new_v4(), "a".to_string(), GameConfig::default()));
+    assert_eq!(policy.expiry_reason(&lobby.summary()), Some("Lobby expired"));
+
+    let mut game = Game::new(Uuid::new_v4(), "b".to_string(), GameConfig::default());
+    game.status = GameStatus::Playing;
+    let playing = state.insert_game(game);
+    assert_eq!(policy.expiry_reason(&playing.summary()), None);
+}
+
+/// Finished games past their window are frozen into the archive and their
+/// actor stops: still readable over REST, and over WS as the final GameOver,
+/// until that window passes too.
+#[tokio::test]
+async fn test_finished_games_stay_readable_in_archive() {
+    let mut state = AppState::new();
+    state.cleanup_policy.finished = Duration::from_millis(100);
+    state.cleanup_policy.archived = Duration::from_secs(2);
+    tokio::spawn(backend::background::start_global_ticker(state.clone()));
+    let server = TestServer::with_state(state.clone()).await;
+
+    let mut game = Game::new(Uuid::new_v4(), "host".to_string(), GameConfig::default());
+    game.join(Uuid::new_v4(), "guest".to_string()).unwrap();
+    game.finish(Some(game.player1.id), "All ships sunk");
+    let game_id = game.id;
+    let mut rx = game.tx.subscribe();
+    state.insert_game(game);
+
+    timeout(Duration::from_secs(3), async {
+        while !state.archive.contains(game_id) {
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("never archived");
+    assert!(!state.games.contains_key(&game_id));
+    timeout(Duration::from_secs(3), async {
+        while rx.recv().await.is_ok() {}
+    })
+    .await
+    .expect("the actor kept running");
+
+    let url = format!("{}/api/game/{}", server.url, game_id);
+    assert_eq!(reqwest::get(&url).await.unwrap().status(), 200);
+    let events: serde_json::Value = reqwest::get(format!("{}/events", url)).await.unwrap().json().await.unwrap();
+    assert!(events["events"].as_array().unwrap().iter().any(|e| e["type"] == "GameOver"), "{}", events);
+    let ws_url = format!("ws://{}/ws/{}", server.addr(), game_id);
+    let (mut ws, _) = tokio_tungstenite::connect_async(ws_url).await.unwrap();
+    let Some(Ok(Message::Text(text))) = ws.next().await else {
+        panic!("expected the result first");
+    };
+    assert!(matches!(serde_json::from_str(&text).unwrap(), ServerMessage::GameOver { .. }));
+    let Some(Ok(Message::Close(Some(frame)))) = ws.next().await else {
+        panic!("expected a close frame");
+    };
+    assert_eq!(u16::from(frame.code), 4000);
+
+    timeout(Duration::from_secs(5), async {
+        while state.archive.contains(game_id) {
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("never dropped from the archive");
+    assert_eq!(reqwest::get(&url).await.unwrap().status(), 404);
+}
+
+/// The archive holds at most `archived_max_games`, dropping the oldest first.
+#[tokio::test]
+async fn test_archive_is_capped() {
+    let mut config = backend::config::Config::default();
+    config.cleanup.archived_max_games = 2;
+    let state = AppState::from_config(config);
+    let mut ids = vec![];
+    for _ in 0..3 {
+        let mut game = Game::new(Uuid::new_v4(), "host".to_string(), GameConfig::default());
+        game.join(Uuid::new_v4(), "guest".to_string()).unwrap();
+        game.finish(None, "Draw");
+        ids.push(game.id);
+        state.insert_game(game);
+        state.archive_game(ids[ids.len() - 1]).await;
+    }
+    assert!(state.games.is_empty());
+    assert_eq!(state.archive.len(), 2);
+    assert!(!state.archive.contains(ids[0]) && state.archive.contains(ids[1]) && state.archive.contains(ids[2]));
+}
@@ -0,0 +1,370 @@
+//! End-to-end test support: the full router on an ephemeral port, games set
+//! up in any phase, and clients with typed expectations.
+//!
+//! ```ignore
+//! let server = TestServer::start().await;
+//! let game = server.in_combat(GameConfig::default());
+//! let mut host = server.connect(game.host_seat()).await;
+//! host.fire(Coord { x: 0, y: 0 }).await.unwrap();
+//! let shot = host.expect_msg::<ShotResult>().await;
+//! ```
+
+// Each test file uses a different subset
+#![allow(dead_code)]
+
+use backend::actor::GameHandle;
+use backend::auth::Role;
+use backend::protocol::{ServerMessage, ShipPlacement};
+use backend::state::{AppState, AssignedProblem, Game, GameConfig, GameStatus};
+use battle_cp_client::{Client, ClientError, Seat};
+use serde::de::DeserializeOwned;
+use std::net::SocketAddr;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// How long `expect_msg` waits before failing the test.
+pub const EXPECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// The server's full router (every route and middleware) on 127.0.0.1.
+pub struct TestServer {
+    pub state: AppState,
+    /// `http://127.0.0.1:<port>`
+    pub url: String,
+}
+
+impl TestServer {
+    pub async fn start() -> Self {
+        Self::with_state(AppState::new()).await
+    }
+
+    pub async fn with_state(state: AppState) -> Self {
+        let app = backend::server::router(state.clone(), &Default::default());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+            .unwrap();
+        });
+        Self {
+            state,
+            url: format!("http://{}", addr),
+        }
+    }
+
+    /// `127.0.0.1:<port>`, for hand-built URLs.
+    pub fn addr(&self) -> &str {
+        self.url.trim_start_matches("http://")
+    }
+
+    /// Register a hand-built game.
+    pub fn insert(&self, game: Game) -> TestGame {
+        let (id, host, guest) = (
+            game.id,
+            game.player1.id,
+            game.player2.as_ref().map(|p| p.id),
+        );
+        let handle = self.state.insert_game(game);
+        TestGame {
+            id,
+            host,
+            guest,
+            handle,
+            state: self.state.clone(),
+        }
+    }
+
+    /// A lobby hosted by "Host", waiting for a guest.
+    pub fn lobby(&self, config: GameConfig) -> TestGame {
+        self.insert(Game::new(Uuid::new_v4(), "Host".to_string(), config))
+    }
+
+    /// "Host" and "Guest" both seated, placing ships. Their solved sets count
+    /// as fetched, so starting the game never calls Codeforces.
+    pub fn in_placement(&self, config: GameConfig) -> TestGame {
+        self.insert(seated(config))
+    }
+
+    /// Combat under way with `fleet()` on both boards, and placeholder
+    /// problems queued for whoever overheats.
+    pub fn in_combat(&self, config: GameConfig) -> TestGame {
+        let mut game = seated(config);
+        for player in std::iter::once(&mut game.player1).chain(game.player2.as_mut()) {
+            let (grid, ships) = battle_cp_core::place_fleet(&fleet()).unwrap();
+            player.home.grid = grid;
+            player.home.ships = ships;
+            player.ships_placed = true;
+            player.ready = true;
+        }
+        game.problem_queue = (1..=10)
+            .map(|contest_id| AssignedProblem {
+                contest_id,
+                index: "A".to_string(),
+                name: format!("Problem {}", contest_id),
+                rating: 800,
+                url: None,
+            })
+            .collect();
+        game.status = GameStatus::Playing;
+        game.problem_queue_ready = true;
+        game.game_started_at = Some(std::time::Instant::now());
+        self.insert(game)
+    }
+
+    /// Join `seat`; panics if the server refuses it.
+    pub async fn connect(&self, seat: Seat) -> TestClient {
+        self.try_connect(seat).await.expect("join refused")
+    }
+
+    pub async fn try_connect(&self, seat: Seat) -> Result<TestClient, ClientError> {
+        TestClient::join(&self.url, seat).await
+    }
+}
+
+fn seated(config: GameConfig) -> Game {
+    let mut game = Game::new(Uuid::new_v4(), "Host".to_string(), config);
+    game.seat_guest(Uuid::new_v4(), "Guest".to_string())
+        .unwrap();
+    game.player1.solved_set_ready = true;
+    if let Some(p2) = game.player2.as_mut() {
+        p2.solved_set_ready = true;
+    }
+    game
+}
+
+/// The standard fleet on rows 1, 3, 5, 7 and 9 (y = 0, 2, 4, 6, 8), bows in
+/// column A: sizes 5, 4, 3, 3, 2.
+pub fn fleet() -> Vec<ShipPlacement> {
+    battle_cp_core::FLEET
+        .iter()
+        .enumerate()
+        .map(|(i, &size)| ShipPlacement {
+            x: 0,
+            y: i * 2,
+            size,
+            vertical: false,
+        })
+        .collect()
+}
+
+/// A registered game, and seats for its players.
+pub struct TestGame {
+    pub id: Uuid,
+    pub host: Uuid,
+    pub guest: Option<Uuid>,
+    pub handle: GameHandle,
+    state: AppState,
+}
+
+impl TestGame {
+    /// The host's seat, with a session token.
+    pub fn host_seat(&self) -> Seat {
+        self.seat(self.host, "Host", Role::Host)
+    }
+
+    /// The seated guest's seat, with a session token.
+    pub fn guest_seat(&self) -> Seat {
+        self.seat(self.guest.expect("no guest seated"), "Guest", Role::Guest)
+    }
+
+    fn seat(&self, player_id: Uuid, cf_handle: &str, role: Role) -> Seat {
+        Seat {
+            game_id: self.id,
+            player_id,
+            cf_handle: cf_handle.to_string(),
+            token: Some(self.state.sessions.issue(player_id, self.id, role)),
+        }
+    }
+
+    /// Read or change the game on its actor.
+    pub async fn call<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut Game) -> R + Send + 'static,
+    ) -> R {
+        self.handle.call(f).await.expect("game removed")
+    }
+}
+
+/// A `ServerMessage` variant as its own type, for `expect_msg`. Only the
+/// declared fields are read, so a test names just the ones it checks.
+pub trait Expect: DeserializeOwned {
+    const TYPE: &'static str;
+}
+
+macro_rules! expectations {
+    ($($name:ident { $($field:ident: $ty:ty),* $(,)? })*) => {
+        $(
+            #[derive(Debug, serde::Deserialize)]
+            pub struct $name {
+                $(pub $field: $ty,)*
+            }
+
+            impl Expect for $name {
+                const TYPE: &'static str = stringify!($name);
+            }
+        )*
+    };
+}
+
+/// Typed views of the messages tests wait for.
+pub mod msg {
+    use super::Expect;
+    use backend::protocol::{
+        ActivityKind, Board, CellShot, DifficultyVote, HintCost, Marker, PlacementFault, RevealedProblem, RevealedShip,
+        ShipPlacement, SpecialWeapon,
+    };
+    use uuid::Uuid;
+
+    expectations! {
+        GameJoined {
+            game_id: Uuid,
+            player_id: Uuid,
+            max_heat: u32,
+            max_vetoes: u32,
+            veto_penalties: Vec<u64>,
+            hint_cost: HintCost,
+            coop: Option<bool>,
+            armada: Option<bool>,
+        }
+        PlayerJoined { player_id: Uuid }
+        SessionToken { token: String }
+        ResumeToken { token: String, window_secs: u64 }
+        Resumed { events: Vec<serde_json::Value> }
+        ShipsConfirmed { player_id: Uuid }
+        StagedShips { ships: Vec<ShipPlacement> }
+        YourShips { ships: Vec<ShipPlacement> }
+        Markers { markers: Vec<Marker> }
+        PlayerReady { player_id: Uuid }
+        Countdown { seconds_remaining: u32 }
+        GameStart {}
+        ProblemSet { problems: Vec<RevealedProblem> }
+        ShotResult {
+            x: usize,
+            y: usize,
+            hit: bool,
+            sunk: bool,
+            shooter_id: Uuid,
+            coord: String,
+            sunk_cells: Option<Vec<[usize; 2]>>,
+            blocked: Option<bool>,
+            board: Option<Board>,
+        }
+        SpecialShotResult { shooter_id: Uuid, weapon: SpecialWeapon, coord: String, shots: Vec<CellShot> }
+        WeaponsLocked { player_id: Uuid }
+        WeaponsUnlocked { player_id: Uuid, reason: String }
+        WeaponGranted { player_id: Uuid, weapon: SpecialWeapon }
+        ProblemAssigned { player_id: Uuid, contest_id: i32, problem_index: String, url: Option<String> }
+        BonusProblemAssigned { player_id: Uuid, contest_id: i32, problem_index: String }
+        ShieldPlaced { coord: String }
+        DifficultyVoteCast { player_id: Uuid, direction: DifficultyVote }
+        DifficultyChanged { difficulty: u32 }
+        VerifyPending { player_id: Uuid }
+        Presence { player_id: Uuid, connected: bool }
+        SpectatorCount { count: u32 }
+        Working { player_id: Uuid, working: bool }
+        IdleWarning { player_id: Uuid, forfeit_in_secs: u64 }
+        IdleCleared { player_id: Uuid }
+        ActivityEvent { kind: ActivityKind, player_id: Option<Uuid>, text: String }
+        Latency { player_id: Uuid, rtt_ms: u32 }
+        ClockPaused { message: String }
+        ClockResumed { paused_secs: u64 }
+        LobbyExpiring { in_secs: u64 }
+        LobbyExtended { in_secs: u64, extensions_left: u32 }
+        GameOver {
+            winner_id: Option<Uuid>,
+            reason: String,
+            p1_id: Uuid,
+            p1_cells_hit: u32,
+            p2_grid: Vec<Vec<String>>,
+            p2_ships: Vec<RevealedShip>,
+            coop_ships: Option<Vec<RevealedShip>>,
+            p2_forward_ships: Option<Vec<RevealedShip>>,
+        }
+        GridSync { my_grid: Vec<Vec<String>>, enemy_grid: Vec<Vec<String>> }
+        Error { code: String, message: String, placement: Option<PlacementFault>, retry_after_secs: Option<u64> }
+    }
+}
+
+/// A player's connection: the SDK client plus typed expectations.
+pub struct TestClient {
+    pub client: Client,
+}
+
+impl std::ops::Deref for TestClient {
+    type Target = Client;
+    fn deref(&self) -> &Client {
+        &self.client
+    }
+}
+
+impl std::ops::DerefMut for TestClient {
+    fn deref_mut(&mut self) -> &mut Client {
+        &mut self.client
+    }
+}
+
+impl TestClient {
+    pub async fn join(server_url: &str, seat: Seat) -> Result<Self, ClientError> {
+        Ok(Self {
+            client: Client::join(server_url, seat).await?,
+        })
+    }
+
+    /// The next `T`, skipping other messages; fails the test after
+    /// `EXPECT_TIMEOUT`, listing what arrived instead.
+    pub async fn expect_msg<T: Expect>(&mut self) -> T {
+        self.expect_within(EXPECT_TIMEOUT).await
+    }
+
+    pub async fn expect_within<T: Expect>(&mut self, timeout: Duration) -> T {
+        let mut skipped = Vec::new();
+        let found = tokio::time::timeout(timeout, async {
+            loop {
+                match self.client.recv().await {
+                    Ok(Some(msg)) => match extract::<T>(&msg) {
+                        Some(found) => return Ok(found),
+                        None => skipped.push(kind(&msg)),
+                    },
+                    Ok(None) => return Err("connection closed".to_string()),
+                    Err(e) => return Err(e.to_string()),
+                }
+            }
+        })
+        .await;
+        match found {
+            Ok(Ok(found)) => found,
+            Ok(Err(e)) => panic!("expected {}: {} (after {:?})", T::TYPE, e, skipped),
+            Err(_) => panic!(
+                "expected {} within {:?}, got {:?}",
+                T::TYPE,
+                timeout,
+                skipped
+            ),
+        }
+    }
+
+    /// The next `Error`, which must carry `code`.
+    pub async fn expect_error(&mut self, code: &str) -> msg::Error {
+        let error = self.expect_msg::<msg::Error>().await;
+        assert_eq!(error.code, code, "unexpected error: {}", error.message);
+        error
+    }
+}
+
+fn extract<T: Expect>(msg: &ServerMessage) -> Option<T> {
+    let value = serde_json::to_value(msg).ok()?;
+    if value["type"] != T::TYPE {
+        return None;
+    }
+    Some(serde_json::from_value(value).unwrap_or_else(|e| panic!("{} fields: {}", T::TYPE, e)))
+}
+
+fn kind(msg: &ServerMessage) -> String {
+    serde_json::to_value(msg)
+        .ok()
+        .and_then(|v| v["type"].as_str().map(str::to_owned))
+        .unwrap_or_default()
+}
@@ -0,0 +1,83 @@
+use backend::config::{Config, ConfigError};
+
+fn no_env(_: &str) -> Option<String> {
+    None
+}
+
+/// The example file documents the defaults, so it must match them.
+#[test]
+fn test_example_file_matches_defaults() {
+    let example = include_str!("../battlecp.example.toml");
+    assert_eq!(Config::from_sources(Some(example), no_env).unwrap(), Config::default());
+}
+
+#[test]
+fn test_env_overrides_file() {
+    let file = r#"
+        [server]
+        port = 4000
+        frontend_url = "https://file.example"
+
+        [limits]
+        max_active_games = 50
+
+        [cors]
+        allowed_origins = ["https://*.vercel.app"]
+    "#;
+    let env = |var: &str| match var {
+        "PORT" => Some("5000".to_string()),
+        "TRUST_PROXY_HEADERS" => Some("1".to_string()),
+        "ALLOWED_ORIGINS" => Some("https://a.example, https://b.example".to_string()),
+        "BANNED_HANDLES" => Some("cheater".to_string()),
+        "FRONTEND_URL" => Some("   ".to_string()),
+        _ => None,
+    };
+    let config = Config::from_sources(Some(file), env).unwrap();
+    assert_eq!(config.server.port, 5000, "env wins over the file");
+    assert!(config.server.trust_proxy_headers);
+    assert_eq!(config.server.frontend_url.as_deref(), Some("https://file.example"), "blank env vars are ignored");
+    assert_eq!(config.limits.max_active_games, 50, "file wins over defaults");
+    assert_eq!(config.limits.max_lobbies_per_ip, 5);
+    assert_eq!(config.cors.allowed_origins, vec!["https://a.example", "https://b.example"]);
+    assert_eq!(config.moderation.banned_handles, vec!["cheater"]);
+}
+
+#[test]
+fn test_rejects_invalid_settings() {
+    let file_err = |toml: &str| Config::from_sources(Some(toml), no_env).unwrap_err();
+    assert!(matches!(file_err("[server]\nprot = 1"), ConfigError::Parse(_)), "unknown keys are typos");
+    assert!(matches!(file_err("[limits]\nmax_active_games = -1"), ConfigError::Parse(_)));
+    assert!(matches!(file_err("[limits]\ncontest_per_min = 0"), ConfigError::Invalid(_)));
+    assert!(matches!(file_err("[codeforces]\nrequest_interval_ms = 500"), ConfigError::Invalid(_)));
+    assert!(matches!(file_err("[server]\ntls_cert_path = \"c.pem\""), ConfigError::Invalid(_)));
+    assert!(matches!(file_err("[cors]\nallowed_origins = [\"https://foo.*.com\"]"), ConfigError::Invalid(_)));
+    assert!(matches!(file_err("[moderation]\nbanned_cidrs = [\"10.0.0.0/99\"]"), ConfigError::Invalid(_)));
+    assert!(matches!(file_err("[auth]\nbot_token_max_ttl_secs = 60"), ConfigError::Invalid(_)), "default above the cap");
+
+    let env_err = |var: &'static str, value: &'static str| {
+        Config::from_sources(None, move |v| (v == var).then(|| value.to_string())).unwrap_err()
+    };
+    match env_err("PORT", "http") {
+        ConfigError::Env { var, value } => assert_eq!((var, value.as_str()), ("PORT", "http")),
+        other => panic!("expected an env error, got {:?}", other),
+    }
+    assert!(matches!(env_err("CORS_ALLOW_ANY", "yes"), ConfigError::Env { .. }));
+    assert!(matches!(env_err("BIND_ADDR", "localhost"), ConfigError::Env { .. }));
+    assert!(matches!(env_err("VERIFY_PER_IP_PER_MIN", "0"), ConfigError::Invalid(_)));
+}
+
+#[test]
+fn test_summary_redacts_secrets() {
+    let env = |var: &str| match var {
+        "JWT_SECRET" => Some("hunter2hunter2hunter2".to_string()),
+        "ADMIN_API_KEYS" => Some("sekrit:manage".to_string()),
+        "DISCORD_WEBHOOK_URL" => Some("https://discord.com/api/webhooks/1/tok".to_string()),
+        _ => None,
+    };
+    let summary = Config::from_sources(None, env).unwrap().summary();
+    for secret in ["hunter2", "sekrit", "webhooks/1/tok"] {
+        assert!(!summary.contains(secret), "{} leaked into:\n{}", secret, summary);
+    }
+    assert!(summary.contains("<redacted>:manage"));
+    assert!(summary.contains("port = 3000"));
+}
@@ -0,0 +1,90 @@
+use axum::http::HeaderName;
+use backend::cors::AllowedOrigins;
+
+#[test]
+fn test_origin_patterns() {
+    let origins = AllowedOrigins::parse(
+        "https://battle-cp.example.com/, https://*.vercel.app, regex:https://pr-[0-9]+\\.preview\\.dev",
+    )
+    .unwrap();
+
+    assert!(origins.allows("https://battle-cp.example.com"), "trailing slash ignored");
+    assert!(origins.allows("https://Battle-CP.example.com"), "host is case-insensitive");
+    assert!(!origins.allows("http://battle-cp.example.com"), "scheme must match");
+
+    assert!(origins.allows("https://battle-cp-git-feature-x.vercel.app"));
+    assert!(origins.allows("https://a.b.vercel.app"));
+    assert!(!origins.allows("https://vercel.app"), "wildcard needs a subdomain");
+    assert!(!origins.allows("http://preview.vercel.app"));
+    assert!(!origins.allows("https://evil.com/.vercel.app"));
+    assert!(!origins.allows("https://evilvercel.app"));
+
+    assert!(origins.allows("https://pr-42.preview.dev"));
+    assert!(!origins.allows("https://pr-42.preview.dev.evil.com"), "regex is anchored");
+    assert!(!origins.allows("https://pr-x.preview.dev"));
+
+    assert!(AllowedOrigins::any().allows("https://anything.example"));
+
+    for bad in ["https://*", "https://foo.*.com", "regex:(unclosed"] {
+        assert!(AllowedOrigins::parse(bad).is_err(), "{:?} accepted", bad);
+    }
+}
+
+#[tokio::test]
+async fn test_cors_layer_echoes_allowed_origin() {
+    let app = axum::Router::new()
+        .route("/health", axum::routing::get(|| async { "ok" }))
+        .layer(
+            AllowedOrigins::parse("https://*.vercel.app")
+                .unwrap()
+                .layer(HeaderName::from_static("x-request-id")),
+        );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let client = reqwest::Client::new();
+    let request = |origin: &'static str| {
+        client
+            .get(format!("http://{}/health", addr))
+            .header("origin", origin)
+            .send()
+    };
+
+    let res = request("https://branch.vercel.app").await.unwrap();
+    assert!(res.status().is_success());
+    assert_eq!(
+        res.headers().get("access-control-allow-origin").unwrap(),
+        "https://branch.vercel.app"
+    );
+
+    let res = request("https://example.com").await.unwrap();
+    assert!(res.headers().get("access-control-allow-origin").is_none());
+}
+
+/// Scripts on an allowed origin can read the request ID, the rate-limit
+/// headers and whether a creation was replayed.
+#[tokio::test]
+async fn test_cors_layer_exposes_headers() {
+    let app = axum::Router::new()
+        .route("/health", axum::routing::get(|| async { "ok" }))
+        .layer(AllowedOrigins::any().layer(HeaderName::from_static("x-request-id")));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let res = reqwest::Client::new()
+        .get(format!("http://{}/health", addr))
+        .header("origin", "https://battle-cp.example.com")
+        .send()
+        .await
+        .unwrap();
+    let exposed = res.headers().get("access-control-expose-headers").unwrap().to_str().unwrap();
+    let exposed: Vec<&str> = exposed.split(',').map(str::trim).collect();
+    for name in ["x-request-id", "ratelimit-limit", "ratelimit-remaining", "ratelimit-reset", "retry-after", "idempotent-replayed"] {
+        assert!(exposed.contains(&name), "{} not in {:?}", name, exposed);
+    }
+}
@@ -0,0 +1,69 @@
+use backend::state::{AppState, Game, GameConfig, GameEvent};
+use backend::protocol::ServerMessage;
+use std::sync::{Arc, Mutex};
+use tokio::time::{sleep, Duration};
+use uuid::Uuid;
+
+/// A public game's lobby link, the host's join ping and the match report are
+/// all posted, driven by the game's own broadcasts.
+#[tokio::test]
+async fn test_posts_match_lifecycle() {
+    let received: Arc<Mutex<Vec<serde_json::Value>>> = Arc::default();
+    let sink = received.clone();
+    let app = axum::Router::new().route(
+        "/hook",
+        axum::routing::post(move |axum::Json(body): axum::Json<serde_json::Value>| {
+            let sink = sink.clone();
+            async move { sink.lock().unwrap().push(body) }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    // Read once, when the config is first used
+    std::env::set_var("DISCORD_WEBHOOK_URL", format!("http://{}/hook", addr));
+    std::env::set_var("FRONTEND_URL", "https://battle.example");
+    backend::discord::init_worker();
+    let state = AppState::new();
+
+    let config = GameConfig { public: true, ..GameConfig::default() };
+    let mut game = Game::new(Uuid::new_v4(), "host".to_string(), config);
+    game.host_discord_id = Some("123456789012345678".to_string());
+    let game_id = game.id;
+    state.insert_game(game);
+
+    let guest_id = Uuid::new_v4();
+    state
+        .with_game(game_id, move |g| {
+            g.seat_guest(guest_id, "guest".to_string()).unwrap();
+            let _ = g.tx.send(GameEvent::Message(ServerMessage::PlayerJoined { player_id: guest_id }));
+            g.game_started_at = Some(std::time::Instant::now());
+            g.finish(Some(guest_id), "AllShipsSunk");
+        })
+        .await
+        .unwrap();
+
+    let mut posts = Vec::new();
+    for _ in 0..100 {
+        posts = received.lock().unwrap().clone();
+        if posts.len() == 3 {
+            break;
+        }
+        sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(posts.len(), 3, "{:?}", posts);
+
+    let lobby = posts[0]["embeds"][0]["description"].as_str().unwrap();
+    assert!(lobby.contains("**host**"));
+    assert!(lobby.contains(&format!("https://battle.example/game/{}", game_id)));
+
+    let ping = posts[1]["content"].as_str().unwrap();
+    assert!(ping.starts_with("<@123456789012345678> "));
+    assert!(ping.contains("**guest** joined **host**'s lobby"));
+    assert_eq!(posts[1]["allowed_mentions"]["users"][0], "123456789012345678");
+
+    let report = posts[2]["embeds"][0]["description"].as_str().unwrap();
+    assert!(report.contains("**guest** defeated **host**"), "{}", report);
+}
@@ -0,0 +1,37 @@
+//! Games removed without ending (abandoned, expired) must still stop: nothing
+//! following them may keep their actor alive.
+
+use backend::state::{AppState, Game, GameConfig, GameStatus};
+use backend::webhooks::{Subscription, WebhookEvent, Webhooks};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{timeout, Duration};
+use uuid::Uuid;
+
+/// An abandoned game expires and its actor stops, followers and all.
+#[tokio::test]
+async fn test_expired_game_stops_with_followers() {
+    // Read once, when the config is first used; nothing needs to answer
+    std::env::set_var("DISCORD_WEBHOOK_URL", "http://127.0.0.1:9/hook");
+    backend::discord::init_worker();
+    let mut state = AppState::new();
+    state.cleanup_policy.abandoned = Duration::from_millis(100);
+    tokio::spawn(backend::background::start_global_ticker(state.clone()));
+
+    let mut game = Game::new(Uuid::new_v4(), "host".to_string(), GameConfig::default());
+    game.join(Uuid::new_v4(), "guest".to_string()).unwrap();
+    game.status = GameStatus::Abandoned;
+    game.abandoned_at = Some(std::time::Instant::now());
+    let callback = Subscription { url: "https://callbacks.example/hook".to_string(), events: vec![WebhookEvent::GameOver] };
+    game.webhooks = Webhooks::new(vec![callback], Some("0123456789abcdef".to_string()), &Default::default()).unwrap();
+    game.room = Some(state.rooms.create("Followers", 10).unwrap());
+    let game_id = game.id;
+    let mut rx = game.tx.subscribe();
+    state.insert_game(game);
+
+    timeout(Duration::from_secs(5), async {
+        while !matches!(rx.recv().await, Err(RecvError::Closed)) {}
+    })
+    .await
+    .expect("the game's actor kept running");
+    assert!(!state.games.contains_key(&game_id));
+}
@@ -0,0 +1,289 @@
+mod common;
+
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use backend::cf_client::Verdict;
+use backend::cses::{parse_results, TaskScore};
+use backend::error::JudgeError;
+use backend::handlers;
+use backend::judge::{pool_queue, JudgeClient};
+use backend::protocol::Coord;
+use backend::rate_limit::ClientIp;
+use backend::state::{AssignedProblem, GameConfig};
+use battle_cp_client::{ClientError, Seat};
+use common::{msg, TestServer};
+use futures::future::BoxFuture;
+use rand::SeedableRng;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// A university judge with two tasks, where everything submitted is accepted.
+/// Handles carry their rating: `r1450` is rated 1450, `newbie` is unrated, and
+/// anyone else can't be looked up.
+struct ClubJudge {
+    problems: Vec<AssignedProblem>,
+}
+
+impl ClubJudge {
+    fn new() -> Self {
+        let task = |id: i32, rating| AssignedProblem {
+            contest_id: id,
+            index: String::new(),
+            name: format!("Task {}", id),
+            rating,
+            url: Some(format!("https://judge.example.edu/task/{}", id)),
+        };
+        Self { problems: vec![task(7, 800), task(8, 1600)] }
+    }
+}
+
+impl JudgeClient for ClubJudge {
+    fn id(&self) -> &str {
+        "club"
+    }
+
+    fn name(&self) -> &str {
+        "Club Judge"
+    }
+
+    fn problems(&self) -> Option<&[AssignedProblem]> {
+        Some(&self.problems)
+    }
+
+    fn is_valid_problem_ref(&self, contest_id: i32, index: &str) -> bool {
+        contest_id > 0 && index.is_empty()
+    }
+
+    fn has_ratings(&self) -> bool {
+        true
+    }
+
+    fn rating<'a>(&'a self, handle: &'a str) -> BoxFuture<'a, Result<Option<u32>, JudgeError>> {
+        Box::pin(async move {
+            match handle.strip_prefix('r').and_then(|r| r.parse().ok()) {
+                Some(rating) => Ok(Some(rating)),
+                None if handle == "newbie" => Ok(None),
+                None => Err(JudgeError::ApiStatus("FAILED".to_string())),
+            }
+        })
+    }
+
+    fn solved_set<'a>(&'a self, _handle: &'a str) -> BoxFuture<'a, Result<HashSet<String>, JudgeError>> {
+        Box::pin(async { Ok(HashSet::new()) })
+    }
+
+    fn verify<'a>(
+        &'a self,
+        _handle: &'a str,
+        _problem: &'a AssignedProblem,
+        _since_unix: Option<u64>,
+    ) -> BoxFuture<'a, Result<Verdict, JudgeError>> {
+        Box::pin(async { Ok(Verdict::Accepted) })
+    }
+}
+
+#[test]
+fn test_cses_results_page() {
+    let html = r#"
+        <table><tr>
+        <td><a href="/problemset/task/1068/" class="task-score icon full"></a></td>
+        <td><a class="task-score icon zero" href="/problemset/task/1083/"></a></td>
+        <td><a href="/problemset/task/1069/" class="task-score icon "></a></td>
+        <td><a href="/problemset/task/1094/">Increasing Array</a></td>
+        <td><a href="/problemset/user/1/" class="full"></a></td>
+        </tr></table>"#;
+    let scores = parse_results(html);
+    assert_eq!(scores.len(), 2, "{:?}", scores);
+    assert_eq!(scores[&1068], TaskScore::Solved);
+    assert_eq!(scores[&1083], TaskScore::Attempted);
+}
+
+/// Unsolved problems come first, nearest the target rating first.
+#[test]
+fn test_pool_queue_order() {
+    let problem = |id: i32, rating| AssignedProblem {
+        contest_id: id,
+        index: String::new(),
+        name: String::new(),
+        rating,
+        url: None,
+    };
+    let pool = [problem(1, 800), problem(2, 1200), problem(3, 1600), problem(4, 1250)];
+    let solved: HashSet<String> = ["4-".to_string()].into();
+    let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+    let queue = pool_queue(&pool, 1200, &solved, &HashSet::new(), &mut rng);
+    let ids: Vec<i32> = queue.iter().map(|p| p.contest_id).collect();
+    assert_eq!(ids[0], 2);
+    assert_eq!(ids[3], 4);
+    assert_eq!(queue.len(), pool.len());
+}
+
+/// A registered plugin can be picked at game creation and verifies the
+/// game's solves; unknown judges are refused.
+#[tokio::test]
+async fn test_plugin_judge_verifies_solves() {
+    let judge = Arc::new(ClubJudge::new());
+    let battle = backend::Server::builder()
+        .config(backend::config::Config::default())
+        .judge_plugin(judge.clone())
+        .without_background_tasks()
+        .build();
+    let server = TestServer::with_state(battle.state().clone()).await;
+
+    let create = |judge: &str| {
+        let payload: handlers::CreateGameRequest =
+            serde_json::from_value(serde_json::json!({ "cf_handle": "host", "judge": judge })).unwrap();
+        handlers::create_game(State(server.state.clone()), ClientIp(None), HeaderMap::new(), axum::Json(payload))
+    };
+    assert_eq!(create("club").await.status(), StatusCode::CREATED);
+    assert_eq!(create("cses").await.status(), StatusCode::BAD_REQUEST, "not enabled in the config");
+
+    let game = server.in_combat(GameConfig { heat_threshold: 1, judge: "club".to_string(), ..GameConfig::default() });
+    let problems = judge.problems.clone();
+    game.call(move |g| g.problem_queue = problems).await;
+    let mut host = server.connect(game.host_seat()).await;
+    host.fire(Coord { x: 9, y: 9 }).await.unwrap();
+    let assigned = host.expect_msg::<msg::ProblemAssigned>().await;
+    assert_eq!(assigned.url.as_deref(), Some("https://judge.example.edu/task/7"));
+
+    // Codeforces-style references aren't this judge's
+    let mut guest = server.connect(game.guest_seat()).await;
+    guest.solve(1950, "C").await.unwrap();
+    guest.expect_error("invalid_problem").await;
+
+    host.solve(assigned.contest_id, "").await.unwrap();
+    let unlocked = host.expect_msg::<msg::WeaponsUnlocked>().await;
+    assert_eq!(unlocked.reason, "solved");
+}
+
+/// Guests outside a lobby's rating range, unrated or unknown are turned away
+/// before taking the seat; one in range gets it.
+#[tokio::test]
+async fn test_rating_gate_on_join() {
+    let battle = backend::Server::builder()
+        .config(backend::config::Config::default())
+        .judge_plugin(Arc::new(ClubJudge::new()))
+        .without_background_tasks()
+        .build();
+    let server = TestServer::with_state(battle.state().clone()).await;
+
+    let settings = |json: serde_json::Value| serde_json::from_value::<handlers::GameSettings>(json).unwrap();
+    let judges = &server.state.judges;
+    assert!(settings(serde_json::json!({ "min_rating": 1600, "max_rating": 1200 })).build(judges).is_err());
+    let (config, _) = settings(serde_json::json!({ "judge": "club", "min_rating": 1200, "max_rating": 1600 }))
+        .build(judges)
+        .unwrap();
+    assert_eq!(config.rating_range().as_deref(), Some("1200–1600"));
+
+    let game = server.lobby(config);
+    for (handle, code) in [("r1100", "rating_out_of_range"), ("newbie", "unrated"), ("nobody", "rating_unavailable")] {
+        match server.try_connect(Seat::guest(game.id, handle)).await {
+            Err(ClientError::Server { code: got, .. }) => assert_eq!(got, code, "{}", handle),
+            other => panic!("{} joined: {:?}", handle, other.map(|c| c.seat().clone())),
+        }
+    }
+    assert!(game.call(|g| g.player2.is_none()).await);
+
+    let mut guest = server.connect(Seat::guest(game.id, "r1450")).await;
+    guest.expect_msg::<msg::GameJoined>().await;
+    assert_eq!(game.call(|g| g.player2.as_ref().map(|p| p.cf_handle.clone())).await.as_deref(), Some("r1450"));
+}
+
+/// A host's rating on the judge suggests a difficulty and heat threshold;
+/// unrated hosts get the easiest settings.
+#[tokio::test]
+async fn test_suggest_config_from_rating() {
+    use axum::extract::Query;
+
+    let battle = backend::Server::builder()
+        .config(backend::config::Config::default())
+        .judge_plugin(Arc::new(ClubJudge::new()))
+        .without_background_tasks()
+        .build();
+    let state = battle.state().clone();
+    let suggest = |handle: &str, judge: &str| {
+        let query = handlers::SuggestConfigQuery { handle: handle.to_string(), judge: Some(judge.to_string()) };
+        handlers::suggest_config(State(state.clone()), Query(query))
+    };
+    let body = |response: axum::response::Response| async move {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice::<serde_json::Value>(&bytes).unwrap()
+    };
+
+    let suggested = body(suggest("r1450", "club").await).await;
+    assert_eq!(suggested["rating"], 1450);
+    assert_eq!((suggested["difficulty_mode"].as_str(), suggested["difficulty"].as_u64()), (Some("cf"), Some(1200)));
+    assert_eq!(suggested["heat_threshold"], 8);
+    let unrated = body(suggest("newbie", "club").await).await;
+    assert_eq!((unrated["rating"].is_null(), unrated["difficulty"].as_u64()), (true, Some(800)));
+
+    assert_eq!(suggest("nobody", "club").await.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(suggest("r1450", "cses").await.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(suggest("no spaces", "club").await.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(handlers::suggested_settings(Some(3900)), (3500, 6));
+}
+
+/// A judge that's up or down on command and accepts nothing.
+struct FlakyJudge {
+    down: std::sync::atomic::AtomicBool,
+}
+
+impl JudgeClient for FlakyJudge {
+    fn id(&self) -> &str {
+        "flaky"
+    }
+
+    fn name(&self) -> &str {
+        "Flaky Judge"
+    }
+
+    fn is_down(&self) -> bool {
+        self.down.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    fn solved_set<'a>(&'a self, _handle: &'a str) -> BoxFuture<'a, Result<HashSet<String>, JudgeError>> {
+        Box::pin(async { Ok(HashSet::new()) })
+    }
+
+    fn verify<'a>(
+        &'a self,
+        _handle: &'a str,
+        _problem: &'a AssignedProblem,
+        _since_unix: Option<u64>,
+    ) -> BoxFuture<'a, Result<Verdict, JudgeError>> {
+        Box::pin(async { Ok(Verdict::Nothing) })
+    }
+}
+
+/// With `pause_on_outage`, the global ticker stops the clock while the
+/// game's judge is down and a player is locked, and restarts it once it's back.
+#[tokio::test]
+async fn test_clock_pauses_during_judge_outage() {
+    use std::sync::atomic::Ordering;
+
+    let judge = Arc::new(FlakyJudge { down: false.into() });
+    let mut config = backend::config::Config::default();
+    config.game.sweep_interval_ms = 50;
+    let battle = backend::Server::builder().config(config).judge_plugin(judge.clone()).build();
+    let server = TestServer::with_state(battle.state().clone()).await;
+    let game = server.in_combat(GameConfig {
+        heat_threshold: 1,
+        pause_on_outage: true,
+        judge: "flaky".to_string(),
+        ..GameConfig::default()
+    });
+    let mut host = server.connect(game.host_seat()).await;
+    host.fire(Coord { x: 9, y: 9 }).await.unwrap();
+    host.expect_msg::<msg::ProblemAssigned>().await;
+
+    judge.down.store(true, Ordering::Relaxed);
+    host.expect_msg::<msg::ClockPaused>().await;
+    let remaining = game.call(|g| g.time_remaining_secs()).await;
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    assert_eq!(game.call(|g| g.time_remaining_secs()).await, remaining, "the clock stood still");
+
+    judge.down.store(false, Ordering::Relaxed);
+    let resumed = host.expect_msg::<msg::ClockResumed>().await;
+    assert!(resumed.paused_secs >= 1, "{:?}", resumed);
+    assert!(game.call(|g| g.clock_paused_at.is_none()).await);
+}
@@ -0,0 +1,614 @@
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use backend::auth::Role;
+use backend::handlers::{self, CancelGameQuery};
+use backend::rate_limit::ClientIp;
+use backend::state::{AppState, Game, GameConfig};
+use uuid::Uuid;
+
+/// Host can cancel a Waiting lobby; guests and unknown IDs cannot.
+#[tokio::test]
+async fn test_cancel_waiting_lobby() {
+    let state = AppState::new();
+    let host_id = Uuid::new_v4();
+    let game = Game::new(host_id, "host".to_string(), GameConfig::default());
+    let game_id = game.id;
+    let mut rx = game.tx.subscribe();
+    state.insert_game(game);
+
+    let cancel = |player_id: Uuid, token: Option<String>| {
+        handlers::cancel_game(
+            State(state.clone()),
+            Path(game_id),
+            Query(CancelGameQuery { player_id, token }),
+            HeaderMap::new(),
+        )
+    };
+
+    // Non-host is rejected, even with a valid token of their own
+    let other = Uuid::new_v4();
+    let other_token = state.sessions.issue(other, game_id, Role::Guest);
+    let (status, _) = cancel(other, Some(other_token)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert!(state.games.contains_key(&game_id));
+
+    // Knowing the host's ID is not enough without their token
+    let (status, _) = cancel(host_id, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let forged = state.sessions.issue(host_id, Uuid::new_v4(), Role::Host);
+    let (status, _) = cancel(host_id, Some(forged)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert!(state.games.contains_key(&game_id));
+
+    // Host cancels — subscribers get GameOver, game is removed
+    let host_token = state.sessions.issue(host_id, game_id, Role::Host);
+    let (status, _) = cancel(host_id, Some(host_token.clone())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!state.games.contains_key(&game_id));
+
+    match rx.recv().await.map(|e| e.message) {
+        Ok(backend::protocol::ServerMessage::GameOver {
+            reason, ..
+        }) => assert_eq!(reason, "LobbyCancelled"),
+        other => panic!("Expected GameOver, got {:?}", other),
+    }
+
+    // Second cancel — game no longer exists
+    let (status, _) = cancel(host_id, Some(host_token)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+/// Lobbies that already have an opponent cannot be cancelled.
+#[tokio::test]
+async fn test_cancel_rejected_after_join() {
+    let state = AppState::new();
+    let host_id = Uuid::new_v4();
+    let mut game = Game::new(host_id, "host".to_string(), GameConfig::default());
+    game.join(Uuid::new_v4(), "guest".to_string()).unwrap();
+    game.status = backend::state::GameStatus::PlacingShips;
+    let game_id = game.id;
+    state.insert_game(game);
+
+    let token = state.sessions.issue(host_id, game_id, Role::Host);
+    let (status, _) = handlers::cancel_game(
+        State(state.clone()),
+        Path(game_id),
+        Query(CancelGameQuery { player_id: host_id, token: Some(token) }),
+        HeaderMap::new(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
+
+/// Snapshot exposes handles and readiness but never ship positions.
+#[tokio::test]
+async fn test_game_snapshot_is_sanitized() {
+    let state = AppState::new();
+    let mut game = Game::new(Uuid::new_v4(), "host".to_string(), GameConfig::default());
+    game.join(Uuid::new_v4(), "guest".to_string()).unwrap();
+    game.player1
+        .place_ship(
+            backend::state::Ship { size: 5, hits: 0, sunk: false, x: 0, y: 0, vertical: false },
+            0,
+            0,
+            false,
+        )
+        .unwrap();
+    game.player1.ships_placed = true;
+    let game_id = game.id;
+    state.insert_game(game);
+
+    let (status, axum::Json(body)) = handlers::get_game(State(state.clone()), Path(game_id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["host"]["cf_handle"], "host");
+    assert_eq!(body["host"]["ships_placed"], true);
+    assert_eq!(body["guest"]["ships_placed"], false);
+    let text = body.to_string();
+    assert!(!text.contains("grid") && !text.contains("ships\""), "snapshot leaked board data: {}", text);
+
+    let (status, _) = handlers::get_game(State(state), Path(Uuid::new_v4())).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+/// Dashboard lists only the player's games still in play, a page at a time.
+#[tokio::test]
+async fn test_list_player_games() {
+    let state = AppState::new();
+    let player_id = Uuid::new_v4();
+
+    let hosted = Game::new(player_id, "me".to_string(), GameConfig::default());
+    let hosted_id = hosted.id;
+    let mut joined = Game::new(Uuid::new_v4(), "other".to_string(), GameConfig::default());
+    joined.join(player_id, "me".to_string()).unwrap();
+    let joined_id = joined.id;
+    let mut finished = Game::new(player_id, "me".to_string(), GameConfig::default());
+    finished.status = backend::state::GameStatus::Finished;
+    let mut abandoned = Game::new(player_id, "me".to_string(), GameConfig::default());
+    abandoned.status = backend::state::GameStatus::Abandoned;
+    abandoned.abandoned_at = Some(std::time::Instant::now());
+    let unrelated = Game::new(Uuid::new_v4(), "stranger".to_string(), GameConfig::default());
+    for g in [hosted, joined, finished, abandoned, unrelated] {
+        state.insert_game(g);
+    }
+
+    let list = |page: serde_json::Value, filter: serde_json::Value| {
+        handlers::list_player_games(
+            State(state.clone()),
+            Path(player_id),
+            Query(serde_json::from_value(page).unwrap()),
+            Query(serde_json::from_value(filter).unwrap()),
+        )
+    };
+    let (status, axum::Json(body)) = list(serde_json::json!({}), serde_json::json!({})).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["next_cursor"].is_null());
+    let games = body["games"].as_array().unwrap();
+    assert_eq!(games.len(), 2);
+    for entry in games {
+        let id = entry["game"]["game_id"].as_str().unwrap();
+        let expected_role = if id == hosted_id.to_string() {
+            "host"
+        } else {
+            assert_eq!(id, joined_id.to_string());
+            "guest"
+        };
+        assert_eq!(entry["role"], expected_role);
+        let link = format!("/game/{}?player_id={}&cf_handle=me", id, player_id);
+        assert!(entry["join_url"].as_str().unwrap().ends_with(&link));
+    }
+
+    // One per page, by game ID
+    let (_, axum::Json(first)) = list(serde_json::json!({ "limit": 1 }), serde_json::json!({})).await;
+    let cursor = first["next_cursor"].as_str().unwrap().to_string();
+    assert_eq!(first["games"][0]["game"]["game_id"], cursor.as_str());
+    let (_, axum::Json(second)) =
+        list(serde_json::json!({ "limit": 1, "cursor": cursor }), serde_json::json!({})).await;
+    assert_eq!(second["games"].as_array().unwrap().len(), 1);
+    assert_ne!(second["games"][0]["game"]["game_id"], first["games"][0]["game"]["game_id"]);
+    assert!(second["next_cursor"].is_null());
+
+    // Filtered by status
+    let count = |body: serde_json::Value| body["games"].as_array().unwrap().len();
+    let (_, axum::Json(waiting)) = list(serde_json::json!({}), serde_json::json!({ "status": "Waiting" })).await;
+    assert_eq!(count(waiting), 2);
+    let (_, axum::Json(playing)) = list(serde_json::json!({}), serde_json::json!({ "status": "Playing" })).await;
+    assert_eq!(count(playing), 0);
+}
+
+/// Creation is refused with 429 + Retry-After once an IP holds too many open
+/// lobbies, or the server hits its active-game cap.
+#[tokio::test]
+async fn test_create_game_backpressure() {
+    let mut state = AppState::new();
+    state.max_lobbies_per_ip = 2;
+    state.max_active_games = 3;
+    let ip: std::net::IpAddr = "203.0.113.7".parse().unwrap();
+
+    let create = |state: AppState, ip: std::net::IpAddr, handle: &str| {
+        let payload: handlers::CreateGameRequest =
+            serde_json::from_value(serde_json::json!({ "cf_handle": handle })).unwrap();
+        handlers::create_game(State(state), ClientIp(Some(ip)), HeaderMap::new(), axum::Json(payload))
+    };
+
+    assert_eq!(create(state.clone(), ip, "a").await.status(), StatusCode::CREATED);
+    assert_eq!(create(state.clone(), ip, "b").await.status(), StatusCode::CREATED);
+
+    // Third lobby from the same IP is refused
+    let resp = create(state.clone(), ip, "c").await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(resp.headers().contains_key("retry-after"));
+
+    // Another IP still gets in, then the global cap kicks in
+    let other: std::net::IpAddr = "203.0.113.8".parse().unwrap();
+    assert_eq!(create(state.clone(), other, "d").await.status(), StatusCode::CREATED);
+    let resp = create(state.clone(), other, "e").await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(state.games.len(), 3);
+}
+
+/// Retrying with the same `Idempotency-Key` returns the original lobby instead
+/// of creating another; other handles, new keys and removed games don't match,
+/// and a different request reusing a live key is refused.
+#[tokio::test]
+async fn test_create_game_idempotency_key() {
+    let state = AppState::new();
+    let ip: std::net::IpAddr = "203.0.113.7".parse().unwrap();
+    let request = |handle: &str, key: &str, ip: std::net::IpAddr, difficulty: u32| {
+        let payload: handlers::CreateGameRequest =
+            serde_json::from_value(serde_json::json!({ "cf_handle": handle, "difficulty": difficulty })).unwrap();
+        let mut headers = HeaderMap::new();
+        headers.insert("idempotency-key", key.parse().unwrap());
+        handlers::create_game(State(state.clone()), ClientIp(Some(ip)), headers, axum::Json(payload))
+    };
+    let create = |handle: &str, key: &str| request(handle, key, ip, 2);
+    let game_id = |resp: axum::response::Response| async move {
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        serde_json::from_value::<Uuid>(body["game_id"].clone()).unwrap()
+    };
+
+    let first = create("host", "retry-1").await;
+    assert_eq!(first.status(), StatusCode::CREATED);
+    assert!(!first.headers().contains_key("idempotent-replayed"));
+    let original = game_id(first).await;
+
+    // Same handle (any case) and key: the same lobby, flagged as a replay
+    let again = create("HOST", "retry-1").await;
+    assert_eq!(again.status(), StatusCode::CREATED);
+    assert_eq!(again.headers()["idempotent-replayed"], "true");
+    assert_eq!(game_id(again).await, original);
+    assert_eq!(state.games.len(), 1);
+
+    // The same key with other settings, or from elsewhere, gets neither
+    let other_ip: std::net::IpAddr = "198.51.100.4".parse().unwrap();
+    assert_eq!(request("host", "retry-1", ip, 3).await.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(request("host", "retry-1", other_ip, 2).await.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(state.games.len(), 1);
+
+    // A different key or handle creates a new lobby
+    assert_ne!(game_id(create("host", "retry-2").await).await, original);
+    assert_ne!(game_id(create("other", "retry-1").await).await, original);
+    assert_eq!(state.games.len(), 3);
+
+    // Once the lobby is gone the key starts fresh
+    state.games.remove(&original);
+    assert_ne!(game_id(create("host", "retry-1").await).await, original);
+
+    assert_eq!(create("host", " ").await.status(), StatusCode::BAD_REQUEST);
+}
+
+/// A scheduled match stays open past the usual lobby retention, then counts
+/// down and starts at its scheduled time without anyone pressing Ready.
+#[tokio::test]
+async fn test_scheduled_match_starts_on_time() {
+    use backend::protocol::ServerMessage;
+    use backend::state::GameStatus;
+
+    let state = AppState::new();
+    let ip: std::net::IpAddr = "203.0.113.9".parse().unwrap();
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let create = |scheduled_start: u64| {
+        let payload: handlers::CreateGameRequest = serde_json::from_value(
+            serde_json::json!({ "cf_handle": "host", "scheduled_start": scheduled_start }),
+        )
+        .unwrap();
+        handlers::create_game(State(state.clone()), ClientIp(Some(ip)), HeaderMap::new(), axum::Json(payload))
+    };
+
+    assert_eq!(create(now - 1).await.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(create(now + 8 * 24 * 3600).await.status(), StatusCode::BAD_REQUEST);
+
+    let start = now + 7;
+    let resp = create(start).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let game_id: Uuid = serde_json::from_value(body["game_id"].clone()).unwrap();
+
+    // Not swept past the lobby retention window while the start is still ahead
+    let handle = state.game(game_id).unwrap();
+    let eager = backend::background::CleanupPolicy {
+        waiting: std::time::Duration::from_millis(200),
+        ..Default::default()
+    };
+    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+    assert_eq!(eager.expiry_reason(&handle.summary()), None);
+
+    // Guest pre-joins and both pre-place; CF data is ready
+    let mut rx = handle.tx.subscribe();
+    state
+        .with_game(game_id, |g| {
+            g.seat_guest(Uuid::new_v4(), "guest".to_string()).unwrap();
+            g.player1.ships_placed = true;
+            g.player2.as_mut().unwrap().ships_placed = true;
+            g.status = GameStatus::Initializing;
+            g.problem_queue_ready = true;
+        })
+        .await
+        .unwrap();
+    let snapshot = state.with_game(game_id, |g| g.snapshot()).await.unwrap();
+    assert_eq!(snapshot.scheduled_start, Some(start));
+
+    let mut countdown = Vec::new();
+    loop {
+        match tokio::time::timeout(std::time::Duration::from_secs(10), rx.recv()).await.map(|r| r.map(|e| e.message)) {
+            Ok(Ok(ServerMessage::Countdown { seconds_remaining })) => {
+                countdown.push(seconds_remaining)
+            }
+            Ok(Ok(ServerMessage::GameStart)) => break,
+            Ok(Ok(_)) => {}
+            other => panic!("Expected countdown and GameStart, got {:?}", other),
+        }
+    }
+    assert_eq!(countdown, vec![5, 4, 3, 2, 1]);
+    let started = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    assert!(started.abs_diff(start) <= 1, "started at {}, scheduled {}", started, start);
+    assert_eq!(handle.status(), GameStatus::Playing);
+}
+
+/// Organizers create a round of games in one call; each guest seat only takes
+/// the paired guest, and the host's link carries a working session token.
+#[tokio::test]
+async fn test_bulk_create_games() {
+    use backend::admin::{self, AdminKeys, AdminQuery, Scope};
+
+    let mut state = AppState::new();
+    state.admin_keys = std::sync::Arc::new(AdminKeys::new([
+        ("observer", Scope::Read),
+        ("organizer", Scope::Manage),
+    ]));
+    let bulk = |key: &str, body: serde_json::Value| {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", format!("Bearer {}", key).parse().unwrap());
+        admin::bulk_create_games(
+            State(state.clone()),
+            Query(AdminQuery { token: None }),
+            headers,
+            axum::Json(serde_json::from_value(body).unwrap()),
+        )
+    };
+    let round = serde_json::json!({
+        "pairs": [{ "host": "alice", "guest": "bob" }, { "host": "carol", "guest": "dave" }],
+        "config": { "difficulty": 2, "game_duration_mins": 30 },
+    });
+
+    assert_eq!(bulk("observer", round.clone()).await.status(), StatusCode::FORBIDDEN);
+    let bad = serde_json::json!({ "pairs": [{ "host": "alice", "guest": "no spaces" }] });
+    assert_eq!(bulk("organizer", bad).await.status(), StatusCode::BAD_REQUEST);
+    assert!(state.games.is_empty());
+
+    let res = bulk("organizer", round).await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let games = body["games"].as_array().unwrap();
+    assert_eq!(games.len(), 2);
+    assert_eq!(state.games.len(), 2);
+
+    let first = &games[0];
+    let game_id: Uuid = serde_json::from_value(first["game_id"].clone()).unwrap();
+    let host_id: Uuid = serde_json::from_value(first["host"]["player_id"].clone()).unwrap();
+    let guest_id: Uuid = serde_json::from_value(first["guest"]["player_id"].clone()).unwrap();
+    let host_link = first["host"]["join_url"].as_str().unwrap();
+    assert!(host_link.contains(&format!("/game/{}?player_id={}&cf_handle=alice&token=", game_id, host_id)));
+    let token = host_link.rsplit("token=").next().unwrap();
+    assert!(state.sessions.verify(token).unwrap().allows(host_id, game_id));
+    assert!(first["guest"]["join_url"]
+        .as_str()
+        .unwrap()
+        .ends_with(&format!("/game/{}?player_id={}&cf_handle=bob", game_id, guest_id)));
+
+    // A leaked guest link is no good under another handle
+    let (config, stranger, impostor, paired) = state
+        .with_game(game_id, move |g| {
+            let stranger = g.seat_guest(Uuid::new_v4(), "mallory".to_string());
+            let impostor = g.seat_guest(guest_id, "mallory".to_string());
+            let paired = g.seat_guest(guest_id, "BOB".to_string());
+            ((g.config.difficulty, g.config.game_duration_secs), stranger, impostor, paired)
+        })
+        .await
+        .unwrap();
+    assert_eq!(config, (2, 30 * 60));
+    assert_eq!(stranger, Err(backend::error::GameError::SeatReserved));
+    assert_eq!(impostor, Err(backend::error::GameError::SeatReserved));
+    assert_eq!(paired, Ok(()));
+}
+
+/// Games created with a tournament tag are exported as Prometheus gauges,
+/// labelled by tournament, game and player; untagged games are left out.
+#[tokio::test]
+async fn test_tournament_metrics() {
+    use backend::admin::{self, AdminKeys, AdminQuery, Scope};
+
+    let mut state = AppState::new();
+    state.admin_keys = std::sync::Arc::new(AdminKeys::new([("organizer", Scope::Manage)]));
+    let auth = |key: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", format!("Bearer {}", key).parse().unwrap());
+        headers
+    };
+    let bulk = |body: serde_json::Value| {
+        admin::bulk_create_games(
+            State(state.clone()),
+            Query(AdminQuery { token: None }),
+            auth("organizer"),
+            axum::Json(serde_json::from_value(body).unwrap()),
+        )
+    };
+    let bad = serde_json::json!({ "pairs": [{ "host": "alice", "guest": "bob" }], "tournament": "spring cup" });
+    assert_eq!(bulk(bad).await.status(), StatusCode::BAD_REQUEST);
+    let res = bulk(serde_json::json!({
+        "pairs": [{ "host": "alice", "guest": "bob" }],
+        "tournament": "spring-cup",
+        "config": { "game_duration_mins": 30 },
+    }))
+    .await;
+    assert_eq!(res.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let game_id: Uuid = serde_json::from_value(body["games"][0]["game_id"].clone()).unwrap();
+    state.insert_game(Game::new(Uuid::new_v4(), "carol".to_string(), GameConfig::default()));
+
+    let metrics = |key: &str| {
+        backend::metrics::tournament_metrics(State(state.clone()), Query(AdminQuery { token: None }), auth(key))
+    };
+    assert_eq!(metrics("nobody").await.status(), StatusCode::UNAUTHORIZED);
+    let res = metrics("organizer").await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert!(res.headers()["content-type"].to_str().unwrap().starts_with("text/plain"));
+    let text = axum::body::to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let text = String::from_utf8(text.to_vec()).unwrap();
+
+    let game = format!("tournament=\"spring-cup\",game_id=\"{}\"", game_id);
+    assert!(text.contains("# TYPE battlecp_game_time_remaining_seconds gauge"));
+    assert!(text.contains(&format!("battlecp_game_status{{{},status=\"Waiting\"}} 1", game)));
+    assert!(text.contains(&format!("battlecp_game_time_remaining_seconds{{{}}} 1800", game)));
+    assert!(text.contains(&format!("battlecp_player_locked{{{},seat=\"host\",handle=\"alice\"}} 0", game)));
+    assert!(!text.contains("carol"), "untagged games aren't exported");
+}
+
+/// Custom veto settings: an explicit penalty list overrides the strictness
+/// preset, bad lists are refused, and later vetoes repeat the last penalty.
+#[tokio::test]
+async fn test_custom_veto_settings() {
+    let state = AppState::new();
+    let build = |settings: serde_json::Value| {
+        serde_json::from_value::<handlers::GameSettings>(settings).unwrap().build(&state.judges).map(|(c, _)| c)
+    };
+
+    let config = build(serde_json::json!({ "max_vetoes": 5, "veto_penalties": [30, 90], "veto_strictness": "high" })).unwrap();
+    assert_eq!((config.max_vetoes, config.veto_penalties.clone()), (5, vec![30, 90]));
+    let penalties: Vec<_> = (0..5).map(|used| config.veto_penalty(used)).collect();
+    assert_eq!(penalties, [Some(30), Some(90), Some(90), Some(90), Some(90)]);
+
+    assert_eq!(build(serde_json::json!({ "veto_strictness": "high" })).unwrap().veto_penalties, [300, 420, 600]);
+    assert_eq!(build(serde_json::json!({ "max_vetoes": 0 })).unwrap().max_vetoes, 0);
+    assert_eq!(build(serde_json::json!({ "max_vetoes": 99 })).unwrap().max_vetoes, handlers::MAX_VETOES);
+    for bad in [vec![], vec![29], vec![60, 1801], vec![60; 21]] {
+        assert!(build(serde_json::json!({ "veto_penalties": bad })).is_err(), "{:?} accepted", bad);
+    }
+
+    // Heat ramps: steps in order, thresholds clamped like `heat_threshold`
+    let ramp = build(serde_json::json!({ "heat_ramp": [
+        { "after_mins": 15, "heat_threshold": 7 },
+        { "after_mins": 30, "heat_threshold": 1 },
+    ] }))
+    .unwrap()
+    .heat_ramp;
+    assert_eq!(ramp.iter().map(|s| s.heat_threshold).collect::<Vec<_>>(), [7, 3]);
+    let step = |mins: u32| serde_json::json!({ "after_mins": mins, "heat_threshold": 5 });
+    for bad in [vec![step(30), step(15)], vec![step(0)], vec![step(121)], (1..=6).map(step).collect()] {
+        assert!(build(serde_json::json!({ "heat_ramp": bad })).is_err(), "{:?} accepted", bad);
+    }
+
+    // Ammo mode: clamped, and can't be mixed with heat settings
+    let ammo = build(serde_json::json!({ "ammo": { "starting": 0, "per_solve": 99 } })).unwrap().ammo.unwrap();
+    assert_eq!((ammo.starting, ammo.per_solve), (1, 50));
+    let ammo = serde_json::json!({ "starting": 5, "per_solve": 2 });
+    for heat in [
+        serde_json::json!({ "heat_ramp": [step(15)] }),
+        serde_json::json!({ "heat_relief_mins": 5 }),
+        serde_json::json!({ "hint_cost": "lower_max_heat" }),
+    ] {
+        let mut settings = heat.clone();
+        settings["ammo"] = ammo.clone();
+        assert!(build(settings).is_err(), "{} accepted with ammo", heat);
+    }
+
+    // Refused over the API too; accepted settings show up in the snapshot
+    let create = |settings: serde_json::Value| {
+        let mut body = settings;
+        body["cf_handle"] = "host".into();
+        let payload: handlers::CreateGameRequest = serde_json::from_value(body).unwrap();
+        handlers::create_game(State(state.clone()), ClientIp(None), HeaderMap::new(), axum::Json(payload))
+    };
+    let resp = create(serde_json::json!({ "veto_penalties": [5] })).await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let resp = create(serde_json::json!({ "max_vetoes": 4, "veto_penalties": [60, 120] })).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let game_id: Uuid = serde_json::from_value(body["game_id"].clone()).unwrap();
+    let snapshot = state.with_game(game_id, |g| g.snapshot()).await.unwrap();
+    assert_eq!((snapshot.max_vetoes, snapshot.veto_penalties), (4, vec![60, 120]));
+}
+
+/// Board images: none for a private game in progress, shots only while
+/// spectators may watch, both fleets once the game is over.
+#[tokio::test]
+async fn test_board_image_visibility() {
+    let state = AppState::new();
+    let mut game = Game::new(Uuid::new_v4(), "host".to_string(), GameConfig::default());
+    game.join(Uuid::new_v4(), "guest".to_string()).unwrap();
+    game.player1
+        .place_ship(
+            backend::state::Ship { size: 5, hits: 0, sunk: false, x: 0, y: 0, vertical: false },
+            0,
+            0,
+            false,
+        )
+        .unwrap();
+    game.player1.home.grid.receive_shot(9, 9).unwrap();
+    game.status = backend::state::GameStatus::Playing;
+    game.game_started_at = Some(std::time::Instant::now());
+    let game_id = game.id;
+    let handle = state.insert_game(game);
+
+    let svg = |state: AppState| async move {
+        let response = handlers::get_board_svg(State(state), Path(game_id)).await;
+        let status = response.status();
+        let cache = response.headers().get("cache-control").map(|v| v.to_str().unwrap().to_string());
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, cache, String::from_utf8(body.to_vec()).unwrap())
+    };
+
+    let (status, _, _) = svg(state.clone()).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // Spectated: the miss shows, the unhit ship does not
+    handle.call(|g| g.config.referee_feed = true).await.unwrap();
+    let (status, cache, body) = svg(state.clone()).await;
+    assert_eq!((status, cache.as_deref()), (StatusCode::OK, Some("no-store")));
+    assert!(body.contains("host&apos;s fleet") && body.contains("guest&apos;s fleet"));
+    assert!(body.contains("#cbd5e1") && !body.contains("#64748b"), "{}", body);
+
+    handle.call(|g| g.status = backend::state::GameStatus::Finished).await.unwrap();
+    let (status, cache, body) = svg(state.clone()).await;
+    assert_eq!((status, cache.as_deref()), (StatusCode::OK, Some("public, max-age=86400")));
+    assert!(body.contains("#64748b"));
+
+    let response = handlers::get_board_png(State(state.clone()), Path(game_id)).await;
+    assert_eq!(response.headers()["content-type"], "image/png");
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert!(body.starts_with(b"\x89PNG\r\n\x1a\n"));
+
+    let response = handlers::get_board_png(State(state), Path(Uuid::new_v4())).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+/// Contest responses carry an ETag that a repeat request can revalidate with
+/// (304, no body), and the response cache drops its least recently used entry.
+#[tokio::test]
+async fn test_contest_problems_etag() {
+    use axum::http::header;
+    use backend::response_cache::ResponseCache;
+
+    let state = AppState::new();
+    let body = serde_json::to_vec(&serde_json::json!({ "problems": [] })).unwrap();
+    let cached = state.contest_responses.insert(1950, body.into());
+    let fetch = |if_none_match: Option<&str>| {
+        let mut headers = HeaderMap::new();
+        if let Some(tag) = if_none_match {
+            headers.insert(header::IF_NONE_MATCH, tag.parse().unwrap());
+        }
+        handlers::get_contest_problems(State(state.clone()), Path(1950), headers)
+    };
+
+    let resp = fetch(None).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()[header::ETAG], cached.etag.as_str());
+    assert_eq!(resp.headers()[header::CACHE_CONTROL], "public, max-age=300");
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(bytes, cached.body);
+
+    let resp = fetch(Some(&format!("W/\"other\", {}", cached.etag))).await;
+    assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(resp.headers()[header::ETAG], cached.etag.as_str());
+    assert!(axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap().is_empty());
+    assert_eq!(fetch(Some("\"stale\"")).await.status(), StatusCode::OK);
+
+    let lru = ResponseCache::new(std::time::Duration::from_secs(60), 2);
+    lru.insert(1, "a".into());
+    lru.insert(2, "b".into());
+    assert!(lru.get(1).is_some());
+    lru.insert(3, "c".into());
+    assert!(lru.get(2).is_none(), "2 was the least recently used");
+    assert!(lru.get(1).is_some() && lru.get(3).is_some());
+    assert_eq!(lru.insert(4, "c".into()).etag, lru.get(3).unwrap().etag, "same body, same tag");
+}
@@ -0,0 +1,51 @@
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use backend::admin::{self, AdminKeys, AdminQuery, LogFilterRequest, Scope};
+use backend::state::AppState;
+use tracing::Level;
+
+/// Organizers can raise verbosity for one target, or one game's spans, at runtime.
+#[tokio::test]
+async fn test_runtime_log_filter() {
+    let log = backend::logging::init("warn");
+    let mut state = AppState::new();
+    state.admin_keys = std::sync::Arc::new(AdminKeys::new([
+        ("observer", Scope::Read),
+        ("organizer", Scope::Manage),
+    ]));
+    let bearer = |key: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, format!("Bearer {}", key).parse().unwrap());
+        headers
+    };
+    let set = |key: &'static str, filter: &str| {
+        admin::set_log_filter(
+            State(state.clone()),
+            Query(AdminQuery { token: None }),
+            bearer(key),
+            axum::Json(LogFilterRequest { filter: filter.to_string() }),
+        )
+    };
+
+    let res = admin::get_log_filter(State(state.clone()), Query(AdminQuery { token: None }), bearer("observer")).await;
+    let body = axum::body::to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(serde_json::from_slice::<serde_json::Value>(&body).unwrap()["filter"], "warn");
+
+    assert_eq!(set("observer", "debug").await.status(), StatusCode::FORBIDDEN);
+    assert_eq!(set("organizer", "game=loud").await.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(log.current(), "warn", "a bad filter changes nothing");
+    assert!(!tracing::enabled!(target: "game", Level::DEBUG));
+
+    assert_eq!(set("organizer", "warn,game=debug").await.status(), StatusCode::OK);
+    assert!(tracing::enabled!(target: "game", Level::DEBUG));
+    assert!(!tracing::enabled!(target: "ws", Level::DEBUG));
+
+    // Only the spans of one game
+    assert_eq!(set("organizer", "warn,[{game_id=watched}]=debug").await.status(), StatusCode::OK);
+    assert!(!tracing::enabled!(target: "ws", Level::DEBUG));
+    let watched = tracing::info_span!("ws", game_id = "watched");
+    let other = tracing::info_span!("ws", game_id = "other");
+    watched.in_scope(|| assert!(tracing::enabled!(target: "ws", Level::DEBUG)));
+    other.in_scope(|| assert!(!tracing::enabled!(target: "ws", Level::DEBUG)));
+    assert_eq!(log.current(), "warn,[{game_id=watched}]=debug");
+}
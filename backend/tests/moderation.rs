@@ -0,0 +1,130 @@
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use backend::admin::{self, AdminKeys, AdminQuery, BanRequest, Scope};
+use backend::moderation::{BanList, Cidr};
+use backend::rate_limit::ClientIp;
+use backend::state::{AppState, Game, GameConfig};
+use uuid::Uuid;
+
+#[test]
+fn test_cidr_matching() {
+    let net: Cidr = "203.0.113.77/24".parse().unwrap();
+    assert_eq!(net.to_string(), "203.0.113.0/24");
+    assert!(net.contains("203.0.113.1".parse().unwrap()));
+    assert!(!net.contains("203.0.114.1".parse().unwrap()));
+    assert!(net.contains("::ffff:203.0.113.9".parse().unwrap()), "IPv4-mapped peers match");
+
+    let host: Cidr = "198.51.100.7".parse().unwrap();
+    assert_eq!(host.to_string(), "198.51.100.7/32");
+    assert!(!host.contains("198.51.100.8".parse().unwrap()));
+
+    let v6: Cidr = "2001:db8::/32".parse().unwrap();
+    assert!(v6.contains("2001:db8:1::1".parse().unwrap()));
+    assert!(!v6.contains("10.0.0.1".parse().unwrap()));
+
+    let everyone: Cidr = "0.0.0.0/0".parse().unwrap();
+    assert!(everyone.contains("8.8.8.8".parse().unwrap()));
+
+    for bad in ["10.0.0.0/33", "10.0.0/8", "::/129", "host/8", ""] {
+        assert!(bad.parse::<Cidr>().is_err(), "{:?} accepted", bad);
+    }
+}
+
+#[test]
+fn test_ban_list() {
+    let bans = BanList::default();
+    let ip = Some("10.1.2.3".parse().unwrap());
+    assert!(!bans.is_banned("tourist", ip));
+
+    assert!(bans.ban_handle("Cheater"));
+    assert!(!bans.ban_handle(" cheater "), "handles are case-insensitive");
+    assert!(bans.is_banned("CHEATER", None));
+
+    bans.ban_cidr("10.0.0.0/8".parse().unwrap());
+    assert!(bans.is_banned("tourist", ip));
+    assert!(!bans.is_banned("tourist", None));
+
+    assert!(bans.unban_cidr("10.9.9.9/8".parse().unwrap()), "same network, same ban");
+    assert!(bans.unban_handle("cheater"));
+    assert!(!bans.is_banned("cheater", ip));
+}
+
+/// Manage keys edit the list through the admin API; banned handles and IPs
+/// can no longer create games.
+#[tokio::test]
+async fn test_admin_bans_block_game_creation() {
+    let mut state = AppState::new();
+    state.admin_keys = std::sync::Arc::new(AdminKeys::new([
+        ("observer", Scope::Read),
+        ("organizer", Scope::Manage),
+    ]));
+    let bearer = |key: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, format!("Bearer {}", key).parse().unwrap());
+        headers
+    };
+    let no_token = || Query(AdminQuery { token: None });
+    let ban = |handle: Option<&str>, cidr: Option<&str>| BanRequest {
+        handle: handle.map(str::to_string),
+        cidr: cidr.map(str::to_string),
+        token: None,
+    };
+
+    let res = admin::add_ban(State(state.clone()), no_token(), bearer("observer"), axum::Json(ban(Some("cheater"), None))).await;
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    let res = admin::add_ban(State(state.clone()), no_token(), bearer("organizer"), axum::Json(ban(None, Some("not-a-cidr")))).await;
+    assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    let res = admin::add_ban(State(state.clone()), no_token(), bearer("organizer"), axum::Json(ban(Some("Cheater"), Some("192.0.2.0/24")))).await;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let res = admin::list_bans(State(state.clone()), no_token(), bearer("observer")).await;
+    let body = axum::body::to_bytes(res.into_body(), usize::MAX).await.unwrap();
+    let listed: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(listed["handles"], serde_json::json!(["cheater"]));
+    assert_eq!(listed["cidrs"], serde_json::json!(["192.0.2.0/24"]));
+
+    let create = |handle: &str, ip: &str| {
+        backend::handlers::create_game(
+            State(state.clone()),
+            ClientIp(Some(ip.parse().unwrap())),
+            HeaderMap::new(),
+            axum::Json(serde_json::from_value(serde_json::json!({ "cf_handle": handle })).unwrap()),
+        )
+    };
+    assert_eq!(create("cheater", "198.51.100.1").await.status(), StatusCode::FORBIDDEN);
+    assert_eq!(create("tourist", "192.0.2.50").await.status(), StatusCode::FORBIDDEN);
+    assert_eq!(create("tourist", "198.51.100.1").await.status(), StatusCode::CREATED);
+
+    // Lifting the handle ban lets them back in (from an unbanned IP)
+    let res = admin::remove_ban(State(state.clone()), Query(ban(Some("cheater"), None)), bearer("organizer")).await;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(create("cheater", "198.51.100.2").await.status(), StatusCode::CREATED);
+}
+
+/// A banned handle can't take the guest seat.
+#[tokio::test]
+async fn test_banned_handle_cannot_join() {
+    let app_state = AppState::new();
+    app_state.bans.ban_handle("cheater");
+    let app = axum::Router::new()
+        .route("/ws/{game_id}", axum::routing::get(backend::ws::ws_handler))
+        .with_state(app_state.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let game = Game::new(Uuid::new_v4(), "host".to_string(), GameConfig::default());
+    let game_id = game.id;
+    let handle = app_state.insert_game(game);
+
+    let seat = battle_cp_client::Seat::guest(game_id, "Cheater");
+    let code = match battle_cp_client::Client::join(&format!("http://{}", addr), seat).await {
+        Err(battle_cp_client::ClientError::Server { code, .. }) => Some(code),
+        Err(e) => panic!("join failed: {}", e),
+        Ok(_) => panic!("banned handle was seated"),
+    };
+    assert_eq!(code.as_deref(), Some("banned"));
+    assert!(handle.call(|g| g.player2.is_none()).await.unwrap());
+}
@@ -0,0 +1,92 @@
+use backend::rate_limit::{limit_by_ip, IpRateLimiter, KeyedLimiter, VerifyLimiters};
+use std::net::SocketAddr;
+use tokio::time::Duration;
+
+/// Limiter counts per IP and resets after its window.
+#[tokio::test]
+async fn test_ip_limiter_window() {
+    let limiter = IpRateLimiter::new(2, Duration::from_millis(200));
+    let a = "10.0.0.1".parse().unwrap();
+    let b = "10.0.0.2".parse().unwrap();
+
+    assert!(limiter.check(a).await.is_ok());
+    assert!(limiter.check(a).await.is_ok());
+    assert!(limiter.check(a).await.is_err(), "third hit should be limited");
+    assert!(limiter.check(b).await.is_ok(), "other IPs are unaffected");
+
+    tokio::time::sleep(Duration::from_millis(250)).await;
+    assert_eq!(limiter.purge_expired().await, 2);
+    assert!(limiter.check(a).await.is_ok(), "window should have reset");
+}
+
+/// Middleware reports the quota on every response, and answers 429 with
+/// Retry-After once the peer IP is over its limit.
+#[tokio::test]
+async fn test_middleware_returns_429() {
+    let limiter = IpRateLimiter::new(1, Duration::from_secs(60));
+    let app = axum::Router::new().route(
+        "/limited",
+        axum::routing::get(|| async { "ok" })
+            .layer(axum::middleware::from_fn_with_state(limiter, limit_by_ip)),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(
+            listener,
+            app.into_make_service_with_connect_info::<SocketAddr>(),
+        )
+        .await
+        .unwrap();
+    });
+
+    let url = format!("http://{}/limited", addr);
+    let header = |resp: &reqwest::Response, name: &str| -> u64 {
+        resp.headers()[name].to_str().unwrap().parse().unwrap()
+    };
+    let first = reqwest::get(&url).await.unwrap();
+    assert_eq!(first.status(), 200);
+    assert_eq!((header(&first, "ratelimit-limit"), header(&first, "ratelimit-remaining")), (1, 0));
+
+    let second = reqwest::get(&url).await.unwrap();
+    assert_eq!(second.status(), 429);
+    let retry_after = header(&second, "retry-after");
+    assert!((1..=60).contains(&retry_after));
+    assert_eq!(header(&second, "ratelimit-reset"), retry_after);
+    let body: serde_json::Value = second.json().await.unwrap();
+    assert_eq!(body["retry_after_secs"], retry_after);
+}
+
+/// Verification budgets are shared per game and per IP (or per bot): exhausting
+/// either one throttles, spending in one game doesn't touch another game's budget,
+/// and a refused verification spends neither.
+#[test]
+fn test_verify_budgets() {
+    let limiters = VerifyLimiters {
+        per_game: KeyedLimiter::new(2, Duration::from_secs(60)),
+        per_ip: KeyedLimiter::new(3, Duration::from_secs(60)),
+        per_bot: KeyedLimiter::new(1, Duration::from_secs(60)),
+    };
+    let (game_a, game_b) = (uuid::Uuid::new_v4(), uuid::Uuid::new_v4());
+    let ip = Some("10.0.0.1".parse().unwrap());
+    let other_ip = Some("10.0.0.2".parse().unwrap());
+
+    assert!(limiters.check(game_a, ip, None).is_ok());
+    assert!(limiters.check(game_a, other_ip, None).is_ok());
+    let retry = limiters.check(game_a, ip, None).expect_err("game A is out of budget");
+    assert!(retry <= Duration::from_secs(60));
+
+    // The IP spent 1 in game A (the refused attempt never reached it)
+    assert!(limiters.check(game_b, ip, None).is_ok());
+    assert!(limiters.check(game_b, ip, None).is_ok());
+    assert!(limiters.check(game_b, ip, None).is_err(), "IP is out of budget");
+    let game_c = uuid::Uuid::new_v4();
+    assert!(limiters.check(game_c, ip, None).is_err());
+    assert!(limiters.check(game_c, other_ip, None).is_ok());
+    assert!(limiters.check(game_c, other_ip, None).is_ok(), "the refusal left game C's budget alone");
+    assert!(limiters.check(uuid::Uuid::new_v4(), None, None).is_ok(), "no IP, only the game budget applies");
+
+    // A bot spends its own budget, not its (exhausted) IP's
+    assert!(limiters.check(uuid::Uuid::new_v4(), ip, Some("practice-bot")).is_ok());
+    assert!(limiters.check(uuid::Uuid::new_v4(), ip, Some("practice-bot")).is_err());
+}
@@ -28,20 +28,17 @@ async fn realistic_tournament_50_games_init_flood() {
     
     // Create and insert all games
     let mut game_ids = Vec::new();
-    {
-        let mut games = state.games.write().await;
-        for i in 0..50 {
-            let p1_id = Uuid::new_v4();
-            let p2_id = Uuid::new_v4();
-            let mut g = Game::new(p1_id, format!("p1_{}", i), GameConfig::default());
-            g.join(p2_id, format!("p2_{}", i)).unwrap();
-
-            // Mark as Waiting (before placement)
-            g.status = GameStatus::Waiting;
-            let gid = g.id;
-            games.insert(gid, g);
-            game_ids.push(gid);
-        }
+    for i in 0..50 {
+        let p1_id = Uuid::new_v4();
+        let p2_id = Uuid::new_v4();
+        let mut g = Game::new(p1_id, format!("p1_{}", i), GameConfig::default());
+        g.join(p2_id, format!("p2_{}", i)).unwrap();
+
+        // Mark as Waiting (before placement)
+        g.status = GameStatus::Waiting;
+        let gid = g.id;
+        state.insert_game(g);
+        game_ids.push(gid);
     }
 
     println!(
@@ -68,24 +65,9 @@ async fn realistic_tournament_50_games_init_flood() {
                 (0, 4, 2, false),   // Destroyer
             ];
 
-            let mut games = state_clone.games.write().await;
-            if let Some(game) = games.get_mut(&gid_copy) {
-                // Place for P1
-                for (x, y, size, vert) in &placements {
-                    let ship = Ship {
-                        size: *size,
-                        hits: 0,
-                        sunk: false,
-                        x: *x,
-                        y: *y,
-                        vertical: *vert,
-                    };
-                    let _ = game.player1.place_ship(ship, *x, *y, *vert);
-                }
-                game.player1.ships_placed = true;
-
-                // Place for P2
-                if let Some(ref mut p2) = game.player2 {
+            state_clone
+                .with_game(gid_copy, move |game| {
+                    // Place for P1
                     for (x, y, size, vert) in &placements {
                         let ship = Ship {
                             size: *size,
@@ -95,15 +77,31 @@ async fn realistic_tournament_50_games_init_flood() {
                             y: *y,
                             vertical: *vert,
                         };
-                        let _ = p2.place_ship(ship, *x, *y, *vert);
+                        let _ = game.player1.place_ship(ship, *x, *y, *vert);
+                    }
+                    game.player1.ships_placed = true;
+
+                    // Place for P2
+                    if let Some(ref mut p2) = game.player2 {
+                        for (x, y, size, vert) in &placements {
+                            let ship = Ship {
+                                size: *size,
+                                hits: 0,
+                                sunk: false,
+                                x: *x,
+                                y: *y,
+                                vertical: *vert,
+                            };
+                            let _ = p2.place_ship(ship, *x, *y, *vert);
+                        }
+                        p2.ships_placed = true;
                     }
-                    p2.ships_placed = true;
-                }
 
-                // Mark as PlacingShips (ready for init)
-                game.status = GameStatus::PlacingShips;
-                game.placement_started_at = Some(std::time::Instant::now());
-            }
+                    // Mark as PlacingShips (ready for init)
+                    game.status = GameStatus::PlacingShips;
+                    game.placement_started_at = Some(std::time::Instant::now());
+                })
+                .await;
         });
         handles.push(h);
     }
@@ -125,13 +123,14 @@ async fn realistic_tournament_50_games_init_flood() {
     // That triggers tokio::spawn(init_game_from_cf(...)) which fetches CF data.
     // Here, we just mark the state — the spawned tasks aren't running without
     // the full WS handler integration. This shows the state transition overhead.
-    {
-        let mut games = state.games.write().await;
-        for g in games.values_mut() {
-            if g.status == GameStatus::PlacingShips {
-                g.status = GameStatus::Initializing;
-            }
-        }
+    for handle in state.all_games() {
+        handle
+            .call(|g| {
+                if g.status == GameStatus::PlacingShips {
+                    g.status = GameStatus::Initializing;
+                }
+            })
+            .await;
     }
 
     println!(
@@ -154,10 +153,10 @@ async fn realistic_tournament_50_games_init_flood() {
     sleep(std::time::Duration::from_secs(2)).await;
 
     let (initializing_final, playing_final, finished_final) = {
-        let games = state.games.read().await;
-        let init_count = games.values().filter(|g| g.status == GameStatus::Initializing).count();
-        let play_count = games.values().filter(|g| g.status == GameStatus::Playing).count();
-        let fin_count = games.values().filter(|g| g.status == GameStatus::Finished).count();
+        let games = state.all_games();
+        let init_count = games.iter().filter(|g| g.status() == GameStatus::Initializing).count();
+        let play_count = games.iter().filter(|g| g.status() == GameStatus::Playing).count();
+        let fin_count = games.iter().filter(|g| g.status() == GameStatus::Finished).count();
         (init_count, play_count, fin_count)
     };
 
@@ -0,0 +1,58 @@
+mod common;
+
+use backend::protocol::{Coord, RefereeEvent, RefereeMessage};
+use backend::state::GameConfig;
+use common::{msg, TestServer};
+use futures::StreamExt;
+use tokio::time::{sleep, timeout, Duration};
+use tokio_tungstenite::connect_async;
+
+async fn next_line<S>(read: &mut S) -> RefereeMessage
+where
+    S: futures::Stream<Item = Result<tokio_tungstenite::tungstenite::Message, tokio_tungstenite::tungstenite::Error>>
+        + Unpin,
+{
+    let msg = timeout(Duration::from_secs(2), read.next())
+        .await
+        .expect("timed out")
+        .expect("closed")
+        .unwrap();
+    serde_json::from_str(msg.to_text().unwrap()).unwrap()
+}
+
+/// The feed is opt-in, opens with ship counts, and reports sinks and locks
+/// without coordinates.
+#[tokio::test]
+async fn test_referee_feed_narrates_without_coordinates() {
+    let server = TestServer::start().await;
+    let closed = server.in_combat(GameConfig::default());
+    let refused = connect_async(format!("ws://{}/ws/{}/referee", server.addr(), closed.id)).await;
+    assert!(refused.is_err(), "games without a referee feed refuse spectators");
+
+    let game = server.in_combat(GameConfig { referee_feed: true, heat_threshold: 2, ..GameConfig::default() });
+    let (ws, _) = connect_async(format!("ws://{}/ws/{}/referee", server.addr(), game.id))
+        .await
+        .expect("Failed to connect");
+    let (_write, mut read) = ws.split();
+    for _ in 0..2 {
+        let line = next_line(&mut read).await;
+        assert!(matches!(line.event, RefereeEvent::ShipsLeft { ships_left: 5, .. }), "{:?}", line);
+    }
+
+    // Players are told someone is watching
+    let mut host = server.connect(game.host_seat()).await;
+    assert_eq!(host.expect_msg::<msg::SpectatorCount>().await.count, 1);
+
+    // Sinking the destroyer (A9, B9) also overheats the host at 2 shots
+    for x in 0..2 {
+        host.fire(Coord { x, y: 8 }).await.unwrap();
+        host.expect_msg::<msg::ShotResult>().await;
+        sleep(Duration::from_millis(250)).await;
+    }
+    let sunk = next_line(&mut read).await;
+    assert_eq!(sunk.event, RefereeEvent::ShipsLeft { player_id: game.guest.unwrap(), ships_left: 4 });
+    assert_eq!(sunk.text, "Guest has 4 ships left");
+    let locked = next_line(&mut read).await;
+    assert_eq!(locked.event, RefereeEvent::Locked { player_id: game.host });
+    assert_eq!(locked.text, "Host overheated and is locked out");
+}
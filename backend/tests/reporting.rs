@@ -0,0 +1,58 @@
+use backend::reporting::{self, TaskContext};
+use std::sync::{Arc, Mutex};
+use tokio::time::{sleep, Duration};
+use uuid::Uuid;
+
+/// Panics in spawned game tasks and reported errors reach the webhook with
+/// their task and game.
+#[tokio::test]
+async fn test_reports_panics_with_task_context() {
+    let received: Arc<Mutex<Vec<serde_json::Value>>> = Arc::default();
+    let sink = received.clone();
+    let app = axum::Router::new().route(
+        "/hook",
+        axum::routing::post(move |axum::Json(body): axum::Json<serde_json::Value>| {
+            let sink = sink.clone();
+            async move { sink.lock().unwrap().push(body) }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    // Read once, when the config is first used
+    std::env::set_var("ERROR_REPORT_URL", format!("http://{}/hook", addr));
+    reporting::install_panic_hook();
+
+    let game_id = Uuid::new_v4();
+    assert_eq!(reporting::current_task(), None);
+    let context = reporting::in_task("game_actor", Some(game_id), async { reporting::current_task() }).await;
+    assert_eq!(context, Some(TaskContext { task: "game_actor", game_id: Some(game_id) }));
+
+    let crashed = tokio::spawn(reporting::in_task("game_actor", Some(game_id), async {
+        panic!("ticker exploded");
+    }))
+    .await;
+    assert!(crashed.unwrap_err().is_panic());
+    reporting::report_error(None, "CF verification failed");
+
+    let mut reports = Vec::new();
+    for _ in 0..100 {
+        reports = received.lock().unwrap().clone();
+        if reports.len() == 2 {
+            break;
+        }
+        sleep(Duration::from_millis(20)).await;
+    }
+    let panic = reports.iter().find(|r| r["report"]["kind"] == "panic").expect("panic reported");
+    assert_eq!(panic["report"]["message"], "ticker exploded");
+    assert_eq!(panic["report"]["task"], "game_actor");
+    assert_eq!(panic["report"]["game_id"], game_id.to_string());
+    assert!(panic["report"]["location"].as_str().unwrap().contains("tests/reporting.rs"));
+    assert!(panic["content"].as_str().unwrap().contains("ticker exploded"));
+
+    let error = reports.iter().find(|r| r["report"]["kind"] == "error").expect("error reported");
+    assert_eq!(error["report"]["message"], "CF verification failed");
+    assert!(error["report"]["game_id"].is_null());
+}
@@ -0,0 +1,244 @@
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderMap, StatusCode};
+use axum::response::Response;
+use axum::Json;
+use backend::handlers::{self, RoomMemberRequest};
+use backend::rate_limit::ClientIp;
+use backend::state::AppState;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+use uuid::Uuid;
+
+async fn body(resp: Response) -> Value {
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+fn member(handle: &str, token: Option<&str>) -> RoomMemberRequest {
+    RoomMemberRequest {
+        cf_handle: handle.to_string(),
+        token: token.map(str::to_string),
+    }
+}
+
+async fn join(state: &AppState, slug: &str, handle: &str, token: Option<&str>) -> Response {
+    handlers::join_room(
+        State(state.clone()),
+        ClientIp(None),
+        Path(slug.to_string()),
+        HeaderMap::new(),
+        Json(member(handle, token)),
+    )
+    .await
+}
+
+/// The room's slug and each member's room token, by handle.
+async fn room_with_members(state: &AppState, name: &str, handles: &[&str]) -> (String, HashMap<String, String>) {
+    let resp = handlers::create_room(
+        State(state.clone()),
+        Json(serde_json::from_value(json!({ "name": name })).unwrap()),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let slug = body(resp).await["slug"].as_str().unwrap().to_string();
+    let mut tokens = HashMap::new();
+    for handle in handles {
+        let resp = join(state, &slug, handle, None).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let token = body(resp).await["token"].as_str().unwrap().to_string();
+        tokens.insert(handle.to_string(), token);
+    }
+    (slug, tokens)
+}
+
+async fn challenge(state: &AppState, slug: &str, from: &str, to: &str, token: &str) -> Response {
+    handlers::create_challenge(
+        State(state.clone()),
+        ClientIp(None),
+        Path(slug.to_string()),
+        HeaderMap::new(),
+        Json(serde_json::from_value(json!({ "from": from, "to": to, "token": token, "difficulty": 2 })).unwrap()),
+    )
+    .await
+}
+
+/// Members challenge each other; only the challenged member can take the seat,
+/// and a finished game lands on the room leaderboard.
+#[tokio::test]
+async fn test_room_challenge_and_leaderboard() {
+    let state = AppState::new();
+    let (slug, tokens) = room_with_members(&state, "CP Club @ IIT", &["alice", "Bob"]).await;
+    assert_eq!(slug, "cp-club-iit");
+    let (alice, bob) = (tokens["alice"].as_str(), tokens["Bob"].as_str());
+
+    // Same slug is taken; non-members can't challenge or be challenged
+    let resp = handlers::create_room(
+        State(state.clone()),
+        Json(serde_json::from_value(json!({ "name": "cp club iit" })).unwrap()),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    assert_eq!(
+        challenge(&state, &slug, "alice", "carol", alice).await.status(),
+        StatusCode::FORBIDDEN
+    );
+
+    let resp = challenge(&state, &slug, "alice", "bob", alice).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let created = body(resp).await;
+    let game_id: Uuid = serde_json::from_value(created["game_id"].clone()).unwrap();
+    let host_id: Uuid = serde_json::from_value(created["player_id"].clone()).unwrap();
+    let token = created["token"].as_str().unwrap();
+    assert!(state
+        .sessions
+        .verify(token)
+        .unwrap()
+        .allows(host_id, game_id));
+
+    let room = body(handlers::get_room(State(state.clone()), Path(slug.clone())).await).await;
+    assert_eq!(room["members"].as_array().unwrap().len(), 2);
+    assert_eq!(room["challenges"][0]["to"], "Bob");
+    assert_eq!(room["games"][0]["room"], slug.as_str());
+
+    let accept = |handle: &str, token: &str| {
+        handlers::accept_challenge(
+            State(state.clone()),
+            ClientIp(None),
+            Path((slug.clone(), game_id)),
+            HeaderMap::new(),
+            Json(member(handle, Some(token))),
+        )
+    };
+    assert_eq!(accept("alice", alice).await.status(), StatusCode::FORBIDDEN);
+    // Naming Bob isn't enough without his room token
+    assert_eq!(accept("bob", alice).await.status(), StatusCode::UNAUTHORIZED);
+    let resp = accept("bob", bob).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let guest_id: Uuid = serde_json::from_value(body(resp).await["player_id"].clone()).unwrap();
+    assert_eq!(accept("bob", bob).await.status(), StatusCode::NOT_FOUND);
+
+    // The reserved seat is Bob's, under his handle; he wins the game
+    let (stranger, impostor, seated) = state
+        .with_game(game_id, move |g| {
+            let stranger = g.seat_guest(Uuid::new_v4(), "mallory".to_string());
+            let impostor = g.seat_guest(guest_id, "mallory".to_string());
+            let seated = g.seat_guest(guest_id, "Bob".to_string());
+            g.game_started_at = Some(std::time::Instant::now());
+            g.finish(Some(guest_id), "AllShipsSunk");
+            (stranger, impostor, seated)
+        })
+        .await
+        .unwrap();
+    assert_eq!(stranger, Err(backend::error::GameError::SeatReserved));
+    assert_eq!(impostor, Err(backend::error::GameError::SeatReserved));
+    assert_eq!(seated, Ok(()));
+
+    let mut leaderboard = Value::Null;
+    for _ in 0..50 {
+        let room = body(handlers::get_room(State(state.clone()), Path(slug.clone())).await).await;
+        leaderboard = room["leaderboard"].clone();
+        if !leaderboard.as_array().unwrap().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(leaderboard[0]["cf_handle"], "Bob");
+    assert_eq!(leaderboard[0]["wins"], 1);
+    assert_eq!(leaderboard[1]["cf_handle"], "alice");
+    assert_eq!(leaderboard[1]["losses"], 1);
+
+    // Also on its own, paged by rank
+    let page = |query: Value| {
+        handlers::get_room_leaderboard(State(state.clone()), Path(slug.clone()), Query(serde_json::from_value(query).unwrap()))
+    };
+    let first = body(page(json!({ "limit": 1 })).await).await;
+    assert_eq!(first["leaderboard"][0]["rank"], 1);
+    assert_eq!(first["leaderboard"][0]["standing"]["cf_handle"], "Bob");
+    assert_eq!(first["next_cursor"], "1");
+    let second = body(page(json!({ "limit": 1, "cursor": "1" })).await).await;
+    assert_eq!(second["leaderboard"][0]["standing"]["cf_handle"], "alice");
+    assert!(second["next_cursor"].is_null());
+}
+
+/// Declining closes the challenge and its lobby.
+#[tokio::test]
+async fn test_room_challenge_decline() {
+    let state = AppState::new();
+    let (slug, tokens) = room_with_members(&state, "Friday Duels", &["alice", "bob", "carol"]).await;
+    let created = body(challenge(&state, &slug, "alice", "bob", &tokens["alice"]).await).await;
+    let game_id: Uuid = serde_json::from_value(created["game_id"].clone()).unwrap();
+    let mut rx = state.game(game_id).unwrap().tx.subscribe();
+
+    let decline = |handle: &str| {
+        handlers::decline_challenge(
+            State(state.clone()),
+            Path((slug.clone(), game_id)),
+            HeaderMap::new(),
+            Json(member(handle, Some(&tokens[handle]))),
+        )
+    };
+    assert_eq!(decline("carol").await.status(), StatusCode::FORBIDDEN);
+    assert_eq!(decline("bob").await.status(), StatusCode::OK);
+    assert!(!state.games.contains_key(&game_id));
+    match rx.recv().await.map(|e| e.message) {
+        Ok(backend::protocol::ServerMessage::GameOver {
+            reason,
+            ..
+        }) => {
+            assert_eq!(reason, "ChallengeDeclined")
+        }
+        other => panic!("Expected GameOver, got {:?}", other),
+    }
+
+    // Leaving the room ends membership; it takes carol's own token
+    let leave = |token: &str| {
+        handlers::leave_room(
+            State(state.clone()),
+            Path(slug.clone()),
+            Query(member("carol", Some(token))),
+            HeaderMap::new(),
+        )
+    };
+    assert_eq!(leave(&tokens["bob"]).await.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(leave(&tokens["carol"]).await.status(), StatusCode::OK);
+    assert_eq!(
+        challenge(&state, &slug, "alice", "carol", &tokens["alice"]).await.status(),
+        StatusCode::FORBIDDEN
+    );
+    let room = body(handlers::get_room(State(state.clone()), Path(slug)).await).await;
+    assert!(room["challenges"].as_array().unwrap().is_empty());
+    assert!(room["games"].as_array().unwrap().is_empty());
+}
+
+/// A member's handle can't be taken over or used without their room token.
+#[tokio::test]
+async fn test_room_actions_need_the_member_token() {
+    let state = AppState::new();
+    let (slug, tokens) = room_with_members(&state, "Token Club", &["alice", "bob"]).await;
+
+    // Rejoining under a member's handle needs their token, and gives it back
+    assert_eq!(join(&state, &slug, "Alice", None).await.status(), StatusCode::CONFLICT);
+    assert_eq!(join(&state, &slug, "alice", Some(&tokens["bob"])).await.status(), StatusCode::CONFLICT);
+    let resp = join(&state, &slug, "alice", Some(&tokens["alice"])).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(body(resp).await["token"], tokens["alice"].as_str());
+
+    // Challenging as alice takes alice's token, also as a bearer header
+    let resp = challenge(&state, &slug, "alice", "bob", &tokens["bob"]).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert_eq!(body(resp).await["code"], "invalid_room_token");
+    let mut headers = HeaderMap::new();
+    headers.insert("authorization", format!("Bearer {}", tokens["alice"]).parse().unwrap());
+    let resp = handlers::create_challenge(
+        State(state.clone()),
+        ClientIp(None),
+        Path(slug.clone()),
+        headers,
+        Json(serde_json::from_value(json!({ "from": "alice", "to": "bob", "difficulty": 2 })).unwrap()),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+}
@@ -0,0 +1,98 @@
+use backend::config::{Config, ServerConfig};
+use backend::server::{self, ListenConfig, TlsPaths};
+use tokio::time::Duration;
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn test_listen_config() {
+    let default = ListenConfig::from_config(&ServerConfig::default());
+    assert_eq!(default.addr, "0.0.0.0:3000".parse().unwrap());
+    assert_eq!(default.scheme(), "http");
+
+    let env = |var: &str| match var {
+        "BIND_ADDR" => Some("::1".to_string()),
+        "PORT" => Some("8443".to_string()),
+        "TLS_CERT_PATH" => Some("c.pem".to_string()),
+        "TLS_KEY_PATH" => Some("k.pem".to_string()),
+        _ => None,
+    };
+    let config = Config::from_sources(None, env).unwrap();
+    let custom = ListenConfig::from_config(&config.server);
+    assert_eq!(custom.addr, "[::1]:8443".parse().unwrap());
+    assert_eq!(custom.tls, Some(TlsPaths { cert: "c.pem".into(), key: "k.pem".into() }));
+    assert_eq!(custom.scheme(), "https");
+}
+
+#[tokio::test]
+async fn test_tls_load_errors() {
+    let missing = TlsPaths { cert: fixture("missing.crt").into(), key: fixture("localhost.key").into() };
+    let err = server::load_tls(&missing).await.unwrap_err();
+    assert!(err.to_string().contains("missing.crt"));
+}
+
+/// With a cert configured the server answers HTTPS directly.
+#[tokio::test]
+async fn test_serves_https() {
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let config = ListenConfig::from_config(&ServerConfig {
+        bind_addr: "127.0.0.1".parse().unwrap(),
+        port,
+        tls_cert_path: Some(fixture("localhost.crt").into()),
+        tls_key_path: Some(fixture("localhost.key").into()),
+        ..ServerConfig::default()
+    });
+    let app = axum::Router::new().route("/health", axum::routing::get(|| async { "ok" }));
+    tokio::spawn(server::serve(app, config));
+
+    let ca = reqwest::Certificate::from_pem(&std::fs::read(fixture("localhost.crt")).unwrap()).unwrap();
+    let client = reqwest::Client::builder().add_root_certificate(ca).build().unwrap();
+    let mut body = None;
+    for _ in 0..50 {
+        if let Ok(res) = client.get(format!("https://localhost:{}/health", port)).send().await {
+            body = Some(res.text().await.unwrap());
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(body.as_deref(), Some("ok"));
+}
+
+/// The engine mounted under a prefix in a host app: its routes and sockets
+/// work there, and games land in the registry the host injected.
+#[tokio::test]
+async fn test_embedded_under_prefix() {
+    let games = std::sync::Arc::new(dashmap::DashMap::new());
+    let battle = backend::Server::builder()
+        .config(Config::default())
+        .games(games.clone())
+        .without_background_tasks()
+        .build();
+    let app = axum::Router::new()
+        .route("/", axum::routing::get(|| async { "training platform" }))
+        .nest("/battle", battle.router());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+            .await
+            .unwrap();
+    });
+
+    let home = reqwest::get(&base).await.unwrap().text().await.unwrap();
+    assert_eq!(home, "training platform");
+    let health: serde_json::Value = reqwest::get(format!("{}/battle/health", base)).await.unwrap().json().await.unwrap();
+    assert_eq!(health["status"], "ok");
+
+    let engine = format!("{}/battle", base);
+    let seat = battle_cp_client::create_game(&engine, "tourist", &serde_json::json!({})).await.unwrap();
+    assert!(games.contains_key(&seat.game_id));
+    assert!(battle.state().game(seat.game_id).is_some());
+    let mut client = battle_cp_client::Client::join(&engine, seat).await.unwrap();
+    let joined = client
+        .wait_for(|m| matches!(m, backend::protocol::ServerMessage::GameJoined { .. }), Duration::from_secs(2))
+        .await;
+    assert!(joined.is_ok(), "no GameJoined over the nested socket: {:?}", joined.err());
+}
@@ -0,0 +1,273 @@
+use backend::protocol::{Coord, ServerMessage, ShipPlacement};
+use backend::sim::{Action, Side, Simulation};
+use backend::state::{GameConfig, GameStatus, SuddenDeathMode};
+use std::time::Duration;
+
+/// Five ships on rows 0, 2, 4, 6, 8, bows in column 0.
+fn row_fleet() -> Vec<ShipPlacement> {
+    [5, 4, 3, 3, 2]
+        .iter()
+        .enumerate()
+        .map(|(i, &size)| ShipPlacement {
+            x: 0,
+            y: i * 2,
+            size,
+            vertical: false,
+        })
+        .collect()
+}
+
+/// The host sinks the whole guest fleet, solving each lock along the way.
+#[tokio::test]
+async fn test_scripted_game_ends_all_sunk() {
+    let mut sim = Simulation::new(GameConfig::default(), 0).await;
+    sim.start(row_fleet(), row_fleet()).await.unwrap();
+
+    let targets: Vec<Coord> = row_fleet()
+        .iter()
+        .flat_map(|s| (0..s.size as usize).map(move |i| Coord { x: s.x + i, y: s.y }))
+        .collect();
+    for (shot, at) in targets.into_iter().enumerate() {
+        // The 7th shot locks; the next one is refused until solved
+        if shot > 0 && shot % 7 == 0 {
+            let refused = sim.apply(Action::Fire(Side::Host, at)).await.unwrap();
+            assert!(
+                matches!(&refused[..], [ServerMessage::Error { code, .. }] if code == "weapons_locked")
+            );
+            sim.apply(Action::Solve(Side::Host)).await.unwrap();
+        }
+        assert!(sim
+            .apply(Action::Fire(Side::Host, at))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    let host = sim.id(Side::Host);
+    let (status, stats) = sim
+        .inspect(|g| (g.status.clone(), g.player1.stats.clone()))
+        .await;
+    assert_eq!(status, GameStatus::Finished);
+    assert_eq!(
+        (stats.cells_hit, stats.ships_sunk, stats.problems_solved),
+        (17, 5, 2)
+    );
+    assert!(sim.log.iter().any(|m| matches!(
+        m,
+        ServerMessage::GameOver { winner_id: Some(w), reason, .. } if *w == host && reason == "AllShipsSunk"
+    )));
+}
+
+/// A solve inside `fast_solve_secs` of the lock earns a cross shot; a slow
+/// one doesn't. The cross resolves every new cell it covers and is used up.
+#[tokio::test]
+async fn test_fast_solve_earns_cross_shot() {
+    let config = GameConfig { heat_threshold: 1, fast_solve_secs: Some(300), ..GameConfig::default() };
+    let mut sim = Simulation::new(config, 2).await;
+    sim.start(row_fleet(), row_fleet()).await.unwrap();
+    let host = sim.id(Side::Host);
+    let granted = |log: &[ServerMessage]| {
+        log.iter()
+            .filter(|m| matches!(m, ServerMessage::WeaponGranted { player_id, .. } if *player_id == host))
+            .count()
+    };
+
+    let cross = backend::protocol::SpecialWeapon::Cross;
+    let at = Coord { x: 1, y: 1 };
+    let refused = sim.apply(Action::FireSpecial(Side::Host, cross, at)).await.unwrap();
+    assert!(
+        matches!(&refused[..], [ServerMessage::Error { code, .. }] if code == "no_special_weapon")
+    );
+
+    // Slow: locked for over 5 minutes
+    sim.apply(Action::Fire(Side::Host, Coord { x: 9, y: 9 })).await.unwrap();
+    sim.apply(Action::Advance(Duration::from_secs(301))).await.unwrap();
+    sim.apply(Action::Solve(Side::Host)).await.unwrap();
+    assert_eq!(granted(&sim.log), 0);
+
+    // Fast
+    sim.apply(Action::Fire(Side::Host, Coord { x: 9, y: 8 })).await.unwrap();
+    sim.apply(Action::Solve(Side::Host)).await.unwrap();
+    assert_eq!(granted(&sim.log), 1);
+
+    // Centred on B2, between the first two ships: only B1 and B3 hit
+    sim.apply(Action::FireSpecial(Side::Host, cross, at)).await.unwrap();
+    let shots = sim.log.iter().find_map(|m| match m {
+        ServerMessage::SpecialShotResult { shots, .. } => Some(shots.clone()),
+        _ => None,
+    });
+    let hits: Vec<String> = shots.unwrap().into_iter().filter(|s| s.hit).map(|s| s.coord).collect();
+    assert_eq!(hits, ["B1", "B3"]);
+    let (inventory, cells_hit) = sim
+        .inspect(|g| (g.player1.inventory.clone(), g.player1.stats.cells_hit))
+        .await;
+    assert!(inventory.is_empty());
+    assert_eq!(cells_hit, 2);
+}
+
+/// A bonus problem earns a shield, which blocks the first shot at its cell.
+#[tokio::test]
+async fn test_bonus_solve_earns_shield() {
+    let mut sim = Simulation::new(GameConfig::default(), 3).await;
+    sim.start(row_fleet(), row_fleet()).await.unwrap();
+    let shielded = Coord { x: 0, y: 8 };
+
+    let refused = sim.apply(Action::PlaceShield(Side::Host, shielded)).await.unwrap();
+    assert!(
+        matches!(&refused[..], [ServerMessage::Error { code, .. }] if code == "no_shield_available")
+    );
+    sim.apply(Action::RequestBonus(Side::Host)).await.unwrap();
+    let refused = sim.apply(Action::RequestBonus(Side::Host)).await.unwrap();
+    assert!(matches!(&refused[..], [ServerMessage::Error { code, .. }] if code == "bonus_pending"));
+    sim.apply(Action::SolveBonus(Side::Host)).await.unwrap();
+    let placed = sim.apply(Action::PlaceShield(Side::Host, shielded)).await.unwrap();
+    assert!(matches!(&placed[..], [ServerMessage::ShieldPlaced { coord, .. }] if coord == "A9"));
+
+    sim.apply(Action::Fire(Side::Guest, shielded)).await.unwrap();
+    sim.apply(Action::Fire(Side::Guest, shielded)).await.unwrap();
+    let shots: Vec<(bool, bool)> = sim
+        .log
+        .iter()
+        .filter_map(|m| match m {
+            ServerMessage::ShotResult { hit, blocked, .. } => Some((*hit, *blocked)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(shots, [(false, true), (true, false)]);
+    let (solved, guest_hits) = sim
+        .inspect(|g| (g.player1.stats.problems_solved, g.player2.as_ref().unwrap().stats.cells_hit))
+        .await;
+    assert_eq!((solved, guest_hits), (1, 1));
+}
+
+/// With neither side firing, the clock runs out into sudden death and then a draw.
+#[tokio::test]
+async fn test_idle_game_times_out() {
+    let mut sim = Simulation::new(GameConfig::default(), 1).await;
+    let (host, guest) = (sim.random_fleet(), sim.random_fleet());
+    sim.start(host, guest).await.unwrap();
+
+    sim.apply(Action::Advance(Duration::from_secs(2700)))
+        .await
+        .unwrap();
+    assert_eq!(
+        sim.inspect(|g| g.status.clone()).await,
+        GameStatus::SuddenDeath
+    );
+    sim.apply(Action::Advance(Duration::from_secs(600)))
+        .await
+        .unwrap();
+    assert_eq!(
+        sim.inspect(|g| g.status.clone()).await,
+        GameStatus::Finished
+    );
+    assert!(sim.log.iter().any(|m| matches!(
+        m,
+        ServerMessage::GameOver { winner_id: None, reason, .. } if reason == "SuddenDeathTimeout"
+    )));
+}
+
+/// Accuracy sudden death: ten shots each, then the better hit rate wins;
+/// an eleventh shot is refused.
+#[tokio::test]
+async fn test_accuracy_sudden_death() {
+    let config = GameConfig {
+        heat_threshold: 20,
+        sudden_death: SuddenDeathMode::Accuracy,
+        ..GameConfig::default()
+    };
+    let mut sim = Simulation::new(config, 4).await;
+    sim.start(row_fleet(), row_fleet()).await.unwrap();
+    sim.apply(Action::Advance(Duration::from_secs(2700))).await.unwrap();
+    assert_eq!(sim.inspect(|g| g.status.clone()).await, GameStatus::SuddenDeath);
+
+    // Host: 3 of 10 down column 0; guest: 1 of 10 down column 9
+    for y in 0..10 {
+        sim.apply(Action::Fire(Side::Host, Coord { x: if y < 6 { 0 } else { 9 }, y })).await.unwrap();
+    }
+    let refused = sim.apply(Action::Fire(Side::Host, Coord { x: 5, y: 5 })).await.unwrap();
+    assert!(
+        matches!(&refused[..], [ServerMessage::Error { code, .. }] if code == "sudden_death_shots_used")
+    );
+    for y in 0..10 {
+        sim.apply(Action::Fire(Side::Guest, Coord { x: if y == 0 { 0 } else { 9 }, y })).await.unwrap();
+    }
+
+    let host = sim.id(Side::Host);
+    assert_eq!(sim.inspect(|g| g.status.clone()).await, GameStatus::Finished);
+    assert!(sim.log.iter().any(|m| matches!(
+        m,
+        ServerMessage::GameOver { winner_id: Some(w), reason, .. }
+            if *w == host && reason == "SuddenDeath - Better accuracy"
+    )));
+}
+
+/// Anti-snipe: shots in the last minute of regulation (when enabled) and of
+/// sudden death push that clock back.
+#[tokio::test]
+async fn test_anti_snipe_extends_the_clock() {
+    let config = GameConfig {
+        heat_threshold: 20,
+        anti_snipe_secs: Some(20),
+        anti_snipe_regulation: true,
+        ..GameConfig::default()
+    };
+    let mut sim = Simulation::new(config, 5).await;
+    sim.start(row_fleet(), row_fleet()).await.unwrap();
+    let extensions = |sim: &Simulation| -> Vec<(u64, u64)> {
+        sim.log
+            .iter()
+            .filter_map(|m| match m {
+                ServerMessage::ClockExtended { added_secs, time_remaining_secs } => {
+                    Some((*added_secs, *time_remaining_secs))
+                }
+                _ => None,
+            })
+            .collect()
+    };
+
+    // A shot with two minutes left is no snipe
+    sim.apply(Action::Advance(Duration::from_secs(2580))).await.unwrap();
+    sim.apply(Action::Fire(Side::Host, Coord { x: 9, y: 0 })).await.unwrap();
+    assert!(extensions(&sim).is_empty());
+
+    // With 30 seconds left, regulation runs 20 seconds longer
+    sim.apply(Action::Advance(Duration::from_secs(90))).await.unwrap();
+    sim.apply(Action::Fire(Side::Host, Coord { x: 9, y: 1 })).await.unwrap();
+    assert_eq!(extensions(&sim), [(20, 50)]);
+    sim.apply(Action::Advance(Duration::from_secs(49))).await.unwrap();
+    assert_eq!(sim.inspect(|g| g.status.clone()).await, GameStatus::Playing);
+    sim.apply(Action::Advance(Duration::from_secs(1))).await.unwrap();
+    assert_eq!(sim.inspect(|g| g.status.clone()).await, GameStatus::SuddenDeath);
+
+    // A miss 10 seconds before the sudden death timeout buys 20 more
+    sim.apply(Action::Advance(Duration::from_secs(590))).await.unwrap();
+    sim.apply(Action::Fire(Side::Guest, Coord { x: 9, y: 9 })).await.unwrap();
+    assert_eq!(extensions(&sim), [(20, 50), (20, 30)]);
+    sim.apply(Action::Advance(Duration::from_secs(29))).await.unwrap();
+    assert_eq!(sim.inspect(|g| g.status.clone()).await, GameStatus::SuddenDeath);
+    sim.apply(Action::Advance(Duration::from_secs(1))).await.unwrap();
+    assert_eq!(sim.inspect(|g| g.status.clone()).await, GameStatus::Finished);
+}
+
+/// Random games across seeds and configs: every invariant holds after every
+/// action and every game ends. A failure names the seed to replay.
+#[tokio::test]
+async fn test_random_games_keep_invariants() {
+    for seed in 0..40u64 {
+        let config = GameConfig {
+            heat_threshold: [3, 5, 7, 10][seed as usize % 4],
+            // Up to 5 vetoes, so some games run past the end of the list
+            veto_penalties: vec![60, 120, 180],
+            max_vetoes: (seed % 6) as u32,
+            game_duration_secs: [300, 900, 2700][seed as usize % 3],
+            ..GameConfig::default()
+        };
+        let mut sim = Simulation::new(config, seed).await;
+        let outcome = sim
+            .run_random(400)
+            .await
+            .unwrap_or_else(|v| panic!("seed {}: {}", seed, v));
+        assert!(outcome.game_over.is_some(), "seed {}: no GameOver", seed);
+    }
+}
@@ -4,7 +4,7 @@ use tokio::time::sleep;
 use uuid::Uuid;
 
 // Stress test: spawn 50 games, mark them Playing, then perform many concurrent
-// fire() calls across all games to exercise per-game write locks, ticker, and
+// fire() calls across all games to exercise the per-game actors, ticker, and
 // general concurrency. This test does NOT call the Codeforces API.
 #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
 async fn stress_50_games_fire_load() {
@@ -28,41 +28,39 @@ async fn stress_50_games_fire_load() {
         g.game_started_at = Some(std::time::Instant::now());
 
         let id = g.id;
-        state.games.write().await.insert(id, g);
+        state.insert_game(g);
         game_ids.push(id);
     }
 
     // For each game spawn a task that performs 200 shots (alternating shooters)
     // using internal API `fire()` to avoid WebSocket overhead.
     let mut handles = Vec::new();
-    for gid in game_ids.iter().cloned() {
+    for gid in game_ids {
         let st = state.clone();
         let h = tokio::spawn(async move {
             for s in 0..200usize {
                 // choose coordinates to avoid repeated 'Already fired here' errors
-                let x = (s % 10) as usize;
-                let y = ((s / 10) % 10) as usize;
+                let x = s % 10;
+                let y = (s / 10) % 10;
 
-                // Acquire write lock briefly and perform one shot
-                {
-                    let mut games = st.games.write().await;
-                    if let Some(game) = games.get_mut(&gid) {
-                        let _ = if s % 2 == 0 {
-                            if let Some(ref mut p2) = game.player2 {
-                                game.player1.fire(p2, x, y, game.config.heat_threshold)
-                            } else {
-                                Err("missing opponent")
-                            }
+                // Queue one shot on the game's actor
+                st.with_game(gid, move |game| {
+                    let _ = if s % 2 == 0 {
+                        if let Some(ref mut p2) = game.player2 {
+                            game.player1.fire(&mut p2.home, x, y, game.config.heat_threshold)
                         } else {
-                            // p2 shoots at p1
-                            if let Some(ref mut p2) = game.player2 {
-                                p2.fire(&mut game.player1, x, y, game.config.heat_threshold)
-                            } else {
-                                Err("missing opponent")
-                            }
-                        };
-                    }
-                }
+                            Err(backend::error::GameError::WaitingForOpponent)
+                        }
+                    } else {
+                        // p2 shoots at p1
+                        if let Some(ref mut p2) = game.player2 {
+                            p2.fire(&mut game.player1.home, x, y, game.config.heat_threshold)
+                        } else {
+                            Err(backend::error::GameError::WaitingForOpponent)
+                        }
+                    };
+                })
+                .await;
 
                 // tiny sleep to spread load, but still intense
                 sleep(std::time::Duration::from_millis(1)).await;
@@ -77,6 +75,5 @@ async fn stress_50_games_fire_load() {
     }
 
     // Sanity check: all games still present
-    let games = state.games.read().await;
-    assert_eq!(games.len(), 50);
+    assert_eq!(state.games.len(), 50);
 }
@@ -0,0 +1,55 @@
+use backend::supervisor::Supervisor;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A task that panics twice is restarted and then stays up, heartbeating.
+#[tokio::test]
+async fn test_restarts_task_after_panic() {
+    let supervisor = Arc::new(Supervisor::with_backoff(
+        Duration::from_millis(10),
+        Duration::from_millis(100),
+    ));
+    let runs = Arc::new(AtomicU32::new(0));
+    let (task_runs, task_supervisor) = (runs.clone(), supervisor.clone());
+    supervisor.spawn("flaky", Some(Duration::from_millis(20)), move || {
+        let runs = task_runs.clone();
+        let supervisor = task_supervisor.clone();
+        async move {
+            if runs.fetch_add(1, Ordering::SeqCst) < 2 {
+                panic!("flaky task failed");
+            }
+            loop {
+                supervisor.heartbeat("flaky");
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+    });
+
+    // Panics print a backtrace first, which can take a while under load
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while runs.load(Ordering::SeqCst) < 3 && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(runs.load(Ordering::SeqCst), 3);
+    let status = &supervisor.statuses()[0];
+    assert_eq!(status.name, "flaky");
+    assert_eq!(status.restarts, 2);
+    assert!(status.alive && status.healthy, "{:?}", status);
+}
+
+/// A task that stops heartbeating is reported unhealthy while still alive.
+#[tokio::test]
+async fn test_stalled_task_is_unhealthy() {
+    let supervisor = Arc::new(Supervisor::default());
+    supervisor.spawn("stuck", Some(Duration::from_millis(10)), || {
+        std::future::pending::<()>()
+    });
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let status = &supervisor.statuses()[0];
+    assert!(status.alive);
+    assert_eq!(status.restarts, 0);
+    assert!(!status.healthy, "no heartbeat for 10 intervals: {:?}", status);
+}
@@ -0,0 +1,94 @@
+use backend::cf_client::ContestProblem;
+use backend::state::{AssignedProblem, LockOutcome, LockRecord};
+use backend::training::{missed_problems, recommend};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+fn lock(player_id: Uuid, contest_id: i32, outcome: Option<LockOutcome>) -> LockRecord {
+    LockRecord {
+        player_id,
+        problem: AssignedProblem {
+            contest_id,
+            index: "A".to_string(),
+            name: format!("Problem {}", contest_id),
+            rating: 1200,
+            url: None,
+        },
+        assigned_at_unix: 0,
+        outcome,
+        secs_taken: outcome.map(|_| 60),
+    }
+}
+
+fn problem(contest_id: i32, rating: i32, tags: &[&str]) -> ContestProblem {
+    ContestProblem {
+        contest_id: Some(contest_id),
+        index: "A".to_string(),
+        name: format!("Problem {}", contest_id),
+        rating: Some(rating),
+        tags: tags.iter().map(|t| t.to_string()).collect(),
+    }
+}
+
+/// Solved locks drop out; vetoed, relieved and still-open ones are misses.
+/// The other player's locks don't count.
+#[test]
+fn test_missed_problems_from_lock_history() {
+    let (me, them) = (Uuid::new_v4(), Uuid::new_v4());
+    let history = vec![
+        lock(me, 1, Some(LockOutcome::Solved)),
+        lock(them, 2, Some(LockOutcome::Vetoed)),
+        lock(me, 3, Some(LockOutcome::Vetoed)),
+        lock(me, 4, Some(LockOutcome::Relieved)),
+        lock(me, 5, Some(LockOutcome::Unsolved)),
+    ];
+    let outcomes: Vec<(i32, Option<LockOutcome>)> = missed_problems(me, &history)
+        .iter()
+        .map(|m| (m.problem.contest_id, m.outcome))
+        .collect();
+    assert_eq!(
+        outcomes,
+        [
+            (3, Some(LockOutcome::Vetoed)),
+            (4, Some(LockOutcome::Relieved)),
+            (5, Some(LockOutcome::Unsolved))
+        ]
+    );
+    assert_eq!(missed_problems(them, &history).len(), 1);
+}
+
+/// Closest matches on tags and rating first, solved and off-rating problems
+/// never, and each miss gets a turn.
+#[test]
+fn test_recommendations_follow_tags_and_rating() {
+    let problemset = vec![
+        problem(10, 1200, &["dp", "greedy"]),
+        problem(20, 1200, &["graphs"]),
+        problem(11, 1200, &["dp", "greedy"]), // solved
+        problem(12, 1300, &["dp"]),
+        problem(13, 1200, &["dp", "greedy"]),
+        problem(14, 1600, &["dp", "greedy"]), // too hard
+        problem(15, 1200, &["strings"]),      // no shared tag
+        problem(21, 1100, &["graphs", "dfs"]),
+    ];
+    let solved: HashSet<String> = ["11-A".to_string()].into();
+
+    let missed = [
+        lock(Uuid::new_v4(), 10, None),
+        lock(Uuid::new_v4(), 20, None),
+    ];
+    let picks = recommend(&missed, &problemset, &solved, 5);
+    let ids: Vec<(i32, &str)> = picks
+        .iter()
+        .map(|r| (r.contest_id, r.similar_to.as_str()))
+        .collect();
+    // Most recent miss (20) first, then alternating while candidates last
+    assert_eq!(ids, [(21, "20-A"), (13, "10-A"), (12, "10-A")]);
+    assert_eq!(
+        picks[1].url,
+        "https://codeforces.com/problemset/problem/13/A"
+    );
+
+    assert_eq!(recommend(&missed[..1], &problemset, &solved, 1).len(), 1);
+    assert!(recommend(&[], &problemset, &solved, 5).is_empty());
+}
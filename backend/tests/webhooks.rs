@@ -0,0 +1,186 @@
+use axum::http::{HeaderMap, StatusCode};
+use backend::protocol::ServerMessage;
+use backend::state::{AppState, Game, GameConfig, GameEvent};
+use backend::config::WebhookConfig;
+use backend::webhooks::{is_public, sign, Subscription, WebhookEvent, Webhooks};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::time::{sleep, Duration};
+use uuid::Uuid;
+
+const SECRET: &str = "correct horse battery staple";
+
+/// Path the hook was POSTed to, its headers and body
+type Delivery = (String, HeaderMap, Vec<u8>);
+
+/// Local development: plain http to this machine.
+fn local() -> WebhookConfig {
+    WebhookConfig { game_callbacks_allow_http: true, game_callbacks_allow_private: true, ..Default::default() }
+}
+
+/// Each subscription gets only its events, signed with the game's secret; a
+/// failed delivery is retried.
+#[tokio::test]
+async fn test_delivers_signed_lifecycle_events() {
+    let received: Arc<Mutex<Vec<Delivery>>> = Arc::default();
+    let failed_once = Arc::new(AtomicBool::new(false));
+    let sink = received.clone();
+    let app = axum::Router::new().route(
+        "/{name}",
+        axum::routing::post(
+            move |axum::extract::Path(name): axum::extract::Path<String>, headers: HeaderMap, body: axum::body::Bytes| {
+                let (sink, failed_once) = (sink.clone(), failed_once.clone());
+                async move {
+                    if name == "flaky" && !failed_once.swap(true, Ordering::SeqCst) {
+                        return StatusCode::SERVICE_UNAVAILABLE;
+                    }
+                    sink.lock().unwrap().push((name, headers, body.to_vec()));
+                    StatusCode::NO_CONTENT
+                }
+            },
+        ),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    let state = AppState::new();
+
+    let subscription = |name: &str, events: Vec<WebhookEvent>| Subscription { url: format!("http://{}/{}", addr, name), events };
+    let subscriptions = vec![
+        subscription("joins", vec![WebhookEvent::OpponentJoined]),
+        subscription("flaky", vec![WebhookEvent::GameStarted, WebhookEvent::GameOver]),
+    ];
+    let mut game = Game::new(Uuid::new_v4(), "host".to_string(), GameConfig::default());
+    game.webhooks = Webhooks::new(subscriptions, Some(SECRET.to_string()), &local()).unwrap();
+    let game_id = game.id;
+    state.insert_game(game);
+
+    let guest_id = Uuid::new_v4();
+    state
+        .with_game(game_id, move |g| {
+            g.seat_guest(guest_id, "guest".to_string()).unwrap();
+            let _ = g.tx.send(GameEvent::Message(ServerMessage::PlayerJoined { player_id: guest_id }));
+            let _ = g.tx.send(GameEvent::Message(ServerMessage::GameStart));
+            g.game_started_at = Some(std::time::Instant::now());
+            g.finish(Some(guest_id), "AllShipsSunk");
+        })
+        .await
+        .unwrap();
+
+    let mut posts = Vec::new();
+    for _ in 0..150 {
+        posts = received.lock().unwrap().clone();
+        if posts.len() == 3 {
+            break;
+        }
+        sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(posts.len(), 3, "{:?}", posts);
+
+    let mut events = Vec::new();
+    for (name, headers, body) in &posts {
+        let timestamp: u64 = headers["x-battle-cp-timestamp"].to_str().unwrap().parse().unwrap();
+        assert_eq!(headers["x-battle-cp-signature"], sign(SECRET, timestamp, body).as_str());
+        let body: serde_json::Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(body["game_id"], game_id.to_string());
+        assert_eq!(headers["x-battle-cp-event"], body["event"].as_str().unwrap());
+        events.push((name.clone(), body));
+    }
+    events.sort_by_key(|(_, body)| body["event_id"].as_u64());
+    let joined = &events[0].1;
+    assert_eq!((events[0].0.as_str(), joined["event"].as_str()), ("joins", Some("opponent_joined")));
+    assert_eq!(joined["data"]["cf_handle"], "guest");
+    // The first game_started delivery got a 503 and was retried
+    assert_eq!((events[1].0.as_str(), events[1].1["event"].as_str()), ("flaky", Some("game_started")));
+    let over = &events[2].1;
+    assert_eq!(over["event"], "game_over");
+    assert_eq!(over["data"]["winner_id"], guest_id.to_string());
+    assert_eq!(over["data"]["reason"], "AllShipsSunk");
+}
+
+#[test]
+fn test_subscription_validation() {
+    let sub = |url: &str, events: Vec<WebhookEvent>| Subscription { url: url.to_string(), events };
+    let secret = || Some(SECRET.to_string());
+    let https = || sub("https://hooks.example/battle", vec![WebhookEvent::GameOver]);
+
+    let strict = WebhookConfig::default();
+    let http_ok = WebhookConfig { game_callbacks_allow_http: true, ..Default::default() };
+    let check = |subs: Vec<Subscription>, config: &WebhookConfig| Webhooks::new(subs, secret(), config).map(|w| w.is_some());
+
+    assert!(Webhooks::new(vec![], None, &strict).unwrap().is_none());
+    assert_eq!(check(vec![https()], &strict), Ok(true));
+    assert!(Webhooks::new(vec![https()], None, &strict).is_err(), "secret required");
+    assert!(Webhooks::new(vec![https()], Some("short".into()), &strict).is_err());
+    assert!(check(vec![https(); 4], &strict).is_err());
+    assert!(check(vec![sub("https://hooks.example", vec![])], &strict).is_err());
+    let over = |url: &str| vec![sub(url, vec![WebhookEvent::GameOver])];
+    assert_eq!(check(over("not a url"), &strict), Err("Invalid webhook url"));
+    let long = format!("https://hooks.example/{}", "a".repeat(2048));
+    assert_eq!(check(over(&long), &strict), Err("Webhook urls must be at most 2048 characters"));
+    assert_eq!(check(over("mailto:ops@hooks.example"), &strict), Err("Webhook urls must be https"));
+    assert_eq!(check(over("http://hooks.example/battle"), &strict), Err("Webhook urls must be https"));
+    assert_eq!(check(over("http://hooks.example/battle"), &http_ok), Ok(true));
+    assert_eq!(check(over("https://"), &strict), Err("Invalid webhook url"));
+
+    // No callbacks into the server's own network, unless allowed
+    for url in [
+        "https://127.0.0.1/hook",
+        "https://10.1.2.3/hook",
+        "https://172.16.0.1/hook",
+        "https://192.168.1.1:8443/hook",
+        "https://169.254.169.254/latest/meta-data",
+        "https://100.64.0.1/hook",
+        "https://0.0.0.0/hook",
+        "https://[::1]/hook",
+        "https://[fd00::1]/hook",
+        "https://[fe80::1]/hook",
+        "https://[::ffff:127.0.0.1]/hook",
+    ] {
+        let refused = Err("Webhook urls can't point at loopback, private or link-local addresses");
+        assert_eq!(check(over(url), &strict), refused, "{}", url);
+        assert_eq!(check(over(url), &local()), Ok(true), "{}", url);
+    }
+    assert_eq!(check(over("https://203.0.113.7/hook"), &strict), Ok(true));
+    assert!(is_public("2001:db8::1".parse().unwrap()));
+}
+
+/// Hostnames are judged by what they resolve to, at creation and again at
+/// each delivery.
+#[tokio::test]
+async fn test_refuses_hosts_resolving_to_private_addresses() {
+    let received = Arc::new(AtomicBool::new(false));
+    let hit = received.clone();
+    let app = axum::Router::new().route(
+        "/hook",
+        axum::routing::post(move || {
+            hit.store(true, Ordering::SeqCst);
+            async { StatusCode::NO_CONTENT }
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let http_ok = WebhookConfig { game_callbacks_allow_http: true, ..Default::default() };
+    let subscription = Subscription { url: format!("http://localhost:{}/hook", port), events: vec![WebhookEvent::GameOver] };
+    let webhooks = Webhooks::new(vec![subscription], Some(SECRET.to_string()), &http_ok).unwrap().unwrap();
+    assert_eq!(
+        webhooks.check_resolved().await,
+        Err("Webhook urls can't point at loopback, private or link-local addresses")
+    );
+
+    // Even past creation (a name re-pointed later), deliveries don't go there
+    let state = AppState::new();
+    let mut game = Game::new(Uuid::new_v4(), "host".to_string(), GameConfig::default());
+    game.webhooks = Some(webhooks);
+    let game_id = game.id;
+    state.insert_game(game);
+    state.with_game(game_id, |g| g.finish(None, "Timeout")).await.unwrap();
+    sleep(Duration::from_millis(500)).await;
+    assert!(!received.load(Ordering::SeqCst));
+}
@@ -114,6 +114,7 @@ ssh "$ORACLE_SSH_HOST" << 'REMOTE'
         -e RUST_LOG="${RUST_LOG:-info}" \
         -e ALLOWED_ORIGINS="${ALLOWED_ORIGINS:-https://battle-cp.vercel.app}" \
         -e DISCORD_WEBHOOK_URL="${DISCORD_WEBHOOK_URL:-}" \
+        -e TRUST_PROXY_HEADERS=true \
         --memory 1g \
         --cpus 1.5 \
         $IMAGE_NAME:latest
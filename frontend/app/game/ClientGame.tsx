@@ -27,6 +27,21 @@ export default function ClientGame({ params }: { params: Promise<{ gameId: strin
 		if (initialized.current) return;
 		initialized.current = true;
 
+		// Organizer join links carry the seat's credentials: keep them, then tidy the URL
+		const linkParams = new URLSearchParams(window.location.search);
+		const linkPlayerId = linkParams.get("player_id");
+		const linkCfHandle = linkParams.get("cf_handle");
+		if (linkPlayerId && linkCfHandle) {
+			localStorage.setItem("battlecp_player_id", linkPlayerId);
+			localStorage.setItem("battlecp_cf_handle", linkCfHandle);
+			localStorage.setItem("battlecp_active_game", gameId);
+			const linkToken = linkParams.get("token");
+			if (linkToken) {
+				localStorage.setItem(`battlecp_session_${gameId}`, linkToken);
+			}
+			window.history.replaceState(null, "", `/game/${gameId}`);
+		}
+
 		const storedPlayerId = localStorage.getItem("battlecp_player_id");
 		const storedCfHandle = localStorage.getItem("battlecp_cf_handle");
 
@@ -59,6 +74,12 @@ export default function ClientGame({ params }: { params: Promise<{ gameId: strin
 	);
 }
 
+function formatClock(seconds: number): string {
+	const mins = Math.floor(seconds / 60);
+	const secs = seconds % 60;
+	return `${mins}:${secs.toString().padStart(2, "0")}`;
+}
+
 function GameContent({
 	gameId,
 	playerId,
@@ -72,7 +93,9 @@ function GameContent({
 	myShips: ShipPlacement[];
 	setMyShips: (ships: ShipPlacement[]) => void;
 }) {
-	const { gameState, isConnected, gameNotFound, fire, placeShips, solveCP, veto } = useGameSocket(gameId, playerId, cfHandle);
+	const { gameState, isConnected, gameNotFound, fire, fireSpecial, requestBonusProblem, placeShield, voteDifficulty, requestHint, extendLobby, placeShips, ready, solveCP, veto } = useGameSocket(gameId, playerId, cfHandle);
+	const [crossArmed, setCrossArmed] = useState(false);
+	const [shieldArmed, setShieldArmed] = useState(false);
 	const { playSuccess: playShipsConfirmed, playJoin, playHit, playMiss } = useSound();
 	const { setPhase: setMusicPhase } = useMusic();
 
@@ -94,10 +117,10 @@ function GameContent({
 				setMusicPhase("combat");
 			}
 		} else if (gameState.phase === "finished") {
-			const isWinner = gameState.winnerId === playerId;
+			const isWinner = gameState.winnerId === playerId || gameState.gameOverReason === "CoopVictory";
 			setMusicPhase(isWinner ? "victory" : "defeat");
 		}
-	}, [gameState.phase, gameState.status, gameState.winnerId, playerId, setMusicPhase]);
+	}, [gameState.phase, gameState.status, gameState.winnerId, gameState.gameOverReason, playerId, setMusicPhase]);
 
 	const handleShipsConfirmed = (ships: { x: number; y: number; size: number; orientation: string }[]) => {
 		const placements: ShipPlacement[] = ships.map((s) => ({
@@ -113,16 +136,28 @@ function GameContent({
 
 	const effectiveMyShips = gameState.myShips.length > 0 ? gameState.myShips : myShips;
 
+	const crossShots = gameState.specialWeapons.filter((w) => w === "cross").length;
+
 	const handleFire = (x: number, y: number) => {
-		if (!gameState.isLocked) {
+		if (gameState.isLocked) return;
+		if (crossArmed && crossShots > 0) {
+			fireSpecial("cross", x, y);
+			setCrossArmed(false);
+		} else {
 			fire(x, y);
 		}
 	};
 
+	const handlePlaceShield = (x: number, y: number) => {
+		placeShield(x, y);
+		setShieldArmed(false);
+	};
+
 	const enemySunkSet = React.useMemo(() => new Set(gameState.enemySunkCells), [gameState.enemySunkCells]);
 	const mySunkSet = React.useMemo(() => new Set(gameState.mySunkCells), [gameState.mySunkCells]);
 
-	const isWinner = gameState.winnerId === playerId;
+	// Co-op teams win (or lose) together
+	const isWinner = gameState.winnerId === playerId || gameState.gameOverReason === "CoopVictory";
 
 	const prevEnemyGridRef = useRef<typeof gameState.enemyGrid | null>(null);
 	const justSyncedRef = useRef(false);
@@ -200,6 +235,7 @@ function GameContent({
 				<HUD
 					heat={gameState.heat}
 					maxHeat={gameState.maxHeat}
+					ammo={gameState.ammo}
 					isLocked={gameState.isLocked}
 					gameTimeRemaining={gameState.gameTimeRemaining}
 					vetoTimeRemaining={gameState.vetoTimeRemaining}
@@ -207,6 +243,9 @@ function GameContent({
 					maxVetoes={gameState.maxVetoes}
 					status={gameState.status}
 					opponentConnected={gameState.opponentConnected}
+					opponentOnline={gameState.opponentOnline}
+					opponentWorking={gameState.opponentWorking}
+					spectators={gameState.spectators}
 				/>
 			)}
 
@@ -243,6 +282,19 @@ function GameContent({
 					<div className="flex flex-col items-center gap-6 text-center">
 						<div className="text-4xl font-heading text-primary animate-pulse">AWAITING OPPONENT</div>
 						<div className="text-zinc-500 max-w-md">Share your game code with a friend to start the battle.</div>
+						{gameState.phaseTimeRemaining !== null && (
+							<div className={cn("font-mono text-sm", gameState.phaseTimeRemaining < 60 ? "text-red-500 animate-pulse" : "text-zinc-400")}>
+								Lobby expires in {formatClock(gameState.phaseTimeRemaining)}
+							</div>
+						)}
+						{gameState.phaseTimeRemaining !== null && gameState.phaseTimeRemaining <= 60 && gameState.lobbyExtensionsLeft > 0 && (
+							<button
+								onClick={extendLobby}
+								className="px-4 py-2 border border-zinc-700 rounded-lg text-xs font-mono text-zinc-300 hover:bg-zinc-800 transition-colors"
+							>
+								KEEP LOBBY OPEN ({gameState.lobbyExtensionsLeft} LEFT)
+							</button>
+						)}
 						<div className="bg-primary/10 border border-primary/30 rounded-lg px-8 py-4">
 							<span className="text-xs text-zinc-500 block mb-1">GAME CODE</span>
 							<span className="text-2xl font-mono font-bold text-white tracking-wide">{gameId}</span>
@@ -270,19 +322,42 @@ function GameContent({
 								<div className="text-center mb-8">
 									<h2 className="text-2xl font-bold mb-2">DEPLOY YOUR FLEET</h2>
 									<p className="text-zinc-500">Drag ships to the grid. Click ROTATE to change orientation.</p>
+									{gameState.phaseTimeRemaining !== null && (
+										<p className={cn("font-mono text-sm mt-2", gameState.phaseTimeRemaining < 60 ? "text-red-500 animate-pulse" : "text-zinc-400")}>
+											Deploy within {formatClock(gameState.phaseTimeRemaining)}
+										</p>
+									)}
 								</div>
 								<PlacementBoard onConfirm={handleShipsConfirmed} />
 							</div>
 						) : (
 							<div className="flex flex-col items-center gap-4">
-								<Loader2 className="w-8 h-8 animate-spin text-primary" />
+								{gameState.countdown !== null ? (
+									<span className="text-6xl font-bold font-mono text-primary">{gameState.countdown}</span>
+								) : (
+									<Loader2 className="w-8 h-8 animate-spin text-primary" />
+								)}
 								<span className="text-zinc-400">
-									{gameState.status === "Initializing"
-										? "Setting up battle..."
-										: gameState.opponentShipsPlaced
-										  ? "Starting battle..."
-										  : "Waiting for opponent to deploy..."}
+									{gameState.countdown !== null
+										? "Battle starting..."
+										: !gameState.myReady
+										  ? gameState.coop
+										    ? "Co-op: the hidden fleet is in place. Ready up when you are."
+										    : "Fleet deployed. Ready up when you are."
+										  : gameState.status === "Initializing"
+										    ? "Setting up battle..."
+										    : gameState.opponentShipsPlaced && gameState.opponentReady
+										      ? "Starting battle..."
+										      : "Waiting for opponent to ready up..."}
 								</span>
+								{!gameState.myReady && (
+									<button
+										onClick={ready}
+										className="px-6 py-3 bg-primary/20 border border-primary/50 rounded-lg hover:bg-primary/30 transition-colors font-mono"
+									>
+										READY
+									</button>
+								)}
 							</div>
 						)}
 					</div>
@@ -290,6 +365,102 @@ function GameContent({
 
 				{gameState.phase === "combat" && (
 					<div className="w-full animate-in fade-in duration-500">
+						<div className="flex flex-wrap justify-center gap-3 mb-3">
+							{crossShots > 0 && (
+								<button
+									onClick={() => setCrossArmed((armed) => !armed)}
+									className={cn(
+										"px-4 py-2 border rounded-lg font-mono text-sm transition-colors",
+										crossArmed
+											? "bg-amber-500/30 border-amber-400 text-amber-200"
+											: "bg-amber-500/10 border-amber-500/40 text-amber-400 hover:bg-amber-500/20"
+									)}
+								>
+									{crossArmed ? "CROSS SHOT ARMED — pick a target" : `CROSS SHOT ×${crossShots}`}
+								</button>
+							)}
+							{gameState.shieldsAvailable > 0 && (
+								<button
+									onClick={() => setShieldArmed((armed) => !armed)}
+									className={cn(
+										"px-4 py-2 border rounded-lg font-mono text-sm transition-colors",
+										shieldArmed
+											? "bg-cyan-500/30 border-cyan-400 text-cyan-200"
+											: "bg-cyan-500/10 border-cyan-500/40 text-cyan-400 hover:bg-cyan-500/20"
+									)}
+								>
+									{shieldArmed ? "PLACING SHIELD — pick one of your ships" : `PLACE SHIELD ×${gameState.shieldsAvailable}`}
+								</button>
+							)}
+							{!gameState.isLocked && gameState.bonusProblemContestId === null && (
+								<button
+									onClick={requestBonusProblem}
+									className="px-4 py-2 border rounded-lg font-mono text-sm transition-colors bg-primary/10 border-primary/40 text-primary hover:bg-primary/20"
+								>
+									BONUS PROBLEM
+								</button>
+							)}
+							{!gameState.isLocked && gameState.bonusProblemContestId !== null && gameState.bonusProblemIndex !== null && (
+								<div className="flex items-center gap-2 px-4 py-2 border rounded-lg font-mono text-sm border-primary/40">
+									<a
+										href={gameState.bonusProblemUrl ?? `https://codeforces.com/contest/${gameState.bonusProblemContestId}/problem/${gameState.bonusProblemIndex}`}
+										target="_blank"
+										rel="noopener noreferrer"
+										className="text-primary hover:underline"
+									>
+										BONUS: {gameState.bonusProblemContestId}{gameState.bonusProblemIndex} {gameState.bonusProblemName}
+									</a>
+									<button
+										onClick={() => solveCP(gameState.bonusProblemContestId!, gameState.bonusProblemIndex!)}
+										disabled={gameState.isVerifying}
+										className="px-2 py-1 bg-primary/20 border border-primary/50 rounded hover:bg-primary/30 disabled:opacity-50"
+									>
+										{gameState.isVerifying ? "CHECKING…" : "VERIFY"}
+									</button>
+								</div>
+							)}
+							{!gameState.revealProblems && (
+								<div className="flex items-center gap-2 px-3 py-1 border rounded-lg font-mono text-xs border-white/10 text-zinc-400">
+									<span>DIFFICULTY</span>
+									{(["down", "up"] as const).map((direction) => (
+										<button
+											key={direction}
+											onClick={() => voteDifficulty(direction)}
+											className={cn(
+												"px-2 py-1 border rounded transition-colors",
+												gameState.myDifficultyVote === direction
+													? "bg-primary/30 border-primary text-primary"
+													: "border-white/10 hover:bg-white/10"
+											)}
+										>
+											{direction === "up" ? "HARDER ▲" : "EASIER ▼"}
+										</button>
+									))}
+									{gameState.opponentDifficultyVote && (
+										<span className="text-amber-400">
+											opponent wants {gameState.opponentDifficultyVote === "up" ? "harder" : "easier"}
+										</span>
+									)}
+								</div>
+							)}
+						</div>
+						{gameState.problemSet.length > 0 && (
+							<div className="flex flex-wrap items-center gap-2 px-3 py-2 border rounded-lg font-mono text-xs border-white/10 text-zinc-400">
+								<span>PROBLEM SET</span>
+								{gameState.problemSet.map((p) => (
+									<a
+										key={`${p.contest_id}-${p.index}`}
+										href={p.url ?? `https://codeforces.com/problemset/problem/${p.contest_id}/${p.index}`}
+										target="_blank"
+										rel="noopener noreferrer"
+										title={`${p.name} (${p.rating})`}
+										className="px-2 py-1 border rounded border-white/10 hover:bg-white/10 text-zinc-300"
+									>
+										{p.contest_id}{p.index}
+									</a>
+								))}
+							</div>
+						)}
 						<CombatGrid
 							myGrid={gameState.myGrid}
 							enemyGrid={gameState.enemyGrid}
@@ -298,6 +469,8 @@ function GameContent({
 							canFire={!gameState.isLocked}
 							enemySunkCells={enemySunkSet}
 							mySunkCells={mySunkSet}
+							onPlaceShield={handlePlaceShield}
+							canPlaceShield={shieldArmed && gameState.shieldsAvailable > 0}
 						/>
 					</div>
 				)}
@@ -310,13 +483,18 @@ function GameContent({
 						difficulty={gameState.difficulty}
 						vetoesRemaining={gameState.vetoesRemaining}
 						maxVetoes={gameState.maxVetoes}
+						vetoPenalties={gameState.vetoPenalties}
 						vetoTimeRemaining={gameState.vetoTimeRemaining}
 						activeProblemContestId={gameState.activeProblemContestId}
 						activeProblemIndex={gameState.activeProblemIndex}
 						activeProblemName={gameState.activeProblemName}
 						activeProblemRating={gameState.activeProblemRating}
+						activeProblemUrl={gameState.activeProblemUrl}
+						hintCost={gameState.hintCost}
+						hintTags={gameState.hintTags}
 						onSolve={solveCP}
 						onVeto={veto}
+						onHint={requestHint}
 					/>
 				)}
 
@@ -329,11 +507,13 @@ function GameContent({
 							cellsHit: gameState.myCellsHit,
 							shipsSunk: gameState.enemyShipsSunk,
 							problemsSolved: gameState.problemsSolved,
+							locks: gameState.myLocks,
 						}}
 						opponentStats={{
 							cellsHit: gameState.opponentCellsHit,
 							shipsSunk: gameState.opponentShipsSunk,
 							problemsSolved: gameState.opponentProblemsSolved,
+							locks: gameState.opponentLocks,
 						}}
 						myGrid={gameState.revealMyGrid}
 						myShips={gameState.revealMyShips}
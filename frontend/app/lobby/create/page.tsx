@@ -16,8 +16,9 @@ import {
 import { Slider } from "@/components/ui/slider";
 import { useRouter } from "next/navigation";
 import { toast } from "sonner";
-import { useState, useEffect } from "react";
+import { useState, useEffect, useRef } from "react";
 import { getApiBaseUrl } from "@/lib/backendUrls";
+import { HintCost, SuddenDeathMode } from "@/types/game";
 
 // Stable constant for FaultyTerminal to prevent re-renders
 const GRID_MUL: [number, number] = [2, 1];
@@ -34,6 +35,9 @@ const BANDS = [
 
 export default function CreateGamePage() {
     const router = useRouter();
+    // Sent with every create attempt from this page, so a retry after a
+    // dropped response gets the same lobby back instead of a duplicate
+    const idempotencyKey = useRef(crypto.randomUUID());
     const [cfHandle, setCfHandle] = useState("");
     const [isCreating, setIsCreating] = useState(false);
     const { playJoin, playSuccess, playShipPlace } = useSound();
@@ -47,6 +51,15 @@ export default function CreateGamePage() {
     const [heatThreshold, setHeatThreshold] = useState(9); // shots before overheat (band default)
     const [vetoStrictness, setVetoStrictness] = useState<"low" | "medium" | "high">("low");
     const [maxVetoes, setMaxVetoes] = useState(3);
+    const [hideSunkInfo, setHideSunkInfo] = useState(false); // fog of war
+    const [refereeFeed, setRefereeFeed] = useState(false);
+    const [activityIndicator, setActivityIndicator] = useState(false);
+    const [heatReliefMins, setHeatReliefMins] = useState(0); // 0 = off
+    const [coop, setCoop] = useState(false);
+    const [revealProblems, setRevealProblems] = useState(false); // virtual contest
+    const [ammoMode, setAmmoMode] = useState(false); // limited rounds instead of heat
+    const [suddenDeath, setSuddenDeath] = useState<SuddenDeathMode>("first_hit");
+    const [hintCost, setHintCost] = useState<HintCost>("off");
 
     // Derived display values
     const difficulty  = difficultyMode === "cf" ? cfDifficulty : bandDifficulty;
@@ -78,7 +91,10 @@ export default function CreateGamePage() {
             const apiBaseUrl = getApiBaseUrl();
             const res = await fetch(`${apiBaseUrl}/api/game`, {
                 method: "POST",
-                headers: { "Content-Type": "application/json" },
+                headers: {
+                    "Content-Type": "application/json",
+                    "Idempotency-Key": idempotencyKey.current,
+                },
                 body: JSON.stringify({
                     cf_handle: cfHandle.trim(),
                     difficulty,
@@ -87,6 +103,15 @@ export default function CreateGamePage() {
                     game_duration_mins: timeLimit,
                     veto_strictness: vetoStrictness,
                     max_vetoes: maxVetoes,
+                    hide_sunk_info: hideSunkInfo,
+                    referee_feed: refereeFeed,
+                    activity_indicator: activityIndicator,
+                    heat_relief_mins: heatReliefMins,
+                    coop,
+                    reveal_problems: revealProblems,
+                    hint_cost: hintCost,
+                    ammo: ammoMode ? { starting: heatThreshold, per_solve: 3 } : undefined,
+                    sudden_death: suddenDeath,
                 }),
             });
 
@@ -103,6 +128,8 @@ export default function CreateGamePage() {
             localStorage.setItem("battlecp_player_id", newPlayerId);
             localStorage.setItem("battlecp_cf_handle", cfHandle.trim());
             localStorage.setItem("battlecp_active_game", newGameId);
+            // Proves we are this game's host when (re)connecting
+            localStorage.setItem(`battlecp_session_${newGameId}`, data.token);
 
             toast.success("Uplink Established", { description: `Lobby ID: ${newGameId}` });
 
@@ -331,6 +358,197 @@ export default function CreateGamePage() {
                                                             {vetoStrictness === "low" ? "1/2/3 min" : vetoStrictness === "high" ? "5/7/10 min" : "3/5/7 min"}
                                                         </p>
                                                     </div>
+
+                                                    {/* Hints */}
+                                                    <div className="space-y-2">
+                                                        <span className="text-xs font-mono text-zinc-400">Problem Hints</span>
+                                                        <div className="flex gap-2">
+                                                            {([["off", "Off"], ["longer_veto", "Veto +2m"], ["lower_max_heat", "Heat −1"]] as const).map(([cost, label]) => (
+                                                                <Button
+                                                                    key={cost}
+                                                                    variant={hintCost === cost ? "default" : "outline"}
+                                                                    size="sm"
+                                                                    className={`flex-1 text-xs ${hintCost === cost ? "bg-emerald-600" : "border-white/10"}`}
+                                                                    onClick={() => setHintCost(cost)}
+                                                                >
+                                                                    {label}
+                                                                </Button>
+                                                            ))}
+                                                        </div>
+                                                        <p className="text-[10px] text-zinc-500 font-mono">
+                                                            {hintCost === "off" ? "No hints" : "Locked players can buy a problem's tags at this cost"}
+                                                        </p>
+                                                    </div>
+
+                                                    {/* Fog of War */}
+                                                    <div className="space-y-2">
+                                                        <span className="text-xs font-mono text-zinc-400">Fog of War</span>
+                                                        <div className="flex gap-2">
+                                                            {([false, true] as const).map((on) => (
+                                                                <Button
+                                                                    key={String(on)}
+                                                                    variant={hideSunkInfo === on ? "default" : "outline"}
+                                                                    size="sm"
+                                                                    className={`flex-1 text-xs ${hideSunkInfo === on ? "bg-emerald-600" : "border-white/10"}`}
+                                                                    onClick={() => setHideSunkInfo(on)}
+                                                                >
+                                                                    {on ? "On" : "Off"}
+                                                                </Button>
+                                                            ))}
+                                                        </div>
+                                                        <p className="text-[10px] text-zinc-500 font-mono">
+                                                            {hideSunkInfo ? "Sunk ships stay hidden until the game ends" : "Sunk ships are announced"}
+                                                        </p>
+                                                    </div>
+
+                                                    {/* Referee Feed */}
+                                                    <div className="space-y-2">
+                                                        <span className="text-xs font-mono text-zinc-400">Referee Feed</span>
+                                                        <div className="flex gap-2">
+                                                            {([false, true] as const).map((on) => (
+                                                                <Button
+                                                                    key={String(on)}
+                                                                    variant={refereeFeed === on ? "default" : "outline"}
+                                                                    size="sm"
+                                                                    className={`flex-1 text-xs ${refereeFeed === on ? "bg-emerald-600" : "border-white/10"}`}
+                                                                    onClick={() => setRefereeFeed(on)}
+                                                                >
+                                                                    {on ? "On" : "Off"}
+                                                                </Button>
+                                                            ))}
+                                                        </div>
+                                                        <p className="text-[10px] text-zinc-500 font-mono">
+                                                            {refereeFeed ? "Spectators get ship counts and locks, never coordinates" : "No spectator commentary"}
+                                                        </p>
+                                                    </div>
+
+                                                    {/* Activity Indicator */}
+                                                    <div className="space-y-2">
+                                                        <span className="text-xs font-mono text-zinc-400">Activity Indicator</span>
+                                                        <div className="flex gap-2">
+                                                            {([false, true] as const).map((on) => (
+                                                                <Button
+                                                                    key={String(on)}
+                                                                    variant={activityIndicator === on ? "default" : "outline"}
+                                                                    size="sm"
+                                                                    className={`flex-1 text-xs ${activityIndicator === on ? "bg-emerald-600" : "border-white/10"}`}
+                                                                    onClick={() => setActivityIndicator(on)}
+                                                                >
+                                                                    {on ? "On" : "Off"}
+                                                                </Button>
+                                                            ))}
+                                                        </div>
+                                                        <p className="text-[10px] text-zinc-500 font-mono">
+                                                            {activityIndicator ? "Players see when the opponent is working on a problem" : "Opponent's problem work stays private"}
+                                                        </p>
+                                                    </div>
+
+                                                    {/* Heat Relief (mercy rule) */}
+                                                    <div className="space-y-2">
+                                                        <span className="text-xs font-mono text-zinc-400">Heat Relief</span>
+                                                        <div className="flex gap-2">
+                                                            {[0, 3, 5].map((mins) => (
+                                                                <Button
+                                                                    key={mins}
+                                                                    variant={heatReliefMins === mins ? "default" : "outline"}
+                                                                    size="sm"
+                                                                    className={`flex-1 text-xs ${heatReliefMins === mins ? "bg-emerald-600" : "border-white/10"}`}
+                                                                    onClick={() => setHeatReliefMins(mins)}
+                                                                >
+                                                                    {mins === 0 ? "Off" : `${mins} min`}
+                                                                </Button>
+                                                            ))}
+                                                        </div>
+                                                        <p className="text-[10px] text-zinc-500 font-mono">
+                                                            {heatReliefMins > 0
+                                                                ? `A wrong answer or TLE on your problem takes 1 heat off ${heatReliefMins} min later`
+                                                                : "Locked players must solve or veto"}
+                                                        </p>
+                                                    </div>
+
+                                                    {/* Co-op */}
+                                                    <div className="space-y-2">
+                                                        <span className="text-xs font-mono text-zinc-400">Co-op</span>
+                                                        <div className="flex gap-2">
+                                                            {([false, true] as const).map((on) => (
+                                                                <Button
+                                                                    key={String(on)}
+                                                                    variant={coop === on ? "default" : "outline"}
+                                                                    size="sm"
+                                                                    className={`flex-1 text-xs ${coop === on ? "bg-emerald-600" : "border-white/10"}`}
+                                                                    onClick={() => setCoop(on)}
+                                                                >
+                                                                    {on ? "On" : "Off"}
+                                                                </Button>
+                                                            ))}
+                                                        </div>
+                                                        <p className="text-[10px] text-zinc-500 font-mono">
+                                                            {coop ? "Team up against a hidden fleet before the clock runs out" : "Head-to-head duel"}
+                                                        </p>
+                                                    </div>
+
+                                                    {/* Virtual Contest */}
+                                                    <div className="space-y-2">
+                                                        <span className="text-xs font-mono text-zinc-400">Virtual Contest</span>
+                                                        <div className="flex gap-2">
+                                                            {([false, true] as const).map((on) => (
+                                                                <Button
+                                                                    key={String(on)}
+                                                                    variant={revealProblems === on ? "default" : "outline"}
+                                                                    size="sm"
+                                                                    className={`flex-1 text-xs ${revealProblems === on ? "bg-emerald-600" : "border-white/10"}`}
+                                                                    onClick={() => setRevealProblems(on)}
+                                                                >
+                                                                    {on ? "On" : "Off"}
+                                                                </Button>
+                                                            ))}
+                                                        </div>
+                                                        <p className="text-[10px] text-zinc-500 font-mono">
+                                                            {revealProblems ? "Every problem is revealed at the start, so you can read ahead" : "Problems are revealed one lock at a time"}
+                                                        </p>
+                                                    </div>
+
+                                                    {/* Sudden Death tie-break */}
+                                                    <div className="space-y-2">
+                                                        <span className="text-xs font-mono text-zinc-400">Sudden Death</span>
+                                                        <div className="flex gap-2">
+                                                            {([["first_hit", "First Hit"], ["accuracy", "Accuracy"]] as const).map(([mode, label]) => (
+                                                                <Button
+                                                                    key={mode}
+                                                                    variant={suddenDeath === mode ? "default" : "outline"}
+                                                                    size="sm"
+                                                                    className={`flex-1 text-xs ${suddenDeath === mode ? "bg-emerald-600" : "border-white/10"}`}
+                                                                    onClick={() => setSuddenDeath(mode)}
+                                                                >
+                                                                    {label}
+                                                                </Button>
+                                                            ))}
+                                                        </div>
+                                                        <p className="text-[10px] text-zinc-500 font-mono">
+                                                            {suddenDeath === "accuracy" ? "On a tie, 10 shots each: the better hit rate wins" : "On a tie, the first hit wins"}
+                                                        </p>
+                                                    </div>
+
+                                                    {/* Ammo Mode */}
+                                                    <div className="space-y-2">
+                                                        <span className="text-xs font-mono text-zinc-400">Ammo Mode</span>
+                                                        <div className="flex gap-2">
+                                                            {([false, true] as const).map((on) => (
+                                                                <Button
+                                                                    key={String(on)}
+                                                                    variant={ammoMode === on ? "default" : "outline"}
+                                                                    size="sm"
+                                                                    className={`flex-1 text-xs ${ammoMode === on ? "bg-emerald-600" : "border-white/10"}`}
+                                                                    onClick={() => setAmmoMode(on)}
+                                                                >
+                                                                    {on ? "On" : "Off"}
+                                                                </Button>
+                                                            ))}
+                                                        </div>
+                                                        <p className="text-[10px] text-zinc-500 font-mono">
+                                                            {ammoMode ? `Start with ${heatThreshold} rounds; each solve reloads 3. Replaces heat, so no heat relief or Heat −1 hints` : "Shots build heat until weapons overheat"}
+                                                        </p>
+                                                    </div>
                                                 </div>
                                             </DialogContent>
                                         </Dialog>
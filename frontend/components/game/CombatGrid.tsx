@@ -14,6 +14,9 @@ interface CombatGridProps {
     canFire: boolean;
     enemySunkCells?: Set<string>;
     mySunkCells?: Set<string>;
+    // Shield placement mode: own unhit, unshielded ship cells become clickable
+    onPlaceShield?: (x: number, y: number) => void;
+    canPlaceShield?: boolean;
 }
 
 const GRID_SIZE = 10;
@@ -35,6 +38,7 @@ const Cell = React.memo(function Cell({
     const isHit = state === "hit";
     const isMiss = state === "miss";
     const isShip = state === "ship";
+    const isShielded = state === "shielded";
     const isEmpty = state === "empty";
 
     return (
@@ -46,8 +50,10 @@ const Cell = React.memo(function Cell({
             className={cn(
                 "w-7 h-7 sm:w-8 sm:h-8 2xl:w-10 2xl:h-10 border border-white/30 relative flex items-center justify-center transition-all duration-200",
                 canClick && isEmpty && "hover:bg-primary/20 hover:border-primary/50 cursor-crosshair",
+                canClick && isShip && "hover:bg-cyan-500/30 hover:border-cyan-400/60 cursor-pointer",
                 !canClick && "cursor-default",
                 isShip && !isEnemy && "bg-primary/30 border-primary/40",
+                isShielded && "bg-cyan-500/30 border-cyan-400/70",
                 isHit && isSunk && "bg-red-600/50 border-red-600/70",
                 isHit && !isSunk && "bg-orange-500/40 border-orange-500/60",
                 isMiss && "bg-zinc-700/30"
@@ -78,6 +84,9 @@ const Cell = React.memo(function Cell({
                 </motion.div>
             )}
 
+            {/* Shield marker */}
+            {isShielded && <div className="w-4 h-4 rounded-full border-2 border-cyan-300/80" />}
+
             {/* Miss marker */}
             {isMiss && (
                 <motion.div
@@ -121,8 +130,8 @@ function GridWithLabels({
                     const sx = ship.vertical ? ship.x : ship.x + i;
                     const sy = ship.vertical ? ship.y + i : ship.y;
                     if (sx === x && sy === y) {
-                        // Check if this cell was hit
-                        if (grid[y][x] === "hit") return "hit";
+                        // Check if this cell was hit or shielded
+                        if (grid[y][x] === "hit" || grid[y][x] === "shielded") return grid[y][x];
                         return "ship";
                     }
                 }
@@ -177,8 +186,8 @@ function GridWithLabels({
                                         state={getCellState(x, y)}
                                         isEnemy={isEnemy}
                                         isSunk={sunkCells?.has(`${x},${y}`) ?? false}
-                                        onClick={isEnemy && onCellClick ? () => onCellClick(x, y) : undefined}
-                                        canClick={isEnemy && canFire && getCellState(x, y) === "empty"}
+                                        onClick={onCellClick ? () => onCellClick(x, y) : undefined}
+                                        canClick={canFire && getCellState(x, y) === (isEnemy ? "empty" : "ship")}
                                     />
                                 ))}
                             </div>
@@ -192,7 +201,7 @@ function GridWithLabels({
 
 import { useSound } from "@/context/SoundContext";
 
-export function CombatGrid({ myGrid, enemyGrid, myShips, onFire, canFire, enemySunkCells, mySunkCells }: CombatGridProps) {
+export function CombatGrid({ myGrid, enemyGrid, myShips, onFire, canFire, enemySunkCells, mySunkCells, onPlaceShield, canPlaceShield }: CombatGridProps) {
     const { playFire } = useSound();
 
     const handleFire = (x: number, y: number) => {
@@ -211,7 +220,8 @@ export function CombatGrid({ myGrid, enemyGrid, myShips, onFire, canFire, enemyS
                 isEnemy={false}
                 ships={myShips}
                 sunkCells={mySunkCells}
-                canFire={false}
+                onCellClick={onPlaceShield}
+                canFire={canPlaceShield ?? false}
             />
 
             {/* Divider */}
@@ -2,12 +2,13 @@
 
 import React from "react";
 import { cn } from "@/lib/utils";
-import { Flame, Clock, AlertTriangle } from "lucide-react";
+import { Flame, Clock, AlertTriangle, Crosshair } from "lucide-react";
 import { motion, AnimatePresence } from "framer-motion";
 
 interface HUDProps {
     heat: number;
     maxHeat: number;
+    ammo: number | null;
     isLocked: boolean;
     gameTimeRemaining: number;
     vetoTimeRemaining: number | null;
@@ -15,6 +16,9 @@ interface HUDProps {
     maxVetoes: number;
     status: string;
     opponentConnected: boolean;
+    opponentOnline: boolean;
+    opponentWorking: boolean;
+    spectators: number;
 }
 
 function formatTime(seconds: number): string {
@@ -26,6 +30,7 @@ function formatTime(seconds: number): string {
 function HUDComponent({
     heat,
     maxHeat,
+    ammo,
     isLocked,
     gameTimeRemaining,
     vetoTimeRemaining,
@@ -33,6 +38,9 @@ function HUDComponent({
     maxVetoes,
     status,
     opponentConnected,
+    opponentOnline,
+    opponentWorking,
+    spectators,
 }: HUDProps) {
     const heatPercentage = (heat / maxHeat) * 100;
     const isOverheating = heat >= maxHeat - 1;
@@ -40,7 +48,19 @@ function HUDComponent({
 
     return (
         <div className="fixed top-0 left-0 right-0 h-16 bg-black/90 backdrop-blur-md border-b border-white/10 flex items-center justify-between px-6 z-50">
-            {/* Left Section: Heat Gauge */}
+            {/* Left Section: Heat Gauge, or rounds left in ammo mode */}
+            {ammo !== null ? (
+            <div className="flex items-center gap-2">
+                <Crosshair className={cn("w-5 h-5", ammo === 0 ? "text-red-500 animate-pulse" : "text-blue-500")} />
+                <span className="text-xs text-zinc-400 uppercase tracking-wider font-mono">Ammo</span>
+                <span className={cn(
+                    "text-sm font-mono font-bold min-w-[40px]",
+                    ammo === 0 ? "text-red-500" : ammo <= 2 ? "text-orange-500" : "text-blue-500"
+                )}>
+                    {ammo}
+                </span>
+            </div>
+            ) : (
             <div className="flex items-center gap-4">
                 <div className="flex items-center gap-2">
                     <Flame className={cn("w-5 h-5", isOverheating ? "text-red-500 animate-pulse" : "text-orange-500")} />
@@ -70,6 +90,7 @@ function HUDComponent({
                     </span>
                 </div>
             </div>
+            )}
 
             {/* Center Section: Timer & Status */}
             <div className="flex flex-col items-center">
@@ -96,7 +117,7 @@ function HUDComponent({
                         </span>
                     ) : (
                         <div className="flex gap-1">
-                            {Array.from({ length: maxVetoes || 3 }).map((_, i) => (
+                            {Array.from({ length: maxVetoes }).map((_, i) => (
                                 <div
                                     key={i}
                                     className={cn(
@@ -115,11 +136,14 @@ function HUDComponent({
                 <div className="flex items-center gap-2">
                     <div className={cn(
                         "w-2 h-2 rounded-full",
-                        opponentConnected ? "bg-green-500" : "bg-yellow-500 animate-pulse"
+                        !opponentConnected ? "bg-yellow-500 animate-pulse" : opponentOnline ? "bg-green-500" : "bg-red-500"
                     )} />
                     <span className="text-xs text-zinc-500 font-mono">
-                        {opponentConnected ? "LINKED" : "WAITING"}
+                        {!opponentConnected ? "WAITING" : !opponentOnline ? "OFFLINE" : opponentWorking ? "SOLVING" : "LINKED"}
                     </span>
+                    {spectators > 0 && (
+                        <span className="text-xs text-zinc-600 font-mono">· {spectators} WATCHING</span>
+                    )}
                 </div>
             </div>
 
@@ -1,6 +1,6 @@
 "use client";
 
-import React, { useState } from 'react';
+import React, { useEffect, useState } from 'react';
 import { DndContext, DragEndEvent, DragOverlay, MouseSensor, TouchSensor, useSensor, useSensors, DragStartEvent } from '@dnd-kit/core';
 import { snapCenterToCursor } from '@dnd-kit/modifiers';
 import { GridCell } from './GridCell';
@@ -8,6 +8,7 @@ import { Ship } from './Ship';
 import { Button } from '@/components/ui/button';
 import { Card } from '@/components/ui/card';
 import { RotateCcw, Check, Shuffle } from 'lucide-react';
+import { checkPlacement, loadCore, type Core } from '@/lib/core';
 
 // Ship Types
 type Orientation = 'horizontal' | 'vertical';
@@ -34,8 +35,13 @@ import { useSound } from '@/context/SoundContext';
 export function PlacementBoard({ onConfirm }: { onConfirm: (ships: ShipData[]) => void }) {
     const [ships, setShips] = useState<ShipData[]>(INITIAL_SHIPS);
     const [activeId, setActiveId] = useState<string | null>(null);
+    const [core, setCore] = useState<Core | null>(null);
     const { playShipPlace, playInvalid, playMiss } = useSound();
 
+    useEffect(() => {
+        loadCore().then(setCore).catch(err => console.error('Failed to load board rules', err));
+    }, []);
+
     const sensors = useSensors(
         useSensor(MouseSensor, { activationConstraint: { distance: 5 } }),
         useSensor(TouchSensor, { activationConstraint: { delay: 100, tolerance: 5 } })
@@ -93,32 +99,14 @@ export function PlacementBoard({ onConfirm }: { onConfirm: (ships: ShipData[]) =
         });
     };
 
-    const isValidPlacement = (ship: ShipData, x: number, y: number, otherShips: ShipData[]) => {
-        // Check bounds
-        if (ship.orientation === 'horizontal') {
-            if (x + ship.size > 10) return false;
-        } else {
-            if (y + ship.size > 10) return false;
-        }
-
-        // Check collisions
-        const shipCells = new Set();
-        for (let i = 0; i < ship.size; i++) {
-            const cx = ship.orientation === 'horizontal' ? x + i : x;
-            const cy = ship.orientation === 'vertical' ? y + i : y;
-            shipCells.add(`${cx}-${cy}`);
-        }
+    const toPlacement = (ship: ShipData, x = ship.x, y = ship.y) =>
+        ({ x, y, size: ship.size, vertical: ship.orientation === 'vertical' });
 
-        for (const other of otherShips) {
-            if (!other.placed) continue;
-            for (let i = 0; i < other.size; i++) {
-                const ox = other.orientation === 'horizontal' ? other.x + i : other.x;
-                const oy = other.orientation === 'vertical' ? other.y + i : other.y;
-                if (shipCells.has(`${ox}-${oy}`)) return false;
-            }
-        }
-
-        return true;
+    // Same rules as the server (battle-cp-core); nothing is placeable until they've loaded
+    const isValidPlacement = (ship: ShipData, x: number, y: number, otherShips: ShipData[]) => {
+        if (!core) return false;
+        const placements = otherShips.filter(s => s.placed).map(s => toPlacement(s));
+        return checkPlacement(core, [...placements, toPlacement(ship, x, y)]) === null;
     };
 
     // Randomize fleet placement
@@ -172,6 +160,7 @@ export function PlacementBoard({ onConfirm }: { onConfirm: (ships: ShipData[]) =
                             variant="outline"
                             size="sm"
                             onClick={randomizeFleet}
+                            disabled={!core}
                             className="text-xs h-7 px-2 border-primary/30 hover:bg-primary/20"
                         >
                             <Shuffle className="w-3 h-3 mr-1" /> Random
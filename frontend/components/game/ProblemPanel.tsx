@@ -5,6 +5,7 @@ import { Button } from "@/components/ui/button";
 import { motion, AnimatePresence } from "framer-motion";
 import { ExternalLink, Check, Clock, Loader2 } from "lucide-react";
 import { toast } from "sonner";
+import { HintCost } from "@/types/game";
 
 interface ProblemPanelProps {
     cfHandle: string;
@@ -13,14 +14,21 @@ interface ProblemPanelProps {
     difficulty: number;
     vetoesRemaining: number;
     maxVetoes: number;
+    vetoPenalties: number[];
     vetoTimeRemaining: number | null;
     // Server-assigned problem — the server is the single source of truth
     activeProblemContestId: number | null;
     activeProblemIndex: string | null;
     activeProblemName: string | null;
     activeProblemRating: number | null;
+    // Set for judges other than Codeforces
+    activeProblemUrl: string | null;
+    // Hints: what one costs ("off" hides the button), and the tags once bought
+    hintCost: HintCost;
+    hintTags: string[] | null;
     onSolve: (contestId: number, problemIndex: string) => void;
     onVeto: () => void;
+    onHint: () => void;
 }
 
 import { useSound } from "@/context/SoundContext";
@@ -30,13 +38,18 @@ export function ProblemPanel({
     isVerifying,
     vetoesRemaining,
     maxVetoes,
+    vetoPenalties,
     vetoTimeRemaining,
     activeProblemContestId,
     activeProblemIndex,
     activeProblemName,
     activeProblemRating,
+    activeProblemUrl,
+    hintCost,
+    hintTags,
     onSolve,
     onVeto,
+    onHint,
 }: ProblemPanelProps) {
     const [verifyCooldown, setVerifyCooldown] = useState(0);
     const [localVetoTime, setLocalVetoTime] = useState<number | null>(null);
@@ -87,7 +100,8 @@ export function ProblemPanel({
     }, [isLocked]);
 
     const handleVerify = () => {
-        if (!activeProblemContestId || !activeProblemIndex || verifyCooldown > 0 || isVerifying) return;
+        // Judges without contests use an empty index
+        if (!activeProblemContestId || activeProblemIndex === null || verifyCooldown > 0 || isVerifying) return;
         setVerifyCooldown(10); // Match backend's 10-second cooldown
         onSolve(activeProblemContestId, activeProblemIndex);
     };
@@ -120,6 +134,9 @@ export function ProblemPanel({
         return `${mins}:${String(s).padStart(2, "0")}`;
     };
 
+    // Lockout the next veto costs (past the list, its last entry repeats)
+    const nextPenalty = vetoPenalties[Math.min(maxVetoes - vetoesRemaining, vetoPenalties.length - 1)];
+
     const hasProblem = activeProblemContestId !== null && activeProblemIndex !== null;
 
     return (
@@ -186,15 +203,36 @@ export function ProblemPanel({
                                         </h3>
                                     </div>
 
+                                    {/* Hint: tags once bought */}
+                                    {hintCost !== "off" && (hintTags ? (
+                                        <div className="flex flex-wrap gap-1">
+                                            {hintTags.length > 0 ? hintTags.map((tag) => (
+                                                <span key={tag} className="text-[10px] font-mono text-amber-300 bg-amber-500/10 px-2 py-0.5 rounded">
+                                                    {tag}
+                                                </span>
+                                            )) : (
+                                                <span className="text-[10px] font-mono text-zinc-500">Tags unavailable</span>
+                                            )}
+                                        </div>
+                                    ) : (
+                                        <Button
+                                            onClick={onHint}
+                                            variant="outline"
+                                            className="w-full h-9 border-amber-500/40 text-amber-400 hover:bg-amber-500/10 text-xs"
+                                        >
+                                            Buy hint (tags) — {hintCost === "longer_veto" ? "next veto +2:00" : "overheat 1 shot sooner"}
+                                        </Button>
+                                    ))}
+
                                     {/* Open Button */}
                                     <a
-                                        href={`https://codeforces.com/problemset/problem/${activeProblemContestId}/${activeProblemIndex}`}
+                                        href={activeProblemUrl ?? `https://codeforces.com/problemset/problem/${activeProblemContestId}/${activeProblemIndex}`}
                                         target="_blank"
                                         rel="noopener noreferrer"
                                         className="flex items-center justify-center gap-2 w-full py-3 bg-blue-600 hover:bg-blue-500 rounded-lg text-white font-bold text-sm transition"
                                     >
                                         <ExternalLink className="w-4 h-4" />
-                                        Open on Codeforces
+                                        {activeProblemUrl ? "Open problem" : "Open on Codeforces"}
                                     </a>
 
                                     {/* Instructions */}
@@ -242,6 +280,9 @@ export function ProblemPanel({
                                             ) : (
                                                 <div className="flex items-center gap-2">
                                                     <span>Veto ({vetoesRemaining}/{maxVetoes})</span>
+                                                    {nextPenalty !== undefined && vetoesRemaining > 0 && (
+                                                        <span className="text-xs text-purple-400/70">+{formatTime(nextPenalty)}</span>
+                                                    )}
                                                 </div>
                                             )}
                                         </Button>
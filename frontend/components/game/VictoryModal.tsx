@@ -19,11 +19,14 @@ interface VictoryModalProps {
         cellsHit: number;
         shipsSunk: number;
         problemsSolved: number;
+        // Lock problems [solved, assigned]
+        locks?: [number, number];
     };
     opponentStats: {
         cellsHit: number;
         shipsSunk: number;
         problemsSolved: number;
+        locks?: [number, number];
     };
     // Board reveal data
     myGrid: string[][] | null;
@@ -39,9 +42,13 @@ const reasonLabels: Record<string, string> = {
     "Timeout - More ships remaining": "Time Limit Reached — More Ships Remaining",
     "SuddenDeath - First hit wins!": "Sudden Death — First Hit Wins!",
     SuddenDeathTimeout: "Sudden Death Timeout — Draw",
+    "SuddenDeath - Better accuracy": "Sudden Death — Better Hit Rate",
+    "SuddenDeath - Equal accuracy": "Sudden Death — Equal Hit Rate, Draw",
     Disconnect: "Opponent Disconnected",
+    IdleForfeit: "Forfeit — Idle Too Long",
     LobbyTimeout: "No Opponent Joined (5 min)",
     PlacementTimeout: "Ships Not Deployed In Time (10 min)",
+    AdminTerminated: "Ended by an Organizer",
 };
 
 const GRID_SIZE = 10;
@@ -269,6 +276,16 @@ export function VictoryModal({
                                         myVal={myStats.problemsSolved}
                                         oppVal={opponentStats.problemsSolved}
                                     />
+                                    {myStats.locks && opponentStats.locks && (myStats.locks[1] > 0 || opponentStats.locks[1] > 0) && (
+                                        <ComparisonRow
+                                            icon={<Brain className="w-3.5 h-3.5" />}
+                                            label="Locks Solved"
+                                            myVal={`${myStats.locks[0]}/${myStats.locks[1]}`}
+                                            oppVal={`${opponentStats.locks[0]}/${opponentStats.locks[1]}`}
+                                            myBetter={myStats.locks[0] > opponentStats.locks[0]}
+                                            oppBetter={opponentStats.locks[0] > myStats.locks[0]}
+                                        />
+                                    )}
                                     {myScore != null && opponentScore != null && (
                                         <ComparisonRow
                                             icon={<Clock className="w-3.5 h-3.5" />}
@@ -2,10 +2,13 @@
 
 import { useEffect, useRef, useState, useCallback } from "react";
 import {
+    CloseReason,
+    DifficultyVote,
     GameState,
     initialGameState,
     ServerMessage,
     ShipPlacement,
+    SpecialWeapon,
 } from "@/types/game";
 import { toast } from "sonner";
 import { getWsBaseUrl } from "@/lib/backendUrls";
@@ -18,6 +21,8 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
     const isConnecting = useRef(false); // Guard against double connections
     const reconnectAttempts = useRef(0);
     const shouldStopReconnect = useRef(false); // Prevent reconnection when game not found
+    const resumeToken = useRef<string | null>(null); // Lets a dropped socket pick up where it left off
+    const lastEventId = useRef(0); // Newest broadcast seen, for resuming
     const maxReconnectAttempts = 5;
 
     // Handle incoming server messages
@@ -41,6 +46,10 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
                         difficulty_mode: msg.difficulty_mode,
                         maxHeat: msg.max_heat,
                         maxVetoes: msg.max_vetoes,
+                        vetoPenalties: msg.veto_penalties ?? prev.vetoPenalties,
+                        hintCost: msg.hint_cost ?? "off",
+                        coop: msg.coop ?? false,
+                        revealProblems: msg.reveal_problems ?? false,
                         vetoesRemaining: msg.max_vetoes, // Initialize from server config
                         status: wasConnecting ? "Waiting for opponent..." : prev.status,
                     };
@@ -52,6 +61,15 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
                 break;
             }
 
+            case "ResumeToken":
+                resumeToken.current = msg.token;
+                break;
+
+            case "SessionToken":
+                // Issued once when we take the guest seat — needed to reconnect
+                localStorage.setItem(`battlecp_session_${gameId}`, msg.token);
+                break;
+
             case "PlayerJoined":
                 setGameState(prev => {
                     // Only show toast if opponent wasn't already connected (prevent duplicate toasts)
@@ -63,12 +81,15 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
                         toast.info("Opponent connected!", { id: "opponent-connected" });
                     }
 
+                    // Co-op: the server hid the fleet, so there's nothing to deploy
                     return {
                         ...prev,
                         opponentId: msg.player_id,
                         opponentConnected: true,
                         phase: shouldAdvance ? "placement" : prev.phase,
-                        status: "Deploy your fleet",
+                        myShipsPlaced: prev.coop || prev.myShipsPlaced,
+                        opponentShipsPlaced: prev.coop || prev.opponentShipsPlaced,
+                        status: prev.coop ? "Ready up to start the hunt" : "Deploy your fleet",
                     };
                 });
                 break;
@@ -85,15 +106,39 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
                 });
                 break;
 
+            case "PlayerReady":
+                setGameState(prev => {
+                    const isMe = msg.player_id === prev.playerId;
+                    return {
+                        ...prev,
+                        myReady: isMe ? true : prev.myReady,
+                        opponentReady: !isMe ? true : prev.opponentReady,
+                    };
+                });
+                break;
+
+            case "Countdown":
+                setGameState(prev => ({
+                    ...prev,
+                    countdown: msg.seconds_remaining,
+                    status: `Battle starts in ${msg.seconds_remaining}...`,
+                }));
+                break;
+
             case "GameStart":
                 setGameState(prev => ({
                     ...prev,
                     phase: "combat",
                     status: "COMBAT ACTIVE",
+                    countdown: null,
                 }));
                 toast.success("Battle commencing!", { id: "battle-start" });
                 break;
 
+            case "ProblemSet":
+                setGameState(prev => ({ ...prev, problemSet: msg.problems }));
+                break;
+
             case "GameUpdate":
                 setGameState(prev => {
                     let newPhase = prev.phase;
@@ -130,9 +175,15 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
                         ...prev,
                         heat: msg.heat,
                         isLocked: msg.is_locked,
+                        specialWeapons: msg.special_weapons ?? [],
+                        shieldsAvailable: msg.shields_available ?? 0,
                         gameTimeRemaining: msg.time_remaining_secs,
                         vetoesRemaining: msg.vetoes_remaining,
                         vetoTimeRemaining: msg.veto_time_remaining_secs ?? null,
+                        // Shrinks on a heat ramp; only sent in combat
+                        maxHeat: msg.max_heat ?? prev.maxHeat,
+                        ammo: msg.ammo ?? null,
+                        phaseTimeRemaining: msg.phase_time_remaining_secs ?? null,
                         status: msg.status,
                         phase: newPhase,
                         // Server-assigned problem — authoritative source of truth.
@@ -148,17 +199,32 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
                         activeProblemName: msg.active_problem_name !== undefined
                             ? msg.active_problem_name ?? null
                             : prev.activeProblemName,
+                        // Only sent for judges other than Codeforces, so absent means none
+                        activeProblemUrl: msg.active_problem_contest_id !== undefined
+                            ? msg.active_problem_url ?? null
+                            : prev.activeProblemUrl,
                     };
                 });
                 break;
 
             case "ShotResult":
                 setGameState(prev => {
-                    const isMyShot = msg.shooter_id === prev.playerId;
+                    // Co-op: my teammate's shots land on the hidden fleet too
+                    const isMyShot = msg.shooter_id === prev.playerId || prev.coop;
+
+                    // A shield absorbed the shot: the cell stays unknown to the shooter
+                    // and goes back to a plain (unshielded) ship cell for the defender
+                    if (msg.blocked) {
+                        toast.info(isMyShot ? "Your shot hit a shield!" : "Shield absorbed the shot!");
+                        if (isMyShot) return prev;
+                        const newMyGrid = prev.myGrid.map(row => [...row]);
+                        newMyGrid[msg.y][msg.x] = "ship";
+                        return { ...prev, myGrid: newMyGrid };
+                    }
 
                     // Side effect for toast - purely visual, can use the derived value
                     if (msg.sunk) {
-                        toast.info(isMyShot ? "Enemy ship destroyed!" : "Your ship was sunk!");
+                        toast.info(prev.coop ? "Hidden ship destroyed!" : isMyShot ? "Enemy ship destroyed!" : "Your ship was sunk!");
                     }
 
                     // Accumulate sunk cells when a ship is sunk
@@ -196,6 +262,154 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
                 });
                 break;
 
+            case "SpecialShotResult":
+                setGameState(prev => {
+                    const isMyShot = msg.shooter_id === prev.playerId || prev.coop;
+                    const sunk = msg.shots.filter(s => s.sunk);
+                    if (sunk.length > 0) {
+                        toast.info(isMyShot ? "Cross shot destroyed a ship!" : "A cross shot sunk your ship!");
+                    }
+                    const newSunkCells = sunk.flatMap(s => (s.sunk_cells ?? []).map(([cx, cy]) => `${cx},${cy}`));
+                    const grid = (isMyShot ? prev.enemyGrid : prev.myGrid).map(row => [...row]);
+                    for (const shot of msg.shots) {
+                        if (shot.blocked) {
+                            // Shooter learns nothing; the defender's shield is used up
+                            if (!isMyShot) grid[shot.y][shot.x] = "ship";
+                            continue;
+                        }
+                        grid[shot.y][shot.x] = shot.hit ? "hit" : "miss";
+                    }
+                    if (isMyShot) {
+                        const used = msg.shooter_id === prev.playerId ? prev.specialWeapons.indexOf(msg.weapon) : -1;
+                        return {
+                            ...prev,
+                            enemyGrid: grid,
+                            enemyShipsSunk: prev.enemyShipsSunk + sunk.length,
+                            enemySunkCells: [...prev.enemySunkCells, ...newSunkCells],
+                            specialWeapons: prev.specialWeapons.filter((_, i) => i !== used),
+                        };
+                    }
+                    return {
+                        ...prev,
+                        myGrid: grid,
+                        mySunkCells: [...prev.mySunkCells, ...newSunkCells],
+                    };
+                });
+                break;
+
+            case "WeaponGranted":
+                setGameState(prev => {
+                    if (msg.player_id !== prev.playerId) return prev;
+                    toast.success("Fast solve! Cross shot ready.", { id: "weapon-granted" });
+                    return { ...prev, specialWeapons: [...prev.specialWeapons, msg.weapon] };
+                });
+                break;
+
+            case "BonusProblemAssigned":
+                setGameState(prev => {
+                    if (msg.player_id !== prev.playerId) return prev;
+                    return {
+                        ...prev,
+                        bonusProblemContestId: msg.contest_id,
+                        bonusProblemIndex: msg.problem_index,
+                        bonusProblemName: msg.problem_name,
+                        bonusProblemUrl: msg.url ?? null,
+                    };
+                });
+                break;
+
+            case "ShieldEarned":
+                setGameState(prev => {
+                    if (msg.player_id !== prev.playerId) return prev;
+                    toast.success("Bonus solved! Place your shield on one of your ships.", { id: "shield-earned" });
+                    return {
+                        ...prev,
+                        isVerifying: false,
+                        problemsSolved: prev.problemsSolved + 1,
+                        bonusProblemContestId: null,
+                        bonusProblemIndex: null,
+                        bonusProblemName: null,
+                        bonusProblemUrl: null,
+                    };
+                });
+                break;
+
+            case "ShieldPlaced":
+                setGameState(prev => {
+                    const newMyGrid = prev.myGrid.map(row => [...row]);
+                    newMyGrid[msg.y][msg.x] = "shielded";
+                    return {
+                        ...prev,
+                        myGrid: newMyGrid,
+                        shieldsAvailable: Math.max(0, prev.shieldsAvailable - 1),
+                    };
+                });
+                break;
+
+            case "Hint":
+                // Only the buyer receives this; max_heat reflects any heat-limit cost
+                setGameState(prev => ({
+                    ...prev,
+                    hintTags: msg.tags,
+                    activeProblemRating: msg.rating,
+                    maxHeat: msg.max_heat,
+                }));
+                break;
+
+            case "DifficultyVoteCast":
+                setGameState(prev => {
+                    if (msg.player_id === prev.playerId) {
+                        return { ...prev, myDifficultyVote: msg.direction };
+                    }
+                    toast.info(`Opponent voted to make problems ${msg.direction === "up" ? "harder" : "easier"}`, { id: "difficulty-vote" });
+                    return { ...prev, opponentDifficultyVote: msg.direction };
+                });
+                break;
+
+            case "DifficultyChanged":
+                toast.success("Both players agreed — difficulty changed for future problems", { id: "difficulty-changed" });
+                setGameState(prev => ({
+                    ...prev,
+                    difficulty: msg.difficulty,
+                    difficulty_mode: msg.difficulty_mode,
+                    myDifficultyVote: null,
+                    opponentDifficultyVote: null,
+                }));
+                break;
+
+            case "Presence":
+                setGameState(prev => {
+                    if (msg.player_id === prev.playerId) return prev;
+                    if (!msg.connected && prev.opponentOnline && prev.phase !== "finished") {
+                        toast.warning("Opponent went offline", { id: "opponent-presence" });
+                    }
+                    return { ...prev, opponentOnline: msg.connected };
+                });
+                break;
+
+            case "SpectatorCount":
+                setGameState(prev => ({ ...prev, spectators: msg.count }));
+                break;
+
+            case "Working":
+                setGameState(prev =>
+                    msg.player_id === prev.playerId ? prev : { ...prev, opponentWorking: msg.working }
+                );
+                break;
+
+            case "IdleWarning":
+                setGameState(prev => {
+                    if (msg.player_id === prev.playerId) {
+                        toast.warning(`You seem idle — do something within ${msg.forfeit_in_secs}s or forfeit`, { id: "idle-warning", duration: 10000 });
+                    }
+                    return prev;
+                });
+                break;
+
+            case "IdleCleared":
+                toast.dismiss("idle-warning");
+                break;
+
             case "YourShips":
                 // Bug 8 fix: Store ships in myShips for CombatGrid rendering after reconnect
                 setGameState(prev => ({
@@ -236,6 +450,8 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
                         activeProblemIndex: msg.problem_index,
                         activeProblemName: msg.problem_name,
                         activeProblemRating: msg.rating,
+                        activeProblemUrl: msg.url ?? null,
+                        hintTags: null, // New problem — any earlier hint was for the old one
                         vetoTimeRemaining: null, // Clear veto timer — problem is ready to solve
                     };
                 });
@@ -246,12 +462,17 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
                 // Use functional update to access latest playerId (avoid stale closure)
                 setGameState(prev => {
                     if (msg.player_id !== prev.playerId) return prev;
-                    toast.success(msg.reason === "solved" ? "Problem solved! Weapons unlocked!" : "Veto expired! Weapons unlocked!", { id: "weapons-unlocked" });
+                    const unlockedBy: Record<string, string> = {
+                        solved: "Problem solved! Weapons unlocked!",
+                        heat_relief: "Your attempts paid off: heat relief unlocked your weapons!",
+                    };
+                    toast.success(unlockedBy[msg.reason] ?? "Veto expired! Weapons unlocked!", { id: "weapons-unlocked" });
                     return {
                         ...prev,
                         isLocked: false,
                         isVerifying: false,
-                        heat: 0,
+                        // Heat relief only takes 1 off; the next GameUpdate has the exact value
+                        heat: msg.reason === "heat_relief" ? Math.max(0, prev.heat - 1) : 0,
                         status: "Weapons unlocked",
                         // Only track problems solved when actually solved (not veto expiry)
                         problemsSolved: msg.reason === "solved" ? prev.problemsSolved + 1 : prev.problemsSolved,
@@ -260,6 +481,7 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
                         activeProblemIndex: null,
                         activeProblemName: null,
                         activeProblemRating: null,
+                        activeProblemUrl: null,
                     };
                 });
                 break;
@@ -292,6 +514,10 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
                     toast.error("Game start failed — ships were not deployed in time.", { id: "placement-timeout", duration: 10000 });
                 } else if (msg.reason === "SuddenDeathTimeout") {
                     toast.error("Sudden Death timed out — no player landed a hit in 10 minutes.", { id: "sd-timeout", duration: 10000 });
+                } else if (msg.reason === "CoopVictory") {
+                    toast.success("Your team sank the hidden fleet!", { id: "coop-victory", duration: 10000 });
+                } else if (msg.reason === "CoopTimeUp") {
+                    toast.error("Time's up — the hidden fleet survived.", { id: "coop-time-up", duration: 10000 });
                 } else if (msg.reason === "CFUnavailable") {
                     toast.error("Codeforces API is unreachable. Game cancelled — please try again later.", { id: "cf-unavailable", duration: 10000 });
                 }
@@ -305,12 +531,15 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
                     const oppShipsSunk = isP1 ? msg.p2_ships_sunk : msg.p1_ships_sunk;
                     const oppProblemsSolved = isP1 ? msg.p2_problems_solved : msg.p1_problems_solved;
                     const oppCellsHit = isP1 ? msg.p2_cells_hit : msg.p1_cells_hit;
+                    const p1Locks: [number, number] = [msg.p1_locks_solved ?? 0, msg.p1_locks_assigned ?? 0];
+                    const p2Locks: [number, number] = [msg.p2_locks_solved ?? 0, msg.p2_locks_assigned ?? 0];
 
                     // Resolve board reveal to my/opponent perspective
                     const revealMyGrid = isP1 ? msg.p1_grid : msg.p2_grid;
                     const revealMyShips = isP1 ? msg.p1_ships : msg.p2_ships;
-                    const revealOpponentGrid = isP1 ? msg.p2_grid : msg.p1_grid;
-                    const revealOpponentShips = isP1 ? msg.p2_ships : msg.p1_ships;
+                    // Co-op: the "opponent" board is the hidden fleet
+                    const revealOpponentGrid = msg.coop_grid ?? (isP1 ? msg.p2_grid : msg.p1_grid);
+                    const revealOpponentShips = msg.coop_ships ?? (isP1 ? msg.p2_ships : msg.p1_ships);
 
                     return {
                         ...prev,
@@ -319,7 +548,7 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
                         gameOverReason: msg.reason,
                         status: msg.reason === "LobbyTimeout" || msg.reason === "PlacementTimeout" || msg.reason === "SuddenDeathTimeout"
                             ? "GAME EXPIRED"
-                            : msg.winner_id === prev.playerId ? "VICTORY" : "DEFEAT",
+                            : msg.winner_id === prev.playerId || msg.reason === "CoopVictory" ? "VICTORY" : "DEFEAT",
                         // Override with authoritative server stats — these are always correct
                         enemyShipsSunk: myShipsSunk,
                         problemsSolved: myProblemsSolved,
@@ -327,6 +556,8 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
                         opponentShipsSunk: oppShipsSunk,
                         opponentProblemsSolved: oppProblemsSolved,
                         opponentCellsHit: oppCellsHit,
+                        myLocks: isP1 ? p1Locks : p2Locks,
+                        opponentLocks: isP1 ? p2Locks : p1Locks,
                         // Board reveal data (resolved to my/opponent perspective)
                         revealMyGrid,
                         revealMyShips,
@@ -348,27 +579,61 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
                 shouldStopReconnect.current = true; // Don't reconnect after game over
                 break;
 
+            case "LobbyExpiring":
+                setGameState(prev => ({ ...prev, phaseTimeRemaining: msg.in_secs }));
+                toast.warning(`Lobby closes in ${msg.in_secs}s unless someone joins`, { id: "lobby-expiring" });
+                break;
+
+            case "LobbyExtended":
+                setGameState(prev => ({
+                    ...prev,
+                    phaseTimeRemaining: msg.in_secs,
+                    lobbyExtensionsLeft: msg.extensions_left,
+                }));
+                toast.dismiss("lobby-expiring");
+                break;
+
+            case "GameExpired":
+                // Server is removing the game — stop reconnecting. Keep the results
+                // screen if the game already ended; otherwise show the not-found view.
+                shouldStopReconnect.current = true;
+                localStorage.removeItem("battlecp_active_game");
+                setGameState(prev => {
+                    if (prev.phase !== "finished") {
+                        setGameNotFound(true);
+                        toast.error(`This game has expired (${msg.reason}). Please create a new game.`, { id: "game-expired" });
+                    }
+                    return prev;
+                });
+                break;
+
             case "Error":
+                if (msg.code === "resume_expired") {
+                    // Gone too long to replay what we missed; rejoin for a full resync
+                    resumeToken.current = null;
+                    wsRef.current?.send(JSON.stringify({ type: "JoinGame", player_id: playerId, cf_handle: cfHandle }));
+                    break;
+                }
                 console.error("[WS] Server error:", msg.message);
                 setGameState(prev => ({
                     ...prev,
                     lastError: msg.message,
                 }));
                 // If game not found, ended, or full - set flag to prevent reconnection
-                const isFatalError = msg.message.includes("not found")
-                    || msg.message.includes("already ended")
-                    || msg.message.includes("full")
-                    || msg.message.includes("2 players already")
-                    || msg.message.includes("You cannot play against yourself");
+                const isFatalError = ["game_not_found", "game_ended", "game_full", "self_play", "unauthorized", "banned"].includes(msg.code);
 
                 if (isFatalError) {
                     setGameNotFound(true);
                     shouldStopReconnect.current = true; // Prevent reconnection attempts
                     localStorage.removeItem("battlecp_active_game");
 
-                    if (msg.message.includes("2 players already") || msg.message.includes("You cannot play against yourself")) {
+                    if (msg.code === "self_play") {
                         toast.error(msg.message, { id: "easter-egg-full", duration: 8000 }); // Show the easter egg!
-                    } else if (msg.message.includes("full")) {
+                    } else if (msg.code === "banned") {
+                        toast.error(msg.message, { id: "banned" });
+                    } else if (msg.code === "unauthorized") {
+                        toast.error("This seat belongs to another session. Rejoin from the device you joined with.", { id: "unauthorized" });
+                    } else if (msg.code === "game_full") {
                         toast.error("This game is full. Both player slots are occupied.", { id: "game-full" });
                     } else {
                         toast.error("Game not found or has ended. Please create a new game.");
@@ -388,6 +653,29 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
         }
     }, []);
 
+    // Local countdown between server ticks. The server only re-sends GameUpdate
+    // when something changes (plus a keepalive every few seconds), so the clocks
+    // tick down here and get corrected by the next update.
+    const inCombat = gameState.phase === "combat";
+    const inSetup = gameState.phase === "lobby" || gameState.phase === "placement";
+    useEffect(() => {
+        if (!inCombat && !inSetup) return;
+
+        const timer = setInterval(() => {
+            setGameState(prev => ({
+                ...prev,
+                gameTimeRemaining: inCombat ? Math.max(0, prev.gameTimeRemaining - 1) : prev.gameTimeRemaining,
+                vetoTimeRemaining: prev.vetoTimeRemaining !== null && prev.vetoTimeRemaining > 1
+                    ? prev.vetoTimeRemaining - 1
+                    : null,
+                phaseTimeRemaining: prev.phaseTimeRemaining !== null
+                    ? Math.max(0, prev.phaseTimeRemaining - 1)
+                    : null,
+            }));
+        }, 1000);
+        return () => clearInterval(timer);
+    }, [inCombat, inSetup]);
+
     // WebSocket connection
     useEffect(() => {
         if (!gameId || !playerId) return;
@@ -406,7 +694,9 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
             }
             isConnecting.current = true;
             const wsBaseUrl = getWsBaseUrl();
-            const ws = new WebSocket(`${wsBaseUrl}/ws/${gameId}?player_id=${playerId}`);
+            const token = localStorage.getItem(`battlecp_session_${gameId}`);
+            const tokenParam = token ? `&token=${encodeURIComponent(token)}` : "";
+            const ws = new WebSocket(`${wsBaseUrl}/ws/${gameId}?player_id=${playerId}&batch=true${tokenParam}`);
             wsRef.current = ws;
 
             ws.onopen = () => {
@@ -414,17 +704,35 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
                 setIsConnected(true);
                 reconnectAttempts.current = 0;
 
-                // Send JoinGame message
-                ws.send(JSON.stringify({
-                    type: "JoinGame",
-                    player_id: playerId,
-                    cf_handle: cfHandle,
-                }));
+                // Resume if we dropped mid-game, otherwise send JoinGame
+                if (resumeToken.current) {
+                    ws.send(JSON.stringify({
+                        type: "Resume",
+                        token: resumeToken.current,
+                        last_event_id: lastEventId.current,
+                    }));
+                } else {
+                    ws.send(JSON.stringify({
+                        type: "JoinGame",
+                        player_id: playerId,
+                        cf_handle: cfHandle,
+                    }));
+                }
             };
 
             ws.onmessage = (event) => {
                 try {
-                    const msg: ServerMessage = JSON.parse(event.data);
+                    const msg: ServerMessage & { event_id?: number } = JSON.parse(event.data);
+                    if (msg.type === "Resumed" || msg.type === "Batch") {
+                        for (const missed of msg.events) {
+                            handleServerMessage(missed);
+                            lastEventId.current = missed.event_id;
+                        }
+                        return;
+                    }
+                    if (msg.event_id !== undefined) {
+                        lastEventId.current = msg.event_id;
+                    }
                     handleServerMessage(msg);
                 } catch (e) {
                     console.error("[WS] Failed to parse message:", e);
@@ -436,6 +744,27 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
                 setIsConnected(false);
                 wsRef.current = null;
 
+                // The server says why when it closes the socket itself
+                if (event.code >= 4000 && event.code < 5000) {
+                    let reason: CloseReason | null = null;
+                    try {
+                        reason = JSON.parse(event.reason);
+                    } catch {
+                        // No reason text: the code alone still counts
+                    }
+                    // A removed game was already announced by GameExpired
+                    if (reason && reason.code !== "game_closed") {
+                        toast.error(reason.message, { id: `ws-close-${reason.code}` });
+                    }
+                    if (reason?.code !== "server_shutdown" && reason?.code !== "rate_limited") {
+                        shouldStopReconnect.current = true;
+                        if (reason?.code === "kicked" || reason?.code === "game_not_found") {
+                            setGameNotFound(true);
+                            localStorage.removeItem("battlecp_active_game");
+                        }
+                    }
+                }
+
                 if (event.code !== 1000 && reconnectAttempts.current < maxReconnectAttempts && !shouldStopReconnect.current) {
                     reconnectAttempts.current++;
                     const delay = Math.min(1000 * Math.pow(2, reconnectAttempts.current), 10000);
@@ -467,6 +796,48 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
         }
     }, [gameState.isLocked]);
 
+    // Action: Fire a special weapon centred on coordinates
+    const fireSpecial = useCallback((weapon: SpecialWeapon, x: number, y: number) => {
+        if (wsRef.current?.readyState === WebSocket.OPEN && !gameState.isLocked) {
+            wsRef.current.send(JSON.stringify({ type: "FireSpecial", weapon, x, y }));
+        }
+    }, [gameState.isLocked]);
+
+    // Action: Ask for a bonus problem (solving it earns a shield)
+    const requestBonusProblem = useCallback(() => {
+        if (wsRef.current?.readyState === WebSocket.OPEN && !gameState.isLocked) {
+            wsRef.current.send(JSON.stringify({ type: "RequestBonusProblem" }));
+        }
+    }, [gameState.isLocked]);
+
+    // Action: Put an earned shield on one of my ship cells
+    const placeShield = useCallback((x: number, y: number) => {
+        if (wsRef.current?.readyState === WebSocket.OPEN) {
+            wsRef.current.send(JSON.stringify({ type: "PlaceShield", x, y }));
+        }
+    }, []);
+
+    // Action: Buy a hint (rating and tags) for the assigned problem
+    const requestHint = useCallback(() => {
+        if (wsRef.current?.readyState === WebSocket.OPEN && gameState.isLocked) {
+            wsRef.current.send(JSON.stringify({ type: "RequestHint" }));
+        }
+    }, [gameState.isLocked]);
+
+    // Action: Keep a lobby nobody has joined yet open longer (host only)
+    const extendLobby = useCallback(() => {
+        if (wsRef.current?.readyState === WebSocket.OPEN) {
+            wsRef.current.send(JSON.stringify({ type: "ExtendLobby" }));
+        }
+    }, []);
+
+    // Action: Vote to make future problems harder or easier
+    const voteDifficulty = useCallback((direction: DifficultyVote) => {
+        if (wsRef.current?.readyState === WebSocket.OPEN) {
+            wsRef.current.send(JSON.stringify({ type: "VoteDifficulty", direction }));
+        }
+    }, []);
+
     // Action: Place ships
     const placeShips = useCallback((ships: ShipPlacement[]) => {
         if (wsRef.current?.readyState === WebSocket.OPEN) {
@@ -474,6 +845,13 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
         }
     }, []);
 
+    // Action: Confirm ready after placement
+    const ready = useCallback(() => {
+        if (wsRef.current?.readyState === WebSocket.OPEN) {
+            wsRef.current.send(JSON.stringify({ type: "Ready" }));
+        }
+    }, []);
+
     // Action: Solve CP problem
     const solveCP = useCallback((contestId: number, problemIndex: string) => {
         if (wsRef.current?.readyState === WebSocket.OPEN) {
@@ -497,7 +875,14 @@ export function useGameSocket(gameId: string, playerId: string, cfHandle: string
         isConnected,
         gameNotFound,
         fire,
+        fireSpecial,
+        requestBonusProblem,
+        placeShield,
+        voteDifficulty,
+        requestHint,
+        extendLobby,
         placeShips,
+        ready,
         solveCP,
         veto,
     };
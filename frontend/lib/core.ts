@@ -0,0 +1,40 @@
+// Board rules shared with the server: backend/core compiled to WASM
+// (`npm run build:core`, output in lib/core-wasm). Placement checks and shot
+// predictions here give exactly the answer the server will.
+
+import type { ShipPlacement } from "@/types/game";
+
+export type Core = typeof import("./core-wasm/battle_cp_core");
+
+export interface RuleViolation {
+  code: string;    // same code as the server's Error message
+  message: string;
+}
+
+let loading: Promise<Core> | null = null;
+
+/** Load and initialize the WASM module once; later calls reuse it. */
+export function loadCore(): Promise<Core> {
+  loading ??= import("./core-wasm/battle_cp_core").then(async (core) => {
+    await core.default();
+    return core;
+  });
+  return loading;
+}
+
+function violation(v: { code: string; message: string; free(): void } | undefined): RuleViolation | null {
+  if (!v) return null;
+  const { code, message } = v;
+  v.free();
+  return { code, message };
+}
+
+/** Ships fit on the board without overlapping (the fleet may be incomplete). */
+export function checkPlacement(core: Core, ships: ShipPlacement[]): RuleViolation | null {
+  return violation(core.checkPlacement(JSON.stringify(ships)));
+}
+
+/** Everything the server checks on PlaceShips, including fleet composition. */
+export function checkFleet(core: Core, ships: ShipPlacement[]): RuleViolation | null {
+  return violation(core.checkFleet(JSON.stringify(ships)));
+}
@@ -1,6 +1,6 @@
 // Game State Types for BattleCP
 
-export type CellState = "empty" | "ship" | "hit" | "miss";
+export type CellState = "empty" | "ship" | "shielded" | "hit" | "miss";
 
 export interface ShipPlacement {
     x: number;
@@ -9,6 +9,25 @@ export interface ShipPlacement {
     vertical: boolean;
 }
 
+// First misplaced ship of a rejected PlaceShips (ships may touch, so no adjacency rule)
+export interface PlacementFault {
+    index: number; // position in the fleet, from 0
+    ship: ShipPlacement;
+    problem: "out_of_bounds" | "overlap";
+    overlaps?: number; // index of the earlier ship it runs into
+}
+
+// A player's private note on an enemy cell (SetMarker)
+export type MarkerKind = "suspect" | "ruled_out";
+
+export type ActivityKind = "first_blood" | "ship_sunk" | "locked" | "veto_used" | "sudden_death";
+
+export interface Marker {
+    x: number;
+    y: number;
+    kind: MarkerKind;
+}
+
 export interface RevealedShip {
     x: number;
     y: number;
@@ -17,6 +36,59 @@ export interface RevealedShip {
     sunk: boolean;
 }
 
+// Heat ramp step: from after_mins into combat, weapons lock at heat_threshold
+export interface HeatStep {
+    after_mins: number;
+    heat_threshold: number;
+}
+
+// Tie-break once the clock runs out: first hit wins, or best hit rate over 10 shots each
+export type SuddenDeathMode = "first_hit" | "accuracy";
+
+// Ammo mode: rounds to start with and reloaded per solve, instead of heat
+export interface AmmoConfig {
+    starting: number;
+    per_solve: number;
+}
+
+// GET /api/suggest-config: settings to pre-fill for the host's rating
+export interface SuggestedConfig {
+    handle: string;
+    judge: string;
+    rating: number | null;
+    difficulty_mode: "cf";
+    difficulty: number;
+    heat_threshold: number;
+}
+
+// A virtual-contest game's problems, revealed at GameStart
+export interface RevealedProblem {
+    contest_id: number;
+    index: string;
+    name: string;
+    rating: number;
+    url?: string;
+}
+
+// One-use weapons earned by fast solves
+export type SpecialWeapon = "cross";
+
+// What buying a hint costs; "off" when the game has hints disabled
+export type HintCost = "off" | "longer_veto" | "lower_max_heat";
+
+// One step harder or easier: a band, or 100 rating in CF mode
+export type DifficultyVote = "up" | "down";
+
+export interface CellShot {
+    x: number;
+    y: number;
+    coord: string;
+    hit: boolean;
+    sunk: boolean;
+    sunk_cells?: [number, number][];
+    blocked?: boolean;
+}
+
 export type GamePhase = "connecting" | "lobby" | "placement" | "combat" | "finished";
 
 export interface GameState {
@@ -25,10 +97,16 @@ export interface GameState {
     playerId: string | null;
     opponentId: string | null;
     opponentConnected: boolean;
+    opponentOnline: boolean;     // Presence: false while the opponent has no socket open
+    opponentWorking: boolean;    // Locked and busy with a problem (lobbies with the activity indicator)
+    spectators: number;          // Watching on the referee feed
 
     // Placement / Reconnection
     myShipsPlaced: boolean;
     opponentShipsPlaced: boolean;
+    myReady: boolean;
+    opponentReady: boolean;
+    countdown: number | null;       // Pre-combat countdown seconds (null when not counting down)
     myShips: ShipPlacement[]; // Bug 8: Ships data for CombatGrid rendering after reconnect
 
     // Combat
@@ -42,13 +120,33 @@ export interface GameState {
     // HUD State
     heat: number;
     maxHeat: number;
+    ammo: number | null; // rounds left in ammo mode; null under heat
     maxVetoes: number;
+    vetoPenalties: number[]; // seconds per veto; later vetoes repeat the last
     isLocked: boolean;
+    specialWeapons: SpecialWeapon[];
+    shieldsAvailable: number;
+    // Optional bonus problem (asked for while unlocked); solving it earns a shield
+    bonusProblemContestId: number | null;
+    bonusProblemIndex: string | null;
+    bonusProblemName: string | null;
+    // Link for judges other than Codeforces (null: build the Codeforces link)
+    bonusProblemUrl: string | null;
     vetoesRemaining: number;
     vetoTimeRemaining: number | null;
     gameTimeRemaining: number;
+    phaseTimeRemaining: number | null; // Lobby expiry / placement deadline (null in combat)
+    lobbyExtensionsLeft: number;       // Host ExtendLobby requests still allowed
     difficulty: number;
     difficulty_mode: "cf" | "band";
+    hintCost: HintCost;
+    coop: boolean;             // Co-op: both players hunt one hidden fleet (enemyGrid) together
+    revealProblems: boolean;   // Virtual contest: problemSet is revealed at GameStart
+    problemSet: RevealedProblem[];
+    hintTags: string[] | null; // Bought for the active problem; cleared on a new problem
+    // Standing difficulty votes; cleared when both agree and the difficulty changes
+    myDifficultyVote: DifficultyVote | null;
+    opponentDifficultyVote: DifficultyVote | null;
     status: string;
 
     // Stats
@@ -69,6 +167,9 @@ export interface GameState {
     opponentProblemsSolved: number;
     opponentCellsHit: number;
     myCellsHit: number;
+    // Lock problems [solved, assigned] from GameOver
+    myLocks: [number, number];
+    opponentLocks: [number, number];
 
     // Swiss tiebreaker score (server-authoritative)
     myScore: number | null;
@@ -86,6 +187,8 @@ export interface GameState {
     activeProblemIndex: string | null;
     activeProblemName: string | null;
     activeProblemRating: number | null;
+    // Link for judges other than Codeforces (null: build the Codeforces link)
+    activeProblemUrl: string | null;
 }
 
 export const initialGameState: GameState = {
@@ -94,9 +197,15 @@ export const initialGameState: GameState = {
     playerId: null,
     opponentId: null,
     opponentConnected: false,
+    opponentOnline: true,
+    opponentWorking: false,
+    spectators: 0,
 
     myShipsPlaced: false,
     opponentShipsPlaced: false,
+    myReady: false,
+    opponentReady: false,
+    countdown: null,
     myShips: [],
 
     myGrid: Array(10).fill(null).map(() => Array(10).fill("empty")),
@@ -107,11 +216,21 @@ export const initialGameState: GameState = {
 
     heat: 0,
     maxHeat: 9, // Default, will be updated from server
+    ammo: null,
     maxVetoes: 3, // Default, will be updated from server
+    vetoPenalties: [180, 300, 420],
     isLocked: false,
+    specialWeapons: [],
+    shieldsAvailable: 0,
+    bonusProblemContestId: null,
+    bonusProblemIndex: null,
+    bonusProblemName: null,
+    bonusProblemUrl: null,
     vetoesRemaining: 3,
     vetoTimeRemaining: null,
     gameTimeRemaining: 25 * 60, // 25 minutes
+    phaseTimeRemaining: null,
+    lobbyExtensionsLeft: 3,
     status: "Connecting...",
 
     problemsSolved: 0,
@@ -128,6 +247,8 @@ export const initialGameState: GameState = {
     opponentProblemsSolved: 0,
     opponentCellsHit: 0,
     myCellsHit: 0,
+    myLocks: [0, 0],
+    opponentLocks: [0, 0],
 
     myScore: null,
     opponentScore: null,
@@ -136,42 +257,103 @@ export const initialGameState: GameState = {
     lastError: null,
     difficulty: 0,
     difficulty_mode: "band",
+    hintCost: "off",
+    coop: false,
+    revealProblems: false,
+    problemSet: [],
+    hintTags: null,
+    myDifficultyVote: null,
+    opponentDifficultyVote: null,
     activeProblemContestId: null,
     activeProblemIndex: null,
     activeProblemName: null,
     activeProblemRating: null,
+    activeProblemUrl: null,
 };
 
+// Which of a player's boards a shot targets; only armada games have "forward"
+export type Board = "home" | "forward";
+
 // Client -> Server Messages
 export type ClientMessage =
-    | { type: "JoinGame"; player_id: string; cf_handle: string }
-    | { type: "PlaceShips"; ships: ShipPlacement[] }
-    | { type: "Fire"; x: number; y: number }
+    | { type: "JoinGame"; player_id: string; cf_handle: string; locale?: string } // BCP 47 tag for server text
+    | { type: "PlaceShips"; ships: ShipPlacement[]; forward?: ShipPlacement[] } // forward: armada games only
+    | { type: "PlaceShip"; ship: ShipPlacement }
+    | { type: "UnplaceShip"; index: number }
+    | { type: "Ready" }
+    | { type: "Fire"; x: number; y: number; board?: Board }
+    | { type: "Fire"; coord: string; board?: Board } // grid notation, e.g. "B7" (column letter, row number)
+    | { type: "FireSpecial"; weapon: SpecialWeapon; x: number; y: number; board?: Board }
     | { type: "SolveCP"; contest_id: number; problem_index: string }
-    | { type: "Veto" };
+    | { type: "Veto" }
+    | { type: "RequestBonusProblem" }
+    | { type: "PlaceShield"; x: number; y: number }
+    | { type: "VoteDifficulty"; direction: DifficultyVote }
+    | { type: "RequestHint" }
+    | { type: "ExtendLobby" } // host only, while waiting for an opponent
+    | { type: "SetMarker"; x: number; y: number; marker?: MarkerKind } // no marker clears the cell
+    | { type: "Resume"; token: string; last_event_id: number }; // instead of JoinGame after a drop
+
+// Reason text of a Close frame the server sent (codes 4000-4999)
+export type CloseCode = "game_closed" | "server_shutdown" | "kicked" | "game_not_found" | "rate_limited";
+export interface CloseReason {
+    code: CloseCode;
+    message: string;
+}
 
 // Server -> Client Messages
 export type ServerMessage =
     // Lobby
-    | { type: "GameJoined"; game_id: string; player_id: string; difficulty: number; difficulty_mode: "cf" | "band"; max_heat: number; max_vetoes: number }
+    | { type: "GameJoined"; game_id: string; player_id: string; difficulty: number; difficulty_mode: "cf" | "band"; max_heat: number; max_vetoes: number; veto_penalties?: number[]; scheduled_start?: number; hint_cost?: HintCost; heat_ramp?: HeatStep[]; ammo?: AmmoConfig; sudden_death?: SuddenDeathMode; coop?: boolean; armada?: boolean; reveal_problems?: boolean }
     | { type: "PlayerJoined"; player_id: string }
+    | { type: "SessionToken"; token: string }
+    | { type: "ResumeToken"; token: string; window_secs: number }
 
     // Placement
     | { type: "ShipsConfirmed"; player_id: string }
+    | { type: "PlayerReady"; player_id: string }
+    | { type: "Countdown"; seconds_remaining: number }
     | { type: "GameStart" }
+    | { type: "ProblemSet"; problems: RevealedProblem[] }
 
     // Reconnection
     | { type: "YourShips"; ships: ShipPlacement[] }
-    | { type: "GridSync"; my_grid: CellState[][]; enemy_grid: CellState[][] }
+    | { type: "StagedShips"; ships: ShipPlacement[] }
+    | { type: "Markers"; markers: Marker[] }
+    | { type: "GridSync"; my_grid: CellState[][]; enemy_grid: CellState[][]; my_forward_grid?: CellState[][]; enemy_forward_grid?: CellState[][] }
+    | { type: "Resumed"; events: (ServerMessage & { event_id: number })[] } // broadcasts missed while away
+    | { type: "Batch"; events: (ServerMessage & { event_id: number })[] } // everything one action caused, in order
 
     // Combat
-    | { type: "GameUpdate"; status: string; is_active: boolean; heat: number; is_locked: boolean; time_remaining_secs: number; vetoes_remaining: number; veto_time_remaining_secs?: number; active_problem_contest_id?: number; active_problem_index?: string; active_problem_name?: string }
-    | { type: "ShotResult"; x: number; y: number; hit: boolean; sunk: boolean; shooter_id: string; sunk_cells?: [number, number][] }
+    | { type: "GameUpdate"; status: string; is_active: boolean; heat: number; is_locked: boolean; time_remaining_secs: number; vetoes_remaining: number; veto_time_remaining_secs?: number; phase_time_remaining_secs?: number; active_problem_contest_id?: number; active_problem_index?: string; active_problem_name?: string; active_problem_url?: string; special_weapons?: SpecialWeapon[]; shields_available?: number; max_heat?: number; ammo?: number }
+    | { type: "ShotResult"; x: number; y: number; hit: boolean; sunk: boolean; shooter_id: string; coord: string; sunk_cells?: [number, number][]; blocked?: boolean; board?: Board }
+    | { type: "SpecialShotResult"; shooter_id: string; weapon: SpecialWeapon; x: number; y: number; coord: string; board?: Board; shots: CellShot[] }
     | { type: "WeaponsLocked"; player_id: string }
-    | { type: "WeaponsUnlocked"; player_id: string; reason: string } // "solved" or "veto_expired"
+    | { type: "WeaponsUnlocked"; player_id: string; reason: string } // "solved", "veto_expired", "heat_relief" or "admin"
+    | { type: "WeaponGranted"; player_id: string; weapon: SpecialWeapon } // fast solve reward
 
     // Server-assigned problem
-    | { type: "ProblemAssigned"; player_id: string; contest_id: number; problem_index: string; problem_name: string; rating: number }
+    | { type: "ProblemAssigned"; player_id: string; contest_id: number; problem_index: string; problem_name: string; rating: number; url?: string }
+
+    // Bonus problems and shields
+    | { type: "BonusProblemAssigned"; player_id: string; contest_id: number; problem_index: string; problem_name: string; rating: number; url?: string }
+    | { type: "ShieldEarned"; player_id: string }
+    | { type: "ShieldPlaced"; x: number; y: number; coord: string } // only sent to the placer
+
+    // Hint for the assigned problem (only sent to the buyer)
+    | { type: "Hint"; contest_id: number; problem_index: string; rating: number; tags: string[]; max_heat: number }
+
+    // Mid-game difficulty vote (both must agree)
+    | { type: "DifficultyVoteCast"; player_id: string; direction: DifficultyVote }
+    | { type: "DifficultyChanged"; difficulty: number; difficulty_mode: "cf" | "band" }
+
+    // Presence
+    | { type: "Presence"; player_id: string; connected: boolean }
+    | { type: "SpectatorCount"; count: number }
+    | { type: "Working"; player_id: string; working: boolean }
+    | { type: "IdleWarning"; player_id: string; forfeit_in_secs: number }
+    | { type: "IdleCleared"; player_id: string }
+    | { type: "ActivityEvent"; kind: ActivityKind; player_id?: string; text: string }
 
     // End
     | {
@@ -186,11 +368,24 @@ export type ServerMessage =
         p2_ships_sunk: number;
         p2_cells_hit: number;
         p2_problems_solved: number;
+        // Lock problems solved / assigned (bonus problems aside)
+        p1_locks_solved?: number;
+        p1_locks_assigned?: number;
+        p2_locks_solved?: number;
+        p2_locks_assigned?: number;
         // Board reveal
         p1_grid: string[][];
         p1_ships: RevealedShip[];
         p2_grid: string[][];
         p2_ships: RevealedShip[];
+        // Co-op: the hidden fleet
+        coop_grid?: string[][];
+        coop_ships?: RevealedShip[];
+        // Armada: the forward fleets
+        p1_forward_grid?: string[][];
+        p1_forward_ships?: RevealedShip[];
+        p2_forward_grid?: string[][];
+        p2_forward_ships?: RevealedShip[];
         // Swiss tiebreaker scores
         time_taken_secs: number;
         winner_score: number;
@@ -201,5 +396,13 @@ export type ServerMessage =
     | { type: "VerifyPending"; player_id: string }
     | { type: "VerifyResult"; player_id: string; accepted: boolean; message: string }
 
-    // Error
-    | { type: "Error"; message: string };
+    // Waiting lobby about to close / pushed back by the host
+    | { type: "LobbyExpiring"; in_secs: number }
+    | { type: "LobbyExtended"; in_secs: number; extensions_left: number }
+
+    // Server dropped the game (finished/idle/abandoned past retention)
+    | { type: "GameExpired"; reason: string }
+
+    // Error (`code` is a stable identifier, e.g. "already_fired"; `placement`
+    // names the misplaced ship on "invalid_placement")
+    | { type: "Error"; code: string; message: string; placement?: PlacementFault; retry_after_secs?: number };